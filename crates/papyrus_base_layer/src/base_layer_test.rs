@@ -1,15 +1,24 @@
 use std::fs::File;
 use std::process::Command;
 
+use ethers::abi::{encode, Abi, Token};
+use ethers::types::{Log, H160, H256, U256};
 use ethers::utils::{Ganache, GanacheInstance};
+use mockito::mock;
+use papyrus_common::l1_to_l2_messages::{L1ToL2Message, L1TransactionHash};
 use pretty_assertions::assert_eq;
 use starknet_api::block::{BlockHash, BlockNumber};
-use starknet_api::hash::StarkFelt;
-use starknet_api::stark_felt;
+use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::{contract_address, patricia_key, stark_felt};
 use tar::Archive;
 use tempfile::{tempdir, TempDir};
 
-use crate::ethereum_base_layer_contract::{EthereumBaseLayerConfig, EthereumBaseLayerContract};
+use crate::ethereum_base_layer_contract::{
+    parse_message_log,
+    EthereumBaseLayerConfig,
+    EthereumBaseLayerContract,
+};
 use crate::BaseLayerContract;
 
 type EthereumContractAddress = String;
@@ -59,8 +68,11 @@ fn get_test_ethereum_node() -> (TestEthereumNodeHandle, EthereumContractAddress)
 // Note: the test requires ganache-cli installed, otherwise it is ignored.
 async fn latest_proved_block_ethereum() {
     let (node_handle, starknet_contract_address) = get_test_ethereum_node();
-    let config =
-        EthereumBaseLayerConfig { node_url: node_handle.0.endpoint(), starknet_contract_address };
+    let config = EthereumBaseLayerConfig {
+        node_url: node_handle.0.endpoint(),
+        starknet_contract_address,
+        proxy_url: None,
+    };
     let contract = EthereumBaseLayerContract::new(config).unwrap();
 
     let first_sn_state_update = (BlockNumber(100), BlockHash(stark_felt!("0x100")));
@@ -80,3 +92,56 @@ async fn latest_proved_block_ethereum() {
         assert_eq!(latest_block, expected);
     }
 }
+
+#[test]
+fn parse_message_to_l2_log() {
+    let abi: Abi = serde_json::from_str(include_str!("core_contract_latest_block.abi")).unwrap();
+    let event = abi.event("LogMessageToL2").unwrap();
+    let from_address = H160::from_low_u64_be(0x18e4);
+    let transaction_hash = H256::from_low_u64_be(1);
+    let log = Log {
+        topics: vec![
+            event.signature(),
+            H256::from(from_address),
+            H256::from_low_u64_be(0x5535),
+            H256::from_low_u64_be(0x3fa7),
+        ],
+        data: encode(&[
+            Token::Array(vec![Token::Uint(U256::from(2)), Token::Uint(U256::from(3))]),
+            Token::Uint(U256::from(0xc01b3)),
+            Token::Uint(U256::from(100)),
+        ])
+        .into(),
+        transaction_hash: Some(transaction_hash),
+        ..Default::default()
+    };
+
+    let expected_message = L1ToL2Message {
+        l1_transaction_hash: L1TransactionHash(transaction_hash.0),
+        from_address: EthAddress(from_address),
+        to_address: contract_address!("0x5535"),
+        entry_point_selector: EntryPointSelector(stark_felt!("0x3fa7")),
+        payload: vec![stark_felt!("0x2"), stark_felt!("0x3")],
+        nonce: Nonce(stark_felt!("0xc01b3")),
+    };
+    assert_eq!(parse_message_log(event, log).unwrap(), expected_message);
+}
+
+#[tokio::test]
+async fn ethereum_requests_through_proxy() {
+    // The proxy gets the full URL of the request, and the node at the URL is never contacted.
+    const NODE_URL: &str = "http://ethereum.invalid:8545/";
+    let mock = mock("POST", NODE_URL)
+        .with_status(200)
+        .with_body(r#"{"jsonrpc": "2.0", "id": 1, "result": "0x1f"}"#)
+        .create();
+    let config = EthereumBaseLayerConfig {
+        node_url: NODE_URL.to_owned(),
+        proxy_url: Some(mockito::server_url()),
+        ..Default::default()
+    };
+    let contract = EthereumBaseLayerContract::new(config).unwrap();
+
+    assert_eq!(contract.latest_block_number(None).await.unwrap(), Some(31));
+    mock.assert();
+}
@@ -1,22 +1,34 @@
 use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use async_trait::async_trait;
-use ethers::abi::{Abi, AbiEncode};
+use ethers::abi::{Abi, AbiEncode, Event, RawLog, Token};
 use ethers::contract::Contract;
 use ethers::prelude::{AbiError, Address, ContractError, Http, Middleware, Provider};
 use ethers::providers::ProviderError;
-use ethers::types::{I256, U256};
-use papyrus_config::dumping::{ser_param, ser_required_param, SerializeConfig};
+use ethers::types::{Filter, Log, H256, I256, U256};
+use metrics::{histogram, increment_counter};
+use papyrus_common::l1_to_l2_messages::{L1ToL2Message, L1TransactionHash};
+use papyrus_config::dumping::{ser_optional_param, ser_param, ser_required_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
+use reqwest::Proxy;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber};
-use starknet_api::hash::StarkHash;
+use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::StarknetApiError;
-use url::ParseError;
+use url::{ParseError, Url};
 
 use crate::BaseLayerContract;
 
+// Name of the metrics.
+const LATEST_PROVED_BLOCK_LATENCY: &str = "base_layer_latest_proved_block_latency_seconds";
+const FAILED_REQUESTS: &str = "base_layer_failed_requests";
+
+// The event the Starknet contract emits for each message sent to Starknet.
+const LOG_MESSAGE_TO_L2: &str = "LogMessageToL2";
+
 #[derive(thiserror::Error, Debug)]
 pub enum EthereumBaseLayerError {
     #[error(transparent)]
@@ -24,15 +36,23 @@ pub enum EthereumBaseLayerError {
     #[error(transparent)]
     Abi(#[from] AbiError),
     #[error(transparent)]
+    EthAbi(#[from] ethers::abi::Error),
+    #[error(transparent)]
     Url(#[from] ParseError),
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
     #[error(transparent)]
     Provider(#[from] ProviderError),
     #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
     BadContract(#[from] ContractError<Provider<Http>>),
     #[error(transparent)]
     StarknetApi(#[from] StarknetApiError),
+    #[error("Received a log without a transaction hash.")]
+    LogWithoutTransactionHash,
+    #[error("Invalid {LOG_MESSAGE_TO_L2} log in transaction {0:?}.")]
+    InvalidMessageLog(H256),
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -40,11 +60,12 @@ pub struct EthereumBaseLayerConfig {
     // TODO(yair): consider using types.
     pub node_url: String,
     pub starknet_contract_address: String,
+    pub proxy_url: Option<String>,
 }
 
 impl SerializeConfig for EthereumBaseLayerConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut dump = BTreeMap::from_iter([
             ser_required_param(
                 "node_url",
                 SerializationType::String,
@@ -57,7 +78,16 @@ impl SerializeConfig for EthereumBaseLayerConfig {
                 "Starknet contract address in ethereum.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+        ]);
+        dump.append(&mut ser_optional_param(
+            &self.proxy_url,
+            String::new(),
+            "proxy_url",
+            "URL of an HTTP, HTTPS or SOCKS5 proxy for the requests to the Ethereum node, for \
+             example socks5://127.0.0.1:1080.",
+            ParamPrivacyInput::Private,
+        ));
+        dump
     }
 }
 
@@ -66,6 +96,7 @@ impl Default for EthereumBaseLayerConfig {
         Self {
             node_url: "https://mainnet.infura.io/v3/<your_api_key>".to_string(),
             starknet_contract_address: "0xc662c410C0ECf747543f5bA90660f6ABeBD9C8c4".to_string(),
+            proxy_url: None,
         }
     }
 }
@@ -77,21 +108,24 @@ pub struct EthereumBaseLayerContract {
 impl EthereumBaseLayerContract {
     pub fn new(config: EthereumBaseLayerConfig) -> Result<Self, EthereumBaseLayerError> {
         let address = config.starknet_contract_address.parse::<Address>()?;
-        let client: Provider<Http> = Provider::<Http>::try_from(config.node_url)?;
-        // The solidity contract was pre-compiled, and only the relevant functions were kept.
+        let client: Provider<Http> = match config.proxy_url {
+            None => Provider::<Http>::try_from(config.node_url)?,
+            Some(proxy_url) => {
+                let http_client =
+                    reqwest::Client::builder().proxy(Proxy::all(proxy_url)?).build()?;
+                Provider::new(Http::new_with_client(Url::parse(&config.node_url)?, http_client))
+            }
+        };
+        // The solidity contract was pre-compiled, and only the relevant functions and events were
+        // kept.
         let abi: Abi = serde_json::from_str::<Abi>(include_str!("core_contract_latest_block.abi"))?;
         Ok(Self { contract: Contract::new(address, abi, Arc::new(client)) })
     }
-}
 
-#[async_trait]
-impl BaseLayerContract for EthereumBaseLayerContract {
-    type Error = EthereumBaseLayerError;
-
-    async fn latest_proved_block(
+    async fn query_latest_proved_block(
         &self,
         min_confirmations: Option<u64>,
-    ) -> Result<Option<(BlockNumber, BlockHash)>, Self::Error> {
+    ) -> Result<Option<(BlockNumber, BlockHash)>, EthereumBaseLayerError> {
         let ethereum_block_number = self
             .contract
             .client()
@@ -114,4 +148,101 @@ impl BaseLayerContract for EthereumBaseLayerContract {
             BlockHash(StarkHash::try_from(state_block_hash.encode_hex().as_str())?),
         )))
     }
+
+    async fn query_l1_to_l2_messages(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<L1ToL2Message>, EthereumBaseLayerError> {
+        let event = self.contract.abi().event(LOG_MESSAGE_TO_L2)?;
+        let filter = Filter::new()
+            .address(self.contract.address())
+            .topic0(event.signature())
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self.contract.client().get_logs(&filter).await?;
+        logs.into_iter().map(|log| parse_message_log(event, log)).collect()
+    }
+}
+
+pub(crate) fn parse_message_log(
+    event: &Event,
+    log: Log,
+) -> Result<L1ToL2Message, EthereumBaseLayerError> {
+    let transaction_hash =
+        log.transaction_hash.ok_or(EthereumBaseLayerError::LogWithoutTransactionHash)?;
+    let invalid_log = || EthereumBaseLayerError::InvalidMessageLog(transaction_hash);
+    let values = event
+        .parse_log(RawLog { topics: log.topics, data: log.data.to_vec() })?
+        .params
+        .into_iter()
+        .map(|param| param.value)
+        .collect::<Vec<_>>();
+    let [from_address, to_address, selector, payload, nonce, _fee] = values.as_slice() else {
+        return Err(invalid_log());
+    };
+    let (Token::Address(from_address), Token::Array(payload)) = (from_address, payload) else {
+        return Err(invalid_log());
+    };
+    let as_felt = |token: &Token| -> Result<StarkFelt, EthereumBaseLayerError> {
+        match token {
+            Token::Uint(value) => Ok(u256_to_felt(*value)?),
+            _ => Err(invalid_log()),
+        }
+    };
+    Ok(L1ToL2Message {
+        l1_transaction_hash: L1TransactionHash(transaction_hash.0),
+        from_address: EthAddress(*from_address),
+        to_address: ContractAddress(PatriciaKey::try_from(as_felt(to_address)?)?),
+        entry_point_selector: EntryPointSelector(as_felt(selector)?),
+        payload: payload.iter().map(as_felt).collect::<Result<_, _>>()?,
+        nonce: Nonce(as_felt(nonce)?),
+    })
+}
+
+fn u256_to_felt(value: U256) -> Result<StarkFelt, StarknetApiError> {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    StarkFelt::new(bytes)
+}
+
+#[async_trait]
+impl BaseLayerContract for EthereumBaseLayerContract {
+    type Error = EthereumBaseLayerError;
+
+    async fn latest_proved_block(
+        &self,
+        min_confirmations: Option<u64>,
+    ) -> Result<Option<(BlockNumber, BlockHash)>, Self::Error> {
+        let start_time = Instant::now();
+        let result = self.query_latest_proved_block(min_confirmations).await;
+        histogram!(LATEST_PROVED_BLOCK_LATENCY, start_time.elapsed().as_secs_f64());
+        if result.is_err() {
+            increment_counter!(FAILED_REQUESTS);
+        }
+        result
+    }
+
+    async fn latest_block_number(
+        &self,
+        min_confirmations: Option<u64>,
+    ) -> Result<Option<u64>, Self::Error> {
+        let result = self.contract.client().get_block_number().await;
+        if result.is_err() {
+            increment_counter!(FAILED_REQUESTS);
+        }
+        Ok(result?.as_u64().checked_sub(min_confirmations.unwrap_or(0)))
+    }
+
+    async fn l1_to_l2_messages(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<L1ToL2Message>, Self::Error> {
+        let result = self.query_l1_to_l2_messages(from_block, to_block).await;
+        if result.is_err() {
+            increment_counter!(FAILED_REQUESTS);
+        }
+        result
+    }
 }
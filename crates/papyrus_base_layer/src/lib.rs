@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use papyrus_common::l1_to_l2_messages::L1ToL2Message;
 use starknet_api::block::{BlockHash, BlockNumber};
 
 #[cfg(test)]
@@ -17,4 +18,19 @@ pub trait BaseLayerContract {
         &self,
         min_confirmations: Option<u64>,
     ) -> Result<Option<(BlockNumber, BlockHash)>, Self::Error>;
+
+    /// Get the number of the latest base layer block.
+    /// Optionally, require minimum confirmations.
+    async fn latest_block_number(
+        &self,
+        min_confirmations: Option<u64>,
+    ) -> Result<Option<u64>, Self::Error>;
+
+    /// Get the messages to Starknet that were sent in the given range of base layer blocks
+    /// (inclusive), ordered by the blocks they were sent in.
+    async fn l1_to_l2_messages(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<L1ToL2Message>, Self::Error>;
 }
@@ -4,7 +4,7 @@ mod block_hash_test;
 
 use std::iter::zip;
 
-use starknet_api::block::{Block, BlockBody};
+use starknet_api::block::{Block, BlockBody, BlockHeader};
 use starknet_api::core::ChainId;
 use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
 use starknet_api::transaction::{
@@ -31,9 +31,7 @@ enum BlockHashVersion {
 /// A hash is valid if it is the result of one of the hash functions that were ever used in
 /// Starknet.
 pub fn validate_block_hash(block: &Block, chain_id: &ChainId) -> Result<bool, StarknetApiError> {
-    for version in
-        [BlockHashVersion::V3, BlockHashVersion::V2, BlockHashVersion::V1, BlockHashVersion::V0]
-    {
+    for version in get_block_hash_versions(chain_id) {
         if calculate_block_hash_by_version(block, version, chain_id)? == block.header.block_hash.0 {
             return Ok(true);
         }
@@ -41,43 +39,123 @@ pub fn validate_block_hash(block: &Block, chain_id: &ChainId) -> Result<bool, St
     Ok(false)
 }
 
+/// The number and the Patricia root of the transactions of a block and of its events, which the
+/// hash of the block commits to in addition to its header.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct BlockCommitments {
+    pub n_transactions: usize,
+    pub transactions_root: StarkHash,
+    pub n_events: usize,
+    pub events_root: StarkHash,
+}
+
+/// Returns the commitments of a starknet block by the hash function its hash is the result of, or
+/// by the current hash function if its hash isn't the result of any of them.
+pub fn calculate_block_commitments(
+    block: &Block,
+    chain_id: &ChainId,
+) -> Result<BlockCommitments, StarknetApiError> {
+    for version in get_block_hash_versions(chain_id) {
+        let commitments = get_block_commitments(&block.body, &version)?;
+        if calculate_header_hash_by_version(&block.header, &commitments, &version, chain_id)?
+            == block.header.block_hash.0
+        {
+            return Ok(commitments);
+        }
+    }
+    get_block_commitments(&block.body, &BlockHashVersion::V3)
+}
+
+/// Validates the hash of a starknet block header, given the commitments of the block instead of its
+/// body. Like [`validate_block_hash`], a hash is valid if it is the result of one of the hash
+/// functions that were ever used in Starknet.
+pub fn validate_header_block_hash(
+    header: &BlockHeader,
+    commitments: &BlockCommitments,
+    chain_id: &ChainId,
+) -> Result<bool, StarknetApiError> {
+    for version in get_block_hash_versions(chain_id) {
+        if calculate_header_hash_by_version(header, commitments, &version, chain_id)?
+            == header.block_hash.0
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Calculates the hash of a starknet block with the current hash function, ignoring the block hash
+/// field in the given block.
+pub fn calculate_block_hash(
+    block: &Block,
+    chain_id: &ChainId,
+) -> Result<StarkHash, StarknetApiError> {
+    calculate_block_hash_by_version(block, BlockHashVersion::V3, chain_id)
+}
+
+// The versions of the hash functions that were used in the chain, from the newest.
+fn get_block_hash_versions(chain_id: &ChainId) -> Vec<BlockHashVersion> {
+    [BlockHashVersion::V3, BlockHashVersion::V2, BlockHashVersion::V1, BlockHashVersion::V0]
+        .into_iter()
+        // Only the chains with a known sequencer address have blocks with block hash version 2.
+        .filter(|version| {
+            version != &BlockHashVersion::V2 || get_chain_sequencer_address(chain_id).is_some()
+        })
+        .collect()
+}
+
 // Calculates hash of a starknet block by version, ignoring the block hash field in the given block.
 fn calculate_block_hash_by_version(
     block: &Block,
     version: BlockHashVersion,
     chain_id: &ChainId,
 ) -> Result<StarkFelt, StarknetApiError> {
-    let (n_transactions, transactions_patricia_root) =
-        get_transactions_hash_data(&block.body, &version)?;
-
-    let (n_events, events_patricia_root) =
-        get_events_hash_data(&block.body.transaction_outputs, &version);
+    let commitments = get_block_commitments(&block.body, &version)?;
+    calculate_header_hash_by_version(&block.header, &commitments, &version, chain_id)
+}
 
+// Calculates hash of a starknet block by version from its header and the commitments to its body,
+// ignoring the block hash field in the given header.
+fn calculate_header_hash_by_version(
+    header: &BlockHeader,
+    commitments: &BlockCommitments,
+    version: &BlockHashVersion,
+    chain_id: &ChainId,
+) -> Result<StarkFelt, StarknetApiError> {
     Ok(HashChain::new()
-        .chain(&block.header.block_number.0.into())
-        .chain(&block.header.state_root.0)
+        .chain(&header.block_number.0.into())
+        .chain(&header.state_root.0)
         .chain_if_else(
-            &get_chain_sequencer_address(chain_id),
-            block.header.sequencer.0.key(),
-            version == BlockHashVersion::V2,
+            &get_chain_sequencer_address(chain_id).unwrap_or_default(),
+            header.sequencer.0.key(),
+            version == &BlockHashVersion::V2,
         )
-        .chain_if_else(&block.header.timestamp.0.into(), &ZERO, version >= BlockHashVersion::V1)
-        .chain(&n_transactions)
-        .chain(&transactions_patricia_root)
-        .chain(&n_events)
-        .chain(&events_patricia_root)
+        .chain_if_else(&header.timestamp.0.into(), &ZERO, version >= &BlockHashVersion::V1)
+        .chain(&usize_into_felt(commitments.n_transactions))
+        .chain(&commitments.transactions_root)
+        .chain(&usize_into_felt(commitments.n_events))
+        .chain(&commitments.events_root)
         .chain(&ZERO) // Not implemented Element.
         .chain(&ZERO) // Not implemented Element.
-        .chain_if(&ascii_as_felt(chain_id.0.as_str())?, version == BlockHashVersion::V0)
-        .chain(&block.header.parent_hash.0).get_pedersen_hash())
+        .chain_if(&ascii_as_felt(chain_id.0.as_str())?, version == &BlockHashVersion::V0)
+        .chain(&header.parent_hash.0).get_pedersen_hash())
+}
+
+// Returns the commitments of the body of a block by version.
+fn get_block_commitments(
+    block_body: &BlockBody,
+    version: &BlockHashVersion,
+) -> Result<BlockCommitments, StarknetApiError> {
+    let (n_transactions, transactions_root) = get_transactions_hash_data(block_body, version)?;
+    let (n_events, events_root) = get_events_hash_data(&block_body.transaction_outputs, version);
+    Ok(BlockCommitments { n_transactions, transactions_root, n_events, events_root })
 }
 
 // Returns the number of the transactions, and the Patricia root of the transactions.
 fn get_transactions_hash_data(
     block_body: &BlockBody,
     version: &BlockHashVersion,
-) -> Result<(StarkFelt, StarkFelt), StarknetApiError> {
-    let n_transactions = usize_into_felt(block_body.transactions.len());
+) -> Result<(usize, StarkFelt), StarknetApiError> {
     let transaction_patricia_leaves =
         zip(block_body.transactions.iter(), block_body.transaction_hashes.iter())
             .map(|(transaction, transaction_hash)| {
@@ -85,7 +163,7 @@ fn get_transactions_hash_data(
             })
             .collect::<Result<Vec<_>, _>>()?;
     let transactions_patricia_root = calculate_root(transaction_patricia_leaves);
-    Ok((n_transactions, transactions_patricia_root))
+    Ok((block_body.transactions.len(), transactions_patricia_root))
 }
 
 // Returns a Patricia leaf value for a transaction.
@@ -128,13 +206,13 @@ fn get_signature_only_from_invoke(transaction: &Transaction) -> Vec<StarkFelt> {
 fn get_events_hash_data(
     transaction_outputs: &[TransactionOutput],
     version: &BlockHashVersion,
-) -> (StarkFelt, StarkFelt) {
+) -> (usize, StarkFelt) {
     if version < &BlockHashVersion::V1 {
-        return (*ZERO, *ZERO);
+        return (0, *ZERO);
     }
     let event_patricia_leaves: Vec<_> =
         transaction_outputs.iter().flat_map(|output| output.events()).map(get_event_leaf).collect();
-    (usize_into_felt(event_patricia_leaves.len()), calculate_root(event_patricia_leaves))
+    (event_patricia_leaves.len(), calculate_root(event_patricia_leaves))
 }
 
 // Returns a Patricia leaf value for an event.
@@ -148,15 +226,17 @@ fn get_event_leaf(event: &Event) -> StarkHash {
 }
 
 // The fixed sequencer addresses of the chains that have historic blocks with block hash version 2.
-fn get_chain_sequencer_address(chain_id: &ChainId) -> StarkHash {
+fn get_chain_sequencer_address(chain_id: &ChainId) -> Option<StarkHash> {
     match chain_id.to_string().as_str() {
-        "SN_MAIN" => StarkHash::try_from(
-            "0x021f4b90b0377c82bf330b7b5295820769e72d79d8acd0effa0ebde6e9988bc5",
-        )
-        .expect("should be a Stark felt in hex representation"),
+        "SN_MAIN" => Some(
+            StarkHash::try_from(
+                "0x021f4b90b0377c82bf330b7b5295820769e72d79d8acd0effa0ebde6e9988bc5",
+            )
+            .expect("should be a Stark felt in hex representation"),
+        ),
         // TODO(yoav): Add sequencers for the rest of the supported chains that have historic blocks
         // with block hash version 2.
-        _ => unimplemented!("Sequencer address for chain"),
+        _ => None,
     }
 }
 
@@ -2,7 +2,13 @@ use starknet_api::block::Block;
 use starknet_api::core::ChainId;
 use test_utils::read_json_file;
 
-use super::calculate_block_hash_by_version;
+use super::{
+    calculate_block_commitments,
+    calculate_block_hash,
+    calculate_block_hash_by_version,
+    validate_block_hash,
+    validate_header_block_hash,
+};
 use crate::block_hash::BlockHashVersion;
 
 fn validate_block_hash_util(file_name: &str, version: BlockHashVersion) -> bool {
@@ -39,3 +45,29 @@ fn test_deprecated_block_hash_v1() {
 fn test_deprecated_block_hash_v0() {
     assert!(validate_block_hash_util("deprecated_block_hash_v0.json", BlockHashVersion::V0));
 }
+
+#[test]
+fn test_validate_block_hash_on_chain_without_hash_version_2() {
+    let chain_id = ChainId("SN_SEPOLIA".to_owned());
+    let mut block: Block = serde_json::from_value(read_json_file("block_hash.json")).unwrap();
+    block.header.block_hash.0 = calculate_block_hash(&block, &chain_id).unwrap();
+    assert!(validate_block_hash(&block, &chain_id).unwrap());
+
+    block.header.timestamp.0 += 1;
+    assert!(!validate_block_hash(&block, &chain_id).unwrap());
+}
+
+#[test]
+fn test_validate_header_block_hash() {
+    let chain_id = ChainId("SN_MAIN".to_owned());
+    for file_name in
+        ["block_hash.json", "deprecated_block_hash_v1.json", "deprecated_block_hash_v0.json"]
+    {
+        let mut block: Block = serde_json::from_value(read_json_file(file_name)).unwrap();
+        let commitments = calculate_block_commitments(&block, &chain_id).unwrap();
+        assert!(validate_header_block_hash(&block.header, &commitments, &chain_id).unwrap());
+
+        block.header.state_root.0 = block.header.block_hash.0;
+        assert!(!validate_header_block_hash(&block.header, &commitments, &chain_id).unwrap());
+    }
+}
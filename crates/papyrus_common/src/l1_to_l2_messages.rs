@@ -0,0 +1,110 @@
+//! Identifiers of the messages that the base layer (L1) sends to Starknet (L2). Each message is
+//! consumed by an L1 handler transaction, and its hash is computed the same way on both layers.
+#[cfg(test)]
+#[path = "l1_to_l2_messages_test.rs"]
+mod l1_to_l2_messages_test;
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{Digest, Keccak256};
+use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::serde_utils::bytes_from_hex_str;
+use starknet_api::transaction::L1HandlerTransaction;
+
+/// The hash of an L1 to L2 message.
+// The hash is Keccak256, so it doesn't necessarily fit in a StarkFelt.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct L1ToL2MessageHash(pub [u8; 32]);
+
+/// The hash of a transaction on the base layer.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct L1TransactionHash(pub [u8; 32]);
+
+/// A message sent from the base layer to Starknet.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct L1ToL2Message {
+    /// The hash of the base layer transaction that sent the message.
+    pub l1_transaction_hash: L1TransactionHash,
+    pub from_address: EthAddress,
+    pub to_address: ContractAddress,
+    pub entry_point_selector: EntryPointSelector,
+    pub payload: Vec<StarkFelt>,
+    pub nonce: Nonce,
+}
+
+impl L1ToL2Message {
+    /// Returns the hash of the message.
+    pub fn hash(&self) -> L1ToL2MessageHash {
+        let mut from_address = [0_u8; 32];
+        from_address[12..].copy_from_slice(self.from_address.0.as_bytes());
+        calculate_message_hash(
+            &from_address,
+            &self.to_address,
+            &self.nonce,
+            &self.entry_point_selector,
+            &self.payload,
+        )
+    }
+}
+
+/// Returns the hash of the message that the L1 handler transaction consumes, or None if the
+/// transaction has no calldata. The first element of the calldata of an L1 handler transaction is
+/// the address of the message sender, and the rest is the message payload.
+pub fn l1_handler_message_hash(transaction: &L1HandlerTransaction) -> Option<L1ToL2MessageHash> {
+    let (from_address, payload) = transaction.calldata.0.split_first()?;
+    Some(calculate_message_hash(
+        from_address.bytes(),
+        &transaction.contract_address,
+        &transaction.nonce,
+        &transaction.entry_point_selector,
+        payload,
+    ))
+}
+
+// For more info: https://docs.starknet.io/documentation/architecture_and_concepts/Network_Architecture/messaging-mechanism/#structure_and_hashing_l1-l2
+fn calculate_message_hash(
+    from_address: &[u8],
+    to_address: &ContractAddress,
+    nonce: &Nonce,
+    entry_point_selector: &EntryPointSelector,
+    payload: &[StarkFelt],
+) -> L1ToL2MessageHash {
+    let mut keccak = Keccak256::default();
+    keccak.update(from_address);
+    keccak.update(to_address.0.key().bytes());
+    keccak.update(nonce.0.bytes());
+    keccak.update(entry_point_selector.0.bytes());
+    keccak.update(StarkFelt::from(payload.len() as u64).bytes());
+    for felt in payload {
+        keccak.update(felt.bytes());
+    }
+    L1ToL2MessageHash(keccak.finalize().into())
+}
+
+macro_rules! impl_hex_serde {
+    ($hash_type:ident) => {
+        impl Display for $hash_type {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "0x{}", hex::encode(self.0))
+            }
+        }
+
+        impl Serialize for $hash_type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.to_string())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $hash_type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                Ok(Self(bytes_from_hex_str::<32, true>(&s).map_err(serde::de::Error::custom)?))
+            }
+        }
+    };
+}
+
+impl_hex_serde!(L1ToL2MessageHash);
+impl_hex_serde!(L1TransactionHash);
@@ -0,0 +1,64 @@
+use pretty_assertions::assert_eq;
+use primitive_types::H160;
+use starknet_api::core::{ContractAddress, EntryPointSelector, EthAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{Calldata, L1HandlerTransaction, TransactionVersion};
+use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
+
+use super::{l1_handler_message_hash, L1ToL2Message, L1ToL2MessageHash, L1TransactionHash};
+
+// A message sent on mainnet, and the L1 handler transaction that consumed it.
+const MESSAGE_HASH: &str = "0xd667cda2d870b8146c115cc4e93d701b3e34313686e5925ddc421576a1c8bbd2";
+const FROM_ADDRESS: &str = "0x18e4a8e2badb5f5950758f46f8108e2c5d357b07";
+
+fn l1_handler_transaction() -> L1HandlerTransaction {
+    L1HandlerTransaction {
+        version: TransactionVersion::ZERO,
+        nonce: Nonce(stark_felt!("0xc01b3")),
+        contract_address: contract_address!(
+            "0x55350a859da02cb244c8c09f29bc38047cef93d38b72033a0e8be03d24c5756"
+        ),
+        entry_point_selector: EntryPointSelector(stark_felt!(
+            "0x3fa70707d0e831418fb142ca8fb7483611b84e89c0c42bf1fc2a7a5c40890ad"
+        )),
+        calldata: calldata![
+            stark_felt!(FROM_ADDRESS),
+            stark_felt!("0x10ae809a95d34dd22538e6c30bec2e11"),
+            stark_felt!("0x8eacfcd7b4046547e3cbe5ff4f08c1f9"),
+            stark_felt!("0x99c3dd"),
+            stark_felt!("0x0")
+        ],
+    }
+}
+
+#[test]
+fn l1_handler_transaction_message_hash() {
+    let message_hash = l1_handler_message_hash(&l1_handler_transaction()).unwrap();
+    assert_eq!(message_hash.to_string(), MESSAGE_HASH);
+
+    let without_calldata =
+        L1HandlerTransaction { calldata: calldata![], ..l1_handler_transaction() };
+    assert_eq!(l1_handler_message_hash(&without_calldata), None);
+}
+
+#[test]
+fn message_hash_matches_l1_handler_transaction() {
+    let transaction = l1_handler_transaction();
+    let message = L1ToL2Message {
+        l1_transaction_hash: L1TransactionHash::default(),
+        from_address: EthAddress(FROM_ADDRESS.parse::<H160>().unwrap()),
+        to_address: transaction.contract_address,
+        entry_point_selector: transaction.entry_point_selector,
+        payload: transaction.calldata.0[1..].to_vec(),
+        nonce: transaction.nonce,
+    };
+    assert_eq!(Some(message.hash()), l1_handler_message_hash(&transaction));
+}
+
+#[test]
+fn message_hash_serde() {
+    let serialized = serde_json::to_string(&MESSAGE_HASH).unwrap();
+    let message_hash = serde_json::from_str::<L1ToL2MessageHash>(&serialized).unwrap();
+    assert_eq!(message_hash, l1_handler_message_hash(&l1_handler_transaction()).unwrap());
+    assert_eq!(serde_json::to_string(&message_hash).unwrap(), serialized);
+}
@@ -2,9 +2,11 @@ use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockNumber};
 
 pub mod block_hash;
+pub mod l1_to_l2_messages;
 pub mod metrics;
 pub mod patricia_hash_tree;
 pub mod pending_classes;
+pub mod raw_transaction;
 pub mod state;
 pub mod transaction_hash;
 
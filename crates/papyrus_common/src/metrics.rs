@@ -21,3 +21,10 @@ pub const PAPYRUS_BASE_LAYER_MARKER: &str = "papyrus_base_layer_marker";
 /// The latency, in seconds, between a block timestamp (as state in its header) and the time the
 /// node stores the header.
 pub const PAPYRUS_HEADER_LATENCY_SEC: &str = "papyrus_header_latency";
+
+/// The number of blocks between the central block marker and the body marker, when the node last
+/// checked for new blocks.
+pub const PAPYRUS_SYNC_LAG_BLOCKS: &str = "papyrus_sync_lag_blocks";
+
+/// The number of peers the node is connected to in the p2p network.
+pub const PAPYRUS_CONNECTED_PEERS: &str = "papyrus_connected_peers";
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The raw JSON of a transaction and its receipt, as the feeder gateway returned them.
+///
+/// It's kept for the transactions that have data which the node doesn't model, such as fields that
+/// newer versions of the feeder gateway added, so that the data isn't lost and can be served back.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct RawTransaction {
+    pub transaction: Value,
+    pub receipt: Value,
+}
@@ -25,6 +25,10 @@ pub(crate) fn update_config_map_by_command_args(
 ) -> Result<(), ConfigError> {
     for param_path_id in arg_match.ids() {
         let param_path = param_path_id.as_str();
+        // The arguments of the command itself aren't params, and are handled by its caller.
+        if !types_map.contains_key(param_path) {
+            continue;
+        }
         let new_value = get_arg_by_type(types_map, arg_match, param_path)?;
         update_config_map(config_map, types_map, param_path, new_value)?;
     }
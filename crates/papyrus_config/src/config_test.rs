@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use assert_matches::assert_matches;
-use clap::Command;
+use clap::{Arg, ArgAction, Command};
 use itertools::chain;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
@@ -15,7 +15,7 @@ use test_utils::get_absolute_path;
 use validator::Validate;
 
 use crate::command::{get_command_matches, update_config_map_by_command_args};
-use crate::converters::deserialize_milliseconds_to_duration;
+use crate::converters::{deserialize_milliseconds_to_duration, deserialize_vec, serialize_slice};
 use crate::dumping::{
     append_sub_config_name,
     combine_config_map_and_pointers,
@@ -310,6 +310,58 @@ fn test_load_many_custom_config_files() {
     assert_eq!(param_path, "custom value");
 }
 
+#[test]
+fn test_load_custom_toml_config_file() {
+    let dir = TempDir::new().unwrap();
+    let file_path = dir.path().join("config.json");
+    OuterConfig { opt_elem: None, opt_config: None, inner_config: InnerConfig { o: 5 } }
+        .dump_to_file(&vec![], file_path.to_str().unwrap())
+        .unwrap();
+    // The params of the sub configs are grouped in tables.
+    let custom_config_path = dir.path().join("custom_config.toml");
+    std::fs::write(
+        &custom_config_path,
+        "[opt_config]\n\"#is_none\" = false\no = 7\n\n[inner_config]\no = 8\n",
+    )
+    .unwrap();
+
+    let args = vec!["Testing", "-f", custom_config_path.to_str().unwrap()];
+    let loaded_config = load_and_process_config::<OuterConfig>(
+        File::open(file_path).unwrap(),
+        Command::new("Program"),
+        args.into_iter().map(|s| s.to_owned()).collect(),
+    )
+    .unwrap();
+    assert_eq!(
+        loaded_config,
+        OuterConfig {
+            opt_elem: None,
+            opt_config: Some(InnerConfig { o: 7 }),
+            inner_config: InnerConfig { o: 8 },
+        }
+    );
+}
+
+#[test]
+fn test_command_args_are_not_params() {
+    let dir = TempDir::new().unwrap();
+    let file_path = dir.path().join("config.json");
+    CustomConfig { param_path: "default value".to_owned(), seed: 5 }
+        .dump_to_file(&vec![], file_path.to_str().unwrap())
+        .unwrap();
+
+    let command =
+        Command::new("Program").arg(Arg::new("flag").long("flag").action(ArgAction::SetTrue));
+    let args = vec!["Testing", "--flag", "--param_path", "command value"];
+    let loaded_config = load_and_process_config::<CustomConfig>(
+        File::open(file_path).unwrap(),
+        command,
+        args.into_iter().map(|s| s.to_owned()).collect(),
+    )
+    .unwrap();
+    assert_eq!(loaded_config.param_path, "command value");
+}
+
 #[test]
 fn test_generated_type() {
     let args = vec!["Testing"];
@@ -328,6 +380,23 @@ fn serialization_precision() {
     assert_eq!(input, deserialized);
 }
 
+#[test]
+fn dump_and_load_vec() {
+    #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+    struct VecConfig {
+        #[serde(deserialize_with = "deserialize_vec")]
+        values: Vec<u32>,
+    }
+
+    for values in [vec![], vec![1], vec![1, 2, 3]] {
+        let dumped = BTreeMap::from([("values".to_owned(), json!(serialize_slice(&values)))]);
+        assert_eq!(load::<VecConfig>(&dumped).unwrap(), VecConfig { values });
+    }
+
+    let dumped = BTreeMap::from([("values".to_owned(), json!("1 a"))]);
+    assert_matches!(load::<VecConfig>(&dumped), Err(ConfigError::MissingParam(_)));
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 struct RequiredConfig {
     param_path: String,
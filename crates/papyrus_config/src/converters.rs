@@ -25,6 +25,8 @@
 //! ```
 
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
 use std::time::Duration;
 
 use serde::de::Error;
@@ -78,3 +80,26 @@ where
     }
     Ok(Some(map))
 }
+
+/// Serializes a list to a "v1 v2" string structure.
+pub fn serialize_slice<T: Display>(values: &[T]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+/// Deserializes a list from a "v1 v2" string structure.
+pub fn deserialize_vec<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let raw_str: String = Deserialize::deserialize(de)?;
+    raw_str
+        .split_whitespace()
+        .map(|raw_value| {
+            raw_value.parse().map_err(|err| {
+                D::Error::custom(format!("value \"{raw_value}\" is not valid: {err}"))
+            })
+        })
+        .collect()
+}
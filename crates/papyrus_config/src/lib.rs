@@ -159,6 +159,8 @@ pub enum ConfigError {
     CommandMatches(#[from] MatchesError),
     #[error(transparent)]
     IOError(#[from] std::io::Error),
+    #[error(transparent)]
+    TomlError(#[from] toml::de::Error),
     #[error("Insert a new param is not allowed.")]
     ParamNotFound { param_path: String },
     #[error("{target_param} is not found.")]
@@ -3,11 +3,18 @@
 //! * Environment variables (capital letters).
 //! * Custom config files, separated by ',' (comma), from last to first.
 //! * Default config file.
+//!
+//! The custom config files are either JSON files of param paths to values, or TOML files (with the
+//! `.toml` extension) in which the params may be grouped in tables, for example:
+//! ```toml
+//! [rpc]
+//! server_address = "0.0.0.0:8080"
+//! ```
 
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::ops::IndexMut;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::parser::Values;
 use clap::Command;
@@ -125,8 +132,7 @@ pub(crate) fn update_config_map_by_custom_configs(
 ) -> Result<(), ConfigError> {
     for config_path in custom_config_paths {
         validate_path_exists(&config_path)?;
-        let file = std::fs::File::open(config_path)?;
-        let custom_config: Map<String, Value> = serde_json::from_reader(file)?;
+        let custom_config = read_custom_config(&config_path)?;
         for (param_path, json_value) in custom_config {
             update_config_map(config_map, types_map, param_path.as_str(), json_value)?;
         }
@@ -134,6 +140,31 @@ pub(crate) fn update_config_map_by_custom_configs(
     Ok(())
 }
 
+// Reads a custom config file into a map of param paths to values.
+fn read_custom_config(config_path: &Path) -> Result<Map<String, Value>, ConfigError> {
+    if config_path.extension().and_then(|extension| extension.to_str()) != Some("toml") {
+        let file = std::fs::File::open(config_path)?;
+        return Ok(serde_json::from_reader(file)?);
+    }
+    let custom_config: Map<String, Value> = toml::from_str(&std::fs::read_to_string(config_path)?)?;
+    let mut flat_custom_config = Map::new();
+    flatten_tables("", custom_config, &mut flat_custom_config);
+    Ok(flat_custom_config)
+}
+
+// The values of the params are never tables, so the tables of a TOML config are sub configs.
+fn flatten_tables(prefix: &str, table: Map<String, Value>, flat_config: &mut Map<String, Value>) {
+    for (name, value) in table {
+        let param_path = if prefix.is_empty() { name } else { format!("{prefix}.{name}") };
+        match value {
+            Value::Object(inner_table) => flatten_tables(&param_path, inner_table, flat_config),
+            value => {
+                flat_config.insert(param_path, value);
+            }
+        }
+    }
+}
+
 // Sets values in the config map to the params in the pointers map.
 pub(crate) fn update_config_map_by_pointers(
     config_map: &mut BTreeMap<ParamPath, Value>,
@@ -0,0 +1,53 @@
+//! A handle for aborting executions that are no longer needed.
+//!
+//! The blockifier can't be interrupted, so executions check the handle between transactions and on
+//! every state read, and stop once it's aborted.
+#[cfg(test)]
+#[path = "abort_handle_test.rs"]
+mod abort_handle_test;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A handle for aborting an execution, either explicitly or once a deadline passes. The default
+/// handle is never aborted. Cloning the handle returns a handle to the same execution.
+#[derive(Clone, Debug, Default)]
+pub struct AbortHandle {
+    aborted: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl AbortHandle {
+    /// Creates a handle that is aborted once the given timeout passes.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self { aborted: Arc::default(), deadline: Instant::now().checked_add(timeout) }
+    }
+
+    /// Aborts the execution.
+    pub fn abort(&self) {
+        self.aborted.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the execution was aborted or its deadline passed.
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+            || self.deadline.map_or(false, |deadline| Instant::now() >= deadline)
+    }
+
+    /// Returns a guard that aborts the execution when it's dropped. Useful for tying the execution
+    /// to the lifetime of the request that started it.
+    pub fn abort_on_drop(&self) -> AbortOnDrop {
+        AbortOnDrop(self.clone())
+    }
+}
+
+/// Aborts an execution when dropped. See [`AbortHandle::abort_on_drop`].
+#[derive(Debug)]
+pub struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use crate::abort_handle::AbortHandle;
+
+#[test]
+fn default_handle_is_not_aborted() {
+    assert!(!AbortHandle::default().is_aborted());
+}
+
+#[test]
+fn abort_affects_clones() {
+    let handle = AbortHandle::default();
+    let cloned_handle = handle.clone();
+    cloned_handle.abort();
+    assert!(handle.is_aborted());
+}
+
+#[test]
+fn aborted_after_timeout() {
+    assert!(AbortHandle::with_timeout(Duration::ZERO).is_aborted());
+    assert!(!AbortHandle::with_timeout(Duration::from_secs(3600)).is_aborted());
+}
+
+#[test]
+fn abort_on_drop() {
+    let handle = AbortHandle::default();
+    let guard = handle.abort_on_drop();
+    assert!(!handle.is_aborted());
+    drop(guard);
+    assert!(handle.is_aborted());
+}
@@ -0,0 +1,101 @@
+//! A cache of compiled contract classes shared between executions.
+//!
+//! Converting a class from its storage representation into a class the blockifier can run requires
+//! parsing the whole program, which for big classes is a significant part of the execution time.
+//! Since a class hash always identifies the same class, the converted classes can be reused by all
+//! the executions, regardless of the state they're executed on.
+#[cfg(test)]
+#[path = "contract_class_cache_test.rs"]
+mod contract_class_cache_test;
+
+use std::sync::{Arc, Mutex};
+
+use blockifier::execution::contract_class::ContractClass as BlockifierContractClass;
+use lru::LruCache;
+use starknet_api::core::ClassHash;
+
+use crate::execution_metrics::record_class_cache_lookup;
+
+/// An LRU cache of compiled contract classes, bounded by the estimated memory size of the classes
+/// it holds. Cloning the cache returns a handle to the same underlying cache.
+#[derive(Clone)]
+pub struct ContractClassCache {
+    inner: Arc<Mutex<ContractClassCacheInner>>,
+}
+
+struct ContractClassCacheInner {
+    classes: LruCache<ClassHash, CachedClass>,
+    max_size_bytes: usize,
+    total_size_bytes: usize,
+}
+
+struct CachedClass {
+    contract_class: BlockifierContractClass,
+    size_bytes: usize,
+}
+
+impl ContractClassCache {
+    /// Creates an empty cache that holds classes with a total estimated size of at most
+    /// `max_size_bytes`. A cache with a budget of 0 bytes never holds any class.
+    pub fn new(max_size_bytes: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ContractClassCacheInner {
+                classes: LruCache::unbounded(),
+                max_size_bytes,
+                total_size_bytes: 0,
+            })),
+        }
+    }
+
+    /// Returns the class with the given hash if it's in the cache, and marks it as the most
+    /// recently used class.
+    pub fn get(&self, class_hash: &ClassHash) -> Option<BlockifierContractClass> {
+        let mut inner = self.inner.lock().expect("Failed to lock contract class cache.");
+        let contract_class =
+            inner.classes.get(class_hash).map(|cached_class| cached_class.contract_class.clone());
+        record_class_cache_lookup(contract_class.is_some());
+        contract_class
+    }
+
+    /// Inserts a class with the given estimated size to the cache, evicting the least recently used
+    /// classes until the cache is within its budget. Classes that are bigger than the entire budget
+    /// aren't cached.
+    pub fn insert(
+        &self,
+        class_hash: ClassHash,
+        contract_class: BlockifierContractClass,
+        size_bytes: usize,
+    ) {
+        let mut inner = self.inner.lock().expect("Failed to lock contract class cache.");
+        if size_bytes > inner.max_size_bytes {
+            return;
+        }
+        if let Some(replaced_class) =
+            inner.classes.put(class_hash, CachedClass { contract_class, size_bytes })
+        {
+            inner.total_size_bytes -= replaced_class.size_bytes;
+        }
+        inner.total_size_bytes += size_bytes;
+        while inner.total_size_bytes > inner.max_size_bytes {
+            let Some((_, evicted_class)) = inner.classes.pop_lru() else {
+                break;
+            };
+            inner.total_size_bytes -= evicted_class.size_bytes;
+        }
+    }
+
+    /// Returns the number of classes in the cache.
+    pub fn len(&self) -> usize {
+        self.inner.lock().expect("Failed to lock contract class cache.").classes.len()
+    }
+
+    /// Returns true if the cache holds no classes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the sum of the estimated sizes of the classes in the cache.
+    pub fn size_bytes(&self) -> usize {
+        self.inner.lock().expect("Failed to lock contract class cache.").total_size_bytes
+    }
+}
@@ -0,0 +1,89 @@
+use blockifier::execution::contract_class::{
+    ContractClass as BlockifierContractClass,
+    ContractClassV0,
+    ContractClassV1,
+};
+use papyrus_storage::test_utils::get_test_storage;
+use starknet_api::block::BlockNumber;
+use starknet_api::class_hash;
+use starknet_api::core::ClassHash;
+use starknet_api::hash::StarkHash;
+use starknet_api::state::StateNumber;
+
+use crate::contract_class_cache::ContractClassCache;
+use crate::execution_utils::get_contract_class;
+use crate::test_utils::{get_test_casm, get_test_deprecated_contract_class, prepare_storage};
+
+fn test_class() -> BlockifierContractClass {
+    BlockifierContractClass::V1(ContractClassV1::try_from(get_test_casm()).unwrap())
+}
+
+#[test]
+fn evicts_least_recently_used_classes() {
+    let cache = ContractClassCache::new(10);
+    cache.insert(class_hash!("0x1"), test_class(), 4);
+    cache.insert(class_hash!("0x2"), test_class(), 4);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.size_bytes(), 8);
+
+    // Mark class 0x1 as recently used so that class 0x2 is the one evicted.
+    assert!(cache.get(&class_hash!("0x1")).is_some());
+    cache.insert(class_hash!("0x3"), test_class(), 4);
+    assert!(cache.get(&class_hash!("0x1")).is_some());
+    assert!(cache.get(&class_hash!("0x2")).is_none());
+    assert!(cache.get(&class_hash!("0x3")).is_some());
+    assert_eq!(cache.size_bytes(), 8);
+
+    // Re-inserting a class replaces its size instead of accumulating it.
+    cache.insert(class_hash!("0x3"), test_class(), 6);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.size_bytes(), 10);
+}
+
+#[test]
+fn class_bigger_than_budget_is_not_cached() {
+    let cache = ContractClassCache::new(10);
+    cache.insert(class_hash!("0x1"), test_class(), 4);
+    cache.insert(class_hash!("0x2"), test_class(), 11);
+    assert!(cache.get(&class_hash!("0x2")).is_none());
+    // The classes that are already in the cache aren't evicted.
+    assert!(cache.get(&class_hash!("0x1")).is_some());
+
+    let disabled_cache = ContractClassCache::new(0);
+    disabled_cache.insert(class_hash!("0x1"), test_class(), 1);
+    assert!(disabled_cache.is_empty());
+}
+
+#[test]
+fn get_contract_class_fills_cache() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let cache = ContractClassCache::new(usize::MAX);
+
+    // These classes are declared in block 0 of the test storage.
+    let class_hash = class_hash!("0x2");
+    let deprecated_class_hash = class_hash!("0x1");
+    let state_number = StateNumber::right_after_block(BlockNumber(0));
+
+    let class = get_contract_class(&txn, &class_hash, state_number, Some(&cache)).unwrap();
+    assert_eq!(class, Some(test_class()));
+    let deprecated_class =
+        get_contract_class(&txn, &deprecated_class_hash, state_number, Some(&cache)).unwrap();
+    let expected_deprecated_class = BlockifierContractClass::V0(
+        ContractClassV0::try_from(get_test_deprecated_contract_class()).unwrap(),
+    );
+    assert_eq!(deprecated_class, Some(expected_deprecated_class.clone()));
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&class_hash), Some(test_class()));
+    assert_eq!(cache.get(&deprecated_class_hash), Some(expected_deprecated_class));
+
+    // Cached classes are still not visible in states before their declaration.
+    let state_before_declaration = StateNumber::right_before_block(BlockNumber(0));
+    for class_hash in [class_hash, deprecated_class_hash] {
+        let class =
+            get_contract_class(&txn, &class_hash, state_before_declaration, Some(&cache)).unwrap();
+        assert!(class.is_none());
+    }
+}
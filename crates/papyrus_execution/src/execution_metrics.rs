@@ -0,0 +1,61 @@
+//! Metrics of the executions, for sizing the hardware of nodes that serve execution requests.
+//!
+//! The latencies of executions are recorded by the `execution_call_latency_seconds` and
+//! `execution_transactions_latency_seconds` histograms.
+use metrics::{counter, increment_counter};
+
+use crate::ExecutionError;
+
+// Name of the metrics.
+const CAIRO_STEPS: &str = "execution_cairo_steps";
+const BUILTIN_USAGE: &str = "execution_builtin_usage";
+const CLASS_CACHE_HITS: &str = "execution_class_cache_hits";
+const CLASS_CACHE_MISSES: &str = "execution_class_cache_misses";
+const FAILED_EXECUTIONS: &str = "execution_failures";
+
+// Labels for the metrics.
+const BUILTIN_LABEL: &str = "builtin";
+const REASON_LABEL: &str = "reason";
+
+// Suffix of the names of builtins in the resources of a transaction.
+const BUILTIN_SUFFIX: &str = "_builtin";
+
+// Records the Cairo steps and builtins used by an execution. Resources that aren't builtins are
+// ignored.
+pub(crate) fn record_execution_resources<'a>(
+    n_steps: usize,
+    resources: impl IntoIterator<Item = (&'a String, &'a usize)>,
+) {
+    counter!(CAIRO_STEPS, n_steps as u64);
+    for (resource, usage) in resources {
+        if resource.ends_with(BUILTIN_SUFFIX) {
+            counter!(BUILTIN_USAGE, *usage as u64, BUILTIN_LABEL => resource.clone());
+        }
+    }
+}
+
+// Records a lookup in the class cache.
+pub(crate) fn record_class_cache_lookup(hit: bool) {
+    if hit {
+        increment_counter!(CLASS_CACHE_HITS);
+    } else {
+        increment_counter!(CLASS_CACHE_MISSES);
+    }
+}
+
+// Records an execution that failed with the given error.
+pub(crate) fn record_failure(err: &ExecutionError) {
+    let reason = match err {
+        ExecutionError::ContractError { .. } => "contract_error",
+        ExecutionError::TransactionExecutionError { .. } => "transaction_execution_error",
+        ExecutionError::MissingCompiledClass { .. } => "missing_compiled_class",
+        ExecutionError::ExecutionAborted => "aborted",
+        _ => "other",
+    };
+    increment_counter!(FAILED_EXECUTIONS, REASON_LABEL => reason);
+}
+
+// Records a transaction that was reverted.
+pub(crate) fn record_revert() {
+    increment_counter!(FAILED_EXECUTIONS, REASON_LABEL => "reverted");
+}
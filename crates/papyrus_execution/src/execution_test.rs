@@ -1,7 +1,9 @@
 // TODO(shahak): Add a test for executing when there's a missing casm that's not required and when
 // there's a missing casm that is required.
 use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use assert_matches::assert_matches;
 use blockifier::abi::abi_utils::get_storage_var_address;
@@ -9,9 +11,13 @@ use blockifier::abi::constants::STEP_GAS_COST;
 use blockifier::execution::call_info::Retdata;
 use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
 use indexmap::indexmap;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use pretty_assertions::assert_eq;
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{BlockBody, BlockHeader, BlockNumber};
 use starknet_api::core::{
     ChainId,
     ClassHash,
@@ -21,11 +27,13 @@ use starknet_api::core::{
     PatriciaKey,
 };
 use starknet_api::hash::{StarkFelt, StarkHash};
-use starknet_api::state::{StateNumber, ThinStateDiff};
+use starknet_api::state::{ContractClass, StateDiff, StateNumber, StorageKey, ThinStateDiff};
 use starknet_api::transaction::{Calldata, Fee};
 use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
 
-use crate::execution_utils::selector_from_name;
+use crate::abort_handle::AbortHandle;
+use crate::contract_class_cache::ContractClassCache;
+use crate::execution_utils::{compile_class, selector_from_name};
 use crate::objects::{
     DeclareTransactionTrace,
     DeployAccountTransactionTrace,
@@ -37,6 +45,7 @@ use crate::objects::{
 };
 use crate::test_utils::{
     execute_simulate_transactions,
+    get_test_sierra_class,
     prepare_storage,
     TxsScenarioBuilder,
     ACCOUNT_ADDRESS,
@@ -52,12 +61,16 @@ use crate::test_utils::{
 };
 use crate::testing_instances::{test_block_execution_config, test_get_default_execution_config};
 use crate::{
+    create_block_context,
+    error_message_with_sources,
     estimate_fee,
     execute_call,
+    simulate_transactions,
     BlockExecutionConfig,
     ExecutableTransactionInput,
     ExecutionConfigByBlock,
     ExecutionError,
+    ExecutionFlags,
     FeeEstimationResult,
     RevertedTransaction,
 };
@@ -82,6 +95,9 @@ fn execute_call_cairo0() {
         selector_from_name("without_arg"),
         Calldata::default(),
         &test_block_execution_config(),
+        None,
+        None,
+        AbortHandle::default(),
     )
     .unwrap()
     .retdata;
@@ -98,6 +114,9 @@ fn execute_call_cairo0() {
         selector_from_name("with_arg"),
         Calldata(Arc::new(vec![StarkFelt::from(25u128)])),
         &test_block_execution_config(),
+        None,
+        None,
+        AbortHandle::default(),
     )
     .unwrap()
     .retdata;
@@ -114,6 +133,9 @@ fn execute_call_cairo0() {
         selector_from_name("return_result"),
         Calldata(Arc::new(vec![StarkFelt::from(123u128)])),
         &test_block_execution_config(),
+        None,
+        None,
+        AbortHandle::default(),
     )
     .unwrap()
     .retdata;
@@ -130,12 +152,69 @@ fn execute_call_cairo0() {
         selector_from_name("test_storage_read_write"),
         Calldata(Arc::new(vec![StarkFelt::from(123u128), StarkFelt::from(456u128)])),
         &test_block_execution_config(),
+        None,
+        None,
+        AbortHandle::default(),
     )
     .unwrap()
     .retdata;
     assert_eq!(retdata, Retdata(vec![StarkFelt::from(456u128)]));
 }
 
+// Test that a deprecated class can call another deprecated class that is looked up by its hash.
+#[test]
+fn execute_call_cairo0_library_call() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let deprecated_class_hash = stark_felt!("0x1");
+    let lib_selector = selector_from_name("return_result");
+    let calldata =
+        calldata![deprecated_class_hash, lib_selector.0, stark_felt!(1_u8), stark_felt!(7_u8)];
+
+    let retdata = execute_call(
+        storage_reader,
+        None,
+        &CHAIN_ID,
+        StateNumber::right_after_block(BlockNumber(0)),
+        BlockNumber(0),
+        &DEPRECATED_CONTRACT_ADDRESS,
+        selector_from_name("test_library_call"),
+        calldata,
+        &test_block_execution_config(),
+        None,
+        None,
+        AbortHandle::default(),
+    )
+    .unwrap()
+    .retdata;
+    assert_eq!(retdata, Retdata(vec![stark_felt!(1_u8), stark_felt!(7_u8)]));
+}
+
+// Deprecated classes fall back to their default entry point when the selector is missing. The test
+// class has no default entry point, so the call should fail.
+#[test]
+fn execute_call_cairo0_missing_entry_point() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let result = execute_call(
+        storage_reader,
+        None,
+        &CHAIN_ID,
+        StateNumber::right_after_block(BlockNumber(0)),
+        BlockNumber(0),
+        &DEPRECATED_CONTRACT_ADDRESS,
+        selector_from_name("non_existing_entry_point"),
+        Calldata::default(),
+        &test_block_execution_config(),
+        None,
+        None,
+        AbortHandle::default(),
+    );
+    assert_matches!(result, Err(ExecutionError::ContractError { .. }));
+}
+
 // Test calling entry points of a cairo 1 class.
 #[test]
 fn execute_call_cairo1() {
@@ -157,6 +236,9 @@ fn execute_call_cairo1() {
         selector_from_name("test_storage_read_write"),
         calldata,
         &test_block_execution_config(),
+        None,
+        None,
+        AbortHandle::default(),
     )
     .unwrap()
     .retdata;
@@ -236,7 +318,7 @@ fn estimate_fee_reverted() {
         .collect();
 
     let failed_estimation = estimate_fees(txs).expect_err("Fee estimation should fail.");
-    assert_matches!(failed_estimation, RevertedTransaction { index: 1, revert_reason: _ })
+    assert_matches!(failed_estimation, RevertedTransaction { index: 1, .. })
 }
 
 fn estimate_fees(txs: Vec<ExecutableTransactionInput>) -> FeeEstimationResult {
@@ -251,11 +333,64 @@ fn estimate_fees(txs: Vec<ExecutableTransactionInput>) -> FeeEstimationResult {
         StateNumber::right_after_block(BlockNumber(0)),
         BlockNumber(1),
         &test_block_execution_config(),
-        false,
+        ExecutionFlags { validate: false, charge_fee: false },
+        None,
+        AbortHandle::default(),
     )
     .unwrap()
 }
 
+#[test]
+fn aborted_execution() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let tx = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    let abort_handle = AbortHandle::default();
+    abort_handle.abort();
+    let result = simulate_transactions(
+        tx,
+        None,
+        &CHAIN_ID,
+        storage_reader,
+        None,
+        StateNumber::right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &test_block_execution_config(),
+        ExecutionFlags::default(),
+        None,
+        None,
+        abort_handle,
+    );
+    assert_matches!(result, Err(ExecutionError::ExecutionAborted));
+}
+
+#[test]
+fn aborted_call() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let abort_handle = AbortHandle::default();
+    abort_handle.abort();
+    let result = execute_call(
+        storage_reader,
+        None,
+        &CHAIN_ID,
+        StateNumber::right_after_block(BlockNumber(0)),
+        BlockNumber(0),
+        &DEPRECATED_CONTRACT_ADDRESS,
+        selector_from_name("without_arg"),
+        Calldata::default(),
+        &test_block_execution_config(),
+        None,
+        None,
+        abort_handle,
+    );
+    assert_matches!(result, Err(ExecutionError::ExecutionAborted));
+}
+
 #[test]
 fn serialization_precision() {
     let input =
@@ -666,12 +801,17 @@ fn test_default_execution_config() {
         fee_contract_address: contract_address!(
             "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"
         ),
+        strk_fee_contract_address: contract_address!(
+            "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d"
+        ),
         invoke_tx_max_n_steps: 3_000_000,
         validate_tx_max_n_steps: 1_000_000,
         max_recursion_depth: 50,
         step_gas_cost: STEP_GAS_COST,
         initial_gas_cost: 10_u64.pow(8) * STEP_GAS_COST,
         vm_resource_fee_cost,
+        eth_l1_gas_price_override: None,
+        strk_l1_gas_price_override: None,
     };
     let mut execution_config_segments = BTreeMap::new();
     execution_config_segments.insert(BlockNumber(0), block_execution_config);
@@ -680,17 +820,73 @@ fn test_default_execution_config() {
     assert_eq!(expected_config, config_from_file);
 }
 
+// Test that the execution config of a chain is loaded from a directory by its chain id.
+#[test]
+fn load_execution_config_of_chain() {
+    let mainnet_config_file = Path::new("../../config/execution/mainnet.json");
+    let config_dir = tempfile::tempdir().unwrap();
+    std::fs::copy(mainnet_config_file, config_dir.path().join("SN_MAIN.json")).unwrap();
+
+    let config =
+        ExecutionConfigByBlock::load(config_dir.path(), &ChainId("SN_MAIN".to_owned())).unwrap();
+    assert_eq!(config, test_get_default_execution_config());
+    assert_matches!(
+        ExecutionConfigByBlock::load(config_dir.path(), &ChainId("SN_OTHER".to_owned())),
+        Err(ExecutionError::ConfigFileError(_))
+    );
+
+    // A config file is used for any chain.
+    let config =
+        ExecutionConfigByBlock::load(mainnet_config_file, &ChainId("SN_OTHER".to_owned())).unwrap();
+    assert_eq!(config, test_get_default_execution_config());
+}
+
+#[test]
+fn gas_price_overrides() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+    let header =
+        storage_reader.begin_ro_txn().unwrap().get_block_header(BlockNumber(0)).unwrap().unwrap();
+
+    let mut execution_config = test_block_execution_config();
+    let block_context = create_block_context(
+        BlockNumber(0),
+        CHAIN_ID.clone(),
+        &storage_reader,
+        None,
+        &execution_config,
+    )
+    .unwrap();
+    assert_eq!(block_context.gas_prices.eth_l1_gas_price, header.eth_l1_gas_price.0);
+
+    execution_config.eth_l1_gas_price_override = Some(header.eth_l1_gas_price.0 + 1);
+    let block_context = create_block_context(
+        BlockNumber(0),
+        CHAIN_ID.clone(),
+        &storage_reader,
+        None,
+        &execution_config,
+    )
+    .unwrap();
+    assert_eq!(block_context.gas_prices.eth_l1_gas_price, header.eth_l1_gas_price.0 + 1);
+    // Prices without an override are still taken from the block.
+    assert_eq!(block_context.gas_prices.strk_l1_gas_price, header.strk_l1_gas_price.0);
+}
+
 fn fill_up_block_execution_config_segment_with_value(value: usize) -> BlockExecutionConfig {
     let vm_resource_fee_cost = HashMap::new();
     let vm_resource_fee_cost = Arc::new(vm_resource_fee_cost);
     BlockExecutionConfig {
         fee_contract_address: contract_address!(format!("{:x}", value).as_str()),
+        strk_fee_contract_address: contract_address!(format!("{:x}", value).as_str()),
         invoke_tx_max_n_steps: value as u32,
         validate_tx_max_n_steps: value as u32,
         max_recursion_depth: value,
         step_gas_cost: value as u64,
         initial_gas_cost: value as u64,
         vm_resource_fee_cost,
+        eth_l1_gas_price_override: None,
+        strk_l1_gas_price_override: None,
     }
 }
 
@@ -855,6 +1051,24 @@ fn simulate_with_query_bit_outputs_same_as_no_query_bit() {
     assert_eq!(res_only_query, res_regular);
 }
 
+#[derive(Debug, thiserror::Error)]
+#[error("Outer error: {0}")]
+struct OuterError(#[source] MiddleError);
+
+#[derive(Debug, thiserror::Error)]
+#[error("Middle error.")]
+struct MiddleError(#[source] InnerError);
+
+#[derive(Debug, thiserror::Error)]
+#[error("Inner error.")]
+struct InnerError;
+
+#[test]
+fn error_message_contains_sources() {
+    let error = OuterError(MiddleError(InnerError));
+    assert_eq!(error_message_with_sources(&error), "Outer error: Middle error.\nInner error.");
+}
+
 // Test that we provide the correct messages for different blockifier error variants.
 // TODO(yair): remove once blockifier arranges the errors.
 #[test]
@@ -864,7 +1078,7 @@ fn blockifier_error_mapping() {
     let blockifier_err =
         BlockifierTransactionExecutionError::ContractConstructorExecutionFailed(child);
     let err = ExecutionError::from((0, blockifier_err));
-    let ExecutionError::TransactionExecutionError { transaction_index, execution_error } = err
+    let ExecutionError::TransactionExecutionError { transaction_index, execution_error, .. } = err
     else {
         panic!("unexpected variant")
     };
@@ -875,7 +1089,7 @@ fn blockifier_error_mapping() {
     let expected = format!("Transaction execution has failed: {child}");
     let blockifier_err = BlockifierTransactionExecutionError::ExecutionError(child);
     let err = ExecutionError::from((0, blockifier_err));
-    let ExecutionError::TransactionExecutionError { transaction_index, execution_error } = err
+    let ExecutionError::TransactionExecutionError { transaction_index, execution_error, .. } = err
     else {
         panic!("unexpected variant")
     };
@@ -886,10 +1100,80 @@ fn blockifier_error_mapping() {
     let expected = format!("Transaction validation has failed: {child}");
     let blockifier_err = BlockifierTransactionExecutionError::ValidateTransactionError(child);
     let err = ExecutionError::from((0, blockifier_err));
-    let ExecutionError::TransactionExecutionError { transaction_index, execution_error } = err
+    let ExecutionError::TransactionExecutionError { transaction_index, execution_error, .. } = err
     else {
         panic!("unexpected variant")
     };
     assert_eq!(execution_error, expected);
     assert_eq!(transaction_index, 0);
 }
+
+#[test]
+fn compile_invalid_class() {
+    assert!(compile_class(&class_hash!("0x2"), ContractClass::default(), Duration::from_secs(10))
+        .is_none());
+}
+
+// Test that a class whose compiled class wasn't downloaded is compiled, executed and kept in the
+// class cache.
+#[test]
+fn execute_call_with_missing_casm() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let class_hash = class_hash!("0x3");
+    let class = get_test_sierra_class();
+    let storage_key = StorageKey(patricia_key!("0x10"));
+    let storage_value = stark_felt!("0x7");
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(
+            BlockNumber(0),
+            &BlockHeader {
+                eth_l1_gas_price: *GAS_PRICE,
+                sequencer: *SEQUENCER_ADDRESS,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            StateDiff {
+                deployed_contracts: indexmap!(*CONTRACT_ADDRESS => class_hash),
+                storage_diffs: indexmap!(
+                    *CONTRACT_ADDRESS => indexmap!(storage_key => storage_value),
+                ),
+                declared_classes: indexmap!(class_hash => (CompiledClassHash::default(), class)),
+                nonces: indexmap!(*CONTRACT_ADDRESS => Nonce::default()),
+                ..Default::default()
+            },
+            indexmap!(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let class_cache = ContractClassCache::new(usize::MAX);
+    let retdata = execute_call(
+        storage_reader.clone(),
+        None,
+        &ChainId(CHAIN_ID.to_string()),
+        StateNumber::right_after_block(BlockNumber(0)),
+        BlockNumber(0),
+        &CONTRACT_ADDRESS,
+        selector_from_name("test_storage_read"),
+        calldata![*storage_key.0.key()],
+        &test_block_execution_config(),
+        Some(class_cache.clone()),
+        None,
+        AbortHandle::default(),
+    )
+    .unwrap()
+    .retdata;
+    assert_eq!(retdata, Retdata(vec![storage_value]));
+
+    // The compiled class is kept only in the cache.
+    assert_eq!(class_cache.len(), 1);
+    assert!(storage_reader.begin_ro_txn().unwrap().get_casm(&class_hash).unwrap().is_none());
+}
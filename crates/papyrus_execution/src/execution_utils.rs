@@ -1,30 +1,58 @@
 //! Utilities for executing contracts and transactions.
 use std::fs::File;
 use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
 
+use blockifier::abi::abi_utils::get_storage_var_address;
 // Expose the tool for creating entry point selectors from function names.
 pub use blockifier::abi::abi_utils::selector_from_name;
+use blockifier::abi::sierra_types::next_storage_key;
 use blockifier::execution::contract_class::{
     ContractClass as BlockifierContractClass,
     ContractClassV0,
     ContractClassV1,
 };
 use blockifier::state::cached_state::{CachedState, MutRefState};
-use blockifier::state::state_api::{State, StateReader};
+use blockifier::state::errors::StateError;
+use blockifier::state::state_api::{State, StateReader, StateResult};
 use blockifier::transaction::objects::TransactionExecutionInfo;
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use cairo_lang_starknet::contract_class::{
+    ContractClass as CairoLangContractClass,
+    ContractEntryPoint,
+    ContractEntryPoints,
+};
+use cairo_lang_utils::bigint::BigUintAsHex;
 use cairo_vm::types::errors::program_errors::ProgramError;
 use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_common::state::{DeployedContract, ReplacedClass, StorageEntry};
 use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::db::{TransactionKind, RO};
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageResult, StorageTxn};
 use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::StarkFelt;
-use starknet_api::state::{StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::state::{
+    ContractClass,
+    EntryPoint,
+    EntryPointType,
+    StateNumber,
+    StorageKey,
+    ThinStateDiff,
+};
 use thiserror::Error;
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+use tracing::debug;
 
-use crate::objects::TransactionTrace;
+use crate::contract_class_cache::ContractClassCache;
+use crate::objects::{StateOverrides, TransactionTrace};
 use crate::state_reader::ExecutionStateReader;
 use crate::{
     BlockifierError,
@@ -34,6 +62,24 @@ use crate::{
     ExecutionResult,
 };
 
+// The maximal time to wait for the compilation of a class whose compiled class wasn't downloaded.
+const SIERRA_COMPILATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The maximal number of classes that are compiled at the same time. Compilations are CPU heavy and
+// can't be interrupted, so executions that need to compile a class while the limit is reached
+// fail instead of waiting.
+const MAX_CONCURRENT_SIERRA_COMPILATIONS: usize = 4;
+
+lazy_static! {
+    static ref SIERRA_COMPILATION_PERMITS: Arc<Semaphore> =
+        Arc::new(Semaphore::new(MAX_CONCURRENT_SIERRA_COMPILATIONS));
+}
+
+// The estimated memory sizes of a single program word and a single hint of a compiled class, used
+// for estimating the memory size of the classes in the class cache.
+const ESTIMATED_PROGRAM_WORD_SIZE_BYTES: usize = 64;
+const ESTIMATED_HINT_SIZE_BYTES: usize = 256;
+
 // An error that can occur during the use of the execution utils.
 #[derive(Debug, Error)]
 pub(crate) enum ExecutionUtilsError {
@@ -55,32 +101,203 @@ impl TryFrom<PathBuf> for ExecutionConfigByBlock {
     }
 }
 
+// Returns the class with the given hash as it should be executed at the given state. Cairo1 classes
+// are executed from their casm, and deprecated (Cairo0) classes are executed from their program.
+// Returns None if the class isn't declared at the given state.
 pub(crate) fn get_contract_class(
     txn: &StorageTxn<'_, RO>,
     class_hash: &ClassHash,
     state_number: StateNumber,
+    class_cache: Option<&ContractClassCache>,
 ) -> Result<Option<BlockifierContractClass>, ExecutionUtilsError> {
-    match txn.get_state_reader()?.get_class_definition_block_number(class_hash)? {
+    let state_reader = txn.get_state_reader()?;
+    match state_reader.get_class_definition_block_number(class_hash)? {
         Some(block_number) if state_number.is_before(block_number) => return Ok(None),
         Some(_block_number) => {
-            let Some(casm) = txn.get_casm(class_hash)? else {
-                return Err(ExecutionUtilsError::CasmTableNotSynced);
+            if let Some(contract_class) = class_cache.and_then(|cache| cache.get(class_hash)) {
+                return Ok(Some(contract_class));
+            }
+            let casm = match txn.get_casm(class_hash)? {
+                Some(casm) => casm,
+                // The compiled class wasn't downloaded yet, so we compile it ourselves. The result
+                // is kept in the class cache so it won't be compiled again by every execution. It
+                // isn't written to the storage, since it isn't the canonical compiled class (it's
+                // compiled without the pythonic hints), so only the downloaded one is stored.
+                None => state_reader
+                    .get_class_definition_at(state_number, class_hash)?
+                    .and_then(|class| compile_class(class_hash, class, SIERRA_COMPILATION_TIMEOUT))
+                    .ok_or(ExecutionUtilsError::CasmTableNotSynced)?,
             };
-            return Ok(Some(BlockifierContractClass::V1(
+            let size_bytes = estimate_casm_size(&casm);
+            let contract_class = BlockifierContractClass::V1(
                 ContractClassV1::try_from(casm).map_err(ExecutionUtilsError::ProgramError)?,
-            )));
+            );
+            if let Some(cache) = class_cache {
+                cache.insert(*class_hash, contract_class.clone(), size_bytes);
+            }
+            return Ok(Some(contract_class));
         }
         None => {}
     };
 
+    match state_reader.get_deprecated_class_definition_block_number(class_hash)? {
+        Some(block_number) if state_number.is_before(block_number) => return Ok(None),
+        Some(_block_number) => {
+            if let Some(contract_class) = class_cache.and_then(|cache| cache.get(class_hash)) {
+                return Ok(Some(contract_class));
+            }
+        }
+        None => return Ok(None),
+    };
     let Some(deprecated_class) =
-        txn.get_state_reader()?.get_deprecated_class_definition_at(state_number, class_hash)?
+        state_reader.get_deprecated_class_definition_at(state_number, class_hash)?
     else {
         return Ok(None);
     };
-    Ok(Some(BlockifierContractClass::V0(
+    let size_bytes = estimate_deprecated_class_size(&deprecated_class);
+    let contract_class = BlockifierContractClass::V0(
         ContractClassV0::try_from(deprecated_class).map_err(ExecutionUtilsError::ProgramError)?,
-    )))
+    );
+    if let Some(cache) = class_cache {
+        cache.insert(*class_hash, contract_class.clone(), size_bytes);
+    }
+    Ok(Some(contract_class))
+}
+
+// Returns a class that's declared by the state overrides as it should be executed. Sierra classes
+// are compiled, and the result isn't kept in the class cache since the class isn't in the state.
+pub(crate) fn overridden_class_to_contract_class(
+    class_hash: &ClassHash,
+    class: &ApiContractClass,
+) -> StateResult<BlockifierContractClass> {
+    match class {
+        ApiContractClass::DeprecatedContractClass(deprecated_class) => {
+            Ok(BlockifierContractClass::V0(ContractClassV0::try_from(deprecated_class.clone())?))
+        }
+        ApiContractClass::ContractClass(class) => {
+            let casm = compile_class(class_hash, class.clone(), SIERRA_COMPILATION_TIMEOUT)
+                .ok_or_else(|| {
+                    StateError::StateReadError(format!("Failed to compile class {class_hash}."))
+                })?;
+            Ok(BlockifierContractClass::V1(ContractClassV1::try_from(casm)?))
+        }
+    }
+}
+
+// Compiles the given Sierra class. Returns None if the compilation failed, didn't finish within
+// the given timeout or couldn't start because too many classes are being compiled. The compilation
+// can't be interrupted, so on timeout it's left to finish in the background and its result is
+// discarded.
+pub(crate) fn compile_class(
+    class_hash: &ClassHash,
+    class: ContractClass,
+    timeout: Duration,
+) -> Option<CasmContractClass> {
+    let Ok(permit) = SIERRA_COMPILATION_PERMITS.clone().try_acquire_owned() else {
+        debug!("Too many classes are being compiled, not compiling class {class_hash}.");
+        return None;
+    };
+    let (sender, receiver) = mpsc::channel();
+    let compile = move || {
+        let result = CasmContractClass::from_contract_class(to_cairo_lang_class(class), false);
+        // The permit is held until the compilation ends, including compilations that timed out.
+        drop(permit);
+        // The receiver is dropped if the compilation timed out.
+        let _ = sender.send(result);
+    };
+    // Prefer the blocking thread pool of the runtime, if the execution runs inside one.
+    match Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn_blocking(compile);
+        }
+        Err(_) => {
+            thread::spawn(compile);
+        }
+    }
+    match receiver.recv_timeout(timeout) {
+        Ok(Ok(casm)) => Some(casm),
+        Ok(Err(err)) => {
+            debug!("Failed to compile class {class_hash}: {err}.");
+            None
+        }
+        Err(_) => {
+            debug!("Compilation of class {class_hash} didn't finish within {timeout:?}.");
+            None
+        }
+    }
+}
+
+// Converts a stored Sierra class into the representation the compiler accepts.
+fn to_cairo_lang_class(class: ContractClass) -> CairoLangContractClass {
+    let entry_points = |entry_point_type| {
+        class
+            .entry_point_by_type
+            .get(&entry_point_type)
+            .into_iter()
+            .flatten()
+            .map(|EntryPoint { function_idx, selector }| ContractEntryPoint {
+                selector: BigUint::from_bytes_be(selector.0.bytes()),
+                function_idx: function_idx.0,
+            })
+            .collect()
+    };
+    CairoLangContractClass {
+        sierra_program: class
+            .sierra_program
+            .iter()
+            .map(|felt| BigUintAsHex { value: BigUint::from_bytes_be(felt.bytes()) })
+            .collect(),
+        sierra_program_debug_info: None,
+        // The stored classes don't contain their version, and the compiler doesn't use it. The
+        // version of the Sierra program is encoded in the program itself.
+        contract_class_version: String::new(),
+        entry_points_by_type: ContractEntryPoints {
+            external: entry_points(EntryPointType::External),
+            l1_handler: entry_points(EntryPointType::L1Handler),
+            constructor: entry_points(EntryPointType::Constructor),
+        },
+        abi: None,
+    }
+}
+
+// Estimates the memory size of a compiled class by the length of its program and the number of its
+// hints. The estimate is only used for bounding the class cache, so it's kept cheap.
+fn estimate_casm_size(casm: &CasmContractClass) -> usize {
+    casm.bytecode.len() * ESTIMATED_PROGRAM_WORD_SIZE_BYTES
+        + casm.hints.len() * ESTIMATED_HINT_SIZE_BYTES
+}
+
+// Estimates the memory size of a compiled deprecated class the same way as for casm classes.
+fn estimate_deprecated_class_size(class: &DeprecatedContractClass) -> usize {
+    let program_length = class.program.data.as_array().map_or(0, Vec::len);
+    let number_of_hints = class.program.hints.as_object().map_or(0, serde_json::Map::len);
+    program_length * ESTIMATED_PROGRAM_WORD_SIZE_BYTES + number_of_hints * ESTIMATED_HINT_SIZE_BYTES
+}
+
+// Translates the balance overrides into overrides of the balances storage of the given fee token.
+// A balance is a u256 whose low and high 128 bits are stored in consecutive storage keys. Explicit
+// storage overrides of the fee token take precedence over the balance overrides.
+pub(crate) fn resolve_balance_overrides(
+    mut state_overrides: StateOverrides,
+    fee_contract_address: ContractAddress,
+) -> StateOverrides {
+    let fee_token_storage = state_overrides.storage_diffs.entry(fee_contract_address).or_default();
+    for (address, balance) in state_overrides.balances.drain(..) {
+        let low_key = get_storage_var_address("ERC20_balances", &[*address.0.key()]);
+        let high_key =
+            next_storage_key(&low_key).expect("The key of a storage var should have a successor.");
+        let (high, low) = balance.bytes().split_at(16);
+        fee_token_storage.entry(low_key).or_insert(u128_bytes_to_felt(low));
+        fee_token_storage.entry(high_key).or_insert(u128_bytes_to_felt(high));
+    }
+    state_overrides
+}
+
+// Converts the big-endian bytes of a u128 to a felt.
+fn u128_bytes_to_felt(bytes: &[u8]) -> StarkFelt {
+    let mut felt_bytes = [0_u8; 32];
+    felt_bytes[16..].copy_from_slice(bytes);
+    StarkFelt::new(felt_bytes).expect("A u128 should fit in a felt.")
 }
 
 /// Given an ExecutableTransactionInput, returns a function that will convert the corresponding
@@ -8,9 +8,13 @@
 //! transactions at the end of block 10, you should use state_number = 11 and
 //! block_context_block_number = 10.
 //! See documentation of [StateNumber] for more details.
+pub mod abort_handle;
+pub mod contract_class_cache;
+mod execution_metrics;
 #[cfg(test)]
 mod execution_test;
 pub mod execution_utils;
+pub mod simulation_session;
 mod state_reader;
 
 #[cfg(test)]
@@ -19,9 +23,13 @@ mod test_utils;
 pub mod testing_instances;
 
 pub mod objects;
+pub mod revert_trace;
 use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::sync::Arc;
 
+use abort_handle::AbortHandle;
+use blockifier::abi::constants::N_STEPS_RESOURCE;
 use blockifier::block_context::{BlockContext, FeeTokenAddresses, GasPrices};
 use blockifier::execution::call_info::CallExecution;
 use blockifier::execution::contract_class::ContractClass as BlockifierContractClass;
@@ -42,12 +50,22 @@ use blockifier::transaction::objects::{
 use blockifier::transaction::transaction_execution::Transaction as BlockifierTransaction;
 use blockifier::transaction::transactions::ExecutableTransaction;
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
-use execution_utils::{get_trace_constructor, induced_state_diff};
-use objects::{PriceUnit, TransactionSimulationOutput};
+use contract_class_cache::ContractClassCache;
+use execution_metrics::{record_execution_resources, record_failure, record_revert};
+use execution_utils::{get_trace_constructor, induced_state_diff, resolve_balance_overrides};
+use objects::{PriceUnit, StateOverrides, TransactionSimulationOutput};
 use papyrus_common::transaction_hash::get_transaction_hash;
 use papyrus_common::TransactionOptions;
+use papyrus_proc_macros::latency_histogram;
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::{StorageError, StorageReader};
+use revert_trace::{
+    revert_trace,
+    revert_trace_from_message,
+    EntryPoint,
+    RevertTraceFrame,
+    TransactionEntryPoints,
+};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockNumber, GasPrice};
 use starknet_api::core::{ChainId, ClassHash, ContractAddress, EntryPointSelector};
@@ -84,8 +102,10 @@ pub type ExecutionResult<T> = Result<T, ExecutionError>;
 /// Parameters that are needed for execution.
 // TODO(yair): Find a way to get them from the Starknet general config.
 pub struct BlockExecutionConfig {
-    /// The adress to receive fees
+    /// The address of the ETH fee token contract
     pub fee_contract_address: ContractAddress,
+    /// The address of the STRK fee token contract
+    pub strk_fee_contract_address: ContractAddress,
     /// The maximum number of steps for an invoke transaction
     pub invoke_tx_max_n_steps: u32,
     /// The maximum number of steps for a validate transaction
@@ -98,6 +118,13 @@ pub struct BlockExecutionConfig {
     pub vm_resource_fee_cost: Arc<HashMap<String, f64>>,
     /// The initial gas cost for a transaction
     pub initial_gas_cost: u64,
+    /// The L1 gas price in Wei to execute with instead of the gas price of the block, for chains
+    /// whose blocks don't carry a meaningful gas price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eth_l1_gas_price_override: Option<u128>,
+    /// The L1 gas price in Fri to execute with instead of the gas price of the block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strk_l1_gas_price_override: Option<u128>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -109,6 +136,18 @@ pub struct ExecutionConfigByBlock {
 }
 
 impl ExecutionConfigByBlock {
+    /// Loads the execution config of the given chain. If the given path is a directory, the config
+    /// is read from the file in it that is named after the chain id (e.g. `SN_MAIN.json`), so a
+    /// single directory can hold the configs of several chains.
+    pub fn load(path: &Path, chain_id: &ChainId) -> ExecutionResult<Self> {
+        let execution_config_file = if path.is_dir() {
+            path.join(format!("{}.json", chain_id.0))
+        } else {
+            path.to_path_buf()
+        };
+        execution_config_file.try_into()
+    }
+
     /// Returns the execution config for a given block number.
     pub fn get_execution_config_for_block(
         &self,
@@ -142,23 +181,33 @@ pub enum ExecutionError {
     ConfigFileError(#[from] std::io::Error),
     #[error(transparent)]
     ConfigSerdeError(#[from] serde_json::Error),
-    #[error(transparent)]
-    ContractError(#[from] BlockifierError),
+    #[error("{error}")]
+    ContractError { error: BlockifierError, revert_trace: Vec<RevertTraceFrame> },
     #[error(
         "The contract at address {contract_address:?} is not found at state number \
          {state_number:?}."
     )]
     ContractNotFound { contract_address: ContractAddress, state_number: StateNumber },
+    #[error("The execution was aborted because it was cancelled or it timed out.")]
+    ExecutionAborted,
     #[error("Missing class hash in call info")]
     MissingClassHash,
     #[error("Missing compiled class with hash {class_hash} (The CASM table isn't synced)")]
     MissingCompiledClass { class_hash: ClassHash },
+    #[error("The simulation session is running another simulation.")]
+    SimulationSessionBusy,
+    #[error("The simulation session can't be used after one of its simulations failed.")]
+    SimulationSessionFailed,
     #[error(transparent)]
     StorageError(#[from] StorageError),
     #[error(
         "Execution failed at transaction {transaction_index:?} with error: {execution_error:?}"
     )]
-    TransactionExecutionError { transaction_index: usize, execution_error: String },
+    TransactionExecutionError {
+        transaction_index: usize,
+        execution_error: String,
+        revert_trace: Vec<RevertTraceFrame>,
+    },
     #[error("Failed to calculate transaction hash.")]
     TransactionHashCalculationFailed(StarknetApiError),
     #[error("Unknown builtin name: {builtin_name}")]
@@ -168,11 +217,75 @@ pub enum ExecutionError {
 /// Whether the only-query bit of the transaction version is on.
 pub type OnlyQuery = bool;
 
+/// Flags that determine which parts of the transactions' flow are executed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExecutionFlags {
+    /// Whether to run the validation entry point of account transactions.
+    pub validate: bool,
+    /// Whether to transfer the fee from the account to the sequencer. If false, the fee is still
+    /// calculated and the transactions don't fail on insufficient balance or max fee.
+    pub charge_fee: bool,
+}
+
+/// Executes the transactions as the sequencer would, including validation and fee charging.
+impl Default for ExecutionFlags {
+    fn default() -> Self {
+        Self { validate: true, charge_fee: true }
+    }
+}
+
+/// A flag of a simulation request that skips a part of the transactions' flow.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SimulationFlag {
+    SkipValidate,
+    SkipFeeCharge,
+}
+
+/// Returns the execution flags that correspond to the simulation flags of a request.
+pub fn simulation_flags_to_execution_flags(simulation_flags: &[SimulationFlag]) -> ExecutionFlags {
+    ExecutionFlags {
+        validate: !simulation_flags.contains(&SimulationFlag::SkipValidate),
+        charge_fee: !simulation_flags.contains(&SimulationFlag::SkipFeeCharge),
+    }
+}
+
 /// Gathers all the possible errors that can be returned from the blockifier.
 type BlockifierError = anyhow::Error;
 
-/// Executes a StarkNet call and returns the execution result.
+impl From<BlockifierError> for ExecutionError {
+    fn from(error: BlockifierError) -> Self {
+        Self::contract_error(error, None)
+    }
+}
+
+impl ExecutionError {
+    // Returns a contract error with the trace of the calls that led to the error. `entry_point` is
+    // the entry point of the outermost call, if it's known.
+    fn contract_error(error: BlockifierError, entry_point: Option<EntryPoint>) -> Self {
+        let revert_trace = revert_trace(error.as_ref(), entry_point);
+        Self::ContractError { error, revert_trace }
+    }
+
+    // Returns a transaction execution error with the trace of the calls that led to the error.
+    // `entry_point` is the entry point of the outermost call, if it's known.
+    fn transaction_execution_error(
+        transaction_index: usize,
+        error: BlockifierTransactionExecutionError,
+        entry_point: Option<EntryPoint>,
+    ) -> Self {
+        Self::TransactionExecutionError {
+            transaction_index,
+            execution_error: error_message_with_sources(&error),
+            revert_trace: revert_trace(&error, entry_point),
+        }
+    }
+}
+
+/// Executes a StarkNet call and returns the execution result. If state overrides are given, the
+/// call is executed as if they were applied on top of the state.
 #[allow(clippy::too_many_arguments)]
+#[latency_histogram("execution_call_latency_seconds")]
 pub fn execute_call(
     storage_reader: StorageReader,
     maybe_pending_data: Option<PendingData>,
@@ -183,13 +296,22 @@ pub fn execute_call(
     entry_point_selector: EntryPointSelector,
     calldata: Calldata,
     execution_config: &BlockExecutionConfig,
+    class_cache: Option<ContractClassCache>,
+    state_overrides: Option<StateOverrides>,
+    abort_handle: AbortHandle,
 ) -> ExecutionResult<CallExecution> {
-    verify_contract_exists(
-        *contract_address,
-        &storage_reader,
-        state_number,
-        maybe_pending_data.as_ref(),
-    )?;
+    let state_overrides = resolve_balance_overrides(
+        state_overrides.unwrap_or_default(),
+        execution_config.fee_contract_address,
+    );
+    if !state_overrides.class_hashes.contains_key(contract_address) {
+        verify_contract_exists(
+            *contract_address,
+            &storage_reader,
+            state_number,
+            maybe_pending_data.as_ref(),
+        )?;
+    }
 
     let call_entry_point = CallEntryPoint {
         class_hash: None,
@@ -217,6 +339,9 @@ pub fn execute_call(
         state_number,
         maybe_pending_data,
         missing_compiled_class: None,
+        class_cache,
+        state_overrides,
+        abort_handle: abort_handle.clone(),
     });
     let mut context = EntryPointExecutionContext::new_invoke(
         &block_context,
@@ -224,17 +349,31 @@ pub fn execute_call(
         &AccountTransactionContext::Deprecated(DeprecatedAccountTransactionContext::default()),
         true, // limit_steps_by_resources
     )
-    .map_err(|err| ExecutionError::ContractError(err.into()))?;
+    .map_err(BlockifierError::new)?;
 
-    let res = call_entry_point
-        .execute(&mut cached_state, &mut ExecutionResources::default(), &mut context)
-        .map_err(|error| {
-            if let Some(class_hash) = cached_state.state.missing_compiled_class {
-                ExecutionError::MissingCompiledClass { class_hash }
-            } else {
-                ExecutionError::ContractError(error.into())
+    let mut execution_resources = ExecutionResources::default();
+    let res =
+        match call_entry_point.execute(&mut cached_state, &mut execution_resources, &mut context) {
+            Ok(res) => res,
+            Err(error) => {
+                let err = if abort_handle.is_aborted() {
+                    ExecutionError::ExecutionAborted
+                } else if let Some(class_hash) = cached_state.state.missing_compiled_class {
+                    ExecutionError::MissingCompiledClass { class_hash }
+                } else {
+                    ExecutionError::contract_error(
+                        error.into(),
+                        Some((*contract_address, entry_point_selector)),
+                    )
+                };
+                record_failure(&err);
+                return Err(err);
             }
-        })?;
+        };
+    record_execution_resources(
+        execution_resources.vm_resources.n_steps,
+        &execution_resources.vm_resources.builtin_instance_counter,
+    );
 
     Ok(res.execution)
 }
@@ -291,14 +430,22 @@ fn create_block_context(
         }
     };
 
+    let gas_prices = GasPrices {
+        eth_l1_gas_price: execution_config
+            .eth_l1_gas_price_override
+            .unwrap_or(gas_prices.eth_l1_gas_price),
+        strk_l1_gas_price: execution_config
+            .strk_l1_gas_price_override
+            .unwrap_or(gas_prices.strk_l1_gas_price),
+    };
+
     Ok(BlockContext {
         chain_id,
         block_number,
         block_timestamp,
         sequencer_address,
-        // TODO(barak, 01/10/2023): Change strk_fee_token_address once it exists.
         fee_token_addresses: FeeTokenAddresses {
-            strk_fee_token_address: execution_config.fee_contract_address,
+            strk_fee_token_address: execution_config.strk_fee_contract_address,
             eth_fee_token_address: execution_config.fee_contract_address,
         },
         vm_resource_fee_cost: Arc::clone(&execution_config.vm_resource_fee_cost),
@@ -437,6 +584,8 @@ pub struct RevertedTransaction {
     pub index: usize,
     /// The revert reason.
     pub revert_reason: String,
+    /// The trace of the calls that led to the revert.
+    pub revert_trace: Vec<RevertTraceFrame>,
 }
 
 /// Valid output for fee estimation for a series of transactions can be either a list of fees or the
@@ -453,7 +602,9 @@ pub fn estimate_fee(
     state_number: StateNumber,
     block_context_block_number: BlockNumber,
     execution_config: &BlockExecutionConfig,
-    validate: bool,
+    flags: ExecutionFlags,
+    class_cache: Option<ContractClassCache>,
+    abort_handle: AbortHandle,
 ) -> ExecutionResult<FeeEstimationResult> {
     let (txs_execution_info, block_context) = execute_transactions(
         txs,
@@ -464,8 +615,10 @@ pub fn estimate_fee(
         state_number,
         block_context_block_number,
         execution_config,
-        false,
-        validate,
+        flags,
+        class_cache,
+        None,
+        abort_handle,
     )?;
     Ok(txs_execution_info
         .into_iter()
@@ -473,7 +626,8 @@ pub fn estimate_fee(
         .map(|(index, tx_execution_output)| {
             // If the transaction reverted, fail the entire estimation.
             if let Some(revert_reason) = tx_execution_output.execution_info.revert_error {
-                Err(RevertedTransaction { index, revert_reason })
+                let revert_trace = revert_trace_from_message(&revert_reason, None);
+                Err(RevertedTransaction { index, revert_reason, revert_trace })
             } else {
                 let gas_price = match tx_execution_output.price_unit {
                     PriceUnit::Wei => GasPrice(block_context.gas_prices.eth_l1_gas_price),
@@ -507,8 +661,10 @@ fn execute_transactions(
     state_number: StateNumber,
     block_context_block_number: BlockNumber,
     execution_config: &BlockExecutionConfig,
-    charge_fee: bool,
-    validate: bool,
+    flags: ExecutionFlags,
+    class_cache: Option<ContractClassCache>,
+    state_overrides: Option<StateOverrides>,
+    abort_handle: AbortHandle,
 ) -> ExecutionResult<(Vec<TransactionExecutionOutput>, BlockContext)> {
     let block_context = create_block_context(
         block_context_block_number,
@@ -524,11 +680,42 @@ fn execute_transactions(
         state_number,
         maybe_pending_data,
         missing_compiled_class: None,
+        class_cache,
+        state_overrides: resolve_balance_overrides(
+            state_overrides.unwrap_or_default(),
+            execution_config.fee_contract_address,
+        ),
+        abort_handle: abort_handle.clone(),
     });
 
     // TODO(yair): this is a temporary bug fix, delete once the blockifier is fixed and add a test.
     set_block_hash_contract(state_number, &mut cached_state)?;
 
+    let res = execute_transactions_on_state(
+        &mut cached_state,
+        &block_context,
+        txs,
+        tx_hashes,
+        chain_id,
+        flags,
+        &abort_handle,
+    )?;
+
+    Ok((res, block_context))
+}
+
+// Executes a series of transactions on top of the given state, which is updated with their
+// changes, and returns the execution results.
+#[latency_histogram("execution_transactions_latency_seconds")]
+fn execute_transactions_on_state(
+    cached_state: &mut CachedState<ExecutionStateReader>,
+    block_context: &BlockContext,
+    txs: Vec<ExecutableTransactionInput>,
+    tx_hashes: Option<Vec<TransactionHash>>,
+    chain_id: &ChainId,
+    flags: ExecutionFlags,
+    abort_handle: &AbortHandle,
+) -> ExecutionResult<Vec<TransactionExecutionOutput>> {
     let (txs, tx_hashes) = match tx_hashes {
         Some(tx_hashes) => (txs, tx_hashes),
         None => {
@@ -541,6 +728,10 @@ fn execute_transactions(
     let mut res = vec![];
     for (transaction_index, (tx, tx_hash)) in txs.into_iter().zip(tx_hashes.into_iter()).enumerate()
     {
+        if abort_handle.is_aborted() {
+            record_failure(&ExecutionError::ExecutionAborted);
+            return Err(ExecutionError::ExecutionAborted);
+        }
         let price_unit = match tx.transaction_version() {
             TransactionVersion::ZERO | TransactionVersion::ONE | TransactionVersion::TWO => {
                 PriceUnit::Wei
@@ -548,7 +739,7 @@ fn execute_transactions(
             // From V3 all transactions are priced in Fri.
             _ => PriceUnit::Fri,
         };
-        let mut transactional_state = CachedState::create_transactional(&mut cached_state);
+        let mut transactional_state = CachedState::create_transactional(cached_state);
         let deprecated_declared_class_hash = match &tx {
             ExecutableTransactionInput::DeclareV0(
                 DeclareTransactionV0V1 { class_hash, .. },
@@ -563,18 +754,46 @@ fn execute_transactions(
             _ => None,
         };
         let blockifier_tx = to_blockifier_tx(tx, tx_hash, transaction_index)?;
-        let tx_execution_info_result =
-            blockifier_tx.execute(&mut transactional_state, &block_context, charge_fee, validate);
+        let entry_points = TransactionEntryPoints::new(&blockifier_tx);
+        let tx_execution_info_result = blockifier_tx.execute(
+            &mut transactional_state,
+            block_context,
+            flags.charge_fee,
+            flags.validate,
+        );
+        // An aborted transaction fails or reverts at its next state read, and its result is
+        // discarded.
+        if abort_handle.is_aborted() {
+            record_failure(&ExecutionError::ExecutionAborted);
+            return Err(ExecutionError::ExecutionAborted);
+        }
         let state_diff =
             induced_state_diff(&mut transactional_state, deprecated_declared_class_hash)?;
         transactional_state.commit();
-        let execution_info = tx_execution_info_result.map_err(|error| {
-            if let Some(class_hash) = cached_state.state.missing_compiled_class {
-                ExecutionError::MissingCompiledClass { class_hash }
-            } else {
-                ExecutionError::from((transaction_index, error))
+        let execution_info = match tx_execution_info_result {
+            Ok(execution_info) => execution_info,
+            Err(error) => {
+                let err = if let Some(class_hash) = cached_state.state.missing_compiled_class {
+                    ExecutionError::MissingCompiledClass { class_hash }
+                } else {
+                    let entry_point = entry_points.failed_entry_point(&error);
+                    ExecutionError::transaction_execution_error(
+                        transaction_index,
+                        error,
+                        entry_point,
+                    )
+                };
+                record_failure(&err);
+                return Err(err);
             }
-        })?;
+        };
+        record_execution_resources(
+            execution_info.actual_resources.0.get(N_STEPS_RESOURCE).copied().unwrap_or_default(),
+            &execution_info.actual_resources.0,
+        );
+        if execution_info.revert_error.is_some() {
+            record_revert();
+        }
         res.push(TransactionExecutionOutput {
             execution_info,
             induced_state_diff: state_diff,
@@ -582,7 +801,7 @@ fn execute_transactions(
         });
     }
 
-    Ok((res, block_context))
+    Ok(res)
 }
 
 /// Converts a transaction index and [BlockifierTransactionExecutionError] to an [ExecutionError].
@@ -590,8 +809,25 @@ fn execute_transactions(
 impl From<(usize, BlockifierTransactionExecutionError)> for ExecutionError {
     fn from(transaction_index_and_error: (usize, BlockifierTransactionExecutionError)) -> Self {
         let (transaction_index, error) = transaction_index_and_error;
-        Self::TransactionExecutionError { transaction_index, execution_error: error.to_string() }
+        Self::transaction_execution_error(transaction_index, error, None)
+    }
+}
+
+/// Returns the message of the error followed by the messages of the errors that caused it, one per
+/// line, so that the failures of inner calls aren't lost. Messages that are already contained in
+/// the message of the error they caused are skipped.
+pub fn error_message_with_sources(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        let source_message = error.to_string();
+        if !message.contains(&source_message) {
+            message.push('\n');
+            message.push_str(&source_message);
+        }
+        source = error.source();
     }
+    message
 }
 
 /// Sets the block hash contract (contract at address 1) with the block hash of the block 10 blocks
@@ -656,6 +892,7 @@ fn to_blockifier_tx(
                     ExecutionError::TransactionExecutionError {
                         transaction_index,
                         execution_error: e.to_string(),
+                        revert_trace: vec![],
                     }
                 },
             )?);
@@ -726,6 +963,8 @@ fn to_blockifier_tx(
 }
 
 /// Simulates a series of transactions and returns the transaction traces and the fee estimations.
+/// If state overrides are given, the transactions are executed as if they were applied on top of
+/// the state.
 // TODO(yair): Return structs instead of tuples.
 #[allow(clippy::too_many_arguments)]
 pub fn simulate_transactions(
@@ -737,8 +976,10 @@ pub fn simulate_transactions(
     state_number: StateNumber,
     block_context_block_number: BlockNumber,
     execution_config: &BlockExecutionConfig,
-    charge_fee: bool,
-    validate: bool,
+    flags: ExecutionFlags,
+    class_cache: Option<ContractClassCache>,
+    state_overrides: Option<StateOverrides>,
+    abort_handle: AbortHandle,
 ) -> ExecutionResult<Vec<TransactionSimulationOutput>> {
     let trace_constructors = txs.iter().map(get_trace_constructor).collect::<Vec<_>>();
     let (execution_results, block_context) = execute_transactions(
@@ -750,9 +991,21 @@ pub fn simulate_transactions(
         state_number,
         block_context_block_number,
         execution_config,
-        charge_fee,
-        validate,
+        flags,
+        class_cache,
+        state_overrides,
+        abort_handle,
     )?;
+    to_simulation_outputs(execution_results, trace_constructors, &block_context)
+}
+
+// Converts the execution results of transactions into simulation outputs, given the trace
+// constructors of the transactions.
+fn to_simulation_outputs(
+    execution_results: Vec<TransactionExecutionOutput>,
+    trace_constructors: Vec<fn(TransactionExecutionInfo) -> ExecutionResult<TransactionTrace>>,
+    block_context: &BlockContext,
+) -> ExecutionResult<Vec<TransactionSimulationOutput>> {
     execution_results
         .into_iter()
         .zip(trace_constructors)
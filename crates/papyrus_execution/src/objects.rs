@@ -23,7 +23,7 @@ use cairo_vm::vm::runners::builtin_runner::{
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources as VmExecutionResources;
 use indexmap::IndexMap;
 use itertools::Itertools;
-use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::pending_classes::{ApiContractClass, PendingClasses};
 use papyrus_common::state::{
     DeclaredClassHashEntry,
     DeployedContract,
@@ -35,7 +35,7 @@ use starknet_api::block::{BlockTimestamp, GasPrice};
 use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector, Nonce};
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::StarkFelt;
-use starknet_api::state::ThinStateDiff;
+use starknet_api::state::{StorageKey, ThinStateDiff};
 use starknet_api::transaction::{
     Builtin,
     Calldata,
@@ -434,6 +434,25 @@ pub struct PendingData {
     pub classes: PendingClasses,
 }
 
+/// Changes to apply on top of the state for the duration of an execution, without writing them to
+/// the state. Used for checking how an execution would behave in a hypothetical state.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct StateOverrides {
+    /// Storage values to set, per contract.
+    pub storage_diffs: IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>>,
+    /// Nonces to set, per contract.
+    pub nonces: IndexMap<ContractAddress, Nonce>,
+    /// Class hashes to set, per contract. The classes must be declared in the state or in the
+    /// class overrides. A contract that isn't deployed is treated as if it was deployed with the
+    /// given class.
+    pub class_hashes: IndexMap<ContractAddress, ClassHash>,
+    /// Classes to treat as declared, by their hashes. Sierra classes are compiled when executed.
+    pub classes: IndexMap<ClassHash, ApiContractClass>,
+    /// ETH fee token balances to set, per contract. Both the low and the high 128 bits of the
+    /// balance are set.
+    pub balances: IndexMap<ContractAddress, StarkFelt>,
+}
+
 /// The unit of the fee.
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
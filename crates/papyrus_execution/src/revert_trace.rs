@@ -0,0 +1,160 @@
+//! The calls that led to the failure of an execution.
+//!
+//! The blockifier reports the failures of inner calls only as text, so the trace is recovered from
+//! the stack trace it attaches to virtual machine errors, one frame per called contract, from the
+//! outermost call to the innermost one.
+#[cfg(test)]
+#[path = "revert_trace_test.rs"]
+mod revert_trace_test;
+
+use blockifier::abi::abi_utils::selector_from_name;
+use blockifier::abi::constants::CONSTRUCTOR_ENTRY_POINT_NAME;
+use blockifier::execution::errors::EntryPointExecutionError;
+use blockifier::transaction::account_transaction::AccountTransaction;
+use blockifier::transaction::constants::{
+    EXECUTE_ENTRY_POINT_NAME,
+    VALIDATE_DECLARE_ENTRY_POINT_NAME,
+    VALIDATE_DEPLOY_ENTRY_POINT_NAME,
+    VALIDATE_ENTRY_POINT_NAME,
+};
+use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
+use blockifier::transaction::transaction_execution::Transaction as BlockifierTransaction;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ContractAddress, EntryPointSelector};
+use starknet_api::hash::StarkFelt;
+
+// The prefix of every frame in the stack trace of the blockifier.
+const CALLED_CONTRACT_PREFIX: &str = "Error in the called contract (";
+
+/// A call in the trace of a failed execution.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct RevertTraceFrame {
+    /// The address of the called contract.
+    pub contract_address: ContractAddress,
+    /// The selector of the called entry point, if it's known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<EntryPointSelector>,
+    /// The data the call failed with, empty for calls that failed without returning data.
+    pub error: Vec<StarkFelt>,
+}
+
+/// The contract address and selector of an entry point.
+pub type EntryPoint = (ContractAddress, EntryPointSelector);
+
+/// Returns the trace of the calls that led to the given error. `entry_point` is the entry point of
+/// the outermost call, if it's known.
+pub fn revert_trace(
+    error: &(dyn std::error::Error + 'static),
+    entry_point: Option<EntryPoint>,
+) -> Vec<RevertTraceFrame> {
+    let mut frames = Vec::new();
+    let mut error_data = Vec::new();
+    let mut source = Some(error);
+    while let Some(error) = source {
+        match error.downcast_ref::<EntryPointExecutionError>() {
+            Some(EntryPointExecutionError::ExecutionFailed { error_data: data }) => {
+                error_data = data.clone();
+                break;
+            }
+            Some(EntryPointExecutionError::VirtualMachineExecutionErrorWithTrace {
+                trace, ..
+            }) => {
+                frames = revert_trace_from_message(trace, entry_point);
+                break;
+            }
+            _ => source = error.source(),
+        }
+    }
+
+    if frames.is_empty() {
+        let Some((contract_address, selector)) = entry_point else {
+            return frames;
+        };
+        frames.push(RevertTraceFrame { contract_address, selector: Some(selector), error: vec![] });
+    }
+    if let Some(innermost_frame) = frames.last_mut() {
+        innermost_frame.error = error_data;
+    }
+    frames
+}
+
+/// Returns the trace of the calls in the stack trace of an error message of the blockifier, e.g.
+/// the revert reason of a reverted transaction. `entry_point` is the entry point of the outermost
+/// call, if it's known.
+pub fn revert_trace_from_message(
+    message: &str,
+    entry_point: Option<EntryPoint>,
+) -> Vec<RevertTraceFrame> {
+    let mut frames = message
+        .lines()
+        .filter_map(|line| {
+            let address = line.strip_prefix(CALLED_CONTRACT_PREFIX)?.strip_suffix("):")?;
+            let contract_address = StarkFelt::try_from(address).ok()?.try_into().ok()?;
+            Some(RevertTraceFrame { contract_address, selector: None, error: vec![] })
+        })
+        .collect::<Vec<_>>();
+    if let (Some(outermost_frame), Some((contract_address, selector))) =
+        (frames.first_mut(), entry_point)
+    {
+        if outermost_frame.contract_address == contract_address {
+            outermost_frame.selector = Some(selector);
+        }
+    }
+    frames
+}
+
+/// The entry points a transaction calls, by the stage of the transaction that calls them.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct TransactionEntryPoints {
+    validate: Option<EntryPoint>,
+    execute: Option<EntryPoint>,
+}
+
+impl TransactionEntryPoints {
+    pub(crate) fn new(tx: &BlockifierTransaction) -> Self {
+        match tx {
+            BlockifierTransaction::AccountTransaction(account_tx) => {
+                let account_address = account_tx.get_account_tx_context().sender_address();
+                let (validate_entry_point_name, execute_entry_point_name) = match account_tx {
+                    AccountTransaction::Declare(_) => (VALIDATE_DECLARE_ENTRY_POINT_NAME, None),
+                    AccountTransaction::DeployAccount(_) => {
+                        (VALIDATE_DEPLOY_ENTRY_POINT_NAME, Some(CONSTRUCTOR_ENTRY_POINT_NAME))
+                    }
+                    AccountTransaction::Invoke(_) => {
+                        (VALIDATE_ENTRY_POINT_NAME, Some(EXECUTE_ENTRY_POINT_NAME))
+                    }
+                };
+                Self {
+                    validate: Some((
+                        account_address,
+                        selector_from_name(validate_entry_point_name),
+                    )),
+                    execute: execute_entry_point_name
+                        .map(|name| (account_address, selector_from_name(name))),
+                }
+            }
+            BlockifierTransaction::L1HandlerTransaction(l1_handler_tx) => Self {
+                validate: None,
+                execute: Some((
+                    l1_handler_tx.tx.contract_address,
+                    l1_handler_tx.tx.entry_point_selector,
+                )),
+            },
+        }
+    }
+
+    /// Returns the entry point whose call failed with the given error.
+    pub(crate) fn failed_entry_point(
+        &self,
+        error: &BlockifierTransactionExecutionError,
+    ) -> Option<EntryPoint> {
+        match error {
+            BlockifierTransactionExecutionError::ValidateTransactionError(_) => self.validate,
+            BlockifierTransactionExecutionError::ExecutionError(_)
+            | BlockifierTransactionExecutionError::ContractConstructorExecutionFailed(_) => {
+                self.execute
+            }
+            _ => None,
+        }
+    }
+}
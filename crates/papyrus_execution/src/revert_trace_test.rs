@@ -0,0 +1,114 @@
+use blockifier::abi::abi_utils::selector_from_name;
+use blockifier::execution::errors::{EntryPointExecutionError, VirtualMachineExecutionError};
+use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
+use cairo_vm::vm::errors::vm_errors::VirtualMachineError;
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::{contract_address, patricia_key, stark_felt};
+
+use crate::revert_trace::{
+    revert_trace,
+    revert_trace_from_message,
+    RevertTraceFrame,
+    TransactionEntryPoints,
+};
+
+const OUTER_CONTRACT_ADDRESS: &str = "0x1";
+const INNER_CONTRACT_ADDRESS: &str = "0x2";
+
+fn vm_trace() -> String {
+    format!(
+        "Error in the called contract ({}):\nError at pc=0:12:\nGot an exception while executing \
+         a hint.\nError in the called contract ({}):\nError at pc=0:3:\nAn ASSERT_EQ instruction \
+         failed: 1 != 0.",
+        StarkFelt::try_from(OUTER_CONTRACT_ADDRESS).unwrap(),
+        StarkFelt::try_from(INNER_CONTRACT_ADDRESS).unwrap(),
+    )
+}
+
+#[test]
+fn revert_trace_of_failed_cairo1_call() {
+    let selector = selector_from_name("foo");
+    let error = BlockifierTransactionExecutionError::ExecutionError(
+        EntryPointExecutionError::ExecutionFailed { error_data: vec![stark_felt!("0x1234")] },
+    );
+
+    assert_eq!(
+        revert_trace(&error, Some((contract_address!(OUTER_CONTRACT_ADDRESS), selector))),
+        vec![RevertTraceFrame {
+            contract_address: contract_address!(OUTER_CONTRACT_ADDRESS),
+            selector: Some(selector),
+            error: vec![stark_felt!("0x1234")],
+        }]
+    );
+    // Without the entry point there's no frame to attach the error data to.
+    assert!(revert_trace(&error, None).is_empty());
+}
+
+#[test]
+fn revert_trace_of_failed_inner_call() {
+    let selector = selector_from_name("foo");
+    let error = EntryPointExecutionError::VirtualMachineExecutionErrorWithTrace {
+        trace: vm_trace(),
+        source: VirtualMachineExecutionError::VirtualMachineError(
+            VirtualMachineError::InvalidInstructionEncoding,
+        ),
+    };
+
+    assert_eq!(
+        revert_trace(&error, Some((contract_address!(OUTER_CONTRACT_ADDRESS), selector))),
+        vec![
+            RevertTraceFrame {
+                contract_address: contract_address!(OUTER_CONTRACT_ADDRESS),
+                selector: Some(selector),
+                error: vec![],
+            },
+            RevertTraceFrame {
+                contract_address: contract_address!(INNER_CONTRACT_ADDRESS),
+                selector: None,
+                error: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn revert_trace_from_message_ignores_entry_point_of_other_contract() {
+    let selector = selector_from_name("foo");
+    let frames = revert_trace_from_message(&vm_trace(), Some((contract_address!("0x3"), selector)));
+    assert_eq!(
+        frames.iter().map(|frame| frame.contract_address).collect::<Vec<_>>(),
+        vec![contract_address!(OUTER_CONTRACT_ADDRESS), contract_address!(INNER_CONTRACT_ADDRESS)]
+    );
+    assert!(frames.iter().all(|frame| frame.selector.is_none()));
+}
+
+#[test]
+fn failed_entry_point_by_transaction_stage() {
+    let validate = (contract_address!("0x1"), selector_from_name("__validate__"));
+    let execute = (contract_address!("0x1"), selector_from_name("__execute__"));
+    let entry_points = TransactionEntryPoints { validate: Some(validate), execute: Some(execute) };
+
+    assert_eq!(
+        entry_points.failed_entry_point(
+            &BlockifierTransactionExecutionError::ValidateTransactionError(
+                EntryPointExecutionError::RecursionDepthExceeded
+            )
+        ),
+        Some(validate)
+    );
+    assert_eq!(
+        entry_points.failed_entry_point(&BlockifierTransactionExecutionError::ExecutionError(
+            EntryPointExecutionError::RecursionDepthExceeded
+        )),
+        Some(execute)
+    );
+    assert_eq!(
+        entry_points.failed_entry_point(
+            &BlockifierTransactionExecutionError::DeclareTransactionError {
+                class_hash: Default::default()
+            }
+        ),
+        None
+    );
+}
@@ -0,0 +1,107 @@
+//! Simulation of many series of transactions on top of the same state.
+//!
+//! A session pins the state at a specific block and keeps the changes of every simulation, so each
+//! simulation runs on top of the ones before it. This spares tools that simulate many dependent
+//! requests from rebuilding the state for every request.
+#[cfg(test)]
+#[path = "simulation_session_test.rs"]
+mod simulation_session_test;
+
+use blockifier::block_context::BlockContext;
+use blockifier::state::cached_state::CachedState;
+use papyrus_storage::StorageReader;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+use starknet_api::state::StateNumber;
+use starknet_api::transaction::TransactionHash;
+
+use crate::abort_handle::AbortHandle;
+use crate::contract_class_cache::ContractClassCache;
+use crate::execution_utils::get_trace_constructor;
+use crate::objects::{PendingData, StateOverrides, TransactionSimulationOutput};
+use crate::state_reader::ExecutionStateReader;
+use crate::{
+    create_block_context,
+    execute_transactions_on_state,
+    set_block_hash_contract,
+    to_simulation_outputs,
+    BlockExecutionConfig,
+    ExecutableTransactionInput,
+    ExecutionError,
+    ExecutionFlags,
+    ExecutionResult,
+};
+
+/// A state on top of which series of transactions are simulated one after the other, each on top of
+/// the changes of the previous ones. If a simulation fails, the session can't be used anymore since
+/// its state may contain some of the changes of the failed simulation.
+pub struct SimulationSession {
+    cached_state: CachedState<ExecutionStateReader>,
+    block_context: BlockContext,
+    chain_id: ChainId,
+    failed: bool,
+}
+
+impl SimulationSession {
+    /// Creates a session whose state is the state at the given state number. The arguments have the
+    /// same meaning as in [`simulate_transactions`](crate::simulate_transactions).
+    pub fn new(
+        chain_id: ChainId,
+        storage_reader: StorageReader,
+        maybe_pending_data: Option<PendingData>,
+        state_number: StateNumber,
+        block_context_block_number: BlockNumber,
+        execution_config: &BlockExecutionConfig,
+        class_cache: Option<ContractClassCache>,
+    ) -> ExecutionResult<Self> {
+        let block_context = create_block_context(
+            block_context_block_number,
+            chain_id.clone(),
+            &storage_reader,
+            maybe_pending_data.as_ref(),
+            execution_config,
+        )?;
+        let mut cached_state = CachedState::from(ExecutionStateReader {
+            storage_reader,
+            state_number,
+            maybe_pending_data,
+            missing_compiled_class: None,
+            class_cache,
+            state_overrides: StateOverrides::default(),
+            // Replaced by the abort handle of each simulation.
+            abort_handle: AbortHandle::default(),
+        });
+        set_block_hash_contract(state_number, &mut cached_state)?;
+        Ok(Self { cached_state, block_context, chain_id, failed: false })
+    }
+
+    /// Simulates a series of transactions on top of the state of the session and keeps their
+    /// changes in it. Returns the transaction traces and the fee estimations.
+    pub fn simulate_transactions(
+        &mut self,
+        txs: Vec<ExecutableTransactionInput>,
+        tx_hashes: Option<Vec<TransactionHash>>,
+        flags: ExecutionFlags,
+        abort_handle: AbortHandle,
+    ) -> ExecutionResult<Vec<TransactionSimulationOutput>> {
+        if self.failed {
+            return Err(ExecutionError::SimulationSessionFailed);
+        }
+        let trace_constructors = txs.iter().map(get_trace_constructor).collect::<Vec<_>>();
+        self.cached_state.state.abort_handle = abort_handle.clone();
+        let execution_results = execute_transactions_on_state(
+            &mut self.cached_state,
+            &self.block_context,
+            txs,
+            tx_hashes,
+            &self.chain_id,
+            flags,
+            &abort_handle,
+        )
+        .map_err(|err| {
+            self.failed = true;
+            err
+        })?;
+        to_simulation_outputs(execution_results, trace_constructors, &self.block_context)
+    }
+}
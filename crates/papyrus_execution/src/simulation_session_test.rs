@@ -0,0 +1,85 @@
+use assert_matches::assert_matches;
+use papyrus_storage::test_utils::get_test_storage;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::Nonce;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::state::StateNumber;
+
+use crate::abort_handle::AbortHandle;
+use crate::simulation_session::SimulationSession;
+use crate::test_utils::{
+    prepare_storage,
+    TxsScenarioBuilder,
+    ACCOUNT_ADDRESS,
+    CHAIN_ID,
+    DEPRECATED_CONTRACT_ADDRESS,
+};
+use crate::testing_instances::test_block_execution_config;
+use crate::{ExecutionError, ExecutionFlags};
+
+#[test]
+fn simulations_build_on_previous_simulations() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage(storage_writer);
+
+    let mut session = SimulationSession::new(
+        CHAIN_ID.clone(),
+        storage_reader,
+        None,
+        StateNumber::right_after_block(BlockNumber(0)),
+        BlockNumber(1),
+        &test_block_execution_config(),
+        None,
+    )
+    .unwrap();
+
+    let first_invoke = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    session
+        .simulate_transactions(
+            first_invoke,
+            None,
+            ExecutionFlags::default(),
+            AbortHandle::default(),
+        )
+        .unwrap();
+
+    // The nonce of the account is valid only if the first invoke is in the state of the session.
+    let second_invoke = TxsScenarioBuilder::default()
+        .invoke_deprecated(
+            *ACCOUNT_ADDRESS,
+            *DEPRECATED_CONTRACT_ADDRESS,
+            Some(Nonce(stark_felt!(1_u8))),
+            false,
+        )
+        .collect();
+    session
+        .simulate_transactions(
+            second_invoke,
+            None,
+            ExecutionFlags::default(),
+            AbortHandle::default(),
+        )
+        .unwrap();
+
+    // An invoke with a stale nonce fails and the session can't be used after that.
+    let stale_invoke = TxsScenarioBuilder::default()
+        .invoke_deprecated(*ACCOUNT_ADDRESS, *DEPRECATED_CONTRACT_ADDRESS, None, false)
+        .collect();
+    let result = session.simulate_transactions(
+        stale_invoke.clone(),
+        None,
+        ExecutionFlags::default(),
+        AbortHandle::default(),
+    );
+    assert_matches!(result, Err(ExecutionError::TransactionExecutionError { .. }));
+    let result = session.simulate_transactions(
+        stale_invoke,
+        None,
+        ExecutionFlags::default(),
+        AbortHandle::default(),
+    );
+    assert_matches!(result, Err(ExecutionError::SimulationSessionFailed));
+}
@@ -17,9 +17,11 @@ use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::hash::StarkFelt;
 use starknet_api::state::{StateNumber, StorageKey};
 
+use crate::abort_handle::AbortHandle;
+use crate::contract_class_cache::ContractClassCache;
 use crate::execution_utils;
 use crate::execution_utils::{get_contract_class, ExecutionUtilsError};
-use crate::objects::PendingData;
+use crate::objects::{PendingData, StateOverrides};
 
 /// A view into the state at a specific state number.
 pub struct ExecutionStateReader {
@@ -29,6 +31,23 @@ pub struct ExecutionStateReader {
     // We want to return a custom error when missing a compiled class, but we need to return
     // Blockifier's error, so we store the missing class's hash in case of error.
     pub missing_compiled_class: Option<ClassHash>,
+    // A cache of compiled classes shared between executions.
+    pub class_cache: Option<ContractClassCache>,
+    // Changes that are applied on top of the state and the pending data. Balance overrides should
+    // already be translated into storage overrides of the fee token.
+    pub state_overrides: StateOverrides,
+    // The blockifier can't be interrupted, so once the execution is aborted the state reads fail,
+    // which stops the running transaction at its next state read.
+    pub abort_handle: AbortHandle,
+}
+
+impl ExecutionStateReader {
+    fn verify_not_aborted(&self) -> StateResult<()> {
+        if self.abort_handle.is_aborted() {
+            return Err(StateError::StateReadError("The execution was aborted.".to_string()));
+        }
+        Ok(())
+    }
 }
 
 impl BlockifierStateReader for ExecutionStateReader {
@@ -37,6 +56,15 @@ impl BlockifierStateReader for ExecutionStateReader {
         contract_address: ContractAddress,
         key: StorageKey,
     ) -> StateResult<StarkFelt> {
+        self.verify_not_aborted()?;
+        if let Some(value) = self
+            .state_overrides
+            .storage_diffs
+            .get(&contract_address)
+            .and_then(|storage_diff| storage_diff.get(&key))
+        {
+            return Ok(*value);
+        }
         execution_utils::get_storage_at(
             &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
             self.state_number,
@@ -49,6 +77,10 @@ impl BlockifierStateReader for ExecutionStateReader {
 
     // Returns the default value if the contract address is not found.
     fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.verify_not_aborted()?;
+        if let Some(nonce) = self.state_overrides.nonces.get(&contract_address) {
+            return Ok(*nonce);
+        }
         Ok(execution_utils::get_nonce_at(
             &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
             self.state_number,
@@ -61,6 +93,10 @@ impl BlockifierStateReader for ExecutionStateReader {
 
     // Returns the default value if the contract address is not found.
     fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.verify_not_aborted()?;
+        if let Some(class_hash) = self.state_overrides.class_hashes.get(&contract_address) {
+            return Ok(*class_hash);
+        }
         Ok(execution_utils::get_class_hash_at(
             &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
             self.state_number,
@@ -77,6 +113,10 @@ impl BlockifierStateReader for ExecutionStateReader {
         &mut self,
         class_hash: &ClassHash,
     ) -> StateResult<BlockifierContractClass> {
+        self.verify_not_aborted()?;
+        if let Some(class) = self.state_overrides.classes.get(class_hash) {
+            return execution_utils::overridden_class_to_contract_class(class_hash, class);
+        }
         if let Some(pending_casm) = self
             .maybe_pending_data
             .as_ref()
@@ -100,6 +140,7 @@ impl BlockifierStateReader for ExecutionStateReader {
             &self.storage_reader.begin_ro_txn().map_err(storage_err_to_state_err)?,
             class_hash,
             self.state_number,
+            self.class_cache.as_ref(),
         ) {
             Ok(Some(contract_class)) => Ok(contract_class),
             Ok(None) => Err(StateError::UndeclaredClassHash(*class_hash)),
@@ -113,6 +154,12 @@ impl BlockifierStateReader for ExecutionStateReader {
     }
 
     fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.verify_not_aborted()?;
+        // The compiled class hash is only used for declaring classes, which the overridden classes
+        // are treated as if they already were.
+        if self.state_overrides.classes.contains_key(&class_hash) {
+            return Ok(CompiledClassHash::default());
+        }
         if let Some(pending_data) = &self.maybe_pending_data {
             for DeclaredClassHashEntry { class_hash: other_class_hash, compiled_class_hash } in
                 &pending_data.declared_classes
@@ -122,34 +169,14 @@ impl BlockifierStateReader for ExecutionStateReader {
                 }
             }
         }
-        let block_number = self
-            .storage_reader
+        self.storage_reader
             .begin_ro_txn()
             .map_err(storage_err_to_state_err)?
             .get_state_reader()
             .map_err(storage_err_to_state_err)?
-            .get_class_definition_block_number(&class_hash)
-            .map_err(storage_err_to_state_err)?
-            .ok_or(StateError::UndeclaredClassHash(class_hash))?;
-
-        let state_diff = self
-            .storage_reader
-            .begin_ro_txn()
-            .map_err(storage_err_to_state_err)?
-            .get_state_diff(block_number)
+            .get_compiled_class_hash(&class_hash)
             .map_err(storage_err_to_state_err)?
-            .ok_or(StateError::StateReadError(format!(
-                "Inner storage error. Missing state diff at block {block_number}."
-            )))?;
-
-        let compiled_class_hash = state_diff.declared_classes.get(&class_hash).ok_or(
-            StateError::StateReadError(format!(
-                "Inner storage error. Missing class declaration at block {block_number}, class \
-                 {class_hash}."
-            )),
-        )?;
-
-        Ok(*compiled_class_hash)
+            .ok_or(StateError::UndeclaredClassHash(class_hash))
     }
 }
 
@@ -26,7 +26,8 @@ use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::{ContractClass, StateDiff, StateNumber, StorageKey};
 use starknet_api::{patricia_key, stark_felt};
 
-use crate::objects::PendingData;
+use crate::abort_handle::AbortHandle;
+use crate::objects::{PendingData, StateOverrides};
 use crate::state_reader::ExecutionStateReader;
 use crate::test_utils::{get_test_casm, get_test_deprecated_contract_class};
 
@@ -66,6 +67,7 @@ fn read_state() {
     let class_hash3 = ClassHash(567_u128.into());
     let class_hash4 = ClassHash(89_u128.into());
     let class_hash5 = ClassHash(98765_u128.into());
+    let class_hash6 = ClassHash(4321_u128.into());
 
     storage_writer
         .begin_rw_txn()
@@ -141,6 +143,9 @@ fn read_state() {
         state_number: state_number0,
         maybe_pending_data: None,
         missing_compiled_class: None,
+        class_cache: None,
+        state_overrides: StateOverrides::default(),
+        abort_handle: AbortHandle::default(),
     };
     let storage_after_block_0 = state_reader0.get_storage_at(address0, storage_key0).unwrap();
     assert_eq!(storage_after_block_0, StarkFelt::default());
@@ -162,6 +167,9 @@ fn read_state() {
         state_number: state_number1,
         maybe_pending_data: None,
         missing_compiled_class: None,
+        class_cache: None,
+        state_overrides: StateOverrides::default(),
+        abort_handle: AbortHandle::default(),
     };
     let storage_after_block_1 = state_reader1.get_storage_at(address0, storage_key0).unwrap();
     assert_eq!(storage_after_block_1, storage_value0);
@@ -184,6 +192,9 @@ fn read_state() {
         state_number: state_number2,
         maybe_pending_data: None,
         missing_compiled_class: None,
+        class_cache: None,
+        state_overrides: StateOverrides::default(),
+        abort_handle: AbortHandle::default(),
     };
     let nonce_after_block_2 = state_reader2.get_nonce_at(address0).unwrap();
     assert_eq!(nonce_after_block_2, nonce0);
@@ -226,7 +237,7 @@ fn read_state() {
     // Test that if the class is deprecated it is returned.
     assert_eq!(
         state_reader2.get_compiled_contract_class(&class_hash4).unwrap(),
-        BlockifierContractClass::V0(ContractClassV0::try_from(class1).unwrap())
+        BlockifierContractClass::V0(ContractClassV0::try_from(class1.clone()).unwrap())
     );
 
     // Test get_class_hash_at when the class is replaced.
@@ -238,6 +249,45 @@ fn read_state() {
     }
     assert_eq!(state_reader2.get_class_hash_at(address0).unwrap(), class_hash3);
     assert_eq!(state_reader2.get_class_hash_at(address2).unwrap(), class_hash3);
+
+    // Test that the state overrides take precedence over the pending data.
+    let overridden_storage_value = stark_felt!("0x999");
+    let overridden_nonce = Nonce(stark_felt!("0x999"));
+    state_reader2.state_overrides = StateOverrides {
+        storage_diffs: indexmap!(address0 => indexmap!(storage_key0 => overridden_storage_value)),
+        nonces: indexmap!(address2 => overridden_nonce),
+        class_hashes: indexmap!(address2 => class_hash0),
+        classes: indexmap!(
+            class_hash6 => ApiContractClass::DeprecatedContractClass(class1.clone()),
+        ),
+        ..Default::default()
+    };
+    assert_eq!(
+        state_reader2.get_storage_at(address0, storage_key0).unwrap(),
+        overridden_storage_value
+    );
+    assert_eq!(state_reader2.get_storage_at(address2, storage_key0).unwrap(), storage_value2);
+    assert_eq!(state_reader2.get_nonce_at(address2).unwrap(), overridden_nonce);
+    assert_eq!(state_reader2.get_nonce_at(address0).unwrap(), nonce0);
+    assert_eq!(state_reader2.get_class_hash_at(address2).unwrap(), class_hash0);
+    assert_eq!(state_reader2.get_class_hash_at(address0).unwrap(), class_hash3);
+    assert_eq!(
+        state_reader2.get_compiled_contract_class(&class_hash6).unwrap(),
+        BlockifierContractClass::V0(ContractClassV0::try_from(class1).unwrap())
+    );
+    assert_eq!(
+        state_reader2.get_compiled_class_hash(class_hash6).unwrap(),
+        CompiledClassHash::default()
+    );
+
+    // Test that the reads of an aborted execution fail, even of overridden values.
+    state_reader2.abort_handle.abort();
+    assert_matches!(
+        state_reader2.get_storage_at(address0, storage_key0),
+        Err(StateError::StateReadError(_))
+    );
+    assert_matches!(state_reader2.get_nonce_at(address2), Err(StateError::StateReadError(_)));
+    assert_matches!(state_reader2.get_class_hash_at(address2), Err(StateError::StateReadError(_)));
 }
 
 // Make sure we have the arbitrary precision feature of serde_json.
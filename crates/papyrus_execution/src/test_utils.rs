@@ -43,10 +43,11 @@ use starknet_api::transaction::{
 use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
 use test_utils::read_json_file;
 
+use crate::abort_handle::AbortHandle;
 use crate::execution_utils::selector_from_name;
 use crate::objects::{PendingData, TransactionSimulationOutput};
 use crate::testing_instances::test_block_execution_config;
-use crate::{simulate_transactions, ExecutableTransactionInput, OnlyQuery};
+use crate::{simulate_transactions, ExecutableTransactionInput, ExecutionFlags, OnlyQuery};
 
 lazy_static! {
     pub static ref CHAIN_ID: ChainId = ChainId(String::from("TEST_CHAIN_ID"));
@@ -84,6 +85,10 @@ pub fn get_test_erc20_fee_contract_class() -> DeprecatedContractClass {
 pub fn get_test_account_class() -> DeprecatedContractClass {
     get_test_instance("account_class.json")
 }
+// A Sierra class for testing, taken from the test contract of Blockifier.
+pub fn get_test_sierra_class() -> ContractClass {
+    get_test_instance("sierra_class.json")
+}
 
 pub fn prepare_storage(mut storage_writer: StorageWriter) {
     let class_hash0 = class_hash!("0x2");
@@ -191,8 +196,10 @@ pub fn execute_simulate_transactions(
         StateNumber::right_after_block(BlockNumber(0)),
         BlockNumber(1),
         &test_block_execution_config(),
-        charge_fee,
-        validate,
+        ExecutionFlags { validate, charge_fee },
+        None,
+        None,
+        AbortHandle::default(),
     )
     .unwrap()
 }
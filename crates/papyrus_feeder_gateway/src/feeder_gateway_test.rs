@@ -0,0 +1,199 @@
+use indexmap::IndexMap;
+use papyrus_common::raw_transaction::RawTransaction;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::chain_fixture::{ChainFixture, ChainFixtureBuilder};
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::open_storage;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_config;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use starknet_api::block::{BlockBody, BlockHeader, BlockNumber};
+use starknet_api::core::ClassHash;
+use starknet_api::stark_felt;
+use starknet_api::state::{ContractClass, StateDiff};
+use starknet_api::transaction::TransactionOffsetInBlock;
+use starknet_client::reader::objects::block::BlockStatus;
+use starknet_client::reader::{
+    ContractClass as ClientContractClass,
+    GenericContractClass,
+    StarknetFeederGatewayClient,
+    StarknetFeederGatewayClientConfig,
+    StarknetReader,
+};
+use starknet_client::RetryConfig;
+
+use super::{objects, read_block, run_server, FeederGatewayConfig};
+
+const N_BLOCKS: u64 = 4;
+
+// Writes a chain to the storage, with a Cairo 1 class declared in its last block and a field
+// unknown to the node in the first transaction of block 1, and returns a client of a feeder gateway
+// server over that storage.
+async fn setup(
+    body_retention_blocks: Option<u64>,
+) -> (StarknetFeederGatewayClient, ChainFixture, ClassHash, tempfile::TempDir) {
+    let (mut storage_config, temp_dir) = get_test_config(None);
+    storage_config.body_retention_blocks = body_retention_blocks;
+    let (storage_reader, mut storage_writer) = open_storage(storage_config).unwrap();
+    let fixture = ChainFixtureBuilder::new(N_BLOCKS - 1)
+        .transactions_per_block(1..=5)
+        .initial_contracts(3)
+        .storage_keys_per_contract(4)
+        .storage_updates_per_block(5)
+        .build();
+    fixture.write_to_storage(&mut storage_writer).unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let block = objects::block(read_block(&txn, BlockNumber(1)).unwrap());
+    drop(txn);
+    let mut raw_transaction = RawTransaction {
+        transaction: serde_json::to_value(&block.transactions[0]).unwrap(),
+        receipt: serde_json::to_value(&block.transaction_receipts[0]).unwrap(),
+    };
+    raw_transaction.transaction["unknown_field"] = json!("0x1");
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_raw_transactions(
+            BlockNumber(1),
+            [(TransactionOffsetInBlock(0), raw_transaction)].into(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let class_hash = ClassHash(stark_felt!("0x1234"));
+    let last_block_number = BlockNumber(N_BLOCKS - 1);
+    let header = BlockHeader {
+        block_number: last_block_number,
+        parent_hash: fixture.blocks.last().unwrap().header.block_hash,
+        ..Default::default()
+    };
+    let state_diff = StateDiff {
+        declared_classes: IndexMap::from([(class_hash, (Default::default(), test_class()))]),
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(last_block_number, &header)
+        .unwrap()
+        .append_body(last_block_number, BlockBody::default())
+        .unwrap()
+        .append_state_diff(last_block_number, state_diff, IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let config = FeederGatewayConfig { server_address: String::from("127.0.0.1:0") };
+    let (address, _handle) = run_server(&config, storage_reader).unwrap();
+    let retry_config = RetryConfig {
+        retry_base_millis: 10,
+        backoff_multiplier: 10,
+        retry_max_delay_millis: 10,
+        max_retries: 0,
+    };
+    let client = StarknetFeederGatewayClient::new(
+        &format!("http://{address}"),
+        StarknetFeederGatewayClientConfig::default(),
+        "test",
+        retry_config,
+    )
+    .unwrap();
+    (client, fixture, class_hash, temp_dir)
+}
+
+fn test_class() -> ContractClass {
+    ContractClass {
+        sierra_program: vec![stark_felt!("0x1"), stark_felt!("0x2")],
+        entry_point_by_type: Default::default(),
+        abi: String::from("[]"),
+    }
+}
+
+#[tokio::test]
+async fn get_block() {
+    let (client, fixture, _class_hash, _temp_dir) = setup(None).await;
+    let expected_block = &fixture.blocks[1];
+
+    let block = client.block(BlockNumber(1)).await.unwrap().unwrap();
+    assert_eq!(block.status, BlockStatus::AcceptedOnL2);
+    // The unknown field is served back.
+    let raw_transaction = &block.raw_transactions[&TransactionOffsetInBlock(0)];
+    assert_eq!(raw_transaction.transaction["unknown_field"], json!("0x1"));
+    let (block, _starknet_version) = block.to_starknet_api_block_and_version().unwrap();
+    assert_eq!(block.header, expected_block.header);
+    assert_eq!(block.body.transaction_hashes, expected_block.body.transaction_hashes);
+    assert_eq!(block.body.transaction_outputs, expected_block.body.transaction_outputs);
+
+    let latest_block = client.latest_block().await.unwrap().unwrap();
+    assert_eq!(latest_block.block_number, BlockNumber(N_BLOCKS - 1));
+    assert!(client.block(BlockNumber(N_BLOCKS)).await.unwrap().is_none());
+    assert!(client.pending_data().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn get_pruned_block() {
+    let (client, fixture, _class_hash, _temp_dir) = setup(Some(2)).await;
+
+    let err = client.block(BlockNumber(1)).await.unwrap_err();
+    assert!(err.to_string().contains("The body of block 1 was pruned."));
+    let block = client.block(BlockNumber(2)).await.unwrap().unwrap();
+    let (block, _starknet_version) = block.to_starknet_api_block_and_version().unwrap();
+    assert_eq!(block.body.transaction_hashes, fixture.blocks[2].body.transaction_hashes);
+
+    // The state of a block whose body was pruned is kept.
+    let state_update = client.state_update(BlockNumber(1)).await.unwrap().unwrap();
+    assert_eq!(state_update.block_hash, fixture.blocks[1].header.block_hash);
+}
+
+#[tokio::test]
+async fn get_state_update() {
+    let (client, fixture, _class_hash, _temp_dir) = setup(None).await;
+    let expected_state_diff = &fixture.state_diffs[1];
+
+    let state_update = client.state_update(BlockNumber(1)).await.unwrap().unwrap();
+    assert_eq!(state_update.block_hash, fixture.blocks[1].header.block_hash);
+    assert_eq!(state_update.old_root, fixture.blocks[0].header.state_root);
+    let state_diff = state_update.state_diff;
+    let deployed_contracts: IndexMap<_, _> = state_diff
+        .deployed_contracts
+        .into_iter()
+        .map(|contract| (contract.address, contract.class_hash))
+        .collect();
+    assert_eq!(deployed_contracts, expected_state_diff.deployed_contracts);
+    let storage_diffs: IndexMap<_, IndexMap<_, _>> = state_diff
+        .storage_diffs
+        .into_iter()
+        .map(|(address, entries)| {
+            (address, entries.into_iter().map(|entry| (entry.key, entry.value)).collect())
+        })
+        .collect();
+    assert_eq!(storage_diffs, expected_state_diff.storage_diffs);
+    assert_eq!(state_diff.nonces, expected_state_diff.nonces);
+
+    assert!(client.state_update(BlockNumber(N_BLOCKS)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn get_class_by_hash() {
+    let (client, _fixture, class_hash, _temp_dir) = setup(None).await;
+    let expected_class = test_class();
+
+    let GenericContractClass::Cairo1ContractClass(class) =
+        client.class_by_hash(class_hash).await.unwrap().unwrap()
+    else {
+        panic!("Expected a Cairo 1 class.");
+    };
+    assert_eq!(
+        class,
+        ClientContractClass {
+            sierra_program: expected_class.sierra_program,
+            entry_points_by_type: expected_class.entry_point_by_type,
+            contract_class_version: String::from("0.1.0"),
+            abi: expected_class.abi,
+        }
+    );
+    assert!(client.class_by_hash(ClassHash(stark_felt!("0x2"))).await.unwrap().is_none());
+}
@@ -0,0 +1,348 @@
+// config compiler to support coverage_attribute feature when running coverage in nightly mode
+// within this crate
+#![cfg_attr(coverage_nightly, feature(coverage_attribute))]
+
+//! A server that serves the read endpoints of the Starknet feeder gateway from the storage of the
+//! node, so that another node (or any other feeder gateway client) can sync from this node instead
+//! of from the feeder gateway.
+//!
+//! Only accepted blocks are served. Requests for the pending block are answered as if there is no
+//! pending block.
+
+#[cfg(test)]
+#[path = "feeder_gateway_test.rs"]
+mod feeder_gateway_test;
+mod objects;
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use objects::StoredBlock;
+use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageError, StorageReader, StorageTxn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ClassHash, GlobalRoot};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StateNumber;
+use starknet_api::transaction::TransactionOffsetInBlock;
+use starknet_client::reader::objects::block::BlockStatus;
+use starknet_client::{KnownStarknetErrorCode, StarknetError, StarknetErrorCode};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, instrument};
+use validator::Validate;
+
+const GET_BLOCK_PATH: &str = "/feeder_gateway/get_block";
+const GET_STATE_UPDATE_PATH: &str = "/feeder_gateway/get_state_update";
+const GET_CLASS_BY_HASH_PATH: &str = "/feeder_gateway/get_class_by_hash";
+const IS_ALIVE_PATH: &str = "/feeder_gateway/is_alive";
+const IS_ALIVE_RESPONSE: &str = "FeederGateway is alive!";
+
+const BLOCK_NUMBER_QUERY: &str = "blockNumber";
+const BLOCK_HASH_QUERY: &str = "blockHash";
+const CLASS_HASH_QUERY: &str = "classHash";
+const INCLUDE_BLOCK_QUERY: &str = "includeBlock";
+const LATEST_BLOCK_ID: &str = "latest";
+const PENDING_BLOCK_ID: &str = "pending";
+
+// The feeder gateway has no code for internal errors, so the code of such errors is unknown to its
+// clients.
+const INTERNAL_ERROR_CODE: &str = "StarknetErrorCode.INTERNAL_ERROR";
+// Neither is there a code for data that a node doesn't keep.
+const PRUNED_DATA_ERROR_CODE: &str = "StarknetErrorCode.PRUNED_DATA";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Validate)]
+pub struct FeederGatewayConfig {
+    pub server_address: String,
+}
+
+impl Default for FeederGatewayConfig {
+    fn default() -> Self {
+        FeederGatewayConfig { server_address: String::from("0.0.0.0:8083") }
+    }
+}
+
+impl SerializeConfig for FeederGatewayConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([ser_param(
+            "server_address",
+            &self.server_address,
+            "IP:PORT of the node`s feeder gateway server.",
+            ParamPrivacyInput::Public,
+        )])
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FeederGatewayServerError {
+    #[error("Invalid server address {0}.")]
+    InvalidServerAddress(String),
+    #[error(transparent)]
+    Hyper(#[from] hyper::Error),
+}
+
+/// Spawns a feeder gateway server. Returns the address the server listens on and a handle to the
+/// server.
+pub fn run_server(
+    config: &FeederGatewayConfig,
+    storage_reader: StorageReader,
+) -> Result<(SocketAddr, JoinHandle<Result<(), hyper::Error>>), FeederGatewayServerError> {
+    let server_address = config.server_address.parse::<SocketAddr>().map_err(|_| {
+        FeederGatewayServerError::InvalidServerAddress(config.server_address.clone())
+    })?;
+    let server =
+        axum::Server::try_bind(&server_address)?.serve(app(storage_reader).into_make_service());
+    let local_address = server.local_addr();
+    info!("Feeder gateway server is running - {local_address}.");
+    Ok((local_address, tokio::spawn(server)))
+}
+
+fn app(storage_reader: StorageReader) -> Router {
+    let block_reader = storage_reader.clone();
+    let state_update_reader = storage_reader.clone();
+    Router::new()
+        .route(GET_BLOCK_PATH, get(move |query| get_block(block_reader, query)))
+        .route(
+            GET_STATE_UPDATE_PATH,
+            get(move |query| get_state_update(state_update_reader, query)),
+        )
+        .route(GET_CLASS_BY_HASH_PATH, get(move |query| get_class_by_hash(storage_reader, query)))
+        .route(IS_ALIVE_PATH, get(|| async { IS_ALIVE_RESPONSE }))
+}
+
+/// Returns a block with its transactions and receipts. The transactions that were synced with
+/// fields unknown to the node are returned as they were received.
+#[instrument(skip(storage_reader), level = "debug", err)]
+async fn get_block(
+    storage_reader: StorageReader,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, FeederGatewayError> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let block_number = get_block_number(&txn, &query)?;
+    Ok(Json(objects::block(read_block(&txn, block_number)?).to_raw_block()?))
+}
+
+/// Returns the state update of a block. If `includeBlock=true` is given, returns the block as
+/// well.
+#[instrument(skip(storage_reader), level = "debug", err)]
+async fn get_state_update(
+    storage_reader: StorageReader,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, FeederGatewayError> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let block_number = get_block_number(&txn, &query)?;
+    let header = txn.get_block_header(block_number)?.ok_or_else(missing_data)?;
+    let old_root = match block_number.prev() {
+        Some(parent_number) => {
+            txn.get_block_header(parent_number)?.ok_or_else(missing_data)?.state_root
+        }
+        None => GlobalRoot::default(),
+    };
+    let state_diff = txn.get_state_diff(block_number)?.ok_or_else(missing_data)?;
+    let state_update = serde_json::to_value(objects::state_update(&header, old_root, state_diff))?;
+
+    match query.get(INCLUDE_BLOCK_QUERY).map(String::as_str) {
+        Some("true") => {
+            let block = objects::block(read_block(&txn, block_number)?).to_raw_block()?;
+            Ok(Json(json!({ "block": block, "state_update": state_update })))
+        }
+        None | Some("false") => Ok(Json(state_update)),
+        Some(include_block) => Err(FeederGatewayError::MalformedRequest(format!(
+            "Invalid value for {INCLUDE_BLOCK_QUERY}: {include_block}."
+        ))),
+    }
+}
+
+/// Returns the definition of a class, as declared at the given block (by default, the latest
+/// block).
+#[instrument(skip(storage_reader), level = "debug", err)]
+async fn get_class_by_hash(
+    storage_reader: StorageReader,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, FeederGatewayError> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let class_hash = ClassHash(parse_felt(&query, CLASS_HASH_QUERY)?.ok_or_else(|| {
+        FeederGatewayError::MalformedRequest(format!("Missing {CLASS_HASH_QUERY}."))
+    })?);
+    // The classes of the pending block are the classes of the latest block.
+    let block_number = match query.get(BLOCK_NUMBER_QUERY).map(String::as_str) {
+        Some(PENDING_BLOCK_ID) => latest_block_number(&txn)?,
+        _ => get_block_number(&txn, &query)?,
+    };
+    let state_number = StateNumber::right_after_block(block_number);
+    match txn.get_state_reader()?.get_class_at(state_number, &class_hash)? {
+        Some(ApiContractClass::ContractClass(class)) => {
+            Ok(Json(serde_json::to_value(objects::contract_class(class))?))
+        }
+        Some(ApiContractClass::DeprecatedContractClass(class)) => {
+            Ok(Json(serde_json::to_value(class)?))
+        }
+        None => Err(FeederGatewayError::UndeclaredClass(class_hash)),
+    }
+}
+
+// Returns the block the request refers to, which must be an accepted block.
+fn get_block_number(
+    txn: &StorageTxn<'_, RO>,
+    query: &HashMap<String, String>,
+) -> Result<BlockNumber, FeederGatewayError> {
+    let latest_block_number = latest_block_number(txn)?;
+    let block_number = match (query.get(BLOCK_NUMBER_QUERY), parse_felt(query, BLOCK_HASH_QUERY)?) {
+        (Some(_), Some(_)) => {
+            return Err(FeederGatewayError::MalformedRequest(format!(
+                "Expected either {BLOCK_NUMBER_QUERY} or {BLOCK_HASH_QUERY}."
+            )));
+        }
+        (None, Some(block_hash)) => txn
+            .get_block_number_by_hash(&BlockHash(block_hash))?
+            .ok_or(FeederGatewayError::BlockNotFound)?,
+        (None, None) => latest_block_number,
+        (Some(block_id), None) => match block_id.as_str() {
+            LATEST_BLOCK_ID => latest_block_number,
+            PENDING_BLOCK_ID => return Err(FeederGatewayError::BlockNotFound),
+            block_number => BlockNumber(block_number.parse().map_err(|_| {
+                FeederGatewayError::MalformedRequest(format!(
+                    "Invalid {BLOCK_NUMBER_QUERY}: {block_number}."
+                ))
+            })?),
+        },
+    };
+    if block_number > latest_block_number {
+        return Err(FeederGatewayError::BlockNotFound);
+    }
+    Ok(block_number)
+}
+
+// The latest block whose header, body and state diff are all in the storage.
+fn latest_block_number(txn: &StorageTxn<'_, RO>) -> Result<BlockNumber, FeederGatewayError> {
+    let marker = txn.get_header_marker()?.min(txn.get_body_marker()?).min(txn.get_state_marker()?);
+    marker.prev().ok_or(FeederGatewayError::BlockNotFound)
+}
+
+fn read_block(
+    txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+) -> Result<StoredBlock, FeederGatewayError> {
+    let header = txn.get_block_header(block_number)?.ok_or_else(missing_data)?;
+    let status = if block_number < txn.get_base_layer_block_marker()? {
+        BlockStatus::AcceptedOnL1
+    } else {
+        BlockStatus::AcceptedOnL2
+    };
+    if block_number < txn.get_pruned_body_marker()? {
+        return Err(FeederGatewayError::PrunedBody(block_number));
+    }
+    let transactions = txn.get_block_transactions(block_number)?.ok_or_else(missing_data)?;
+    let transaction_hashes =
+        txn.get_block_transaction_hashes(block_number)?.ok_or_else(missing_data)?;
+    let transaction_outputs =
+        txn.get_block_transaction_outputs(block_number)?.ok_or_else(missing_data)?;
+    let events = (0..transactions.len())
+        .map(|offset| {
+            let transaction_index =
+                TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            txn.get_transaction_events(transaction_index)?.ok_or_else(missing_data)
+        })
+        .collect::<Result<_, _>>()?;
+    let mut raw_transactions = BTreeMap::new();
+    for offset in 0..transactions.len() {
+        let transaction_offset = TransactionOffsetInBlock(offset);
+        let transaction_index = TransactionIndex(block_number, transaction_offset);
+        if let Some(raw_transaction) = txn.get_raw_transaction(transaction_index)? {
+            raw_transactions.insert(transaction_offset, raw_transaction);
+        }
+    }
+    Ok(StoredBlock {
+        header,
+        status,
+        starknet_version: txn
+            .get_starknet_version(block_number)?
+            .map(|version| version.0)
+            .unwrap_or_default(),
+        transactions,
+        transaction_hashes,
+        transaction_outputs,
+        events,
+        raw_transactions,
+    })
+}
+
+fn parse_felt(
+    query: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<StarkFelt>, FeederGatewayError> {
+    query
+        .get(name)
+        .map(|value| {
+            StarkFelt::try_from(value.as_str()).map_err(|_| {
+                FeederGatewayError::MalformedRequest(format!("Invalid {name}: {value}."))
+            })
+        })
+        .transpose()
+}
+
+fn missing_data() -> FeederGatewayError {
+    FeederGatewayError::MissingData
+}
+
+#[derive(thiserror::Error, Debug)]
+enum FeederGatewayError {
+    #[error("Block not found.")]
+    BlockNotFound,
+    #[error("Class with hash {0:?} is not declared.")]
+    UndeclaredClass(ClassHash),
+    #[error("{0}")]
+    MalformedRequest(String),
+    #[error("Missing data of an accepted block.")]
+    MissingData,
+    #[error("The body of block {0} was pruned.")]
+    PrunedBody(BlockNumber),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+impl IntoResponse for FeederGatewayError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            FeederGatewayError::BlockNotFound => (
+                StatusCode::BAD_REQUEST,
+                StarknetErrorCode::KnownErrorCode(KnownStarknetErrorCode::BlockNotFound),
+            ),
+            FeederGatewayError::UndeclaredClass(_) => (
+                StatusCode::BAD_REQUEST,
+                StarknetErrorCode::KnownErrorCode(KnownStarknetErrorCode::UndeclaredClass),
+            ),
+            FeederGatewayError::MalformedRequest(_) => (
+                StatusCode::BAD_REQUEST,
+                StarknetErrorCode::KnownErrorCode(KnownStarknetErrorCode::MalformedRequest),
+            ),
+            FeederGatewayError::PrunedBody(_) => (
+                StatusCode::BAD_REQUEST,
+                StarknetErrorCode::UnknownErrorCode(PRUNED_DATA_ERROR_CODE.to_owned()),
+            ),
+            FeederGatewayError::MissingData
+            | FeederGatewayError::Storage(_)
+            | FeederGatewayError::Serde(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StarknetErrorCode::UnknownErrorCode(INTERNAL_ERROR_CODE.to_owned()),
+            ),
+        };
+        debug!("Feeder gateway request failed: {self}.");
+        (status, Json(StarknetError { code, message: self.to_string() })).into_response()
+    }
+}
@@ -0,0 +1,432 @@
+//! Conversions of the data in the storage to the objects of the feeder gateway, as the
+//! [`starknet_client`] reads them.
+
+use std::collections::{BTreeMap, HashMap};
+
+use papyrus_common::raw_transaction::RawTransaction;
+use papyrus_storage::body::events::ThinTransactionOutput;
+use serde_json::Map;
+use starknet_api::block::BlockHeader;
+use starknet_api::core::{ContractAddress, EthAddress, GlobalRoot};
+use starknet_api::state::{ContractClass, ThinStateDiff};
+use starknet_api::transaction::{
+    DeclareTransaction,
+    DeployAccountTransaction,
+    Event,
+    ExecutionResources,
+    InvokeTransaction,
+    L1HandlerTransaction,
+    L1ToL2Payload,
+    Transaction,
+    TransactionHash,
+    TransactionOffsetInBlock,
+    TransactionVersion,
+};
+use starknet_client::reader::objects::block::BlockStatus;
+use starknet_client::reader::objects::transaction::{
+    Builtin as ClientBuiltin,
+    DeployTransaction as ClientDeployTransaction,
+    ExecutionResources as ClientExecutionResources,
+    IntermediateDeclareTransaction,
+    IntermediateDeployAccountTransaction,
+    IntermediateInvokeTransaction,
+    L1HandlerTransaction as ClientL1HandlerTransaction,
+    L1ToL2Message,
+    L1ToL2Nonce,
+    L2ToL1Message,
+    ReservedDataAvailabilityMode,
+    Transaction as ClientTransaction,
+    TransactionReceipt,
+};
+use starknet_client::reader::{
+    Block,
+    ContractClass as ClientContractClass,
+    DeclaredClassHashEntry,
+    DeployedContract,
+    ReplacedClass,
+    StateDiff,
+    StateUpdate,
+    StorageEntry,
+};
+
+// The feeder gateway serves only Sierra classes of this version.
+const CONTRACT_CLASS_VERSION: &str = "0.1.0";
+
+/// The data of a block in the storage.
+pub(crate) struct StoredBlock {
+    pub header: BlockHeader,
+    pub status: BlockStatus,
+    pub starknet_version: String,
+    pub transactions: Vec<Transaction>,
+    pub transaction_hashes: Vec<TransactionHash>,
+    pub transaction_outputs: Vec<ThinTransactionOutput>,
+    pub events: Vec<Vec<Event>>,
+    pub raw_transactions: BTreeMap<TransactionOffsetInBlock, RawTransaction>,
+}
+
+pub(crate) fn block(stored_block: StoredBlock) -> Block {
+    let StoredBlock {
+        header,
+        status,
+        starknet_version,
+        transactions,
+        transaction_hashes,
+        transaction_outputs,
+        events,
+        raw_transactions,
+    } = stored_block;
+    let mut client_transactions = vec![];
+    let mut transaction_receipts = vec![];
+    for (offset, (((transaction, transaction_hash), output), events)) in transactions
+        .into_iter()
+        .zip(transaction_hashes)
+        .zip(transaction_outputs)
+        .zip(events)
+        .enumerate()
+    {
+        transaction_receipts.push(transaction_receipt(
+            TransactionOffsetInBlock(offset),
+            transaction_hash,
+            &transaction,
+            &output,
+            events,
+        ));
+        client_transactions.push(transaction(transaction, transaction_hash, &output));
+    }
+    Block {
+        block_hash: header.block_hash,
+        block_number: header.block_number,
+        eth_l1_gas_price: header.eth_l1_gas_price,
+        strk_l1_gas_price: header.strk_l1_gas_price,
+        parent_block_hash: header.parent_hash,
+        sequencer_address: header.sequencer,
+        state_root: header.state_root,
+        status,
+        timestamp: header.timestamp,
+        transactions: client_transactions,
+        transaction_receipts,
+        starknet_version,
+        raw_transactions,
+    }
+}
+
+/// Returns the state update of a block, given the state root of its parent block.
+pub(crate) fn state_update(
+    header: &BlockHeader,
+    old_root: GlobalRoot,
+    state_diff: ThinStateDiff,
+) -> StateUpdate {
+    StateUpdate {
+        block_hash: header.block_hash,
+        new_root: header.state_root,
+        old_root,
+        state_diff: StateDiff {
+            storage_diffs: state_diff
+                .storage_diffs
+                .into_iter()
+                .map(|(address, entries)| {
+                    let entries =
+                        entries.into_iter().map(|(key, value)| StorageEntry { key, value });
+                    (address, entries.collect())
+                })
+                .collect(),
+            deployed_contracts: state_diff
+                .deployed_contracts
+                .into_iter()
+                .map(|(address, class_hash)| DeployedContract { address, class_hash })
+                .collect(),
+            declared_classes: state_diff
+                .declared_classes
+                .into_iter()
+                .map(|(class_hash, compiled_class_hash)| DeclaredClassHashEntry {
+                    class_hash,
+                    compiled_class_hash,
+                })
+                .collect(),
+            old_declared_contracts: state_diff.deprecated_declared_classes,
+            nonces: state_diff.nonces,
+            replaced_classes: state_diff
+                .replaced_classes
+                .into_iter()
+                .map(|(address, class_hash)| ReplacedClass { address, class_hash })
+                .collect(),
+        },
+    }
+}
+
+pub(crate) fn contract_class(class: ContractClass) -> ClientContractClass {
+    ClientContractClass {
+        sierra_program: class.sierra_program,
+        entry_points_by_type: class.entry_point_by_type,
+        contract_class_version: CONTRACT_CLASS_VERSION.to_owned(),
+        abi: class.abi,
+    }
+}
+
+// The address of a deployed contract is not part of its deploy transaction, so it's taken from the
+// output of the transaction.
+fn transaction(
+    transaction: Transaction,
+    transaction_hash: TransactionHash,
+    output: &ThinTransactionOutput,
+) -> ClientTransaction {
+    let deployed_contract_address = match output {
+        ThinTransactionOutput::Deploy(output) => output.contract_address,
+        ThinTransactionOutput::DeployAccount(output) => output.contract_address,
+        _ => ContractAddress::default(),
+    };
+    match transaction {
+        Transaction::Declare(declare_tx) => {
+            ClientTransaction::Declare(declare_transaction(declare_tx, transaction_hash))
+        }
+        Transaction::Deploy(deploy_tx) => ClientTransaction::Deploy(ClientDeployTransaction {
+            contract_address: deployed_contract_address,
+            contract_address_salt: deploy_tx.contract_address_salt,
+            class_hash: deploy_tx.class_hash,
+            constructor_calldata: deploy_tx.constructor_calldata,
+            transaction_hash,
+            version: deploy_tx.version,
+            unknown_fields: Map::new(),
+        }),
+        Transaction::DeployAccount(deploy_account_tx) => {
+            ClientTransaction::DeployAccount(deploy_account_transaction(
+                deploy_account_tx,
+                transaction_hash,
+                deployed_contract_address,
+            ))
+        }
+        Transaction::Invoke(invoke_tx) => {
+            ClientTransaction::Invoke(invoke_transaction(invoke_tx, transaction_hash))
+        }
+        Transaction::L1Handler(l1_handler_tx) => {
+            ClientTransaction::L1Handler(ClientL1HandlerTransaction {
+                transaction_hash,
+                version: l1_handler_tx.version,
+                nonce: l1_handler_tx.nonce,
+                contract_address: l1_handler_tx.contract_address,
+                entry_point_selector: l1_handler_tx.entry_point_selector,
+                calldata: l1_handler_tx.calldata,
+                unknown_fields: Map::new(),
+            })
+        }
+    }
+}
+
+fn declare_transaction(
+    declare_tx: DeclareTransaction,
+    transaction_hash: TransactionHash,
+) -> IntermediateDeclareTransaction {
+    let version = declare_tx.version();
+    match declare_tx {
+        DeclareTransaction::V0(tx) | DeclareTransaction::V1(tx) => IntermediateDeclareTransaction {
+            resource_bounds: None,
+            tip: None,
+            signature: tx.signature,
+            nonce: tx.nonce,
+            class_hash: tx.class_hash,
+            compiled_class_hash: None,
+            sender_address: tx.sender_address,
+            nonce_data_availability_mode: None,
+            fee_data_availability_mode: None,
+            paymaster_data: None,
+            account_deployment_data: None,
+            max_fee: Some(tx.max_fee),
+            version,
+            transaction_hash,
+            unknown_fields: Map::new(),
+        },
+        DeclareTransaction::V2(tx) => IntermediateDeclareTransaction {
+            resource_bounds: None,
+            tip: None,
+            signature: tx.signature,
+            nonce: tx.nonce,
+            class_hash: tx.class_hash,
+            compiled_class_hash: Some(tx.compiled_class_hash),
+            sender_address: tx.sender_address,
+            nonce_data_availability_mode: None,
+            fee_data_availability_mode: None,
+            paymaster_data: None,
+            account_deployment_data: None,
+            max_fee: Some(tx.max_fee),
+            version: TransactionVersion::TWO,
+            transaction_hash,
+            unknown_fields: Map::new(),
+        },
+        DeclareTransaction::V3(tx) => IntermediateDeclareTransaction {
+            resource_bounds: Some(tx.resource_bounds),
+            tip: Some(tx.tip),
+            signature: tx.signature,
+            nonce: tx.nonce,
+            class_hash: tx.class_hash,
+            compiled_class_hash: Some(tx.compiled_class_hash),
+            sender_address: tx.sender_address,
+            nonce_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+            fee_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+            paymaster_data: Some(tx.paymaster_data),
+            account_deployment_data: Some(tx.account_deployment_data),
+            max_fee: None,
+            version: TransactionVersion::THREE,
+            transaction_hash,
+            unknown_fields: Map::new(),
+        },
+    }
+}
+
+fn deploy_account_transaction(
+    deploy_account_tx: DeployAccountTransaction,
+    transaction_hash: TransactionHash,
+    sender_address: ContractAddress,
+) -> IntermediateDeployAccountTransaction {
+    match deploy_account_tx {
+        DeployAccountTransaction::V1(tx) => IntermediateDeployAccountTransaction {
+            resource_bounds: None,
+            tip: None,
+            signature: tx.signature,
+            nonce: tx.nonce,
+            class_hash: tx.class_hash,
+            contract_address_salt: tx.contract_address_salt,
+            constructor_calldata: tx.constructor_calldata,
+            nonce_data_availability_mode: None,
+            fee_data_availability_mode: None,
+            paymaster_data: None,
+            sender_address,
+            max_fee: Some(tx.max_fee),
+            transaction_hash,
+            version: TransactionVersion::ONE,
+            unknown_fields: Map::new(),
+        },
+        DeployAccountTransaction::V3(tx) => IntermediateDeployAccountTransaction {
+            resource_bounds: Some(tx.resource_bounds),
+            tip: Some(tx.tip),
+            signature: tx.signature,
+            nonce: tx.nonce,
+            class_hash: tx.class_hash,
+            contract_address_salt: tx.contract_address_salt,
+            constructor_calldata: tx.constructor_calldata,
+            nonce_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+            fee_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+            paymaster_data: Some(tx.paymaster_data),
+            sender_address,
+            max_fee: None,
+            transaction_hash,
+            version: TransactionVersion::THREE,
+            unknown_fields: Map::new(),
+        },
+    }
+}
+
+fn invoke_transaction(
+    invoke_tx: InvokeTransaction,
+    transaction_hash: TransactionHash,
+) -> IntermediateInvokeTransaction {
+    match invoke_tx {
+        InvokeTransaction::V0(tx) => IntermediateInvokeTransaction {
+            calldata: tx.calldata,
+            sender_address: tx.contract_address,
+            entry_point_selector: Some(tx.entry_point_selector),
+            max_fee: Some(tx.max_fee),
+            signature: tx.signature,
+            transaction_hash,
+            version: TransactionVersion::ZERO,
+            ..Default::default()
+        },
+        InvokeTransaction::V1(tx) => IntermediateInvokeTransaction {
+            calldata: tx.calldata,
+            sender_address: tx.sender_address,
+            nonce: Some(tx.nonce),
+            max_fee: Some(tx.max_fee),
+            signature: tx.signature,
+            transaction_hash,
+            version: TransactionVersion::ONE,
+            ..Default::default()
+        },
+        InvokeTransaction::V3(tx) => IntermediateInvokeTransaction {
+            resource_bounds: Some(tx.resource_bounds),
+            tip: Some(tx.tip),
+            calldata: tx.calldata,
+            sender_address: tx.sender_address,
+            entry_point_selector: None,
+            nonce: Some(tx.nonce),
+            max_fee: None,
+            signature: tx.signature,
+            nonce_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+            fee_data_availability_mode: Some(ReservedDataAvailabilityMode::Reserved),
+            paymaster_data: Some(tx.paymaster_data),
+            account_deployment_data: Some(tx.account_deployment_data),
+            transaction_hash,
+            version: TransactionVersion::THREE,
+            unknown_fields: Map::new(),
+        },
+    }
+}
+
+fn transaction_receipt(
+    transaction_index: TransactionOffsetInBlock,
+    transaction_hash: TransactionHash,
+    transaction: &Transaction,
+    output: &ThinTransactionOutput,
+    events: Vec<Event>,
+) -> TransactionReceipt {
+    let l1_to_l2_consumed_message = match transaction {
+        Transaction::L1Handler(l1_handler_tx) => consumed_message(l1_handler_tx),
+        _ => L1ToL2Message::default(),
+    };
+    TransactionReceipt {
+        transaction_index,
+        transaction_hash,
+        l1_to_l2_consumed_message,
+        l2_to_l1_messages: output
+            .messages_sent()
+            .iter()
+            .map(|message| L2ToL1Message {
+                from_address: message.from_address,
+                to_address: message.to_address,
+                payload: message.payload.clone(),
+            })
+            .collect(),
+        events,
+        execution_resources: execution_resources(output.execution_resources()),
+        actual_fee: output.actual_fee(),
+        execution_status: output.execution_status().clone(),
+        unknown_fields: Map::new(),
+    }
+}
+
+// The first element of the calldata of an L1 handler transaction is the address of the message
+// sender, and the rest is the message payload.
+fn consumed_message(l1_handler_tx: &L1HandlerTransaction) -> L1ToL2Message {
+    let Some((from_address, payload)) = l1_handler_tx.calldata.0.split_first() else {
+        return L1ToL2Message::default();
+    };
+    L1ToL2Message {
+        from_address: EthAddress::try_from(*from_address).unwrap_or_default(),
+        to_address: l1_handler_tx.contract_address,
+        selector: l1_handler_tx.entry_point_selector,
+        payload: L1ToL2Payload(payload.to_vec()),
+        nonce: L1ToL2Nonce(l1_handler_tx.nonce.0),
+    }
+}
+
+fn execution_resources(execution_resources: &ExecutionResources) -> ClientExecutionResources {
+    ClientExecutionResources {
+        n_steps: execution_resources.steps,
+        builtin_instance_counter: execution_resources
+            .builtin_instance_counter
+            .iter()
+            .map(|(builtin, count)| {
+                let builtin = match builtin {
+                    starknet_api::transaction::Builtin::RangeCheck => ClientBuiltin::RangeCheck,
+                    starknet_api::transaction::Builtin::Pedersen => ClientBuiltin::Pedersen,
+                    starknet_api::transaction::Builtin::Poseidon => ClientBuiltin::Poseidon,
+                    starknet_api::transaction::Builtin::EcOp => ClientBuiltin::EcOp,
+                    starknet_api::transaction::Builtin::Ecdsa => ClientBuiltin::Ecdsa,
+                    starknet_api::transaction::Builtin::Bitwise => ClientBuiltin::Bitwise,
+                    starknet_api::transaction::Builtin::Keccak => ClientBuiltin::Keccak,
+                    starknet_api::transaction::Builtin::SegmentArena => ClientBuiltin::SegmentArena,
+                };
+                (builtin, *count)
+            })
+            .collect::<HashMap<_, _>>(),
+        n_memory_holes: execution_resources.memory_holes,
+    }
+}
@@ -0,0 +1,116 @@
+// config compiler to support coverage_attribute feature when running coverage in nightly mode
+// within this crate
+#![cfg_attr(coverage_nightly, feature(coverage_attribute))]
+
+//! A GraphQL server over the storage of the node, for consumers that query the chain
+//! relationally, such as indexers. It serves the blocks, transactions, receipts and events of the
+//! chain, and paginates lists with cursors.
+//!
+//! The server serves the queries in `POST /graphql` and an interactive GraphiQL page in
+//! `GET /graphql`.
+
+mod schema;
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql_axum::GraphQL;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::StorageReader;
+use schema::{build_schema, StarknetSchema};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::info;
+use validator::Validate;
+
+const GRAPHQL_PATH: &str = "/graphql";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Validate)]
+pub struct GraphQLConfig {
+    pub server_address: String,
+    #[validate(range(min = 1))]
+    pub max_page_size: usize,
+    #[validate(range(min = 1))]
+    pub max_query_depth: usize,
+    #[validate(range(min = 1))]
+    pub max_query_complexity: usize,
+}
+
+impl Default for GraphQLConfig {
+    fn default() -> Self {
+        GraphQLConfig {
+            server_address: String::from("0.0.0.0:8082"),
+            max_page_size: 100,
+            max_query_depth: 10,
+            max_query_complexity: 1000,
+        }
+    }
+}
+
+impl SerializeConfig for GraphQLConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "server_address",
+                &self.server_address,
+                "IP:PORT of the node`s GraphQL server.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_page_size",
+                &self.max_page_size,
+                "Maximum number of items in a page of a paginated list.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_query_depth",
+                &self.max_query_depth,
+                "Maximum depth of the nested fields of a query.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_query_complexity",
+                &self.max_query_complexity,
+                "Maximum complexity of a query, which is the number of fields it selects.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GraphQLServerError {
+    #[error("Invalid server address {0}.")]
+    InvalidServerAddress(String),
+    #[error(transparent)]
+    Hyper(#[from] hyper::Error),
+}
+
+/// Spawns a GraphQL server. Returns the address the server listens on and a handle to the server.
+pub fn run_server(
+    config: &GraphQLConfig,
+    storage_reader: StorageReader,
+) -> Result<(SocketAddr, JoinHandle<Result<(), hyper::Error>>), GraphQLServerError> {
+    let server_address = config
+        .server_address
+        .parse::<SocketAddr>()
+        .map_err(|_| GraphQLServerError::InvalidServerAddress(config.server_address.clone()))?;
+    let server = axum::Server::try_bind(&server_address)?
+        .serve(app(build_schema(storage_reader, config)).into_make_service());
+    let local_address = server.local_addr();
+    info!("GraphQL server is running - {local_address}.");
+    Ok((local_address, tokio::spawn(server)))
+}
+
+fn app(schema: StarknetSchema) -> Router {
+    Router::new().route(GRAPHQL_PATH, get(graphiql).post_service(GraphQL::new(schema)))
+}
+
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint(GRAPHQL_PATH).finish())
+}
@@ -0,0 +1,537 @@
+//! The GraphQL schema of the chain. All the fields of a request read the storage in a single read
+//! transaction, so they read the same snapshot of the storage, and only the blocks that have both a
+//! header and a body are served.
+
+#[cfg(test)]
+#[path = "schema_test.rs"]
+mod schema_test;
+
+use std::cmp::min;
+use std::sync::Arc;
+
+use async_graphql::async_trait::async_trait;
+use async_graphql::connection::{Connection, CursorType, Edge, OpaqueCursor};
+use async_graphql::extensions::{
+    Extension,
+    ExtensionContext,
+    ExtensionFactory,
+    NextPrepareRequest,
+};
+use async_graphql::{
+    scalar,
+    Context,
+    EmptyMutation,
+    EmptySubscription,
+    Enum,
+    Error,
+    Object,
+    Request,
+    Result,
+    ServerError,
+    ServerResult,
+};
+use papyrus_storage::body::events::{EventIndex, EventsReader, ThinTransactionOutput};
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::{OwnedStorageTxn, StorageReader, StorageTxn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{
+    EventContent,
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionExecutionStatus,
+    TransactionHash,
+    TransactionOffsetInBlock,
+};
+
+use crate::GraphQLConfig;
+
+pub(crate) type StarknetSchema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub(crate) fn build_schema(
+    storage_reader: StorageReader,
+    config: &GraphQLConfig,
+) -> StarknetSchema {
+    async_graphql::Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(storage_reader)
+        .data(MaxPageSize(config.max_page_size))
+        .extension(ReadSnapshot)
+        .limit_depth(config.max_query_depth)
+        .limit_complexity(config.max_query_complexity)
+        .finish()
+}
+
+struct MaxPageSize(usize);
+
+// Starts the read transaction of a request, before the request is executed.
+struct ReadSnapshot;
+
+impl ExtensionFactory for ReadSnapshot {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ReadSnapshot)
+    }
+}
+
+#[async_trait]
+impl Extension for ReadSnapshot {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let txn = ctx
+            .data_unchecked::<StorageReader>()
+            .begin_owned_ro_txn()
+            .map_err(|err| ServerError::new(err.to_string(), None))?;
+        next.run(ctx, request.data(txn)).await
+    }
+}
+
+/// A field element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Felt(StarkFelt);
+scalar!(Felt, "Felt", "A field element, as a hex string with a 0x prefix.");
+
+/// An address of an Ethereum account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct EthAddress(starknet_api::core::EthAddress);
+scalar!(EthAddress, "EthAddress", "An Ethereum address, as a hex string with a 0x prefix.");
+
+pub(crate) struct Query;
+
+#[Object]
+impl Query {
+    /// The block with the given number or hash, or the latest block if neither is given.
+    async fn block(
+        &self,
+        ctx: &Context<'_>,
+        number: Option<u64>,
+        hash: Option<Felt>,
+    ) -> Result<Option<Block>> {
+        let txn = read_txn(ctx);
+        let block_marker = block_marker(txn)?;
+        let block_number = match (number, hash) {
+            (Some(_), Some(_)) => {
+                return Err(Error::new("Expected either a block number or a block hash."));
+            }
+            (Some(number), None) => BlockNumber(number),
+            (None, Some(hash)) => match txn.get_block_number_by_hash(&BlockHash(hash.0))? {
+                Some(block_number) => block_number,
+                None => return Ok(None),
+            },
+            (None, None) => match block_marker.prev() {
+                Some(block_number) => block_number,
+                None => return Ok(None),
+            },
+        };
+        if block_number >= block_marker {
+            return Ok(None);
+        }
+        Ok(Some(Block(get_block_header(txn, block_number)?)))
+    }
+
+    /// The blocks, in ascending order of their numbers.
+    async fn blocks(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<usize>,
+    ) -> Result<Connection<OpaqueCursor<BlockNumber>, Block>> {
+        let page_size = page_size(ctx, first)?;
+        let txn = read_txn(ctx);
+        let block_marker = block_marker(txn)?;
+        let start = match after {
+            Some(after) => decode_cursor::<BlockNumber>(&after)?.next(),
+            None => BlockNumber(0),
+        };
+        let end = min(BlockNumber(start.0.saturating_add(page_size as u64)), block_marker);
+
+        let mut connection = Connection::new(start > BlockNumber(0), end < block_marker);
+        for block_number in start.0..end.0 {
+            let block_number = BlockNumber(block_number);
+            let header = get_block_header(txn, block_number)?;
+            connection.edges.push(Edge::new(OpaqueCursor(block_number), Block(header)));
+        }
+        Ok(connection)
+    }
+
+    /// The transaction with the given hash.
+    async fn transaction(&self, ctx: &Context<'_>, hash: Felt) -> Result<Option<Transaction>> {
+        let txn = read_txn(ctx);
+        let Some(transaction_index) = txn.get_transaction_idx_by_hash(&TransactionHash(hash.0))?
+        else {
+            return Ok(None);
+        };
+        if transaction_index.0 >= block_marker(txn)? {
+            return Ok(None);
+        }
+        // The hashes of the transactions of a block are kept when its body is pruned.
+        let block_number = transaction_index.0;
+        if block_number < txn.get_pruned_body_marker()? {
+            return Err(Error::new(format!("The body of block {block_number} was pruned.")));
+        }
+        let transaction = txn.get_transaction(transaction_index)?.ok_or_else(missing_data)?;
+        Ok(Some(Transaction {
+            index: transaction_index,
+            hash: TransactionHash(hash.0),
+            transaction,
+        }))
+    }
+
+    /// The events of the blocks in the given range, in the order they were emitted. An event
+    /// matches the keys filter if, for every position in the filter, its key in that position is
+    /// one of the keys in the filter. An empty list of keys matches any key.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+        address: Option<Felt>,
+        keys: Option<Vec<Vec<Felt>>>,
+        after: Option<String>,
+        first: Option<usize>,
+    ) -> Result<Connection<OpaqueCursor<EventIndex>, Event>> {
+        let page_size = page_size(ctx, first)?;
+        let address = address
+            .map(|address| PatriciaKey::try_from(address.0).map(ContractAddress))
+            .transpose()?;
+        let keys = keys.unwrap_or_default();
+        let txn = read_txn(ctx);
+        let Some(latest_block_number) = block_marker(txn)?.prev() else {
+            return Ok(Connection::new(false, false));
+        };
+        let to_block_number = to_block.map_or(latest_block_number, |to_block| {
+            min(BlockNumber(to_block), latest_block_number)
+        });
+        let has_previous_page = after.is_some();
+        let start_event_index = match after {
+            Some(after) => {
+                let EventIndex(transaction_index, event_index) =
+                    decode_cursor::<EventIndex>(&after)?;
+                EventIndex(transaction_index, EventIndexInTransactionOutput(event_index.0 + 1))
+            }
+            None => EventIndex(
+                TransactionIndex(BlockNumber(from_block.unwrap_or(0)), TransactionOffsetInBlock(0)),
+                EventIndexInTransactionOutput(0),
+            ),
+        };
+
+        let mut connection = Connection::new(has_previous_page, false);
+        if start_event_index.0.0 > to_block_number {
+            return Ok(connection);
+        }
+        for ((from_address, event_index), content) in
+            txn.iter_events(address, start_event_index, to_block_number)?
+        {
+            if event_index.0.0 > to_block_number {
+                break;
+            }
+            // When iterating by address, the iterator continues to the events of the next
+            // addresses after the events of the given address.
+            if address.is_some_and(|address| address != from_address) {
+                break;
+            }
+            if !do_event_keys_match(&content.keys, &keys) {
+                continue;
+            }
+            if connection.edges.len() == page_size {
+                connection.has_next_page = true;
+                break;
+            }
+            connection.edges.push(Edge::new(
+                OpaqueCursor(event_index),
+                Event { index: event_index, from_address, content },
+            ));
+        }
+        Ok(connection)
+    }
+}
+
+pub(crate) struct Block(BlockHeader);
+
+#[Object]
+impl Block {
+    async fn number(&self) -> u64 {
+        self.0.block_number.0
+    }
+
+    async fn hash(&self) -> Felt {
+        Felt(self.0.block_hash.0)
+    }
+
+    async fn parent_hash(&self) -> Felt {
+        Felt(self.0.parent_hash.0)
+    }
+
+    async fn state_root(&self) -> Felt {
+        Felt(self.0.state_root.0)
+    }
+
+    async fn sequencer_address(&self) -> Felt {
+        Felt(*self.0.sequencer.0.key())
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp.0
+    }
+
+    async fn l1_gas_price_in_wei(&self) -> Felt {
+        Felt(StarkFelt::from(self.0.eth_l1_gas_price.0))
+    }
+
+    async fn l1_gas_price_in_fri(&self) -> Felt {
+        Felt(StarkFelt::from(self.0.strk_l1_gas_price.0))
+    }
+
+    /// The parent block, or null for the genesis block.
+    async fn parent(&self, ctx: &Context<'_>) -> Result<Option<Block>> {
+        let Some(parent_number) = self.0.block_number.prev() else {
+            return Ok(None);
+        };
+        let txn = read_txn(ctx);
+        Ok(Some(Block(get_block_header(txn, parent_number)?)))
+    }
+
+    async fn transaction_count(&self, ctx: &Context<'_>) -> Result<usize> {
+        let txn = read_txn(ctx);
+        txn.get_block_transactions_count(self.0.block_number)?.ok_or_else(missing_data)
+    }
+
+    /// The transactions of the block, in the order they were executed.
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<usize>,
+    ) -> Result<Connection<OpaqueCursor<usize>, Transaction>> {
+        let page_size = page_size(ctx, first)?;
+        let txn = read_txn(ctx);
+        let block_number = self.0.block_number;
+        let transactions = txn.get_block_transactions(block_number)?.ok_or_else(missing_data)?;
+        let transaction_hashes =
+            txn.get_block_transaction_hashes(block_number)?.ok_or_else(missing_data)?;
+        let start = match after {
+            Some(after) => decode_cursor::<usize>(&after)? + 1,
+            None => 0,
+        };
+        let end = min(start.saturating_add(page_size), transactions.len());
+
+        let mut connection = Connection::new(start > 0, end < transactions.len());
+        for (offset, (transaction, hash)) in
+            transactions.into_iter().zip(transaction_hashes).enumerate().take(end).skip(start)
+        {
+            let index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            connection
+                .edges
+                .push(Edge::new(OpaqueCursor(offset), Transaction { index, hash, transaction }));
+        }
+        Ok(connection)
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum TransactionType {
+    Declare,
+    Deploy,
+    DeployAccount,
+    Invoke,
+    L1Handler,
+}
+
+pub(crate) struct Transaction {
+    index: TransactionIndex,
+    hash: TransactionHash,
+    transaction: starknet_api::transaction::Transaction,
+}
+
+#[Object]
+impl Transaction {
+    async fn hash(&self) -> Felt {
+        Felt(self.hash.0)
+    }
+
+    #[graphql(name = "type")]
+    async fn transaction_type(&self) -> TransactionType {
+        match self.transaction {
+            starknet_api::transaction::Transaction::Declare(_) => TransactionType::Declare,
+            starknet_api::transaction::Transaction::Deploy(_) => TransactionType::Deploy,
+            starknet_api::transaction::Transaction::DeployAccount(_) => {
+                TransactionType::DeployAccount
+            }
+            starknet_api::transaction::Transaction::Invoke(_) => TransactionType::Invoke,
+            starknet_api::transaction::Transaction::L1Handler(_) => TransactionType::L1Handler,
+        }
+    }
+
+    async fn block_number(&self) -> u64 {
+        self.index.0.0
+    }
+
+    /// The position of the transaction in its block.
+    async fn index_in_block(&self) -> usize {
+        self.index.1 .0
+    }
+
+    async fn block(&self, ctx: &Context<'_>) -> Result<Block> {
+        let txn = read_txn(ctx);
+        Ok(Block(get_block_header(txn, self.index.0)?))
+    }
+
+    async fn receipt(&self, ctx: &Context<'_>) -> Result<Receipt> {
+        let txn = read_txn(ctx);
+        let output = txn.get_transaction_output(self.index)?.ok_or_else(missing_data)?;
+        Ok(Receipt { transaction_hash: self.hash, output })
+    }
+
+    /// The events the transaction emitted, in the order they were emitted.
+    async fn events(&self, ctx: &Context<'_>) -> Result<Vec<Event>> {
+        let txn = read_txn(ctx);
+        let events = txn.get_transaction_events(self.index)?.ok_or_else(missing_data)?;
+        Ok(events
+            .into_iter()
+            .enumerate()
+            .map(|(event_index, event)| Event {
+                index: EventIndex(self.index, EventIndexInTransactionOutput(event_index)),
+                from_address: event.from_address,
+                content: event.content,
+            })
+            .collect())
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ExecutionStatus {
+    Succeeded,
+    Reverted,
+}
+
+pub(crate) struct Receipt {
+    transaction_hash: TransactionHash,
+    output: ThinTransactionOutput,
+}
+
+#[Object]
+impl Receipt {
+    async fn transaction_hash(&self) -> Felt {
+        Felt(self.transaction_hash.0)
+    }
+
+    async fn actual_fee(&self) -> Felt {
+        Felt(StarkFelt::from(self.output.actual_fee().0))
+    }
+
+    async fn execution_status(&self) -> ExecutionStatus {
+        match self.output.execution_status() {
+            TransactionExecutionStatus::Succeeded => ExecutionStatus::Succeeded,
+            TransactionExecutionStatus::Reverted => ExecutionStatus::Reverted,
+        }
+    }
+
+    /// The messages the transaction sent to the base layer.
+    async fn messages_sent(&self) -> Vec<MessageToL1> {
+        self.output
+            .messages_sent()
+            .iter()
+            .map(|message| MessageToL1 {
+                from_address: Felt(*message.from_address.0.key()),
+                to_address: EthAddress(message.to_address),
+                payload: message.payload.0.iter().copied().map(Felt).collect(),
+            })
+            .collect()
+    }
+}
+
+#[derive(async_graphql::SimpleObject)]
+pub(crate) struct MessageToL1 {
+    from_address: Felt,
+    to_address: EthAddress,
+    payload: Vec<Felt>,
+}
+
+pub(crate) struct Event {
+    index: EventIndex,
+    from_address: ContractAddress,
+    content: EventContent,
+}
+
+#[Object]
+impl Event {
+    async fn from_address(&self) -> Felt {
+        Felt(*self.from_address.0.key())
+    }
+
+    async fn keys(&self) -> Vec<Felt> {
+        self.content.keys.iter().map(|key| Felt(key.0)).collect()
+    }
+
+    async fn data(&self) -> Vec<Felt> {
+        self.content.data.0.iter().copied().map(Felt).collect()
+    }
+
+    async fn block_number(&self) -> u64 {
+        self.index.0.0.0
+    }
+
+    /// The position of the event in the events of its transaction.
+    async fn index_in_transaction(&self) -> usize {
+        self.index.1 .0
+    }
+
+    /// The transaction that emitted the event.
+    async fn transaction(&self, ctx: &Context<'_>) -> Result<Transaction> {
+        let txn = read_txn(ctx);
+        let transaction_index = self.index.0;
+        let hash = txn.get_transaction_hash_by_idx(&transaction_index)?.ok_or_else(missing_data)?;
+        let transaction = txn.get_transaction(transaction_index)?.ok_or_else(missing_data)?;
+        Ok(Transaction { index: transaction_index, hash, transaction })
+    }
+}
+
+fn read_txn<'a>(ctx: &Context<'a>) -> &'a StorageTxn<'a, RO> {
+    ctx.data_unchecked::<OwnedStorageTxn>().txn()
+}
+
+// The first block that doesn't have both a header and a body.
+fn block_marker(txn: &StorageTxn<'_, RO>) -> Result<BlockNumber> {
+    Ok(min(txn.get_header_marker()?, txn.get_body_marker()?))
+}
+
+fn get_block_header(txn: &StorageTxn<'_, RO>, block_number: BlockNumber) -> Result<BlockHeader> {
+    txn.get_block_header(block_number)?.ok_or_else(missing_data)
+}
+
+fn page_size(ctx: &Context<'_>, first: Option<usize>) -> Result<usize> {
+    let MaxPageSize(max_page_size) = ctx.data_unchecked::<MaxPageSize>();
+    match first {
+        Some(first) if first > *max_page_size => {
+            Err(Error::new(format!("The page size is limited to {max_page_size} items.")))
+        }
+        Some(first) => Ok(first),
+        None => Ok(*max_page_size),
+    }
+}
+
+fn decode_cursor<T: Serialize + DeserializeOwned + Send + Sync>(cursor: &str) -> Result<T> {
+    OpaqueCursor::<T>::decode_cursor(cursor)
+        .map(|cursor| cursor.0)
+        .map_err(|_| Error::new("Invalid cursor."))
+}
+
+fn do_event_keys_match(event_keys: &[EventKey], keys: &[Vec<Felt>]) -> bool {
+    keys.iter().enumerate().all(|(i, keys)| {
+        event_keys.len() > i && (keys.is_empty() || keys.contains(&Felt(event_keys[i].0)))
+    })
+}
+
+// The storage is missing data of a block that is below the markers.
+fn missing_data() -> Error {
+    Error::new("Missing data in the storage.")
+}
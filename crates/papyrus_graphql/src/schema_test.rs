@@ -0,0 +1,286 @@
+use async_graphql::Response;
+use papyrus_storage::chain_fixture::{ChainFixture, ChainFixtureBuilder};
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::transaction::Transaction;
+
+use super::{build_schema, StarknetSchema};
+use crate::GraphQLConfig;
+
+const MAX_PAGE_SIZE: usize = 5;
+
+fn setup_schema(n_blocks: u64) -> (StarknetSchema, ChainFixture, tempfile::TempDir) {
+    let ((storage_reader, mut storage_writer), temp_dir) = get_test_storage();
+    let fixture = ChainFixtureBuilder::new(n_blocks)
+        .transactions_per_block(1..=3)
+        .max_events_per_transaction(3)
+        .initial_contracts(2)
+        .build();
+    fixture.write_to_storage(&mut storage_writer).unwrap();
+    let config = GraphQLConfig { max_page_size: MAX_PAGE_SIZE, ..Default::default() };
+    (build_schema(storage_reader, &config), fixture, temp_dir)
+}
+
+async fn execute(schema: &StarknetSchema, query: &str) -> Value {
+    let response: Response = schema.execute(query).await;
+    assert!(response.errors.is_empty(), "Unexpected errors: {:?}", response.errors);
+    response.data.into_json().unwrap()
+}
+
+async fn execute_with_error(schema: &StarknetSchema, query: &str) -> String {
+    let response = schema.execute(query).await;
+    assert_eq!(response.errors.len(), 1);
+    response.errors[0].message.clone()
+}
+
+#[tokio::test]
+async fn get_block() {
+    let (schema, fixture, _temp_dir) = setup_schema(3);
+    let header = &fixture.blocks[1].header;
+
+    let expected_block = json!({
+        "block": {
+            "number": 1,
+            "hash": header.block_hash,
+            "parentHash": header.parent_hash,
+            "timestamp": header.timestamp.0,
+            "parent": { "number": 0 },
+            "transactionCount": fixture.blocks[1].body.transactions.len(),
+        }
+    });
+    let fields = "number hash parentHash timestamp parent { number } transactionCount";
+    let res = execute(&schema, &format!("{{ block(number: 1) {{ {fields} }} }}")).await;
+    assert_eq!(res, expected_block);
+    let res = execute(
+        &schema,
+        &format!(
+            "{{ block(hash: \"{}\") {{ {fields} }} }}",
+            json!(header.block_hash).as_str().unwrap()
+        ),
+    )
+    .await;
+    assert_eq!(res, expected_block);
+
+    // The latest block.
+    let res = execute(&schema, "{ block { number } }").await;
+    assert_eq!(res, json!({ "block": { "number": 2 } }));
+
+    let res = execute(&schema, "{ block(number: 3) { number } }").await;
+    assert_eq!(res, json!({ "block": null }));
+    let res = execute(&schema, "{ block(number: 0) { parent { number } } }").await;
+    assert_eq!(res, json!({ "block": { "parent": null } }));
+
+    let message = execute_with_error(
+        &schema,
+        &format!(
+            "{{ block(number: 1, hash: \"{}\") {{ number }} }}",
+            json!(header.block_hash).as_str().unwrap()
+        ),
+    )
+    .await;
+    assert_eq!(message, "Expected either a block number or a block hash.");
+}
+
+#[tokio::test]
+async fn paginate_blocks() {
+    let (schema, _fixture, _temp_dir) = setup_schema(7);
+    let query = |after: &str| {
+        format!(
+            "{{ blocks({after}) {{ edges {{ cursor node {{ number }} }} pageInfo {{ \
+             hasPreviousPage hasNextPage endCursor }} }} }}"
+        )
+    };
+
+    let res = execute(&schema, &query("")).await;
+    let blocks = &res["blocks"];
+    let numbers: Vec<_> = blocks["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edge| edge["node"]["number"].as_u64().unwrap())
+        .collect();
+    assert_eq!(numbers, vec![0, 1, 2, 3, 4]);
+    assert_eq!(blocks["pageInfo"]["hasPreviousPage"], json!(false));
+    assert_eq!(blocks["pageInfo"]["hasNextPage"], json!(true));
+
+    let end_cursor = blocks["pageInfo"]["endCursor"].as_str().unwrap();
+    let res = execute(&schema, &query(&format!("after: \"{end_cursor}\", first: 5"))).await;
+    let blocks = &res["blocks"];
+    let numbers: Vec<_> = blocks["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edge| edge["node"]["number"].as_u64().unwrap())
+        .collect();
+    assert_eq!(numbers, vec![5, 6]);
+    assert_eq!(blocks["pageInfo"]["hasPreviousPage"], json!(true));
+    assert_eq!(blocks["pageInfo"]["hasNextPage"], json!(false));
+
+    let message = execute_with_error(&schema, "{ blocks(first: 6) { edges { cursor } } }").await;
+    assert_eq!(message, format!("The page size is limited to {MAX_PAGE_SIZE} items."));
+    let message =
+        execute_with_error(&schema, "{ blocks(after: \"invalid\") { edges { cursor } } }").await;
+    assert_eq!(message, "Invalid cursor.");
+}
+
+#[tokio::test]
+async fn get_transaction_with_receipt_and_events() {
+    let (schema, fixture, _temp_dir) = setup_schema(3);
+    let body = &fixture.blocks[2].body;
+    let offset = body.transactions.len() - 1;
+    let transaction_hash = body.transaction_hashes[offset];
+    let output = &body.transaction_outputs[offset];
+    let expected_type = match body.transactions[offset] {
+        Transaction::Declare(_) => "DECLARE",
+        Transaction::Deploy(_) => "DEPLOY",
+        Transaction::DeployAccount(_) => "DEPLOY_ACCOUNT",
+        Transaction::Invoke(_) => "INVOKE",
+        Transaction::L1Handler(_) => "L1_HANDLER",
+    };
+    let expected_events: Vec<_> = output
+        .events()
+        .iter()
+        .enumerate()
+        .map(|(index, event)| {
+            json!({
+                "fromAddress": event.from_address,
+                "keys": event.content.keys,
+                "indexInTransaction": index,
+            })
+        })
+        .collect();
+
+    let res = execute(
+        &schema,
+        &format!(
+            "{{ transaction(hash: \"{}\") {{ hash type blockNumber indexInBlock block {{ number \
+             }} receipt {{ transactionHash executionStatus }} events {{ fromAddress keys \
+             indexInTransaction }} }} }}",
+            json!(transaction_hash).as_str().unwrap()
+        ),
+    )
+    .await;
+    assert_eq!(
+        res,
+        json!({
+            "transaction": {
+                "hash": transaction_hash,
+                "type": expected_type,
+                "blockNumber": 2,
+                "indexInBlock": offset,
+                "block": { "number": 2 },
+                "receipt": { "transactionHash": transaction_hash, "executionStatus": "SUCCEEDED" },
+                "events": expected_events,
+            }
+        })
+    );
+
+    let res = execute(&schema, "{ transaction(hash: \"0x1234\") { hash } }").await;
+    assert_eq!(res, json!({ "transaction": null }));
+}
+
+#[tokio::test]
+async fn paginate_block_transactions() {
+    let (schema, fixture, _temp_dir) = setup_schema(1);
+    let transaction_hashes = &fixture.blocks[0].body.transaction_hashes;
+
+    let res = execute(
+        &schema,
+        "{ block(number: 0) { transactions(first: 1) { edges { node { hash } } pageInfo { \
+         hasNextPage endCursor } } } }",
+    )
+    .await;
+    let transactions = &res["block"]["transactions"];
+    assert_eq!(transactions["edges"], json!([{ "node": { "hash": transaction_hashes[0] } }]));
+    assert_eq!(transactions["pageInfo"]["hasNextPage"], json!(transaction_hashes.len() > 1));
+
+    let end_cursor = transactions["pageInfo"]["endCursor"].as_str().unwrap();
+    let res = execute(
+        &schema,
+        &format!(
+            "{{ block(number: 0) {{ transactions(after: \"{end_cursor}\") {{ edges {{ node {{ \
+             hash }} }} }} }} }}"
+        ),
+    )
+    .await;
+    let expected_edges: Vec<_> =
+        transaction_hashes[1..].iter().map(|hash| json!({ "node": { "hash": hash } })).collect();
+    assert_eq!(res["block"]["transactions"]["edges"], json!(expected_edges));
+}
+
+#[tokio::test]
+async fn filter_and_paginate_events() {
+    let (schema, fixture, _temp_dir) = setup_schema(6);
+    let blocks = &fixture.blocks[1..=4];
+    let address = blocks
+        .iter()
+        .flat_map(|block| &block.body.transaction_outputs)
+        .find_map(|output| output.events().first())
+        .expect("The blocks should have events.")
+        .from_address;
+    let mut expected_events = vec![];
+    for block in blocks {
+        for output in &block.body.transaction_outputs {
+            for event in output.events() {
+                if event.from_address == address {
+                    expected_events.push(json!({
+                        "blockNumber": block.header.block_number.0,
+                        "fromAddress": event.from_address,
+                        "keys": event.content.keys,
+                    }));
+                }
+            }
+        }
+    }
+
+    let mut events = vec![];
+    let mut after = String::new();
+    loop {
+        let res = execute(
+            &schema,
+            &format!(
+                "{{ events(fromBlock: 1, toBlock: 4, address: \"{}\"{after}) {{ edges {{ node {{ \
+                 blockNumber fromAddress keys }} }} pageInfo {{ hasNextPage endCursor }} }} }}",
+                json!(address).as_str().unwrap()
+            ),
+        )
+        .await;
+        let page = &res["events"];
+        events.extend(page["edges"].as_array().unwrap().iter().map(|edge| edge["node"].clone()));
+        if !page["pageInfo"]["hasNextPage"].as_bool().unwrap() {
+            break;
+        }
+        after = format!(", after: \"{}\"", page["pageInfo"]["endCursor"].as_str().unwrap());
+    }
+    assert_eq!(events, expected_events);
+
+    // Filter by the key of the first event.
+    let key = &expected_events[0]["keys"][0];
+    let res = execute(
+        &schema,
+        &format!("{{ events(keys: [[{key}]], first: 5) {{ edges {{ node {{ keys }} }} }} }}"),
+    )
+    .await;
+    for edge in res["events"]["edges"].as_array().unwrap() {
+        assert_eq!(&edge["node"]["keys"][0], key);
+    }
+}
+
+#[tokio::test]
+async fn limit_queries() {
+    let (schema, _fixture, _temp_dir) = setup_schema(1);
+    let config = GraphQLConfig::default();
+
+    let n_parents = config.max_query_depth;
+    let query =
+        format!("{{ block {} number {} }}", "{ parent ".repeat(n_parents), "}".repeat(n_parents));
+    assert_eq!(execute_with_error(&schema, &query).await, "Query is nested too deep.");
+
+    let fields = (0..config.max_query_complexity)
+        .map(|i| format!("number{i}: number"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let query = format!("{{ block {{ {fields} }} }}");
+    assert_eq!(execute_with_error(&schema, &query).await, "Query is too complex.");
+}
@@ -0,0 +1,102 @@
+// Replays recorded JSON-RPC requests against papyrus and another node and prints the fields in
+// which their responses differ. Exits with an error if any response differs.
+// The requests file has a JSON-RPC request per line, for example:
+//      {"method": "starknet_getBlockWithTxHashes", "params": [{"block_number": 1}]}
+// To compare papyrus with another node run:
+//      cargo run -r -p papyrus_load_test --bin rpc_compare -- --requests_file requests.jsonl \
+//          --papyrus_url http://127.0.0.1:8080/rpc/v0_6 --other_url <other node url>
+
+use std::collections::HashSet;
+use std::process::ExitCode;
+
+use clap::{Arg, Command};
+use papyrus_load_test::rpc_compare::{compare_responses, read_requests};
+use reqwest::Client;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<ExitCode> {
+    let cli_params = get_cli_params();
+    let requests = read_requests(&cli_params.requests_file)?;
+    let client = Client::new();
+
+    let mut n_mismatched_requests = 0;
+    for request in requests.iter().cloned() {
+        let comparison = compare_responses(
+            &client,
+            &cli_params.papyrus_url,
+            &cli_params.other_url,
+            request,
+            &cli_params.ignored_fields,
+        )
+        .await?;
+        if comparison.differences.is_empty() {
+            continue;
+        }
+        n_mismatched_requests += 1;
+        println!("Responses to {} differ (papyrus != other):", comparison.request);
+        for difference in comparison.differences {
+            println!("    {difference}");
+        }
+    }
+
+    println!("{n_mismatched_requests} out of {} requests got different responses.", requests.len());
+    Ok(if n_mismatched_requests == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+struct CliParams {
+    requests_file: String,
+    papyrus_url: String,
+    other_url: String,
+    ignored_fields: HashSet<String>,
+}
+
+/// The requests_file, papyrus_url and other_url arguments are mandatory. The ignored_fields
+/// argument is a comma separated list of field names that aren't compared, e.g. error messages
+/// that differ between implementations.
+fn get_cli_params() -> CliParams {
+    let matches = Command::new("RPC compare")
+        .arg(
+            Arg::new("requests_file")
+                .short('r')
+                .long("requests_file")
+                .required(true)
+                .help("The file with the requests to replay, a JSON-RPC request per line."),
+        )
+        .arg(
+            Arg::new("papyrus_url")
+                .short('p')
+                .long("papyrus_url")
+                .required(true)
+                .help("The url of the papyrus JSON-RPC endpoint, including the version path."),
+        )
+        .arg(
+            Arg::new("other_url")
+                .short('o')
+                .long("other_url")
+                .required(true)
+                .help("The url of the JSON-RPC endpoint to compare with."),
+        )
+        .arg(
+            Arg::new("ignored_fields")
+                .short('i')
+                .long("ignored_fields")
+                .default_value("")
+                .help("A comma separated list of field names that aren't compared."),
+        )
+        .get_matches();
+
+    let get_arg = |name: &str| {
+        matches.get_one::<String>(name).unwrap_or_else(|| panic!("Failed parsing {name}")).clone()
+    };
+    let ignored_fields = get_arg("ignored_fields")
+        .split(',')
+        .filter(|field| !field.is_empty())
+        .map(str::to_owned)
+        .collect();
+    CliParams {
+        requests_file: get_arg("requests_file"),
+        papyrus_url: get_arg("papyrus_url"),
+        other_url: get_arg("other_url"),
+        ignored_fields,
+    }
+}
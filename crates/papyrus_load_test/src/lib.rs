@@ -7,6 +7,7 @@ pub mod create_files;
 pub mod create_request;
 #[cfg(test)]
 mod precision_test;
+pub mod rpc_compare;
 pub mod scenarios;
 pub mod transactions;
 
@@ -4,6 +4,11 @@
 //      cargo run -r -p papyrus_load_test -- -t 5m -H http://127.0.0.1:8080 --scenarios=generalrequestv004
 // To see all the available scenarios run:
 //      cargo run -r -p papyrus_load_test -- --scenarios-list
+// To send a fixed rate of requests per second, for example 100, add --throttle-requests 100.
+// To change the mix of requests, set the env variable REQUEST_MIX to the weights of the request
+// categories, for example REQUEST_MIX=read=8,events=1,trace=1. By default all the categories have
+// the same weight.
+// The report at the end of the run has the latency percentiles and the error rate of each request.
 // To create the files of requests run:
 //      cargo run -r -p papyrus_load_test -- --create_files 127.0.0.1:8080
 // For more options run:
@@ -15,7 +20,7 @@ use std::fs::File;
 use assert_matches::assert_matches;
 use goose::{util, GooseAttack};
 use papyrus_load_test::create_files::create_files;
-use papyrus_load_test::scenarios;
+use papyrus_load_test::scenarios::{self, RequestMix};
 use serde::Serialize;
 
 #[tokio::main]
@@ -27,10 +32,15 @@ async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    let request_mix = match env::var("REQUEST_MIX") {
+        Ok(request_mix) => request_mix.parse::<RequestMix>()?,
+        Err(_) => RequestMix::default(),
+    };
+
     let metrics = GooseAttack::initialize()?
         // The choice between versions must be also in the environment variable VERSION_ID.
         // TODO(dvir): Add scenarios for v0_5 and v0_6.
-        .register_scenario(scenarios::general_request_v0_4())
+        .register_scenario(scenarios::general_request_v0_4(request_mix))
         .execute()
         .await?;
 
@@ -0,0 +1,163 @@
+//! Replays recorded JSON-RPC requests against two nodes and compares their responses field by
+//! field.
+#[cfg(test)]
+#[path = "rpc_compare_test.rs"]
+mod rpc_compare_test;
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs;
+
+use reqwest::Client;
+use serde_json::{json, Value as jsonVal};
+
+use crate::jsonrpc_request;
+
+// The fields of a response that identify the request rather than hold its result.
+const ENVELOPE_FIELDS: [&str; 2] = ["jsonrpc", "id"];
+
+/// A field whose value differs between two responses. A missing field has no value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDifference {
+    /// The path of the field in the response, e.g. `result.transactions[3].type`.
+    pub path: String,
+    pub left: Option<jsonVal>,
+    pub right: Option<jsonVal>,
+}
+
+impl Display for FieldDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value_to_string = |value: &Option<jsonVal>| {
+            value.as_ref().map_or("<missing>".to_owned(), jsonVal::to_string)
+        };
+        write!(
+            f,
+            "{}: {} != {}",
+            self.path,
+            value_to_string(&self.left),
+            value_to_string(&self.right)
+        )
+    }
+}
+
+/// The comparison of the responses of two nodes to a single request.
+#[derive(Debug, Clone)]
+pub struct RequestComparison {
+    pub request: jsonVal,
+    pub differences: Vec<FieldDifference>,
+}
+
+/// Reads the requests to replay from a file with a request per line. A request is either a full
+/// JSON-RPC request or an object with a method and optional params. Empty lines are skipped.
+pub fn read_requests(path: &str) -> anyhow::Result<Vec<jsonVal>> {
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut request: jsonVal = serde_json::from_str(line)?;
+            let Some(method) = request.get("method").and_then(jsonVal::as_str).map(str::to_owned)
+            else {
+                anyhow::bail!("Request without a method: {line}");
+            };
+            if request.get("jsonrpc").is_none() {
+                let params = request.get_mut("params").map(jsonVal::take).unwrap_or(json!([]));
+                request = jsonrpc_request(&method, params);
+            }
+            Ok(request)
+        })
+        .collect()
+}
+
+/// Sends the request to both urls and compares the responses. Fields whose name is in
+/// ignored_fields aren't compared.
+pub async fn compare_responses(
+    client: &Client,
+    left_url: &str,
+    right_url: &str,
+    request: jsonVal,
+    ignored_fields: &HashSet<String>,
+) -> anyhow::Result<RequestComparison> {
+    let (left, right) = tokio::try_join!(
+        send_jsonrpc_request(client, left_url, &request),
+        send_jsonrpc_request(client, right_url, &request)
+    )?;
+    let differences = diff_json(&left, &right, ignored_fields);
+    Ok(RequestComparison { request, differences })
+}
+
+async fn send_jsonrpc_request(
+    client: &Client,
+    url: &str,
+    request: &jsonVal,
+) -> anyhow::Result<jsonVal> {
+    let mut response: jsonVal = client.post(url).json(request).send().await?.json().await?;
+    if let Some(response) = response.as_object_mut() {
+        for field in ENVELOPE_FIELDS {
+            response.remove(field);
+        }
+    }
+    Ok(response)
+}
+
+/// Returns the fields that differ between the two values, ordered by their path. Objects are
+/// compared by key and arrays by index.
+pub fn diff_json(
+    left: &jsonVal,
+    right: &jsonVal,
+    ignored_fields: &HashSet<String>,
+) -> Vec<FieldDifference> {
+    let mut differences = vec![];
+    diff_json_at(String::new(), left, right, ignored_fields, &mut differences);
+    differences
+}
+
+fn diff_json_at(
+    path: String,
+    left: &jsonVal,
+    right: &jsonVal,
+    ignored_fields: &HashSet<String>,
+    differences: &mut Vec<FieldDifference>,
+) {
+    match (left, right) {
+        (jsonVal::Object(left), jsonVal::Object(right)) => {
+            let mut keys = left.keys().chain(right.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys.into_iter().filter(|key| !ignored_fields.contains(*key)) {
+                let field_path =
+                    if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                match (left.get(key), right.get(key)) {
+                    (Some(left), Some(right)) => {
+                        diff_json_at(field_path, left, right, ignored_fields, differences)
+                    }
+                    (left, right) => differences.push(FieldDifference {
+                        path: field_path,
+                        left: left.cloned(),
+                        right: right.cloned(),
+                    }),
+                }
+            }
+        }
+        (jsonVal::Array(left), jsonVal::Array(right)) => {
+            for i in 0..left.len().max(right.len()) {
+                let element_path = format!("{path}[{i}]");
+                match (left.get(i), right.get(i)) {
+                    (Some(left), Some(right)) => {
+                        diff_json_at(element_path, left, right, ignored_fields, differences)
+                    }
+                    (left, right) => differences.push(FieldDifference {
+                        path: element_path,
+                        left: left.cloned(),
+                        right: right.cloned(),
+                    }),
+                }
+            }
+        }
+        (left, right) if left != right => differences.push(FieldDifference {
+            path,
+            left: Some(left.clone()),
+            right: Some(right.clone()),
+        }),
+        _ => {}
+    }
+}
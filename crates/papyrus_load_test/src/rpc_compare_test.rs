@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+use super::{diff_json, read_requests, FieldDifference};
+use crate::jsonrpc_request;
+
+#[test]
+fn equal_values_have_no_differences() {
+    let value = json!({"result": {"block_number": 1, "transactions": ["0x1", "0x2"]}});
+    assert_eq!(diff_json(&value, &value.clone(), &HashSet::new()), vec![]);
+}
+
+#[test]
+fn differences_by_path() {
+    let left = json!({
+        "result": {
+            "block_number": 1,
+            "status": "ACCEPTED_ON_L2",
+            "transactions": [{"type": "INVOKE"}, {"type": "DECLARE"}],
+        }
+    });
+    let right = json!({
+        "result": {
+            "block_number": 1,
+            "transactions": [{"type": "INVOKE"}, {"type": "DEPLOY_ACCOUNT"}, {"type": "INVOKE"}],
+            "l1_gas_price": "0x1",
+        }
+    });
+    assert_eq!(
+        diff_json(&left, &right, &HashSet::new()),
+        vec![
+            FieldDifference {
+                path: "result.l1_gas_price".to_owned(),
+                left: None,
+                right: Some(json!("0x1")),
+            },
+            FieldDifference {
+                path: "result.status".to_owned(),
+                left: Some(json!("ACCEPTED_ON_L2")),
+                right: None,
+            },
+            FieldDifference {
+                path: "result.transactions[1].type".to_owned(),
+                left: Some(json!("DECLARE")),
+                right: Some(json!("DEPLOY_ACCOUNT")),
+            },
+            FieldDifference {
+                path: "result.transactions[2]".to_owned(),
+                left: None,
+                right: Some(json!({"type": "INVOKE"})),
+            },
+        ]
+    );
+}
+
+#[test]
+fn ignored_fields_are_not_compared() {
+    let left = json!({"error": {"code": 24, "message": "Block not found"}});
+    let right = json!({"error": {"code": 24, "message": "Invalid block id"}});
+    let ignored_fields = HashSet::from(["message".to_owned()]);
+    assert_eq!(diff_json(&left, &right, &ignored_fields), vec![]);
+}
+
+#[test]
+fn read_requests_from_file() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    let full_request = jsonrpc_request("starknet_getNonce", json!(["latest", "0x1"]));
+    writeln!(file, "{full_request}").unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, r#"{{"method": "starknet_blockNumber"}}"#).unwrap();
+
+    let requests = read_requests(file.path().to_str().unwrap()).unwrap();
+    assert_eq!(requests, vec![full_request, jsonrpc_request("starknet_blockNumber", json!([]))]);
+}
@@ -1,4 +1,11 @@
-use goose::goose::Scenario;
+#[cfg(test)]
+#[path = "scenarios_test.rs"]
+mod scenarios_test;
+
+use std::str::FromStr;
+
+use anyhow::bail;
+use goose::goose::{Scenario, Transaction};
 
 use crate::{
     transactions as txs,
@@ -35,76 +42,155 @@ use crate::{
     TRACE_TRANSACTION_WEIGHT,
 };
 
+/// The kinds of requests the load test sends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestCategory {
+    /// Requests for blocks, transactions, classes and state.
+    Read,
+    /// Requests for events.
+    Events,
+    /// Requests for transaction traces.
+    Trace,
+}
+
+/// The weight of each request category in the load. The weight of a request is its own weight
+/// times the weight of its category, and the requests of a category with weight 0 aren't sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequestMix {
+    pub read: usize,
+    pub events: usize,
+    pub trace: usize,
+}
+
+impl Default for RequestMix {
+    fn default() -> Self {
+        Self { read: 1, events: 1, trace: 1 }
+    }
+}
+
+impl RequestMix {
+    pub fn weight(&self, category: RequestCategory) -> usize {
+        match category {
+            RequestCategory::Read => self.read,
+            RequestCategory::Events => self.events,
+            RequestCategory::Trace => self.trace,
+        }
+    }
+}
+
+/// Parses a comma separated list of category weights, e.g. `read=8,events=1,trace=1`. Categories
+/// that aren't in the list have weight 0.
+impl FromStr for RequestMix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mix = Self { read: 0, events: 0, trace: 0 };
+        for category_weight in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let Some((category, weight)) = category_weight.split_once('=') else {
+                bail!("Expected <category>=<weight>, got {category_weight}.");
+            };
+            let weight = weight.trim().parse::<usize>()?;
+            match category.trim() {
+                "read" => mix.read = weight,
+                "events" => mix.events = weight,
+                "trace" => mix.trace = weight,
+                other => bail!("Unknown request category {other}, expected read, events or trace."),
+            }
+        }
+        if mix == (Self { read: 0, events: 0, trace: 0 }) {
+            bail!("At least one request category must have a positive weight.");
+        }
+        Ok(mix)
+    }
+}
+
 // TODO(dvir): add also traceTransaction, simulateTransactions, estimateFee and call endpoints.
-pub fn general_request_v0_4() -> Scenario {
+pub fn general_request_v0_4(request_mix: RequestMix) -> Scenario {
     let mut scenario = Scenario::new("general_request_v0_4");
     // This is the scenario name to run from the command line.
     // This name must be alphanumeric, so instead of letting Goose do the conversion from the
     // scenario name for us, we give it the name we want.
     scenario.machine_name = "generalrequestv04".to_string();
 
-    let trans_and_weights = vec![
-        (txs::block_hash_and_number(), BLOCK_HASH_AND_NUMBER_WEIGHT),
-        (txs::block_number(), BLOCK_NUMBER_WEIGHT),
-        (txs::chain_id(), CHAIN_ID_WEIGHT),
-        (txs::get_block_transaction_count_by_hash(), GET_BLOCK_TRANSACTION_COUNT_BY_HASH_WEIGHT),
+    for (transaction, weight, category) in transactions_v0_4() {
+        let weight = weight * request_mix.weight(category);
+        if weight > 0 {
+            scenario = scenario.register_transaction(transaction.set_weight(weight).unwrap());
+        }
+    }
+    scenario
+}
+
+// Returns the transactions of the v0_4 requests with their weights and categories.
+fn transactions_v0_4() -> Vec<(Transaction, usize, RequestCategory)> {
+    use RequestCategory::{Events, Read, Trace};
+    vec![
+        (txs::block_hash_and_number(), BLOCK_HASH_AND_NUMBER_WEIGHT, Read),
+        (txs::block_number(), BLOCK_NUMBER_WEIGHT, Read),
+        (txs::chain_id(), CHAIN_ID_WEIGHT, Read),
+        (
+            txs::get_block_transaction_count_by_hash(),
+            GET_BLOCK_TRANSACTION_COUNT_BY_HASH_WEIGHT,
+            Read,
+        ),
         (
             txs::get_block_transaction_count_by_number(),
             GET_BLOCK_TRANSACTION_COUNT_BY_NUMBER_WEIGHT,
+            Read,
         ),
         (
             txs::get_block_with_full_transactions_by_hash(),
             GET_BLOCK_WITH_FULL_TRANSACTIONS_BY_HASH_WEIGHT,
+            Read,
         ),
         (
             txs::get_block_with_full_transactions_by_number(),
             GET_BLOCK_WITH_FULL_TRANSACTIONS_BY_NUMBER_WEIGHT,
+            Read,
         ),
         (
             txs::get_block_with_transaction_hashes_by_hash(),
             GET_BLOCK_WITH_TRANSACTION_HASHES_BY_HASH_WEIGHT,
+            Read,
         ),
         (
             txs::get_block_with_transaction_hashes_by_number(),
             GET_BLOCK_WITH_TRANSACTION_HASHES_BY_NUMBER_WEIGHT,
+            Read,
         ),
-        (txs::get_class_at_by_hash(), GET_CLASS_AT_BY_HASH_WEIGHT),
-        (txs::get_class_at_by_number(), GET_CLASS_AT_BY_NUMBER_WEIGHT),
-        (txs::get_class_by_hash(), GET_CLASS_BY_HASH_WEIGHT),
-        (txs::get_class_by_number(), GET_CLASS_BY_NUMBER_WEIGHT),
-        (txs::get_class_hash_at_by_hash(), GET_CLASS_HASH_AT_BY_HASH_WEIGHT),
-        (txs::get_class_hash_at_by_number(), GET_CLASS_HASH_AT_BY_NUMBER_WEIGHT),
-        (txs::get_events_without_address(), GET_EVENTS_WITHOUT_ADDRESS_WEIGHT),
-        (txs::get_events_with_address(), GET_EVENTS_WITH_ADDRESS_WEIGHT),
-        (txs::get_nonce_by_hash(), GET_NONCE_BY_HASH_WEIGHT),
-        (txs::get_nonce_by_number(), GET_NONCE_BY_NUMBER_WEIGHT),
-        (txs::get_state_update_by_hash(), GET_STATE_UPDATE_BY_HASH_WEIGHT),
-        (txs::get_state_update_by_number(), GET_STATE_UPDATE_BY_NUMBER_WEIGHT),
-        (txs::get_storage_at_by_hash(), GET_STORAGE_AT_BY_HASH_WEIGHT),
-        (txs::get_storage_at_by_number(), GET_STORAGE_AT_BY_NUMBER_WEIGHT),
+        (txs::get_class_at_by_hash(), GET_CLASS_AT_BY_HASH_WEIGHT, Read),
+        (txs::get_class_at_by_number(), GET_CLASS_AT_BY_NUMBER_WEIGHT, Read),
+        (txs::get_class_by_hash(), GET_CLASS_BY_HASH_WEIGHT, Read),
+        (txs::get_class_by_number(), GET_CLASS_BY_NUMBER_WEIGHT, Read),
+        (txs::get_class_hash_at_by_hash(), GET_CLASS_HASH_AT_BY_HASH_WEIGHT, Read),
+        (txs::get_class_hash_at_by_number(), GET_CLASS_HASH_AT_BY_NUMBER_WEIGHT, Read),
+        (txs::get_events_without_address(), GET_EVENTS_WITHOUT_ADDRESS_WEIGHT, Events),
+        (txs::get_events_with_address(), GET_EVENTS_WITH_ADDRESS_WEIGHT, Events),
+        (txs::get_nonce_by_hash(), GET_NONCE_BY_HASH_WEIGHT, Read),
+        (txs::get_nonce_by_number(), GET_NONCE_BY_NUMBER_WEIGHT, Read),
+        (txs::get_state_update_by_hash(), GET_STATE_UPDATE_BY_HASH_WEIGHT, Read),
+        (txs::get_state_update_by_number(), GET_STATE_UPDATE_BY_NUMBER_WEIGHT, Read),
+        (txs::get_storage_at_by_hash(), GET_STORAGE_AT_BY_HASH_WEIGHT, Read),
+        (txs::get_storage_at_by_number(), GET_STORAGE_AT_BY_NUMBER_WEIGHT, Read),
         (
             txs::get_transaction_by_block_id_and_index_by_hash(),
             GET_TRANSACTION_BY_BLOCK_ID_AND_INDEX_BY_HASH_WEIGHT,
+            Read,
         ),
         (
             txs::get_transaction_by_block_id_and_index_by_number(),
             GET_TRANSACTION_BY_BLOCK_ID_AND_INDEX_BY_NUMBER_WEIGHT,
+            Read,
         ),
-        (txs::get_transaction_by_hash(), GET_TRANSACTION_BY_HASH_WEIGHT),
-        (txs::get_transaction_receipt(), GET_TRANSACTION_RECEIPT_WEIGHT),
-        (txs::syncing(), SYNCING_WEIGHT),
-    ];
-    for (transaction, weight) in trans_and_weights.into_iter() {
-        scenario = scenario.register_transaction(transaction.set_weight(weight).unwrap());
-    }
-
-    let new_trans_and_weights = vec![
-        (txs::trace_block_transactions_by_hash(), TRACE_BLOCK_TRANSACTIONS_BY_HASH_WEIGHT),
-        (txs::trace_block_transactions_by_number(), TRACE_BLOCK_TRANSACTIONS_BY_NUMBER_WEIGHT),
-        (txs::trace_transaction(), TRACE_TRANSACTION_WEIGHT),
-    ];
-    for (transaction, weight) in new_trans_and_weights.into_iter() {
-        scenario = scenario.register_transaction(transaction.set_weight(weight).unwrap());
-    }
-    scenario
+        (txs::get_transaction_by_hash(), GET_TRANSACTION_BY_HASH_WEIGHT, Read),
+        (txs::get_transaction_receipt(), GET_TRANSACTION_RECEIPT_WEIGHT, Read),
+        (txs::syncing(), SYNCING_WEIGHT, Read),
+        (txs::trace_block_transactions_by_hash(), TRACE_BLOCK_TRANSACTIONS_BY_HASH_WEIGHT, Trace),
+        (
+            txs::trace_block_transactions_by_number(),
+            TRACE_BLOCK_TRANSACTIONS_BY_NUMBER_WEIGHT,
+            Trace,
+        ),
+        (txs::trace_transaction(), TRACE_TRANSACTION_WEIGHT, Trace),
+    ]
 }
@@ -0,0 +1,23 @@
+use pretty_assertions::assert_eq;
+
+use super::{RequestCategory, RequestMix};
+
+#[test]
+fn parse_request_mix() {
+    assert_eq!(
+        "read=8, events=1,trace=2".parse::<RequestMix>().unwrap(),
+        RequestMix { read: 8, events: 1, trace: 2 }
+    );
+    // Categories that aren't in the list aren't requested.
+    let mix = "trace=1".parse::<RequestMix>().unwrap();
+    assert_eq!(mix, RequestMix { read: 0, events: 0, trace: 1 });
+    assert_eq!(mix.weight(RequestCategory::Read), 0);
+    assert_eq!(mix.weight(RequestCategory::Trace), 1);
+}
+
+#[test]
+fn parse_invalid_request_mix() {
+    for invalid_mix in ["", "read=0", "read", "read=-1", "writes=1"] {
+        assert!(invalid_mix.parse::<RequestMix>().is_err(), "{invalid_mix} was parsed.");
+    }
+}
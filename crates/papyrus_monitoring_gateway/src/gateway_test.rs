@@ -1,21 +1,32 @@
 use std::net::{SocketAddr, TcpListener};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use axum::body::Body;
-use axum::http::{Request, StatusCode};
+use axum::http::{Method, Request, StatusCode};
 use axum::response::Response;
 use axum::Router;
 use http_body::combinators::UnsyncBoxBody;
 use metrics::{absolute_counter, describe_counter, register_counter};
 use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_storage::header::HeaderStorageWriter;
 use papyrus_storage::{table_names, test_utils};
 use pretty_assertions::assert_eq;
 use serde_json::{json, Value};
+use starknet_api::block::{BlockHeader, BlockNumber};
 use starknet_client::reader::MockStarknetReader;
 use starknet_client::writer::MockStarknetWriter;
 use tower::ServiceExt;
 
-use crate::{app, is_ready, MONITORING_PREFIX};
+use crate::{
+    app,
+    is_ready,
+    GetPeers,
+    SetLogFilter,
+    SyncStatus,
+    MONITORING_PREFIX,
+    STORAGE_TABLE_ENTRIES,
+    TABLE_LABEL,
+};
 
 const TEST_CONFIG_PRESENTATION: &str = "full_general_config_presentation";
 const PUBLIC_TEST_CONFIG_PRESENTATION: &str = "public_general_config_presentation";
@@ -33,6 +44,8 @@ fn setup_app() -> Router {
         serde_json::to_value(PUBLIC_TEST_CONFIG_PRESENTATION).unwrap(),
         SECRET.to_string(),
         None,
+        None,
+        None,
     )
 }
 
@@ -116,6 +129,140 @@ async fn alive() {
     assert_eq!(response.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn log_filter() {
+    // The log filter is valid if it isn't empty.
+    let log_filter = Arc::new(Mutex::new(None));
+    let set_log_filter: SetLogFilter = {
+        let log_filter = log_filter.clone();
+        Arc::new(move |filter: &str| {
+            if filter.is_empty() {
+                return Err("Empty filter.".to_owned());
+            }
+            *log_filter.lock().unwrap() = Some(filter.to_owned());
+            Ok(())
+        })
+    };
+    let ((storage_reader, _), _temp_dir) = test_utils::get_test_storage();
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::Value::default(),
+        serde_json::Value::default(),
+        SECRET.to_string(),
+        None,
+        Some(set_log_filter),
+        None,
+    );
+    let request_log_filter = |secret: &str, filter: &str| {
+        app.clone().oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/{MONITORING_PREFIX}/logFilter/{secret}"))
+                .body(Body::from(filter.to_owned()))
+                .unwrap(),
+        )
+    };
+
+    let response = request_log_filter("zzz", "papyrus_sync=debug").await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = request_log_filter(SECRET, "").await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(*log_filter.lock().unwrap(), None);
+
+    let response = request_log_filter(SECRET, "papyrus_sync=debug").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(*log_filter.lock().unwrap(), Some("papyrus_sync=debug".to_owned()));
+}
+
+#[tokio::test]
+async fn log_filter_without_setter() {
+    let app = setup_app();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/{MONITORING_PREFIX}/logFilter/{SECRET}"))
+                .body(Body::from("info"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn sync_status() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = test_utils::get_test_storage();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::Value::default(),
+        serde_json::Value::default(),
+        SECRET.to_string(),
+        None,
+        None,
+        None,
+    );
+    let response = request_app(app, "syncStatus").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let sync_status: SyncStatus = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        sync_status,
+        SyncStatus {
+            header_marker: BlockNumber(1),
+            body_marker: BlockNumber(0),
+            state_marker: BlockNumber(0),
+            compiled_class_marker: BlockNumber(0),
+            base_layer_marker: BlockNumber(0),
+        }
+    );
+}
+
+#[tokio::test]
+async fn peers() {
+    let get_peers: GetPeers = Arc::new(|| vec!["peer_1".to_owned(), "peer_2".to_owned()]);
+    let ((storage_reader, _), _temp_dir) = test_utils::get_test_storage();
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::Value::default(),
+        serde_json::Value::default(),
+        SECRET.to_string(),
+        None,
+        None,
+        Some(get_peers),
+    );
+    let response = request_app(app, "peers").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!(["peer_1", "peer_2"]));
+}
+
+#[tokio::test]
+async fn peers_without_network() {
+    let app = setup_app();
+    let response = request_app(app, "peers").await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
 #[tokio::test]
 async fn ready() {
     let mut gateway_client_mock = MockStarknetWriter::new();
@@ -152,6 +299,8 @@ async fn with_metrics() {
         serde_json::Value::default(),
         String::new(),
         Some(prometheus_handle),
+        None,
+        None,
     );
 
     // Register a metric.
@@ -173,6 +322,10 @@ async fn with_metrics() {
          {metric_value}\n\n"
     );
     assert!(body_string.starts_with(&expected_prefix));
+    // The storage metrics are recorded when the metrics are requested.
+    assert!(
+        body_string.contains(&format!("{STORAGE_TABLE_ENTRIES}{{{TABLE_LABEL}=\"headers\"}} 0"))
+    );
 }
 
 #[tokio::test]
@@ -14,26 +14,50 @@ use std::sync::Arc;
 use axum::extract::Path;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router};
+use metrics::gauge;
 use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
 use metrics_process::Collector;
 use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
 use papyrus_config::dumping::{ser_generated_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
+use papyrus_storage::base_layer::BaseLayerStorageReader;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::compiled_class::CasmStorageReader;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{DbStats, StorageError, StorageReader};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
-use starknet_client::reader::{StarknetFeederGatewayClient, StarknetReader};
+use starknet_api::block::BlockNumber;
+use starknet_client::reader::{
+    StarknetFeederGatewayClient,
+    StarknetFeederGatewayClientConfig,
+    StarknetReader,
+};
 use starknet_client::writer::{StarknetGatewayClient, StarknetWriter};
 use starknet_client::RetryConfig;
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
 use validator::Validate;
 
 const MONITORING_PREFIX: &str = "monitoring";
 const PROCESS_METRICS_PREFIX: &str = "papyrus_";
 
+// Name of the storage metrics, which are recorded when the metrics are requested.
+const STORAGE_SIZE: &str = "storage_size_bytes";
+const STORAGE_TABLE_SIZE: &str = "storage_table_size_bytes";
+const STORAGE_TABLE_ENTRIES: &str = "storage_table_entries";
+const TABLE_LABEL: &str = "table";
+
+/// Sets the filter of the logs of the node, given in the syntax of the `RUST_LOG` environment
+/// variable. Returns a description of the error if the filter is invalid.
+pub type SetLogFilter = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Returns the ids of the peers that the node is connected to.
+pub type GetPeers = Arc<dyn Fn() -> Vec<String> + Send + Sync>;
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Validate)]
 pub struct MonitoringGatewayConfig {
     pub server_address: String,
@@ -118,6 +142,8 @@ pub struct MonitoringServer {
     storage_reader: StorageReader,
     version: &'static str,
     prometheus_handle: Option<PrometheusHandle>,
+    set_log_filter: Option<SetLogFilter>,
+    get_peers: Option<GetPeers>,
 }
 
 impl MonitoringServer {
@@ -127,6 +153,8 @@ impl MonitoringServer {
         public_general_config_presentation: serde_json::Value,
         storage_reader: StorageReader,
         version: &'static str,
+        set_log_filter: Option<SetLogFilter>,
+        get_peers: Option<GetPeers>,
     ) -> Result<Self, BuildError> {
         let prometheus_handle = if config.collect_metrics {
             let mut builder = PrometheusBuilder::new();
@@ -146,6 +174,8 @@ impl MonitoringServer {
             public_general_config_presentation,
             version,
             prometheus_handle,
+            set_log_filter,
+            get_peers,
         })
     }
 
@@ -174,12 +204,15 @@ impl MonitoringServer {
             self.public_general_config_presentation.clone(),
             self.config.present_full_config_secret.clone(),
             self.prometheus_handle.clone(),
+            self.set_log_filter.clone(),
+            self.get_peers.clone(),
         );
         debug!("Starting monitoring gateway.");
         axum::Server::bind(&server_address).serve(app.into_make_service()).await
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn app(
     starknet_url: String,
     storage_reader: StorageReader,
@@ -188,23 +221,38 @@ fn app(
     public_general_config_presentation: serde_json::Value,
     present_full_config_secret: String,
     prometheus_handle: Option<PrometheusHandle>,
+    set_log_filter: Option<SetLogFilter>,
+    get_peers: Option<GetPeers>,
 ) -> Router {
-    let is_ready_retry_config =
-        RetryConfig { retry_base_millis: 50, retry_max_delay_millis: 1000, max_retries: 0 };
+    let is_ready_retry_config = RetryConfig {
+        retry_base_millis: 50,
+        backoff_multiplier: 50,
+        retry_max_delay_millis: 1000,
+        max_retries: 0,
+    };
     let starknet_feeder_client = Arc::new(
         StarknetFeederGatewayClient::new(
             starknet_url.as_str(),
-            None,
+            StarknetFeederGatewayClientConfig::default(),
             version,
             is_ready_retry_config,
         )
         .expect("Failed creating Starknet feeder client."),
     );
     let starknet_client = Arc::new(
-        StarknetGatewayClient::new(starknet_url.as_str(), version, is_ready_retry_config)
-            .expect("Failed creating Starknet client."),
+        StarknetGatewayClient::new(
+            starknet_url.as_str(),
+            None,
+            None,
+            version,
+            is_ready_retry_config,
+        )
+        .expect("Failed creating Starknet client."),
     );
 
+    let metrics_storage_reader = storage_reader.clone();
+    let sync_status_storage_reader = storage_reader.clone();
+    let log_filter_secret = present_full_config_secret.clone();
     Router::new()
         .route(
             format!("/{MONITORING_PREFIX}/dbTablesStats").as_str(),
@@ -225,6 +273,13 @@ fn app(
                 )
             }),
         )
+        .route(
+            // The "*secret" captures the end of the path and stores it in "secret".
+            format!("/{MONITORING_PREFIX}/logFilter/*secret").as_str(),
+            post(move |secret, filter| {
+                log_filter_by_secret(set_log_filter, secret, log_filter_secret, filter)
+            }),
+        )
         .route(
             format!("/{MONITORING_PREFIX}/nodeVersion").as_str(),
             get(move || node_version(version)),
@@ -233,9 +288,14 @@ fn app(
             format!("/{MONITORING_PREFIX}/alive").as_str(),
             get(move || async { StatusCode::OK.to_string() }),
         )
+        .route(
+            format!("/{MONITORING_PREFIX}/syncStatus").as_str(),
+            get(move || sync_status(sync_status_storage_reader)),
+        )
+        .route(format!("/{MONITORING_PREFIX}/peers").as_str(), get(move || peers(get_peers)))
         .route(
             format!("/{MONITORING_PREFIX}/metrics").as_str(),
-            get(move || metrics(prometheus_handle)),
+            get(move || metrics(prometheus_handle, metrics_storage_reader)),
         )
         .route(
             format!("/{MONITORING_PREFIX}/ready").as_str(),
@@ -262,6 +322,41 @@ async fn db_tables_stats(storage_reader: StorageReader) -> Result<Json<DbStats>,
     Ok(storage_reader.db_tables_stats()?.into())
 }
 
+/// The sync progress of the node: the first block that wasn't stored yet, for each part of the
+/// blocks.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct SyncStatus {
+    header_marker: BlockNumber,
+    body_marker: BlockNumber,
+    state_marker: BlockNumber,
+    compiled_class_marker: BlockNumber,
+    base_layer_marker: BlockNumber,
+}
+
+/// Returns the sync status of the node.
+#[instrument(skip(storage_reader), level = "debug", ret)]
+async fn sync_status(storage_reader: StorageReader) -> Result<Json<SyncStatus>, ServerError> {
+    let txn = storage_reader.begin_ro_txn()?;
+    Ok(SyncStatus {
+        header_marker: txn.get_header_marker()?,
+        body_marker: txn.get_body_marker()?,
+        state_marker: txn.get_state_marker()?,
+        compiled_class_marker: txn.get_compiled_class_marker()?,
+        base_layer_marker: txn.get_base_layer_block_marker()?,
+    }
+    .into())
+}
+
+/// Returns the ids of the peers that the node is connected to.
+/// In case the node doesn't run the P2P network returns status code 405: method not allowed.
+#[instrument(level = "debug", ret, skip(get_peers))]
+async fn peers(get_peers: Option<GetPeers>) -> Result<Json<Vec<String>>, StatusCode> {
+    match get_peers {
+        Some(get_peers) => Ok(get_peers().into()),
+        None => Err(StatusCode::METHOD_NOT_ALLOWED),
+    }
+}
+
 /// Returns the node config.
 #[instrument(level = "debug", ret)]
 async fn node_config(
@@ -284,20 +379,59 @@ async fn node_config_by_secret(
     }
 }
 
+/// Sets the filter of the node logs to the request body, in the syntax of `RUST_LOG`.
+/// In case the node can't change its log filter returns status code 405: method not allowed.
+#[instrument(level = "debug", ret, skip(set_log_filter, expected_secret))]
+async fn log_filter_by_secret(
+    set_log_filter: Option<SetLogFilter>,
+    given_secret: Path<String>,
+    expected_secret: String,
+    filter: String,
+) -> Result<String, (StatusCode, String)> {
+    if given_secret.to_string() != expected_secret {
+        return Err((StatusCode::FORBIDDEN, String::new()));
+    }
+    let Some(set_log_filter) = set_log_filter else {
+        return Err((StatusCode::METHOD_NOT_ALLOWED, String::new()));
+    };
+    set_log_filter(&filter).map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    Ok(StatusCode::OK.to_string())
+}
+
 /// Returns prometheus metrics.
 /// In case the node doesn’t collect metrics returns an empty response with status code 405: method
 /// not allowed.
-#[instrument(level = "debug", ret, skip(prometheus_handle))]
-async fn metrics(prometheus_handle: Option<PrometheusHandle>) -> Response {
+#[instrument(level = "debug", ret, skip(prometheus_handle, storage_reader))]
+async fn metrics(
+    prometheus_handle: Option<PrometheusHandle>,
+    storage_reader: StorageReader,
+) -> Response {
     match prometheus_handle {
         Some(handle) => {
             Collector::default().prefix(PROCESS_METRICS_PREFIX).collect();
+            record_storage_metrics(&storage_reader);
             handle.render().into_response()
         }
         None => StatusCode::METHOD_NOT_ALLOWED.into_response(),
     }
 }
 
+// Records the size of the storage and of each of its tables.
+fn record_storage_metrics(storage_reader: &StorageReader) {
+    let db_stats = match storage_reader.db_tables_stats() {
+        Ok(db_stats) => db_stats,
+        Err(err) => {
+            warn!("Failed to read the storage stats for the metrics: {err}.");
+            return;
+        }
+    };
+    gauge!(STORAGE_SIZE, db_stats.db_stats.total_size as f64);
+    for (table, table_stats) in db_stats.tables_stats {
+        gauge!(STORAGE_TABLE_SIZE, table_stats.total_size as f64, TABLE_LABEL => table.clone());
+        gauge!(STORAGE_TABLE_ENTRIES, table_stats.entries as f64, TABLE_LABEL => table);
+    }
+}
+
 /// Returns the node version.
 #[instrument(level = "debug", ret)]
 async fn node_version(version: &'static str) -> String {
@@ -0,0 +1,14 @@
+#![no_main]
+//! Reads length prefixed protobuf messages from arbitrary data sent by a peer.
+//! Run from the papyrus_network directory with `cargo +nightly fuzz run read_message`.
+
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use papyrus_network::messages::{protobuf, read_message};
+
+fuzz_target!(|data: &[u8]| {
+    block_on(async {
+        let _ = read_message::<protobuf::BlockHeadersRequest, _>(data).await;
+        let _ = read_message::<protobuf::BlockHeadersResponse, _>(data).await;
+    });
+});
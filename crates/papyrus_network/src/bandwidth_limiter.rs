@@ -0,0 +1,31 @@
+//! Limits the rate in which the node sends data to its peers, so that peers can't exhaust the
+//! bandwidth of the node by querying it.
+#[cfg(test)]
+#[path = "bandwidth_limiter_test.rs"]
+mod bandwidth_limiter_test;
+
+use std::num::NonZeroU64;
+use std::time::{Duration, Instant};
+
+/// Spreads the sent data over time so that on average, no more than the configured amount of bytes
+/// is sent every second.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    bytes_per_second: NonZeroU64,
+    next_send_time: Instant,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_second: NonZeroU64) -> Self {
+        Self { bytes_per_second, next_send_time: Instant::now() }
+    }
+
+    /// Reserves the bandwidth for sending the given amount of bytes, and returns the time from
+    /// which they can be sent. The bandwidth that wasn't used while idle isn't saved for later.
+    pub fn reserve(&mut self, num_bytes: u64) -> Instant {
+        let send_time = self.next_send_time.max(Instant::now());
+        self.next_send_time = send_time
+            + Duration::from_secs_f64(num_bytes as f64 / self.bytes_per_second.get() as f64);
+        send_time
+    }
+}
@@ -0,0 +1,30 @@
+use std::num::NonZeroU64;
+use std::time::{Duration, Instant};
+
+use super::BandwidthLimiter;
+
+const BYTES_PER_SECOND: u64 = 1000;
+
+#[test]
+fn data_is_spread_over_time() {
+    let mut bandwidth_limiter = BandwidthLimiter::new(NonZeroU64::new(BYTES_PER_SECOND).unwrap());
+    let start_time = Instant::now();
+
+    // The first data is sent immediately, and each of the following waits for the previous one.
+    let first_send_time = bandwidth_limiter.reserve(BYTES_PER_SECOND);
+    assert!(first_send_time <= Instant::now());
+    let second_send_time = bandwidth_limiter.reserve(BYTES_PER_SECOND / 2);
+    assert!(second_send_time >= start_time + Duration::from_secs(1));
+    let third_send_time = bandwidth_limiter.reserve(BYTES_PER_SECOND);
+    assert_eq!(third_send_time - second_send_time, Duration::from_millis(500));
+}
+
+#[test]
+fn idle_time_is_not_saved() {
+    let mut bandwidth_limiter = BandwidthLimiter::new(NonZeroU64::new(BYTES_PER_SECOND).unwrap());
+    std::thread::sleep(Duration::from_millis(10));
+
+    let first_send_time = bandwidth_limiter.reserve(BYTES_PER_SECOND);
+    let second_send_time = bandwidth_limiter.reserve(0);
+    assert_eq!(second_send_time - first_send_time, Duration::from_secs(1));
+}
@@ -0,0 +1,665 @@
+//! Conversions between the protobuf messages of the network and the types of starknet_api.
+#[cfg(test)]
+#[path = "converters_test.rs"]
+mod converters_test;
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use papyrus_common::block_hash::BlockCommitments;
+use papyrus_common::pending_classes::ApiContractClass;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockTimestamp};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    GlobalRoot,
+    Nonce,
+    PatriciaKey,
+    SequencerContractAddress,
+};
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass,
+    EntryPoint as DeprecatedEntryPoint,
+    EntryPointOffset,
+    EntryPointType as DeprecatedEntryPointType,
+};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{
+    ContractClass,
+    EntryPoint,
+    EntryPointType,
+    FunctionIndex,
+    StorageKey,
+    ThinStateDiff,
+};
+use starknet_api::StarknetApiError;
+
+use crate::messages::protobuf;
+use crate::{BlockID, BlockQuery, ClassesQuery, Direction, InvalidBlockQuery, InvalidClassesQuery};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProtobufConversionError {
+    #[error("The {field_description} field is missing from the message.")]
+    MissingField { field_description: &'static str },
+    #[error("Expected {num_expected} bytes for {type_description}, got {num_received}.")]
+    BytesDataLengthMismatch {
+        type_description: &'static str,
+        num_expected: usize,
+        num_received: usize,
+    },
+    #[error("The value of the {type_description} is out of range: {error}.")]
+    OutOfRangeValue { type_description: &'static str, error: StarknetApiError },
+    #[error("{value} isn't a valid value of {type_description}.")]
+    UnknownEnumValue { type_description: &'static str, value: i32 },
+    #[error("Failed to deserialize the {type_description}: {error}.")]
+    Deserialization { type_description: &'static str, error: String },
+    #[error(transparent)]
+    InvalidQuery(#[from] InvalidBlockQuery),
+    #[error(transparent)]
+    InvalidClassesQuery(#[from] InvalidClassesQuery),
+}
+
+fn bytes_to_stark_hash(
+    bytes: Vec<u8>,
+    type_description: &'static str,
+) -> Result<StarkHash, ProtobufConversionError> {
+    let num_received = bytes.len();
+    let bytes: [u8; 32] =
+        bytes.try_into().map_err(|_| ProtobufConversionError::BytesDataLengthMismatch {
+            type_description,
+            num_expected: 32,
+            num_received,
+        })?;
+    StarkHash::new(bytes)
+        .map_err(|error| ProtobufConversionError::OutOfRangeValue { type_description, error })
+}
+
+impl TryFrom<protobuf::Hash> for StarkHash {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Hash) -> Result<Self, Self::Error> {
+        bytes_to_stark_hash(value.elements, "hash")
+    }
+}
+
+impl From<StarkHash> for protobuf::Hash {
+    fn from(value: StarkHash) -> Self {
+        Self { elements: value.bytes().to_vec() }
+    }
+}
+
+impl TryFrom<protobuf::Felt252> for StarkFelt {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Felt252) -> Result<Self, Self::Error> {
+        bytes_to_stark_hash(value.elements, "felt")
+    }
+}
+
+impl From<StarkFelt> for protobuf::Felt252 {
+    fn from(value: StarkFelt) -> Self {
+        Self { elements: value.bytes().to_vec() }
+    }
+}
+
+impl TryFrom<protobuf::Address> for ContractAddress {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Address) -> Result<Self, Self::Error> {
+        let type_description = "address";
+        ContractAddress::try_from(bytes_to_stark_hash(value.elements, type_description)?)
+            .map_err(|error| ProtobufConversionError::OutOfRangeValue { type_description, error })
+    }
+}
+
+impl From<ContractAddress> for protobuf::Address {
+    fn from(value: ContractAddress) -> Self {
+        Self { elements: value.0.key().bytes().to_vec() }
+    }
+}
+
+/// The block hash isn't part of the header message, so it's left as the default. It's sent in the
+/// signatures message that follows the header.
+impl TryFrom<protobuf::BlockHeader> for BlockHeader {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::BlockHeader) -> Result<Self, Self::Error> {
+        let parent_hash = BlockHash(
+            value
+                .parent_header
+                .ok_or(ProtobufConversionError::MissingField {
+                    field_description: "BlockHeader::parent_header",
+                })?
+                .try_into()?,
+        );
+        let timestamp = value
+            .time
+            .ok_or(ProtobufConversionError::MissingField {
+                field_description: "BlockHeader::time",
+            })?
+            .seconds;
+        let sequencer = SequencerContractAddress(
+            value
+                .sequencer_address
+                .ok_or(ProtobufConversionError::MissingField {
+                    field_description: "BlockHeader::sequencer_address",
+                })?
+                .try_into()?,
+        );
+        let state_root = GlobalRoot(
+            value
+                .state
+                .and_then(|state| state.root)
+                .ok_or(ProtobufConversionError::MissingField {
+                    field_description: "BlockHeader::state",
+                })?
+                .try_into()?,
+        );
+        Ok(BlockHeader {
+            parent_hash,
+            block_number: BlockNumber(value.number),
+            // Timestamps before the epoch aren't valid, so they're treated as the epoch.
+            timestamp: BlockTimestamp(u64::try_from(timestamp).unwrap_or_default()),
+            sequencer,
+            state_root,
+            ..Default::default()
+        })
+    }
+}
+
+impl From<BlockHeader> for protobuf::BlockHeader {
+    fn from(value: BlockHeader) -> Self {
+        Self {
+            parent_header: Some(value.parent_hash.0.into()),
+            number: value.block_number.0,
+            time: Some(prost_types::Timestamp {
+                // Timestamps that don't fit are capped, since they're far in the future anyway.
+                seconds: i64::try_from(value.timestamp.0).unwrap_or(i64::MAX),
+                nanos: 0,
+            }),
+            sequencer_address: Some(value.sequencer.0.into()),
+            // The height of the global state trie.
+            state: Some(protobuf::Patricia { height: 251, root: Some(value.state_root.0.into()) }),
+            ..Default::default()
+        }
+    }
+}
+
+/// The header message of a block also holds the commitments to its body, so that the hash of the
+/// block can be validated without the body.
+impl From<(BlockHeader, BlockCommitments)> for protobuf::BlockHeader {
+    fn from((header, commitments): (BlockHeader, BlockCommitments)) -> Self {
+        Self {
+            transactions: Some(protobuf::Merkle {
+                n_leaves: u32::try_from(commitments.n_transactions)
+                    .expect("The number of transactions in a block should fit in 32 bits."),
+                root: Some(commitments.transactions_root.into()),
+            }),
+            events: Some(protobuf::Merkle {
+                n_leaves: u32::try_from(commitments.n_events)
+                    .expect("The number of events in a block should fit in 32 bits."),
+                root: Some(commitments.events_root.into()),
+            }),
+            ..header.into()
+        }
+    }
+}
+
+impl TryFrom<&protobuf::BlockHeader> for BlockCommitments {
+    type Error = ProtobufConversionError;
+    fn try_from(value: &protobuf::BlockHeader) -> Result<Self, Self::Error> {
+        let transactions =
+            value.transactions.clone().ok_or(ProtobufConversionError::MissingField {
+                field_description: "BlockHeader::transactions",
+            })?;
+        let events = value.events.clone().ok_or(ProtobufConversionError::MissingField {
+            field_description: "BlockHeader::events",
+        })?;
+        Ok(BlockCommitments {
+            n_transactions: transactions.n_leaves as usize,
+            transactions_root: transactions
+                .root
+                .ok_or(ProtobufConversionError::MissingField { field_description: "Merkle::root" })?
+                .try_into()?,
+            n_events: events.n_leaves as usize,
+            events_root: events
+                .root
+                .ok_or(ProtobufConversionError::MissingField { field_description: "Merkle::root" })?
+                .try_into()?,
+        })
+    }
+}
+
+// The iterations of all the queries are converted the same way, so the requests differ only in the
+// description of their iteration field.
+fn block_query_from_iteration(
+    iteration: Option<protobuf::Iteration>,
+    field_description: &'static str,
+) -> Result<BlockQuery, ProtobufConversionError> {
+    let iteration = iteration.ok_or(ProtobufConversionError::MissingField { field_description })?;
+    let start = match iteration
+        .start
+        .ok_or(ProtobufConversionError::MissingField { field_description: "Iteration::start" })?
+    {
+        protobuf::iteration::Start::BlockNumber(block_number) => {
+            BlockID::Number(BlockNumber(block_number))
+        }
+        protobuf::iteration::Start::Header(block_hash) => {
+            BlockID::Hash(BlockHash(block_hash.try_into()?))
+        }
+    };
+    let direction =
+        match protobuf::iteration::Direction::try_from(iteration.direction).map_err(|_| {
+            ProtobufConversionError::UnknownEnumValue {
+                type_description: "Iteration::Direction",
+                value: iteration.direction,
+            }
+        })? {
+            protobuf::iteration::Direction::Forward => Direction::Forward,
+            protobuf::iteration::Direction::Backward => Direction::Backward,
+        };
+    let query =
+        BlockQuery { start, direction, limit: iteration.limit, skip: 0, step: iteration.step };
+    query.validate()?;
+    Ok(query)
+}
+
+impl TryFrom<BlockQuery> for protobuf::Iteration {
+    type Error = InvalidBlockQuery;
+    fn try_from(value: BlockQuery) -> Result<Self, Self::Error> {
+        value.validate()?;
+        // The iteration has no skip, so the skipped blocks are folded into its start.
+        let start = match value.start {
+            BlockID::Hash(block_hash) => {
+                // TODO(shahak): Support skipping blocks in queries that start from a block hash.
+                if value.skip != 0 {
+                    return Err(InvalidBlockQuery::SkipFromBlockHash);
+                }
+                protobuf::iteration::Start::Header(protobuf::Hash::from(block_hash.0))
+            }
+            BlockID::Number(BlockNumber(block_number)) => protobuf::iteration::Start::BlockNumber(
+                match value.direction {
+                    Direction::Forward => block_number.checked_add(value.skip),
+                    Direction::Backward => block_number.checked_sub(value.skip),
+                }
+                .ok_or(InvalidBlockQuery::BlockNumberOutOfRange)?,
+            ),
+        };
+        let direction = match value.direction {
+            Direction::Forward => protobuf::iteration::Direction::Forward,
+            Direction::Backward => protobuf::iteration::Direction::Backward,
+        };
+        Ok(Self {
+            start: Some(start),
+            direction: direction.into(),
+            limit: value.limit,
+            step: value.step,
+        })
+    }
+}
+
+impl TryFrom<protobuf::BlockHeadersRequest> for BlockQuery {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::BlockHeadersRequest) -> Result<Self, Self::Error> {
+        block_query_from_iteration(value.iteration, "BlockHeadersRequest::iteration")
+    }
+}
+
+impl TryFrom<BlockQuery> for protobuf::BlockHeadersRequest {
+    type Error = InvalidBlockQuery;
+    fn try_from(value: BlockQuery) -> Result<Self, Self::Error> {
+        Ok(Self { iteration: Some(value.try_into()?) })
+    }
+}
+
+impl TryFrom<protobuf::StateDiffsRequest> for BlockQuery {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::StateDiffsRequest) -> Result<Self, Self::Error> {
+        block_query_from_iteration(value.iteration, "StateDiffsRequest::iteration")
+    }
+}
+
+impl TryFrom<BlockQuery> for protobuf::StateDiffsRequest {
+    type Error = InvalidBlockQuery;
+    fn try_from(value: BlockQuery) -> Result<Self, Self::Error> {
+        Ok(Self { iteration: Some(value.try_into()?) })
+    }
+}
+
+/// The state diff message holds the hashes of the declared classes, and their definitions are sent
+/// by the classes protocol. Since the message doesn't tell a deployed contract apart from a
+/// contract whose class was replaced, every class hash change is treated as a deployment, and it's
+/// up to the receiver to tell them apart according to its state.
+impl TryFrom<protobuf::StateDiff> for ThinStateDiff {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::StateDiff) -> Result<Self, Self::Error> {
+        let mut state_diff = ThinStateDiff::default();
+        for contract_diff in value.contract_diffs {
+            let address = ContractAddress::try_from(contract_diff.address.ok_or(
+                ProtobufConversionError::MissingField {
+                    field_description: "ContractDiff::address",
+                },
+            )?)?;
+            if let Some(nonce) = contract_diff.nonce {
+                state_diff.nonces.insert(address, Nonce(nonce.try_into()?));
+            }
+            if let Some(class_hash) = contract_diff.class_hash {
+                state_diff.deployed_contracts.insert(address, ClassHash(class_hash.try_into()?));
+            }
+            if contract_diff.values.is_empty() {
+                continue;
+            }
+            let storage_diff = state_diff.storage_diffs.entry(address).or_default();
+            for stored_value in contract_diff.values {
+                let key = stored_value.key.ok_or(ProtobufConversionError::MissingField {
+                    field_description: "ContractStoredValue::key",
+                })?;
+                let value = stored_value.value.ok_or(ProtobufConversionError::MissingField {
+                    field_description: "ContractStoredValue::value",
+                })?;
+                let type_description = "storage key";
+                let key = StorageKey(PatriciaKey::try_from(StarkFelt::try_from(key)?).map_err(
+                    |error| ProtobufConversionError::OutOfRangeValue { type_description, error },
+                )?);
+                storage_diff.insert(key, value.try_into()?);
+            }
+        }
+        for declared_class in value.declared_classes {
+            let class_hash =
+                declared_class.class_hash.ok_or(ProtobufConversionError::MissingField {
+                    field_description: "DeclaredClass::class_hash",
+                })?;
+            let compiled_class_hash = declared_class.compiled_class_hash.ok_or(
+                ProtobufConversionError::MissingField {
+                    field_description: "DeclaredClass::compiled_class_hash",
+                },
+            )?;
+            state_diff.declared_classes.insert(
+                ClassHash(class_hash.try_into()?),
+                CompiledClassHash(compiled_class_hash.try_into()?),
+            );
+        }
+        for class_hash in value.deprecated_declared_classes {
+            state_diff.deprecated_declared_classes.push(ClassHash(class_hash.try_into()?));
+        }
+        Ok(state_diff)
+    }
+}
+
+/// The definitions of the declared classes aren't part of the message. See the conversion from the
+/// message.
+impl From<ThinStateDiff> for protobuf::StateDiff {
+    fn from(value: ThinStateDiff) -> Self {
+        // The changes of each contract are gathered into a single contract diff, ordered by the
+        // first change of the contract.
+        let mut contract_diffs = IndexMap::new();
+        fn contract_diff(
+            contract_diffs: &mut IndexMap<ContractAddress, protobuf::state_diff::ContractDiff>,
+            address: ContractAddress,
+        ) -> &mut protobuf::state_diff::ContractDiff {
+            contract_diffs.entry(address).or_insert_with(|| protobuf::state_diff::ContractDiff {
+                address: Some(address.into()),
+                ..Default::default()
+            })
+        }
+        let class_hashes = value.deployed_contracts.into_iter().chain(value.replaced_classes);
+        for (address, class_hash) in class_hashes {
+            contract_diff(&mut contract_diffs, address).class_hash = Some(class_hash.0.into());
+        }
+        for (address, nonce) in value.nonces {
+            contract_diff(&mut contract_diffs, address).nonce = Some(nonce.0.into());
+        }
+        for (address, storage_diff) in value.storage_diffs {
+            contract_diff(&mut contract_diffs, address).values.extend(
+                storage_diff.into_iter().map(|(key, value)| protobuf::ContractStoredValue {
+                    key: Some((*key.0.key()).into()),
+                    value: Some(value.into()),
+                }),
+            );
+        }
+        let declared_classes = value
+            .declared_classes
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| protobuf::state_diff::DeclaredClass {
+                class_hash: Some(class_hash.0.into()),
+                compiled_class_hash: Some(compiled_class_hash.0.into()),
+            })
+            .collect();
+        let deprecated_declared_classes = value
+            .deprecated_declared_classes
+            .into_iter()
+            .map(|class_hash| class_hash.0.into())
+            .collect();
+        Self {
+            domain: 0,
+            contract_diffs: contract_diffs.into_values().collect(),
+            declared_classes,
+            deprecated_declared_classes,
+        }
+    }
+}
+
+impl TryFrom<protobuf::ClassesRequest> for ClassesQuery {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::ClassesRequest) -> Result<Self, Self::Error> {
+        let class_hashes = value
+            .class_hashes
+            .into_iter()
+            .map(|class_hash| Ok(ClassHash(class_hash.try_into()?)))
+            .collect::<Result<_, ProtobufConversionError>>()?;
+        let query = Self { class_hashes };
+        query.validate()?;
+        Ok(query)
+    }
+}
+
+impl TryFrom<ClassesQuery> for protobuf::ClassesRequest {
+    type Error = InvalidClassesQuery;
+    fn try_from(value: ClassesQuery) -> Result<Self, Self::Error> {
+        value.validate()?;
+        Ok(Self {
+            class_hashes: value
+                .class_hashes
+                .into_iter()
+                .map(|class_hash| class_hash.0.into())
+                .collect(),
+        })
+    }
+}
+
+fn entry_point_selector(
+    selector: Option<protobuf::Felt252>,
+    field_description: &'static str,
+) -> Result<EntryPointSelector, ProtobufConversionError> {
+    Ok(EntryPointSelector(
+        selector.ok_or(ProtobufConversionError::MissingField { field_description })?.try_into()?,
+    ))
+}
+
+impl TryFrom<protobuf::EntryPoint> for DeprecatedEntryPoint {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::EntryPoint) -> Result<Self, Self::Error> {
+        let selector = entry_point_selector(value.selector, "EntryPoint::selector")?;
+        let offset =
+            StarkFelt::try_from(value.offset.ok_or(ProtobufConversionError::MissingField {
+                field_description: "EntryPoint::offset",
+            })?)?;
+        let offset =
+            usize::try_from(offset).map_err(|error| ProtobufConversionError::OutOfRangeValue {
+                type_description: "entry point offset",
+                error,
+            })?;
+        Ok(Self { selector, offset: EntryPointOffset(offset) })
+    }
+}
+
+impl From<DeprecatedEntryPoint> for protobuf::EntryPoint {
+    fn from(value: DeprecatedEntryPoint) -> Self {
+        Self {
+            selector: Some(value.selector.0.into()),
+            offset: Some(StarkFelt::from(value.offset.0 as u64).into()),
+        }
+    }
+}
+
+impl TryFrom<protobuf::SierraEntryPoint> for EntryPoint {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::SierraEntryPoint) -> Result<Self, Self::Error> {
+        let selector = entry_point_selector(value.selector, "SierraEntryPoint::selector")?;
+        let function_idx =
+            usize::try_from(value.index).map_err(|_| ProtobufConversionError::OutOfRangeValue {
+                type_description: "function index",
+                error: StarknetApiError::OutOfRange { string: value.index.to_string() },
+            })?;
+        Ok(Self { function_idx: FunctionIndex(function_idx), selector })
+    }
+}
+
+impl From<EntryPoint> for protobuf::SierraEntryPoint {
+    fn from(value: EntryPoint) -> Self {
+        Self { index: value.function_idx.0 as u64, selector: Some(value.selector.0.into()) }
+    }
+}
+
+// Converts the entry points of every type, so that a class has the entry points of all the types
+// even if some of them are empty.
+fn convert_entry_points<ProtobufEntryPoint, EntryPointT, EntryPointTypeT>(
+    entry_points_by_type: [(EntryPointTypeT, Vec<ProtobufEntryPoint>); 3],
+) -> Result<HashMap<EntryPointTypeT, Vec<EntryPointT>>, ProtobufConversionError>
+where
+    EntryPointT: TryFrom<ProtobufEntryPoint, Error = ProtobufConversionError>,
+    EntryPointTypeT: Eq + std::hash::Hash,
+{
+    entry_points_by_type
+        .into_iter()
+        .map(|(entry_point_type, entry_points)| {
+            Ok((
+                entry_point_type,
+                entry_points.into_iter().map(EntryPointT::try_from).collect::<Result<_, _>>()?,
+            ))
+        })
+        .collect()
+}
+
+impl TryFrom<protobuf::Cairo0Class> for DeprecatedContractClass {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Cairo0Class) -> Result<Self, Self::Error> {
+        let abi = serde_json::from_slice(&value.abi).map_err(|error| {
+            ProtobufConversionError::Deserialization {
+                type_description: "Cairo0Class::abi",
+                error: error.to_string(),
+            }
+        })?;
+        let program = serde_json::from_slice(&value.program).map_err(|error| {
+            ProtobufConversionError::Deserialization {
+                type_description: "Cairo0Class::program",
+                error: error.to_string(),
+            }
+        })?;
+        let entry_points_by_type = convert_entry_points([
+            (DeprecatedEntryPointType::Constructor, value.constructors),
+            (DeprecatedEntryPointType::External, value.externals),
+            (DeprecatedEntryPointType::L1Handler, value.l1_handlers),
+        ])?;
+        Ok(Self { abi, program, entry_points_by_type })
+    }
+}
+
+impl From<DeprecatedContractClass> for protobuf::Cairo0Class {
+    fn from(mut value: DeprecatedContractClass) -> Self {
+        let mut entry_points = |entry_point_type| {
+            value
+                .entry_points_by_type
+                .remove(&entry_point_type)
+                .unwrap_or_default()
+                .into_iter()
+                .map(protobuf::EntryPoint::from)
+                .collect()
+        };
+        Self {
+            constructors: entry_points(DeprecatedEntryPointType::Constructor),
+            externals: entry_points(DeprecatedEntryPointType::External),
+            l1_handlers: entry_points(DeprecatedEntryPointType::L1Handler),
+            abi: serde_json::to_vec(&value.abi).expect("Serializing a class should not fail."),
+            program: serde_json::to_vec(&value.program)
+                .expect("Serializing a class should not fail."),
+        }
+    }
+}
+
+/// The contract class version and the compiled class aren't stored, so they're left empty.
+impl TryFrom<protobuf::Cairo1Class> for ContractClass {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Cairo1Class) -> Result<Self, Self::Error> {
+        let abi = String::from_utf8(value.abi).map_err(|error| {
+            ProtobufConversionError::Deserialization {
+                type_description: "Cairo1Class::abi",
+                error: error.to_string(),
+            }
+        })?;
+        let sierra_program =
+            value.program.into_iter().map(StarkFelt::try_from).collect::<Result<_, _>>()?;
+        let entry_points = value.entry_points.ok_or(ProtobufConversionError::MissingField {
+            field_description: "Cairo1Class::entry_points",
+        })?;
+        let entry_point_by_type = convert_entry_points([
+            (EntryPointType::Constructor, entry_points.constructors),
+            (EntryPointType::External, entry_points.externals),
+            (EntryPointType::L1Handler, entry_points.l1_handlers),
+        ])?;
+        Ok(Self { sierra_program, entry_point_by_type, abi })
+    }
+}
+
+impl From<ContractClass> for protobuf::Cairo1Class {
+    fn from(mut value: ContractClass) -> Self {
+        let mut entry_points = |entry_point_type| {
+            value
+                .entry_point_by_type
+                .remove(&entry_point_type)
+                .unwrap_or_default()
+                .into_iter()
+                .map(protobuf::SierraEntryPoint::from)
+                .collect()
+        };
+        let entry_points = protobuf::Cairo1EntryPoints {
+            constructors: entry_points(EntryPointType::Constructor),
+            externals: entry_points(EntryPointType::External),
+            l1_handlers: entry_points(EntryPointType::L1Handler),
+        };
+        Self {
+            abi: value.abi.into_bytes(),
+            entry_points: Some(entry_points),
+            program: value.sierra_program.into_iter().map(protobuf::Felt252::from).collect(),
+            contract_class_version: String::new(),
+            compiled: vec![],
+        }
+    }
+}
+
+impl TryFrom<protobuf::Class> for ApiContractClass {
+    type Error = ProtobufConversionError;
+    fn try_from(value: protobuf::Class) -> Result<Self, Self::Error> {
+        match value
+            .class
+            .ok_or(ProtobufConversionError::MissingField { field_description: "Class::class" })?
+        {
+            protobuf::class::Class::Cairo0(class) => {
+                Ok(ApiContractClass::DeprecatedContractClass(class.try_into()?))
+            }
+            protobuf::class::Class::Cairo1(class) => {
+                Ok(ApiContractClass::ContractClass(class.try_into()?))
+            }
+        }
+    }
+}
+
+impl From<ApiContractClass> for protobuf::Class {
+    fn from(value: ApiContractClass) -> Self {
+        let class = match value {
+            ApiContractClass::DeprecatedContractClass(class) => {
+                protobuf::class::Class::Cairo0(class.into())
+            }
+            ApiContractClass::ContractClass(class) => protobuf::class::Class::Cairo1(class.into()),
+        };
+        Self { class: Some(class) }
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use papyrus_common::block_hash::BlockCommitments;
+use papyrus_common::pending_classes::ApiContractClass;
+use pretty_assertions::assert_eq;
+use prost_types::Timestamp;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockTimestamp};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    EntryPointSelector,
+    GlobalRoot,
+    Nonce,
+    PatriciaKey,
+    SequencerContractAddress,
+};
+use starknet_api::deprecated_contract_class::{
+    ContractClass as DeprecatedContractClass,
+    EntryPoint as DeprecatedEntryPoint,
+    EntryPointOffset,
+    EntryPointType as DeprecatedEntryPointType,
+    Program,
+};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{
+    ContractClass,
+    EntryPoint,
+    EntryPointType,
+    FunctionIndex,
+    StorageKey,
+    ThinStateDiff,
+};
+use starknet_api::{patricia_key, stark_felt};
+
+use super::ProtobufConversionError;
+use crate::messages::protobuf;
+use crate::{
+    BlockID,
+    BlockQuery,
+    ClassesQuery,
+    Direction,
+    InvalidBlockQuery,
+    InvalidClassesQuery,
+    MAX_BLOCKS_PER_QUERY,
+    MAX_CLASSES_PER_QUERY,
+};
+
+fn protobuf_block_header() -> protobuf::BlockHeader {
+    protobuf::BlockHeader {
+        parent_header: Some(stark_felt!("0x1").into()),
+        number: 2,
+        time: Some(Timestamp { seconds: 3, nanos: 0 }),
+        sequencer_address: Some(protobuf::Address {
+            elements: stark_felt!("0x4").bytes().to_vec(),
+        }),
+        state: Some(protobuf::Patricia { height: 251, root: Some(stark_felt!("0x5").into()) }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn block_header_from_protobuf() {
+    assert_eq!(
+        BlockHeader::try_from(protobuf_block_header()).unwrap(),
+        BlockHeader {
+            parent_hash: BlockHash(stark_felt!("0x1")),
+            block_number: BlockNumber(2),
+            timestamp: BlockTimestamp(3),
+            sequencer: SequencerContractAddress(ContractAddress(patricia_key!("0x4"))),
+            state_root: GlobalRoot(stark_felt!("0x5")),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn block_header_from_invalid_protobuf() {
+    let header = protobuf::BlockHeader { time: None, ..protobuf_block_header() };
+    assert_matches!(
+        BlockHeader::try_from(header),
+        Err(ProtobufConversionError::MissingField { field_description: "BlockHeader::time" })
+    );
+
+    let header = protobuf::BlockHeader {
+        parent_header: Some(protobuf::Hash { elements: vec![1; 31] }),
+        ..protobuf_block_header()
+    };
+    assert_matches!(
+        BlockHeader::try_from(header),
+        Err(ProtobufConversionError::BytesDataLengthMismatch {
+            num_expected: 32,
+            num_received: 31,
+            ..
+        })
+    );
+
+    let header = protobuf::BlockHeader {
+        parent_header: Some(protobuf::Hash { elements: vec![u8::MAX; 32] }),
+        ..protobuf_block_header()
+    };
+    assert_matches!(
+        BlockHeader::try_from(header),
+        Err(ProtobufConversionError::OutOfRangeValue { .. })
+    );
+}
+
+#[test]
+fn block_header_to_protobuf_and_back() {
+    let header = BlockHeader::try_from(protobuf_block_header()).unwrap();
+    let protobuf_header = protobuf::BlockHeader::from(header.clone());
+    assert_eq!(BlockHeader::try_from(protobuf_header).unwrap(), header);
+}
+
+#[test]
+fn block_commitments_to_protobuf_and_back() {
+    let header = BlockHeader::try_from(protobuf_block_header()).unwrap();
+    let commitments = BlockCommitments {
+        n_transactions: 1,
+        transactions_root: stark_felt!("0x2"),
+        n_events: 3,
+        events_root: stark_felt!("0x4"),
+    };
+    let protobuf_header = protobuf::BlockHeader::from((header.clone(), commitments));
+    assert_eq!(BlockCommitments::try_from(&protobuf_header).unwrap(), commitments);
+    assert_eq!(BlockHeader::try_from(protobuf_header).unwrap(), header);
+
+    assert_matches!(
+        BlockCommitments::try_from(&protobuf_block_header()),
+        Err(ProtobufConversionError::MissingField {
+            field_description: "BlockHeader::transactions"
+        })
+    );
+}
+
+#[test]
+fn block_query_from_block_headers_request() {
+    let request = protobuf::BlockHeadersRequest {
+        iteration: Some(protobuf::Iteration {
+            start: Some(protobuf::iteration::Start::Header(stark_felt!("0x1").into())),
+            direction: protobuf::iteration::Direction::Backward.into(),
+            limit: 5,
+            step: 3,
+        }),
+    };
+    assert_eq!(
+        BlockQuery::try_from(request).unwrap(),
+        BlockQuery {
+            start: BlockID::Hash(BlockHash(stark_felt!("0x1"))),
+            direction: Direction::Backward,
+            limit: 5,
+            skip: 0,
+            step: 3,
+        }
+    );
+
+    assert_matches!(
+        BlockQuery::try_from(protobuf::BlockHeadersRequest { iteration: None }),
+        Err(ProtobufConversionError::MissingField {
+            field_description: "BlockHeadersRequest::iteration"
+        })
+    );
+}
+
+#[test]
+fn state_diffs_request_to_block_query_and_back() {
+    let query = BlockQuery {
+        start: BlockID::Number(BlockNumber(10)),
+        direction: Direction::Forward,
+        limit: 5,
+        skip: 0,
+        step: 2,
+    };
+    let request = protobuf::StateDiffsRequest::try_from(query).unwrap();
+    assert_eq!(BlockQuery::try_from(request).unwrap(), query);
+
+    assert_matches!(
+        BlockQuery::try_from(protobuf::StateDiffsRequest { iteration: None }),
+        Err(ProtobufConversionError::MissingField {
+            field_description: "StateDiffsRequest::iteration"
+        })
+    );
+}
+
+#[test]
+fn block_headers_request_from_block_query() {
+    let query = BlockQuery {
+        start: BlockID::Number(BlockNumber(10)),
+        direction: Direction::Backward,
+        limit: 5,
+        skip: 2,
+        step: 3,
+    };
+    assert_eq!(
+        protobuf::BlockHeadersRequest::try_from(query).unwrap(),
+        protobuf::BlockHeadersRequest {
+            iteration: Some(protobuf::Iteration {
+                start: Some(protobuf::iteration::Start::BlockNumber(8)),
+                direction: protobuf::iteration::Direction::Backward.into(),
+                limit: 5,
+                step: 3,
+            }),
+        }
+    );
+
+    let block_hash = BlockHash(StarkHash::ONE);
+    let query = BlockQuery { start: BlockID::Hash(block_hash), skip: 0, ..query };
+    assert_eq!(
+        protobuf::BlockHeadersRequest::try_from(query).unwrap().iteration.unwrap().start,
+        Some(protobuf::iteration::Start::Header(block_hash.0.into()))
+    );
+}
+
+#[test]
+fn invalid_block_headers_request() {
+    let iteration = protobuf::Iteration {
+        start: Some(protobuf::iteration::Start::BlockNumber(1)),
+        direction: protobuf::iteration::Direction::Forward.into(),
+        limit: 5,
+        step: 1,
+    };
+    let to_query = |iteration: protobuf::Iteration| {
+        BlockQuery::try_from(protobuf::BlockHeadersRequest { iteration: Some(iteration) })
+    };
+    assert_matches!(
+        to_query(protobuf::Iteration { direction: 2, ..iteration.clone() }),
+        Err(ProtobufConversionError::UnknownEnumValue { value: 2, .. })
+    );
+    assert_matches!(
+        to_query(protobuf::Iteration { limit: 0, ..iteration.clone() }),
+        Err(ProtobufConversionError::InvalidQuery(InvalidBlockQuery::ZeroLimit))
+    );
+    assert_matches!(
+        to_query(protobuf::Iteration { limit: MAX_BLOCKS_PER_QUERY + 1, ..iteration.clone() }),
+        Err(ProtobufConversionError::InvalidQuery(InvalidBlockQuery::LimitTooLarge { .. }))
+    );
+    assert_matches!(
+        to_query(protobuf::Iteration { step: 0, ..iteration.clone() }),
+        Err(ProtobufConversionError::InvalidQuery(InvalidBlockQuery::ZeroStep))
+    );
+    assert_matches!(
+        to_query(protobuf::Iteration {
+            start: Some(protobuf::iteration::Start::BlockNumber(u64::MAX - 1)),
+            ..iteration
+        }),
+        Err(ProtobufConversionError::InvalidQuery(InvalidBlockQuery::BlockNumberOutOfRange))
+    );
+}
+
+#[test]
+fn invalid_block_query() {
+    let query = BlockQuery {
+        start: BlockID::Number(BlockNumber(10)),
+        direction: Direction::Backward,
+        limit: 5,
+        skip: 11,
+        step: 1,
+    };
+    // Skipping the blocks passes the genesis block.
+    assert_eq!(
+        protobuf::BlockHeadersRequest::try_from(query),
+        Err(InvalidBlockQuery::BlockNumberOutOfRange)
+    );
+    let query = BlockQuery { start: BlockID::Hash(BlockHash(StarkHash::ONE)), ..query };
+    assert_eq!(
+        protobuf::BlockHeadersRequest::try_from(query),
+        Err(InvalidBlockQuery::SkipFromBlockHash)
+    );
+    let query = BlockQuery { direction: Direction::Forward, step: u64::MAX, skip: 0, ..query };
+    assert_eq!(
+        protobuf::BlockHeadersRequest::try_from(query),
+        Err(InvalidBlockQuery::BlockNumberOutOfRange)
+    );
+}
+
+#[test]
+fn state_diff_to_protobuf_and_back() {
+    let state_diff = ThinStateDiff {
+        deployed_contracts: indexmap! {
+            ContractAddress(patricia_key!("0x1")) => ClassHash(stark_felt!("0x2")),
+        },
+        storage_diffs: indexmap! {
+            ContractAddress(patricia_key!("0x1")) => indexmap! {
+                StorageKey(patricia_key!("0x3")) => stark_felt!("0x4"),
+                StorageKey(patricia_key!("0x5")) => stark_felt!("0x6"),
+            },
+            ContractAddress(patricia_key!("0x7")) => indexmap! {
+                StorageKey(patricia_key!("0x3")) => stark_felt!("0x8"),
+            },
+        },
+        declared_classes: indexmap! {
+            ClassHash(stark_felt!("0xa")) => CompiledClassHash(stark_felt!("0xb")),
+        },
+        deprecated_declared_classes: vec![ClassHash(stark_felt!("0xc"))],
+        nonces: indexmap! {
+            ContractAddress(patricia_key!("0x7")) => Nonce(stark_felt!("0x9")),
+        },
+        ..Default::default()
+    };
+
+    let protobuf_state_diff = protobuf::StateDiff::from(state_diff.clone());
+    // The changes of each contract are sent together.
+    assert_eq!(protobuf_state_diff.contract_diffs.len(), 2);
+    assert_eq!(ThinStateDiff::try_from(protobuf_state_diff).unwrap(), state_diff);
+}
+
+#[test]
+fn replaced_class_is_received_as_deployed_contract() {
+    let address = ContractAddress(patricia_key!("0x1"));
+    let class_hash = ClassHash(stark_felt!("0x2"));
+    let state_diff = ThinStateDiff {
+        replaced_classes: indexmap! { address => class_hash },
+        ..Default::default()
+    };
+    assert_eq!(
+        ThinStateDiff::try_from(protobuf::StateDiff::from(state_diff)).unwrap(),
+        ThinStateDiff {
+            deployed_contracts: indexmap! { address => class_hash },
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn state_diff_from_invalid_protobuf() {
+    let contract_diff = protobuf::state_diff::ContractDiff {
+        address: Some(ContractAddress(patricia_key!("0x1")).into()),
+        values: vec![protobuf::ContractStoredValue {
+            key: Some(stark_felt!("0x2").into()),
+            value: None,
+        }],
+        ..Default::default()
+    };
+    assert_matches!(
+        ThinStateDiff::try_from(protobuf::StateDiff {
+            contract_diffs: vec![contract_diff.clone()],
+            ..Default::default()
+        }),
+        Err(ProtobufConversionError::MissingField {
+            field_description: "ContractStoredValue::value"
+        })
+    );
+    assert_matches!(
+        ThinStateDiff::try_from(protobuf::StateDiff {
+            contract_diffs: vec![protobuf::state_diff::ContractDiff {
+                address: None,
+                ..contract_diff
+            }],
+            ..Default::default()
+        }),
+        Err(ProtobufConversionError::MissingField { field_description: "ContractDiff::address" })
+    );
+    assert_matches!(
+        ThinStateDiff::try_from(protobuf::StateDiff {
+            declared_classes: vec![protobuf::state_diff::DeclaredClass {
+                class_hash: Some(stark_felt!("0x3").into()),
+                compiled_class_hash: None,
+            }],
+            ..Default::default()
+        }),
+        Err(ProtobufConversionError::MissingField {
+            field_description: "DeclaredClass::compiled_class_hash"
+        })
+    );
+}
+
+#[test]
+fn classes_request_to_classes_query_and_back() {
+    let query = ClassesQuery {
+        class_hashes: vec![ClassHash(stark_felt!("0x1")), ClassHash(stark_felt!("0x2"))],
+    };
+    let request = protobuf::ClassesRequest::try_from(query.clone()).unwrap();
+    assert_eq!(ClassesQuery::try_from(request).unwrap(), query);
+
+    assert_eq!(
+        protobuf::ClassesRequest::try_from(ClassesQuery { class_hashes: vec![] }),
+        Err(InvalidClassesQuery::NoClasses)
+    );
+    let request = protobuf::ClassesRequest {
+        class_hashes: vec![stark_felt!("0x1").into(); MAX_CLASSES_PER_QUERY + 1],
+    };
+    assert_matches!(
+        ClassesQuery::try_from(request),
+        Err(ProtobufConversionError::InvalidClassesQuery(
+            InvalidClassesQuery::TooManyClasses { .. }
+        ))
+    );
+}
+
+#[test]
+fn deprecated_class_to_protobuf_and_back() {
+    let class = ApiContractClass::DeprecatedContractClass(DeprecatedContractClass {
+        abi: None,
+        program: Program {
+            attributes: serde_json::json!([]),
+            builtins: serde_json::json!(["pedersen"]),
+            compiler_version: serde_json::json!("0.10.3"),
+            data: serde_json::json!(["0x1", "0x2"]),
+            debug_info: serde_json::json!({}),
+            hints: serde_json::json!({}),
+            identifiers: serde_json::json!({}),
+            main_scope: serde_json::json!("__main__"),
+            prime: serde_json::json!(
+                "0x800000000000011000000000000000000000000000000000000000000000001"
+            ),
+            reference_manager: serde_json::json!({}),
+        },
+        entry_points_by_type: HashMap::from([
+            (DeprecatedEntryPointType::Constructor, vec![]),
+            (
+                DeprecatedEntryPointType::External,
+                vec![DeprecatedEntryPoint {
+                    selector: EntryPointSelector(stark_felt!("0x3")),
+                    offset: EntryPointOffset(4),
+                }],
+            ),
+            (DeprecatedEntryPointType::L1Handler, vec![]),
+        ]),
+    });
+    let protobuf_class = protobuf::Class::from(class.clone());
+    assert_eq!(ApiContractClass::try_from(protobuf_class).unwrap(), class);
+}
+
+#[test]
+fn class_to_protobuf_and_back() {
+    let class = ApiContractClass::ContractClass(ContractClass {
+        sierra_program: vec![stark_felt!("0x1"), stark_felt!("0x2")],
+        entry_point_by_type: HashMap::from([
+            (
+                EntryPointType::Constructor,
+                vec![EntryPoint {
+                    function_idx: FunctionIndex(3),
+                    selector: EntryPointSelector(stark_felt!("0x4")),
+                }],
+            ),
+            (EntryPointType::External, vec![]),
+            (EntryPointType::L1Handler, vec![]),
+        ]),
+        abi: "[]".to_owned(),
+    });
+    let protobuf_class = protobuf::Class::from(class.clone());
+    assert_eq!(ApiContractClass::try_from(protobuf_class).unwrap(), class);
+}
+
+#[test]
+fn class_from_invalid_protobuf() {
+    let class = protobuf::Class {
+        class: Some(protobuf::class::Class::Cairo0(protobuf::Cairo0Class {
+            program: b"not a program".to_vec(),
+            ..Default::default()
+        })),
+    };
+    assert_matches!(
+        ApiContractClass::try_from(class),
+        Err(ProtobufConversionError::Deserialization { .. })
+    );
+    assert_matches!(
+        ApiContractClass::try_from(protobuf::Class { class: None }),
+        Err(ProtobufConversionError::MissingField { field_description: "Class::class" })
+    );
+}
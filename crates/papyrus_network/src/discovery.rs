@@ -0,0 +1,120 @@
+//! Discovers the peers of the node with the Kademlia DHT, starting from the bootstrap peers, and
+//! keeps track of which protocols the connected peers serve.
+#[cfg(test)]
+#[path = "discovery_test.rs"]
+mod discovery_test;
+
+use std::collections::HashMap;
+
+use libp2p::identity::Keypair;
+use libp2p::kad::store::MemoryStore;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::{NetworkBehaviour, StreamProtocol};
+use libp2p::{identify, kad, Multiaddr, PeerId};
+
+use crate::messages::protobuf;
+use crate::network_manager::BLOCK_HEADERS_PROTOCOL_NAME;
+use crate::streamed_data::behaviour::Behaviour;
+use crate::streamed_data::Config;
+
+/// The protocol version that the node sends to its peers when they identify each other.
+pub const IDENTIFY_PROTOCOL_VERSION: &str = "/starknet/0.1.0";
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+#[error("The multiaddress {multiaddr} doesn't contain the id of its peer.")]
+pub struct MissingPeerId {
+    pub multiaddr: Multiaddr,
+}
+
+/// Returns the id of the peer of the multiaddress, which is required to add the peer to the DHT.
+pub fn get_peer_id(multiaddr: &Multiaddr) -> Result<PeerId, MissingPeerId> {
+    multiaddr
+        .iter()
+        .find_map(|protocol| match protocol {
+            Protocol::P2p(peer_id) => Some(peer_id),
+            _ => None,
+        })
+        .ok_or_else(|| MissingPeerId { multiaddr: multiaddr.clone() })
+}
+
+/// The behaviours that the swarm of the node runs.
+#[derive(NetworkBehaviour)]
+pub(crate) struct MixedBehaviour {
+    pub kademlia: kad::Behaviour<MemoryStore>,
+    pub identify: identify::Behaviour,
+    pub block_headers: Behaviour<protobuf::BlockHeadersRequest, protobuf::BlockHeadersResponse>,
+    pub state_diffs: Behaviour<protobuf::StateDiffsRequest, protobuf::StateDiffsResponse>,
+    pub classes: Behaviour<protobuf::ClassesRequest, protobuf::ClassesResponse>,
+}
+
+impl MixedBehaviour {
+    pub fn new(
+        keypair: &Keypair,
+        block_headers_config: Config,
+        state_diffs_config: Config,
+        classes_config: Config,
+    ) -> Self {
+        let local_peer_id = keypair.public().to_peer_id();
+        Self {
+            kademlia: kad::Behaviour::new(local_peer_id, MemoryStore::new(local_peer_id)),
+            identify: identify::Behaviour::new(identify::Config::new(
+                IDENTIFY_PROTOCOL_VERSION.to_owned(),
+                keypair.public(),
+            )),
+            block_headers: Behaviour::new(block_headers_config),
+            state_diffs: Behaviour::new(state_diffs_config),
+            classes: Behaviour::new(classes_config),
+        }
+    }
+}
+
+/// The peers that the node is connected to. A peer is known to serve a protocol once it identifies
+/// itself with it.
+#[derive(Debug, Default)]
+pub struct PeerSet {
+    // The protocols that each peer serves, or None if the peer didn't identify itself yet.
+    peers: HashMap<PeerId, Option<Vec<StreamProtocol>>>,
+}
+
+impl PeerSet {
+    pub fn add_peer(&mut self, peer_id: PeerId) {
+        self.peers.entry(peer_id).or_default();
+    }
+
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+    }
+
+    /// Records the protocols that a connected peer supports.
+    pub fn set_peer_info(&mut self, peer_id: PeerId, info: &identify::Info) {
+        if let Some(protocols) = self.peers.get_mut(&peer_id) {
+            *protocols = Some(info.protocols.clone());
+        }
+    }
+
+    /// Returns the peers that are known to serve the protocol.
+    pub fn providers(&self, protocol: &StreamProtocol) -> Vec<PeerId> {
+        self.peers
+            .iter()
+            .filter_map(|(peer_id, protocols)| {
+                protocols
+                    .as_ref()
+                    .is_some_and(|protocols| protocols.contains(protocol))
+                    .then_some(*peer_id)
+            })
+            .collect()
+    }
+
+    /// Returns the peers that are known to serve blocks.
+    pub fn block_providers(&self) -> Vec<PeerId> {
+        self.providers(&BLOCK_HEADERS_PROTOCOL_NAME)
+    }
+
+    /// Returns the peers that didn't identify themselves yet, so they might serve any protocol.
+    pub fn unidentified_peers(&self) -> Vec<PeerId> {
+        self.peers
+            .iter()
+            .filter_map(|(peer_id, protocols)| protocols.is_none().then_some(*peer_id))
+            .collect()
+    }
+}
@@ -0,0 +1,60 @@
+use libp2p::identity::Keypair;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::StreamProtocol;
+use libp2p::{identify, Multiaddr, PeerId};
+use pretty_assertions::assert_eq;
+
+use super::{get_peer_id, MissingPeerId, PeerSet, IDENTIFY_PROTOCOL_VERSION};
+use crate::network_manager::{BLOCK_HEADERS_PROTOCOL_NAME, STATE_DIFFS_PROTOCOL_NAME};
+
+fn identify_info(protocols: Vec<StreamProtocol>) -> identify::Info {
+    identify::Info {
+        public_key: Keypair::generate_ed25519().public(),
+        protocol_version: IDENTIFY_PROTOCOL_VERSION.to_owned(),
+        agent_version: "".to_owned(),
+        listen_addrs: vec![],
+        protocols,
+        observed_addr: Multiaddr::empty(),
+    }
+}
+
+#[test]
+fn get_peer_id_from_multiaddr() {
+    let peer_id = PeerId::random();
+    let multiaddr: Multiaddr = "/ip4/127.0.0.1/tcp/10000".parse().unwrap();
+    assert_eq!(get_peer_id(&multiaddr.clone().with(Protocol::P2p(peer_id))), Ok(peer_id));
+    assert_eq!(get_peer_id(&multiaddr), Err(MissingPeerId { multiaddr }));
+}
+
+#[test]
+fn peers_are_classified_by_their_info() {
+    let mut peer_set = PeerSet::default();
+    let unidentified_peer_id = PeerId::random();
+    peer_set.add_peer(unidentified_peer_id);
+    assert_eq!(peer_set.unidentified_peers(), vec![unidentified_peer_id]);
+    assert!(peer_set.block_providers().is_empty());
+
+    let non_provider_peer_id = PeerId::random();
+    peer_set.add_peer(non_provider_peer_id);
+    peer_set.set_peer_info(non_provider_peer_id, &identify_info(vec![]));
+    let provider_peer_id = PeerId::random();
+    peer_set.add_peer(provider_peer_id);
+    peer_set.set_peer_info(provider_peer_id, &identify_info(vec![BLOCK_HEADERS_PROTOCOL_NAME]));
+    assert_eq!(peer_set.block_providers(), vec![provider_peer_id]);
+    assert_eq!(peer_set.unidentified_peers(), vec![unidentified_peer_id]);
+
+    assert_eq!(peer_set.providers(&BLOCK_HEADERS_PROTOCOL_NAME), vec![provider_peer_id]);
+    assert!(peer_set.providers(&STATE_DIFFS_PROTOCOL_NAME).is_empty());
+
+    peer_set.remove_peer(&provider_peer_id);
+    peer_set.remove_peer(&unidentified_peer_id);
+    assert!(peer_set.block_providers().is_empty());
+    assert!(peer_set.unidentified_peers().is_empty());
+}
+
+#[test]
+fn info_of_disconnected_peer_is_ignored() {
+    let mut peer_set = PeerSet::default();
+    peer_set.set_peer_info(PeerId::random(), &identify_info(vec![BLOCK_HEADERS_PROTOCOL_NAME]));
+    assert!(peer_set.block_providers().is_empty());
+}
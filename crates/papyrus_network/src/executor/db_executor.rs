@@ -1,12 +1,36 @@
-use futures::channel::mpsc::UnboundedReceiver;
+#[cfg(test)]
+#[path = "db_executor_test.rs"]
+mod db_executor_test;
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use futures::channel::oneshot;
 #[cfg(test)]
 use mockall::automock;
+use papyrus_common::block_hash::calculate_block_commitments;
+use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::utils::read_block;
+use papyrus_storage::{StorageError, StorageReader, StorageTxn};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::ChainId;
+use starknet_api::state::StateNumber;
+use starknet_api::StarknetApiError;
 
-use crate::BlockQuery;
+use crate::messages::protobuf;
+use crate::{BlockID, BlockQuery, ClassesQuery, Direction};
 
 #[derive(thiserror::Error, Debug)]
-pub enum ReaderError {}
+pub enum ReaderError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    StarknetApi(#[from] StarknetApiError),
+    #[error("Block with hash {block_hash} wasn't found.")]
+    BlockNotFound { block_hash: BlockHash },
+}
 
 pub struct ReaderCommunication<Response> {
     pub result_receiver: UnboundedReceiver<Response>,
@@ -14,6 +38,232 @@ pub struct ReaderCommunication<Response> {
 }
 
 #[cfg_attr(test, automock)]
-pub trait ReaderExecutor<Response> {
-    fn start_reading(&self, blocks_range: BlockQuery) -> ReaderCommunication<Response>;
+pub trait ReaderExecutor<Query, Response> {
+    fn start_reading(&self, query: Query) -> ReaderCommunication<Response>;
+}
+
+/// Reads the block headers that peers query from the storage. The commitments of the blocks of the
+/// given chain are sent with the headers.
+pub struct BlockHeadersDBExecutor {
+    storage_reader: StorageReader,
+    chain_id: ChainId,
+}
+
+impl BlockHeadersDBExecutor {
+    pub fn new(storage_reader: StorageReader, chain_id: ChainId) -> Self {
+        Self { storage_reader, chain_id }
+    }
+}
+
+impl ReaderExecutor<BlockQuery, protobuf::BlockHeadersResponse> for BlockHeadersDBExecutor {
+    fn start_reading(
+        &self,
+        blocks_range: BlockQuery,
+    ) -> ReaderCommunication<protobuf::BlockHeadersResponse> {
+        let storage_reader = self.storage_reader.clone();
+        let chain_id = self.chain_id.clone();
+        spawn_reading(move |result_sender| {
+            read_blocks(&storage_reader, blocks_range, result_sender, |txn, block_number| {
+                read_block_header(txn, block_number, &chain_id)
+            })
+        })
+    }
+}
+
+/// Reads the state diffs that peers query from the storage.
+pub struct StateDiffsDBExecutor {
+    storage_reader: StorageReader,
+}
+
+impl StateDiffsDBExecutor {
+    pub fn new(storage_reader: StorageReader) -> Self {
+        Self { storage_reader }
+    }
+}
+
+impl ReaderExecutor<BlockQuery, protobuf::StateDiffsResponse> for StateDiffsDBExecutor {
+    fn start_reading(
+        &self,
+        blocks_range: BlockQuery,
+    ) -> ReaderCommunication<protobuf::StateDiffsResponse> {
+        let storage_reader = self.storage_reader.clone();
+        spawn_reading(move |result_sender| {
+            read_blocks(&storage_reader, blocks_range, result_sender, read_state_diff)
+        })
+    }
+}
+
+/// Reads the contract classes that peers query from the storage.
+pub struct ClassesDBExecutor {
+    storage_reader: StorageReader,
+}
+
+impl ClassesDBExecutor {
+    pub fn new(storage_reader: StorageReader) -> Self {
+        Self { storage_reader }
+    }
+}
+
+impl ReaderExecutor<ClassesQuery, protobuf::ClassesResponse> for ClassesDBExecutor {
+    fn start_reading(&self, query: ClassesQuery) -> ReaderCommunication<protobuf::ClassesResponse> {
+        let storage_reader = self.storage_reader.clone();
+        spawn_reading(move |result_sender| read_classes(&storage_reader, query, result_sender))
+    }
+}
+
+// The reading is done in a blocking task, so that the network isn't blocked by the storage.
+fn spawn_reading<Response: Send + 'static>(
+    read: impl FnOnce(&UnboundedSender<Response>) -> Result<(), ReaderError> + Send + 'static,
+) -> ReaderCommunication<Response> {
+    let (result_sender, result_receiver) = unbounded();
+    let (is_finished_sender, is_finished) = oneshot::channel();
+    tokio::task::spawn_blocking(move || {
+        let result = read(&result_sender);
+        // The receiver might have been dropped if the session was closed, which is fine.
+        let _ = is_finished_sender.send(result);
+    });
+    ReaderCommunication { result_receiver, is_finished }
+}
+
+// Sends the response of every block in the range. The range ends early at the last stored block.
+// The response of a single block is read by `read_block`, which returns None if the block isn't
+// stored.
+fn read_blocks<Response>(
+    storage_reader: &StorageReader,
+    blocks_range: BlockQuery,
+    result_sender: &UnboundedSender<Response>,
+    read_block: impl Fn(&StorageTxn<'_, RO>, BlockNumber) -> Result<Option<Response>, ReaderError>,
+) -> Result<(), ReaderError> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let start_block_number = match blocks_range.start {
+        BlockID::Number(block_number) => block_number,
+        BlockID::Hash(block_hash) => txn
+            .get_block_number_by_hash(&block_hash)?
+            .ok_or(ReaderError::BlockNotFound { block_hash })?,
+    };
+    for block_index in 0..blocks_range.limit {
+        let Some(block_number) = get_block_number(start_block_number, blocks_range, block_index)
+        else {
+            break;
+        };
+        let Some(response) = read_block(&txn, block_number)? else {
+            break;
+        };
+        if result_sender.unbounded_send(response).is_err() {
+            // The session was closed, so there's no need to continue reading.
+            break;
+        }
+    }
+    Ok(())
+}
+
+// The header is sent with the commitments to the body of the block, which are calculated from the
+// body, so a block is read only once its body is stored and as long as it isn't pruned. The header
+// is followed by the signatures part, which holds the hash of the block.
+fn read_block_header(
+    txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+    chain_id: &ChainId,
+) -> Result<Option<protobuf::BlockHeadersResponse>, ReaderError> {
+    if block_number >= txn.get_body_marker()? || block_number < txn.get_pruned_body_marker()? {
+        return Ok(None);
+    }
+    let block = read_block(txn, block_number)?;
+    let commitments = calculate_block_commitments(&block, chain_id)?;
+    let header = block.header;
+    let block_hash = header.block_hash;
+    Ok(Some(protobuf::BlockHeadersResponse {
+        part: vec![
+            protobuf::BlockHeadersResponsePart {
+                header_message: Some(protobuf::block_headers_response_part::HeaderMessage::Header(
+                    (header, commitments).into(),
+                )),
+            },
+            protobuf::BlockHeadersResponsePart {
+                header_message: Some(
+                    protobuf::block_headers_response_part::HeaderMessage::Signatures(
+                        protobuf::Signatures {
+                            block: Some(protobuf::BlockId {
+                                number: block_number.0,
+                                header: Some(block_hash.0.into()),
+                            }),
+                            // TODO(shahak): Send the signatures once they're stored.
+                            signatures: vec![],
+                        },
+                    ),
+                ),
+            },
+        ],
+    }))
+}
+
+// A block is read only once both its header and its state diff are stored, since the response
+// holds the hash of the block.
+fn read_state_diff(
+    txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+) -> Result<Option<protobuf::StateDiffsResponse>, ReaderError> {
+    let Some(header) = txn.get_block_header(block_number)? else {
+        return Ok(None);
+    };
+    let Some(state_diff) = txn.get_state_diff(block_number)? else {
+        return Ok(None);
+    };
+    Ok(Some(protobuf::StateDiffsResponse {
+        block_id: Some(protobuf::BlockId {
+            number: block_number.0,
+            header: Some(header.block_hash.0.into()),
+        }),
+        state_diff_message: Some(protobuf::state_diffs_response::StateDiffMessage::Diff(
+            state_diff.into(),
+        )),
+    }))
+}
+
+// Sends the class of every hash in the query, up to the first class that isn't stored. Classes are
+// read from the state after the last stored state diff.
+fn read_classes(
+    storage_reader: &StorageReader,
+    query: ClassesQuery,
+    result_sender: &UnboundedSender<protobuf::ClassesResponse>,
+) -> Result<(), ReaderError> {
+    let txn = storage_reader.begin_ro_txn()?;
+    let state_number = StateNumber::right_before_block(txn.get_state_marker()?);
+    let state_reader = txn.get_state_reader()?;
+    for class_hash in query.class_hashes {
+        let class =
+            if let Some(class) = state_reader.get_class_definition_at(state_number, &class_hash)? {
+                ApiContractClass::ContractClass(class)
+            } else if let Some(class) =
+                state_reader.get_deprecated_class_definition_at(state_number, &class_hash)?
+            {
+                ApiContractClass::DeprecatedContractClass(class)
+            } else {
+                break;
+            };
+        let response = protobuf::ClassesResponse {
+            class_hash: Some(class_hash.0.into()),
+            class_message: Some(protobuf::classes_response::ClassMessage::Class(class.into())),
+        };
+        if result_sender.unbounded_send(response).is_err() {
+            // The session was closed, so there's no need to continue reading.
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Returns the number of the block at the given index of the range, or None if it's out of the
+// block numbers range.
+fn get_block_number(
+    start_block_number: BlockNumber,
+    blocks_range: BlockQuery,
+    block_index: u64,
+) -> Option<BlockNumber> {
+    let offset = block_index.checked_mul(blocks_range.step)?.checked_add(blocks_range.skip)?;
+    match blocks_range.direction {
+        Direction::Forward => start_block_number.0.checked_add(offset),
+        Direction::Backward => start_block_number.0.checked_sub(offset),
+    }
+    .map(BlockNumber)
 }
@@ -0,0 +1,242 @@
+use assert_matches::assert_matches;
+use futures::StreamExt;
+use indexmap::{indexmap, IndexMap};
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageReader;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ChainId, ClassHash, CompiledClassHash};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkHash;
+use starknet_api::state::{ContractClass, StateDiff};
+
+use super::{
+    BlockHeadersDBExecutor,
+    ClassesDBExecutor,
+    ReaderCommunication,
+    ReaderError,
+    ReaderExecutor,
+    StateDiffsDBExecutor,
+};
+use crate::messages::protobuf;
+use crate::{BlockID, BlockQuery, ClassesQuery, Direction};
+
+const N_BLOCKS: u64 = 10;
+
+fn block_hash(block_number: u64) -> BlockHash {
+    BlockHash(StarkHash::from(block_number + 1))
+}
+
+// The headers are sent only for blocks whose body is stored, so the blocks are stored with empty
+// bodies.
+fn storage_with_blocks() -> (StorageReader, tempfile::TempDir) {
+    let ((storage_reader, mut storage_writer), temp_dir) = get_test_storage();
+    for block_number in 0..N_BLOCKS {
+        let header = BlockHeader {
+            block_number: BlockNumber(block_number),
+            block_hash: block_hash(block_number),
+            ..Default::default()
+        };
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(BlockNumber(block_number), &header)
+            .unwrap()
+            .append_body(BlockNumber(block_number), BlockBody::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+    (storage_reader, temp_dir)
+}
+
+// Returns the numbers of the blocks in the responses, and the result of the reading.
+async fn read(
+    storage_reader: StorageReader,
+    query: BlockQuery,
+) -> (Vec<u64>, Result<(), ReaderError>) {
+    let ReaderCommunication { result_receiver, is_finished } =
+        BlockHeadersDBExecutor::new(storage_reader, ChainId::default()).start_reading(query);
+    let block_numbers = result_receiver
+        .map(|response| match &response.part[0].header_message {
+            Some(protobuf::block_headers_response_part::HeaderMessage::Header(header)) => {
+                header.number
+            }
+            _ => panic!("Expected the first part of the response to be a header."),
+        })
+        .collect()
+        .await;
+    (block_numbers, is_finished.await.unwrap())
+}
+
+#[tokio::test]
+async fn read_block_headers() {
+    let (storage_reader, _temp_dir) = storage_with_blocks();
+    let query = BlockQuery {
+        start: BlockID::Number(BlockNumber(1)),
+        direction: Direction::Forward,
+        limit: 3,
+        skip: 1,
+        step: 2,
+    };
+    let (block_numbers, result) = read(storage_reader.clone(), query).await;
+    assert_eq!(block_numbers, vec![2, 4, 6]);
+    result.unwrap();
+
+    let query = BlockQuery {
+        start: BlockID::Hash(block_hash(5)),
+        direction: Direction::Backward,
+        skip: 0,
+        ..query
+    };
+    let (block_numbers, result) = read(storage_reader, query).await;
+    assert_eq!(block_numbers, vec![5, 3, 1]);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn read_block_headers_out_of_range() {
+    let (storage_reader, _temp_dir) = storage_with_blocks();
+    // The reading stops at the last stored block.
+    let query = BlockQuery {
+        start: BlockID::Number(BlockNumber(N_BLOCKS - 2)),
+        direction: Direction::Forward,
+        limit: 5,
+        skip: 0,
+        step: 1,
+    };
+    let (block_numbers, result) = read(storage_reader.clone(), query).await;
+    assert_eq!(block_numbers, vec![N_BLOCKS - 2, N_BLOCKS - 1]);
+    result.unwrap();
+
+    // The reading stops at the genesis block.
+    let query = BlockQuery {
+        start: BlockID::Number(BlockNumber(1)),
+        direction: Direction::Backward,
+        ..query
+    };
+    let (block_numbers, result) = read(storage_reader.clone(), query).await;
+    assert_eq!(block_numbers, vec![1, 0]);
+    result.unwrap();
+
+    let query = BlockQuery { start: BlockID::Hash(block_hash(N_BLOCKS)), ..query };
+    let (block_numbers, result) = read(storage_reader, query).await;
+    assert!(block_numbers.is_empty());
+    assert_matches!(result, Err(ReaderError::BlockNotFound { .. }));
+}
+
+#[tokio::test]
+async fn read_state_diffs() {
+    const N_STATE_DIFFS: u64 = 3;
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    for block_number in 0..N_BLOCKS {
+        let header = BlockHeader {
+            block_number: BlockNumber(block_number),
+            block_hash: block_hash(block_number),
+            ..Default::default()
+        };
+        let mut txn = storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(BlockNumber(block_number), &header)
+            .unwrap();
+        if block_number < N_STATE_DIFFS {
+            txn = txn
+                .append_state_diff(BlockNumber(block_number), StateDiff::default(), IndexMap::new())
+                .unwrap();
+        }
+        txn.commit().unwrap();
+    }
+
+    // The reading stops at the last block whose state diff is stored.
+    let query = BlockQuery {
+        start: BlockID::Number(BlockNumber(1)),
+        direction: Direction::Forward,
+        limit: 5,
+        skip: 0,
+        step: 1,
+    };
+    let ReaderCommunication { result_receiver, is_finished } =
+        StateDiffsDBExecutor::new(storage_reader).start_reading(query);
+    let block_ids = result_receiver
+        .map(|response| {
+            assert_matches!(
+                response.state_diff_message,
+                Some(protobuf::state_diffs_response::StateDiffMessage::Diff(_))
+            );
+            response.block_id.unwrap()
+        })
+        .collect::<Vec<_>>()
+        .await;
+    is_finished.await.unwrap().unwrap();
+    assert_eq!(
+        block_ids,
+        (1..N_STATE_DIFFS)
+            .map(|block_number| protobuf::BlockId {
+                number: block_number,
+                header: Some(block_hash(block_number).0.into()),
+            })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn read_classes() {
+    let class_hash = ClassHash(StarkHash::ONE);
+    let deprecated_class_hash = ClassHash(StarkHash::TWO);
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_state_diff(
+            BlockNumber(0),
+            StateDiff {
+                declared_classes: indexmap! {
+                    class_hash => (CompiledClassHash::default(), ContractClass::default()),
+                },
+                deprecated_declared_classes: indexmap! {
+                    deprecated_class_hash => DeprecatedContractClass::default(),
+                },
+                ..Default::default()
+            },
+            IndexMap::new(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The reading stops at the first class that isn't stored.
+    let query = ClassesQuery {
+        class_hashes: vec![
+            deprecated_class_hash,
+            class_hash,
+            ClassHash(StarkHash::from(3_u64)),
+            class_hash,
+        ],
+    };
+    let ReaderCommunication { result_receiver, is_finished } =
+        ClassesDBExecutor::new(storage_reader).start_reading(query);
+    let responses = result_receiver.collect::<Vec<_>>().await;
+    is_finished.await.unwrap().unwrap();
+    assert_eq!(
+        responses.iter().map(|response| response.class_hash.clone()).collect::<Vec<_>>(),
+        vec![Some(deprecated_class_hash.0.into()), Some(class_hash.0.into())]
+    );
+    assert_matches!(
+        &responses[0].class_message,
+        Some(protobuf::classes_response::ClassMessage::Class(protobuf::Class {
+            class: Some(protobuf::class::Class::Cairo0(_)),
+        }))
+    );
+    assert_matches!(
+        &responses[1].class_message,
+        Some(protobuf::classes_response::ClassMessage::Class(protobuf::Class {
+            class: Some(protobuf::class::Class::Cairo1(_)),
+        }))
+    );
+}
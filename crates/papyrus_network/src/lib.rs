@@ -2,23 +2,33 @@
 /// to the [`Starknet p2p specs`]
 ///
 /// [`Starknet p2p specs`]: https://github.com/starknet-io/starknet-p2p-specs/
+pub mod bandwidth_limiter;
+pub mod converters;
+pub mod discovery;
+pub mod executor;
 pub mod messages;
+pub mod network_manager;
+pub mod peer_manager;
 pub mod streamed_data;
 #[cfg(test)]
 mod test_utils;
 
 use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::ClassHash;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Direction {
     Forward,
     Backward,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum BlockID {
     Hash(BlockHash),
     Number(BlockNumber),
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct BlockQuery {
     pub start: BlockID,
     pub direction: Direction,
@@ -27,4 +37,87 @@ pub struct BlockQuery {
     pub step: u64,
 }
 
-// TODO(shahak): Implement conversion from GetBlocks to BlockQuery.
+/// The maximal number of blocks that a query can ask for.
+pub const MAX_BLOCKS_PER_QUERY: u64 = 10000;
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum InvalidBlockQuery {
+    #[error("The query doesn't ask for any block.")]
+    ZeroLimit,
+    #[error("The query asks for {limit} blocks, more than the maximum of {max_limit}.")]
+    LimitTooLarge { limit: u64, max_limit: u64 },
+    #[error("The step of the query is zero.")]
+    ZeroStep,
+    #[error("The block numbers of the query are out of range.")]
+    BlockNumberOutOfRange,
+    #[error("Skipping blocks isn't supported in queries that start from a block hash.")]
+    SkipFromBlockHash,
+}
+
+impl BlockQuery {
+    /// Checks that the query asks for a positive number of blocks, up to [`MAX_BLOCKS_PER_QUERY`],
+    /// with a positive step, and that the numbers of the blocks it asks for don't overflow.
+    pub fn validate(&self) -> Result<(), InvalidBlockQuery> {
+        if self.limit == 0 {
+            return Err(InvalidBlockQuery::ZeroLimit);
+        }
+        if self.limit > MAX_BLOCKS_PER_QUERY {
+            return Err(InvalidBlockQuery::LimitTooLarge {
+                limit: self.limit,
+                max_limit: MAX_BLOCKS_PER_QUERY,
+            });
+        }
+        if self.step == 0 {
+            return Err(InvalidBlockQuery::ZeroStep);
+        }
+        // The offset of the last block from the start of the query. Backward queries that pass
+        // the genesis block are answered with the blocks up to it, so only forward queries can
+        // overflow.
+        let last_block_offset = (self.limit - 1)
+            .checked_mul(self.step)
+            .and_then(|offset| offset.checked_add(self.skip))
+            .ok_or(InvalidBlockQuery::BlockNumberOutOfRange)?;
+        if let (BlockID::Number(BlockNumber(start)), Direction::Forward) =
+            (self.start, self.direction)
+        {
+            start.checked_add(last_block_offset).ok_or(InvalidBlockQuery::BlockNumberOutOfRange)?;
+        }
+        Ok(())
+    }
+}
+
+/// A query for the definitions of contract classes. The classes are answered in the order of their
+/// hashes, up to the first class that the peer doesn't have.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassesQuery {
+    pub class_hashes: Vec<ClassHash>,
+}
+
+/// The maximal number of classes that a query can ask for.
+pub const MAX_CLASSES_PER_QUERY: usize = 100;
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum InvalidClassesQuery {
+    #[error("The query doesn't ask for any class.")]
+    NoClasses,
+    #[error("The query asks for {num_classes} classes, more than the maximum of {max_classes}.")]
+    TooManyClasses { num_classes: usize, max_classes: usize },
+}
+
+impl ClassesQuery {
+    /// Checks that the query asks for a positive number of classes, up to
+    /// [`MAX_CLASSES_PER_QUERY`].
+    pub fn validate(&self) -> Result<(), InvalidClassesQuery> {
+        let num_classes = self.class_hashes.len();
+        if num_classes == 0 {
+            return Err(InvalidClassesQuery::NoClasses);
+        }
+        if num_classes > MAX_CLASSES_PER_QUERY {
+            return Err(InvalidClassesQuery::TooManyClasses {
+                num_classes,
+                max_classes: MAX_CLASSES_PER_QUERY,
+            });
+        }
+        Ok(())
+    }
+}
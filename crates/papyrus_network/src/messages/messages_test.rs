@@ -2,8 +2,9 @@ use std::time::Duration;
 
 use futures::AsyncWriteExt;
 use pretty_assertions::assert_eq;
+use prost::Message;
 
-use super::{read_message, write_message};
+use super::{read_message, write_message, write_usize, MAX_MESSAGE_SIZE};
 use crate::messages::protobuf;
 use crate::test_utils::{dummy_data, get_connected_streams};
 
@@ -15,7 +16,10 @@ async fn read_write_positive_flow() {
         write_message(message.clone(), &mut stream1).await.unwrap();
     }
     for expected_message in &messages {
-        assert_eq!(*expected_message, read_message(&mut stream2).await.unwrap().unwrap());
+        assert_eq!(
+            *expected_message,
+            read_message(&mut stream2, MAX_MESSAGE_SIZE).await.unwrap().unwrap()
+        );
     }
 }
 
@@ -24,7 +28,10 @@ async fn read_message_returns_none_when_other_stream_is_closed() {
     let (mut stream1, mut stream2, _) = get_connected_streams().await;
     stream1.close().await.unwrap();
     assert!(
-        read_message::<protobuf::BlockHeadersResponse, _>(&mut stream2).await.unwrap().is_none()
+        read_message::<protobuf::BlockHeadersResponse, _>(&mut stream2, MAX_MESSAGE_SIZE)
+            .await
+            .unwrap()
+            .is_none()
     );
 }
 
@@ -34,9 +41,35 @@ async fn read_message_is_pending_when_other_stream_didnt_send() {
     assert!(
         tokio::time::timeout(
             Duration::from_millis(10),
-            read_message::<protobuf::BlockHeadersResponse, _>(&mut stream2)
+            read_message::<protobuf::BlockHeadersResponse, _>(&mut stream2, MAX_MESSAGE_SIZE)
         )
         .await
         .is_err()
     );
 }
+
+#[tokio::test]
+async fn read_message_fails_when_other_stream_sent_less_than_the_length() {
+    let (mut stream1, mut stream2, _) = get_connected_streams().await;
+    write_usize(&mut stream1, MAX_MESSAGE_SIZE).await.unwrap();
+    stream1.write_all(&[1, 2, 3]).await.unwrap();
+    stream1.close().await.unwrap();
+    let error = read_message::<protobuf::BlockHeadersResponse, _>(&mut stream2, MAX_MESSAGE_SIZE)
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[tokio::test]
+async fn read_message_fails_when_message_exceeds_max_size() {
+    let (mut stream1, mut stream2, _) = get_connected_streams().await;
+    let message = dummy_data().remove(0);
+    let message_size = message.encoded_len();
+    write_message(message.clone(), &mut stream1).await.unwrap();
+    write_message(message.clone(), &mut stream1).await.unwrap();
+    assert_eq!(message, read_message(&mut stream2, message_size).await.unwrap().unwrap());
+    let error = read_message::<protobuf::BasicMessage, _>(&mut stream2, message_size - 1)
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
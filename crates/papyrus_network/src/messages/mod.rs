@@ -11,6 +11,7 @@ use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use prost::Message;
 use unsigned_varint::encode::usize_buffer;
 
+/// The default maximal size in bytes of a message that is read from a peer.
 pub const MAX_MESSAGE_SIZE: usize = 1 << 20;
 
 pub async fn write_message<T: Message, Stream: AsyncWrite + Unpin>(
@@ -27,20 +28,26 @@ pub async fn write_message<T: Message, Stream: AsyncWrite + Unpin>(
 
 pub async fn read_message<T: Message + Default, Stream: AsyncRead + Unpin>(
     mut io: Stream,
+    max_message_size: usize,
 ) -> Result<Option<T>, io::Error> {
     // This code is based on read_length_prefixed from libp2p v0.52 which was erased in v0.53.
     let Some(message_len) = read_usize(&mut io).await? else { return Ok(None) };
-    if message_len > MAX_MESSAGE_SIZE {
+    if message_len > max_message_size {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
             format!(
-                "Received data size ({message_len} bytes) exceeds maximum ({MAX_MESSAGE_SIZE} \
+                "Received data size ({message_len} bytes) exceeds maximum ({max_message_size} \
                  bytes)"
             ),
         ));
     }
-    let mut buf = vec![0; message_len];
-    io.read_exact(&mut buf).await?;
+    // The buffer grows as the data arrives, so a peer can't make us allocate a large buffer just by
+    // sending a large length.
+    let mut buf = Vec::new();
+    (&mut io).take(message_len as u64).read_to_end(&mut buf).await?;
+    if buf.len() < message_len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
     Ok(Some(T::decode(buf.as_slice())?))
 }
 
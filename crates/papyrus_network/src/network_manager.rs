@@ -0,0 +1,977 @@
+//! Runs the libp2p swarm of the node. Discovers peers, sends the block headers, state diffs and
+//! classes queries of the node to its peers, routes the responses of the peers back to the
+//! queriers, and answers the queries of the peers from the storage.
+#[cfg(test)]
+#[path = "network_manager_test.rs"]
+mod network_manager_test;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::io;
+use std::num::NonZeroU64;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::channel::oneshot;
+use futures::future::{ready, FutureExt};
+use futures::stream::{self, BoxStream, SelectAll};
+use futures::{select, StreamExt};
+use libp2p::swarm::{DialError, StreamProtocol, SwarmEvent};
+use libp2p::{
+    identify,
+    kad,
+    noise,
+    tcp,
+    yamux,
+    Multiaddr,
+    PeerId,
+    Swarm,
+    SwarmBuilder,
+    TransportError,
+};
+use papyrus_common::metrics as papyrus_metrics;
+use papyrus_config::converters::{
+    deserialize_seconds_to_duration,
+    deserialize_vec,
+    serialize_slice,
+};
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_storage::StorageReader;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::ChainId;
+use tokio_stream::wrappers::IntervalStream;
+use tracing::{debug, info, warn};
+
+use crate::bandwidth_limiter::BandwidthLimiter;
+use crate::converters::ProtobufConversionError;
+use crate::discovery::{get_peer_id, MissingPeerId, MixedBehaviour, MixedBehaviourEvent, PeerSet};
+use crate::executor::db_executor::{
+    BlockHeadersDBExecutor,
+    ClassesDBExecutor,
+    ReaderCommunication,
+    ReaderExecutor,
+    StateDiffsDBExecutor,
+};
+use crate::messages::{protobuf, MAX_MESSAGE_SIZE};
+use crate::peer_manager::{PeerManager, PeerMisconduct};
+use crate::streamed_data::behaviour::{Behaviour, Event, SessionError};
+use crate::streamed_data::{
+    self,
+    DataBound,
+    InboundSessionId,
+    OutboundSessionId,
+    QueryBound,
+    SessionId,
+};
+use crate::{BlockQuery, ClassesQuery, InvalidBlockQuery, InvalidClassesQuery};
+
+/// The protocol of the block headers queries.
+pub const BLOCK_HEADERS_PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/starknet/headers/1");
+
+/// The protocol of the state diffs queries.
+pub const STATE_DIFFS_PROTOCOL_NAME: StreamProtocol =
+    StreamProtocol::new("/starknet/state_diffs/1");
+
+/// The protocol of the contract classes queries.
+pub const CLASSES_PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/starknet/classes/1");
+
+// How often the node looks for new peers in the DHT.
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct NetworkConfig {
+    pub tcp_port: u16,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub session_timeout: Duration,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub idle_connection_timeout: Duration,
+    #[serde(deserialize_with = "deserialize_vec")]
+    pub bootstrap_peer_multiaddrs: Vec<Multiaddr>,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub peer_ban_duration: Duration,
+    pub max_message_size: usize,
+    pub max_inbound_sessions_per_peer: usize,
+    pub max_bytes_per_second_served: NonZeroU64,
+}
+
+impl SerializeConfig for NetworkConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "tcp_port",
+                &self.tcp_port,
+                "The port that the node listens on for incoming tcp connections.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "session_timeout",
+                &self.session_timeout.as_secs(),
+                "Maximal time in seconds that each session can take before failing on timeout.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "idle_connection_timeout",
+                &self.idle_connection_timeout.as_secs(),
+                "Amount of time in seconds that a connection with no active sessions will stay \
+                 alive.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "bootstrap_peer_multiaddrs",
+                &serialize_slice(&self.bootstrap_peer_multiaddrs),
+                "Space separated multiaddresses of the peers that the node connects to on startup \
+                 to discover other peers, each including its peer id. If empty, the node waits \
+                 for other nodes to connect to it.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "peer_ban_duration",
+                &self.peer_ban_duration.as_secs(),
+                "Time in seconds that a peer is banned for after misbehaving, for example after \
+                 sending invalid messages or timing out too many times.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_message_size",
+                &self.max_message_size,
+                "Maximal size in bytes of a message that the node accepts from a peer.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_inbound_sessions_per_peer",
+                &self.max_inbound_sessions_per_peer,
+                "Maximal number of queries of a single peer that the node answers at the same \
+                 time. Other queries of the peer are rejected.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_bytes_per_second_served",
+                &self.max_bytes_per_second_served,
+                "Maximal number of bytes per second that the node sends in answers to the queries \
+                 of all of its peers.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            tcp_port: 10000,
+            session_timeout: Duration::from_secs(10),
+            idle_connection_timeout: Duration::from_secs(10),
+            bootstrap_peer_multiaddrs: vec![],
+            peer_ban_duration: Duration::from_secs(3600),
+            max_message_size: MAX_MESSAGE_SIZE,
+            max_inbound_sessions_per_peer: 10,
+            max_bytes_per_second_served: NonZeroU64::new(10 * (1 << 20))
+                .expect("The default bandwidth should be positive."),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NetworkError {
+    #[error(transparent)]
+    Noise(#[from] noise::Error),
+    #[error(transparent)]
+    Listen(#[from] TransportError<io::Error>),
+    #[error(transparent)]
+    Dial(#[from] DialError),
+    #[error(transparent)]
+    MissingPeerId(#[from] MissingPeerId),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QueryError {
+    #[error("The node isn't connected to any peer.")]
+    NoPeers,
+    #[error("The network manager stopped.")]
+    NetworkManagerStopped,
+    #[error(transparent)]
+    InvalidQuery(#[from] InvalidBlockQuery),
+    #[error(transparent)]
+    InvalidClassesQuery(#[from] InvalidClassesQuery),
+    #[error("The session with the peer failed: {reason}")]
+    SessionFailed { reason: String },
+}
+
+/// The responses of a peer to a query. The stream ends when the peer finishes responding or when
+/// the session is closed. If the session fails, the stream ends with the error, so that the query
+/// can be sent again to another peer.
+pub type QueryResponses<Response> = UnboundedReceiver<Result<Response, QueryError>>;
+
+pub type BlockHeadersResponses = QueryResponses<protobuf::BlockHeadersResponse>;
+pub type StateDiffsResponses = QueryResponses<protobuf::StateDiffsResponse>;
+pub type ClassesResponses = QueryResponses<protobuf::ClassesResponse>;
+
+/// A query that was sent to a peer.
+#[derive(Debug)]
+pub struct QuerySession<Response> {
+    pub outbound_session_id: OutboundSessionId,
+    pub responses: QueryResponses<Response>,
+}
+
+type QueryResultSender<Response> = oneshot::Sender<Result<QuerySession<Response>, QueryError>>;
+type BlockProvidersSender = oneshot::Sender<Vec<PeerId>>;
+
+struct QueryRequest<QueryT, Response> {
+    query: QueryT,
+    result_sender: QueryResultSender<Response>,
+}
+
+// A request of a querier to close a session, with the misconduct of the peer that caused it, if
+// any.
+struct CloseSessionRequest {
+    outbound_session_id: OutboundSessionId,
+    misconduct: Option<PeerMisconduct>,
+}
+
+/// Sends the queries of a single protocol to the peers of the node through the network manager.
+pub struct Querier<QueryT, Response> {
+    query_request_sender: UnboundedSender<QueryRequest<QueryT, Response>>,
+    close_session_request_sender: UnboundedSender<CloseSessionRequest>,
+    block_providers_request_sender: UnboundedSender<BlockProvidersSender>,
+}
+
+pub type BlockHeadersQuerier = Querier<BlockQuery, protobuf::BlockHeadersResponse>;
+pub type StateDiffsQuerier = Querier<BlockQuery, protobuf::StateDiffsResponse>;
+pub type ClassesQuerier = Querier<ClassesQuery, protobuf::ClassesResponse>;
+
+// Derived Clone would require the responses to be Clone.
+impl<QueryT, Response> Clone for Querier<QueryT, Response> {
+    fn clone(&self) -> Self {
+        Self {
+            query_request_sender: self.query_request_sender.clone(),
+            close_session_request_sender: self.close_session_request_sender.clone(),
+            block_providers_request_sender: self.block_providers_request_sender.clone(),
+        }
+    }
+}
+
+impl<QueryT, Response> Querier<QueryT, Response> {
+    /// Sends the query to one of the peers of the node and returns the responses of the peer.
+    pub async fn query(&self, query: QueryT) -> Result<QuerySession<Response>, QueryError> {
+        let (result_sender, result_receiver) = oneshot::channel();
+        self.query_request_sender
+            .unbounded_send(QueryRequest { query, result_sender })
+            .map_err(|_| QueryError::NetworkManagerStopped)?;
+        result_receiver.await.map_err(|_| QueryError::NetworkManagerStopped)?
+    }
+
+    /// Closes the session of a query before the peer finishes responding, for example when the
+    /// peer is too slow. The responses stream of the query ends.
+    pub fn close_session(&self, outbound_session_id: OutboundSessionId) -> Result<(), QueryError> {
+        self.close_session_request_sender
+            .unbounded_send(CloseSessionRequest { outbound_session_id, misconduct: None })
+            .map_err(|_| QueryError::NetworkManagerStopped)
+    }
+
+    /// Closes the session of a query whose peer sent data that contradicts the chain. The peer is
+    /// banned and disconnected, so the next queries are sent to other peers.
+    pub fn report_session(&self, outbound_session_id: OutboundSessionId) -> Result<(), QueryError> {
+        self.close_session_request_sender
+            .unbounded_send(CloseSessionRequest {
+                outbound_session_id,
+                misconduct: Some(PeerMisconduct::InvalidData),
+            })
+            .map_err(|_| QueryError::NetworkManagerStopped)
+    }
+
+    /// Returns the connected peers that are known to serve blocks.
+    pub async fn get_block_providers(&self) -> Result<Vec<PeerId>, QueryError> {
+        let (block_providers_sender, block_providers_receiver) = oneshot::channel();
+        self.block_providers_request_sender
+            .unbounded_send(block_providers_sender)
+            .map_err(|_| QueryError::NetworkManagerStopped)?;
+        block_providers_receiver.await.map_err(|_| QueryError::NetworkManagerStopped)
+    }
+}
+
+// A response that ends the responses to a query, with the reason if the query wasn't answered in
+// full.
+trait FinResponse {
+    fn fin(error: Option<protobuf::fin::Error>) -> Self;
+}
+
+impl FinResponse for protobuf::BlockHeadersResponse {
+    fn fin(error: Option<protobuf::fin::Error>) -> Self {
+        Self {
+            part: vec![protobuf::BlockHeadersResponsePart {
+                header_message: Some(protobuf::block_headers_response_part::HeaderMessage::Fin(
+                    protobuf::Fin { error: error.map(i32::from) },
+                )),
+            }],
+        }
+    }
+}
+
+impl FinResponse for protobuf::ClassesResponse {
+    fn fin(error: Option<protobuf::fin::Error>) -> Self {
+        Self {
+            class_hash: None,
+            class_message: Some(protobuf::classes_response::ClassMessage::Fin(protobuf::Fin {
+                error: error.map(i32::from),
+            })),
+        }
+    }
+}
+
+impl FinResponse for protobuf::StateDiffsResponse {
+    fn fin(error: Option<protobuf::fin::Error>) -> Self {
+        Self {
+            block_id: None,
+            state_diff_message: Some(protobuf::state_diffs_response::StateDiffMessage::Fin(
+                protobuf::Fin { error: error.map(i32::from) },
+            )),
+        }
+    }
+}
+
+struct OutboundSession<Response> {
+    data_sender: UnboundedSender<Result<Response, QueryError>>,
+    peer_id: PeerId,
+    start_time: Instant,
+    received_bytes: u64,
+}
+
+// The responses to an inbound session, followed by None once the session should be closed.
+type InboundSessionResponses<Response> = BoxStream<'static, (InboundSessionId, Option<Response>)>;
+
+// The sessions of a single protocol, and the channels through which its queriers reach the network
+// manager. The limit on the concurrent queries of a peer applies to each protocol separately, while
+// the bandwidth limit is shared by all the protocols.
+struct ProtocolSessions<QueryT, Response, DBExecutorT> {
+    db_executor: DBExecutorT,
+    max_inbound_sessions_per_peer: usize,
+    bandwidth_limiter: Arc<Mutex<BandwidthLimiter>>,
+    query_request_sender: UnboundedSender<QueryRequest<QueryT, Response>>,
+    query_request_receiver: UnboundedReceiver<QueryRequest<QueryT, Response>>,
+    close_session_request_sender: UnboundedSender<CloseSessionRequest>,
+    close_session_request_receiver: UnboundedReceiver<CloseSessionRequest>,
+    outbound_sessions: HashMap<OutboundSessionId, OutboundSession<Response>>,
+    inbound_session_responses: SelectAll<InboundSessionResponses<Response>>,
+    inbound_session_peers: HashMap<InboundSessionId, PeerId>,
+}
+
+/// The peers that the node is connected to, updated by the network manager while it runs.
+pub type ConnectedPeers = Arc<RwLock<Vec<PeerId>>>;
+
+pub struct GenericNetworkManager<
+    BlockHeadersDBExecutorT: ReaderExecutor<BlockQuery, protobuf::BlockHeadersResponse>,
+    StateDiffsDBExecutorT: ReaderExecutor<BlockQuery, protobuf::StateDiffsResponse>,
+    ClassesDBExecutorT: ReaderExecutor<ClassesQuery, protobuf::ClassesResponse>,
+> {
+    swarm: Swarm<MixedBehaviour>,
+    peer_set: PeerSet,
+    connected_peers: ConnectedPeers,
+    peer_manager: PeerManager,
+    discovery_ticks: stream::Fuse<IntervalStream>,
+    block_providers_request_sender: UnboundedSender<BlockProvidersSender>,
+    block_providers_request_receiver: UnboundedReceiver<BlockProvidersSender>,
+    block_headers:
+        ProtocolSessions<BlockQuery, protobuf::BlockHeadersResponse, BlockHeadersDBExecutorT>,
+    state_diffs: ProtocolSessions<BlockQuery, protobuf::StateDiffsResponse, StateDiffsDBExecutorT>,
+    classes: ProtocolSessions<ClassesQuery, protobuf::ClassesResponse, ClassesDBExecutorT>,
+}
+
+pub type NetworkManager =
+    GenericNetworkManager<BlockHeadersDBExecutor, StateDiffsDBExecutor, ClassesDBExecutor>;
+
+impl NetworkManager {
+    /// Creates the network manager of a node of the given chain, which answers the queries of its
+    /// peers from the storage.
+    pub fn new(
+        config: NetworkConfig,
+        storage_reader: StorageReader,
+        chain_id: ChainId,
+    ) -> Result<Self, NetworkError> {
+        let listen_address: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", config.tcp_port)
+            .parse()
+            .expect("The listen address should be a valid multiaddress.");
+        let session_config = |protocol_name| streamed_data::Config {
+            substream_timeout: config.session_timeout,
+            protocol_name,
+            max_message_size: config.max_message_size,
+        };
+        let mut swarm = SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(tcp::Config::default(), noise::Config::new, yamux::Config::default)?
+            .with_behaviour(|keypair| {
+                MixedBehaviour::new(
+                    keypair,
+                    session_config(BLOCK_HEADERS_PROTOCOL_NAME),
+                    session_config(STATE_DIFFS_PROTOCOL_NAME),
+                    session_config(CLASSES_PROTOCOL_NAME),
+                )
+            })
+            .expect("Creating the behaviour should not fail.")
+            .with_swarm_config(|swarm_config| {
+                swarm_config.with_idle_connection_timeout(config.idle_connection_timeout)
+            })
+            .build();
+        swarm.listen_on(listen_address)?;
+        info!("The local peer id of the node is {}.", swarm.local_peer_id());
+        // The other peers are discovered through the bootstrap peers.
+        for bootstrap_peer_multiaddr in &config.bootstrap_peer_multiaddrs {
+            let peer_id = get_peer_id(bootstrap_peer_multiaddr)?;
+            swarm.behaviour_mut().kademlia.add_address(&peer_id, bootstrap_peer_multiaddr.clone());
+            swarm.dial(bootstrap_peer_multiaddr.clone())?;
+        }
+        Ok(Self::from_swarm(
+            swarm,
+            BlockHeadersDBExecutor::new(storage_reader.clone(), chain_id),
+            StateDiffsDBExecutor::new(storage_reader.clone()),
+            ClassesDBExecutor::new(storage_reader),
+            &config,
+        ))
+    }
+}
+
+impl<
+    BlockHeadersDBExecutorT: ReaderExecutor<BlockQuery, protobuf::BlockHeadersResponse>,
+    StateDiffsDBExecutorT: ReaderExecutor<BlockQuery, protobuf::StateDiffsResponse>,
+    ClassesDBExecutorT: ReaderExecutor<ClassesQuery, protobuf::ClassesResponse>,
+> GenericNetworkManager<BlockHeadersDBExecutorT, StateDiffsDBExecutorT, ClassesDBExecutorT>
+{
+    // The swarm should have been built according to the config.
+    fn from_swarm(
+        swarm: Swarm<MixedBehaviour>,
+        block_headers_db_executor: BlockHeadersDBExecutorT,
+        state_diffs_db_executor: StateDiffsDBExecutorT,
+        classes_db_executor: ClassesDBExecutorT,
+        config: &NetworkConfig,
+    ) -> Self {
+        let (block_providers_request_sender, block_providers_request_receiver) = unbounded();
+        // The swarm might already be connected to peers.
+        let mut peer_set = PeerSet::default();
+        for peer_id in swarm.connected_peers() {
+            peer_set.add_peer(*peer_id);
+        }
+        let connected_peers = Arc::new(RwLock::new(swarm.connected_peers().copied().collect()));
+        let bandwidth_limiter =
+            Arc::new(Mutex::new(BandwidthLimiter::new(config.max_bytes_per_second_served)));
+        Self {
+            swarm,
+            peer_set,
+            connected_peers,
+            peer_manager: PeerManager::new(config.peer_ban_duration),
+            discovery_ticks: IntervalStream::new(tokio::time::interval(DISCOVERY_INTERVAL)).fuse(),
+            block_providers_request_sender,
+            block_providers_request_receiver,
+            block_headers: ProtocolSessions::new(
+                block_headers_db_executor,
+                config,
+                bandwidth_limiter.clone(),
+            ),
+            state_diffs: ProtocolSessions::new(
+                state_diffs_db_executor,
+                config,
+                bandwidth_limiter.clone(),
+            ),
+            classes: ProtocolSessions::new(classes_db_executor, config, bandwidth_limiter),
+        }
+    }
+
+    pub fn get_block_headers_querier(&self) -> BlockHeadersQuerier {
+        self.block_headers.querier(self.block_providers_request_sender.clone())
+    }
+
+    pub fn get_state_diffs_querier(&self) -> StateDiffsQuerier {
+        self.state_diffs.querier(self.block_providers_request_sender.clone())
+    }
+
+    pub fn get_classes_querier(&self) -> ClassesQuerier {
+        self.classes.querier(self.block_providers_request_sender.clone())
+    }
+
+    pub fn get_connected_peers(&self) -> ConnectedPeers {
+        self.connected_peers.clone()
+    }
+
+    /// Runs the swarm and serves the queriers. Never returns.
+    pub async fn run(mut self) {
+        loop {
+            select! {
+                event = self.swarm.select_next_some() => self.handle_swarm_event(event),
+                query_request = self.block_headers.query_request_receiver.select_next_some() => {
+                    let peer_id = self.choose_peer(&BLOCK_HEADERS_PROTOCOL_NAME);
+                    let behaviour = &mut self.swarm.behaviour_mut().block_headers;
+                    self.block_headers.send_query(query_request, peer_id, behaviour);
+                }
+                query_request = self.state_diffs.query_request_receiver.select_next_some() => {
+                    let peer_id = self.choose_peer(&STATE_DIFFS_PROTOCOL_NAME);
+                    let behaviour = &mut self.swarm.behaviour_mut().state_diffs;
+                    self.state_diffs.send_query(query_request, peer_id, behaviour);
+                }
+                query_request = self.classes.query_request_receiver.select_next_some() => {
+                    let peer_id = self.choose_peer(&CLASSES_PROTOCOL_NAME);
+                    let behaviour = &mut self.swarm.behaviour_mut().classes;
+                    self.classes.send_query(query_request, peer_id, behaviour);
+                }
+                (inbound_session_id, response) =
+                    self.block_headers.inbound_session_responses.select_next_some() => {
+                    self.block_headers.handle_inbound_session_response(
+                        inbound_session_id,
+                        response,
+                        &mut self.swarm.behaviour_mut().block_headers,
+                    )
+                }
+                (inbound_session_id, response) =
+                    self.state_diffs.inbound_session_responses.select_next_some() => {
+                    self.state_diffs.handle_inbound_session_response(
+                        inbound_session_id,
+                        response,
+                        &mut self.swarm.behaviour_mut().state_diffs,
+                    )
+                }
+                (inbound_session_id, response) =
+                    self.classes.inbound_session_responses.select_next_some() => {
+                    self.classes.handle_inbound_session_response(
+                        inbound_session_id,
+                        response,
+                        &mut self.swarm.behaviour_mut().classes,
+                    )
+                }
+                CloseSessionRequest { outbound_session_id, misconduct } =
+                    self.block_headers.close_session_request_receiver.select_next_some() => {
+                    let banned_peer_id = self.block_headers.close_outbound_session(
+                        outbound_session_id,
+                        misconduct,
+                        &mut self.swarm.behaviour_mut().block_headers,
+                        &mut self.peer_manager,
+                    );
+                    if let Some(peer_id) = banned_peer_id {
+                        let _ = self.swarm.disconnect_peer_id(peer_id);
+                    }
+                }
+                CloseSessionRequest { outbound_session_id, misconduct } =
+                    self.state_diffs.close_session_request_receiver.select_next_some() => {
+                    let banned_peer_id = self.state_diffs.close_outbound_session(
+                        outbound_session_id,
+                        misconduct,
+                        &mut self.swarm.behaviour_mut().state_diffs,
+                        &mut self.peer_manager,
+                    );
+                    if let Some(peer_id) = banned_peer_id {
+                        let _ = self.swarm.disconnect_peer_id(peer_id);
+                    }
+                }
+                CloseSessionRequest { outbound_session_id, misconduct } =
+                    self.classes.close_session_request_receiver.select_next_some() => {
+                    let banned_peer_id = self.classes.close_outbound_session(
+                        outbound_session_id,
+                        misconduct,
+                        &mut self.swarm.behaviour_mut().classes,
+                        &mut self.peer_manager,
+                    );
+                    if let Some(peer_id) = banned_peer_id {
+                        let _ = self.swarm.disconnect_peer_id(peer_id);
+                    }
+                }
+                block_providers_sender =
+                    self.block_providers_request_receiver.select_next_some() => {
+                    // The querier might have stopped waiting, which is fine.
+                    let _ = block_providers_sender.send(self.peer_set.block_providers());
+                }
+                _ = self.discovery_ticks.select_next_some() => self.discover_peers(),
+            }
+        }
+    }
+
+    // Peers that are known to serve the protocol are preferred over peers that didn't identify
+    // themselves yet.
+    // TODO(shahak): Choose the peer according to its blocks as well.
+    fn choose_peer(&mut self, protocol: &StreamProtocol) -> Option<PeerId> {
+        self.peer_manager
+            .choose_peer(self.peer_set.providers(protocol))
+            .or_else(|| self.peer_manager.choose_peer(self.peer_set.unidentified_peers()))
+    }
+
+    fn update_connected_peers(&self) {
+        let connected_peers: Vec<PeerId> = self.swarm.connected_peers().copied().collect();
+        metrics::gauge!(papyrus_metrics::PAPYRUS_CONNECTED_PEERS, connected_peers.len() as f64);
+        *self.connected_peers.write().expect("The connected peers shouldn't be poisoned.") =
+            connected_peers;
+    }
+
+    fn handle_swarm_event(&mut self, event: SwarmEvent<MixedBehaviourEvent>) {
+        let banned_peer_id = match event {
+            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                if self.peer_manager.is_banned(&peer_id) {
+                    debug!("Disconnecting from banned peer {peer_id}.");
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                    return;
+                }
+                debug!("Connected to peer {peer_id}.");
+                self.peer_set.add_peer(peer_id);
+                self.update_connected_peers();
+                None
+            }
+            SwarmEvent::ConnectionClosed { peer_id, cause, num_established, .. } => {
+                debug!("Connection to peer {peer_id} closed. Cause: {cause:?}.");
+                if num_established == 0 {
+                    self.peer_set.remove_peer(&peer_id);
+                    self.update_connected_peers();
+                }
+                None
+            }
+            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+                warn!("Failed to connect to peer {peer_id:?}: {error}.");
+                None
+            }
+            SwarmEvent::Behaviour(MixedBehaviourEvent::BlockHeaders(event)) => {
+                self.block_headers.handle_behaviour_event(
+                    event,
+                    &mut self.swarm.behaviour_mut().block_headers,
+                    &mut self.peer_manager,
+                )
+            }
+            SwarmEvent::Behaviour(MixedBehaviourEvent::StateDiffs(event)) => {
+                self.state_diffs.handle_behaviour_event(
+                    event,
+                    &mut self.swarm.behaviour_mut().state_diffs,
+                    &mut self.peer_manager,
+                )
+            }
+            SwarmEvent::Behaviour(MixedBehaviourEvent::Classes(event)) => {
+                self.classes.handle_behaviour_event(
+                    event,
+                    &mut self.swarm.behaviour_mut().classes,
+                    &mut self.peer_manager,
+                )
+            }
+            SwarmEvent::Behaviour(MixedBehaviourEvent::Identify(event)) => {
+                self.handle_identify_event(event);
+                None
+            }
+            SwarmEvent::Behaviour(MixedBehaviourEvent::Kademlia(event)) => {
+                self.handle_kademlia_event(event);
+                None
+            }
+            _ => None,
+        };
+        if let Some(peer_id) = banned_peer_id {
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+        }
+    }
+
+    // The addresses that a peer listens on are added to the DHT, so that other peers can find it
+    // through the node.
+    fn handle_identify_event(&mut self, event: identify::Event) {
+        if let identify::Event::Received { peer_id, info } = event {
+            debug!("Peer {peer_id} identified itself with the protocols {:?}.", info.protocols);
+            for listen_addr in &info.listen_addrs {
+                self.swarm.behaviour_mut().kademlia.add_address(&peer_id, listen_addr.clone());
+            }
+            self.peer_set.set_peer_info(peer_id, &info);
+        }
+    }
+
+    fn handle_kademlia_event(&mut self, event: kad::Event) {
+        if let kad::Event::RoutingUpdated { peer, is_new_peer: true, .. } = event {
+            debug!("Discovered peer {peer}.");
+            if self.peer_manager.is_banned(&peer) {
+                return;
+            }
+            if let Err(err) = self.swarm.dial(peer) {
+                debug!("Failed to dial discovered peer {peer}: {err}.");
+            }
+        }
+    }
+
+    fn discover_peers(&mut self) {
+        // Bootstrapping fails only if the DHT has no peers yet, in which case the node waits for
+        // other nodes to connect to it.
+        if let Err(err) = self.swarm.behaviour_mut().kademlia.bootstrap() {
+            debug!("Failed to look for new peers: {err:?}.");
+        }
+    }
+}
+
+impl<QueryT, Response, DBExecutorT> ProtocolSessions<QueryT, Response, DBExecutorT>
+where
+    QueryT: Clone + Debug + Send + 'static,
+    Response: DataBound + FinResponse + Send,
+    DBExecutorT: ReaderExecutor<QueryT, Response>,
+{
+    fn new(
+        db_executor: DBExecutorT,
+        config: &NetworkConfig,
+        bandwidth_limiter: Arc<Mutex<BandwidthLimiter>>,
+    ) -> Self {
+        let (query_request_sender, query_request_receiver) = unbounded();
+        let (close_session_request_sender, close_session_request_receiver) = unbounded();
+        Self {
+            db_executor,
+            max_inbound_sessions_per_peer: config.max_inbound_sessions_per_peer,
+            bandwidth_limiter,
+            query_request_sender,
+            query_request_receiver,
+            close_session_request_sender,
+            close_session_request_receiver,
+            outbound_sessions: HashMap::new(),
+            inbound_session_responses: SelectAll::new(),
+            inbound_session_peers: HashMap::new(),
+        }
+    }
+
+    fn querier(
+        &self,
+        block_providers_request_sender: UnboundedSender<BlockProvidersSender>,
+    ) -> Querier<QueryT, Response> {
+        Querier {
+            query_request_sender: self.query_request_sender.clone(),
+            close_session_request_sender: self.close_session_request_sender.clone(),
+            block_providers_request_sender,
+        }
+    }
+
+    fn send_query<Query: QueryBound + TryFrom<QueryT>>(
+        &mut self,
+        QueryRequest { query, result_sender }: QueryRequest<QueryT, Response>,
+        peer_id: Option<PeerId>,
+        behaviour: &mut Behaviour<Query, Response>,
+    ) where
+        QueryError: From<Query::Error>,
+    {
+        let request = match Query::try_from(query.clone()) {
+            Ok(request) => request,
+            Err(err) => {
+                // The querier might have stopped waiting, which is fine.
+                let _ = result_sender.send(Err(err.into()));
+                return;
+            }
+        };
+        let Some(peer_id) = peer_id else {
+            // The querier might have stopped waiting, which is fine.
+            let _ = result_sender.send(Err(QueryError::NoPeers));
+            return;
+        };
+        let outbound_session_id = match behaviour.send_query(request, peer_id) {
+            Ok(outbound_session_id) => outbound_session_id,
+            Err(_) => {
+                let _ = result_sender.send(Err(QueryError::NoPeers));
+                return;
+            }
+        };
+        debug!("Sent query {query:?} to peer {peer_id} in session {outbound_session_id}.");
+        let (data_sender, data_receiver) = unbounded();
+        self.outbound_sessions.insert(
+            outbound_session_id,
+            OutboundSession { data_sender, peer_id, start_time: Instant::now(), received_bytes: 0 },
+        );
+        let _ =
+            result_sender.send(Ok(QuerySession { outbound_session_id, responses: data_receiver }));
+    }
+
+    // Returns the peer that was banned because of the event, if any.
+    fn handle_behaviour_event<Query: QueryBound>(
+        &mut self,
+        event: Event<Query, Response>,
+        behaviour: &mut Behaviour<Query, Response>,
+        peer_manager: &mut PeerManager,
+    ) -> Option<PeerId>
+    where
+        QueryT: TryFrom<Query, Error = ProtobufConversionError>,
+    {
+        match event {
+            Event::NewInboundSession { query, inbound_session_id, peer_id } => {
+                let query = match QueryT::try_from(query) {
+                    Ok(query) => query,
+                    Err(err) => {
+                        debug!("Peer {peer_id} sent an invalid query: {err}.");
+                        let fin_error = match err {
+                            ProtobufConversionError::InvalidQuery(
+                                InvalidBlockQuery::LimitTooLarge { .. },
+                            )
+                            | ProtobufConversionError::InvalidClassesQuery(
+                                InvalidClassesQuery::TooManyClasses { .. },
+                            ) => protobuf::fin::Error::TooMuch,
+                            _ => protobuf::fin::Error::Unknown,
+                        };
+                        self.reject_inbound_session(inbound_session_id, fin_error);
+                        return None;
+                    }
+                };
+                let num_peer_sessions = self
+                    .inbound_session_peers
+                    .values()
+                    .filter(|session_peer_id| **session_peer_id == peer_id)
+                    .count();
+                if num_peer_sessions >= self.max_inbound_sessions_per_peer {
+                    debug!("Peer {peer_id} sent too many concurrent queries, rejecting {query:?}.");
+                    self.reject_inbound_session(inbound_session_id, protobuf::fin::Error::Busy);
+                    return None;
+                }
+                debug!(
+                    "Peer {peer_id} sent query {query:?} in inbound session {inbound_session_id}."
+                );
+                self.inbound_session_peers.insert(inbound_session_id, peer_id);
+                self.start_inbound_session(inbound_session_id, query);
+                None
+            }
+            Event::ReceivedData { outbound_session_id, data } => {
+                let outbound_session = self.outbound_sessions.get_mut(&outbound_session_id)?;
+                outbound_session.received_bytes += data.encoded_len() as u64;
+                if outbound_session.data_sender.unbounded_send(Ok(data)).is_err() {
+                    // The querier stopped waiting for the responses.
+                    self.close_outbound_session(outbound_session_id, None, behaviour, peer_manager);
+                }
+                None
+            }
+            Event::SessionFailed { session_id, error } => {
+                debug!("Session {session_id:?} failed: {error}.");
+                let mut banned_peer_id = None;
+                if let SessionId::OutboundSessionId(outbound_session_id) = session_id {
+                    banned_peer_id =
+                        self.report_session_failure(outbound_session_id, &error, peer_manager);
+                    if let Some(outbound_session) = self.outbound_sessions.get(&outbound_session_id)
+                    {
+                        // The querier might have stopped waiting, which is fine.
+                        let _ = outbound_session.data_sender.unbounded_send(Err(
+                            QueryError::SessionFailed { reason: error.to_string() },
+                        ));
+                    }
+                }
+                self.remove_session(session_id, peer_manager);
+                banned_peer_id
+            }
+            Event::SessionClosedByRequest { session_id }
+            | Event::SessionClosedByPeer { session_id } => {
+                self.remove_session(session_id, peer_manager);
+                None
+            }
+        }
+    }
+
+    // Reads the responses to the query from the storage. Once the reading finishes, a fin response
+    // is sent, with an error if the reading failed, and the session is closed. The responses are
+    // delayed as needed so that the responses to all the peers fit in the bandwidth limit.
+    fn start_inbound_session(&mut self, inbound_session_id: InboundSessionId, query: QueryT) {
+        let ReaderCommunication { result_receiver, is_finished } =
+            self.db_executor.start_reading(query);
+        let bandwidth_limiter = self.bandwidth_limiter.clone();
+        let fin = is_finished.map(move |result| {
+            let error = match result {
+                Ok(Ok(())) => None,
+                Ok(Err(err)) => {
+                    debug!("Failed reading the responses of session {inbound_session_id}: {err}.");
+                    Some(protobuf::fin::Error::Unknown)
+                }
+                Err(_) => Some(protobuf::fin::Error::Unknown),
+            };
+            Response::fin(error)
+        });
+        let responses = result_receiver
+            .chain(fin.into_stream())
+            .then(move |response| {
+                let send_time = bandwidth_limiter
+                    .lock()
+                    .expect("The bandwidth limiter shouldn't be poisoned.")
+                    .reserve(response.encoded_len() as u64);
+                tokio::time::sleep_until(send_time.into()).map(|()| response)
+            })
+            .map(Some)
+            .chain(stream::once(ready(None)))
+            .map(move |response| (inbound_session_id, response))
+            .boxed();
+        self.inbound_session_responses.push(responses);
+    }
+
+    // Answers a query that won't be served with a fin response that holds the reason of the
+    // rejection.
+    fn reject_inbound_session(
+        &mut self,
+        inbound_session_id: InboundSessionId,
+        fin_error: protobuf::fin::Error,
+    ) {
+        self.inbound_session_responses.push(
+            stream::iter([Some(Response::fin(Some(fin_error))), None])
+                .map(move |response| (inbound_session_id, response))
+                .boxed(),
+        );
+    }
+
+    fn handle_inbound_session_response<Query: QueryBound>(
+        &mut self,
+        inbound_session_id: InboundSessionId,
+        response: Option<Response>,
+        behaviour: &mut Behaviour<Query, Response>,
+    ) {
+        // Fails if the session was closed by the peer or failed, which is fine.
+        let _ = match response {
+            Some(response) => behaviour.send_data(response, inbound_session_id),
+            None => {
+                self.inbound_session_peers.remove(&inbound_session_id);
+                behaviour.close_session(inbound_session_id.into())
+            }
+        };
+    }
+
+    // Dropping the sender of an outbound session ends the responses stream of its querier.
+    fn remove_session(&mut self, session_id: SessionId, peer_manager: &mut PeerManager) {
+        match session_id {
+            SessionId::OutboundSessionId(outbound_session_id) => {
+                if let Some(OutboundSession { peer_id, start_time, received_bytes, .. }) =
+                    self.outbound_sessions.remove(&outbound_session_id)
+                {
+                    peer_manager.report_session(peer_id, received_bytes, start_time.elapsed());
+                }
+            }
+            SessionId::InboundSessionId(inbound_session_id) => {
+                self.inbound_session_peers.remove(&inbound_session_id);
+            }
+        }
+    }
+
+    // Returns the peer of the session if it was banned because of the given misconduct.
+    fn close_outbound_session<Query: QueryBound>(
+        &mut self,
+        outbound_session_id: OutboundSessionId,
+        misconduct: Option<PeerMisconduct>,
+        behaviour: &mut Behaviour<Query, Response>,
+        peer_manager: &mut PeerManager,
+    ) -> Option<PeerId> {
+        debug!("Closing outbound session {outbound_session_id}.");
+        let banned_peer_id = misconduct.and_then(|misconduct| {
+            let peer_id = self.outbound_sessions.get(&outbound_session_id)?.peer_id;
+            if !peer_manager.report_misconduct(peer_id, misconduct) {
+                return None;
+            }
+            warn!("Banning peer {peer_id} after {misconduct:?}.");
+            Some(peer_id)
+        });
+        self.remove_session(outbound_session_id.into(), peer_manager);
+        // Fails if the session already ended, which is fine.
+        let _ = behaviour.close_session(outbound_session_id.into());
+        banned_peer_id
+    }
+
+    // Failures that might be caused by the network or by the node itself aren't held against the
+    // peer. Returns the peer if it was banned because of the failure.
+    fn report_session_failure(
+        &mut self,
+        outbound_session_id: OutboundSessionId,
+        error: &SessionError,
+        peer_manager: &mut PeerManager,
+    ) -> Option<PeerId> {
+        let peer_id = self.outbound_sessions.get(&outbound_session_id)?.peer_id;
+        let misconduct = match error {
+            SessionError::Timeout { .. } => PeerMisconduct::Timeout,
+            SessionError::IOError(io_error) if io_error.kind() == io::ErrorKind::InvalidData => {
+                PeerMisconduct::InvalidMessage
+            }
+            _ => return None,
+        };
+        if !peer_manager.report_misconduct(peer_id, misconduct) {
+            return None;
+        }
+        warn!("Banning peer {peer_id} after {misconduct:?}.");
+        Some(peer_id)
+    }
+}
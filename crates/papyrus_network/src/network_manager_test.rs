@@ -0,0 +1,520 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use futures::channel::mpsc::unbounded;
+use futures::channel::oneshot;
+use futures::StreamExt;
+use libp2p::swarm::{StreamProtocol, SwarmEvent};
+use libp2p::Swarm;
+use libp2p_swarm_test::SwarmExt;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::ChainId;
+use starknet_api::hash::StarkHash;
+
+use super::{
+    FinResponse,
+    GenericNetworkManager,
+    NetworkConfig,
+    QueryError,
+    QuerySession,
+    BLOCK_HEADERS_PROTOCOL_NAME,
+    CLASSES_PROTOCOL_NAME,
+    STATE_DIFFS_PROTOCOL_NAME,
+};
+use crate::discovery::MixedBehaviour;
+use crate::executor::db_executor::{
+    BlockHeadersDBExecutor,
+    MockReaderExecutor,
+    ReaderCommunication,
+};
+use crate::messages::{protobuf, MAX_MESSAGE_SIZE};
+use crate::peer_manager::PeerMisconduct;
+use crate::streamed_data::behaviour::{Behaviour, Event};
+use crate::streamed_data::{Config, SessionId};
+use crate::{
+    BlockID,
+    BlockQuery,
+    ClassesQuery,
+    Direction,
+    InvalidBlockQuery,
+    InvalidClassesQuery,
+    MAX_BLOCKS_PER_QUERY,
+};
+
+const QUERY: BlockQuery = BlockQuery {
+    start: BlockID::Number(BlockNumber(1)),
+    direction: Direction::Forward,
+    limit: 2,
+    skip: 0,
+    step: 1,
+};
+
+type BlockHeadersBehaviour =
+    Behaviour<protobuf::BlockHeadersRequest, protobuf::BlockHeadersResponse>;
+type StateDiffsBehaviour = Behaviour<protobuf::StateDiffsRequest, protobuf::StateDiffsResponse>;
+
+fn config(protocol_name: StreamProtocol) -> Config {
+    Config {
+        substream_timeout: Duration::from_secs(10),
+        protocol_name,
+        max_message_size: MAX_MESSAGE_SIZE,
+    }
+}
+
+fn create_swarm() -> Swarm<MixedBehaviour> {
+    Swarm::new_ephemeral(|keypair| {
+        MixedBehaviour::new(
+            keypair,
+            config(BLOCK_HEADERS_PROTOCOL_NAME),
+            config(STATE_DIFFS_PROTOCOL_NAME),
+            config(CLASSES_PROTOCOL_NAME),
+        )
+    })
+}
+
+// The peers only serve block headers, without discovery.
+fn create_peer_swarm() -> Swarm<BlockHeadersBehaviour> {
+    Swarm::new_ephemeral(|_| Behaviour::new(config(BLOCK_HEADERS_PROTOCOL_NAME)))
+}
+
+fn create_state_diffs_peer_swarm() -> Swarm<StateDiffsBehaviour> {
+    Swarm::new_ephemeral(|_| Behaviour::new(config(STATE_DIFFS_PROTOCOL_NAME)))
+}
+
+fn responses() -> Vec<protobuf::BlockHeadersResponse> {
+    (1..=2)
+        .map(|number| protobuf::BlockHeadersResponse {
+            part: vec![protobuf::BlockHeadersResponsePart {
+                header_message: Some(protobuf::block_headers_response_part::HeaderMessage::Header(
+                    protobuf::BlockHeader { number, ..Default::default() },
+                )),
+            }],
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn query_is_answered_by_peer() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let querier = network_manager.get_block_headers_querier();
+    tokio::spawn(network_manager.run());
+    // The peer answers the query and closes the session.
+    tokio::spawn(async move {
+        loop {
+            let SwarmEvent::Behaviour(Event::NewInboundSession {
+                query, inbound_session_id, ..
+            }) = peer_swarm.select_next_some().await
+            else {
+                continue;
+            };
+            assert_eq!(query, protobuf::BlockHeadersRequest::try_from(QUERY).unwrap());
+            for response in responses() {
+                peer_swarm.behaviour_mut().send_data(response, inbound_session_id).unwrap();
+            }
+            peer_swarm.behaviour_mut().close_session(inbound_session_id.into()).unwrap();
+        }
+    });
+
+    let received_responses =
+        querier.query(QUERY).await.unwrap().responses.map(Result::unwrap).collect::<Vec<_>>().await;
+    assert_eq!(received_responses, responses());
+}
+
+#[tokio::test]
+async fn state_diffs_query_is_answered_by_peer() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_state_diffs_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let querier = network_manager.get_state_diffs_querier();
+    tokio::spawn(network_manager.run());
+    let responses = (1..=2)
+        .map(|number| protobuf::StateDiffsResponse {
+            block_id: Some(protobuf::BlockId { number, header: None }),
+            state_diff_message: Some(protobuf::state_diffs_response::StateDiffMessage::Diff(
+                protobuf::StateDiff::default(),
+            )),
+        })
+        .collect::<Vec<_>>();
+    // The peer answers the query and closes the session.
+    let peer_responses = responses.clone();
+    tokio::spawn(async move {
+        loop {
+            let SwarmEvent::Behaviour(Event::NewInboundSession {
+                query, inbound_session_id, ..
+            }) = peer_swarm.select_next_some().await
+            else {
+                continue;
+            };
+            assert_eq!(query, protobuf::StateDiffsRequest::try_from(QUERY).unwrap());
+            for response in peer_responses.clone() {
+                peer_swarm.behaviour_mut().send_data(response, inbound_session_id).unwrap();
+            }
+            peer_swarm.behaviour_mut().close_session(inbound_session_id.into()).unwrap();
+        }
+    });
+
+    let received_responses =
+        querier.query(QUERY).await.unwrap().responses.map(Result::unwrap).collect::<Vec<_>>().await;
+    assert_eq!(received_responses, responses);
+}
+
+#[tokio::test]
+async fn query_session_is_closed_by_querier() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let querier = network_manager.get_block_headers_querier();
+    tokio::spawn(network_manager.run());
+    // The peer sends a single response and waits for the session to be closed.
+    let (peer_closed_sender, peer_closed_receiver) = oneshot::channel();
+    tokio::spawn(async move {
+        let mut peer_closed_sender = Some(peer_closed_sender);
+        loop {
+            match peer_swarm.select_next_some().await {
+                SwarmEvent::Behaviour(Event::NewInboundSession { inbound_session_id, .. }) => {
+                    let response = responses().remove(0);
+                    peer_swarm.behaviour_mut().send_data(response, inbound_session_id).unwrap();
+                }
+                SwarmEvent::Behaviour(Event::SessionClosedByPeer { .. }) => {
+                    if let Some(peer_closed_sender) = peer_closed_sender.take() {
+                        peer_closed_sender.send(()).unwrap();
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+
+    let QuerySession { outbound_session_id, mut responses } = querier.query(QUERY).await.unwrap();
+    assert_eq!(responses.next().await.unwrap().unwrap(), self::responses().remove(0));
+    querier.close_session(outbound_session_id).unwrap();
+    assert!(responses.next().await.is_none());
+    peer_closed_receiver.await.unwrap();
+}
+
+#[tokio::test]
+async fn reported_peer_is_not_queried() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let querier = network_manager.get_block_headers_querier();
+    tokio::spawn(network_manager.run());
+    tokio::spawn(async move {
+        loop {
+            if let SwarmEvent::Behaviour(Event::NewInboundSession { inbound_session_id, .. }) =
+                peer_swarm.select_next_some().await
+            {
+                let response = responses().remove(0);
+                peer_swarm.behaviour_mut().send_data(response, inbound_session_id).unwrap();
+            }
+        }
+    });
+
+    let QuerySession { outbound_session_id, mut responses } = querier.query(QUERY).await.unwrap();
+    assert_eq!(responses.next().await.unwrap().unwrap(), self::responses().remove(0));
+    querier.report_session(outbound_session_id).unwrap();
+    assert!(responses.next().await.is_none());
+    assert_matches!(querier.query(QUERY).await, Err(QueryError::NoPeers));
+}
+
+#[tokio::test]
+async fn connected_peers_are_shared() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let connected_peers = network_manager.get_connected_peers();
+    assert_eq!(*connected_peers.read().unwrap(), vec![*peer_swarm.local_peer_id()]);
+}
+
+#[tokio::test]
+async fn query_without_peers_fails() {
+    let network_manager = GenericNetworkManager::from_swarm(
+        create_swarm(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let querier = network_manager.get_block_headers_querier();
+    tokio::spawn(network_manager.run());
+
+    assert_matches!(querier.query(QUERY).await, Err(QueryError::NoPeers));
+}
+
+#[tokio::test]
+async fn banned_peer_is_not_queried() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+    let mut network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let peer_id = *peer_swarm.local_peer_id();
+    while !network_manager.peer_manager.report_misconduct(peer_id, PeerMisconduct::Timeout) {}
+    let querier = network_manager.get_block_headers_querier();
+    tokio::spawn(network_manager.run());
+    tokio::spawn(async move {
+        loop {
+            peer_swarm.select_next_some().await;
+        }
+    });
+
+    assert_matches!(querier.query(QUERY).await, Err(QueryError::NoPeers));
+}
+
+#[tokio::test]
+async fn invalid_query_fails() {
+    let network_manager = GenericNetworkManager::from_swarm(
+        create_swarm(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let querier = network_manager.get_block_headers_querier();
+    tokio::spawn(network_manager.run());
+
+    assert_matches!(
+        querier.query(BlockQuery { step: 0, ..QUERY }).await,
+        Err(QueryError::InvalidQuery(InvalidBlockQuery::ZeroStep))
+    );
+}
+
+#[tokio::test]
+async fn invalid_classes_query_fails() {
+    let network_manager = GenericNetworkManager::from_swarm(
+        create_swarm(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    let querier = network_manager.get_classes_querier();
+    tokio::spawn(network_manager.run());
+
+    assert_matches!(
+        querier.query(ClassesQuery { class_hashes: vec![] }).await,
+        Err(QueryError::InvalidClassesQuery(InvalidClassesQuery::NoClasses))
+    );
+}
+
+#[tokio::test]
+async fn invalid_peer_query_is_rejected() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+    let peer_id = *swarm.local_peer_id();
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    tokio::spawn(network_manager.run());
+
+    let mut request = protobuf::BlockHeadersRequest::try_from(QUERY).unwrap();
+    request.iteration.as_mut().unwrap().limit = MAX_BLOCKS_PER_QUERY + 1;
+    peer_swarm.behaviour_mut().send_query(request, peer_id).unwrap();
+    let mut received_responses = vec![];
+    loop {
+        match peer_swarm.select_next_some().await {
+            SwarmEvent::Behaviour(Event::ReceivedData { data, .. }) => {
+                received_responses.push(data)
+            }
+            SwarmEvent::Behaviour(Event::SessionClosedByPeer { .. }) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        received_responses,
+        vec![protobuf::BlockHeadersResponse::fin(Some(protobuf::fin::Error::TooMuch))]
+    );
+}
+
+#[tokio::test]
+async fn too_many_peer_queries_are_rejected() {
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+    let peer_id = *swarm.local_peer_id();
+    // The first query is never answered, so its session stays open.
+    let (_result_sender, result_receiver) = unbounded();
+    let (_is_finished_sender, is_finished) = oneshot::channel();
+    let mut db_executor = MockReaderExecutor::new();
+    db_executor
+        .expect_start_reading()
+        .times(1)
+        .return_once(move |_| ReaderCommunication { result_receiver, is_finished });
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        db_executor,
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig { max_inbound_sessions_per_peer: 1, ..Default::default() },
+    );
+    tokio::spawn(network_manager.run());
+
+    for _ in 0..2 {
+        peer_swarm.behaviour_mut().send_query(QUERY.try_into().unwrap(), peer_id).unwrap();
+    }
+    let mut received_responses = HashMap::<_, Vec<_>>::new();
+    let rejected_session_id = loop {
+        match peer_swarm.select_next_some().await {
+            SwarmEvent::Behaviour(Event::ReceivedData { outbound_session_id, data }) => {
+                received_responses.entry(outbound_session_id).or_default().push(data)
+            }
+            SwarmEvent::Behaviour(Event::SessionClosedByPeer {
+                session_id: SessionId::OutboundSessionId(outbound_session_id),
+            }) => break outbound_session_id,
+            _ => {}
+        }
+    };
+    assert_eq!(
+        received_responses.remove(&rejected_session_id).unwrap(),
+        vec![protobuf::BlockHeadersResponse::fin(Some(protobuf::fin::Error::Busy))]
+    );
+}
+
+#[tokio::test]
+async fn peer_query_is_answered_from_storage() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let headers = (0..3)
+        .map(|block_number| BlockHeader {
+            block_number: BlockNumber(block_number),
+            block_hash: BlockHash(StarkHash::from(block_number + 1)),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    for header in &headers {
+        storage_writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(header.block_number, header)
+            .unwrap()
+            .append_body(header.block_number, BlockBody::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    let mut swarm = create_swarm();
+    let mut peer_swarm = create_peer_swarm();
+    swarm.listen().with_memory_addr_external().await;
+    peer_swarm.listen().with_memory_addr_external().await;
+    swarm.connect(&mut peer_swarm).await;
+    let peer_id = *swarm.local_peer_id();
+    let network_manager = GenericNetworkManager::from_swarm(
+        swarm,
+        BlockHeadersDBExecutor::new(storage_reader, ChainId::default()),
+        MockReaderExecutor::new(),
+        MockReaderExecutor::new(),
+        &NetworkConfig::default(),
+    );
+    tokio::spawn(network_manager.run());
+
+    // The query asks for blocks 1 and 2, and the storage has them both.
+    peer_swarm.behaviour_mut().send_query(QUERY.try_into().unwrap(), peer_id).unwrap();
+    let mut received_responses = vec![];
+    loop {
+        match peer_swarm.select_next_some().await {
+            SwarmEvent::Behaviour(Event::ReceivedData { data, .. }) => {
+                received_responses.push(data)
+            }
+            SwarmEvent::Behaviour(Event::SessionClosedByPeer { .. }) => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(received_responses.len(), 3);
+    for (response, header) in received_responses.iter().zip(&headers[1..]) {
+        assert_matches!(
+            &response.part[..],
+            [
+                protobuf::BlockHeadersResponsePart {
+                    header_message: Some(
+                        protobuf::block_headers_response_part::HeaderMessage::Header(
+                            protobuf_header
+                        )
+                    ),
+                },
+                protobuf::BlockHeadersResponsePart {
+                    header_message: Some(
+                        protobuf::block_headers_response_part::HeaderMessage::Signatures(
+                            protobuf::Signatures { block: Some(block_id), .. }
+                        )
+                    ),
+                },
+            ] if protobuf_header.number == header.block_number.0
+                && block_id.header == Some(header.block_hash.0.into())
+        );
+    }
+    assert_eq!(received_responses[2], protobuf::BlockHeadersResponse::fin(None));
+}
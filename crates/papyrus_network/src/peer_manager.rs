@@ -0,0 +1,143 @@
+//! Keeps the reputation of the peers of the node. Every misconduct of a peer lowers its score, and
+//! a peer whose score drops to the ban threshold is banned for a while. When choosing a peer to
+//! query, peers with a higher score are preferred, and among them the peers with a higher
+//! throughput.
+#[cfg(test)]
+#[path = "peer_manager_test.rs"]
+mod peer_manager_test;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+
+/// A peer whose score drops to this score is banned.
+pub const BAN_THRESHOLD: i64 = -100;
+
+/// Something that a peer did wrong.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PeerMisconduct {
+    /// The peer sent a message that couldn't be parsed or that is invalid.
+    InvalidMessage,
+    /// The peer didn't answer a query in time.
+    Timeout,
+    /// The peer sent data that contradicts the chain, for example a block header whose hash
+    /// doesn't match its fields. The peer is banned right away.
+    InvalidData,
+}
+
+impl PeerMisconduct {
+    fn penalty(&self) -> i64 {
+        match self {
+            PeerMisconduct::InvalidMessage => 50,
+            PeerMisconduct::Timeout => 10,
+            PeerMisconduct::InvalidData => BAN_THRESHOLD.saturating_neg(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PeerMetrics {
+    invalid_messages: i64,
+    timeouts: i64,
+    invalid_data: i64,
+    received_bytes: u64,
+    session_time: Duration,
+    banned_until: Option<Instant>,
+}
+
+impl PeerMetrics {
+    fn score(&self) -> i64 {
+        let invalid_messages_penalty =
+            self.invalid_messages.saturating_mul(PeerMisconduct::InvalidMessage.penalty());
+        let timeouts_penalty = self.timeouts.saturating_mul(PeerMisconduct::Timeout.penalty());
+        let invalid_data_penalty =
+            self.invalid_data.saturating_mul(PeerMisconduct::InvalidData.penalty());
+        invalid_messages_penalty
+            .saturating_add(timeouts_penalty)
+            .saturating_add(invalid_data_penalty)
+            .saturating_neg()
+    }
+
+    // In bytes per second.
+    fn throughput(&self) -> f64 {
+        if self.session_time.is_zero() {
+            return 0.0;
+        }
+        self.received_bytes as f64 / self.session_time.as_secs_f64()
+    }
+}
+
+/// Tracks the metrics of the peers, including peers that disconnected, so that a banned peer stays
+/// banned if it reconnects.
+#[derive(Debug)]
+pub struct PeerManager {
+    ban_duration: Duration,
+    peers: HashMap<PeerId, PeerMetrics>,
+}
+
+impl PeerManager {
+    pub fn new(ban_duration: Duration) -> Self {
+        Self { ban_duration, peers: HashMap::new() }
+    }
+
+    /// Records a misconduct of the peer. Returns true if the peer was banned because of it.
+    pub fn report_misconduct(&mut self, peer_id: PeerId, misconduct: PeerMisconduct) -> bool {
+        let ban_duration = self.ban_duration;
+        let metrics = self.peers.entry(peer_id).or_default();
+        match misconduct {
+            PeerMisconduct::InvalidMessage => metrics.invalid_messages += 1,
+            PeerMisconduct::Timeout => metrics.timeouts += 1,
+            PeerMisconduct::InvalidData => metrics.invalid_data += 1,
+        }
+        if metrics.banned_until.is_some() || metrics.score() > BAN_THRESHOLD {
+            return false;
+        }
+        metrics.banned_until = Some(Instant::now() + ban_duration);
+        true
+    }
+
+    /// Records the data that the peer sent in a session that took the given time.
+    pub fn report_session(&mut self, peer_id: PeerId, received_bytes: u64, session_time: Duration) {
+        let metrics = self.peers.entry(peer_id).or_default();
+        metrics.received_bytes += received_bytes;
+        metrics.session_time += session_time;
+    }
+
+    /// Returns whether the peer is banned. Once the ban is over, the peer starts over with a clean
+    /// score.
+    pub fn is_banned(&mut self, peer_id: &PeerId) -> bool {
+        let Some(metrics) = self.peers.get_mut(peer_id) else {
+            return false;
+        };
+        match metrics.banned_until {
+            Some(banned_until) if banned_until > Instant::now() => true,
+            Some(_) => {
+                *metrics = PeerMetrics::default();
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the score of the peer. A peer starts with a score of 0.
+    pub fn score(&self, peer_id: &PeerId) -> i64 {
+        self.peers.get(peer_id).map(PeerMetrics::score).unwrap_or_default()
+    }
+
+    fn throughput(&self, peer_id: &PeerId) -> f64 {
+        self.peers.get(peer_id).map(PeerMetrics::throughput).unwrap_or_default()
+    }
+
+    /// Chooses the candidate with the highest score that isn't banned, preferring the one with the
+    /// highest throughput among candidates with the same score.
+    pub fn choose_peer(&mut self, candidates: impl IntoIterator<Item = PeerId>) -> Option<PeerId> {
+        let candidates =
+            candidates.into_iter().filter(|peer_id| !self.is_banned(peer_id)).collect::<Vec<_>>();
+        candidates.into_iter().max_by(|peer_id, other_peer_id| {
+            self.score(peer_id)
+                .cmp(&self.score(other_peer_id))
+                .then_with(|| self.throughput(peer_id).total_cmp(&self.throughput(other_peer_id)))
+        })
+    }
+}
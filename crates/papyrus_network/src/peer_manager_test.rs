@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use libp2p::PeerId;
+use pretty_assertions::assert_eq;
+
+use super::{PeerManager, PeerMisconduct, BAN_THRESHOLD};
+
+const BAN_DURATION: Duration = Duration::from_secs(3600);
+
+#[test]
+fn peer_is_banned_once_its_score_drops_to_the_threshold() {
+    let mut peer_manager = PeerManager::new(BAN_DURATION);
+    let peer_id = PeerId::random();
+    assert_eq!(peer_manager.score(&peer_id), 0);
+
+    let mut num_reports = 0;
+    while peer_manager.score(&peer_id) > BAN_THRESHOLD {
+        assert!(!peer_manager.is_banned(&peer_id));
+        let is_banned = peer_manager.report_misconduct(peer_id, PeerMisconduct::Timeout);
+        assert_eq!(is_banned, peer_manager.score(&peer_id) <= BAN_THRESHOLD);
+        num_reports += 1;
+    }
+    assert!(num_reports > 1);
+    assert!(peer_manager.is_banned(&peer_id));
+    assert_eq!(peer_manager.choose_peer([peer_id]), None);
+
+    // A banned peer is banned only once.
+    assert!(!peer_manager.report_misconduct(peer_id, PeerMisconduct::InvalidMessage));
+}
+
+#[test]
+fn peer_is_unbanned_after_the_ban_duration() {
+    let mut peer_manager = PeerManager::new(Duration::ZERO);
+    let peer_id = PeerId::random();
+    while !peer_manager.report_misconduct(peer_id, PeerMisconduct::InvalidMessage) {}
+
+    assert!(!peer_manager.is_banned(&peer_id));
+    assert_eq!(peer_manager.score(&peer_id), 0);
+    assert_eq!(peer_manager.choose_peer([peer_id]), Some(peer_id));
+}
+
+#[test]
+fn peer_is_banned_after_invalid_data() {
+    let mut peer_manager = PeerManager::new(BAN_DURATION);
+    let peer_id = PeerId::random();
+
+    assert!(peer_manager.report_misconduct(peer_id, PeerMisconduct::InvalidData));
+    assert!(peer_manager.is_banned(&peer_id));
+}
+
+#[test]
+fn invalid_message_is_worse_than_timeout() {
+    let mut peer_manager = PeerManager::new(BAN_DURATION);
+    let invalid_message_peer_id = PeerId::random();
+    let timeout_peer_id = PeerId::random();
+    peer_manager.report_misconduct(invalid_message_peer_id, PeerMisconduct::InvalidMessage);
+    peer_manager.report_misconduct(timeout_peer_id, PeerMisconduct::Timeout);
+
+    assert!(peer_manager.score(&invalid_message_peer_id) < peer_manager.score(&timeout_peer_id));
+    assert_eq!(
+        peer_manager.choose_peer([invalid_message_peer_id, timeout_peer_id]),
+        Some(timeout_peer_id)
+    );
+}
+
+#[test]
+fn peer_with_higher_throughput_is_chosen() {
+    let mut peer_manager = PeerManager::new(BAN_DURATION);
+    let slow_peer_id = PeerId::random();
+    let fast_peer_id = PeerId::random();
+    let new_peer_id = PeerId::random();
+    peer_manager.report_session(slow_peer_id, 100, Duration::from_secs(10));
+    peer_manager.report_session(fast_peer_id, 100, Duration::from_secs(1));
+
+    assert_eq!(
+        peer_manager.choose_peer([slow_peer_id, fast_peer_id, new_peer_id]),
+        Some(fast_peer_id)
+    );
+    assert_eq!(peer_manager.choose_peer([slow_peer_id, new_peer_id]), Some(slow_peer_id));
+    assert_eq!(peer_manager.choose_peer([]), None);
+}
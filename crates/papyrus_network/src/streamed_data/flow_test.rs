@@ -10,7 +10,7 @@ use libp2p::{PeerId, Swarm};
 
 use super::behaviour::{Behaviour, Event};
 use super::{InboundSessionId, OutboundSessionId, SessionId};
-use crate::messages::protobuf;
+use crate::messages::{protobuf, MAX_MESSAGE_SIZE};
 use crate::streamed_data::Config;
 use crate::test_utils::{create_fully_connected_swarms_stream, StreamHashMap};
 
@@ -178,6 +178,7 @@ async fn everyone_sends_to_everyone() {
         Behaviour::<protobuf::BasicMessage, protobuf::BasicMessage>::new(Config {
             substream_timeout: Duration::from_secs(60),
             protocol_name: StreamProtocol::new("/"),
+            max_message_size: MAX_MESSAGE_SIZE,
         })
     })
     .await;
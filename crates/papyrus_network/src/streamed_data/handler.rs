@@ -153,7 +153,7 @@ impl<Query: QueryBound, Data: DataBound> ConnectionHandler for Handler<Query, Da
 
     fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, Self::InboundOpenInfo> {
         SubstreamProtocol::new(
-            InboundProtocol::new(self.config.protocol_name.clone()),
+            InboundProtocol::new(self.config.protocol_name.clone(), self.config.max_message_size),
             InboundSessionId { value: self.next_inbound_session_id.fetch_add(1, Ordering::AcqRel) },
         )
         .with_timeout(self.config.substream_timeout)
@@ -327,8 +327,10 @@ impl<Query: QueryBound, Data: DataBound> ConnectionHandler for Handler<Query, Da
                 protocol: stream,
                 info: outbound_session_id,
             }) => {
-                self.id_to_outbound_session
-                    .insert(outbound_session_id, OutboundSession::new(stream));
+                self.id_to_outbound_session.insert(
+                    outbound_session_id,
+                    OutboundSession::new(stream, self.config.max_message_size),
+                );
             }
             ConnectionEvent::FullyNegotiatedInbound(FullyNegotiatedInbound {
                 protocol: (query, stream),
@@ -13,6 +13,7 @@ use crate::messages::read_message;
 
 pub(super) struct OutboundSession<Data: DataBound> {
     read_task: BoxFuture<'static, Result<(Data, ReadHalf<Stream>), FinishReason>>,
+    max_message_size: usize,
     close_task: BoxFuture<'static, FinishReason>,
     should_close: bool,
     finished: bool,
@@ -26,10 +27,11 @@ pub(super) enum FinishReason {
 }
 
 impl<Data: DataBound> OutboundSession<Data> {
-    pub fn new(stream: Stream) -> Self {
+    pub fn new(stream: Stream, max_message_size: usize) -> Self {
         let (read_half, mut write_half) = stream.split();
         Self {
-            read_task: Self::read_data(read_half).boxed(),
+            read_task: Self::read_data(read_half, max_message_size).boxed(),
+            max_message_size,
             close_task: async move {
                 match write_half.close().await {
                     Ok(()) => FinishReason::Closed,
@@ -52,8 +54,9 @@ impl<Data: DataBound> OutboundSession<Data> {
 
     async fn read_data(
         mut stream: ReadHalf<Stream>,
+        max_message_size: usize,
     ) -> Result<(Data, ReadHalf<Stream>), FinishReason> {
-        match read_message::<Data, _>(&mut stream).await {
+        match read_message::<Data, _>(&mut stream, max_message_size).await {
             Ok(Some(data)) => Ok((data, stream)),
             Ok(None) => Err(FinishReason::OtherPeerClosed),
             Err(io_error) => Err(FinishReason::Error(io_error)),
@@ -82,7 +85,8 @@ impl<Data: DataBound> StreamTrait for OutboundSession<Data> {
         };
 
         let result = result_with_stream.map(|(data, stream)| {
-            unpinned_self.read_task = Self::read_data(stream).boxed();
+            unpinned_self.read_task =
+                Self::read_data(stream, unpinned_self.max_message_size).boxed();
             data
         });
         if result.is_err() {
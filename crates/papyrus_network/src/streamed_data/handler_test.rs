@@ -18,7 +18,7 @@ use libp2p::PeerId;
 
 use super::super::{Config, DataBound, InboundSessionId, OutboundSessionId, QueryBound, SessionId};
 use super::{Handler, HandlerEvent, RequestFromBehaviourEvent, SessionError, ToBehaviourEvent};
-use crate::messages::{protobuf, read_message, write_message};
+use crate::messages::{protobuf, read_message, write_message, MAX_MESSAGE_SIZE};
 use crate::test_utils::{dummy_data, get_connected_streams};
 
 impl<Query: QueryBound, Data: DataBound> Unpin for Handler<Query, Data> {}
@@ -200,7 +200,10 @@ async fn read_messages<Query: QueryBound, Data: DataBound>(
     ) -> Vec<protobuf::BasicMessage> {
         let mut result = Vec::new();
         for _ in 0..num_messages {
-            match read_message::<protobuf::BasicMessage, _>(&mut *stream).await.unwrap() {
+            match read_message::<protobuf::BasicMessage, _>(&mut *stream, MAX_MESSAGE_SIZE)
+                .await
+                .unwrap()
+            {
                 Some(message) => result.push(message),
                 None => return result,
             }
@@ -61,4 +61,5 @@ pub(crate) enum GenericEvent<Query: QueryBound, Data: DataBound, SessionError> {
 pub(crate) struct Config {
     pub substream_timeout: Duration,
     pub protocol_name: StreamProtocol,
+    pub max_message_size: usize,
 }
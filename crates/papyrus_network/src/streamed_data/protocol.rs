@@ -19,11 +19,12 @@ use crate::messages::{read_message, write_message};
 pub struct InboundProtocol<Query: Message + Default> {
     phantom: PhantomData<Query>,
     protocol_name: StreamProtocol,
+    max_message_size: usize,
 }
 
 impl<Query: Message + Default> InboundProtocol<Query> {
-    pub fn new(protocol_name: StreamProtocol) -> Self {
-        Self { protocol_name, phantom: PhantomData }
+    pub fn new(protocol_name: StreamProtocol, max_message_size: usize) -> Self {
+        Self { protocol_name, max_message_size, phantom: PhantomData }
     }
 }
 
@@ -47,7 +48,7 @@ where
 
     fn upgrade_inbound(self, mut stream: Stream, _: Self::Info) -> Self::Future {
         async move {
-            let request = read_message::<Query, _>(&mut stream)
+            let request = read_message::<Query, _>(&mut stream, self.max_message_size)
                 .await?
                 .ok_or::<io::Error>(io::ErrorKind::UnexpectedEof.into())?;
             Ok((request, stream))
@@ -8,7 +8,7 @@ use libp2p::swarm::StreamProtocol;
 use pretty_assertions::assert_eq;
 
 use super::{InboundProtocol, OutboundProtocol};
-use crate::messages::{protobuf, read_message, write_message, write_usize};
+use crate::messages::{protobuf, read_message, write_message, write_usize, MAX_MESSAGE_SIZE};
 use crate::test_utils::{dummy_data, get_connected_streams};
 
 pub const PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/get_blocks/1.0.0");
@@ -24,7 +24,8 @@ fn outbound_protocol_info() {
 
 #[test]
 fn inbound_protocol_info() {
-    let inbound_protocol = InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME);
+    let inbound_protocol =
+        InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME, MAX_MESSAGE_SIZE);
     assert_eq!(inbound_protocol.protocol_info().collect::<Vec<_>>(), vec![PROTOCOL_NAME]);
 }
 
@@ -34,7 +35,8 @@ async fn positive_flow() {
 
     let query = protobuf::BasicMessage::default();
     let outbound_protocol = OutboundProtocol { query: query.clone(), protocol_name: PROTOCOL_NAME };
-    let inbound_protocol = InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME);
+    let inbound_protocol =
+        InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME, MAX_MESSAGE_SIZE);
 
     tokio::join!(
         async move {
@@ -50,7 +52,10 @@ async fn positive_flow() {
                 outbound_protocol.upgrade_outbound(outbound_stream, PROTOCOL_NAME).await.unwrap();
             for expected_response in dummy_data() {
                 let response =
-                    read_message::<protobuf::BasicMessage, _>(&mut stream).await.unwrap().unwrap();
+                    read_message::<protobuf::BasicMessage, _>(&mut stream, MAX_MESSAGE_SIZE)
+                        .await
+                        .unwrap()
+                        .unwrap();
                 assert_eq!(response, expected_response);
             }
         }
@@ -60,7 +65,8 @@ async fn positive_flow() {
 #[tokio::test]
 async fn outbound_sends_invalid_request() {
     let (inbound_stream, mut outbound_stream, _) = get_connected_streams().await;
-    let inbound_protocol = InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME);
+    let inbound_protocol =
+        InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME, MAX_MESSAGE_SIZE);
 
     tokio::join!(
         async move {
@@ -78,7 +84,8 @@ async fn outbound_sends_invalid_request() {
 #[tokio::test]
 async fn outbound_sends_no_request() {
     let (inbound_stream, mut outbound_stream, _) = get_connected_streams().await;
-    let inbound_protocol = InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME);
+    let inbound_protocol =
+        InboundProtocol::<protobuf::BasicMessage>::new(PROTOCOL_NAME, MAX_MESSAGE_SIZE);
 
     tokio::join!(
         async move {
@@ -15,7 +15,7 @@ use libp2p_swarm_test::SwarmExt;
 use tokio::task::JoinHandle;
 use tokio_stream::StreamExt as TokioStreamExt;
 
-use crate::messages::protobuf;
+use crate::messages::{protobuf, MAX_MESSAGE_SIZE};
 
 /// Create two streams that are connected to each other. Return them and a join handle for a thread
 /// that will perform the sends between the streams (this thread will run forever so it shouldn't
@@ -51,7 +51,11 @@ pub(crate) fn dummy_data() -> Vec<protobuf::BasicMessage> {
 
 impl crate::streamed_data::Config {
     pub fn get_test_config() -> Self {
-        Self { substream_timeout: Duration::MAX, protocol_name: StreamProtocol::new("/") }
+        Self {
+            substream_timeout: Duration::MAX,
+            protocol_name: StreamProtocol::new("/"),
+            max_message_size: MAX_MESSAGE_SIZE,
+        }
     }
 }
 
@@ -38,7 +38,7 @@ async fn main() {
     let mut block_marker = initial_block_number;
     let block_stream = central_source.stream_new_blocks(block_marker, last_block_number).fuse();
     pin_mut!(block_stream);
-    while let Some(Ok((block_number, _block, _block_signature_data, _starknet_version))) =
+    while let Some(Ok((block_number, _block, _block_signature_data, _starknet_version, _))) =
         block_stream.next().await
     {
         assert!(
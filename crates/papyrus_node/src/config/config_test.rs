@@ -61,6 +61,14 @@ fn load_default_config() {
     NodeConfig::load_and_process(get_args(vec![])).expect("Failed to load the config.");
 }
 
+#[test]
+fn load_config_with_dump_config_flag() {
+    env::set_current_dir(get_absolute_path("")).expect("Couldn't set working dir.");
+    // The flag of the node command isn't a config param.
+    let config = NodeConfig::load_and_process(get_args(vec!["--dump_config"])).unwrap();
+    assert_eq!(config, NodeConfig::default());
+}
+
 #[test]
 fn load_http_headers() {
     let args = get_args(vec!["--central.http_headers", "NAME_1:VALUE_1 NAME_2:VALUE_2"]);
@@ -10,16 +10,20 @@ use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{env, fs, io};
 
-use clap::{arg, value_parser, Arg, ArgMatches, Command};
+use clap::{arg, value_parser, Arg, ArgAction, ArgMatches, Command};
 use itertools::{chain, Itertools};
 use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerConfig;
 use papyrus_config::dumping::{append_sub_config_name, ser_optional_sub_config, SerializeConfig};
 use papyrus_config::loading::load_and_process_config;
 use papyrus_config::{ConfigError, ParamPath, SerializedParam};
+use papyrus_feeder_gateway::FeederGatewayConfig;
+use papyrus_graphql::GraphQLConfig;
 use papyrus_monitoring_gateway::MonitoringGatewayConfig;
+use papyrus_network::network_manager::NetworkConfig;
 use papyrus_rpc::RpcConfig;
 use papyrus_storage::db::DbConfig;
 use papyrus_storage::StorageConfig;
+use papyrus_sync::p2p_sync::P2PSyncConfig;
 use papyrus_sync::sources::central::CentralSourceConfig;
 use papyrus_sync::SyncConfig;
 use serde::{Deserialize, Serialize};
@@ -28,11 +32,16 @@ use starknet_api::core::ChainId;
 use starknet_client::RetryConfig;
 use validator::Validate;
 
+use crate::logging::LoggingConfig;
+use crate::multi_chain::MULTI_CHAIN_CONFIG_FILE_ARG;
 use crate::version::VERSION_FULL;
 
 // The path of the default configuration file, provided as part of the crate.
 pub const DEFAULT_CONFIG_PATH: &str = "config/default_config.json";
 
+/// The flag for printing the loaded config instead of running the node.
+pub const DUMP_CONFIG_ARG: &str = "dump_config";
+
 /// The configurations of the various components of the node.
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Validate)]
 pub struct NodeConfig {
@@ -45,6 +54,18 @@ pub struct NodeConfig {
     pub storage: StorageConfig,
     /// None if the syncing should be disabled.
     pub sync: Option<SyncConfig>,
+    /// None if the GraphQL server should be disabled.
+    #[validate]
+    pub graphql: Option<GraphQLConfig>,
+    /// None if the feeder gateway server should be disabled.
+    #[validate]
+    pub feeder_gateway: Option<FeederGatewayConfig>,
+    /// None if the node shouldn't connect to the p2p network.
+    pub network: Option<NetworkConfig>,
+    /// None if the block headers shouldn't be synced from the peers of the node. Requires the
+    /// network config.
+    pub p2p_sync: Option<P2PSyncConfig>,
+    pub logging: LoggingConfig,
 }
 
 // Default configuration values.
@@ -57,6 +78,11 @@ impl Default for NodeConfig {
             monitoring_gateway: MonitoringGatewayConfig::default(),
             storage: StorageConfig::default(),
             sync: Some(SyncConfig::default()),
+            graphql: None,
+            feeder_gateway: None,
+            network: None,
+            p2p_sync: None,
+            logging: LoggingConfig::default(),
         }
     }
 }
@@ -69,7 +95,12 @@ impl SerializeConfig for NodeConfig {
             append_sub_config_name(self.rpc.dump(), "rpc"),
             append_sub_config_name(self.monitoring_gateway.dump(), "monitoring_gateway"),
             append_sub_config_name(self.storage.dump(), "storage"),
+            append_sub_config_name(self.logging.dump(), "logging"),
             ser_optional_sub_config(&self.sync, "sync"),
+            ser_optional_sub_config(&self.graphql, "graphql"),
+            ser_optional_sub_config(&self.feeder_gateway, "feeder_gateway"),
+            ser_optional_sub_config(&self.network, "network"),
+            ser_optional_sub_config(&self.p2p_sync, "p2p_sync"),
         )
         .collect()
     }
@@ -89,4 +120,22 @@ pub fn node_command() -> Command {
     Command::new("Papyrus")
         .version(VERSION_FULL)
         .about("Papyrus is a StarkNet full node written in Rust.")
+        .arg(
+            Arg::new(MULTI_CHAIN_CONFIG_FILE_ARG)
+                .long(MULTI_CHAIN_CONFIG_FILE_ARG)
+                .help(
+                    "Optionally runs multiple chains, each by its own config files, as described \
+                     in the given file. The other arguments are ignored.",
+                )
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new(DUMP_CONFIG_ARG)
+                .long(DUMP_CONFIG_ARG)
+                .help(
+                    "Prints the config that the node would run with, without its private params, \
+                     and exits.",
+                )
+                .action(ArgAction::SetTrue),
+        )
 }
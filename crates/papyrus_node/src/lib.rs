@@ -4,6 +4,9 @@
 
 #[allow(unused_imports)]
 pub mod config;
+pub mod logging;
+pub mod multi_chain;
 #[cfg(test)]
 mod precision_test;
+pub mod setup;
 pub mod version;
@@ -0,0 +1,104 @@
+//! Configures the logs of the node: their format, and a filter of the log levels per target, in the
+//! syntax of the `RUST_LOG` environment variable (for example `info,papyrus_sync=debug`). The
+//! filter can be changed while the node runs, through the monitoring gateway.
+#[cfg(test)]
+#[path = "logging_test.rs"]
+mod logging_test;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::OnceLock;
+
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+// Changes the filter of the logs once the tracing is configured.
+static LOG_FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// The format of the logs.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum LogFormat {
+    /// A single line per log, without the target of the log.
+    #[default]
+    Compact,
+    /// Multiple lines per log, for reading by humans.
+    Pretty,
+    /// A JSON object per line, for log collectors.
+    Json,
+}
+
+/// The configuration of the logs of the node.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig { format: LogFormat::default(), filter: String::from("info") }
+    }
+}
+
+impl SerializeConfig for LoggingConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "format",
+                &self.format,
+                "The format of the logs: Compact, Pretty or Json.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "filter",
+                &self.filter,
+                "The log levels per target, in the syntax of RUST_LOG, for example \
+                 'info,papyrus_sync=debug'. The RUST_LOG environment variable overrides it.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LoggingError {
+    #[error("Invalid log filter: {0}.")]
+    InvalidFilter(#[from] ParseError),
+    #[error(transparent)]
+    Reload(#[from] reload::Error),
+    #[error("The tracing of the node isn't configured.")]
+    NotConfigured,
+}
+
+/// Sets the subscriber of the logs of all the threads. Should be called once.
+pub fn configure_tracing(config: &LoggingConfig) -> Result<(), LoggingError> {
+    let filter = match env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(env_filter) => EnvFilter::try_new(env_filter)?,
+        Err(_) => EnvFilter::try_new(&config.filter)?,
+    };
+    let (filter_layer, filter_handle) = reload::Layer::new(filter);
+    let fmt_layer = match config.format {
+        LogFormat::Compact => fmt::layer().compact().with_target(false).boxed(),
+        LogFormat::Pretty => fmt::layer().pretty().boxed(),
+        LogFormat::Json => fmt::layer().json().boxed(),
+    };
+    tracing_subscriber::registry().with(filter_layer).with(fmt_layer).init();
+    let _ = LOG_FILTER_HANDLE.set(filter_handle);
+    Ok(())
+}
+
+/// Replaces the filter of the logs, given in the syntax of RUST_LOG.
+pub fn set_log_filter(filter: &str) -> Result<(), LoggingError> {
+    // The filter is parsed before the tracing is checked, so that invalid filters are always
+    // reported as such.
+    let filter = EnvFilter::try_new(filter)?;
+    let filter_handle = LOG_FILTER_HANDLE.get().ok_or(LoggingError::NotConfigured)?;
+    filter_handle.reload(filter)?;
+    info!("The log filter was changed.");
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use assert_matches::assert_matches;
+
+use crate::logging::{set_log_filter, LoggingError};
+
+#[test]
+fn invalid_log_filter() {
+    assert_matches!(set_log_filter("papyrus_sync=loud"), Err(LoggingError::InvalidFilter(_)));
+}
+
+#[test]
+fn log_filter_without_tracing() {
+    assert_matches!(set_log_filter("info,papyrus_sync=debug"), Err(LoggingError::NotConfigured));
+}
@@ -2,89 +2,155 @@
 mod main_test;
 
 use std::env::args;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::process::exit;
 use std::sync::Arc;
 
-use papyrus_common::pending_classes::PendingClasses;
-use papyrus_common::BlockHashAndNumber;
+use futures_util::future::{pending, select_all};
 use papyrus_config::presentation::get_config_presentation;
 use papyrus_config::validators::config_validate;
 use papyrus_config::ConfigError;
-use papyrus_monitoring_gateway::MonitoringServer;
-use papyrus_node::config::NodeConfig;
+use papyrus_feeder_gateway::run_server as run_feeder_gateway_server;
+use papyrus_graphql::run_server as run_graphql_server;
+use papyrus_monitoring_gateway::{GetPeers, MonitoringServer};
+use papyrus_network::network_manager::NetworkManager;
+use papyrus_node::config::{node_command, NodeConfig, DUMP_CONFIG_ARG};
+use papyrus_node::logging::{configure_tracing, set_log_filter, LoggingConfig};
+use papyrus_node::multi_chain::{run_rpc_router, MultiChainConfig, MULTI_CHAIN_CONFIG_FILE_ARG};
+use papyrus_node::setup::{create_central_sources, create_sync, run_rpc_server, SharedSyncState};
 use papyrus_node::version::VERSION_FULL;
-use papyrus_rpc::run_server;
-use papyrus_storage::{open_storage, StorageReader, StorageWriter};
+use papyrus_storage::{open_storage, open_storage_read_only, StorageReader, StorageWriter};
+use papyrus_sync::p2p_sync::P2PSync;
 use papyrus_sync::sources::base_layer::{BaseLayerSourceError, EthereumBaseLayerSource};
-use papyrus_sync::sources::central::{CentralError, CentralSource};
-use papyrus_sync::sources::pending::PendingSource;
-use papyrus_sync::{StateSync, StateSyncError};
-use starknet_api::block::BlockHash;
-use starknet_api::hash::{StarkFelt, GENESIS_HASH};
-use starknet_api::stark_felt;
-use starknet_client::reader::objects::pending_data::PendingBlock;
-use starknet_client::reader::PendingData;
-use tokio::sync::RwLock;
-use tracing::metadata::LevelFilter;
-use tracing::{error, info};
-use tracing_subscriber::prelude::*;
-use tracing_subscriber::{fmt, EnvFilter};
-
-// TODO(yair): Add to config.
-const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+use papyrus_sync::sources::central::CentralError;
+use papyrus_sync::sources::p2p::P2PSource;
+use papyrus_sync::StateSyncError;
+use tracing::{error, info, info_span, warn, Instrument};
 
 async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
-    let (storage_reader, storage_writer) = open_storage(config.storage.clone())?;
+    // A read-only storage is synced by another node process, and this node only serves it.
+    let (storage_reader, storage_writer) = if config.storage.read_only {
+        (open_storage_read_only(config.storage.clone())?, None)
+    } else {
+        let (storage_reader, storage_writer) = open_storage(config.storage.clone())?;
+        (storage_reader, Some(storage_writer))
+    };
+
+    // P2P network.
+    let network_manager = config
+        .network
+        .clone()
+        .map(|network_config| {
+            NetworkManager::new(
+                network_config,
+                storage_reader.clone(),
+                config.storage.db_config.chain_id.clone(),
+            )
+        })
+        .transpose()?;
+    let p2p_source = network_manager.as_ref().map(|network_manager| P2PSource {
+        headers_querier: network_manager.get_block_headers_querier(),
+        state_diffs_querier: network_manager.get_state_diffs_querier(),
+        classes_querier: network_manager.get_classes_querier(),
+        chain_id: config.storage.db_config.chain_id.clone(),
+    });
 
     // Monitoring server.
+    let get_peers = network_manager.as_ref().map(|network_manager| {
+        let connected_peers = network_manager.get_connected_peers();
+        let get_peers: GetPeers = Arc::new(move || {
+            connected_peers
+                .read()
+                .expect("The connected peers shouldn't be poisoned.")
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        });
+        get_peers
+    });
     let monitoring_server = MonitoringServer::new(
         config.monitoring_gateway.clone(),
         get_config_presentation(&config, true)?,
         get_config_presentation(&config, false)?,
         storage_reader.clone(),
         VERSION_FULL,
+        Some(Arc::new(|filter: &str| set_log_filter(filter).map_err(|err| err.to_string()))),
+        get_peers,
     )?;
     let monitoring_server_handle = monitoring_server.spawn_server().await;
 
-    // The sync is the only writer of the syncing state.
-    let shared_highest_block = Arc::new(RwLock::new(None));
-    let pending_data = Arc::new(RwLock::new(PendingData {
-        block: PendingBlock {
-            parent_block_hash: BlockHash(stark_felt!(GENESIS_HASH)),
-            ..Default::default()
-        },
-        ..Default::default()
-    }));
-    let pending_classes = Arc::new(RwLock::new(PendingClasses::default()));
+    let shared_sync_state = SharedSyncState::default();
 
     // JSON-RPC server.
-    let (_, server_handle) = run_server(
-        &config.rpc,
-        shared_highest_block.clone(),
-        pending_data.clone(),
-        pending_classes.clone(),
-        storage_reader.clone(),
-        VERSION_FULL,
-    )
-    .await?;
+    let (_, server_handle) =
+        run_rpc_server(&config.rpc, &shared_sync_state, storage_reader.clone()).await?;
     let server_handle_future = tokio::spawn(server_handle.stopped());
 
+    // GraphQL server.
+    let graphql_server_handle = config
+        .graphql
+        .as_ref()
+        .map(|graphql_config| run_graphql_server(graphql_config, storage_reader.clone()))
+        .transpose()?;
+    let graphql_server_future = async move {
+        match graphql_server_handle {
+            Some((_, handle)) => handle.await,
+            None => pending().await,
+        }
+    };
+
+    // Feeder gateway server.
+    let feeder_gateway_server_handle = config
+        .feeder_gateway
+        .as_ref()
+        .map(|feeder_gateway_config| {
+            run_feeder_gateway_server(feeder_gateway_config, storage_reader.clone())
+        })
+        .transpose()?;
+    let feeder_gateway_server_future = async move {
+        match feeder_gateway_server_handle {
+            Some((_, handle)) => handle.await,
+            None => pending().await,
+        }
+    };
+
+    let network_future = async move {
+        match network_manager {
+            Some(network_manager) => tokio::spawn(network_manager.run()).await,
+            None => pending().await,
+        }
+    };
+
     // Sync task.
-    let sync_future = run_sync(
-        config,
-        shared_highest_block,
-        pending_data,
-        pending_classes,
-        storage_reader.clone(),
-        storage_writer,
-    );
-    let sync_handle = tokio::spawn(sync_future);
+    let sync_handle = match storage_writer {
+        Some(storage_writer) => tokio::spawn(run_sync(
+            config,
+            p2p_source,
+            shared_sync_state,
+            storage_reader.clone(),
+            storage_writer,
+        )),
+        None => {
+            info!("The storage is read-only, not syncing.");
+            tokio::spawn(pending())
+        }
+    };
 
     tokio::select! {
         res = server_handle_future => {
             error!("RPC server stopped.");
             res?
         }
+        res = graphql_server_future => {
+            error!("GraphQL server stopped.");
+            res??
+        }
+        res = feeder_gateway_server_future => {
+            error!("Feeder gateway server stopped.");
+            res??
+        }
         res = monitoring_server_handle => {
             error!("Monitoring server stopped.");
             res??
@@ -93,31 +159,50 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
             error!("Sync stopped.");
             res??
         }
+        res = network_future => {
+            error!("Network stopped.");
+            res?
+        }
     };
     error!("Task ended with unexpected Ok.");
     return Ok(());
 
     async fn run_sync(
         config: NodeConfig,
-        shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
-        pending_data: Arc<RwLock<PendingData>>,
-        pending_classes: Arc<RwLock<PendingClasses>>,
+        p2p_source: Option<P2PSource>,
+        shared_sync_state: SharedSyncState,
         storage_reader: StorageReader,
-        storage_writer: StorageWriter,
+        mut storage_writer: StorageWriter,
     ) -> Result<(), StateSyncError> {
+        // The headers and the state diffs are synced from the peers while possible, and then the
+        // central sync continues from where the p2p sync stopped.
+        if let (Some(p2p_sync_config), Some(p2p_source)) = (config.p2p_sync, p2p_source) {
+            let mut p2p_sync =
+                P2PSync::new(p2p_sync_config, p2p_source, storage_reader.clone(), storage_writer);
+            if let Err(err) = p2p_sync.run().await {
+                warn!("P2P sync stopped: {err}. Continuing with the central sync.");
+            }
+            storage_writer = p2p_sync.into_storage_writer();
+        }
         let Some(sync_config) = config.sync else { return Ok(()) };
-        let central_source =
-            CentralSource::new(config.central.clone(), VERSION_FULL, storage_reader.clone())
-                .map_err(CentralError::ClientCreation)?;
-        let pending_source = PendingSource::new(config.central, VERSION_FULL)
-            .map_err(CentralError::ClientCreation)?;
+        let (central_source, pending_source) =
+            create_central_sources(config.central, storage_reader.clone())?;
+        // The central source may be unreachable for a while, so only a mismatch of the chain id
+        // stops the sync.
+        while let Err(err) =
+            central_source.verify_chain_id(&config.storage.db_config.chain_id).await
+        {
+            if let CentralError::ChainIdMismatch { .. } = err {
+                return Err(err.into());
+            }
+            warn!("Failed to verify the chain id of the central source: {err}. Retrying.");
+            tokio::time::sleep(sync_config.recoverable_error_sleep_duration).await;
+        }
         let base_layer_source = EthereumBaseLayerSource::new(config.base_layer)
             .map_err(|e| BaseLayerSourceError::BaseLayerSourceCreationError(e.to_string()))?;
-        let mut sync = StateSync::new(
+        let mut sync = create_sync(
             sync_config,
-            shared_highest_block,
-            pending_data,
-            pending_classes,
+            shared_sync_state,
             central_source,
             pending_source,
             base_layer_source,
@@ -128,29 +213,75 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
     }
 }
 
-// TODO(yair): add dynamic level filtering.
-// TODO(dan): filter out logs from dependencies (happens when RUST_LOG=DEBUG)
-// TODO(yair): define and implement configurable filtering.
-fn configure_tracing() {
-    let fmt_layer = fmt::layer().compact().with_target(false);
-    let level_filter_layer =
-        EnvFilter::builder().with_default_directive(DEFAULT_LEVEL.into()).from_env_lossy();
-
-    // This sets a single subscriber to all of the threads. We may want to implement different
-    // subscriber for some threads and use set_global_default instead of init.
-    tracing_subscriber::registry().with(fmt_layer).with(level_filter_layer).init();
+// Runs the chains concurrently, until one of them or the JSON-RPC router stops.
+async fn run_chains(multi_chain_config: MultiChainConfig) -> anyhow::Result<()> {
+    let chains = multi_chain_config.load_chains()?;
+    for chain in &chains {
+        if let Err(errors) = config_validate(&chain.config) {
+            error!("Invalid config of chain {}: {}", chain.mount_path, errors);
+            exit(1);
+        }
+    }
+
+    let mut tasks: Vec<Pin<Box<dyn Future<Output = anyhow::Result<()>>>>> = vec![Box::pin(
+        run_rpc_router(&multi_chain_config.rpc_server_address, &chains)
+            .instrument(info_span!("rpc_router")),
+    )];
+    for chain in &chains {
+        info!("Running chain {} (chain id {}).", chain.mount_path, chain.config.rpc.chain_id);
+        tasks.push(Box::pin(
+            run_threads(chain.config.clone())
+                .instrument(info_span!("chain", mount_path = %chain.mount_path)),
+        ));
+    }
+    let (res, _, _) = select_all(tasks).await;
+    error!("A chain stopped.");
+    res
+}
+
+// Returns the multi-chain config file, if the node should run multiple chains.
+fn get_multi_chain_config_file(args: &[String]) -> Option<PathBuf> {
+    node_command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .ok()?
+        .get_one::<PathBuf>(MULTI_CHAIN_CONFIG_FILE_ARG)
+        .cloned()
+}
+
+// Returns whether the node should print its config instead of running.
+fn should_dump_config(args: &[String]) -> bool {
+    node_command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .is_ok_and(|matches| matches.get_flag(DUMP_CONFIG_ARG))
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config = NodeConfig::load_and_process(args().collect());
+    let args: Vec<String> = args().collect();
+    if let Some(multi_chain_config_file) = get_multi_chain_config_file(&args) {
+        configure_tracing(&LoggingConfig::default())?;
+        let multi_chain_config = MultiChainConfig::load(&multi_chain_config_file)?;
+        info!("Booting up {} chains.", multi_chain_config.chains.len());
+        return run_chains(multi_chain_config).await;
+    }
+
+    let dump_config = should_dump_config(&args);
+    let config = NodeConfig::load_and_process(args);
     if let Err(ConfigError::CommandInput(clap_err)) = config {
         clap_err.exit();
     }
 
-    configure_tracing();
+    // The errors of an invalid config are logged in the default format.
+    let logging_config = config.as_ref().map(|config| config.logging.clone()).unwrap_or_default();
+    configure_tracing(&logging_config)?;
 
     let config = config?;
+    if dump_config {
+        println!("{}", serde_json::to_string_pretty(&get_config_presentation(&config, false)?)?);
+        return Ok(());
+    }
     if let Err(errors) = config_validate(&config) {
         error!("{}", errors);
         exit(1);
@@ -0,0 +1,321 @@
+//! Running multiple chains in one process. Each chain has its own node config, and so its own
+//! storage, sync sources and servers. The JSON-RPC servers of the chains are served together by a
+//! router that forwards every request to the chain whose mount path prefixes the request path, so
+//! `/<mount_path>/rpc/<version>` is served by `/rpc/<version>` of the chain.
+//!
+//! The chains are configured by a JSON file, for example:
+//! ```json
+//! {
+//!   "rpc_server_address": "0.0.0.0:8080",
+//!   "chains": [
+//!     { "mount_path": "mainnet", "config_files": ["mainnet_config.json"] },
+//!     { "mount_path": "sepolia", "config_files": ["sepolia_config.json"] }
+//!   ]
+//! }
+//! ```
+//! The router forwards WebSocket upgrades as well, so the subscriptions of the chains are served
+//! through it too.
+//!
+//! The config files of a chain are applied in order on top of the default node config. The
+//! servers and the p2p networks of the chains must use different ports, and at most one chain can
+//! collect metrics, since the metrics are collected for the whole process. For the same reason,
+//! the logs are written with the default logging config, and the logging configs of the chains
+//! are ignored.
+#[cfg(test)]
+#[path = "multi_chain_test.rs"]
+mod multi_chain_test;
+
+use std::collections::{BTreeMap, HashSet};
+use std::convert::Infallible;
+use std::fs::File;
+use std::iter::once;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use hyper::client::HttpConnector;
+use hyper::header::UPGRADE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Client, Request, Response, Server, StatusCode, Uri};
+use itertools::Itertools;
+use papyrus_config::ConfigError;
+use serde::{Deserialize, Serialize};
+use tokio::io::copy_bidirectional;
+use tracing::{debug, error};
+
+use crate::config::NodeConfig;
+
+/// The name of the command line argument of the multi-chain config file.
+pub const MULTI_CHAIN_CONFIG_FILE_ARG: &str = "multi_chain_config_file";
+
+/// The configuration of running multiple chains in one process.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MultiChainConfig {
+    /// IP:PORT of the JSON-RPC router of the chains.
+    pub rpc_server_address: String,
+    pub chains: Vec<ChainConfigFiles>,
+}
+
+/// The configuration of a chain, out of the chains running in one process.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ChainConfigFiles {
+    /// The first component of the paths of the JSON-RPC requests to the chain.
+    pub mount_path: String,
+    /// The node config files of the chain.
+    pub config_files: Vec<PathBuf>,
+}
+
+/// A chain to run, out of the chains running in one process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainToRun {
+    pub mount_path: String,
+    pub config: NodeConfig,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MultiChainError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error("Failed to read the multi-chain config file: {0}")]
+    ConfigFile(#[from] std::io::Error),
+    #[error("Failed to parse the multi-chain config file: {0}")]
+    ConfigFileFormat(#[from] serde_json::Error),
+    #[error("Invalid mount path {mount_path:?}, it should be a non-empty path component.")]
+    InvalidMountPath { mount_path: String },
+    #[error("The mount path {mount_path} is used by more than one chain.")]
+    DuplicateMountPath { mount_path: String },
+    #[error("The port of the {component} address {address} is used by another server.")]
+    DuplicateServerPort { component: &'static str, address: String },
+    #[error("The storage path {path:?} is used by more than one chain.")]
+    DuplicateStoragePath { path: PathBuf },
+    #[error("Metrics are collected by more than one chain.")]
+    MetricsCollectedByMultipleChains,
+    #[error("Invalid {component} address {address}.")]
+    InvalidServerAddress { component: &'static str, address: String },
+}
+
+impl MultiChainConfig {
+    /// Reads the multi-chain config from the given file.
+    pub fn load(path: &Path) -> Result<Self, MultiChainError> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// Loads the node configs of the chains and verifies that the chains can run together.
+    pub fn load_chains(&self) -> Result<Vec<ChainToRun>, MultiChainError> {
+        let chains = self
+            .chains
+            .iter()
+            .map(|chain| {
+                let config_files =
+                    chain.config_files.iter().map(|path| path.display().to_string()).join(",");
+                let args =
+                    vec!["papyrus_node".to_owned(), "--config_file".to_owned(), config_files];
+                Ok(ChainToRun {
+                    mount_path: chain.mount_path.clone(),
+                    config: NodeConfig::load_and_process(args)?,
+                })
+            })
+            .collect::<Result<Vec<_>, MultiChainError>>()?;
+        verify_chains(&self.rpc_server_address, &chains)?;
+        Ok(chains)
+    }
+}
+
+/// Verifies that the chains don't share resources that can't be shared, with each other and with
+/// the JSON-RPC router.
+pub fn verify_chains(
+    rpc_router_address: &str,
+    chains: &[ChainToRun],
+) -> Result<(), MultiChainError> {
+    for chain in chains {
+        if chain.mount_path.is_empty() || chain.mount_path.contains('/') {
+            return Err(MultiChainError::InvalidMountPath { mount_path: chain.mount_path.clone() });
+        }
+    }
+    if let Some(mount_path) = chains.iter().map(|chain| &chain.mount_path).duplicates().next() {
+        return Err(MultiChainError::DuplicateMountPath { mount_path: mount_path.clone() });
+    }
+    let server_addresses = chains.iter().flat_map(|chain| {
+        [
+            ("JSON-RPC", chain.config.rpc.server_address.as_str()),
+            ("monitoring", chain.config.monitoring_gateway.server_address.as_str()),
+        ]
+        .into_iter()
+        .chain(
+            chain.config.graphql.as_ref().map(|config| ("GraphQL", config.server_address.as_str())),
+        )
+        .chain(
+            chain
+                .config
+                .feeder_gateway
+                .as_ref()
+                .map(|config| ("feeder gateway", config.server_address.as_str())),
+        )
+    });
+    let mut used_ports = HashSet::new();
+    for (component, address) in
+        once(("JSON-RPC router", rpc_router_address)).chain(server_addresses)
+    {
+        let socket_address = address.parse::<SocketAddr>().map_err(|_| {
+            MultiChainError::InvalidServerAddress { component, address: address.to_owned() }
+        })?;
+        if !used_ports.insert(socket_address.port()) {
+            return Err(MultiChainError::DuplicateServerPort {
+                component,
+                address: address.to_owned(),
+            });
+        }
+    }
+    // The p2p network of a chain listens for tcp connections on all the interfaces.
+    for network_config in chains.iter().filter_map(|chain| chain.config.network.as_ref()) {
+        if !used_ports.insert(network_config.tcp_port) {
+            return Err(MultiChainError::DuplicateServerPort {
+                component: "p2p network",
+                address: format!("0.0.0.0:{}", network_config.tcp_port),
+            });
+        }
+    }
+    if let Some(path) =
+        chains.iter().map(|chain| &chain.config.storage.db_config.path_prefix).duplicates().next()
+    {
+        return Err(MultiChainError::DuplicateStoragePath { path: path.clone() });
+    }
+    let n_collecting_metrics = chains
+        .iter()
+        .filter(|chain| {
+            chain.config.monitoring_gateway.collect_metrics || chain.config.rpc.collect_metrics
+        })
+        .count();
+    if n_collecting_metrics > 1 {
+        return Err(MultiChainError::MetricsCollectedByMultipleChains);
+    }
+    Ok(())
+}
+
+/// Runs a server that forwards the JSON-RPC requests to the servers of the chains by their mount
+/// paths.
+pub async fn run_rpc_router(
+    server_address: &str,
+    chains: &[ChainToRun],
+) -> Result<(), anyhow::Error> {
+    let server_address = server_address.parse::<SocketAddr>()?;
+    let mut routes = BTreeMap::new();
+    for chain in chains {
+        let address = &chain.config.rpc.server_address;
+        let socket_address =
+            address.parse::<SocketAddr>().map_err(|_| MultiChainError::InvalidServerAddress {
+                component: "JSON-RPC",
+                address: address.clone(),
+            })?;
+        routes.insert(chain.mount_path.clone(), local_address(socket_address));
+    }
+    let routes = Arc::new(routes);
+    let client = Client::new();
+    let make_service = make_service_fn(move |_connection| {
+        let routes = routes.clone();
+        let client = client.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                route_request(request, routes.clone(), client.clone())
+            }))
+        }
+    });
+    Server::try_bind(&server_address)?.serve(make_service).await?;
+    Ok(())
+}
+
+async fn route_request(
+    request: Request<Body>,
+    routes: Arc<BTreeMap<String, SocketAddr>>,
+    client: Client<HttpConnector>,
+) -> Result<Response<Body>, Infallible> {
+    let uri = request.uri();
+    let Some((mount_path, chain_path)) = split_mount_path(uri.path()) else {
+        return Ok(error_response(StatusCode::NOT_FOUND, "Missing chain mount path."));
+    };
+    let Some(chain_address) = routes.get(mount_path).copied() else {
+        return Ok(error_response(StatusCode::NOT_FOUND, "Unknown chain mount path."));
+    };
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{chain_path}?{query}"),
+        None => chain_path.to_owned(),
+    };
+    let mount_path = mount_path.to_owned();
+    let chain_uri = match Uri::builder()
+        .scheme("http")
+        .authority(chain_address.to_string())
+        .path_and_query(path_and_query)
+        .build()
+    {
+        Ok(chain_uri) => chain_uri,
+        Err(err) => {
+            debug!("Failed to build the URI of a request to {mount_path}: {err}.");
+            return Ok(error_response(StatusCode::BAD_REQUEST, "Invalid request path."));
+        }
+    };
+
+    let (mut parts, body) = request.into_parts();
+    parts.uri = chain_uri;
+    let mut request = Request::from_parts(parts, body);
+    // The subscriptions are served over WebSocket, so an upgrade of the connection to the router is
+    // forwarded to the chain, and the two upgraded connections are joined.
+    let router_upgrade =
+        request.headers().contains_key(UPGRADE).then(|| hyper::upgrade::on(&mut request));
+    let mut response = match client.request(request).await {
+        Ok(response) => response,
+        Err(err) => {
+            error!("Failed to forward a request to the JSON-RPC server of {mount_path}: {err}.");
+            return Ok(error_response(StatusCode::BAD_GATEWAY, "The chain server is unavailable."));
+        }
+    };
+    if let Some(router_upgrade) = router_upgrade {
+        if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+            let chain_upgrade = hyper::upgrade::on(&mut response);
+            tokio::spawn(async move {
+                match tokio::try_join!(router_upgrade, chain_upgrade) {
+                    Ok((mut router_connection, mut chain_connection)) => {
+                        if let Err(err) =
+                            copy_bidirectional(&mut router_connection, &mut chain_connection).await
+                        {
+                            debug!("An upgraded connection to {mount_path} failed: {err}.");
+                        }
+                    }
+                    Err(err) => debug!("Failed to upgrade a connection to {mount_path}: {err}."),
+                }
+            });
+        }
+    }
+    Ok(response)
+}
+
+// Splits a request path to the mount path of the chain and the path of the request to the chain.
+fn split_mount_path(path: &str) -> Option<(&str, &str)> {
+    let path = path.strip_prefix('/')?;
+    let (mount_path, chain_path) = match path.find('/') {
+        Some(index) => path.split_at(index),
+        None => (path, "/"),
+    };
+    if mount_path.is_empty() {
+        return None;
+    }
+    Some((mount_path, chain_path))
+}
+
+// The servers of the chains run in this process, so a server listening on all the interfaces is
+// reached through the loopback interface.
+fn local_address(mut address: SocketAddr) -> SocketAddr {
+    if address.ip().is_unspecified() {
+        let loopback = match address.ip() {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        };
+        address.set_ip(loopback);
+    }
+    address
+}
+
+fn error_response(status: StatusCode, message: &'static str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    response
+}
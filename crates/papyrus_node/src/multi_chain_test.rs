@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use hyper::header::{CONNECTION, UPGRADE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Client, Request, Response, Server, StatusCode};
+use papyrus_feeder_gateway::FeederGatewayConfig;
+use papyrus_graphql::GraphQLConfig;
+use papyrus_network::network_manager::NetworkConfig;
+use pretty_assertions::assert_eq;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::{route_request, split_mount_path, verify_chains, ChainToRun, MultiChainError};
+use crate::config::NodeConfig;
+
+const ROUTER_ADDRESS: &str = "0.0.0.0:8090";
+
+fn chain(mount_path: &str, port: u16) -> ChainToRun {
+    let mut config = NodeConfig::default();
+    config.rpc.server_address = format!("0.0.0.0:{port}");
+    config.monitoring_gateway.server_address = format!("0.0.0.0:{}", port + 1);
+    config.storage.db_config.path_prefix = format!("./data/{mount_path}").into();
+    ChainToRun { mount_path: mount_path.to_owned(), config }
+}
+
+#[test]
+fn split_request_path() {
+    assert_eq!(split_mount_path("/mainnet/rpc/v0_6"), Some(("mainnet", "/rpc/v0_6")));
+    assert_eq!(split_mount_path("/mainnet"), Some(("mainnet", "/")));
+    assert_eq!(split_mount_path("/"), None);
+    assert_eq!(split_mount_path("//rpc/v0_6"), None);
+}
+
+#[test]
+fn verify_chains_that_can_run_together() {
+    verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8080), chain("sepolia", 8082)]).unwrap();
+}
+
+#[test]
+fn verify_chains_with_shared_resources() {
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8080), chain("mainnet", 8082)]),
+        Err(MultiChainError::DuplicateMountPath { .. })
+    );
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("main/net", 8080)]),
+        Err(MultiChainError::InvalidMountPath { .. })
+    );
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8080), chain("sepolia", 8081)]),
+        Err(MultiChainError::DuplicateServerPort { component: "JSON-RPC", .. })
+    );
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8089)]),
+        Err(MultiChainError::DuplicateServerPort { component: "monitoring", .. })
+    );
+    let mut sepolia = chain("sepolia", 8082);
+    sepolia.config.graphql =
+        Some(GraphQLConfig { server_address: "0.0.0.0:8080".to_owned(), ..Default::default() });
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8080), sepolia]),
+        Err(MultiChainError::DuplicateServerPort { component: "GraphQL", .. })
+    );
+    let mut sepolia = chain("sepolia", 8082);
+    sepolia.config.feeder_gateway =
+        Some(FeederGatewayConfig { server_address: "0.0.0.0:8081".to_owned() });
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8080), sepolia]),
+        Err(MultiChainError::DuplicateServerPort { component: "feeder gateway", .. })
+    );
+
+    let mut mainnet = chain("mainnet", 8080);
+    let mut sepolia = chain("sepolia", 8082);
+    mainnet.config.network = Some(NetworkConfig { tcp_port: 10000, ..Default::default() });
+    sepolia.config.network = Some(NetworkConfig { tcp_port: 10000, ..Default::default() });
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[mainnet, sepolia]),
+        Err(MultiChainError::DuplicateServerPort { component: "p2p network", .. })
+    );
+    let mut sepolia = chain("sepolia", 8082);
+    sepolia.config.network = Some(NetworkConfig { tcp_port: 8080, ..Default::default() });
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8080), sepolia]),
+        Err(MultiChainError::DuplicateServerPort { component: "p2p network", .. })
+    );
+
+    let mut sepolia = chain("sepolia", 8082);
+    sepolia.config.storage.db_config.path_prefix = "./data/mainnet".into();
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[chain("mainnet", 8080), sepolia]),
+        Err(MultiChainError::DuplicateStoragePath { .. })
+    );
+
+    let mut mainnet = chain("mainnet", 8080);
+    let mut sepolia = chain("sepolia", 8082);
+    mainnet.config.rpc.collect_metrics = true;
+    sepolia.config.monitoring_gateway.collect_metrics = true;
+    assert_matches!(
+        verify_chains(ROUTER_ADDRESS, &[mainnet, sepolia]),
+        Err(MultiChainError::MetricsCollectedByMultipleChains)
+    );
+}
+
+#[tokio::test]
+async fn route_requests_by_mount_path() {
+    // A server that responds with the path and query of the request.
+    let make_service = make_service_fn(|_connection| async {
+        Ok::<_, Infallible>(service_fn(|request: Request<Body>| async move {
+            let path_and_query = request.uri().path_and_query().unwrap().to_string();
+            Ok::<_, Infallible>(Response::new(Body::from(path_and_query)))
+        }))
+    });
+    let chain_server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let chain_address = chain_server.local_addr();
+    tokio::spawn(chain_server);
+    let routes = Arc::new(BTreeMap::from([("mainnet".to_owned(), chain_address)]));
+
+    let request = Request::builder()
+        .uri("http://localhost/mainnet/rpc/v0_6?id=1")
+        .body(Body::empty())
+        .unwrap();
+    let response = route_request(request, routes.clone(), Client::new()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, "/rpc/v0_6?id=1");
+
+    let request =
+        Request::builder().uri("http://localhost/sepolia/rpc/v0_6").body(Body::empty()).unwrap();
+    let response = route_request(request, routes, Client::new()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn route_upgraded_connections() {
+    // A server that upgrades every connection and echoes what it reads from the upgraded
+    // connection.
+    let make_service = make_service_fn(|_connection| async {
+        Ok::<_, Infallible>(service_fn(|mut request: Request<Body>| async move {
+            tokio::spawn(async move {
+                let mut connection = hyper::upgrade::on(&mut request).await.unwrap();
+                let mut message = [0u8; 4];
+                connection.read_exact(&mut message).await.unwrap();
+                connection.write_all(&message).await.unwrap();
+            });
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .status(StatusCode::SWITCHING_PROTOCOLS)
+                    .header(CONNECTION, "upgrade")
+                    .header(UPGRADE, "echo")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        }))
+    });
+    let chain_server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let chain_address = chain_server.local_addr();
+    tokio::spawn(chain_server);
+    let routes = Arc::new(BTreeMap::from([("mainnet".to_owned(), chain_address)]));
+
+    let make_service = make_service_fn(move |_connection| {
+        let routes = routes.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                route_request(request, routes.clone(), Client::new())
+            }))
+        }
+    });
+    let router = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let router_address = router.local_addr();
+    tokio::spawn(router);
+
+    let request = Request::builder()
+        .uri(format!("http://{router_address}/mainnet/rpc/v0_6"))
+        .header(CONNECTION, "upgrade")
+        .header(UPGRADE, "echo")
+        .body(Body::empty())
+        .unwrap();
+    let response = Client::new().request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+    let mut connection = hyper::upgrade::on(response).await.unwrap();
+    connection.write_all(b"ping").await.unwrap();
+    let mut message = [0u8; 4];
+    connection.read_exact(&mut message).await.unwrap();
+    assert_eq!(&message, b"ping");
+}
@@ -0,0 +1,106 @@
+//! The creation of the components of the node, shared by the node binary and the end-to-end tests
+//! of the node.
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use jsonrpsee::server::ServerHandle;
+use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::BlockHashAndNumber;
+use papyrus_rpc::{run_server, RpcConfig};
+use papyrus_storage::{StorageReader, StorageWriter};
+use papyrus_sync::sources::base_layer::BaseLayerSourceTrait;
+use papyrus_sync::sources::central::{CentralError, CentralSource, CentralSourceConfig};
+use papyrus_sync::sources::pending::PendingSource;
+use papyrus_sync::{GenericStateSync, SyncConfig};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::hash::{StarkFelt, GENESIS_HASH};
+use starknet_api::stark_felt;
+use starknet_client::reader::objects::pending_data::PendingBlock;
+use starknet_client::reader::PendingData;
+use tokio::sync::{broadcast, RwLock};
+
+use crate::version::VERSION_FULL;
+
+// The number of stored blocks a JSON-RPC subscriber can fall behind before it skips blocks.
+const NEW_BLOCKS_CHANNEL_CAPACITY: usize = 100;
+
+/// The state the sync shares with the JSON-RPC server. The sync is its only writer.
+#[derive(Clone)]
+pub struct SharedSyncState {
+    pub shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
+    pub pending_data: Arc<RwLock<PendingData>>,
+    pub pending_classes: Arc<RwLock<PendingClasses>>,
+    /// The sync notifies the JSON-RPC subscriptions of the blocks it stores.
+    pub new_block_sender: broadcast::Sender<BlockNumber>,
+}
+
+impl Default for SharedSyncState {
+    fn default() -> Self {
+        Self {
+            shared_highest_block: Arc::new(RwLock::new(None)),
+            pending_data: Arc::new(RwLock::new(PendingData {
+                block: PendingBlock {
+                    parent_block_hash: BlockHash(stark_felt!(GENESIS_HASH)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })),
+            pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+            new_block_sender: broadcast::channel(NEW_BLOCKS_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+/// Runs the JSON-RPC server of the node.
+pub async fn run_rpc_server(
+    config: &RpcConfig,
+    shared_state: &SharedSyncState,
+    storage_reader: StorageReader,
+) -> anyhow::Result<(SocketAddr, ServerHandle)> {
+    run_server(
+        config,
+        shared_state.shared_highest_block.clone(),
+        shared_state.pending_data.clone(),
+        shared_state.pending_classes.clone(),
+        storage_reader,
+        shared_state.new_block_sender.clone(),
+        VERSION_FULL,
+    )
+    .await
+}
+
+/// Creates the sources the sync downloads blocks and pending data from. The pending source shares
+/// the client of the central source, so that their identical requests share responses.
+pub fn create_central_sources(
+    config: CentralSourceConfig,
+    storage_reader: StorageReader,
+) -> Result<(CentralSource, PendingSource), CentralError> {
+    let central_source = CentralSource::new(config, VERSION_FULL, storage_reader)
+        .map_err(CentralError::ClientCreation)?;
+    let pending_source = PendingSource { starknet_client: central_source.starknet_client.clone() };
+    Ok((central_source, pending_source))
+}
+
+/// Creates the sync of the node, which syncs the central sources and the given base layer source.
+pub fn create_sync<TBaseLayerSource: BaseLayerSourceTrait + Sync + Send>(
+    config: SyncConfig,
+    shared_state: SharedSyncState,
+    central_source: CentralSource,
+    pending_source: PendingSource,
+    base_layer_source: TBaseLayerSource,
+    storage_reader: StorageReader,
+    storage_writer: StorageWriter,
+) -> GenericStateSync<CentralSource, PendingSource, TBaseLayerSource> {
+    GenericStateSync::new(
+        config,
+        shared_state.shared_highest_block,
+        shared_state.pending_data,
+        shared_state.pending_classes,
+        central_source,
+        pending_source,
+        base_layer_source,
+        storage_reader,
+        storage_writer,
+        shared_state.new_block_sender,
+    )
+}
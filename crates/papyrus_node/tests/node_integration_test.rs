@@ -0,0 +1,33 @@
+#![allow(clippy::unwrap_used)]
+mod test_node;
+
+use starknet_api::block::BlockNumber;
+use test_node::{chain_hashes, TestNode};
+
+#[tokio::test]
+async fn sync_and_serve_chain_with_reorg() {
+    let chain = chain_hashes(4, 0);
+    let node = TestNode::start(&chain).await;
+    node.wait_for_latest_block(BlockNumber(3), chain[3]).await;
+
+    let block = node.send_request("starknet_getBlockWithTxHashes", r#"{"block_number": 2}"#).await;
+    assert_eq!(block["result"]["block_hash"], serde_json::json!(chain[2]));
+    assert_eq!(block["result"]["parent_hash"], serde_json::json!(chain[1]));
+
+    // Reorg blocks 2 and 3, and extend the chain so the node notices the reorg.
+    let reorged_chain = [&chain[..2], &chain_hashes(6, 1)[2..]].concat();
+    node.set_chain(&reorged_chain);
+    node.wait_for_latest_block(BlockNumber(5), reorged_chain[5]).await;
+
+    let block = node.send_request("starknet_getBlockWithTxHashes", r#"{"block_number": 2}"#).await;
+    assert_eq!(block["result"]["block_hash"], serde_json::json!(reorged_chain[2]));
+    let block = node.send_request("starknet_getBlockWithTxHashes", r#"{"block_number": 1}"#).await;
+    assert_eq!(block["result"]["block_hash"], serde_json::json!(chain[1]));
+    let old_block = node
+        .send_request(
+            "starknet_getBlockWithTxHashes",
+            &format!(r#"{{"block_hash": "{}"}}"#, chain[3]),
+        )
+        .await;
+    assert!(old_block["error"].is_object(), "A reorged block is served: {old_block}.");
+}
@@ -0,0 +1,229 @@
+//! A harness for end-to-end tests of the node. The sync, the storage and the JSON-RPC server run
+//! in-process like in the node binary, and the sync downloads a scripted chain from a
+//! [`MockGateway`]. The base layer isn't synced.
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use jsonrpsee::server::ServerHandle;
+use papyrus_common::l1_to_l2_messages::L1ToL2Message;
+use papyrus_node::config::NodeConfig;
+use papyrus_node::setup::{create_central_sources, create_sync, run_rpc_server, SharedSyncState};
+use papyrus_storage::open_storage;
+use papyrus_sync::sources::base_layer::{BaseLayerSourceError, BaseLayerSourceTrait};
+use papyrus_sync::{StateSyncResult, SyncConfig};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::hash::{StarkFelt, GENESIS_HASH};
+use starknet_api::stark_felt;
+use starknet_client::reader::objects::pending_data::PendingBlock;
+use starknet_client::reader::{
+    Block,
+    BlockSignatureData,
+    BlockSignatureMessage,
+    PendingData,
+    StateUpdate,
+};
+use starknet_client::retry::RetryConfig;
+use starknet_client::test_utils::mock_gateway::{MockGateway, MockResponse};
+use tempfile::TempDir;
+use tokio::task::JoinHandle;
+
+const RPC_VERSION: &str = "V0_6";
+// The time to wait for the node to reach an expected state.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// A base layer without proved blocks or messages.
+struct NoBaseLayer;
+
+#[async_trait]
+impl BaseLayerSourceTrait for NoBaseLayer {
+    async fn latest_proved_block(
+        &self,
+    ) -> Result<Option<(BlockNumber, BlockHash)>, BaseLayerSourceError> {
+        Ok(None)
+    }
+
+    async fn latest_block_number(
+        &self,
+        _min_confirmations: u64,
+    ) -> Result<Option<u64>, BaseLayerSourceError> {
+        Ok(None)
+    }
+
+    async fn l1_to_l2_messages(
+        &self,
+        _from_block: u64,
+        _to_block: u64,
+    ) -> Result<Vec<L1ToL2Message>, BaseLayerSourceError> {
+        Ok(vec![])
+    }
+}
+
+/// A node that syncs from a mock gateway and serves JSON-RPC requests. Stops when dropped.
+pub struct TestNode {
+    pub gateway: MockGateway,
+    pub rpc_address: SocketAddr,
+    sync_handle: JoinHandle<StateSyncResult>,
+    server_handle: ServerHandle,
+    _storage_dir: TempDir,
+}
+
+impl TestNode {
+    /// Starts a node that syncs the given chain, see [`TestNode::set_chain`].
+    pub async fn start(chain: &[BlockHash]) -> Self {
+        let gateway = MockGateway::start();
+        set_chain(&gateway, chain);
+
+        let storage_dir = TempDir::new().unwrap();
+        let mut config = NodeConfig::default();
+        config.storage.db_config.path_prefix = storage_dir.path().into();
+        config.rpc.server_address = "127.0.0.1:0".to_owned();
+        config.rpc.execution_config =
+            test_utils::get_absolute_path("config/execution/mainnet.json");
+        config.central.url = gateway.url();
+        // Responses change whenever the chain is reorged, so they aren't cached.
+        config.central.response_cache_ttl = Duration::ZERO;
+        config.central.retry_config = RetryConfig {
+            retry_base_millis: 10,
+            backoff_multiplier: 10,
+            retry_max_delay_millis: 100,
+            max_retries: 3,
+        };
+        let sync_config = SyncConfig {
+            block_propagation_sleep_duration: POLL_INTERVAL,
+            base_layer_propagation_sleep_duration: POLL_INTERVAL,
+            recoverable_error_sleep_duration: POLL_INTERVAL,
+            ..Default::default()
+        };
+
+        let (storage_reader, storage_writer) = open_storage(config.storage.clone()).unwrap();
+        let shared_sync_state = SharedSyncState::default();
+        let (rpc_address, server_handle) =
+            run_rpc_server(&config.rpc, &shared_sync_state, storage_reader.clone()).await.unwrap();
+        let (central_source, pending_source) =
+            create_central_sources(config.central, storage_reader.clone()).unwrap();
+        let mut sync = create_sync(
+            sync_config,
+            shared_sync_state,
+            central_source,
+            pending_source,
+            NoBaseLayer,
+            storage_reader,
+            storage_writer,
+        );
+        let sync_handle = tokio::spawn(async move { sync.run().await });
+
+        Self { gateway, rpc_address, sync_handle, server_handle, _storage_dir: storage_dir }
+    }
+
+    /// Replaces the chain the gateway serves. The chain is given by the hashes of its blocks,
+    /// starting from block 0, and its blocks have no transactions. A block whose hash differs from
+    /// the hash it had in the previous chain is reorged, so to reorg the node the new chain must be
+    /// longer than the chain the node synced.
+    pub fn set_chain(&self, chain: &[BlockHash]) {
+        set_chain(&self.gateway, chain);
+    }
+
+    /// Sends a JSON-RPC request to the node and returns the response.
+    pub async fn send_request(&self, method: &str, params: &str) -> serde_json::Value {
+        test_utils::send_request(self.rpc_address, method, params, RPC_VERSION).await
+    }
+
+    /// Waits until the given condition on the node holds. Panics if it doesn't hold in time or if
+    /// the sync stopped.
+    pub async fn wait_until<F, Fut>(&self, description: &str, condition: F)
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let wait = async {
+            while !condition().await {
+                assert!(!self.sync_handle.is_finished(), "The sync stopped.");
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        };
+        tokio::time::timeout(WAIT_TIMEOUT, wait)
+            .await
+            .unwrap_or_else(|_| panic!("Timed out waiting until {description}."));
+    }
+
+    /// Waits until the latest block of the node is the given block.
+    pub async fn wait_for_latest_block(&self, block_number: BlockNumber, block_hash: BlockHash) {
+        let expected = serde_json::json!({
+            "block_hash": block_hash,
+            "block_number": block_number,
+        });
+        self.wait_until(
+            &format!("block {block_number} with hash {block_hash} is synced"),
+            || async {
+                self.send_request("starknet_blockHashAndNumber", "").await["result"] == expected
+            },
+        )
+        .await;
+    }
+}
+
+impl Drop for TestNode {
+    fn drop(&mut self) {
+        self.sync_handle.abort();
+        self.server_handle.stop().ok();
+    }
+}
+
+/// Returns the hashes of a chain with the given number of blocks. Chains with different forks
+/// have different hashes in all their blocks.
+pub fn chain_hashes(n_blocks: u64, fork: u64) -> Vec<BlockHash> {
+    (0..n_blocks).map(|i| BlockHash(StarkFelt::from(((fork << 32) | i) + 1))).collect()
+}
+
+fn set_chain(gateway: &MockGateway, chain: &[BlockHash]) {
+    let mut parent_block_hash = BlockHash(stark_felt!(GENESIS_HASH));
+    for (i, block_hash) in chain.iter().enumerate() {
+        let block_number = BlockNumber(i as u64);
+        let block = Block {
+            block_hash: *block_hash,
+            block_number,
+            parent_block_hash,
+            ..Default::default()
+        };
+        set_json_response(gateway, &format!("get_block?blockNumber={block_number}"), &block);
+        set_json_response(
+            gateway,
+            &format!("get_signature?blockNumber={block_number}"),
+            &BlockSignatureData {
+                block_number,
+                signature: [StarkFelt::default(); 2],
+                signature_input: BlockSignatureMessage {
+                    block_hash: *block_hash,
+                    ..Default::default()
+                },
+            },
+        );
+        set_json_response(
+            gateway,
+            &format!("get_state_update?blockNumber={block_number}"),
+            &StateUpdate { block_hash: *block_hash, ..Default::default() },
+        );
+        if i + 1 == chain.len() {
+            set_json_response(gateway, "get_block?blockNumber=latest", &block);
+        }
+        parent_block_hash = *block_hash;
+    }
+    set_json_response(
+        gateway,
+        "get_state_update?blockNumber=pending&includeBlock=true",
+        &PendingData {
+            block: PendingBlock { parent_block_hash, ..Default::default() },
+            ..Default::default()
+        },
+    );
+}
+
+fn set_json_response(gateway: &MockGateway, endpoint: &str, object: &impl serde::Serialize) {
+    gateway.set_response(
+        &format!("/feeder_gateway/{endpoint}"),
+        MockResponse::ok(serde_json::to_string(object).unwrap()),
+    );
+}
@@ -0,0 +1,94 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::{Arg, Command};
+use papyrus_parquet_export::export_block_range;
+
+/// This executable exports the blocks, transactions, events and state diffs of a block range from
+/// the storage into Parquet files.
+fn main() -> ExitCode {
+    let cli_params = get_cli_params();
+    let summary = match export_block_range(
+        cli_params.path_prefix.into(),
+        &cli_params.chain_id,
+        Path::new(&cli_params.output_dir),
+        cli_params.start_block,
+        cli_params.end_block,
+    ) {
+        Ok(summary) => summary,
+        Err(e) => {
+            println!("Failed exporting the storage with error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "Exported blocks {}..{} to {}: {} blocks, {} transactions, {} events and {} state diff \
+         entries.",
+        cli_params.start_block,
+        summary.end_block,
+        cli_params.output_dir,
+        summary.blocks,
+        summary.transactions,
+        summary.events,
+        summary.state_diff_entries
+    );
+    ExitCode::SUCCESS
+}
+
+struct CliParams {
+    path_prefix: String,
+    chain_id: String,
+    output_dir: String,
+    start_block: u64,
+    end_block: Option<u64>,
+}
+
+/// The path_prefix, chain_id and output_dir arguments are mandatory. The start_block argument is
+/// inclusive and defaults to 0, and the end_block argument is exclusive and defaults to the last
+/// fully stored block.
+fn get_cli_params() -> CliParams {
+    let matches = Command::new("Export parquet")
+        .arg(
+            Arg::new("path_prefix")
+                .short('p')
+                .long("path_prefix")
+                .required(true)
+                .help("The path prefix of the storage."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storage, SN_MAIN/SN_GOERLI."),
+        )
+        .arg(
+            Arg::new("output_dir")
+                .short('o')
+                .long("output_dir")
+                .required(true)
+                .help("The directory to write the Parquet files to."),
+        )
+        .arg(
+            Arg::new("start_block")
+                .long("start_block")
+                .default_value("0")
+                .help("The block number to start exporting from."),
+        )
+        .arg(Arg::new("end_block").long("end_block").help("The block number to end exporting at."))
+        .get_matches();
+
+    let get_arg = |name: &str| matches.get_one::<String>(name).map(String::to_string);
+    let parse_block_number =
+        |block_number: String| block_number.parse::<u64>().expect("Failed parsing block number");
+    let path_prefix = get_arg("path_prefix").expect("Failed parsing path_prefix");
+    let chain_id = get_arg("chain_id").expect("Failed parsing chain_id");
+    let output_dir = get_arg("output_dir").expect("Failed parsing output_dir");
+    let start_block =
+        parse_block_number(get_arg("start_block").expect("Failed parsing start_block"));
+    let end_block = get_arg("end_block").map(parse_block_number);
+    if end_block.is_some_and(|end_block| start_block >= end_block) {
+        panic!("start_block must be smaller than end_block");
+    }
+    CliParams { path_prefix, chain_id, output_dir, start_block, end_block }
+}
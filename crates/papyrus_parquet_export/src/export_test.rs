@@ -0,0 +1,138 @@
+use std::fs::File;
+use std::path::Path;
+
+use arrow::array::{Array, StringArray, UInt64Array};
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use papyrus_storage::chain_fixture::ChainFixtureBuilder;
+use papyrus_storage::test_utils::get_test_storage;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+
+use crate::tables::{BlocksTable, EventsTable, StateDiffsTable, Table, TransactionsTable};
+use crate::{export_block_range_from_reader, ExportSummary};
+
+const N_BLOCKS: u64 = 6;
+
+// Reads a Parquet file into a single record batch, after checking its schema.
+fn read_table<T: Table>(output_dir: &Path) -> RecordBatch {
+    let file = File::open(output_dir.join(T::FILE_NAME)).unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap().build().unwrap();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    let schema = batches[0].schema();
+    assert_eq!(Schema::new(schema.fields().clone()), T::schema());
+    arrow::compute::concat_batches(&schema, &batches).unwrap()
+}
+
+fn string_column<'a>(batch: &'a RecordBatch, name: &str) -> &'a StringArray {
+    batch.column_by_name(name).unwrap().as_any().downcast_ref::<StringArray>().unwrap()
+}
+
+fn u64_column<'a>(batch: &'a RecordBatch, name: &str) -> &'a UInt64Array {
+    batch.column_by_name(name).unwrap().as_any().downcast_ref::<UInt64Array>().unwrap()
+}
+
+#[test]
+fn export_chain() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let fixture = ChainFixtureBuilder::new(N_BLOCKS)
+        .transactions_per_block(1..=5)
+        .max_events_per_transaction(3)
+        .initial_contracts(3)
+        .storage_keys_per_contract(4)
+        .storage_updates_per_block(5)
+        .build();
+    fixture.write_to_storage(&mut writer).unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+
+    let start_block = 1;
+    let summary =
+        export_block_range_from_reader(&reader, output_dir.path(), start_block, None).unwrap();
+
+    let exported_blocks = &fixture.blocks[start_block as usize..];
+    let exported_state_diffs = &fixture.state_diffs[start_block as usize..];
+    let expected_summary = ExportSummary {
+        end_block: BlockNumber(N_BLOCKS),
+        blocks: exported_blocks.len(),
+        transactions: exported_blocks.iter().map(|block| block.body.transactions.len()).sum(),
+        events: exported_blocks
+            .iter()
+            .flat_map(|block| &block.body.transaction_outputs)
+            .map(|output| output.events().len())
+            .sum(),
+        state_diff_entries: exported_state_diffs
+            .iter()
+            .map(|state_diff| {
+                state_diff.deployed_contracts.len()
+                    + state_diff.storage_diffs.values().map(|entries| entries.len()).sum::<usize>()
+                    + state_diff.declared_classes.len()
+                    + state_diff.deprecated_declared_classes.len()
+                    + state_diff.nonces.len()
+                    + state_diff.replaced_classes.len()
+            })
+            .sum(),
+    };
+    assert_eq!(summary, expected_summary);
+
+    let blocks = read_table::<BlocksTable>(output_dir.path());
+    let expected_block_numbers: Vec<_> =
+        exported_blocks.iter().map(|block| Some(block.header.block_number.0)).collect();
+    assert_eq!(
+        u64_column(&blocks, "block_number").iter().collect::<Vec<_>>(),
+        expected_block_numbers
+    );
+    let expected_block_hashes: Vec<_> = exported_blocks
+        .iter()
+        .map(|block| Some(format!("0x{}", hex::encode(block.header.block_hash.0.bytes()))))
+        .collect();
+    assert_eq!(
+        string_column(&blocks, "block_hash")
+            .iter()
+            .map(|hash| hash.map(String::from))
+            .collect::<Vec<_>>(),
+        expected_block_hashes
+    );
+
+    let transactions = read_table::<TransactionsTable>(output_dir.path());
+    assert_eq!(transactions.num_rows(), expected_summary.transactions);
+    let expected_transaction_hashes: Vec<_> = exported_blocks
+        .iter()
+        .flat_map(|block| &block.body.transaction_hashes)
+        .map(|hash| Some(format!("0x{}", hex::encode(hash.0.bytes()))))
+        .collect();
+    assert_eq!(
+        string_column(&transactions, "transaction_hash")
+            .iter()
+            .map(|hash| hash.map(String::from))
+            .collect::<Vec<_>>(),
+        expected_transaction_hashes
+    );
+    assert_eq!(string_column(&transactions, "actual_fee").null_count(), 0);
+
+    let events = read_table::<EventsTable>(output_dir.path());
+    assert_eq!(events.num_rows(), expected_summary.events);
+
+    let state_diffs = read_table::<StateDiffsTable>(output_dir.path());
+    assert_eq!(state_diffs.num_rows(), expected_summary.state_diff_entries);
+}
+
+#[test]
+fn export_bounded_range() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let fixture = ChainFixtureBuilder::new(N_BLOCKS).build();
+    fixture.write_to_storage(&mut writer).unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+
+    let summary = export_block_range_from_reader(&reader, output_dir.path(), 2, Some(4)).unwrap();
+    assert_eq!(summary.end_block, BlockNumber(4));
+    assert_eq!(summary.blocks, 2);
+    let blocks = read_table::<BlocksTable>(output_dir.path());
+    assert_eq!(u64_column(&blocks, "block_number").values().to_vec(), vec![2, 3]);
+
+    // The range ends at the last stored block.
+    let summary =
+        export_block_range_from_reader(&reader, output_dir.path(), 0, Some(N_BLOCKS + 10)).unwrap();
+    assert_eq!(summary.end_block, BlockNumber(N_BLOCKS));
+    assert_eq!(summary.blocks, N_BLOCKS as usize);
+}
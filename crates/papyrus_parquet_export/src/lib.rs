@@ -0,0 +1,142 @@
+// config compiler to support coverage_attribute feature when running coverage in nightly mode
+// within this crate
+#![cfg_attr(coverage_nightly, feature(coverage_attribute))]
+
+//! Exports the blocks, transactions, events and state diffs of a block range from the storage into
+//! Parquet files, so that the data can be loaded into analytics engines such as Spark or DuckDB
+//! without querying the node's RPC.
+//!
+//! Each table is written to its own file in the output directory. See the [`tables`] module for
+//! the schemas.
+
+#[cfg(test)]
+#[path = "export_test.rs"]
+mod export_test;
+mod tables;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use arrow::error::ArrowError;
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{open_storage_read_only, StorageConfig, StorageError, StorageReader};
+use parquet::errors::ParquetError;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+use starknet_api::transaction::TransactionOffsetInBlock;
+use tables::{BlocksTable, EventsTable, StateDiffsTable, TableWriter, TransactionsTable};
+
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+}
+
+/// The number of rows that were written to each file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// The block after the last exported block.
+    pub end_block: BlockNumber,
+    pub blocks: usize,
+    pub transactions: usize,
+    pub events: usize,
+    pub state_diff_entries: usize,
+}
+
+/// Exports the blocks in the range [start_block, end_block) from the storage into Parquet files in
+/// the output directory. The range ends at the first block that is not fully stored (header, body
+/// and state diff) if that comes first. The storage is opened read-only, so it can be exported
+/// while a node syncs it.
+pub fn export_block_range(
+    path_prefix: PathBuf,
+    chain_id: &str,
+    output_dir: &Path,
+    start_block: u64,
+    end_block: Option<u64>,
+) -> Result<ExportSummary, ExportError> {
+    let mut storage_config = StorageConfig::default();
+    storage_config.db_config.path_prefix = path_prefix;
+    storage_config.db_config.chain_id = ChainId(chain_id.to_string());
+    storage_config.db_config.enforce_file_exists = true;
+    let reader = open_storage_read_only(storage_config)?;
+    export_block_range_from_reader(&reader, output_dir, start_block, end_block)
+}
+
+fn export_block_range_from_reader(
+    reader: &StorageReader,
+    output_dir: &Path,
+    start_block: u64,
+    end_block: Option<u64>,
+) -> Result<ExportSummary, ExportError> {
+    let txn = reader.begin_ro_txn()?;
+    let marker = txn.get_header_marker()?.min(txn.get_body_marker()?).min(txn.get_state_marker()?);
+    let end_block = end_block.map_or(marker, |end_block| marker.min(BlockNumber(end_block)));
+    drop(txn);
+
+    fs::create_dir_all(output_dir)?;
+    let mut blocks = TableWriter::<BlocksTable>::new(output_dir)?;
+    let mut transactions = TableWriter::<TransactionsTable>::new(output_dir)?;
+    let mut events = TableWriter::<EventsTable>::new(output_dir)?;
+    let mut state_diffs = TableWriter::<StateDiffsTable>::new(output_dir)?;
+
+    // A transaction per block, so that a long export doesn't hold a single read transaction open.
+    for block_number in (start_block..end_block.0).map(BlockNumber) {
+        let txn = reader.begin_ro_txn()?;
+        let missing_data = || StorageError::DBInconsistency {
+            msg: format!("Missing data of block {block_number}."),
+        };
+        let header = txn.get_block_header(block_number)?.ok_or_else(missing_data)?;
+        let block_transactions =
+            txn.get_block_transactions(block_number)?.ok_or_else(missing_data)?;
+        let transaction_hashes =
+            txn.get_block_transaction_hashes(block_number)?.ok_or_else(missing_data)?;
+        let transaction_outputs =
+            txn.get_block_transaction_outputs(block_number)?.ok_or_else(missing_data)?;
+        let state_diff = txn.get_state_diff(block_number)?.ok_or_else(missing_data)?;
+        let starknet_version = txn.get_starknet_version(block_number)?;
+
+        let mut event_count = 0;
+        for (offset, ((transaction, transaction_hash), output)) in
+            block_transactions.iter().zip(&transaction_hashes).zip(&transaction_outputs).enumerate()
+        {
+            transactions.table.append(&header, offset, transaction_hash, transaction, output);
+            let transaction_index =
+                TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            let transaction_events =
+                txn.get_transaction_events(transaction_index)?.ok_or_else(missing_data)?;
+            for (event_index, event) in transaction_events.iter().enumerate() {
+                events.table.append(&header, offset, transaction_hash, event_index, event);
+            }
+            event_count += transaction_events.len();
+        }
+        blocks.table.append(
+            &header,
+            block_transactions.len(),
+            event_count,
+            starknet_version.as_ref().map(|version| version.0.as_str()),
+        );
+        state_diffs.table.append(&header, &state_diff);
+
+        blocks.flush_if_full()?;
+        transactions.flush_if_full()?;
+        events.flush_if_full()?;
+        state_diffs.flush_if_full()?;
+    }
+
+    Ok(ExportSummary {
+        end_block,
+        blocks: blocks.close()?,
+        transactions: transactions.close()?,
+        events: events.close()?,
+        state_diff_entries: state_diffs.close()?,
+    })
+}
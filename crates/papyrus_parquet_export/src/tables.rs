@@ -0,0 +1,570 @@
+//! The tables that are exported, each into its own Parquet file.
+//!
+//! The schemas are stable: a column is never removed, renamed or retyped, and new columns are only
+//! added at the end of a table. Felts are written as 0x-prefixed, zero-padded 64 digit hex strings
+//! and 128-bit amounts (fees and gas prices) as decimal strings, since neither fits an integer
+//! column.
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayBuilder, ArrayRef, ListBuilder, StringBuilder, UInt64Builder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use papyrus_storage::body::events::ThinTransactionOutput;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use starknet_api::block::BlockHeader;
+use starknet_api::core::ContractAddress;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{
+    DeclareTransaction,
+    DeployAccountTransaction,
+    Event,
+    InvokeTransaction,
+    Transaction,
+    TransactionExecutionStatus,
+    TransactionHash,
+};
+
+use crate::ExportError;
+
+// The number of rows that are buffered before they are written to the file.
+const ROWS_PER_BATCH: usize = 8192;
+
+/// A table whose rows are buffered in Arrow builders.
+pub(crate) trait Table: Default {
+    /// The name of the file of the table in the output directory.
+    const FILE_NAME: &'static str;
+
+    fn schema() -> Schema;
+
+    fn num_rows(&self) -> usize;
+
+    /// Returns the columns of the buffered rows and clears the buffer.
+    fn finish(&mut self) -> Vec<ArrayRef>;
+}
+
+/// Writes the rows of a table to its Parquet file.
+pub(crate) struct TableWriter<T: Table> {
+    pub table: T,
+    schema: SchemaRef,
+    writer: ArrowWriter<File>,
+    written_rows: usize,
+}
+
+impl<T: Table> TableWriter<T> {
+    pub fn new(output_dir: &Path) -> Result<Self, ExportError> {
+        let schema = Arc::new(T::schema());
+        let file = File::create(output_dir.join(T::FILE_NAME))?;
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))?;
+        Ok(Self { table: T::default(), schema, writer, written_rows: 0 })
+    }
+
+    /// Writes the buffered rows if there are enough of them.
+    pub fn flush_if_full(&mut self) -> Result<(), ExportError> {
+        if self.table.num_rows() >= ROWS_PER_BATCH {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining rows and closes the file. Returns the number of rows in the file.
+    pub fn close(mut self) -> Result<usize, ExportError> {
+        self.flush()?;
+        self.writer.close()?;
+        Ok(self.written_rows)
+    }
+
+    fn flush(&mut self) -> Result<(), ExportError> {
+        let num_rows = self.table.num_rows();
+        if num_rows == 0 {
+            return Ok(());
+        }
+        let batch = RecordBatch::try_new(self.schema.clone(), self.table.finish())?;
+        self.writer.write(&batch)?;
+        self.written_rows += num_rows;
+        Ok(())
+    }
+}
+
+/// A row per block.
+#[derive(Default)]
+pub(crate) struct BlocksTable {
+    block_number: UInt64Builder,
+    block_hash: StringBuilder,
+    parent_hash: StringBuilder,
+    state_root: StringBuilder,
+    sequencer_address: StringBuilder,
+    timestamp: UInt64Builder,
+    eth_l1_gas_price: StringBuilder,
+    strk_l1_gas_price: StringBuilder,
+    transaction_count: UInt64Builder,
+    event_count: UInt64Builder,
+    starknet_version: StringBuilder,
+}
+
+impl BlocksTable {
+    pub fn append(
+        &mut self,
+        header: &BlockHeader,
+        transaction_count: usize,
+        event_count: usize,
+        starknet_version: Option<&str>,
+    ) {
+        self.block_number.append_value(header.block_number.0);
+        self.block_hash.append_value(felt_to_hex(&header.block_hash.0));
+        self.parent_hash.append_value(felt_to_hex(&header.parent_hash.0));
+        self.state_root.append_value(felt_to_hex(&header.state_root.0));
+        self.sequencer_address.append_value(address_to_hex(&header.sequencer));
+        self.timestamp.append_value(header.timestamp.0);
+        self.eth_l1_gas_price.append_value(header.eth_l1_gas_price.0.to_string());
+        self.strk_l1_gas_price.append_value(header.strk_l1_gas_price.0.to_string());
+        self.transaction_count.append_value(transaction_count as u64);
+        self.event_count.append_value(event_count as u64);
+        self.starknet_version.append_option(starknet_version);
+    }
+}
+
+impl Table for BlocksTable {
+    const FILE_NAME: &'static str = "blocks.parquet";
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("block_number", DataType::UInt64, false),
+            Field::new("block_hash", DataType::Utf8, false),
+            Field::new("parent_hash", DataType::Utf8, false),
+            Field::new("state_root", DataType::Utf8, false),
+            Field::new("sequencer_address", DataType::Utf8, false),
+            Field::new("timestamp", DataType::UInt64, false),
+            Field::new("eth_l1_gas_price", DataType::Utf8, false),
+            Field::new("strk_l1_gas_price", DataType::Utf8, false),
+            Field::new("transaction_count", DataType::UInt64, false),
+            Field::new("event_count", DataType::UInt64, false),
+            Field::new("starknet_version", DataType::Utf8, true),
+        ])
+    }
+
+    fn num_rows(&self) -> usize {
+        self.block_number.len()
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            self.block_number.finish_array(),
+            self.block_hash.finish_array(),
+            self.parent_hash.finish_array(),
+            self.state_root.finish_array(),
+            self.sequencer_address.finish_array(),
+            self.timestamp.finish_array(),
+            self.eth_l1_gas_price.finish_array(),
+            self.strk_l1_gas_price.finish_array(),
+            self.transaction_count.finish_array(),
+            self.event_count.finish_array(),
+            self.starknet_version.finish_array(),
+        ]
+    }
+}
+
+/// A row per transaction, with the fields of its receipt.
+#[derive(Default)]
+pub(crate) struct TransactionsTable {
+    block_number: UInt64Builder,
+    transaction_index: UInt64Builder,
+    transaction_hash: StringBuilder,
+    transaction_type: StringBuilder,
+    version: StringBuilder,
+    contract_address: StringBuilder,
+    class_hash: StringBuilder,
+    nonce: StringBuilder,
+    calldata: ListBuilder<StringBuilder>,
+    signature: ListBuilder<StringBuilder>,
+    actual_fee: StringBuilder,
+    execution_status: StringBuilder,
+}
+
+impl TransactionsTable {
+    pub fn append(
+        &mut self,
+        header: &BlockHeader,
+        transaction_index: usize,
+        transaction_hash: &TransactionHash,
+        transaction: &Transaction,
+        output: &ThinTransactionOutput,
+    ) {
+        let fields = TransactionFields::new(transaction, output);
+        self.block_number.append_value(header.block_number.0);
+        self.transaction_index.append_value(transaction_index as u64);
+        self.transaction_hash.append_value(felt_to_hex(&transaction_hash.0));
+        self.transaction_type.append_value(fields.transaction_type);
+        self.version.append_value(felt_to_hex(&fields.version));
+        self.contract_address.append_value(address_to_hex(&fields.contract_address));
+        self.class_hash.append_option(fields.class_hash.as_ref().map(felt_to_hex));
+        self.nonce.append_option(fields.nonce.as_ref().map(felt_to_hex));
+        append_optional_felts(&mut self.calldata, fields.calldata);
+        append_optional_felts(&mut self.signature, fields.signature);
+        self.actual_fee.append_value(output.actual_fee().0.to_string());
+        self.execution_status.append_value(match output.execution_status() {
+            TransactionExecutionStatus::Succeeded => "SUCCEEDED",
+            TransactionExecutionStatus::Reverted => "REVERTED",
+        });
+    }
+}
+
+impl Table for TransactionsTable {
+    const FILE_NAME: &'static str = "transactions.parquet";
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("block_number", DataType::UInt64, false),
+            Field::new("transaction_index", DataType::UInt64, false),
+            Field::new("transaction_hash", DataType::Utf8, false),
+            Field::new("type", DataType::Utf8, false),
+            Field::new("version", DataType::Utf8, false),
+            // The sender of a declare or an invoke, the deployed contract of a deploy or a deploy
+            // account, and the called contract of an L1 handler.
+            Field::new("contract_address", DataType::Utf8, false),
+            Field::new("class_hash", DataType::Utf8, true),
+            Field::new("nonce", DataType::Utf8, true),
+            Field::new("calldata", felt_list_type(), true),
+            Field::new("signature", felt_list_type(), true),
+            Field::new("actual_fee", DataType::Utf8, false),
+            Field::new("execution_status", DataType::Utf8, false),
+        ])
+    }
+
+    fn num_rows(&self) -> usize {
+        self.block_number.len()
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            self.block_number.finish_array(),
+            self.transaction_index.finish_array(),
+            self.transaction_hash.finish_array(),
+            self.transaction_type.finish_array(),
+            self.version.finish_array(),
+            self.contract_address.finish_array(),
+            self.class_hash.finish_array(),
+            self.nonce.finish_array(),
+            self.calldata.finish_array(),
+            self.signature.finish_array(),
+            self.actual_fee.finish_array(),
+            self.execution_status.finish_array(),
+        ]
+    }
+}
+
+/// A row per event.
+#[derive(Default)]
+pub(crate) struct EventsTable {
+    block_number: UInt64Builder,
+    transaction_index: UInt64Builder,
+    transaction_hash: StringBuilder,
+    event_index: UInt64Builder,
+    from_address: StringBuilder,
+    keys: ListBuilder<StringBuilder>,
+    data: ListBuilder<StringBuilder>,
+}
+
+impl EventsTable {
+    pub fn append(
+        &mut self,
+        header: &BlockHeader,
+        transaction_index: usize,
+        transaction_hash: &TransactionHash,
+        event_index: usize,
+        event: &Event,
+    ) {
+        self.block_number.append_value(header.block_number.0);
+        self.transaction_index.append_value(transaction_index as u64);
+        self.transaction_hash.append_value(felt_to_hex(&transaction_hash.0));
+        self.event_index.append_value(event_index as u64);
+        self.from_address.append_value(address_to_hex(&event.from_address));
+        append_felts(&mut self.keys, event.content.keys.iter().map(|key| &key.0));
+        append_felts(&mut self.data, &event.content.data.0);
+    }
+}
+
+impl Table for EventsTable {
+    const FILE_NAME: &'static str = "events.parquet";
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("block_number", DataType::UInt64, false),
+            Field::new("transaction_index", DataType::UInt64, false),
+            Field::new("transaction_hash", DataType::Utf8, false),
+            // The index of the event in its transaction.
+            Field::new("event_index", DataType::UInt64, false),
+            Field::new("from_address", DataType::Utf8, false),
+            Field::new("keys", felt_list_type(), false),
+            Field::new("data", felt_list_type(), false),
+        ])
+    }
+
+    fn num_rows(&self) -> usize {
+        self.block_number.len()
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            self.block_number.finish_array(),
+            self.transaction_index.finish_array(),
+            self.transaction_hash.finish_array(),
+            self.event_index.finish_array(),
+            self.from_address.finish_array(),
+            self.keys.finish_array(),
+            self.data.finish_array(),
+        ]
+    }
+}
+
+/// A row per entry of a state diff. The meaning of the address, key and value columns depends on
+/// the type of the entry:
+///
+/// | type                      | address          | key         | value               |
+/// |---------------------------|------------------|-------------|---------------------|
+/// | STORAGE                   | contract address | storage key | storage value       |
+/// | NONCE                     | contract address |             | nonce               |
+/// | DEPLOYED_CONTRACT         | contract address |             | class hash          |
+/// | REPLACED_CLASS            | contract address |             | class hash          |
+/// | DECLARED_CLASS            |                  | class hash  | compiled class hash |
+/// | DEPRECATED_DECLARED_CLASS |                  | class hash  |                     |
+#[derive(Default)]
+pub(crate) struct StateDiffsTable {
+    block_number: UInt64Builder,
+    entry_type: StringBuilder,
+    address: StringBuilder,
+    key: StringBuilder,
+    value: StringBuilder,
+}
+
+impl StateDiffsTable {
+    pub fn append(&mut self, header: &BlockHeader, state_diff: &ThinStateDiff) {
+        for (address, class_hash) in &state_diff.deployed_contracts {
+            self.append_entry(
+                header,
+                "DEPLOYED_CONTRACT",
+                Some(address),
+                None,
+                Some(&class_hash.0),
+            );
+        }
+        for (address, storage_entries) in &state_diff.storage_diffs {
+            for (key, value) in storage_entries {
+                self.append_entry(header, "STORAGE", Some(address), Some(key.0.key()), Some(value));
+            }
+        }
+        for (class_hash, compiled_class_hash) in &state_diff.declared_classes {
+            self.append_entry(
+                header,
+                "DECLARED_CLASS",
+                None,
+                Some(&class_hash.0),
+                Some(&compiled_class_hash.0),
+            );
+        }
+        for class_hash in &state_diff.deprecated_declared_classes {
+            self.append_entry(header, "DEPRECATED_DECLARED_CLASS", None, Some(&class_hash.0), None);
+        }
+        for (address, nonce) in &state_diff.nonces {
+            self.append_entry(header, "NONCE", Some(address), None, Some(&nonce.0));
+        }
+        for (address, class_hash) in &state_diff.replaced_classes {
+            self.append_entry(header, "REPLACED_CLASS", Some(address), None, Some(&class_hash.0));
+        }
+    }
+
+    fn append_entry(
+        &mut self,
+        header: &BlockHeader,
+        entry_type: &str,
+        address: Option<&ContractAddress>,
+        key: Option<&StarkFelt>,
+        value: Option<&StarkFelt>,
+    ) {
+        self.block_number.append_value(header.block_number.0);
+        self.entry_type.append_value(entry_type);
+        self.address.append_option(address.map(address_to_hex));
+        self.key.append_option(key.map(felt_to_hex));
+        self.value.append_option(value.map(felt_to_hex));
+    }
+}
+
+impl Table for StateDiffsTable {
+    const FILE_NAME: &'static str = "state_diffs.parquet";
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("block_number", DataType::UInt64, false),
+            Field::new("type", DataType::Utf8, false),
+            Field::new("address", DataType::Utf8, true),
+            Field::new("key", DataType::Utf8, true),
+            Field::new("value", DataType::Utf8, true),
+        ])
+    }
+
+    fn num_rows(&self) -> usize {
+        self.block_number.len()
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            self.block_number.finish_array(),
+            self.entry_type.finish_array(),
+            self.address.finish_array(),
+            self.key.finish_array(),
+            self.value.finish_array(),
+        ]
+    }
+}
+
+// The fields of a transaction that are exported, which are spread differently in each type and
+// version of transactions.
+struct TransactionFields<'a> {
+    transaction_type: &'static str,
+    version: StarkFelt,
+    contract_address: ContractAddress,
+    class_hash: Option<StarkFelt>,
+    nonce: Option<StarkFelt>,
+    calldata: Option<&'a [StarkFelt]>,
+    signature: Option<&'a [StarkFelt]>,
+}
+
+impl<'a> TransactionFields<'a> {
+    fn new(transaction: &'a Transaction, output: &ThinTransactionOutput) -> Self {
+        // The address of a deployed contract is not part of its deploy transaction.
+        let deployed_contract_address = match output {
+            ThinTransactionOutput::Deploy(output) => output.contract_address,
+            ThinTransactionOutput::DeployAccount(output) => output.contract_address,
+            _ => ContractAddress::default(),
+        };
+        match transaction {
+            Transaction::Declare(tx) => {
+                let (sender_address, class_hash, nonce, signature) = match tx {
+                    DeclareTransaction::V0(tx) | DeclareTransaction::V1(tx) => {
+                        (tx.sender_address, tx.class_hash, tx.nonce, &tx.signature)
+                    }
+                    DeclareTransaction::V2(tx) => {
+                        (tx.sender_address, tx.class_hash, tx.nonce, &tx.signature)
+                    }
+                    DeclareTransaction::V3(tx) => {
+                        (tx.sender_address, tx.class_hash, tx.nonce, &tx.signature)
+                    }
+                };
+                Self {
+                    transaction_type: "DECLARE",
+                    version: tx.version().0,
+                    contract_address: sender_address,
+                    class_hash: Some(class_hash.0),
+                    nonce: Some(nonce.0),
+                    calldata: None,
+                    signature: Some(&signature.0),
+                }
+            }
+            Transaction::Deploy(tx) => Self {
+                transaction_type: "DEPLOY",
+                version: tx.version.0,
+                contract_address: deployed_contract_address,
+                class_hash: Some(tx.class_hash.0),
+                nonce: None,
+                calldata: Some(&tx.constructor_calldata.0),
+                signature: None,
+            },
+            Transaction::DeployAccount(tx) => {
+                let (class_hash, nonce, calldata, signature) = match tx {
+                    DeployAccountTransaction::V1(tx) => {
+                        (tx.class_hash, tx.nonce, &tx.constructor_calldata, &tx.signature)
+                    }
+                    DeployAccountTransaction::V3(tx) => {
+                        (tx.class_hash, tx.nonce, &tx.constructor_calldata, &tx.signature)
+                    }
+                };
+                Self {
+                    transaction_type: "DEPLOY_ACCOUNT",
+                    version: tx.version().0,
+                    contract_address: deployed_contract_address,
+                    class_hash: Some(class_hash.0),
+                    nonce: Some(nonce.0),
+                    calldata: Some(&calldata.0),
+                    signature: Some(&signature.0),
+                }
+            }
+            Transaction::Invoke(tx) => {
+                let (sender_address, nonce, calldata, signature) = match tx {
+                    InvokeTransaction::V0(tx) => {
+                        (tx.contract_address, None, &tx.calldata, &tx.signature)
+                    }
+                    InvokeTransaction::V1(tx) => {
+                        (tx.sender_address, Some(tx.nonce.0), &tx.calldata, &tx.signature)
+                    }
+                    InvokeTransaction::V3(tx) => {
+                        (tx.sender_address, Some(tx.nonce.0), &tx.calldata, &tx.signature)
+                    }
+                };
+                Self {
+                    transaction_type: "INVOKE",
+                    version: tx.version().0,
+                    contract_address: sender_address,
+                    class_hash: None,
+                    nonce,
+                    calldata: Some(&calldata.0),
+                    signature: Some(&signature.0),
+                }
+            }
+            Transaction::L1Handler(tx) => Self {
+                transaction_type: "L1_HANDLER",
+                version: tx.version.0,
+                contract_address: tx.contract_address,
+                class_hash: None,
+                nonce: Some(tx.nonce.0),
+                calldata: Some(&tx.calldata.0),
+                signature: None,
+            },
+        }
+    }
+}
+
+fn felt_list_type() -> DataType {
+    DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)))
+}
+
+fn append_felts<'a>(
+    builder: &mut ListBuilder<StringBuilder>,
+    felts: impl IntoIterator<Item = &'a StarkFelt>,
+) {
+    for felt in felts {
+        builder.values().append_value(felt_to_hex(felt));
+    }
+    builder.append(true);
+}
+
+fn append_optional_felts(builder: &mut ListBuilder<StringBuilder>, felts: Option<&[StarkFelt]>) {
+    match felts {
+        Some(felts) => append_felts(builder, felts),
+        None => builder.append(false),
+    }
+}
+
+fn felt_to_hex(felt: &StarkFelt) -> String {
+    format!("0x{}", hex::encode(felt.bytes()))
+}
+
+fn address_to_hex(address: &ContractAddress) -> String {
+    felt_to_hex(address.0.key())
+}
+
+// Finishes a builder into an array, without the concrete array type.
+trait FinishArray {
+    fn finish_array(&mut self) -> ArrayRef;
+}
+
+impl<T: ArrayBuilder> FinishArray for T {
+    fn finish_array(&mut self) -> ArrayRef {
+        ArrayBuilder::finish(self)
+    }
+}
@@ -0,0 +1,31 @@
+#![no_main]
+//! Calls the JSON-RPC methods with arbitrary params. The first byte of the data chooses the method
+//! and the rest of it is the params.
+//! Run from the papyrus_rpc directory with `cargo +nightly fuzz run rpc_request`.
+
+use std::sync::OnceLock;
+
+use jsonrpsee::Methods;
+use libfuzzer_sys::fuzz_target;
+use papyrus_rpc::fuzzing::get_methods_for_fuzzing;
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+
+static METHODS: OnceLock<(Methods, TempDir)> = OnceLock::new();
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let Some((method_index, params)) = data.split_first() else {
+        return;
+    };
+    let (methods, _storage_dir) = METHODS.get_or_init(get_methods_for_fuzzing);
+    let method_names = methods.method_names().collect::<Vec<_>>();
+    let method = method_names[usize::from(*method_index) % method_names.len()];
+    let params = String::from_utf8_lossy(params);
+    let request = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"{method}","params":{params}}}"#);
+
+    let runtime = RUNTIME.get_or_init(|| Runtime::new().expect("Failed to create a runtime"));
+    runtime.block_on(async {
+        let _ = methods.raw_json_request(&request, 1).await;
+    });
+});
@@ -1,8 +1,10 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use jsonrpsee::{Methods, RpcModule};
 use papyrus_common::pending_classes::PendingClasses;
 use papyrus_common::BlockHashAndNumber;
+use papyrus_execution::contract_class_cache::ContractClassCache;
 use papyrus_execution::ExecutionConfigByBlock;
 use papyrus_storage::StorageReader;
 use serde::{Deserialize, Serialize};
@@ -16,6 +18,9 @@ use tokio::sync::RwLock;
 use crate::v0_4::api::api_impl::JsonRpcServerV0_4Impl;
 use crate::v0_5::api::api_impl::JsonRpcServerV0_5Impl;
 use crate::v0_6::api::api_impl::JsonRpcServerV0_6Impl;
+use crate::v0_6::block_with_receipts::BlockWithReceiptsJsonRpcV0_6Server;
+use crate::v0_6::raw_transactions::RawTransactionsJsonRpcV0_6Server;
+use crate::v0_6::state_overrides::StateOverridesJsonRpcV0_6Server;
 use crate::version_config;
 
 #[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -57,6 +62,8 @@ pub struct CallRequest {
 pub fn get_methods_from_supported_apis(
     chain_id: &ChainId,
     execution_config: ExecutionConfigByBlock,
+    class_cache: ContractClassCache,
+    execution_timeout: Duration,
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
@@ -70,6 +77,8 @@ pub fn get_methods_from_supported_apis(
     let server_gen = JsonRpcServerImplGenerator {
         chain_id: chain_id.clone(),
         execution_config,
+        class_cache,
+        execution_timeout,
         storage_reader,
         max_events_chunk_size,
         max_events_keys,
@@ -107,6 +116,21 @@ pub fn get_methods_from_supported_apis(
             let _res = methods.merge(new_methods);
             methods
         });
+    // The state overrides API extends the call and simulation methods of the latest version, so
+    // it's served by the same server.
+    let _res = methods.merge(StateOverridesJsonRpcV0_6Server::into_rpc(
+        server_gen.clone().server::<JsonRpcServerV0_6Impl>(),
+    ));
+    // The block with receipts API reads the blocks and the receipts like the latest version, so
+    // it's served by the same server.
+    let _res = methods.merge(BlockWithReceiptsJsonRpcV0_6Server::into_rpc(
+        server_gen.clone().server::<JsonRpcServerV0_6Impl>(),
+    ));
+    // The raw transactions API reads the storage like the latest version, so it's served by the
+    // same server.
+    let _res = methods.merge(RawTransactionsJsonRpcV0_6Server::into_rpc(
+        server_gen.server::<JsonRpcServerV0_6Impl>(),
+    ));
     methods
 }
 
@@ -115,6 +139,8 @@ pub trait JsonRpcServerImpl: Sized {
     fn new(
         chain_id: ChainId,
         execution_config: ExecutionConfigByBlock,
+        class_cache: ContractClassCache,
+        execution_timeout: Duration,
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
@@ -132,6 +158,8 @@ pub trait JsonRpcServerImpl: Sized {
 struct JsonRpcServerImplGenerator {
     chain_id: ChainId,
     execution_config: ExecutionConfigByBlock,
+    class_cache: ContractClassCache,
+    execution_timeout: Duration,
     storage_reader: StorageReader,
     max_events_chunk_size: usize,
     max_events_keys: usize,
@@ -146,6 +174,8 @@ struct JsonRpcServerImplGenerator {
 type JsonRpcServerImplParams = (
     ChainId,
     ExecutionConfigByBlock,
+    ContractClassCache,
+    Duration,
     StorageReader,
     usize,
     usize,
@@ -161,6 +191,8 @@ impl JsonRpcServerImplGenerator {
         (
             self.chain_id,
             self.execution_config,
+            self.class_cache,
+            self.execution_timeout,
             self.storage_reader,
             self.max_events_chunk_size,
             self.max_events_keys,
@@ -173,12 +205,21 @@ impl JsonRpcServerImplGenerator {
     }
 
     fn generator<T>(self) -> Methods
+    where
+        T: JsonRpcServerImpl,
+    {
+        Into::<Methods>::into(self.server::<T>().into_rpc_module())
+    }
+
+    fn server<T>(self) -> T
     where
         T: JsonRpcServerImpl,
     {
         let (
             chain_id,
             fee_contract_address,
+            class_cache,
+            execution_timeout,
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
@@ -188,20 +229,19 @@ impl JsonRpcServerImplGenerator {
             pending_classes,
             starknet_writer,
         ) = self.get_params();
-        Into::<Methods>::into(
-            T::new(
-                chain_id,
-                fee_contract_address,
-                storage_reader,
-                max_events_chunk_size,
-                max_events_keys,
-                starting_block,
-                shared_highest_block,
-                pending_data,
-                pending_classes,
-                starknet_writer,
-            )
-            .into_rpc_module(),
+        T::new(
+            chain_id,
+            fee_contract_address,
+            class_cache,
+            execution_timeout,
+            storage_reader,
+            max_events_chunk_size,
+            max_events_keys,
+            starting_block,
+            shared_highest_block,
+            pending_data,
+            pending_classes,
+            starknet_writer,
         )
     }
 }
@@ -0,0 +1,202 @@
+//! Authenticates the clients of the JSON-RPC server, for operators that serve their node only to
+//! their own users. A request is authenticated by one of the configured API keys in the
+//! `x-api-key` header, or by a JWT that is signed with the configured secret (HS256) in the
+//! `Authorization: Bearer` header. Requests that call only the allowed unauthenticated methods
+//! don't need either. Other requests are rejected with status code 401 (unauthorized).
+//!
+//! The methods called through a WebSocket connection aren't known when it's opened, so opening a
+//! connection always needs to be authenticated.
+#[cfg(test)]
+#[path = "authentication_test.rs"]
+mod authentication_test;
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::{ready, Either, Ready};
+use hyper::header::AUTHORIZATION;
+use hyper::{Body, Request, Response, StatusCode};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use papyrus_config::converters::{deserialize_vec, serialize_slice};
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use tower::{Layer, Service};
+use tracing::debug;
+
+use crate::middleware::json_rpc_error_response;
+use crate::request_limits::CalledMethods;
+
+/// The header of the API key of a request.
+pub(crate) const API_KEY_HEADER: &str = "x-api-key";
+
+// The JSON-RPC error code of an unauthenticated request, from the range of the server errors that
+// JSON-RPC leaves to the implementations.
+const UNAUTHORIZED_CODE: i32 = -32099;
+
+/// The credentials that the clients of the JSON-RPC server authenticate with.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AuthenticationConfig {
+    #[serde(deserialize_with = "deserialize_vec")]
+    pub api_keys: Vec<String>,
+    /// None if requests can't be authenticated by a JWT.
+    pub jwt_secret: Option<String>,
+    #[serde(deserialize_with = "deserialize_vec")]
+    pub unauthenticated_methods: Vec<String>,
+}
+
+impl Default for AuthenticationConfig {
+    fn default() -> Self {
+        AuthenticationConfig {
+            api_keys: Vec::new(),
+            jwt_secret: None,
+            unauthenticated_methods: vec![
+                String::from("starknet_chainId"),
+                String::from("starknet_specVersion"),
+            ],
+        }
+    }
+}
+
+impl SerializeConfig for AuthenticationConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut self_params_dump = BTreeMap::from_iter([
+            ser_param(
+                "api_keys",
+                &serialize_slice(&self.api_keys),
+                "Space separated API keys that authenticate the requests that carry one of them \
+                 in the x-api-key header.",
+                ParamPrivacyInput::Private,
+            ),
+            ser_param(
+                "unauthenticated_methods",
+                &serialize_slice(&self.unauthenticated_methods),
+                "Space separated methods, without their version, that can be called without \
+                 authentication.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        self_params_dump.append(&mut ser_optional_param(
+            &self.jwt_secret,
+            String::new(),
+            "jwt_secret",
+            "Secret of the HS256 JWTs that authenticate the requests that carry one of them in \
+             the 'Authorization: Bearer' header. The JWTs must have an expiration time.",
+            ParamPrivacyInput::Private,
+        ));
+        self_params_dump
+    }
+}
+
+/// [`Tower`] layer that rejects the requests that aren't authenticated.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct AuthenticationLayer {
+    authenticator: Arc<Authenticator>,
+}
+
+impl AuthenticationLayer {
+    pub(crate) fn new(config: &AuthenticationConfig) -> Self {
+        Self {
+            authenticator: Arc::new(Authenticator {
+                api_keys: config.api_keys.iter().cloned().collect(),
+                jwt_key: config
+                    .jwt_secret
+                    .as_ref()
+                    .map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+                jwt_validation: Validation::new(Algorithm::HS256),
+                unauthenticated_methods: config.unauthenticated_methods.iter().cloned().collect(),
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for AuthenticationLayer {
+    type Service = Authentication<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authentication { inner, authenticator: self.authenticator.clone() }
+    }
+}
+
+struct Authenticator {
+    api_keys: HashSet<String>,
+    jwt_key: Option<DecodingKey>,
+    jwt_validation: Validation,
+    unauthenticated_methods: HashSet<String>,
+}
+
+impl Authenticator {
+    fn is_authenticated(&self, req: &Request<Body>) -> bool {
+        self.calls_only_unauthenticated_methods(req)
+            || self.has_valid_api_key(req)
+            || self.has_valid_jwt(req)
+    }
+
+    fn calls_only_unauthenticated_methods(&self, req: &Request<Body>) -> bool {
+        req.extensions().get::<CalledMethods>().is_some_and(|CalledMethods(methods)| {
+            !methods.is_empty()
+                && methods.iter().all(|method| self.unauthenticated_methods.contains(method))
+        })
+    }
+
+    fn has_valid_api_key(&self, req: &Request<Body>) -> bool {
+        req.headers()
+            .get(API_KEY_HEADER)
+            .and_then(|api_key| api_key.to_str().ok())
+            .is_some_and(|api_key| self.api_keys.contains(api_key))
+    }
+
+    fn has_valid_jwt(&self, req: &Request<Body>) -> bool {
+        let Some(jwt_key) = &self.jwt_key else {
+            return false;
+        };
+        let Some(token) = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        match decode::<serde_json::Value>(token, jwt_key, &self.jwt_validation) {
+            Ok(_) => true,
+            Err(err) => {
+                debug!("Invalid JWT: {err}.");
+                false
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Authentication<S> {
+    inner: S,
+    authenticator: Arc<Authenticator>,
+}
+
+impl<S> Service<Request<Body>> for Authentication<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Response<Body>, S::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        if self.authenticator.is_authenticated(&req) {
+            return Either::Left(self.inner.call(req));
+        }
+        Either::Right(ready(Ok(json_rpc_error_response(
+            StatusCode::UNAUTHORIZED,
+            UNAUTHORIZED_CODE,
+            "Unauthorized.",
+        ))))
+    }
+}
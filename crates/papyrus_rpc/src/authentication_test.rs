@@ -0,0 +1,93 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hyper::header::{HeaderName, AUTHORIZATION};
+use hyper::{Body, Request, Response, StatusCode};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tower::{service_fn, BoxError, Layer, Service, ServiceExt};
+
+use super::{AuthenticationConfig, AuthenticationLayer, API_KEY_HEADER};
+use crate::request_limits::CalledMethods;
+
+const API_KEY: &str = "api_key";
+const JWT_SECRET: &str = "jwt_secret";
+const UNAUTHENTICATED_METHOD: &str = "starknet_chainId";
+const AUTHENTICATED_METHOD: &str = "starknet_blockNumber";
+
+async fn response_status(request: Request<Body>) -> StatusCode {
+    let inner =
+        service_fn(|_: Request<Body>| async { Ok::<_, BoxError>(Response::new(Body::empty())) });
+    let config = AuthenticationConfig {
+        api_keys: vec![API_KEY.to_owned()],
+        jwt_secret: Some(JWT_SECRET.to_owned()),
+        ..Default::default()
+    };
+    let mut service = AuthenticationLayer::new(&config).layer(inner);
+    service.ready().await.unwrap().call(request).await.unwrap().status()
+}
+
+fn request(methods: &[&str]) -> Request<Body> {
+    let mut request = Request::new(Body::empty());
+    request
+        .extensions_mut()
+        .insert(CalledMethods(methods.iter().map(|method| method.to_string()).collect()));
+    request
+}
+
+fn with_header(mut request: Request<Body>, name: HeaderName, value: &str) -> Request<Body> {
+    request.headers_mut().insert(name, value.parse().unwrap());
+    request
+}
+
+// A JWT that expires in the given number of seconds, or has expired if it's negative.
+fn jwt(secret: &str, expires_in_secs: i64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let claims = json!({"exp": now + expires_in_secs});
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+}
+
+#[tokio::test]
+async fn unauthenticated_methods() {
+    let status = response_status(request(&[UNAUTHENTICATED_METHOD, UNAUTHENTICATED_METHOD])).await;
+    assert_eq!(status, StatusCode::OK);
+    let status = response_status(request(&[UNAUTHENTICATED_METHOD, AUTHENTICATED_METHOD])).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    // For example, a request that opens a WebSocket connection.
+    let status = response_status(Request::new(Body::empty())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_key() {
+    let status = response_status(with_header(
+        request(&[AUTHENTICATED_METHOD]),
+        HeaderName::from_static(API_KEY_HEADER),
+        API_KEY,
+    ))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let status = response_status(with_header(
+        request(&[AUTHENTICATED_METHOD]),
+        HeaderName::from_static(API_KEY_HEADER),
+        "other_api_key",
+    ))
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn jwt_bearer() {
+    for (token, expected_status) in [
+        (jwt(JWT_SECRET, 60), StatusCode::OK),
+        (jwt(JWT_SECRET, -120), StatusCode::UNAUTHORIZED),
+        (jwt("other_secret", 60), StatusCode::UNAUTHORIZED),
+    ] {
+        let request = with_header(
+            request(&[AUTHENTICATED_METHOD]),
+            AUTHORIZATION,
+            &format!("Bearer {token}"),
+        );
+        assert_eq!(response_status(request).await, expected_status);
+    }
+}
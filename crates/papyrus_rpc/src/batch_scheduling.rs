@@ -0,0 +1,140 @@
+//! Schedules the calls of the JSON-RPC batch requests, so that a huge batch can't starve the other
+//! requests of the server. The calls of a batch are executed as separate requests, a few at a
+//! time, and only while they hold one of the permits that all the batches share. The permits are
+//! given in the order that they are requested, so the calls of concurrent batches are executed in
+//! turns, and requests that aren't batches don't wait for them at all.
+//!
+//! The batches over the maximal batch size are passed to the server as is, which rejects them. The
+//! batches sent through a WebSocket connection aren't in the body of the HTTP request that opens
+//! the connection, so they are only limited in their size.
+#[cfg(test)]
+#[path = "batch_scheduling_test.rs"]
+mod batch_scheduling_test;
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use futures::{stream, FutureExt, StreamExt, TryStreamExt};
+use hyper::body::Bytes;
+use hyper::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::http::request::Parts;
+use hyper::{Body, Request, Response};
+use serde_json::value::RawValue;
+use tokio::sync::Semaphore;
+use tower::{Layer, Service, ServiceExt};
+
+// The number of calls of a single batch that are executed, or wait for a permit, at once.
+const CONCURRENT_CALLS_PER_BATCH: usize = 4;
+
+/// [`Tower`] layer that executes the calls of the batch requests in turns with the calls of the
+/// other batches.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct BatchSchedulingLayer {
+    max_batch_size: usize,
+    batch_call_permits: Arc<Semaphore>,
+}
+
+impl BatchSchedulingLayer {
+    pub(crate) fn new(max_batch_size: usize, max_concurrent_batch_calls: usize) -> Self {
+        Self {
+            max_batch_size,
+            batch_call_permits: Arc::new(Semaphore::new(max_concurrent_batch_calls)),
+        }
+    }
+}
+
+impl<S> Layer<S> for BatchSchedulingLayer {
+    type Service = BatchScheduling<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BatchScheduling { inner, scheduler: self.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct BatchScheduling<S> {
+    inner: S,
+    scheduler: BatchSchedulingLayer,
+}
+
+impl<S> Service<Request<Body>> for BatchScheduling<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<Body>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The inner service that is ready handles the request, and its clone the next requests.
+        let inner_clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, inner_clone);
+        let scheduler = self.scheduler.clone();
+        async move {
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let calls = match serde_json::from_slice::<Vec<&RawValue>>(&body) {
+                Ok(calls) if !calls.is_empty() && calls.len() <= scheduler.max_batch_size => calls,
+                _ => return inner.call(Request::from_parts(parts, Body::from(body.clone()))).await,
+            };
+            let responses: Vec<Bytes> = stream::iter(calls)
+                .map(|call| {
+                    let call_request = call_request(&parts, call);
+                    let inner = inner.clone();
+                    let batch_call_permits = scheduler.batch_call_permits.clone();
+                    async move {
+                        let _permit = batch_call_permits
+                            .acquire_owned()
+                            .await
+                            .expect("The semaphore shouldn't be closed.");
+                        let response = inner.oneshot(call_request).await?;
+                        Ok::<_, S::Error>(hyper::body::to_bytes(response.into_body()).await?)
+                    }
+                })
+                .buffered(CONCURRENT_CALLS_PER_BATCH)
+                .try_collect()
+                .await?;
+            Ok(batch_response(responses))
+        }
+        .boxed()
+    }
+}
+
+// A request with a single call of the batch.
+fn call_request(batch_parts: &Parts, call: &RawValue) -> Request<Body> {
+    let body = call.get().to_owned();
+    let mut request = Request::new(Body::empty());
+    *request.method_mut() = batch_parts.method.clone();
+    *request.uri_mut() = batch_parts.uri.clone();
+    *request.version_mut() = batch_parts.version;
+    *request.headers_mut() = batch_parts.headers.clone();
+    request.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+    *request.body_mut() = Body::from(body);
+    request
+}
+
+// The responses to the calls of the batch, in the order of the calls. Notifications aren't
+// answered, and a batch of notifications has an empty response.
+fn batch_response(call_responses: Vec<Bytes>) -> Response<Body> {
+    let call_responses: Vec<&[u8]> = call_responses
+        .iter()
+        .filter(|response| !response.is_empty())
+        .map(|response| response.as_ref())
+        .collect();
+    if call_responses.is_empty() {
+        return Response::new(Body::empty());
+    }
+    let body = [b"[".as_slice(), &call_responses.join(b",".as_slice()), b"]"].concat();
+    let mut response = Response::new(Body::from(body));
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::join;
+use hyper::{Body, Request, Response};
+use pretty_assertions::assert_eq;
+use tower::{service_fn, BoxError, Layer, Service, ServiceExt};
+
+use super::BatchSchedulingLayer;
+
+const MAX_BATCH_SIZE: usize = 10;
+
+// A service that answers each request with its body, and counts the requests.
+fn echo_service(
+    max_batch_size: usize,
+    max_concurrent_batch_calls: usize,
+    requests: Arc<AtomicUsize>,
+) -> impl Service<Request<Body>, Response = Response<Body>, Error = BoxError> + Clone {
+    let inner = service_fn(move |request: Request<Body>| {
+        let requests = requests.clone();
+        async move {
+            requests.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, BoxError>(Response::new(request.into_body()))
+        }
+    });
+    BatchSchedulingLayer::new(max_batch_size, max_concurrent_batch_calls).layer(inner)
+}
+
+async fn call(
+    service: impl Service<Request<Body>, Response = Response<Body>, Error = BoxError>,
+    body: &str,
+) -> String {
+    let response = service.oneshot(Request::new(Body::from(body.to_owned()))).await.unwrap();
+    String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn batch_calls_are_executed_separately() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let service = echo_service(MAX_BATCH_SIZE, 1, requests.clone());
+    let batch = r#"[{"id":1},{"id":2},{"id":3}]"#;
+    assert_eq!(call(service, batch).await, batch);
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn other_requests_are_passed_as_is() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let service = echo_service(2, 1, requests.clone());
+    let single_request = r#"{"id":1}"#;
+    assert_eq!(call(service.clone(), single_request).await, single_request);
+    let large_batch = r#"[{"id":1},{"id":2},{"id":3}]"#;
+    assert_eq!(call(service.clone(), large_batch).await, large_batch);
+    assert_eq!(call(service, "[]").await, "[]");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn notifications_are_not_answered() {
+    let inner =
+        service_fn(|_: Request<Body>| async { Ok::<_, BoxError>(Response::new(Body::empty())) });
+    let service = BatchSchedulingLayer::new(MAX_BATCH_SIZE, 1).layer(inner);
+    assert_eq!(call(service, r#"[{"method":"a"},{"method":"b"}]"#).await, "");
+}
+
+#[tokio::test]
+async fn concurrent_batch_calls_are_limited() {
+    const MAX_CONCURRENT_BATCH_CALLS: usize = 2;
+    let running_calls = Arc::new(AtomicUsize::new(0));
+    let max_running_calls = Arc::new(AtomicUsize::new(0));
+    let inner = {
+        let running_calls = running_calls.clone();
+        let max_running_calls = max_running_calls.clone();
+        service_fn(move |request: Request<Body>| {
+            let running_calls = running_calls.clone();
+            let max_running_calls = max_running_calls.clone();
+            async move {
+                let running = running_calls.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running_calls.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                running_calls.fetch_sub(1, Ordering::SeqCst);
+                Ok::<_, BoxError>(Response::new(request.into_body()))
+            }
+        })
+    };
+    let service =
+        BatchSchedulingLayer::new(MAX_BATCH_SIZE, MAX_CONCURRENT_BATCH_CALLS).layer(inner);
+    let batch = format!("[{}]", vec![r#"{"id":1}"#; MAX_BATCH_SIZE].join(","));
+    let (first_response, second_response) =
+        join(call(service.clone(), &batch), call(service, &batch)).await;
+    assert_eq!(first_response, batch);
+    assert_eq!(second_response, batch);
+    assert_eq!(max_running_calls.load(Ordering::SeqCst), MAX_CONCURRENT_BATCH_CALLS);
+}
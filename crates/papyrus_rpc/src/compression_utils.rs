@@ -5,6 +5,10 @@ mod compression_utils_test;
 use papyrus_storage::compression_utils::serialize_and_compress;
 use papyrus_storage::db::serialization::{StorageSerde, StorageSerdeError};
 
+/// The maximal size of a decompressed program of a deprecated class in a request - 64MB. Larger
+/// programs are rejected, so a small compressed program can't make the node allocate unboundedly.
+pub(crate) const MAX_DECOMPRESSED_PROGRAM_SIZE: usize = 1 << 26;
+
 pub fn compress_and_encode(value: serde_json::Value) -> Result<String, StorageSerdeError> {
     Ok(base64::encode(serialize_and_compress(&JsonValue(value))?))
 }
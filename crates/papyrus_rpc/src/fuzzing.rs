@@ -0,0 +1,95 @@
+//! Entry points for fuzzing the JSON-RPC server, used by the targets in the fuzz directory.
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use jsonrpsee::Methods;
+use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::BlockHashAndNumber;
+use papyrus_execution::contract_class_cache::ContractClassCache;
+use papyrus_storage::test_utils::get_test_storage;
+use starknet_client::reader::PendingData;
+use starknet_client::writer::objects::response::{
+    DeclareResponse,
+    DeployAccountResponse,
+    InvokeResponse,
+};
+use starknet_client::writer::objects::transaction::{
+    DeclareTransaction,
+    DeployAccountTransaction,
+    InvokeTransaction,
+};
+use starknet_client::writer::{StarknetWriter, WriterClientError, WriterClientResult};
+use tempfile::TempDir;
+use tokio::sync::RwLock;
+
+use crate::api::get_methods_from_supported_apis;
+use crate::RpcConfig;
+
+const EXECUTION_CONFIG_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test_config.json");
+
+/// Returns the methods of all the supported API versions, with the names the server serves them
+/// by. The methods read from an empty storage, which is deleted when the returned directory is
+/// dropped, and all the transactions they add are rejected.
+pub fn get_methods_for_fuzzing() -> (Methods, TempDir) {
+    let ((storage_reader, _), storage_dir) = get_test_storage();
+    let config = RpcConfig::default();
+    let execution_config = PathBuf::from(EXECUTION_CONFIG_PATH)
+        .try_into()
+        .expect("Failed to load the execution config");
+    let methods = get_methods_from_supported_apis(
+        &config.chain_id,
+        execution_config,
+        ContractClassCache::new(config.execution_class_cache_size_bytes),
+        config.execution_timeout,
+        storage_reader,
+        config.max_events_chunk_size,
+        config.max_events_keys,
+        BlockHashAndNumber::default(),
+        Arc::new(RwLock::new(None)),
+        Arc::new(RwLock::new(PendingData::default())),
+        Arc::new(RwLock::new(PendingClasses::default())),
+        Arc::new(RejectingStarknetWriter),
+    );
+    (methods, storage_dir)
+}
+
+// A writer that rejects all the transactions without sending them.
+struct RejectingStarknetWriter;
+
+impl RejectingStarknetWriter {
+    fn error() -> WriterClientError {
+        WriterClientError::SerdeError(serde::de::Error::custom(
+            "Transactions aren't sent while fuzzing.",
+        ))
+    }
+}
+
+#[async_trait]
+impl StarknetWriter for RejectingStarknetWriter {
+    async fn add_invoke_transaction(
+        &self,
+        _tx: &InvokeTransaction,
+    ) -> WriterClientResult<InvokeResponse> {
+        Err(Self::error())
+    }
+
+    async fn add_declare_transaction(
+        &self,
+        _tx: &DeclareTransaction,
+    ) -> WriterClientResult<DeclareResponse> {
+        Err(Self::error())
+    }
+
+    async fn add_deploy_account_transaction(
+        &self,
+        _tx: &DeployAccountTransaction,
+    ) -> WriterClientResult<DeployAccountResponse> {
+        Err(Self::error())
+    }
+
+    async fn is_alive(&self) -> bool {
+        false
+    }
+}
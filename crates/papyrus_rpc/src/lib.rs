@@ -3,38 +3,65 @@
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
 mod api;
+mod authentication;
+mod batch_scheduling;
 mod compression_utils;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
 mod middleware;
 mod pending;
+mod request_limits;
 mod rpc_metrics;
 #[cfg(test)]
 mod rpc_test;
+mod slow_requests;
 mod syncing_state;
 #[cfg(test)]
 mod test_utils;
+mod tls;
 mod v0_4;
 mod v0_5;
 mod v0_6;
 mod version_config;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 use std::net::SocketAddr;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use jsonrpsee::core::RpcResult;
-use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::server::{BatchRequestConfig, ServerBuilder, ServerHandle};
 use jsonrpsee::types::error::ErrorCode::InternalError;
 use jsonrpsee::types::error::INTERNAL_ERROR_MSG;
 use jsonrpsee::types::ErrorObjectOwned;
 use papyrus_common::pending_classes::PendingClasses;
 use papyrus_common::BlockHashAndNumber;
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_config::converters::{
+    deserialize_milliseconds_to_duration,
+    deserialize_optional_map,
+    deserialize_seconds_to_duration,
+    deserialize_vec,
+    serialize_optional_map,
+    serialize_slice,
+};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_optional_sub_config,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::validators::{validate_ascii, validate_path_exists};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_execution::abort_handle::AbortHandle;
+use papyrus_execution::contract_class_cache::ContractClassCache;
+use papyrus_execution::{ExecutionConfigByBlock, ExecutionError, ExecutionResult};
 use papyrus_storage::base_layer::BaseLayerStorageReader;
 use papyrus_storage::body::events::EventIndex;
+use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::db::TransactionKind;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageReader, StorageScope, StorageTxn};
@@ -45,21 +72,46 @@ use starknet_api::core::ChainId;
 use starknet_client::reader::PendingData;
 use starknet_client::writer::StarknetGatewayClient;
 use starknet_client::RetryConfig;
-use tokio::sync::RwLock;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch, RwLock};
+use tower_http::timeout::TimeoutLayer;
 use tracing::{debug, error, info, instrument};
 use validator::Validate;
 
 use crate::api::get_methods_from_supported_apis;
-use crate::middleware::{deny_requests_with_unsupported_path, proxy_rpc_request};
+pub use crate::authentication::AuthenticationConfig;
+use crate::authentication::AuthenticationLayer;
+use crate::batch_scheduling::BatchSchedulingLayer;
+use crate::middleware::{cors_layer, deny_requests_with_unsupported_path, proxy_rpc_request};
+use crate::request_limits::{
+    deserialize_method_limits,
+    serialize_method_limits,
+    RequestLimitsLayer,
+};
+use crate::slow_requests::SlowRequestsLayer;
 use crate::syncing_state::get_last_synced_block;
+use crate::tls::{create_tls_acceptor, run_tls_server, JsonRpcService};
+pub use crate::tls::{TlsConfig, TlsError};
 pub use crate::v0_4::transaction::{
     InvokeTransaction as InvokeTransactionRPC0_4,
     InvokeTransactionV1 as InvokeTransactionV1RPC0_4,
     TransactionVersion1 as TransactionVersion1RPC0_4,
 };
 pub use crate::v0_4::write_api_result::AddInvokeOkResult as AddInvokeOkResultRPC0_4;
+use crate::v0_6::block_transactions::{
+    BlockTransactionsJsonRpcV0_6Server,
+    BlockTransactionsV0_6Impl,
+};
+use crate::v0_6::capabilities::{CapabilitiesJsonRpcV0_6Server, CapabilitiesV0_6Impl};
+use crate::v0_6::l1_to_l2_messages::{L1ToL2MessagesJsonRpcV0_6Server, L1ToL2MessagesV0_6Impl};
+use crate::v0_6::simulation_sessions::{
+    SimulationSessionsJsonRpcV0_6Server,
+    SimulationSessionsV0_6Impl,
+};
+use crate::v0_6::storage_proof::{StorageProofJsonRpcV0_6Server, StorageProofV0_6Impl};
+use crate::v0_6::subscriptions::{SubscriptionsJsonRpcV0_6Server, SubscriptionsV0_6Impl};
 
-/// Maximum size of a supported transaction body - 10MB.
+/// The default maximum size of a request body - 10MB.
 pub const SERVER_MAX_BODY_SIZE: u32 = 10 * 1024 * 1024;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Validate)]
@@ -67,13 +119,41 @@ pub struct RpcConfig {
     #[validate(custom = "validate_ascii")]
     pub chain_id: ChainId,
     pub server_address: String,
+    /// If set, the server is served over HTTPS and WSS instead of HTTP and WebSocket.
+    #[validate]
+    pub tls: Option<TlsConfig>,
+    /// If set, the requests must be authenticated, except for those that call only the allowed
+    /// unauthenticated methods.
+    pub authentication: Option<AuthenticationConfig>,
+    #[serde(deserialize_with = "deserialize_vec")]
+    pub cors_allowed_origins: Vec<String>,
+    pub max_request_body_size: u32,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub request_timeout: Duration,
+    pub max_in_flight_requests: usize,
+    #[serde(deserialize_with = "deserialize_method_limits")]
+    pub method_concurrency_limits: BTreeMap<String, usize>,
+    pub max_batch_size: u32,
+    pub max_concurrent_batch_calls: usize,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub slow_request_threshold: Duration,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
     pub collect_metrics: bool,
     pub starknet_url: String,
+    #[serde(deserialize_with = "deserialize_optional_map")]
+    pub starknet_gateway_http_headers: Option<HashMap<String, String>>,
+    pub starknet_gateway_proxy_url: Option<String>,
     pub starknet_gateway_retry_config: RetryConfig,
     #[validate(custom = "validate_path_exists")]
     pub execution_config: PathBuf,
+    pub execution_class_cache_size_bytes: usize,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub execution_timeout: Duration,
+    pub enable_simulation_sessions: bool,
+    pub max_simulation_sessions: NonZeroUsize,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub simulation_session_ttl: Duration,
 }
 
 impl Default for RpcConfig {
@@ -81,16 +161,39 @@ impl Default for RpcConfig {
         RpcConfig {
             chain_id: ChainId("SN_MAIN".to_string()),
             server_address: String::from("0.0.0.0:8080"),
+            tls: None,
+            authentication: None,
+            cors_allowed_origins: Vec::new(),
+            max_request_body_size: SERVER_MAX_BODY_SIZE,
+            request_timeout: Duration::from_secs(120),
+            max_in_flight_requests: 1000,
+            method_concurrency_limits: BTreeMap::from([(
+                String::from("starknet_traceBlockTransactions"),
+                10,
+            )]),
+            max_batch_size: 1000,
+            max_concurrent_batch_calls: 100,
+            slow_request_threshold: Duration::from_millis(500),
             max_events_chunk_size: 1000,
             max_events_keys: 100,
             collect_metrics: false,
             starknet_url: String::from("https://alpha-mainnet.starknet.io/"),
+            starknet_gateway_http_headers: None,
+            starknet_gateway_proxy_url: None,
             starknet_gateway_retry_config: RetryConfig {
                 retry_base_millis: 50,
+                backoff_multiplier: 50,
                 retry_max_delay_millis: 1000,
                 max_retries: 5,
             },
             execution_config: PathBuf::from("config/execution/mainnet.json"),
+            // 512 MiB.
+            execution_class_cache_size_bytes: 1 << 29,
+            execution_timeout: Duration::from_secs(60),
+            enable_simulation_sessions: false,
+            max_simulation_sessions: NonZeroUsize::new(100)
+                .expect("The default number of simulation sessions should be positive."),
+            simulation_session_ttl: Duration::from_secs(600),
         }
     }
 }
@@ -110,6 +213,61 @@ impl SerializeConfig for RpcConfig {
                 "IP:PORT of the node`s JSON-RPC server.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "cors_allowed_origins",
+                &serialize_slice(&self.cors_allowed_origins),
+                "Space separated origins of the web pages that may call the JSON-RPC server from \
+                 a browser, for example 'https://app.example.com'. '*' allows any origin.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_request_body_size",
+                &self.max_request_body_size,
+                "Maximum size in bytes of the body of a JSON-RPC request.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "request_timeout",
+                &self.request_timeout.as_secs(),
+                "Maximal time in seconds for answering an HTTP request. Requests that exceed it \
+                 are answered with a timeout status.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_in_flight_requests",
+                &self.max_in_flight_requests,
+                "Maximum number of HTTP requests that the JSON-RPC server handles concurrently. \
+                 Requests above it are rejected with a too many requests status.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "method_concurrency_limits",
+                &serialize_method_limits(&self.method_concurrency_limits),
+                "'method1:limit1 method2:limit2 ...' maximum number of concurrent calls of each \
+                 method over HTTP, for example 'starknet_traceBlockTransactions:10'. Requests \
+                 above it are rejected with a too many requests status.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_batch_size",
+                &self.max_batch_size,
+                "Maximum number of calls in a batch request. Larger batches are rejected.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_concurrent_batch_calls",
+                &self.max_concurrent_batch_calls,
+                "Maximum number of calls of all the batch requests over HTTP that are executed \
+                 concurrently. The calls of concurrent batches are executed in turns.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "slow_request_threshold",
+                &self.slow_request_threshold.as_millis(),
+                "Time in milliseconds above which the answered requests are logged as slow, with \
+                 their parameters.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "max_events_chunk_size",
                 &self.max_events_chunk_size,
@@ -134,10 +292,51 @@ impl SerializeConfig for RpcConfig {
                 "URL for communicating with Starknet in write_api methods.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "starknet_gateway_http_headers",
+                &serialize_optional_map(&self.starknet_gateway_http_headers),
+                "'k1:v1 k2:v2 ...' headers for requests to Starknet gateway in write_api methods.",
+                ParamPrivacyInput::Private,
+            ),
             ser_param(
                 "execution_config",
                 &self.execution_config,
-                "Path to the execution configuration file.",
+                "Path to the execution configuration file, or to a directory with the \
+                 configuration file of each chain, named <chain_id>.json.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "execution_class_cache_size_bytes",
+                &self.execution_class_cache_size_bytes,
+                "Memory budget in bytes for the compiled classes that are cached between \
+                 executions. If 0, classes are not cached.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "execution_timeout",
+                &self.execution_timeout.as_secs(),
+                "Maximal time in seconds for executing the transactions of a single request. \
+                 Executions that exceed it are aborted.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "enable_simulation_sessions",
+                &self.enable_simulation_sessions,
+                "If true, serve the papyrus simulation sessions API, which keeps the state changes \
+                 of simulations for the following simulations in the same session.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_simulation_sessions",
+                &self.max_simulation_sessions,
+                "Maximum number of open simulation sessions. Opening a session when there are \
+                 more closes the least recently used one.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "simulation_session_ttl",
+                &self.simulation_session_ttl.as_secs(),
+                "Time in seconds after which a simulation session that wasn't used is closed.",
                 ParamPrivacyInput::Public,
             ),
         ]);
@@ -153,6 +352,17 @@ impl SerializeConfig for RpcConfig {
             );
         }
         self_params_dump.append(&mut retry_config_dump);
+        self_params_dump.append(&mut ser_optional_param(
+            &self.starknet_gateway_proxy_url,
+            String::new(),
+            "starknet_gateway_proxy_url",
+            "URL of an HTTP, HTTPS or SOCKS5 proxy for the requests to Starknet gateway in \
+             write_api methods, for example socks5://127.0.0.1:1080.",
+            ParamPrivacyInput::Private,
+        ));
+        self_params_dump.append(&mut ser_optional_sub_config(&self.tls, "tls"));
+        self_params_dump
+            .append(&mut ser_optional_sub_config(&self.authentication, "authentication"));
         self_params_dump
     }
 }
@@ -167,6 +377,22 @@ fn internal_server_error_with_msg(err: impl Display) -> ErrorObjectOwned {
     ErrorObjectOwned::owned(InternalError.code(), err.to_string(), None::<()>)
 }
 
+// Runs an execution on the blocking thread pool and aborts it once the given timeout passes or the
+// request is dropped. An aborted execution stops at its next state read, but the request doesn't
+// wait for it and fails with an aborted execution error right away.
+async fn run_execution<T: Send + 'static>(
+    execution_timeout: Duration,
+    execution: impl FnOnce(AbortHandle) -> ExecutionResult<T> + Send + 'static,
+) -> RpcResult<ExecutionResult<T>> {
+    let abort_handle = AbortHandle::with_timeout(execution_timeout);
+    let _abort_on_drop = abort_handle.abort_on_drop();
+    let execution = tokio::task::spawn_blocking(move || execution(abort_handle));
+    match tokio::time::timeout(execution_timeout, execution).await {
+        Ok(execution_result) => execution_result.map_err(internal_server_error),
+        Err(_) => Ok(Err(ExecutionError::ExecutionAborted)),
+    }
+}
+
 fn verify_storage_scope(storage_reader: &StorageReader) -> RpcResult<()> {
     match storage_reader.get_scope() {
         StorageScope::StateOnly => {
@@ -176,11 +402,33 @@ fn verify_storage_scope(storage_reader: &StorageReader) -> RpcResult<()> {
     }
 }
 
-/// Get the latest block that we've downloaded and that we've downloaded its state diff.
+// The JSON-RPC error code of a request for data that was pruned from the storage, from the range of
+// the server errors that JSON-RPC leaves to the implementations.
+const DATA_PRUNED_CODE: i32 = -32098;
+
+// Returns an error if the body of the block was pruned from the storage.
+fn verify_body_not_pruned<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_number: BlockNumber,
+) -> RpcResult<()> {
+    if block_number < txn.get_pruned_body_marker().map_err(internal_server_error)? {
+        return Err(ErrorObjectOwned::owned(
+            DATA_PRUNED_CODE,
+            "Data pruned",
+            Some(format!("The body of block {block_number} was pruned.")),
+        ));
+    }
+    Ok(())
+}
+
+/// Get the latest block that we've downloaded both its body and its state diff. The state diffs are
+/// downloaded in parallel to the bodies, so either of them may be ahead of the other.
 fn get_latest_block_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
 ) -> Result<Option<BlockNumber>, ErrorObjectOwned> {
-    Ok(txn.get_state_marker().map_err(internal_server_error)?.prev())
+    let state_marker = txn.get_state_marker().map_err(internal_server_error)?;
+    let body_marker = txn.get_body_marker().map_err(internal_server_error)?;
+    Ok(state_marker.min(body_marker).prev())
 }
 
 fn get_block_status<Mode: TransactionKind>(
@@ -207,46 +455,113 @@ pub async fn run_server(
     pending_data: Arc<RwLock<PendingData>>,
     pending_classes: Arc<RwLock<PendingClasses>>,
     storage_reader: StorageReader,
+    new_block_sender: broadcast::Sender<BlockNumber>,
     node_version: &'static str,
 ) -> anyhow::Result<(SocketAddr, ServerHandle)> {
     let starting_block = get_last_synced_block(storage_reader.clone())?;
     debug!("Starting JSON-RPC.");
-    let methods = get_methods_from_supported_apis(
+    let class_cache = ContractClassCache::new(config.execution_class_cache_size_bytes);
+    let mut methods = get_methods_from_supported_apis(
         &config.chain_id,
-        config.execution_config.clone().try_into()?,
-        storage_reader,
+        ExecutionConfigByBlock::load(&config.execution_config, &config.chain_id)?,
+        class_cache.clone(),
+        config.execution_timeout,
+        storage_reader.clone(),
         config.max_events_chunk_size,
         config.max_events_keys,
         starting_block,
         shared_highest_block,
-        pending_data,
+        pending_data.clone(),
         pending_classes,
         Arc::new(StarknetGatewayClient::new(
             &config.starknet_url,
+            config.starknet_gateway_http_headers.clone(),
+            config.starknet_gateway_proxy_url.clone(),
             node_version,
             config.starknet_gateway_retry_config,
         )?),
     );
+    methods.merge(L1ToL2MessagesV0_6Impl::new(storage_reader.clone()).into_rpc())?;
+    methods.merge(
+        CapabilitiesV0_6Impl::new(storage_reader.clone(), config.enable_simulation_sessions)
+            .into_rpc(),
+    )?;
+    methods.merge(StorageProofV0_6Impl::new(storage_reader.clone()).into_rpc())?;
+    methods.merge(BlockTransactionsV0_6Impl::new(storage_reader.clone()).into_rpc())?;
+    methods.merge(
+        SubscriptionsV0_6Impl::new(
+            storage_reader.clone(),
+            new_block_sender,
+            pending_data,
+            config.max_events_keys,
+        )
+        .into_rpc(),
+    )?;
+    if config.enable_simulation_sessions {
+        methods.merge(
+            SimulationSessionsV0_6Impl::new(
+                config.chain_id.clone(),
+                ExecutionConfigByBlock::load(&config.execution_config, &config.chain_id)?,
+                class_cache,
+                config.execution_timeout,
+                config.max_simulation_sessions,
+                config.simulation_session_ttl,
+                storage_reader,
+            )
+            .into_rpc(),
+        )?;
+    }
     let addr;
     let handle;
-    let server_builder =
-        ServerBuilder::default().max_request_body_size(SERVER_MAX_BODY_SIZE).set_middleware(
-            tower::ServiceBuilder::new()
-                .filter_async(deny_requests_with_unsupported_path)
-                .filter_async(proxy_rpc_request),
-        );
+    let max_request_body_size = config.max_request_body_size;
+    let middleware = tower::ServiceBuilder::new()
+        .layer(cors_layer(&config.cors_allowed_origins)?)
+        .layer(TimeoutLayer::new(config.request_timeout))
+        .filter_async(deny_requests_with_unsupported_path)
+        .filter_async(move |req| proxy_rpc_request(req, max_request_body_size))
+        .option_layer(config.authentication.as_ref().map(AuthenticationLayer::new))
+        .layer(RequestLimitsLayer::new(
+            config.max_in_flight_requests,
+            &config.method_concurrency_limits,
+        ))
+        .layer(BatchSchedulingLayer::new(
+            config.max_batch_size as usize,
+            config.max_concurrent_batch_calls,
+        ))
+        .layer(SlowRequestsLayer::new(config.slow_request_threshold));
 
-    if config.collect_metrics {
-        let server = server_builder
-            .set_logger(MetricLogger::new(&methods))
-            .build(&config.server_address)
-            .await?;
-        addr = server.local_addr()?;
-        handle = server.start(methods);
+    if let Some(tls_acceptor) = config.tls.as_ref().map(create_tls_acceptor).transpose()? {
+        let listener = TcpListener::bind(&config.server_address).await?;
+        addr = listener.local_addr()?;
+        let (stop_sender, stop) = watch::channel(());
+        handle = ServerHandle::new(stop_sender);
+        if config.collect_metrics {
+            let logger = MetricLogger::new(&methods);
+            let service =
+                JsonRpcService::new(methods, logger, max_request_body_size, config.max_batch_size);
+            tokio::spawn(run_tls_server(listener, tls_acceptor, middleware, service, stop));
+        } else {
+            let service =
+                JsonRpcService::new(methods, (), max_request_body_size, config.max_batch_size);
+            tokio::spawn(run_tls_server(listener, tls_acceptor, middleware, service, stop));
+        }
     } else {
-        let server = server_builder.build(&config.server_address).await?;
-        addr = server.local_addr()?;
-        handle = server.start(methods);
+        let server_builder = ServerBuilder::default()
+            .max_request_body_size(max_request_body_size)
+            .set_batch_request_config(BatchRequestConfig::Limit(config.max_batch_size))
+            .set_middleware(middleware);
+        if config.collect_metrics {
+            let server = server_builder
+                .set_logger(MetricLogger::new(&methods))
+                .build(&config.server_address)
+                .await?;
+            addr = server.local_addr()?;
+            handle = server.start(methods);
+        } else {
+            let server = server_builder.build(&config.server_address).await?;
+            addr = server.local_addr()?;
+            handle = server.start(methods);
+        }
     }
     info!(local_address = %addr, "JSON-RPC is running.");
     Ok((addr, handle))
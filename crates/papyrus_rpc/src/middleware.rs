@@ -1,11 +1,22 @@
-use hyper::{Body, Request};
+use hyper::header::{
+    HeaderName,
+    HeaderValue,
+    InvalidHeaderValue,
+    AUTHORIZATION,
+    CONTENT_TYPE,
+    UPGRADE,
+};
+use hyper::{Body, Method, Request, Response, StatusCode};
 use jsonrpsee::core::http_helpers::read_body;
 use regex::Regex;
+use serde_json::json;
 use tower::BoxError;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tracing::{debug, instrument};
 
+use crate::authentication::API_KEY_HEADER;
+use crate::request_limits::CalledMethods;
 use crate::version_config::{VersionState, VERSION_CONFIG, VERSION_PATTERN};
-use crate::SERVER_MAX_BODY_SIZE;
 
 /// [`Tower`] middleware intended to proxy method requests to the right version of the API.
 /// The middleware reads the JsonRPC request body and request path
@@ -14,26 +25,33 @@ use crate::SERVER_MAX_BODY_SIZE;
 ///
 /// # Arguments
 /// * req - [`hyper::Request`] object passed by the server.
+/// * max_request_body_size - the maximum size in bytes of the request body.
 ///
 /// [`Tower`]: https://crates.io/crates/tower
-pub(crate) async fn proxy_rpc_request(req: Request<Body>) -> Result<Request<Body>, BoxError> {
-    debug!("proxy_rpc_request -> Request received: {:?}", req);
+pub(crate) async fn proxy_rpc_request(
+    req: Request<Body>,
+    max_request_body_size: u32,
+) -> Result<Request<Body>, BoxError> {
+    // The headers may hold the credentials of the client, so only the method and path are logged.
+    debug!("proxy_rpc_request -> Request received: {} {}", req.method(), req.uri().path());
+    // The messages of a WebSocket connection aren't in the body of the request that opens it, so
+    // they aren't routed by the path, and should name the versioned methods.
+    if is_websocket_upgrade(&req) {
+        return Ok(req);
+    }
     let uri = &req.uri().clone();
     let prefix = get_version_as_prefix(uri.path())?;
-    let (parts, body) = req.into_parts();
+    let (mut parts, body) = req.into_parts();
     let (body_bytes, is_single) =
-        read_body(&parts.headers, body, SERVER_MAX_BODY_SIZE).await.map_err(BoxError::from)?;
-    let new_body = match is_single {
-        true => {
-            let body = serde_json::from_slice::<jsonrpsee::types::Request<'_>>(&body_bytes)?;
-            add_version_to_method_name_in_body(vec![body], prefix, is_single)
-        }
-        false => {
-            let vec_body =
-                serde_json::from_slice::<Vec<jsonrpsee::types::Request<'_>>>(&body_bytes)?;
-            add_version_to_method_name_in_body(vec_body, prefix, is_single)
-        }
-    }?;
+        read_body(&parts.headers, body, max_request_body_size).await.map_err(BoxError::from)?;
+    let vec_body = match is_single {
+        true => vec![serde_json::from_slice::<jsonrpsee::types::Request<'_>>(&body_bytes)?],
+        false => serde_json::from_slice::<Vec<jsonrpsee::types::Request<'_>>>(&body_bytes)?,
+    };
+    parts
+        .extensions
+        .insert(CalledMethods(vec_body.iter().map(|body| body.method.to_string()).collect()));
+    let new_body = add_version_to_method_name_in_body(vec_body, prefix, is_single)?;
     Ok(Request::from_parts(parts, new_body.into()))
 }
 
@@ -47,7 +65,11 @@ pub(crate) async fn proxy_rpc_request(req: Request<Body>) -> Result<Request<Body
 pub(crate) async fn deny_requests_with_unsupported_path(
     req: Request<Body>,
 ) -> Result<Request<Body>, BoxError> {
-    debug!("deny_requests_with_unsupported_path -> Request received: {:?}", req);
+    debug!(
+        "deny_requests_with_unsupported_path -> Request received: {} {}",
+        req.method(),
+        req.uri().path()
+    );
     let uri = req.uri();
     match is_supported_path(uri.path()) {
         true => Ok(req),
@@ -55,6 +77,48 @@ pub(crate) async fn deny_requests_with_unsupported_path(
     }
 }
 
+/// [`Tower`] layer that allows browsers to send JSON-RPC requests from the given origins, or from
+/// any origin if one of them is "*". Without allowed origins, browsers can't call the server from
+/// other origins.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+pub(crate) fn cors_layer(allowed_origins: &[String]) -> Result<CorsLayer, InvalidHeaderValue> {
+    let allow_origin = if allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            allowed_origins
+                .iter()
+                .map(|origin| HeaderValue::from_str(origin))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+    Ok(CorsLayer::new().allow_origin(allow_origin).allow_methods([Method::POST]).allow_headers([
+        CONTENT_TYPE,
+        AUTHORIZATION,
+        HeaderName::from_static(API_KEY_HEADER),
+    ]))
+}
+
+/// A response with the given status to a request that the middleware rejects, with a JSON-RPC
+/// error in its body so that JSON-RPC clients can report it.
+pub(crate) fn json_rpc_error_response(
+    status: StatusCode,
+    code: i32,
+    message: &str,
+) -> Response<Body> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "error": {"code": code, "message": message},
+        "id": null,
+    });
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("The response should be valid.")
+}
+
 fn add_version_to_method_name_in_body(
     mut vec_body: Vec<jsonrpsee::types::Request<'_>>,
     prefix: &str,
@@ -116,6 +180,12 @@ fn get_version_as_prefix(path: &str) -> Result<&str, BoxError> {
     Ok(version_id.name)
 }
 
+pub(crate) fn is_websocket_upgrade(req: &Request<Body>) -> bool {
+    req.headers()
+        .get(UPGRADE)
+        .is_some_and(|upgrade| upgrade.as_bytes().eq_ignore_ascii_case(b"websocket"))
+}
+
 fn is_supported_path(path: &str) -> bool {
     let re = Regex::new((r"^\/rpc\/".to_string() + VERSION_PATTERN + "$").as_str())
         .expect("should be a valid regex");
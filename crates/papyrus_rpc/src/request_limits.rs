@@ -0,0 +1,196 @@
+//! Limits the JSON-RPC requests that the server handles concurrently: all the requests together,
+//! and the calls of specific methods, for example the expensive trace methods. Requests above the
+//! limits are rejected with status code 429 (too many requests) instead of waiting, so that their
+//! clients can retry them later or against another node. A batch request holds a call permit of a
+//! method for each of its calls of the method, so a batch with more calls of a method than its
+//! limit is rejected as invalid, since it can never be handled.
+//!
+//! The server doesn't pass the address of the client to its middleware, so the requests aren't
+//! limited per client. The methods called through a WebSocket connection aren't limited either,
+//! since they aren't in the body of the HTTP request that opens the connection.
+#[cfg(test)]
+#[path = "request_limits_test.rs"]
+mod request_limits_test;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::{ready, BoxFuture};
+use futures::FutureExt;
+use hyper::{Body, Request, Response, StatusCode};
+use papyrus_config::converters::deserialize_optional_map;
+use serde::de::Error as DeserializationError;
+use serde::Deserializer;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Layer, Service};
+use tracing::debug;
+
+use crate::middleware::json_rpc_error_response;
+
+// The JSON-RPC error code of a request that exceeds a limit, as defined in EIP-1474.
+const TOO_MANY_REQUESTS_CODE: i32 = -32005;
+// The JSON-RPC error code of an invalid request, as defined in the JSON-RPC specification.
+const INVALID_REQUEST_CODE: i32 = -32600;
+
+/// The methods called by a request, without their version. Recorded by the middleware that routes
+/// the request to its version.
+#[derive(Clone, Debug)]
+pub(crate) struct CalledMethods(pub(crate) Vec<String>);
+
+/// [`Tower`] layer that rejects the requests above the limits.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct RequestLimitsLayer {
+    in_flight_requests: Arc<Semaphore>,
+    // The concurrency limit of each limited method, and the permits of its calls.
+    method_calls: Arc<HashMap<String, (usize, Arc<Semaphore>)>>,
+}
+
+// The reason a request is rejected.
+enum Rejection {
+    TooManyRequests,
+    BatchExceedsMethodLimit { method: String, calls: usize, limit: usize },
+}
+
+impl RequestLimitsLayer {
+    pub(crate) fn new(
+        max_in_flight_requests: usize,
+        method_concurrency_limits: &BTreeMap<String, usize>,
+    ) -> Self {
+        Self {
+            in_flight_requests: Arc::new(Semaphore::new(max_in_flight_requests)),
+            method_calls: Arc::new(
+                method_concurrency_limits
+                    .iter()
+                    .map(|(method, limit)| {
+                        (method.clone(), (*limit, Arc::new(Semaphore::new(*limit))))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    // Returns the reason the request is rejected if it exceeds one of the limits. The request is
+    // handled while the permits are held.
+    fn acquire_permits(&self, req: &Request<Body>) -> Result<Vec<OwnedSemaphorePermit>, Rejection> {
+        let mut calls_per_method = BTreeMap::<&str, usize>::new();
+        if let Some(CalledMethods(methods)) = req.extensions().get::<CalledMethods>() {
+            for method in methods {
+                *calls_per_method.entry(method.as_str()).or_default() += 1;
+            }
+        }
+        let Ok(in_flight_permit) = self.in_flight_requests.clone().try_acquire_owned() else {
+            return Err(Rejection::TooManyRequests);
+        };
+        let mut permits = vec![in_flight_permit];
+        for (method, calls) in calls_per_method {
+            let Some((limit, method_calls)) = self.method_calls.get(method) else {
+                continue;
+            };
+            if calls > *limit {
+                return Err(Rejection::BatchExceedsMethodLimit {
+                    method: method.to_owned(),
+                    calls,
+                    limit: *limit,
+                });
+            }
+            let calls = u32::try_from(calls).expect("The calls should be within the limit.");
+            let Ok(permit) = method_calls.clone().try_acquire_many_owned(calls) else {
+                debug!("Too many concurrent calls of {method}.");
+                return Err(Rejection::TooManyRequests);
+            };
+            permits.push(permit);
+        }
+        Ok(permits)
+    }
+}
+
+impl<S> Layer<S> for RequestLimitsLayer {
+    type Service = RequestLimits<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestLimits { inner, limits: self.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RequestLimits<S> {
+    inner: S,
+    limits: RequestLimitsLayer,
+}
+
+impl<S> Service<Request<Body>> for RequestLimits<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Future: Send + 'static,
+    S::Error: 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<Body>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let permits = match self.limits.acquire_permits(&req) {
+            Ok(permits) => permits,
+            Err(Rejection::TooManyRequests) => {
+                let response = json_rpc_error_response(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    TOO_MANY_REQUESTS_CODE,
+                    "Too many requests.",
+                );
+                return ready(Ok(response)).boxed();
+            }
+            Err(Rejection::BatchExceedsMethodLimit { method, calls, limit }) => {
+                let response = json_rpc_error_response(
+                    StatusCode::BAD_REQUEST,
+                    INVALID_REQUEST_CODE,
+                    &format!(
+                        "The batch exceeds the method limit: it calls {method} {calls} times, but \
+                         at most {limit} calls of it are handled concurrently."
+                    ),
+                );
+                return ready(Ok(response)).boxed();
+            }
+        };
+        let response = self.inner.call(req);
+        async move {
+            let response = response.await;
+            drop(permits);
+            response
+        }
+        .boxed()
+    }
+}
+
+/// Serializes the concurrency limits of the methods to a "method1:limit1 method2:limit2" string.
+pub(crate) fn serialize_method_limits(method_limits: &BTreeMap<String, usize>) -> String {
+    method_limits
+        .iter()
+        .map(|(method, limit)| format!("{method}:{limit}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Deserializes the concurrency limits of the methods from a "method1:limit1 method2:limit2"
+/// string.
+pub(crate) fn deserialize_method_limits<'de, D>(de: D) -> Result<BTreeMap<String, usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_method_limits = deserialize_optional_map(de)?.unwrap_or_default();
+    raw_method_limits
+        .into_iter()
+        .map(|(method, raw_limit)| {
+            let limit = raw_limit.parse().map_err(|err| {
+                D::Error::custom(format!("limit \"{raw_limit}\" of {method} is not valid: {err}"))
+            })?;
+            Ok((method, limit))
+        })
+        .collect()
+}
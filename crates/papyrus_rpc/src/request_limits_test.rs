@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use hyper::{Body, Request, Response, StatusCode};
+use pretty_assertions::assert_eq;
+use tower::{service_fn, BoxError, Layer, Service, ServiceExt};
+
+use super::{CalledMethods, RequestLimitsLayer};
+
+const LIMITED_METHOD: &str = "starknet_traceBlockTransactions";
+const OTHER_METHOD: &str = "starknet_blockNumber";
+
+fn limited_service(
+    max_in_flight_requests: usize,
+) -> impl Service<Request<Body>, Response = Response<Body>, Error = BoxError> {
+    let inner =
+        service_fn(|_: Request<Body>| async { Ok::<_, BoxError>(Response::new(Body::empty())) });
+    let method_limits = BTreeMap::from([(LIMITED_METHOD.to_owned(), 1)]);
+    RequestLimitsLayer::new(max_in_flight_requests, &method_limits).layer(inner)
+}
+
+fn request(methods: &[&str]) -> Request<Body> {
+    let mut request = Request::new(Body::empty());
+    request
+        .extensions_mut()
+        .insert(CalledMethods(methods.iter().map(|method| method.to_string()).collect()));
+    request
+}
+
+#[tokio::test]
+async fn in_flight_requests_are_limited() {
+    let mut service = limited_service(1);
+    // The permits of a request are taken when it's called, and released once it's answered.
+    let in_flight_request = service.ready().await.unwrap().call(request(&[OTHER_METHOD]));
+    let response = service.ready().await.unwrap().call(request(&[OTHER_METHOD])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    assert_eq!(in_flight_request.await.unwrap().status(), StatusCode::OK);
+    let response = service.ready().await.unwrap().call(request(&[OTHER_METHOD])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn method_calls_are_limited() {
+    let mut service = limited_service(10);
+    // A batch that calls a method more times than its limit can never be handled.
+    let response = service
+        .ready()
+        .await
+        .unwrap()
+        .call(request(&[LIMITED_METHOD, LIMITED_METHOD]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("The batch exceeds the method limit"));
+    let response = service
+        .ready()
+        .await
+        .unwrap()
+        .call(request(&[LIMITED_METHOD, OTHER_METHOD, OTHER_METHOD]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let in_flight_request = service.ready().await.unwrap().call(request(&[LIMITED_METHOD]));
+    let response = service.ready().await.unwrap().call(request(&[LIMITED_METHOD])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let response = service.ready().await.unwrap().call(request(&[OTHER_METHOD])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(in_flight_request.await.unwrap().status(), StatusCode::OK);
+}
@@ -13,11 +13,14 @@ use metrics::{histogram, increment_counter, register_counter, register_histogram
 // Name of the metrics.
 const INCOMING_REQUEST: &str = "rpc_incoming_requests";
 const FAILED_REQUESTS: &str = "rpc_failed_requests";
+// The failed requests per JSON-RPC error code.
+const REQUEST_ERRORS: &str = "rpc_request_errors";
 const REQUEST_LATENCY: &str = "rpc_request_latency_seconds";
 
 // Labels for the metrics.
 const METHOD_LABEL: &str = "method";
 const VERSION_LABEL: &str = "version";
+const ERROR_CODE_LABEL: &str = "code";
 const ILLEGAL_METHOD: &str = "illegal_method";
 
 // Register the metrics and returns a set of the method names.
@@ -60,8 +63,9 @@ impl Logger for MetricLogger {
         // To prevent creating metrics for illegal methods.
         if self.methods_set.contains(method_name) {
             let (method, version) = get_method_and_version(method_name);
-            if let jsonrpsee::helpers::MethodResponseResult::Failed(_) = success_or_error {
+            if let jsonrpsee::helpers::MethodResponseResult::Failed(code) = success_or_error {
                 increment_counter!(FAILED_REQUESTS, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
+                increment_counter!(REQUEST_ERRORS, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone(), ERROR_CODE_LABEL=> code.to_string());
             }
             increment_counter!(INCOMING_REQUEST, METHOD_LABEL=> method.clone(), VERSION_LABEL=> version.clone());
             let latency = started_at.elapsed().as_secs_f64();
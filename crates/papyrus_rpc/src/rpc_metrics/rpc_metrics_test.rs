@@ -17,15 +17,18 @@ use test_utils::{prometheus_is_contained, send_request};
 use crate::rpc_metrics::{
     get_method_and_version,
     MetricLogger,
+    ERROR_CODE_LABEL,
     FAILED_REQUESTS,
     ILLEGAL_METHOD,
     INCOMING_REQUEST,
     METHOD_LABEL,
+    REQUEST_ERRORS,
     VERSION_LABEL,
 };
 use crate::run_server;
 use crate::test_utils::{
     get_test_highest_block,
+    get_test_new_block_sender,
     get_test_pending_classes,
     get_test_pending_data,
     get_test_rpc_config,
@@ -112,6 +115,11 @@ fn logger_test() {
         prometheus_is_contained(handle.render(), FAILED_REQUESTS, &labels),
         Some(Counter(1f64))
     );
+    let error_code_labels = [labels.clone(), vec![(ERROR_CODE_LABEL, "0")]].concat();
+    assert_eq!(
+        prometheus_is_contained(handle.render(), REQUEST_ERRORS, &error_code_labels),
+        Some(Counter(1f64))
+    );
     assert_eq!(
         prometheus_is_contained(handle.render(), FAILED_REQUESTS, &illegal_method_label),
         Some(Counter(0f64))
@@ -164,6 +172,7 @@ async fn server_metrics() {
         get_test_pending_data(),
         get_test_pending_classes(),
         storage_reader,
+        get_test_new_block_sender(),
         "NODE VERSION",
     )
     .await
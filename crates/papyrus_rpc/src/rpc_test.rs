@@ -4,29 +4,42 @@ use std::{panic, vec};
 use assert_matches::assert_matches;
 use futures_util::future::join_all;
 use hyper::{header, Body, Request};
-use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::client::{ClientT, Subscription, SubscriptionClientT};
 use jsonrpsee::core::http_helpers::read_body;
 use jsonrpsee::core::{Error, RpcResult};
 use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::rpc_params;
 use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::ws_client::WsClientBuilder;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
-use papyrus_storage::header::HeaderStorageWriter;
-use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::{HeaderStorageWriter, StarknetVersion};
+use papyrus_storage::open_storage;
+use papyrus_storage::test_utils::{get_test_config, get_test_storage};
 use pretty_assertions::assert_eq;
 use rand::seq::SliceRandom;
-use starknet_api::block::{BlockHash, BlockHeader, BlockNumber, BlockStatus};
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber, BlockStatus};
 use test_utils::get_rng;
 use tower::BoxError;
 
 use crate::middleware::proxy_rpc_request;
 use crate::test_utils::{
     get_test_highest_block,
+    get_test_new_block_sender,
     get_test_pending_classes,
     get_test_pending_data,
     get_test_rpc_config,
 };
+use crate::v0_6::block::BlockHeader as BlockHeaderV0_6;
 use crate::version_config::VERSION_CONFIG;
-use crate::{get_block_status, run_server, SERVER_MAX_BODY_SIZE};
+use crate::{
+    get_block_status,
+    run_server,
+    verify_body_not_pruned,
+    RpcConfig,
+    DATA_PRUNED_CODE,
+    SERVER_MAX_BODY_SIZE,
+};
 
 #[tokio::test]
 async fn run_server_no_blocks() {
@@ -41,6 +54,7 @@ async fn run_server_no_blocks() {
         pending_data,
         pending_classes,
         storage_reader,
+        get_test_new_block_sender(),
         "NODE VERSION",
     )
     .await
@@ -59,6 +73,83 @@ async fn run_server_no_blocks() {
     };
 }
 
+#[tokio::test]
+async fn subscribe_over_websocket() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let new_block_sender = get_test_new_block_sender();
+    let (addr, _handle) = run_server(
+        &get_test_rpc_config(),
+        get_test_highest_block(),
+        get_test_pending_data(),
+        get_test_pending_classes(),
+        storage_reader,
+        new_block_sender.clone(),
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+    let client = WsClientBuilder::default().build(format!("ws://{addr:?}/rpc/v0_6")).await.unwrap();
+    let mut subscription: Subscription<BlockHeaderV0_6> = client
+        .subscribe(
+            "starknet_V0_6_subscribeNewHeads",
+            rpc_params![],
+            "starknet_V0_6_unsubscribeNewHeads",
+        )
+        .await
+        .unwrap();
+
+    let header = BlockHeader::default();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_number, &header)
+        .unwrap()
+        .update_starknet_version(&header.block_number, &StarknetVersion::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    new_block_sender.send(header.block_number).unwrap();
+
+    let notification = subscription.next().await.unwrap().unwrap();
+    assert_eq!(notification, BlockHeaderV0_6::from((header, StarknetVersion::default())));
+}
+
+#[tokio::test]
+async fn cors_allowed_origins() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let allowed_origin = "https://app.example.com";
+    let gateway_config = RpcConfig {
+        cors_allowed_origins: vec![allowed_origin.to_owned()],
+        ..get_test_rpc_config()
+    };
+    let (addr, _handle) = run_server(
+        &gateway_config,
+        get_test_highest_block(),
+        get_test_pending_data(),
+        get_test_pending_classes(),
+        storage_reader,
+        get_test_new_block_sender(),
+        "NODE VERSION",
+    )
+    .await
+    .unwrap();
+    let preflight_request = |origin: &'static str| {
+        reqwest::Client::new()
+            .request(reqwest::Method::OPTIONS, format!("http://{addr:?}/rpc/v0_6"))
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .send()
+    };
+
+    let response = preflight_request(allowed_origin).await.unwrap();
+    assert_eq!(
+        response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+        allowed_origin
+    );
+    let response = preflight_request("https://other.example.com").await.unwrap();
+    assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+}
+
 /// Given an HTTP request, using the "read_body" function from jsonrpsee library,
 /// parse the body, make sure it's a formatted JSON and within the MAX_BODY_SIZE length.
 async fn get_json_rpc_body(request: Request<Body>) -> Vec<u8> {
@@ -79,7 +170,7 @@ async fn call_proxy_request_get_method_in_out(
         .header(header::CONTENT_TYPE, "application/json")
         .body(Body::from(request_body.unwrap()))
         .unwrap();
-    let res = proxy_rpc_request(req_no_version).await?;
+    let res = proxy_rpc_request(req_no_version, SERVER_MAX_BODY_SIZE).await?;
     let body_bytes = get_json_rpc_body(res).await;
     digest_body_and_assert(is_batch_request, body_bytes, params, method_name)
 }
@@ -138,6 +229,18 @@ fn get_request_body(
 
 // TODO: nevo - add middleware negative cases tests
 
+#[tokio::test]
+async fn proxy_rejects_large_request_body() {
+    let params = serde_json::from_str(r#"[{"myParam": "myValue"}]"#).unwrap();
+    let request_body = get_request_body(false, params, "myMethod").unwrap();
+    let max_request_body_size = u32::try_from(request_body.len()).unwrap() - 1;
+    let request = Request::post("http://localhost:8080/rpc/v0_6")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(request_body))
+        .unwrap();
+    assert!(proxy_rpc_request(request, max_request_body_size).await.is_err());
+}
+
 #[tokio::test]
 async fn test_version_middleware() {
     let base_uri = "http://localhost:8080/rpc/";
@@ -224,6 +327,29 @@ fn get_block_status_test() {
     assert_eq!(get_block_status(&txn, BlockNumber(2)).unwrap(), BlockStatus::AcceptedOnL2);
 }
 
+#[test]
+fn pruned_body_is_reported() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.body_retention_blocks = Some(1);
+    let (reader, mut writer) = open_storage(config).unwrap();
+    for block_number in 0..2 {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(BlockNumber(block_number), &BlockHeader::default())
+            .unwrap()
+            .append_body(BlockNumber(block_number), BlockBody::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let err = verify_body_not_pruned(&txn, BlockNumber(0)).unwrap_err();
+    assert_eq!(err.code(), DATA_PRUNED_CODE);
+    verify_body_not_pruned(&txn, BlockNumber(1)).unwrap();
+}
+
 #[test]
 fn serialization_precision() {
     let input =
@@ -0,0 +1,93 @@
+//! Logs the JSON-RPC requests that take longer than a threshold to answer, with their methods and
+//! parameters, so that operators can find the calls that load their node. The calls of a batch are
+//! logged separately, since they are executed as separate requests. The calls through a WebSocket
+//! connection aren't logged.
+#[cfg(test)]
+#[path = "slow_requests_test.rs"]
+mod slow_requests_test;
+
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use hyper::{Body, Request, Response};
+use tower::{Layer, Service};
+use tracing::warn;
+
+// The logged requests are truncated to this number of characters.
+const MAX_LOGGED_REQUEST_LENGTH: usize = 1000;
+
+/// [`Tower`] layer that logs the requests that take longer than the threshold.
+///
+/// [`Tower`]: https://crates.io/crates/tower
+#[derive(Clone)]
+pub(crate) struct SlowRequestsLayer {
+    threshold: Duration,
+}
+
+impl SlowRequestsLayer {
+    pub(crate) fn new(threshold: Duration) -> Self {
+        Self { threshold }
+    }
+}
+
+impl<S> Layer<S> for SlowRequestsLayer {
+    type Service = SlowRequests<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SlowRequests { inner, threshold: self.threshold }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SlowRequests<S> {
+    inner: S,
+    threshold: Duration,
+}
+
+impl<S> Service<Request<Body>> for SlowRequests<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<Body>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The inner service that is ready handles the request, and its clone the next requests.
+        let inner_clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, inner_clone);
+        let threshold = self.threshold;
+        async move {
+            let start_time = Instant::now();
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let response = inner.call(Request::from_parts(parts, Body::from(body.clone()))).await;
+            let duration = start_time.elapsed();
+            if duration > threshold {
+                warn!(
+                    ?duration,
+                    request = %truncated_request(&body),
+                    "Slow JSON-RPC request."
+                );
+            }
+            response
+        }
+        .boxed()
+    }
+}
+
+fn truncated_request(body: &[u8]) -> String {
+    let request = String::from_utf8_lossy(body);
+    match request.char_indices().nth(MAX_LOGGED_REQUEST_LENGTH) {
+        Some((end, _)) => format!("{}...", &request[..end]),
+        None => request.into_owned(),
+    }
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use hyper::{Body, Request, Response};
+use pretty_assertions::assert_eq;
+use tower::{service_fn, BoxError, Layer, ServiceExt};
+
+use super::{truncated_request, SlowRequestsLayer, MAX_LOGGED_REQUEST_LENGTH};
+
+#[tokio::test]
+async fn requests_are_passed_as_is() {
+    let inner = service_fn(|request: Request<Body>| async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok::<_, BoxError>(Response::new(request.into_body()))
+    });
+    let service = SlowRequestsLayer::new(Duration::ZERO).layer(inner);
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"starknet_blockNumber"}"#;
+    let response = service.oneshot(Request::new(Body::from(body))).await.unwrap();
+    assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), body);
+}
+
+#[test]
+fn long_requests_are_truncated() {
+    let short_request = "a".repeat(MAX_LOGGED_REQUEST_LENGTH);
+    assert_eq!(truncated_request(short_request.as_bytes()), short_request);
+    let long_request = "a".repeat(MAX_LOGGED_REQUEST_LENGTH + 1);
+    assert_eq!(truncated_request(long_request.as_bytes()), format!("{short_request}..."));
+}
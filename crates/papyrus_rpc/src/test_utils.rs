@@ -8,20 +8,27 @@ use jsonrpsee::types::ErrorObjectOwned;
 use jsonschema::JSONSchema;
 use papyrus_common::pending_classes::PendingClasses;
 use papyrus_common::BlockHashAndNumber;
+use papyrus_execution::contract_class_cache::ContractClassCache;
 use papyrus_storage::test_utils::get_test_storage_by_scope;
 use papyrus_storage::{StorageScope, StorageWriter};
 use pretty_assertions::assert_eq;
+use rand_chacha::ChaCha8Rng;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use starknet_api::block::BlockNumber;
 use starknet_api::core::ChainId;
+use starknet_api::transaction::Transaction as StarknetApiTransaction;
+use starknet_client::reader::objects::transaction::Transaction as ClientTransaction;
 use starknet_client::reader::PendingData;
 use starknet_client::writer::MockStarknetWriter;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
-use tokio::sync::RwLock;
+use test_utils::GetTestInstance;
+use tokio::sync::{broadcast, RwLock};
 
 use crate::api::JsonRpcServerImpl;
+use crate::v0_6::transaction::TransactionWithHash;
 use crate::version_config::{VersionId, VERSION_PATTERN};
 use crate::RpcConfig;
 
@@ -52,6 +59,34 @@ pub(crate) fn get_test_pending_classes() -> Arc<RwLock<PendingClasses>> {
     Arc::new(RwLock::new(PendingClasses::default()))
 }
 
+pub(crate) fn get_test_new_block_sender() -> broadcast::Sender<BlockNumber> {
+    broadcast::channel(1).0
+}
+
+// Returns a random client transaction and the same transaction in the V0_6 format.
+pub(crate) fn generate_client_transaction_and_rpc_transaction(
+    rng: &mut ChaCha8Rng,
+) -> (ClientTransaction, TransactionWithHash) {
+    // TODO(shahak): Remove retry once v3 transactions are supported and the impl of TryInto will
+    // become impl of Into.
+    loop {
+        let client_transaction = ClientTransaction::get_test_instance(rng);
+        let Ok(starknet_api_transaction): Result<StarknetApiTransaction, _> =
+            client_transaction.clone().try_into()
+        else {
+            continue;
+        };
+        let Ok(rpc_transaction) = starknet_api_transaction.try_into() else {
+            continue;
+        };
+        let transaction_hash = client_transaction.transaction_hash();
+        break (
+            client_transaction,
+            TransactionWithHash { transaction: rpc_transaction, transaction_hash },
+        );
+    }
+}
+
 pub(crate) fn get_test_rpc_server_and_storage_writer<T: JsonRpcServerImpl>()
 -> (RpcModule<T>, StorageWriter) {
     get_test_rpc_server_and_storage_writer_from_params(None, None, None, None, None)
@@ -64,6 +99,25 @@ pub(crate) fn get_test_rpc_server_and_storage_writer_from_params<T: JsonRpcServe
     pending_classes: Option<Arc<RwLock<PendingClasses>>>,
     storage_scope: Option<StorageScope>,
 ) -> (RpcModule<T>, StorageWriter) {
+    let (server, storage_writer) = get_test_rpc_server_impl_and_storage_writer_from_params::<T>(
+        mock_client,
+        shared_highest_block,
+        pending_data,
+        pending_classes,
+        storage_scope,
+    );
+    (server.into_rpc_module(), storage_writer)
+}
+
+// Returns the server itself rather than its rpc module, for testing the extension APIs that it
+// implements.
+pub(crate) fn get_test_rpc_server_impl_and_storage_writer_from_params<T: JsonRpcServerImpl>(
+    mock_client: Option<MockStarknetWriter>,
+    shared_highest_block: Option<Arc<RwLock<Option<BlockHashAndNumber>>>>,
+    pending_data: Option<Arc<RwLock<PendingData>>>,
+    pending_classes: Option<Arc<RwLock<PendingClasses>>>,
+    storage_scope: Option<StorageScope>,
+) -> (T, StorageWriter) {
     let mock_client = mock_client.unwrap_or_default();
     let shared_highest_block = shared_highest_block.unwrap_or(get_test_highest_block());
     let pending_data = pending_data.unwrap_or(get_test_pending_data());
@@ -77,6 +131,8 @@ pub(crate) fn get_test_rpc_server_and_storage_writer_from_params<T: JsonRpcServe
         T::new(
             config.chain_id,
             config.execution_config.try_into().expect("failed to load execution config"),
+            ContractClassCache::new(config.execution_class_cache_size_bytes),
+            config.execution_timeout,
             storage_reader,
             config.max_events_chunk_size,
             config.max_events_keys,
@@ -85,8 +141,7 @@ pub(crate) fn get_test_rpc_server_and_storage_writer_from_params<T: JsonRpcServe
             pending_data,
             pending_classes,
             mock_client_arc,
-        )
-        .into_rpc_module(),
+        ),
         storage_writer,
     )
 }
@@ -0,0 +1,427 @@
+//! Serves the JSON-RPC server over TLS (HTTPS and WSS). The server of jsonrpsee only accepts plain
+//! TCP connections, so the TLS connections are served by hyper, behind the same middleware, and the
+//! JSON-RPC requests they carry are dispatched to the methods of the server.
+#[cfg(test)]
+#[path = "tls_test.rs"]
+mod tls_test;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::{join_all, BoxFuture};
+use futures::{AsyncRead, AsyncWrite};
+use hyper::body::HttpBody;
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::Http;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use jsonrpsee::core::http_helpers::read_body;
+use jsonrpsee::core::{GenericTransportError, TEN_MB_SIZE_BYTES};
+use jsonrpsee::helpers::{batch_response_error, prepare_error};
+use jsonrpsee::server::logger::{Logger, MethodKind, TransportProtocol};
+use jsonrpsee::types::error::{reject_too_big_batch_request, ErrorCode};
+use jsonrpsee::types::{ErrorObject, Id, Params};
+use jsonrpsee::{BatchResponseBuilder, MethodCallback, MethodResponse, Methods};
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::validators::validate_path_exists;
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use rustls_pemfile::Item;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use soketto::connection::{Receiver as WebSocketReceiver, Sender as WebSocketSender};
+use soketto::handshake::http::Server as WebSocketHandshake;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tower::{BoxError, Layer, Service, ServiceBuilder};
+use tracing::{debug, warn};
+use validator::Validate;
+
+use crate::middleware::{is_websocket_upgrade, json_rpc_error_response};
+
+// How long a client has to complete the TLS handshake after it connects.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// The number of messages buffered for a WebSocket connection or a subscription, like the server of
+// jsonrpsee.
+const MESSAGE_BUFFER_SIZE: usize = 1024;
+
+/// The certificate of the JSON-RPC server and its private key, in PEM files.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Validate)]
+pub struct TlsConfig {
+    #[validate(custom = "validate_path_exists")]
+    pub certificate_path: PathBuf,
+    #[validate(custom = "validate_path_exists")]
+    pub private_key_path: PathBuf,
+}
+
+impl SerializeConfig for TlsConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "certificate_path",
+                &self.certificate_path,
+                "Path to a PEM file of the certificate chain of the server, starting with its own \
+                 certificate.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "private_key_path",
+                &self.private_key_path,
+                "Path to a PEM file of the private key of the server certificate.",
+                ParamPrivacyInput::Private,
+            ),
+        ])
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TlsError {
+    #[error(transparent)]
+    IOError(#[from] io::Error),
+    #[error(transparent)]
+    Rustls(#[from] tokio_rustls::rustls::Error),
+    #[error("No certificate found in {0}.")]
+    MissingCertificate(PathBuf),
+    #[error("No private key found in {0}.")]
+    MissingPrivateKey(PathBuf),
+}
+
+pub(crate) fn create_tls_acceptor(config: &TlsConfig) -> Result<TlsAcceptor, TlsError> {
+    let certificates: Vec<Certificate> =
+        rustls_pemfile::certs(&mut open(&config.certificate_path)?)?
+            .into_iter()
+            .map(Certificate)
+            .collect();
+    if certificates.is_empty() {
+        return Err(TlsError::MissingCertificate(config.certificate_path.clone()));
+    }
+    let private_key = rustls_pemfile::read_all(&mut open(&config.private_key_path)?)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::MissingPrivateKey(config.private_key_path.clone()))?;
+    let server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certificates, private_key)?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn open(path: &Path) -> io::Result<BufReader<File>> {
+    Ok(BufReader::new(File::open(path)?))
+}
+
+/// Accepts the TLS connections of the clients and serves them with the given middleware in front
+/// of the methods of the server, until the server is stopped.
+pub(crate) async fn run_tls_server<B, S, ResBody, L>(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    middleware: ServiceBuilder<B>,
+    service: JsonRpcService<L>,
+    mut stop_receiver: watch::Receiver<()>,
+) where
+    L: Logger,
+    B: Layer<JsonRpcService<L>, Service = S> + Send + 'static,
+    S: Service<Request<Body>, Response = Response<ResBody>> + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    ResBody: HttpBody + Send + 'static,
+    ResBody::Data: Send,
+    ResBody::Error: Into<BoxError>,
+{
+    loop {
+        let (stream, client_address) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(connection) => connection,
+                Err(err) => {
+                    warn!("Failed to accept a TLS connection: {err}.");
+                    continue;
+                }
+            },
+            _ = stop_receiver.changed() => return,
+        };
+        let acceptor = acceptor.clone();
+        let service = middleware.service(service.clone());
+        let mut stop_receiver = stop_receiver.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                result = serve_connection(stream, acceptor, service) => {
+                    if let Err(err) = result {
+                        debug!("TLS connection of {client_address} failed: {err}.");
+                    }
+                }
+                _ = stop_receiver.changed() => {}
+            }
+        });
+    }
+}
+
+async fn serve_connection<S, ResBody>(
+    stream: TcpStream,
+    acceptor: TlsAcceptor,
+    service: S,
+) -> Result<(), BoxError>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>> + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    ResBody: HttpBody + Send + 'static,
+    ResBody::Data: Send,
+    ResBody::Error: Into<BoxError>,
+{
+    // Bounded, so that clients that never complete the handshake don't hold connections forever.
+    let tls_stream = tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await??;
+    Http::new().serve_connection(tls_stream, service).with_upgrades().await?;
+    Ok(())
+}
+
+/// A service that calls the methods of the server for the JSON-RPC requests in the bodies of HTTP
+/// requests, and in the messages of the WebSocket connections that HTTP requests upgrade to.
+#[derive(Clone)]
+pub(crate) struct JsonRpcService<L> {
+    methods: Methods,
+    logger: L,
+    max_request_body_size: u32,
+    max_batch_size: u32,
+}
+
+impl<L: Logger> JsonRpcService<L> {
+    pub(crate) fn new(
+        methods: Methods,
+        logger: L,
+        max_request_body_size: u32,
+        max_batch_size: u32,
+    ) -> Self {
+        Self { methods, logger, max_request_body_size, max_batch_size }
+    }
+
+    async fn handle_http_request(self, request: Request<Body>) -> Response<Body> {
+        if request.method() != Method::POST {
+            return json_rpc_error_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                ErrorCode::InvalidRequest.code(),
+                "Only POST requests are supported.",
+            );
+        }
+        let (body, is_single) =
+            match read_body(request.headers(), request.into_body(), self.max_request_body_size)
+                .await
+            {
+                Ok(body) => body,
+                Err(GenericTransportError::TooLarge) => {
+                    return json_rpc_error_response(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        ErrorCode::OversizedRequest.code(),
+                        "The request is too big.",
+                    );
+                }
+                Err(err) => {
+                    return json_rpc_error_response(
+                        StatusCode::BAD_REQUEST,
+                        ErrorCode::ParseError.code(),
+                        &err.to_string(),
+                    );
+                }
+            };
+        let response = match is_single {
+            true => self.handle_call(&body, TransportProtocol::Http).await.0.result,
+            false => self.handle_batch(&body, TransportProtocol::Http).await,
+        };
+        Response::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(response))
+            .expect("The response should be valid.")
+    }
+
+    // Calls the method of a single JSON-RPC request. Returns its response and the notifications of
+    // the subscription it opened, if it opened one.
+    async fn handle_call(
+        &self,
+        request: &[u8],
+        transport: TransportProtocol,
+    ) -> (MethodResponse, mpsc::Receiver<String>) {
+        let Ok(parsed_request) = serde_json::from_slice::<jsonrpsee::types::Request<'_>>(request)
+        else {
+            let (id, code) = prepare_error(request);
+            return (MethodResponse::error(id, ErrorObject::from(code)), mpsc::channel(1).1);
+        };
+        let started_at = self.logger.on_request(transport);
+        let kind = match self.methods.method(&parsed_request.method) {
+            Some(MethodCallback::Subscription(_)) => MethodKind::Subscription,
+            Some(MethodCallback::Unsubscription(_)) => MethodKind::Unsubscription,
+            Some(_) => MethodKind::MethodCall,
+            None => MethodKind::Unknown,
+        };
+        let params = Params::new(parsed_request.params.map(|params| params.get()));
+        self.logger.on_call(&parsed_request.method, params, kind, transport);
+        // The request was parsed as JSON, so it's valid UTF-8.
+        let request = std::str::from_utf8(request).expect("The request should be valid UTF-8.");
+        let (response, notifications) =
+            match self.methods.raw_json_request(request, MESSAGE_BUFFER_SIZE).await {
+                Ok(result) => result,
+                Err(_) => (
+                    MethodResponse::error(
+                        parsed_request.id.clone(),
+                        ErrorObject::from(ErrorCode::ParseError),
+                    ),
+                    mpsc::channel(1).1,
+                ),
+            };
+        self.logger.on_result(
+            &parsed_request.method,
+            response.success_or_error,
+            started_at,
+            transport,
+        );
+        self.logger.on_response(&response.result, started_at, transport);
+        (response, notifications)
+    }
+
+    // Calls the methods of a batch of JSON-RPC requests and returns their responses.
+    async fn handle_batch(&self, request: &[u8], transport: TransportProtocol) -> String {
+        let Ok(requests) = serde_json::from_slice::<Vec<&RawValue>>(request) else {
+            return batch_response_error(Id::Null, ErrorObject::from(ErrorCode::ParseError));
+        };
+        if requests.is_empty() {
+            return batch_response_error(Id::Null, ErrorObject::from(ErrorCode::InvalidRequest));
+        }
+        if requests.len() > self.max_batch_size as usize {
+            return batch_response_error(
+                Id::Null,
+                reject_too_big_batch_request(self.max_batch_size as usize),
+            );
+        }
+        let responses = join_all(
+            requests.iter().map(|request| self.handle_call(request.get().as_bytes(), transport)),
+        )
+        .await;
+        let mut batch_response = BatchResponseBuilder::new_with_limit(TEN_MB_SIZE_BYTES as usize);
+        for (response, _) in &responses {
+            if let Err(too_large) = batch_response.append(response) {
+                return too_large;
+            }
+        }
+        batch_response.finish()
+    }
+
+    fn upgrade_to_websocket(self, request: Request<Body>) -> Response<Body> {
+        let mut handshake = WebSocketHandshake::new();
+        let response = match handshake.receive_request(&request) {
+            Ok(response) => response,
+            Err(err) => {
+                return json_rpc_error_response(
+                    StatusCode::BAD_REQUEST,
+                    ErrorCode::InvalidRequest.code(),
+                    &format!("Failed to upgrade the connection to WebSocket: {err}."),
+                );
+            }
+        };
+        tokio::spawn(async move {
+            let upgraded = match hyper::upgrade::on(request).await {
+                Ok(upgraded) => upgraded,
+                Err(err) => {
+                    debug!("Failed to upgrade a TLS connection to WebSocket: {err}.");
+                    return;
+                }
+            };
+            let mut builder = handshake.into_builder(futures::io::BufReader::new(
+                futures::io::BufWriter::new(upgraded.compat()),
+            ));
+            builder.set_max_message_size(self.max_request_body_size as usize);
+            let (sender, receiver) = builder.finish();
+            self.serve_websocket(sender, receiver).await;
+        });
+        response.map(|()| Body::empty())
+    }
+
+    async fn serve_websocket<T>(
+        self,
+        mut sender: WebSocketSender<T>,
+        mut receiver: WebSocketReceiver<T>,
+    ) where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        // Receiving a message can't be interrupted to send one, so the responses and the
+        // notifications are sent by another task.
+        let (messages_sender, mut messages_receiver) = mpsc::channel::<String>(MESSAGE_BUFFER_SIZE);
+        let sending = tokio::spawn(async move {
+            while let Some(message) = messages_receiver.recv().await {
+                if sender.send_text_owned(message).await.is_err() || sender.flush().await.is_err() {
+                    break;
+                }
+            }
+        });
+        let mut message = Vec::new();
+        loop {
+            if let Err(err) = receiver.receive_data(&mut message).await {
+                debug!("WebSocket connection over TLS closed: {err}.");
+                break;
+            }
+            let service = self.clone();
+            let request = std::mem::take(&mut message);
+            let messages_sender = messages_sender.clone();
+            tokio::spawn(service.handle_websocket_message(request, messages_sender));
+        }
+        // Closes the channel of the messages, which ends the subscriptions of the connection.
+        sending.abort();
+    }
+
+    async fn handle_websocket_message(
+        self,
+        message: Vec<u8>,
+        messages_sender: mpsc::Sender<String>,
+    ) {
+        let is_batch = message.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'[');
+        if is_batch {
+            let response = self.handle_batch(&message, TransportProtocol::WebSocket).await;
+            let _ = messages_sender.send(response).await;
+            return;
+        }
+        let (response, mut notifications) =
+            self.handle_call(&message, TransportProtocol::WebSocket).await;
+        if messages_sender.send(response.result).await.is_err() {
+            return;
+        }
+        loop {
+            tokio::select! {
+                notification = notifications.recv() => {
+                    let Some(notification) = notification else {
+                        return;
+                    };
+                    if messages_sender.send(notification).await.is_err() {
+                        return;
+                    }
+                }
+                _ = messages_sender.closed() => return,
+            }
+        }
+    }
+}
+
+impl<L: Logger> Service<Request<Body>> for JsonRpcService<L> {
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Response<Body>, BoxError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move {
+            if is_websocket_upgrade(&request) {
+                return Ok(service.upgrade_to_websocket(request));
+            }
+            Ok(service.handle_http_request(request).await)
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use jsonrpsee::server::ServerHandle;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageReader;
+use soketto::handshake::{Client as WebSocketClient, ServerResponse};
+use test_utils::get_absolute_path;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+use super::{create_tls_acceptor, TlsConfig, TlsError};
+use crate::test_utils::{
+    get_test_highest_block,
+    get_test_new_block_sender,
+    get_test_pending_classes,
+    get_test_pending_data,
+    get_test_rpc_config,
+};
+use crate::{run_server, RpcConfig};
+
+const CERTIFICATE_PATH: &str = "crates/papyrus_rpc/resources/tls_test_certificate.pem";
+const PRIVATE_KEY_PATH: &str = "crates/papyrus_rpc/resources/tls_test_key.pem";
+// The chain id of the test config, SN_GOERLI, in hex.
+const CHAIN_ID_RESULT: &str = r#""result":"0x534e5f474f45524c49""#;
+
+fn test_tls_config() -> TlsConfig {
+    TlsConfig {
+        certificate_path: get_absolute_path(CERTIFICATE_PATH),
+        private_key_path: get_absolute_path(PRIVATE_KEY_PATH),
+    }
+}
+
+// Trusts only the test certificate.
+fn test_tls_connector() -> TlsConnector {
+    let certificate_file = std::fs::File::open(get_absolute_path(CERTIFICATE_PATH)).unwrap();
+    let mut root_store = RootCertStore::empty();
+    for certificate in
+        rustls_pemfile::certs(&mut std::io::BufReader::new(certificate_file)).unwrap()
+    {
+        root_store.add(&Certificate(certificate)).unwrap();
+    }
+    let client_config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(client_config))
+}
+
+async fn run_test_server_over_tls(storage_reader: StorageReader) -> (SocketAddr, ServerHandle) {
+    let config = RpcConfig { tls: Some(test_tls_config()), ..get_test_rpc_config() };
+    run_server(
+        &config,
+        get_test_highest_block(),
+        get_test_pending_data(),
+        get_test_pending_classes(),
+        storage_reader,
+        get_test_new_block_sender(),
+        "NODE VERSION",
+    )
+    .await
+    .unwrap()
+}
+
+async fn connect_over_tls(addr: SocketAddr) -> TlsStream<TcpStream> {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let server_name = ServerName::try_from("localhost").unwrap();
+    test_tls_connector().connect(server_name, stream).await.unwrap()
+}
+
+// Sends the body in an HTTPS request and returns the raw response.
+async fn post_over_tls(addr: SocketAddr, body: &str) -> String {
+    let mut tls_stream = connect_over_tls(addr).await;
+    let request = format!(
+        "POST /rpc/v0_6 HTTP/1.1\r\nHost: localhost\r\nContent-Type: \
+         application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    tls_stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    tls_stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn serve_over_tls() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let (addr, _handle) = run_test_server_over_tls(storage_reader).await;
+
+    let response =
+        post_over_tls(addr, r#"{"jsonrpc":"2.0","id":"1","method":"starknet_chainId"}"#).await;
+
+    assert!(response.starts_with("HTTP/1.1 200"), "Unexpected response: {response}");
+    assert!(response.contains(CHAIN_ID_RESULT), "Unexpected response: {response}");
+}
+
+#[tokio::test]
+async fn serve_batch_over_tls() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let (addr, _handle) = run_test_server_over_tls(storage_reader).await;
+
+    let response = post_over_tls(
+        addr,
+        r#"[{"jsonrpc":"2.0","id":"1","method":"starknet_chainId"},{"jsonrpc":"2.0","id":"2","method":"starknet_chainId"}]"#,
+    )
+    .await;
+
+    assert!(response.starts_with("HTTP/1.1 200"), "Unexpected response: {response}");
+    assert_eq!(response.matches(CHAIN_ID_RESULT).count(), 2, "Unexpected response: {response}");
+}
+
+#[tokio::test]
+async fn serve_websocket_over_tls() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let (addr, _handle) = run_test_server_over_tls(storage_reader).await;
+
+    let tls_stream = connect_over_tls(addr).await;
+    let mut client = WebSocketClient::new(tls_stream.compat(), "localhost", "/rpc/v0_6");
+    assert_matches!(client.handshake().await.unwrap(), ServerResponse::Accepted { .. });
+    let (mut sender, mut receiver) = client.into_builder().finish();
+    sender
+        .send_text(r#"{"jsonrpc":"2.0","id":"1","method":"starknet_V0_6_chainId"}"#)
+        .await
+        .unwrap();
+    sender.flush().await.unwrap();
+    let mut response = Vec::new();
+    receiver.receive_data(&mut response).await.unwrap();
+    let response = String::from_utf8(response).unwrap();
+
+    assert!(response.contains(CHAIN_ID_RESULT), "Unexpected response: {response}");
+}
+
+#[tokio::test]
+async fn stop_server_over_tls() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let (addr, handle) = run_test_server_over_tls(storage_reader).await;
+
+    handle.stop().unwrap();
+    handle.stopped().await;
+
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[test]
+fn missing_private_key() {
+    let config =
+        TlsConfig { private_key_path: get_absolute_path(CERTIFICATE_PATH), ..test_tls_config() };
+    assert_matches!(create_tls_acceptor(&config), Err(TlsError::MissingPrivateKey(_)));
+}
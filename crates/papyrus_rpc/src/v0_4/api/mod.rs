@@ -7,6 +7,7 @@ use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::types::ErrorObjectOwned;
 use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_common::BlockHashAndNumber;
+pub use papyrus_execution::SimulationFlag;
 use papyrus_execution::{ExecutableTransactionInput, ExecutionError};
 use papyrus_proc_macros::versioned_rpc;
 use papyrus_storage::compiled_class::CasmStorageReader;
@@ -54,6 +55,7 @@ use super::transaction::{
 };
 use super::write_api_result::{AddDeclareOkResult, AddDeployAccountOkResult, AddInvokeOkResult};
 use crate::api::{BlockId, CallRequest};
+use crate::compression_utils::MAX_DECOMPRESSED_PROGRAM_SIZE;
 use crate::syncing_state::SyncingState;
 use crate::{internal_server_error, ContinuationTokenAsStruct};
 
@@ -300,13 +302,6 @@ pub struct SimulatedTransaction {
     pub fee_estimation: FeeEstimate,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum SimulationFlag {
-    SkipValidate,
-    SkipFeeCharge,
-}
-
 impl TryFrom<BroadcastedTransaction> for ExecutableTransactionInput {
     type Error = ErrorObjectOwned;
     fn try_from(value: BroadcastedTransaction) -> Result<Self, Self::Error> {
@@ -547,9 +542,16 @@ pub(crate) fn decompress_program(
     base64::decode(base64_compressed_program).map_err(internal_server_error)?;
     let compressed_data =
         base64::decode(base64_compressed_program).map_err(internal_server_error)?;
-    let mut decoder = GzDecoder::new(compressed_data.as_slice());
+    // Reads one byte more than the limit to tell if the program is too large.
+    let mut decoder =
+        GzDecoder::new(compressed_data.as_slice()).take(MAX_DECOMPRESSED_PROGRAM_SIZE as u64 + 1);
     let mut decompressed = Vec::new();
     decoder.read_to_end(&mut decompressed).map_err(internal_server_error)?;
+    if decompressed.len() > MAX_DECOMPRESSED_PROGRAM_SIZE {
+        return Err(internal_server_error(format!(
+            "The decompressed program is larger than {MAX_DECOMPRESSED_PROGRAM_SIZE} bytes."
+        )));
+    }
     serde_json::from_reader(decompressed.as_slice()).map_err(internal_server_error)
 }
 
@@ -29,6 +29,7 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{
     Block as StarknetApiBlock,
+    BlockBody,
     BlockHash,
     BlockHeader,
     BlockNumber,
@@ -159,6 +160,7 @@ use crate::test_utils::{
     get_starknet_spec_api_schema_for_components,
     get_starknet_spec_api_schema_for_method_results,
     get_test_highest_block,
+    get_test_new_block_sender,
     get_test_pending_classes,
     get_test_pending_data,
     get_test_rpc_config,
@@ -234,7 +236,7 @@ async fn block_hash_and_number() {
     )
     .await;
 
-    // Add a state diff to the block and check that we get the block.
+    // Add a state diff and a body to the block and check that we get the block.
     storage_writer
         .begin_rw_txn()
         .unwrap()
@@ -244,6 +246,8 @@ async fn block_hash_and_number() {
             IndexMap::new(),
         )
         .unwrap()
+        .append_body(block.header.block_number, BlockBody::default())
+        .unwrap()
         .commit()
         .unwrap();
     call_api_then_assert_and_validate_schema_for_result(
@@ -296,7 +300,7 @@ async fn block_number() {
     )
     .await;
 
-    // Add a state diff to the block and check that we get the block.
+    // Add a state diff and a body to the block and check that we get the block.
     storage_writer
         .begin_rw_txn()
         .unwrap()
@@ -306,6 +310,8 @@ async fn block_number() {
             IndexMap::new(),
         )
         .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
         .commit()
         .unwrap();
     call_api_then_assert_and_validate_schema_for_result(
@@ -795,6 +801,8 @@ async fn get_class() {
         .unwrap()
         .append_header(parent_header.block_number, &parent_header)
         .unwrap()
+        .append_body(parent_header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(
             parent_header.block_number,
             starknet_api::state::StateDiff::default(),
@@ -803,6 +811,8 @@ async fn get_class() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1076,6 +1086,8 @@ async fn get_class_at() {
         .unwrap()
         .append_header(parent_header.block_number, &parent_header)
         .unwrap()
+        .append_body(parent_header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(
             parent_header.block_number,
             starknet_api::state::StateDiff::default(),
@@ -1084,6 +1096,8 @@ async fn get_class_at() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1249,6 +1263,8 @@ async fn get_class_hash_at() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1403,6 +1419,8 @@ async fn get_nonce() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1539,6 +1557,8 @@ async fn get_storage_at() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -2899,6 +2919,7 @@ async fn serialize_returns_valid_json() {
         get_test_pending_data(),
         get_test_pending_classes(),
         storage_reader,
+        get_test_new_block_sender(),
         NODE_VERSION,
     )
     .await
@@ -3064,6 +3085,8 @@ async fn get_deprecated_class_state_mutability() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, state_diff, IndexMap::new())
         .unwrap()
         .commit()
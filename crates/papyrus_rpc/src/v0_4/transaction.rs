@@ -36,7 +36,7 @@ use starknet_api::transaction::{
 use starknet_client::writer::objects::transaction as client_transaction;
 
 use super::error::BLOCK_NOT_FOUND;
-use crate::internal_server_error;
+use crate::{internal_server_error, verify_body_not_pruned};
 
 #[derive(
     Debug, Deserialize, Serialize, Default, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord,
@@ -716,6 +716,7 @@ pub fn get_block_txs_by_number<
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
 ) -> Result<Vec<Transaction>, ErrorObjectOwned> {
+    verify_body_not_pruned(txn, block_number)?;
     let transactions = txn
         .get_block_transactions(block_number)
         .map_err(internal_server_error)?
@@ -728,6 +729,7 @@ pub fn get_block_tx_hashes_by_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
 ) -> Result<Vec<TransactionHash>, ErrorObjectOwned> {
+    verify_body_not_pruned(txn, block_number)?;
     let transaction_hashes = txn
         .get_block_transaction_hashes(block_number)
         .map_err(internal_server_error)?
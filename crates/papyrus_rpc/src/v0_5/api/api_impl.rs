@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
@@ -6,6 +7,7 @@ use jsonrpsee::types::ErrorObjectOwned;
 use jsonrpsee::RpcModule;
 use lazy_static::lazy_static;
 use papyrus_common::pending_classes::{PendingClasses, PendingClassesTrait};
+use papyrus_execution::contract_class_cache::ContractClassCache;
 use papyrus_execution::objects::{
     PendingData as ExecutionPendingData,
     TransactionSimulationOutput,
@@ -16,9 +18,11 @@ use papyrus_execution::{
     execute_call,
     execution_utils,
     simulate_transactions as exec_simulate_transactions,
+    simulation_flags_to_execution_flags,
     ExecutableTransactionInput,
     ExecutionConfigByBlock,
     ExecutionError,
+    ExecutionFlags,
 };
 use papyrus_storage::body::events::{EventIndex, EventsReader};
 use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
@@ -33,6 +37,7 @@ use starknet_api::state::{StateNumber, StorageKey};
 use starknet_api::transaction::{
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     Fee,
     Transaction as StarknetApiTransaction,
     TransactionHash,
@@ -128,6 +133,8 @@ use crate::{
     get_block_status,
     get_latest_block_number,
     internal_server_error,
+    run_execution,
+    verify_body_not_pruned,
     verify_storage_scope,
     ContinuationTokenAsStruct,
 };
@@ -141,6 +148,8 @@ lazy_static! {
 pub struct JsonRpcServerV0_5Impl {
     pub chain_id: ChainId,
     pub execution_config: ExecutionConfigByBlock,
+    pub class_cache: ContractClassCache,
+    pub execution_timeout: Duration,
     pub storage_reader: StorageReader,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
@@ -333,6 +342,7 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
         if let Some(transaction_index) =
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
+            verify_body_not_pruned(&txn, transaction_index.0)?;
             let transaction = txn
                 .get_transaction(transaction_index)
                 .map_err(internal_server_error)?
@@ -382,6 +392,7 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
                 (client_transaction.try_into().map_err(internal_server_error)?, transaction_hash)
             } else {
                 let block_number = get_accepted_block_number(&txn, block_id)?;
+                verify_body_not_pruned(&txn, block_number)?;
 
                 let tx_index = TransactionIndex(block_number, index);
                 let transaction = txn
@@ -412,6 +423,7 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
             Ok(transactions_len)
         } else {
             let block_number = get_accepted_block_number(&txn, block_id)?;
+            verify_body_not_pruned(&txn, block_number)?;
             Ok(txn
                 .get_block_transactions_count(block_number)
                 .map_err(internal_server_error)?
@@ -478,6 +490,7 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
         if let Some(transaction_index) =
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
+            verify_body_not_pruned(&txn, transaction_index.0)?;
             let block_number = transaction_index.0;
             let status = get_block_status(&txn, block_number)?;
 
@@ -740,10 +753,28 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
         if start_event_index.0.0 <= latest_block_number {
-            for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
-                .map_err(internal_server_error)?
-            {
+            verify_body_not_pruned(&txn, start_event_index.0.0)?;
+            // Without an address, a single first key narrows the events the most. Otherwise, the
+            // blocks without matching events are skipped by their events bloom filters.
+            let events = match (filter.address, filter_single_first_key(&filter)) {
+                (None, Some(first_key)) => {
+                    txn.iter_events_by_first_key(first_key, start_event_index)
+                }
+                // Every block matches a filter without an address and keys, so its bloom filter
+                // isn't read.
+                (None, None) if filter.keys.iter().all(|keys| keys.is_empty()) => {
+                    txn.iter_events(None, start_event_index, to_block_number)
+                }
+                (None, None) => txn.iter_events_by_bloom_filter(
+                    None,
+                    filter.keys.clone(),
+                    start_event_index,
+                    to_block_number,
+                ),
+                _ => txn.iter_events(filter.address, start_event_index, to_block_number),
+            }
+            .map_err(internal_server_error)?;
+            for ((from_address, event_index), content) in events {
                 let block_number = (event_index.0).0;
                 if block_number > to_block_number {
                     break;
@@ -881,9 +912,10 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
+        let class_cache = self.class_cache.clone();
         let contract_address_copy = request.contract_address;
 
-        let call_result = tokio::task::spawn_blocking(move || {
+        let call_result = run_execution(self.execution_timeout, move |abort_handle| {
             execute_call(
                 reader,
                 maybe_pending_data,
@@ -894,10 +926,12 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
                 request.entry_point_selector,
                 request.calldata,
                 &block_execution_config,
+                Some(class_cache),
+                None,
+                abort_handle,
             )
         })
-        .await
-        .map_err(internal_server_error)?;
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -975,10 +1009,14 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
         let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
-            Some(client_pending_data_to_execution_pending_data(
-                read_pending_data(&self.pending_data, &storage_txn).await?,
-                self.pending_classes.read().await.clone(),
-            ))
+            Some(
+                read_pending_data_for_fee_estimation(
+                    &self.pending_data,
+                    &self.pending_classes,
+                    &storage_txn,
+                )
+                .await?,
+            )
         } else {
             None
         };
@@ -1000,8 +1038,8 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
-
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
+        let class_cache = self.class_cache.clone();
+        let estimate_fee_result = run_execution(self.execution_timeout, move |abort_handle| {
             exec_estimate_fee(
                 executable_txns,
                 &chain_id,
@@ -1010,11 +1048,12 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
                 state_number,
                 block_number,
                 &block_execution_config,
-                false,
+                ExecutionFlags { validate: false, charge_fee: false },
+                Some(class_cache),
+                abort_handle,
             )
         })
-        .await
-        .map_err(internal_server_error)?;
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1048,10 +1087,14 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
         let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
-            Some(client_pending_data_to_execution_pending_data(
-                read_pending_data(&self.pending_data, &storage_txn).await?,
-                self.pending_classes.read().await.clone(),
-            ))
+            Some(
+                read_pending_data_for_fee_estimation(
+                    &self.pending_data,
+                    &self.pending_classes,
+                    &storage_txn,
+                )
+                .await?,
+            )
         } else {
             None
         };
@@ -1070,26 +1113,27 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
+        let class_cache = self.class_cache.clone();
+        let flags = simulation_flags_to_execution_flags(&simulation_flags);
 
-        let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
-        let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
-
-        let simulate_transactions_result = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_txns,
-                None,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                charge_fee,
-                validate,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?;
+        let simulate_transactions_result =
+            run_execution(self.execution_timeout, move |abort_handle| {
+                exec_simulate_transactions(
+                    executable_txns,
+                    None,
+                    &chain_id,
+                    reader,
+                    maybe_pending_data,
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    flags,
+                    Some(class_cache),
+                    None,
+                    abort_handle,
+                )
+            })
+            .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1174,6 +1218,7 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
                 .get_transaction_idx_by_hash(&transaction_hash)
                 .map_err(internal_server_error)?
                 .ok_or(INVALID_TRANSACTION_HASH)?;
+            verify_body_not_pruned(&storage_txn, block_number)?;
 
             let block_transactions = storage_txn
                 .get_block_transactions(block_number)
@@ -1217,23 +1262,25 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
-
-        let simulate_transactions_result = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_transactions,
-                Some(transaction_hashes),
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                true,
-                true,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?;
+        let class_cache = self.class_cache.clone();
+        let simulate_transactions_result =
+            run_execution(self.execution_timeout, move |abort_handle| {
+                exec_simulate_transactions(
+                    executable_transactions,
+                    Some(transaction_hashes),
+                    &chain_id,
+                    reader,
+                    maybe_pending_data,
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    ExecutionFlags::default(),
+                    Some(class_cache),
+                    None,
+                    abort_handle,
+                )
+            })
+            .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1261,6 +1308,9 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
         };
 
         let block_number = get_accepted_block_number(&storage_txn, block_id)?;
+        if maybe_client_pending_data.is_none() {
+            verify_body_not_pruned(&storage_txn, block_number)?;
+        }
 
         let block_not_reverted_validator =
             BlockNotRevertedValidator::new(block_number, &storage_txn)?;
@@ -1330,24 +1380,27 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
+        let class_cache = self.class_cache.clone();
         let transaction_hashes_clone = transaction_hashes.clone();
 
-        let simulate_transactions_result = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_txns,
-                Some(transaction_hashes_clone),
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                true,
-                true,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?;
+        let simulate_transactions_result =
+            run_execution(self.execution_timeout, move |abort_handle| {
+                exec_simulate_transactions(
+                    executable_txns,
+                    Some(transaction_hashes_clone),
+                    &chain_id,
+                    reader,
+                    maybe_pending_data,
+                    state_number,
+                    block_number,
+                    &block_execution_config,
+                    ExecutionFlags::default(),
+                    Some(class_cache),
+                    None,
+                    abort_handle,
+                )
+            })
+            .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1373,10 +1426,14 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
         trace!("Estimating fee of message: {:#?}", message);
         let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
-            Some(client_pending_data_to_execution_pending_data(
-                read_pending_data(&self.pending_data, &storage_txn).await?,
-                self.pending_classes.read().await.clone(),
-            ))
+            Some(
+                read_pending_data_for_fee_estimation(
+                    &self.pending_data,
+                    &self.pending_classes,
+                    &storage_txn,
+                )
+                .await?,
+            )
         } else {
             None
         };
@@ -1401,8 +1458,8 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
-
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
+        let class_cache = self.class_cache.clone();
+        let estimate_fee_result = run_execution(self.execution_timeout, move |abort_handle| {
             exec_estimate_fee(
                 executable_txns,
                 &chain_id,
@@ -1411,11 +1468,12 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
                 state_number,
                 block_number,
                 &block_execution_config,
-                false,
+                ExecutionFlags { validate: false, charge_fee: false },
+                Some(class_cache),
+                abort_handle,
             )
         })
-        .await
-        .map_err(internal_server_error)?;
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1448,6 +1506,15 @@ impl JsonRpcServer for JsonRpcServerV0_5Impl {
 async fn read_pending_data<Mode: TransactionKind>(
     pending_data: &Arc<RwLock<PendingData>>,
     txn: &StorageTxn<'_, Mode>,
+) -> RpcResult<PendingData> {
+    pending_data_on_latest_block(&*pending_data.read().await, txn)
+}
+
+// Returns the given pending data if it extends the latest block, and otherwise an empty pending
+// block on top of the latest block.
+fn pending_data_on_latest_block<Mode: TransactionKind>(
+    pending_data: &PendingData,
+    txn: &StorageTxn<'_, Mode>,
 ) -> RpcResult<PendingData> {
     let (latest_header, starknet_version) = match get_latest_block_number(txn)? {
         Some(latest_block_number) => get_block_header_by_number(txn, latest_block_number)?,
@@ -1461,9 +1528,8 @@ async fn read_pending_data<Mode: TransactionKind>(
             StarknetVersion::default(),
         ),
     };
-    let pending_data = &pending_data.read().await;
     if pending_data.block.parent_block_hash == latest_header.block_hash {
-        Ok((*pending_data).clone())
+        Ok(pending_data.clone())
     } else {
         Ok(PendingData {
             block: PendingBlock {
@@ -1483,6 +1549,36 @@ async fn read_pending_data<Mode: TransactionKind>(
     }
 }
 
+// Returns the pending data that fees should be estimated on. If the pending block doesn't extend
+// the latest block because the node hasn't synced the pending block's parent yet, the pending block
+// still holds the most recent L1 gas prices, so they're used instead of the latest block's prices.
+async fn read_pending_data_for_fee_estimation<Mode: TransactionKind>(
+    pending_data: &Arc<RwLock<PendingData>>,
+    pending_classes: &Arc<RwLock<PendingClasses>>,
+    txn: &StorageTxn<'_, Mode>,
+) -> RpcResult<ExecutionPendingData> {
+    // The pending data is read once, so the gas prices are taken from the same pending block that
+    // is checked against the latest block.
+    let pending_data = pending_data.read().await.clone();
+    let mut execution_pending_data = client_pending_data_to_execution_pending_data(
+        pending_data_on_latest_block(&pending_data, txn)?,
+        pending_classes.read().await.clone(),
+    );
+    if pending_data.block.timestamp > execution_pending_data.timestamp {
+        execution_pending_data.eth_l1_gas_price = pending_data.block.eth_l1_gas_price;
+        execution_pending_data.strk_l1_gas_price = pending_data.block.strk_l1_gas_price;
+    }
+    Ok(execution_pending_data)
+}
+
+// Returns the first key of the filtered events if the filter allows only one.
+fn filter_single_first_key(filter: &EventFilter) -> Option<EventKey> {
+    match filter.keys.first() {
+        Some(first_keys) if first_keys.len() == 1 => first_keys.iter().next().cloned(),
+        _ => None,
+    }
+}
+
 fn do_event_keys_match_filter(event_content: &EventContent, filter: &EventFilter) -> bool {
     filter.keys.iter().enumerate().all(|(i, keys)| {
         event_content.keys.len() > i && (keys.is_empty() || keys.contains(&event_content.keys[i]))
@@ -1493,6 +1589,8 @@ impl JsonRpcServerImpl for JsonRpcServerV0_5Impl {
     fn new(
         chain_id: ChainId,
         execution_config: ExecutionConfigByBlock,
+        class_cache: ContractClassCache,
+        execution_timeout: Duration,
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
@@ -1505,6 +1603,8 @@ impl JsonRpcServerImpl for JsonRpcServerV0_5Impl {
         Self {
             chain_id,
             execution_config,
+            class_cache,
+            execution_timeout,
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
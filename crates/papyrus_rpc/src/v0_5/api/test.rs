@@ -29,6 +29,7 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{
     Block as StarknetApiBlock,
+    BlockBody,
     BlockHash,
     BlockHeader,
     BlockNumber,
@@ -161,6 +162,7 @@ use crate::test_utils::{
     get_starknet_spec_api_schema_for_components,
     get_starknet_spec_api_schema_for_method_results,
     get_test_highest_block,
+    get_test_new_block_sender,
     get_test_pending_classes,
     get_test_pending_data,
     get_test_rpc_config,
@@ -254,7 +256,7 @@ async fn block_hash_and_number() {
     )
     .await;
 
-    // Add a state diff to the block and check that we get the block.
+    // Add a state diff and a body to the block and check that we get the block.
     storage_writer
         .begin_rw_txn()
         .unwrap()
@@ -264,6 +266,8 @@ async fn block_hash_and_number() {
             IndexMap::new(),
         )
         .unwrap()
+        .append_body(block.header.block_number, BlockBody::default())
+        .unwrap()
         .commit()
         .unwrap();
     call_api_then_assert_and_validate_schema_for_result(
@@ -318,7 +322,7 @@ async fn block_number() {
     )
     .await;
 
-    // Add a state diff to the block and check that we get the block.
+    // Add a state diff and a body to the block and check that we get the block.
     storage_writer
         .begin_rw_txn()
         .unwrap()
@@ -328,6 +332,8 @@ async fn block_number() {
             IndexMap::new(),
         )
         .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
         .commit()
         .unwrap();
     call_api_then_assert_and_validate_schema_for_result(
@@ -837,6 +843,8 @@ async fn get_class() {
         .unwrap()
         .update_starknet_version(&parent_header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(parent_header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(
             parent_header.block_number,
             starknet_api::state::StateDiff::default(),
@@ -845,6 +853,8 @@ async fn get_class() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1240,6 +1250,8 @@ async fn get_class_at() {
         .unwrap()
         .update_starknet_version(&parent_header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(parent_header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(
             parent_header.block_number,
             starknet_api::state::StateDiff::default(),
@@ -1248,6 +1260,8 @@ async fn get_class_at() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1415,6 +1429,8 @@ async fn get_class_hash_at() {
         .unwrap()
         .update_starknet_version(&header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1571,6 +1587,8 @@ async fn get_nonce() {
         .unwrap()
         .update_starknet_version(&header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1709,6 +1727,8 @@ async fn get_storage_at() {
         .unwrap()
         .update_starknet_version(&header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -3085,6 +3105,7 @@ async fn serialize_returns_valid_json() {
         get_test_pending_data(),
         get_test_pending_classes(),
         storage_reader,
+        get_test_new_block_sender(),
         NODE_VERSION,
     )
     .await
@@ -3246,6 +3267,8 @@ async fn get_deprecated_class_state_mutability() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, state_diff, IndexMap::new())
         .unwrap()
         .commit()
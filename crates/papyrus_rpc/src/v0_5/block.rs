@@ -91,8 +91,8 @@ pub fn get_block_header_by_number<Mode: TransactionKind>(
 }
 
 /// Return the closest block number that corresponds to the given block id and is accepted (i.e not
-/// pending). Latest block means the most advanced block that we've downloaded and that we've
-/// downloaded its state diff.
+/// pending). Latest block means the most advanced block that we've downloaded both its body and
+/// its state diff.
 pub(crate) fn get_accepted_block_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_id: BlockId,
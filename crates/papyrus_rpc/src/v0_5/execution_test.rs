@@ -398,6 +398,50 @@ async fn pending_call_estimate_fee() {
     // calculated inside the hash.
 }
 
+// Test that fee estimations on the pending block use the gas price of the pending block even if the
+// node hasn't synced the pending block's parent yet.
+#[tokio::test]
+async fn pending_call_estimate_fee_with_unsynced_parent() {
+    let pending_data = get_test_pending_data();
+    *pending_data.write().await = PendingData {
+        block: PendingBlock {
+            eth_l1_gas_price: *GAS_PRICE,
+            sequencer_address: *SEQUENCER_ADDRESS,
+            timestamp: BlockTimestamp(BLOCK_TIMESTAMP.0 + 1),
+            parent_block_hash: BlockHash(stark_felt!("0x2")),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (module, storage_writer) = get_test_rpc_server_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, Some(pending_data), None, None);
+    // The latest block in the storage has a different gas price than the pending block.
+    prepare_storage_for_execution(storage_writer);
+
+    let invoke = BroadcastedTransaction::Invoke(InvokeTransaction::Version1(InvokeTransactionV1 {
+        max_fee: Fee(1000000 * GAS_PRICE.0),
+        version: TransactionVersion1::default(),
+        sender_address: *ACCOUNT_ADDRESS,
+        calldata: calldata![
+            *DEPRECATED_CONTRACT_ADDRESS.0.key(),  // Contract address.
+            selector_from_name("return_result").0, // EP selector.
+            stark_felt!(1_u8),                     // Calldata length.
+            stark_felt!(2_u8)                      // Calldata: num.
+        ],
+        ..Default::default()
+    }));
+
+    let res = module
+        .call::<_, Vec<FeeEstimate>>(
+            "starknet_V0_5_estimateFee",
+            (vec![invoke], BlockId::Tag(Tag::Pending)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![EXPECTED_FEE_ESTIMATE.clone()]);
+}
+
 #[tokio::test]
 async fn call_simulate() {
     let (module, storage_writer) = get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use jsonrpsee::core::RpcResult;
@@ -6,16 +7,20 @@ use jsonrpsee::types::ErrorObjectOwned;
 use jsonrpsee::RpcModule;
 use lazy_static::lazy_static;
 use papyrus_common::pending_classes::{PendingClasses, PendingClassesTrait};
-use papyrus_execution::objects::{PendingData as ExecutionPendingData, TransactionTrace};
+use papyrus_execution::contract_class_cache::ContractClassCache;
+use papyrus_execution::objects::{PendingData as ExecutionPendingData, StateOverrides};
 use papyrus_execution::{
     estimate_fee as exec_estimate_fee,
     execute_call,
     execution_utils,
     simulate_transactions as exec_simulate_transactions,
+    simulation_flags_to_execution_flags,
     ExecutableTransactionInput,
     ExecutionConfigByBlock,
+    ExecutionError,
+    ExecutionFlags,
 };
-use papyrus_storage::body::events::{EventIndex, EventsReader};
+use papyrus_storage::body::events::{EventIndex, EventsReader, ThinTransactionOutput};
 use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
 use papyrus_storage::db::TransactionKind;
 use papyrus_storage::header::StarknetVersion;
@@ -28,6 +33,7 @@ use starknet_api::state::{StateNumber, StorageKey};
 use starknet_api::transaction::{
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     Fee,
     Transaction as StarknetApiTransaction,
     TransactionHash,
@@ -37,6 +43,10 @@ use starknet_client::reader::objects::pending_data::{
     PendingBlock,
     PendingStateUpdate as ClientPendingStateUpdate,
 };
+use starknet_client::reader::objects::transaction::{
+    Transaction as ClientTransaction,
+    TransactionReceipt as ClientTransactionReceipt,
+};
 use starknet_client::reader::PendingData;
 use starknet_client::writer::{StarknetWriter, WriterClientError};
 use starknet_client::ClientError;
@@ -49,6 +59,7 @@ use super::super::block::{
     Block,
     BlockHeader,
     BlockNotRevertedValidator,
+    BlockWithReceipts,
     GeneralBlockHeader,
     PendingBlockHeader,
     ResourcePrice,
@@ -86,6 +97,7 @@ use super::super::transaction::{
     TransactionReceipt,
     TransactionStatus,
     TransactionWithHash,
+    TransactionWithReceipt,
     Transactions,
     TypedDeployAccountTransaction,
     TypedInvokeTransaction,
@@ -114,6 +126,7 @@ use super::{
     JsonRpcV0_6Server as JsonRpcServer,
     SimulatedTransaction,
     SimulationFlag,
+    TransactionTrace,
     TransactionTraceWithHash,
 };
 use crate::api::{BlockHashOrNumber, JsonRpcServerImpl, Tag};
@@ -124,6 +137,8 @@ use crate::{
     get_block_status,
     get_latest_block_number,
     internal_server_error,
+    run_execution,
+    verify_body_not_pruned,
     verify_storage_scope,
     ContinuationTokenAsStruct,
 };
@@ -134,9 +149,12 @@ lazy_static! {
 }
 
 /// Rpc server.
+#[derive(Clone)]
 pub struct JsonRpcServerV0_6Impl {
     pub chain_id: ChainId,
     pub execution_config: ExecutionConfigByBlock,
+    pub class_cache: ContractClassCache,
+    pub execution_timeout: Duration,
     pub storage_reader: StorageReader,
     pub max_events_chunk_size: usize,
     pub max_events_keys: usize,
@@ -335,6 +353,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         if let Some(transaction_index) =
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
+            verify_body_not_pruned(&txn, transaction_index.0)?;
             let transaction = txn
                 .get_transaction(transaction_index)
                 .map_err(internal_server_error)?
@@ -384,6 +403,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 (client_transaction.try_into().map_err(internal_server_error)?, transaction_hash)
             } else {
                 let block_number = get_accepted_block_number(&txn, block_id)?;
+                verify_body_not_pruned(&txn, block_number)?;
 
                 let tx_index = TransactionIndex(block_number, index);
                 let transaction = txn
@@ -414,6 +434,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             Ok(transactions_len)
         } else {
             let block_number = get_accepted_block_number(&txn, block_id)?;
+            verify_body_not_pruned(&txn, block_number)?;
             Ok(txn
                 .get_block_transactions_count(block_number)
                 .map_err(internal_server_error)?
@@ -480,6 +501,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         if let Some(transaction_index) =
             txn.get_transaction_idx_by_hash(&transaction_hash).map_err(internal_server_error)?
         {
+            verify_body_not_pruned(&txn, transaction_index.0)?;
             let block_number = transaction_index.0;
             let status = get_block_status(&txn, block_number)?;
 
@@ -500,15 +522,6 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 .map_err(internal_server_error)?
                 .unwrap_or_else(|| panic!("Should have tx {}", transaction_hash));
 
-            // TODO: Add version function to transaction in SN_API.
-            let tx_version = match &tx {
-                StarknetApiTransaction::Declare(tx) => tx.version(),
-                StarknetApiTransaction::Deploy(tx) => tx.version,
-                StarknetApiTransaction::DeployAccount(tx) => tx.version(),
-                StarknetApiTransaction::Invoke(tx) => tx.version(),
-                StarknetApiTransaction::L1Handler(tx) => tx.version,
-            };
-
             let thin_tx_output = txn
                 .get_transaction_output(transaction_index)
                 .map_err(internal_server_error)?
@@ -519,22 +532,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 .map_err(internal_server_error)?
                 .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
 
-            let msg_hash = match thin_tx_output {
-                papyrus_storage::body::events::ThinTransactionOutput::L1Handler(_) => {
-                    let starknet_api::transaction::Transaction::L1Handler(tx) = tx else {
-                        panic!("tx {} should be L1 handler", transaction_hash);
-                    };
-                    Some(tx.calc_msg_hash())
-                }
-                _ => None,
-            };
-
-            let output = TransactionOutput::from_thin_transaction_output(
-                thin_tx_output,
-                tx_version,
-                events,
-                msg_hash,
-            );
+            let output = stored_transaction_output(&tx, transaction_hash, thin_tx_output, events);
 
             Ok(GeneralTransactionReceipt::TransactionReceipt(TransactionReceipt {
                 finality_status: status.into(),
@@ -562,19 +560,8 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 .iter()
                 .find(|transaction| transaction.transaction_hash() == transaction_hash)
                 .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
-            let starknet_api_output =
-                client_transaction_receipt.into_starknet_api_transaction_output(client_transaction);
-            let msg_hash = match client_transaction {
-                starknet_client::reader::objects::transaction::Transaction::L1Handler(tx) => {
-                    Some(tx.calc_msg_hash())
-                }
-                _ => None,
-            };
-            let output = PendingTransactionOutput::try_from(TransactionOutput::from((
-                starknet_api_output,
-                client_transaction.transaction_version(),
-                msg_hash,
-            )))?;
+            let output =
+                pending_transaction_output(client_transaction, client_transaction_receipt)?;
             Ok(GeneralTransactionReceipt::PendingTransactionReceipt(PendingTransactionReceipt {
                 // ACCEPTED_ON_L2 is the only finality status of a pending transaction.
                 finality_status: PendingTransactionFinalityStatus::AcceptedOnL2,
@@ -605,22 +592,13 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
 
         let block_number = get_accepted_block_number(&txn, block_id)?;
         let state_number = StateNumber::right_after_block(block_number);
-        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
-
-        // The class might be a deprecated class. Search it first in the declared classes and if not
-        // found, search in the deprecated classes.
-        if let Some(class) = state_reader
-            .get_class_definition_at(state_number, &class_hash)
+        txn.get_state_reader()
             .map_err(internal_server_error)?
-        {
-            Ok(GatewayContractClass::Sierra(class.into()))
-        } else {
-            let class = state_reader
-                .get_deprecated_class_definition_at(state_number, &class_hash)
-                .map_err(internal_server_error)?
-                .ok_or_else(|| ErrorObjectOwned::from(CLASS_HASH_NOT_FOUND))?;
-            Ok(GatewayContractClass::Cairo0(class.try_into().map_err(internal_server_error)?))
-        }
+            .get_class_at(state_number, &class_hash)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(CLASS_HASH_NOT_FOUND))?
+            .try_into()
+            .map_err(internal_server_error)
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
@@ -757,10 +735,28 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
         if start_event_index.0.0 <= latest_block_number {
-            for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
-                .map_err(internal_server_error)?
-            {
+            verify_body_not_pruned(&txn, start_event_index.0.0)?;
+            // Without an address, a single first key narrows the events the most. Otherwise, the
+            // blocks without matching events are skipped by their events bloom filters.
+            let events = match (filter.address, filter_single_first_key(&filter)) {
+                (None, Some(first_key)) => {
+                    txn.iter_events_by_first_key(first_key, start_event_index)
+                }
+                // Every block matches a filter without an address and keys, so its bloom filter
+                // isn't read.
+                (None, None) if filter.keys.iter().all(|keys| keys.is_empty()) => {
+                    txn.iter_events(None, start_event_index, to_block_number)
+                }
+                (None, None) => txn.iter_events_by_bloom_filter(
+                    None,
+                    filter.keys.clone(),
+                    start_event_index,
+                    to_block_number,
+                ),
+                _ => txn.iter_events(filter.address, start_event_index, to_block_number),
+            }
+            .map_err(internal_server_error)?;
+            for ((from_address, event_index), content) in events {
                 let block_number = (event_index.0).0;
                 if block_number > to_block_number {
                     break;
@@ -876,50 +872,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
 
     #[instrument(skip(self), level = "debug", err, ret)]
     async fn call(&self, request: CallRequest, block_id: BlockId) -> RpcResult<Vec<StarkFelt>> {
-        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
-        let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
-            Some(client_pending_data_to_execution_pending_data(
-                read_pending_data(&self.pending_data, &txn).await?,
-                self.pending_classes.read().await.clone(),
-            ))
-        } else {
-            None
-        };
-        let block_number = get_accepted_block_number(&txn, block_id)?;
-        let block_not_reverted_validator = BlockNotRevertedValidator::new(block_number, &txn)?;
-        drop(txn);
-        let state_number = StateNumber::right_after_block(block_number);
-        let block_execution_config = self
-            .execution_config
-            .get_execution_config_for_block(block_number)
-            .map_err(|err| {
-                internal_server_error(format!("Failed to get execution config: {}", err))
-            })?
-            .clone();
-        let chain_id = self.chain_id.clone();
-        let reader = self.storage_reader.clone();
-        let contract_address_copy = request.contract_address;
-
-        let res = tokio::task::spawn_blocking(move || {
-            execute_call(
-                reader,
-                maybe_pending_data,
-                &chain_id,
-                state_number,
-                block_number,
-                &contract_address_copy,
-                request.entry_point_selector,
-                request.calldata,
-                &block_execution_config,
-            )
-        })
-        .await
-        .map_err(internal_server_error)?
-        .map_err(execution_error_to_error_object_owned)?;
-
-        block_not_reverted_validator.validate(&self.storage_reader)?;
-
-        Ok(res.retdata.0)
+        self.call_with_state_overrides(request, block_id, None).await
     }
 
     #[instrument(skip(self), level = "debug", err, ret)]
@@ -983,15 +936,23 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         block_id: BlockId,
     ) -> RpcResult<Vec<FeeEstimate>> {
         trace!("Estimating fee of transactions: {:#?}", transactions);
-        let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
+        // Fee estimations don't transfer the fee, so that they don't fail on insufficient balance.
+        let flags = ExecutionFlags {
+            charge_fee: false,
+            ..simulation_flags_to_execution_flags(&simulation_flags)
+        };
 
         let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
 
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
-            Some(client_pending_data_to_execution_pending_data(
-                read_pending_data(&self.pending_data, &storage_txn).await?,
-                self.pending_classes.read().await.clone(),
-            ))
+            Some(
+                read_pending_data_for_fee_estimation(
+                    &self.pending_data,
+                    &self.pending_classes,
+                    &storage_txn,
+                )
+                .await?,
+            )
         } else {
             None
         };
@@ -1013,8 +974,8 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
-
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
+        let class_cache = self.class_cache.clone();
+        let estimate_fee_result = run_execution(self.execution_timeout, move |abort_handle| {
             exec_estimate_fee(
                 executable_txns,
                 &chain_id,
@@ -1023,11 +984,12 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 state_number,
                 block_number,
                 &block_execution_config,
-                validate,
+                flags,
+                Some(class_cache),
+                abort_handle,
             )
         })
-        .await
-        .map_err(internal_server_error)?;
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1041,10 +1003,11 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                     TransactionExecutionError {
                         transaction_index: reverted_tx.index,
                         execution_error: reverted_tx.revert_reason,
+                        revert_trace: reverted_tx.revert_trace,
                     },
                 )))
             }
-            Err(err) => Err(internal_server_error(err)),
+            Err(err) => Err(execution_error_to_error_object_owned(err)),
         }
     }
 
@@ -1055,70 +1018,13 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         transactions: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
     ) -> RpcResult<Vec<SimulatedTransaction>> {
-        trace!("Simulating transactions: {:#?}", transactions);
-        let executable_txns =
-            transactions.into_iter().map(|tx| tx.try_into()).collect::<Result<_, _>>()?;
-
-        let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
-
-        let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
-            Some(client_pending_data_to_execution_pending_data(
-                read_pending_data(&self.pending_data, &storage_txn).await?,
-                self.pending_classes.read().await.clone(),
-            ))
-        } else {
-            None
-        };
-
-        let block_number = get_accepted_block_number(&storage_txn, block_id)?;
-        let block_not_reverted_validator =
-            BlockNotRevertedValidator::new(block_number, &storage_txn)?;
-        drop(storage_txn);
-        let state_number = StateNumber::right_after_block(block_number);
-        let block_execution_config = self
-            .execution_config
-            .get_execution_config_for_block(block_number)
-            .map_err(|err| {
-                internal_server_error(format!("Failed to get execution config: {}", err))
-            })?
-            .clone();
-        let chain_id = self.chain_id.clone();
-        let reader = self.storage_reader.clone();
-
-        let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
-        let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
-
-        let simulation_results = tokio::task::spawn_blocking(move || {
-            exec_simulate_transactions(
-                executable_txns,
-                None,
-                &chain_id,
-                reader,
-                maybe_pending_data,
-                state_number,
-                block_number,
-                &block_execution_config,
-                charge_fee,
-                validate,
-            )
-        })
+        self.simulate_transactions_with_state_overrides(
+            block_id,
+            transactions,
+            simulation_flags,
+            None,
+        )
         .await
-        .map_err(internal_server_error)?
-        .map_err(execution_error_to_error_object_owned)?;
-
-        block_not_reverted_validator.validate(&self.storage_reader)?;
-
-        Ok(simulation_results
-            .into_iter()
-            .map(|simulation_output| SimulatedTransaction {
-                transaction_trace: simulation_output.transaction_trace,
-                fee_estimation: FeeEstimate::from(
-                    simulation_output.gas_price,
-                    simulation_output.fee,
-                    simulation_output.price_unit,
-                ),
-            })
-            .collect())
     }
 
     #[instrument(skip(self), level = "debug", err)]
@@ -1187,6 +1093,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 .get_transaction_idx_by_hash(&transaction_hash)
                 .map_err(internal_server_error)?
                 .ok_or(TRANSACTION_HASH_NOT_FOUND)?;
+            verify_body_not_pruned(&storage_txn, block_number)?;
 
             let block_transactions = storage_txn
                 .get_block_transactions(block_number)
@@ -1230,8 +1137,8 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
-
-        let mut simulation_results = tokio::task::spawn_blocking(move || {
+        let class_cache = self.class_cache.clone();
+        let mut simulation_results = run_execution(self.execution_timeout, move |abort_handle| {
             exec_simulate_transactions(
                 executable_transactions,
                 Some(transaction_hashes),
@@ -1241,20 +1148,18 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 state_number,
                 block_number,
                 &block_execution_config,
-                true,
-                true,
+                ExecutionFlags::default(),
+                Some(class_cache),
+                None,
+                abort_handle,
             )
         })
-        .await
-        .map_err(internal_server_error)?
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
-        Ok(simulation_results
-            .pop()
-            .expect("Should have transaction exeuction result")
-            .transaction_trace)
+        Ok(simulation_results.pop().expect("Should have transaction exeuction result").into())
     }
 
     #[instrument(skip(self), level = "debug", err)]
@@ -1271,6 +1176,9 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         };
 
         let block_number = get_accepted_block_number(&storage_txn, block_id)?;
+        if maybe_client_pending_data.is_none() {
+            verify_body_not_pruned(&storage_txn, block_number)?;
+        }
 
         let block_not_reverted_validator =
             BlockNotRevertedValidator::new(block_number, &storage_txn)?;
@@ -1340,9 +1248,10 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
+        let class_cache = self.class_cache.clone();
         let transaction_hashes_clone = transaction_hashes.clone();
 
-        let simulation_results = tokio::task::spawn_blocking(move || {
+        let simulation_results = run_execution(self.execution_timeout, move |abort_handle| {
             exec_simulate_transactions(
                 executable_txns,
                 Some(transaction_hashes_clone),
@@ -1352,12 +1261,13 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 state_number,
                 block_number,
                 &block_execution_config,
-                true,
-                true,
+                ExecutionFlags::default(),
+                Some(class_cache),
+                None,
+                abort_handle,
             )
         })
-        .await
-        .map_err(internal_server_error)?
+        .await?
         .map_err(execution_error_to_error_object_owned)?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
@@ -1367,7 +1277,7 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             .zip(transaction_hashes)
             .map(|(simulation_output, transaction_hash)| TransactionTraceWithHash {
                 transaction_hash,
-                trace_root: simulation_output.transaction_trace,
+                trace_root: simulation_output.into(),
             })
             .collect())
     }
@@ -1381,10 +1291,14 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
         trace!("Estimating fee of message: {:#?}", message);
         let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
         let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
-            Some(client_pending_data_to_execution_pending_data(
-                read_pending_data(&self.pending_data, &storage_txn).await?,
-                self.pending_classes.read().await.clone(),
-            ))
+            Some(
+                read_pending_data_for_fee_estimation(
+                    &self.pending_data,
+                    &self.pending_classes,
+                    &storage_txn,
+                )
+                .await?,
+            )
         } else {
             None
         };
@@ -1409,8 +1323,8 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             .clone();
         let chain_id = self.chain_id.clone();
         let reader = self.storage_reader.clone();
-
-        let estimate_fee_result = tokio::task::spawn_blocking(move || {
+        let class_cache = self.class_cache.clone();
+        let estimate_fee_result = run_execution(self.execution_timeout, move |abort_handle| {
             exec_estimate_fee(
                 executable_txns,
                 &chain_id,
@@ -1419,11 +1333,12 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
                 state_number,
                 block_number,
                 &block_execution_config,
-                false,
+                ExecutionFlags { validate: false, charge_fee: false },
+                Some(class_cache),
+                abort_handle,
             )
         })
-        .await
-        .map_err(internal_server_error)?;
+        .await?;
 
         block_not_reverted_validator.validate(&self.storage_reader)?;
 
@@ -1445,17 +1360,278 @@ impl JsonRpcServer for JsonRpcServerV0_6Impl {
             // Error in the execution of the contract.
             Ok(Err(reverted_tx)) => Err(JsonRpcError::<ContractError>::from(ContractError {
                 revert_error: reverted_tx.revert_reason,
+                revert_trace: reverted_tx.revert_trace,
             })
             .into()),
-            // Internal error during the execution.
-            Err(err) => Err(internal_server_error(err)),
+            Err(ExecutionError::TransactionExecutionError {
+                execution_error,
+                revert_trace,
+                ..
+            }) => Err(JsonRpcError::<ContractError>::from(ContractError {
+                revert_error: execution_error,
+                revert_trace,
+            })
+            .into()),
+            Err(err) => Err(execution_error_to_error_object_owned(err)),
         }
     }
 }
 
+impl JsonRpcServerV0_6Impl {
+    // Returns the block with the receipts of its transactions.
+    pub(crate) async fn get_block_with_receipts(
+        &self,
+        block_id: BlockId,
+    ) -> RpcResult<BlockWithReceipts> {
+        verify_storage_scope(&self.storage_reader)?;
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        if let BlockId::Tag(Tag::Pending) = block_id {
+            let block = read_pending_data(&self.pending_data, &txn).await?.block;
+            let pending_block_header = PendingBlockHeader {
+                parent_hash: block.parent_block_hash,
+                sequencer_address: block.sequencer_address,
+                timestamp: block.timestamp,
+                l1_gas_price: ResourcePrice {
+                    price_in_wei: block.eth_l1_gas_price,
+                    price_in_fri: block.strk_l1_gas_price,
+                },
+                starknet_version: block.starknet_version,
+            };
+            let header = GeneralBlockHeader::PendingBlockHeader(pending_block_header);
+            // The receipts of the pending block are in the order of its transactions.
+            let transactions = block
+                .transactions
+                .iter()
+                .zip(block.transaction_receipts)
+                .map(|(client_transaction, client_transaction_receipt)| {
+                    let starknet_api_transaction: StarknetApiTransaction =
+                        client_transaction.clone().try_into().map_err(internal_server_error)?;
+                    Ok(TransactionWithReceipt {
+                        transaction: starknet_api_transaction
+                            .try_into()
+                            .map_err(internal_server_error)?,
+                        receipt: GeneralTransactionReceipt::PendingTransactionReceipt(
+                            PendingTransactionReceipt {
+                                // ACCEPTED_ON_L2 is the only finality status of a pending
+                                // transaction.
+                                finality_status: PendingTransactionFinalityStatus::AcceptedOnL2,
+                                transaction_hash: client_transaction.transaction_hash(),
+                                output: pending_transaction_output(
+                                    client_transaction,
+                                    client_transaction_receipt,
+                                )?,
+                            },
+                        ),
+                    })
+                })
+                .collect::<Result<Vec<_>, ErrorObjectOwned>>()?;
+            return Ok(BlockWithReceipts { status: None, header, transactions });
+        }
+
+        // The body and the receipts are read in the same storage transaction, so that they are
+        // consistent with each other.
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        verify_body_not_pruned(&txn, block_number)?;
+        let status = get_block_status(&txn, block_number)?;
+        let (header, starknet_version) = get_block_header_by_number(&txn, block_number)?;
+        let block_hash = header.block_hash;
+        let transactions = txn
+            .get_block_transactions(block_number)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+        let transaction_hashes = get_block_tx_hashes_by_number(&txn, block_number)?;
+        let thin_transaction_outputs = txn
+            .get_block_transaction_outputs(block_number)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+
+        let transactions_with_receipts = transactions
+            .into_iter()
+            .zip(transaction_hashes)
+            .zip(thin_transaction_outputs)
+            .enumerate()
+            .map(|(offset, ((transaction, transaction_hash), thin_tx_output))| {
+                let events = txn
+                    .get_transaction_events(TransactionIndex(
+                        block_number,
+                        TransactionOffsetInBlock(offset),
+                    ))
+                    .map_err(internal_server_error)?
+                    .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+                let output = stored_transaction_output(
+                    &transaction,
+                    transaction_hash,
+                    thin_tx_output,
+                    events,
+                );
+                Ok(TransactionWithReceipt {
+                    transaction: transaction.try_into()?,
+                    receipt: GeneralTransactionReceipt::TransactionReceipt(TransactionReceipt {
+                        finality_status: status.into(),
+                        transaction_hash,
+                        block_hash,
+                        block_number,
+                        output,
+                    }),
+                })
+            })
+            .collect::<Result<Vec<_>, ErrorObjectOwned>>()?;
+
+        Ok(BlockWithReceipts {
+            status: Some(status),
+            header: GeneralBlockHeader::BlockHeader((header, starknet_version).into()),
+            transactions: transactions_with_receipts,
+        })
+    }
+
+    // Executes a call as if the given state overrides were applied on top of the state.
+    pub(crate) async fn call_with_state_overrides(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        state_overrides: Option<StateOverrides>,
+    ) -> RpcResult<Vec<StarkFelt>> {
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
+            Some(client_pending_data_to_execution_pending_data(
+                read_pending_data(&self.pending_data, &txn).await?,
+                self.pending_classes.read().await.clone(),
+            ))
+        } else {
+            None
+        };
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let block_not_reverted_validator = BlockNotRevertedValidator::new(block_number, &txn)?;
+        drop(txn);
+        let state_number = StateNumber::right_after_block(block_number);
+        let block_execution_config = self
+            .execution_config
+            .get_execution_config_for_block(block_number)
+            .map_err(|err| {
+                internal_server_error(format!("Failed to get execution config: {}", err))
+            })?
+            .clone();
+        let chain_id = self.chain_id.clone();
+        let reader = self.storage_reader.clone();
+        let class_cache = self.class_cache.clone();
+        let contract_address_copy = request.contract_address;
+
+        let res = run_execution(self.execution_timeout, move |abort_handle| {
+            execute_call(
+                reader,
+                maybe_pending_data,
+                &chain_id,
+                state_number,
+                block_number,
+                &contract_address_copy,
+                request.entry_point_selector,
+                request.calldata,
+                &block_execution_config,
+                Some(class_cache),
+                state_overrides,
+                abort_handle,
+            )
+        })
+        .await?
+        .map_err(execution_error_to_error_object_owned)?;
+
+        block_not_reverted_validator.validate(&self.storage_reader)?;
+
+        Ok(res.retdata.0)
+    }
+
+    // Simulates transactions as if the given state overrides were applied on top of the state.
+    pub(crate) async fn simulate_transactions_with_state_overrides(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+        state_overrides: Option<StateOverrides>,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        trace!("Simulating transactions: {:#?}", transactions);
+        let executable_txns =
+            transactions.into_iter().map(|tx| tx.try_into()).collect::<Result<_, _>>()?;
+
+        let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+
+        let maybe_pending_data = if let BlockId::Tag(Tag::Pending) = block_id {
+            Some(
+                read_pending_data_for_fee_estimation(
+                    &self.pending_data,
+                    &self.pending_classes,
+                    &storage_txn,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+        let block_number = get_accepted_block_number(&storage_txn, block_id)?;
+        let block_not_reverted_validator =
+            BlockNotRevertedValidator::new(block_number, &storage_txn)?;
+        drop(storage_txn);
+        let state_number = StateNumber::right_after_block(block_number);
+        let block_execution_config = self
+            .execution_config
+            .get_execution_config_for_block(block_number)
+            .map_err(|err| {
+                internal_server_error(format!("Failed to get execution config: {}", err))
+            })?
+            .clone();
+        let chain_id = self.chain_id.clone();
+        let reader = self.storage_reader.clone();
+        let class_cache = self.class_cache.clone();
+        let flags = simulation_flags_to_execution_flags(&simulation_flags);
+
+        let simulation_results = run_execution(self.execution_timeout, move |abort_handle| {
+            exec_simulate_transactions(
+                executable_txns,
+                None,
+                &chain_id,
+                reader,
+                maybe_pending_data,
+                state_number,
+                block_number,
+                &block_execution_config,
+                flags,
+                Some(class_cache),
+                state_overrides,
+                abort_handle,
+            )
+        })
+        .await?
+        .map_err(execution_error_to_error_object_owned)?;
+
+        block_not_reverted_validator.validate(&self.storage_reader)?;
+
+        Ok(simulation_results
+            .into_iter()
+            .map(|simulation_output| SimulatedTransaction {
+                fee_estimation: FeeEstimate::from(
+                    simulation_output.gas_price,
+                    simulation_output.fee,
+                    simulation_output.price_unit,
+                ),
+                transaction_trace: simulation_output.into(),
+            })
+            .collect())
+    }
+}
+
 async fn read_pending_data<Mode: TransactionKind>(
     pending_data: &Arc<RwLock<PendingData>>,
     txn: &StorageTxn<'_, Mode>,
+) -> RpcResult<PendingData> {
+    pending_data_on_latest_block(&*pending_data.read().await, txn)
+}
+
+// Returns the given pending data if it extends the latest block, and otherwise an empty pending
+// block on top of the latest block.
+fn pending_data_on_latest_block<Mode: TransactionKind>(
+    pending_data: &PendingData,
+    txn: &StorageTxn<'_, Mode>,
 ) -> RpcResult<PendingData> {
     let (latest_header, starknet_version) = match get_latest_block_number(txn)? {
         Some(latest_block_number) => get_block_header_by_number(txn, latest_block_number)?,
@@ -1469,9 +1645,8 @@ async fn read_pending_data<Mode: TransactionKind>(
             StarknetVersion::default(),
         ),
     };
-    let pending_data = &pending_data.read().await;
     if pending_data.block.parent_block_hash == latest_header.block_hash {
-        Ok((*pending_data).clone())
+        Ok(pending_data.clone())
     } else {
         Ok(PendingData {
             block: PendingBlock {
@@ -1491,6 +1666,83 @@ async fn read_pending_data<Mode: TransactionKind>(
     }
 }
 
+// Returns the pending data that fees should be estimated on. If the pending block doesn't extend
+// the latest block because the node hasn't synced the pending block's parent yet, the pending block
+// still holds the most recent L1 gas prices, so they're used instead of the latest block's prices.
+async fn read_pending_data_for_fee_estimation<Mode: TransactionKind>(
+    pending_data: &Arc<RwLock<PendingData>>,
+    pending_classes: &Arc<RwLock<PendingClasses>>,
+    txn: &StorageTxn<'_, Mode>,
+) -> RpcResult<ExecutionPendingData> {
+    // The pending data is read once, so the gas prices are taken from the same pending block that
+    // is checked against the latest block.
+    let pending_data = pending_data.read().await.clone();
+    let mut execution_pending_data = client_pending_data_to_execution_pending_data(
+        pending_data_on_latest_block(&pending_data, txn)?,
+        pending_classes.read().await.clone(),
+    );
+    if pending_data.block.timestamp > execution_pending_data.timestamp {
+        execution_pending_data.eth_l1_gas_price = pending_data.block.eth_l1_gas_price;
+        execution_pending_data.strk_l1_gas_price = pending_data.block.strk_l1_gas_price;
+    }
+    Ok(execution_pending_data)
+}
+
+// Returns the receipt output of a stored transaction from its thin output and its events.
+fn stored_transaction_output(
+    transaction: &StarknetApiTransaction,
+    transaction_hash: TransactionHash,
+    thin_tx_output: ThinTransactionOutput,
+    events: Vec<starknet_api::transaction::Event>,
+) -> TransactionOutput {
+    // TODO: Add version function to transaction in SN_API.
+    let tx_version = match transaction {
+        StarknetApiTransaction::Declare(tx) => tx.version(),
+        StarknetApiTransaction::Deploy(tx) => tx.version,
+        StarknetApiTransaction::DeployAccount(tx) => tx.version(),
+        StarknetApiTransaction::Invoke(tx) => tx.version(),
+        StarknetApiTransaction::L1Handler(tx) => tx.version,
+    };
+
+    let msg_hash = match thin_tx_output {
+        ThinTransactionOutput::L1Handler(_) => {
+            let StarknetApiTransaction::L1Handler(tx) = transaction else {
+                panic!("tx {} should be L1 handler", transaction_hash);
+            };
+            Some(tx.calc_msg_hash())
+        }
+        _ => None,
+    };
+
+    TransactionOutput::from_thin_transaction_output(thin_tx_output, tx_version, events, msg_hash)
+}
+
+// Returns the receipt output of a transaction in the pending block.
+fn pending_transaction_output(
+    client_transaction: &ClientTransaction,
+    client_transaction_receipt: ClientTransactionReceipt,
+) -> RpcResult<PendingTransactionOutput> {
+    let starknet_api_output =
+        client_transaction_receipt.into_starknet_api_transaction_output(client_transaction);
+    let msg_hash = match client_transaction {
+        ClientTransaction::L1Handler(tx) => Some(tx.calc_msg_hash()),
+        _ => None,
+    };
+    Ok(PendingTransactionOutput::try_from(TransactionOutput::from((
+        starknet_api_output,
+        client_transaction.transaction_version(),
+        msg_hash,
+    )))?)
+}
+
+// Returns the first key of the filtered events if the filter allows only one.
+fn filter_single_first_key(filter: &EventFilter) -> Option<EventKey> {
+    match filter.keys.first() {
+        Some(first_keys) if first_keys.len() == 1 => first_keys.iter().next().cloned(),
+        _ => None,
+    }
+}
+
 fn do_event_keys_match_filter(event_content: &EventContent, filter: &EventFilter) -> bool {
     filter.keys.iter().enumerate().all(|(i, keys)| {
         event_content.keys.len() > i && (keys.is_empty() || keys.contains(&event_content.keys[i]))
@@ -1501,6 +1753,8 @@ impl JsonRpcServerImpl for JsonRpcServerV0_6Impl {
     fn new(
         chain_id: ChainId,
         execution_config: ExecutionConfigByBlock,
+        class_cache: ContractClassCache,
+        execution_timeout: Duration,
         storage_reader: StorageReader,
         max_events_chunk_size: usize,
         max_events_keys: usize,
@@ -1513,6 +1767,8 @@ impl JsonRpcServerImpl for JsonRpcServerV0_6Impl {
         Self {
             chain_id,
             execution_config,
+            class_cache,
+            execution_timeout,
             storage_reader,
             max_events_chunk_size,
             max_events_keys,
@@ -7,8 +7,13 @@ use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::types::ErrorObjectOwned;
 use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_common::BlockHashAndNumber;
-use papyrus_execution::objects::{PriceUnit, TransactionTrace};
-use papyrus_execution::{ExecutableTransactionInput, ExecutionError};
+use papyrus_execution::objects::{
+    PriceUnit,
+    TransactionSimulationOutput,
+    TransactionTrace as ExecutionTransactionTrace,
+};
+pub use papyrus_execution::SimulationFlag;
+use papyrus_execution::{error_message_with_sources, ExecutableTransactionInput, ExecutionError};
 use papyrus_proc_macros::versioned_rpc;
 use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::db::serialization::StorageSerdeError;
@@ -34,11 +39,12 @@ use super::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use super::error::{
     ContractError,
     JsonRpcError,
+    TransactionExecutionError,
     BLOCK_NOT_FOUND,
     CONTRACT_NOT_FOUND,
     INVALID_CONTINUATION_TOKEN,
 };
-use super::state::{ContractClass, StateUpdate};
+use super::state::{ContractClass, StateUpdate, ThinStateDiff};
 use super::transaction::{
     DeployAccountTransaction,
     DeployAccountTransactionV1,
@@ -57,11 +63,14 @@ use super::transaction::{
 };
 use super::write_api_result::{AddDeclareOkResult, AddDeployAccountOkResult, AddInvokeOkResult};
 use crate::api::{BlockId, CallRequest};
+use crate::compression_utils::MAX_DECOMPRESSED_PROGRAM_SIZE;
 use crate::syncing_state::SyncingState;
-use crate::{internal_server_error, ContinuationTokenAsStruct};
+use crate::{internal_server_error, internal_server_error_with_msg, ContinuationTokenAsStruct};
 
 pub mod api_impl;
 #[cfg(test)]
+mod spec_params_test;
+#[cfg(test)]
 mod test;
 
 #[versioned_rpc("V0_6")]
@@ -319,13 +328,6 @@ pub struct SimulatedTransaction {
     pub fee_estimation: FeeEstimate,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum SimulationFlag {
-    SkipValidate,
-    SkipFeeCharge,
-}
-
 impl TryFrom<BroadcastedTransaction> for ExecutableTransactionInput {
     type Error = ErrorObjectOwned;
     fn try_from(value: BroadcastedTransaction) -> Result<Self, Self::Error> {
@@ -468,7 +470,7 @@ impl TryFrom<BroadcastedDeclareTransaction> for ExecutableTransactionInput {
     }
 }
 
-fn user_deprecated_contract_class_to_sn_api(
+pub(crate) fn user_deprecated_contract_class_to_sn_api(
     value: starknet_client::writer::objects::transaction::DeprecatedContractClass,
 ) -> Result<starknet_api::deprecated_contract_class::ContractClass, ErrorObjectOwned> {
     Ok(starknet_api::deprecated_contract_class::ContractClass {
@@ -605,12 +607,28 @@ pub(crate) fn execution_error_to_error_object_owned(err: ExecutionError) -> Erro
             );
             BLOCK_NOT_FOUND.into()
         }
-        ExecutionError::ContractError(blockifier_err) => {
-            let contract_err = ContractError { revert_error: blockifier_err.to_string() };
+        ExecutionError::ContractError { error, revert_trace } => {
+            let contract_err = ContractError {
+                revert_error: error_message_with_sources(error.as_ref()),
+                revert_trace,
+            };
             let rpc_err: JsonRpcError<ContractError> = contract_err.into();
             rpc_err.into()
         }
         ExecutionError::ContractNotFound { .. } => CONTRACT_NOT_FOUND.into(),
+        ExecutionError::TransactionExecutionError {
+            transaction_index,
+            execution_error,
+            revert_trace,
+        } => {
+            let rpc_err: JsonRpcError<TransactionExecutionError> =
+                TransactionExecutionError { transaction_index, execution_error, revert_trace }
+                    .into();
+            rpc_err.into()
+        }
+        ExecutionError::ExecutionAborted
+        | ExecutionError::SimulationSessionBusy
+        | ExecutionError::SimulationSessionFailed => internal_server_error_with_msg(err),
         _ => internal_server_error(err),
     }
 }
@@ -621,12 +639,36 @@ pub(crate) fn decompress_program(
     base64::decode(base64_compressed_program).map_err(internal_server_error)?;
     let compressed_data =
         base64::decode(base64_compressed_program).map_err(internal_server_error)?;
-    let mut decoder = GzDecoder::new(compressed_data.as_slice());
+    // Reads one byte more than the limit to tell if the program is too large.
+    let mut decoder =
+        GzDecoder::new(compressed_data.as_slice()).take(MAX_DECOMPRESSED_PROGRAM_SIZE as u64 + 1);
     let mut decompressed = Vec::new();
     decoder.read_to_end(&mut decompressed).map_err(internal_server_error)?;
+    if decompressed.len() > MAX_DECOMPRESSED_PROGRAM_SIZE {
+        return Err(internal_server_error(format!(
+            "The decompressed program is larger than {MAX_DECOMPRESSED_PROGRAM_SIZE} bytes."
+        )));
+    }
     serde_json::from_reader(decompressed.as_slice()).map_err(internal_server_error)
 }
 
+/// The execution trace of a transaction, along with the state diff induced by the transaction.
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+pub struct TransactionTrace {
+    #[serde(flatten)]
+    pub trace: ExecutionTransactionTrace,
+    pub state_diff: ThinStateDiff,
+}
+
+impl From<TransactionSimulationOutput> for TransactionTrace {
+    fn from(simulation_output: TransactionSimulationOutput) -> Self {
+        Self {
+            trace: simulation_output.transaction_trace,
+            state_diff: simulation_output.induced_state_diff.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
 pub struct TransactionTraceWithHash {
     pub transaction_hash: TransactionHash,
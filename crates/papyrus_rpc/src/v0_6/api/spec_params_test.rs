@@ -0,0 +1,187 @@
+//! Checks that the params the API accepts match the spec, by validating params serialized the way
+//! the API deserializes them against the schemas of the method params in the vendored spec.
+use std::collections::HashSet;
+
+use serde::Serialize;
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::ContractAddress;
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::transaction::EventKey;
+use test_utils::{get_rng, GetTestInstance, RANDOM_INSTANCES_PER_TEST};
+
+use super::{ContinuationToken, EventFilter, SimulationFlag};
+use crate::api::{BlockHashOrNumber, BlockId, Tag};
+use crate::test_utils::{get_starknet_spec_api_schema_for_method_param, validate_schema, SpecFile};
+use crate::v0_6::broadcasted_transaction::{
+    BroadcastedDeclareTransaction,
+    BroadcastedDeclareV2Transaction,
+    BroadcastedDeclareV3Transaction,
+    BroadcastedTransaction,
+};
+use crate::v0_6::transaction::{
+    DeployAccountTransaction,
+    InvokeTransaction,
+    InvokeTransactionV1,
+    InvokeTransactionV3,
+    TypedDeployAccountTransaction,
+    TypedInvokeTransaction,
+};
+use crate::version_config::VERSION_0_6 as VERSION;
+
+// Asserts that every one of the given params is valid as the param at the given index of the given
+// method.
+fn assert_params_fit_spec<Param: Serialize>(
+    spec_file: SpecFile,
+    method: &str,
+    param_index: usize,
+    params: impl IntoIterator<Item = Param>,
+) {
+    let schema = get_starknet_spec_api_schema_for_method_param(
+        &[(spec_file, &[(method, param_index)])],
+        &VERSION,
+    );
+    for param in params {
+        let param = serde_json::to_value(param).unwrap();
+        assert!(
+            validate_schema(&schema, &param),
+            "Param {param_index} of {method} doesn't fit the spec: {param}."
+        );
+    }
+}
+
+fn block_ids() -> Vec<BlockId> {
+    vec![
+        BlockId::HashOrNumber(BlockHashOrNumber::Hash(BlockHash(stark_felt!("0x1234")))),
+        BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(5))),
+        BlockId::Tag(Tag::Latest),
+        BlockId::Tag(Tag::Pending),
+    ]
+}
+
+// Returns random transactions of every type that can be broadcasted.
+fn broadcasted_transactions() -> Vec<BroadcastedTransaction> {
+    let mut rng = get_rng();
+    (0..RANDOM_INSTANCES_PER_TEST)
+        .flat_map(|_| {
+            [
+                BroadcastedTransaction::Invoke(InvokeTransaction::Version1(
+                    InvokeTransactionV1::get_test_instance(&mut rng),
+                )),
+                BroadcastedTransaction::Invoke(InvokeTransaction::Version3(
+                    InvokeTransactionV3::get_test_instance(&mut rng),
+                )),
+                BroadcastedTransaction::DeployAccount(DeployAccountTransaction::get_test_instance(
+                    &mut rng,
+                )),
+                BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(
+                    BroadcastedDeclareV2Transaction::get_test_instance(&mut rng),
+                )),
+                BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(
+                    BroadcastedDeclareV3Transaction::get_test_instance(&mut rng),
+                )),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn block_id_fits_spec() {
+    assert_params_fit_spec(
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getBlockWithTxHashes",
+        0,
+        block_ids(),
+    );
+}
+
+#[test]
+fn event_filter_fits_spec() {
+    let full_filter = EventFilter {
+        from_block: Some(BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))),
+        to_block: Some(BlockId::Tag(Tag::Latest)),
+        continuation_token: Some(ContinuationToken("continuation token".to_owned())),
+        chunk_size: 10,
+        address: Some(ContractAddress::default()),
+        keys: vec![HashSet::from([EventKey(stark_felt!("0x1")), EventKey(stark_felt!("0x2"))])],
+    };
+    let minimal_filter = EventFilter { chunk_size: 10, ..Default::default() };
+    assert_params_fit_spec(
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_getEvents",
+        0,
+        [full_filter, minimal_filter],
+    );
+}
+
+#[test]
+fn estimate_fee_params_fit_spec() {
+    assert_params_fit_spec(
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_estimateFee",
+        0,
+        [broadcasted_transactions()],
+    );
+    // Only validation can be skipped when estimating fees.
+    assert_params_fit_spec(
+        SpecFile::StarknetApiOpenrpc,
+        "starknet_estimateFee",
+        1,
+        [vec![], vec![SimulationFlag::SkipValidate]],
+    );
+    assert_params_fit_spec(SpecFile::StarknetApiOpenrpc, "starknet_estimateFee", 2, block_ids());
+}
+
+#[test]
+fn simulate_transactions_params_fit_spec() {
+    assert_params_fit_spec(SpecFile::TraceApi, "starknet_simulateTransactions", 0, block_ids());
+    assert_params_fit_spec(
+        SpecFile::TraceApi,
+        "starknet_simulateTransactions",
+        1,
+        [broadcasted_transactions()],
+    );
+    assert_params_fit_spec(
+        SpecFile::TraceApi,
+        "starknet_simulateTransactions",
+        2,
+        [vec![], vec![SimulationFlag::SkipValidate, SimulationFlag::SkipFeeCharge]],
+    );
+}
+
+#[test]
+fn write_api_params_fit_spec() {
+    let mut rng = get_rng();
+    assert_params_fit_spec(
+        SpecFile::WriteApi,
+        "starknet_addInvokeTransaction",
+        0,
+        [
+            TypedInvokeTransaction::Invoke(InvokeTransaction::Version1(
+                InvokeTransactionV1::get_test_instance(&mut rng),
+            )),
+            TypedInvokeTransaction::Invoke(InvokeTransaction::Version3(
+                InvokeTransactionV3::get_test_instance(&mut rng),
+            )),
+        ],
+    );
+    assert_params_fit_spec(
+        SpecFile::WriteApi,
+        "starknet_addDeployAccountTransaction",
+        0,
+        [TypedDeployAccountTransaction::get_test_instance(&mut rng)],
+    );
+    assert_params_fit_spec(
+        SpecFile::WriteApi,
+        "starknet_addDeclareTransaction",
+        0,
+        [
+            BroadcastedDeclareTransaction::V2(BroadcastedDeclareV2Transaction::get_test_instance(
+                &mut rng,
+            )),
+            BroadcastedDeclareTransaction::V3(BroadcastedDeclareV3Transaction::get_test_instance(
+                &mut rng,
+            )),
+        ],
+    );
+}
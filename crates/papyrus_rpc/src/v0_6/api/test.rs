@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
 use std::iter;
 use std::net::SocketAddr;
@@ -14,6 +14,7 @@ use jsonschema::JSONSchema;
 use lazy_static::lazy_static;
 use mockall::predicate::eq;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClassesTrait};
+use papyrus_common::raw_transaction::RawTransaction;
 use papyrus_common::BlockHashAndNumber;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
 use papyrus_storage::body::events::EventIndex;
@@ -27,8 +28,10 @@ use rand::{random, RngCore};
 use rand_chacha::ChaCha8Rng;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use starknet_api::block::{
     Block as StarknetApiBlock,
+    BlockBody,
     BlockHash,
     BlockHeader,
     BlockNumber,
@@ -97,7 +100,14 @@ use test_utils::{
 };
 
 use super::super::api::EventsChunk;
-use super::super::block::{Block, GeneralBlockHeader, PendingBlockHeader, ResourcePrice};
+use super::super::block::{
+    Block,
+    BlockWithReceipts,
+    GeneralBlockHeader,
+    PendingBlockHeader,
+    ResourcePrice,
+};
+use super::super::block_with_receipts::BlockWithReceiptsJsonRpcV0_6Server;
 use super::super::broadcasted_transaction::BroadcastedDeclareTransaction;
 use super::super::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use super::super::error::{
@@ -115,6 +125,7 @@ use super::super::error::{
     TOO_MANY_KEYS_IN_FILTER,
     TRANSACTION_HASH_NOT_FOUND,
 };
+use super::super::raw_transactions::RawTransactionsJsonRpcV0_6Server;
 use super::super::state::{
     AcceptedStateUpdate,
     ClassHashes,
@@ -143,6 +154,7 @@ use super::super::transaction::{
     TransactionReceipt,
     TransactionStatus,
     TransactionWithHash,
+    TransactionWithReceipt,
     Transactions,
     TypedDeployAccountTransaction,
     TypedInvokeTransaction,
@@ -159,15 +171,18 @@ use crate::syncing_state::SyncStatus;
 use crate::test_utils::{
     call_api_then_assert_and_validate_schema_for_err,
     call_api_then_assert_and_validate_schema_for_result,
+    generate_client_transaction_and_rpc_transaction,
     get_method_names_from_spec,
     get_starknet_spec_api_schema_for_components,
     get_starknet_spec_api_schema_for_method_results,
     get_test_highest_block,
+    get_test_new_block_sender,
     get_test_pending_classes,
     get_test_pending_data,
     get_test_rpc_config,
     get_test_rpc_server_and_storage_writer,
     get_test_rpc_server_and_storage_writer_from_params,
+    get_test_rpc_server_impl_and_storage_writer_from_params,
     method_name_to_spec_method_name,
     raw_call,
     validate_schema,
@@ -255,7 +270,8 @@ async fn block_hash_and_number() {
     )
     .await;
 
-    // Add a state diff to the block and check that we get the block.
+    // Add a state diff to the block and check that there are still no blocks, since the body
+    // of the block is missing.
     storage_writer
         .begin_rw_txn()
         .unwrap()
@@ -267,6 +283,24 @@ async fn block_hash_and_number() {
         .unwrap()
         .commit()
         .unwrap();
+    call_api_then_assert_and_validate_schema_for_err::<_, BlockHashAndNumber>(
+        &module,
+        method_name,
+        vec![],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &NO_BLOCKS.into(),
+    )
+    .await;
+
+    // Add the body of the block and check that we get the block.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(block.header.block_number, BlockBody::default())
+        .unwrap()
+        .commit()
+        .unwrap();
     call_api_then_assert_and_validate_schema_for_result(
         &module,
         method_name,
@@ -319,7 +353,8 @@ async fn block_number() {
     )
     .await;
 
-    // Add a state diff to the block and check that we get the block.
+    // Add a state diff to the block and check that there are still no blocks, since the body
+    // of the block is missing.
     storage_writer
         .begin_rw_txn()
         .unwrap()
@@ -331,6 +366,24 @@ async fn block_number() {
         .unwrap()
         .commit()
         .unwrap();
+    call_api_then_assert_and_validate_schema_for_err::<_, BlockNumber>(
+        &module,
+        method_name,
+        vec![],
+        &VERSION,
+        SpecFile::StarknetApiOpenrpc,
+        &expected_err,
+    )
+    .await;
+
+    // Add the body of the block and check that we get the block.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .commit()
+        .unwrap();
     call_api_then_assert_and_validate_schema_for_result(
         &module,
         method_name,
@@ -848,6 +901,8 @@ async fn get_class() {
         .unwrap()
         .update_starknet_version(&parent_header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(parent_header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(
             parent_header.block_number,
             starknet_api::state::StateDiff::default(),
@@ -856,6 +911,8 @@ async fn get_class() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1237,6 +1294,162 @@ async fn get_transaction_receipt() {
     .await;
 }
 
+#[tokio::test]
+async fn get_block_w_receipts() {
+    let method_name = "starknet_V0_6_getBlockWithReceipts";
+    let pending_data = get_test_pending_data();
+    let (server, mut storage_writer) = get_test_rpc_server_impl_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, Some(pending_data.clone()), None, None);
+    // The methods the expected block is read with are served by the same server.
+    let mut module = crate::api::JsonRpcServerImpl::into_rpc_module(server.clone());
+    module.merge(BlockWithReceiptsJsonRpcV0_6Server::into_rpc(server)).unwrap();
+    let block = get_test_block(3, None, None, None);
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block.header.block_number, &block.header)
+        .unwrap()
+        .update_starknet_version(&block.header.block_number, &StarknetVersion::default())
+        .unwrap()
+        .append_body(block.header.block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The transactions and the receipts should be the ones returned by getBlockWithTxs and
+    // getTransactionReceipt.
+    let block_with_txs = module
+        .call::<_, Block>("starknet_V0_6_getBlockWithTxs", [BlockId::Tag(Tag::Latest)])
+        .await
+        .unwrap();
+    let Transactions::Full(transactions) = block_with_txs.transactions else {
+        panic!("Unexpected transactions type. Expected full transactions.");
+    };
+    let mut expected_transactions = vec![];
+    for transaction in transactions {
+        let receipt = module
+            .call::<_, TransactionReceipt>(
+                "starknet_V0_6_getTransactionReceipt",
+                [transaction.transaction_hash],
+            )
+            .await
+            .unwrap();
+        expected_transactions.push(TransactionWithReceipt {
+            transaction: transaction.transaction,
+            receipt: GeneralTransactionReceipt::TransactionReceipt(receipt),
+        });
+    }
+    let expected_block = BlockWithReceipts {
+        status: block_with_txs.status,
+        header: block_with_txs.header,
+        transactions: expected_transactions,
+    };
+    let res = module
+        .call::<_, BlockWithReceipts>(
+            method_name,
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(block.header.block_number))],
+        )
+        .await;
+    // The returned jsons of some transaction outputs are the same, so we compare the serialized
+    // data (see get_transaction_receipt).
+    assert_eq!(
+        serde_json::to_value(res.unwrap()).unwrap(),
+        serde_json::to_value(&expected_block).unwrap(),
+    );
+
+    // Get pending block.
+    let mut rng = get_rng();
+    let (client_transaction, client_transaction_receipt, expected_receipt) =
+        generate_client_transaction_client_receipt_and_rpc_receipt(&mut rng);
+    {
+        let pending_block = &mut pending_data.write().await.block;
+        pending_block.parent_block_hash = block.header.block_hash;
+        pending_block.transactions.push(client_transaction);
+        pending_block.transaction_receipts.push(client_transaction_receipt);
+    }
+    let pending_block_with_txs = module
+        .call::<_, Block>("starknet_V0_6_getBlockWithTxs", [BlockId::Tag(Tag::Pending)])
+        .await
+        .unwrap();
+    let Transactions::Full(mut pending_transactions) = pending_block_with_txs.transactions else {
+        panic!("Unexpected transactions type. Expected full transactions.");
+    };
+    let expected_pending_block = BlockWithReceipts {
+        status: None,
+        header: pending_block_with_txs.header,
+        transactions: vec![TransactionWithReceipt {
+            transaction: pending_transactions.remove(0).transaction,
+            receipt: GeneralTransactionReceipt::PendingTransactionReceipt(expected_receipt),
+        }],
+    };
+    let res = module.call::<_, BlockWithReceipts>(method_name, [BlockId::Tag(Tag::Pending)]).await;
+    assert_eq!(
+        serde_json::to_value(res.unwrap()).unwrap(),
+        serde_json::to_value(&expected_pending_block).unwrap(),
+    );
+
+    // Ask for an invalid block number.
+    let err = module
+        .call::<_, BlockWithReceipts>(
+            method_name,
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))],
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == BLOCK_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn get_raw_transaction() {
+    let method_name = "starknet_V0_6_papyrusGetRawTransaction";
+    let (server, mut storage_writer) = get_test_rpc_server_impl_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, None, None, None);
+    let module = RawTransactionsJsonRpcV0_6Server::into_rpc(server);
+    let block = get_test_block(2, None, None, None);
+    let raw_transaction = RawTransaction {
+        transaction: json!({"type": "INVOKE_FUNCTION", "version": "0x4"}),
+        receipt: json!({"transaction_index": 1, "unknown_field": "0x1"}),
+    };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block.header.block_number, &block.header)
+        .unwrap()
+        .append_body(block.header.block_number, block.body.clone())
+        .unwrap()
+        .append_raw_transactions(
+            block.header.block_number,
+            BTreeMap::from([(TransactionOffsetInBlock(1), raw_transaction.clone())]),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let res = module
+        .call::<_, Option<RawTransaction>>(method_name, [block.body.transaction_hashes[1]])
+        .await
+        .unwrap();
+    assert_eq!(res, Some(raw_transaction));
+
+    // The node models all the data of the other transaction.
+    let res = module
+        .call::<_, Option<RawTransaction>>(method_name, [block.body.transaction_hashes[0]])
+        .await
+        .unwrap();
+    assert_eq!(res, None);
+
+    // Ask for an unknown transaction.
+    let (_, res) = raw_call::<_, _, Option<RawTransaction>>(
+        &module,
+        method_name,
+        &[TransactionHash(StarkHash::from(1_u8))],
+    )
+    .await;
+    assert_eq!(res.unwrap_err(), TRANSACTION_HASH_NOT_FOUND.into());
+}
+
 #[tokio::test]
 async fn get_class_at() {
     let method_name = "starknet_V0_6_getClassAt";
@@ -1268,6 +1481,8 @@ async fn get_class_at() {
         .unwrap()
         .update_starknet_version(&parent_header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(parent_header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(
             parent_header.block_number,
             starknet_api::state::StateDiff::default(),
@@ -1276,6 +1491,8 @@ async fn get_class_at() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1443,6 +1660,8 @@ async fn get_class_hash_at() {
         .unwrap()
         .update_starknet_version(&header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1599,6 +1818,8 @@ async fn get_nonce() {
         .unwrap()
         .update_starknet_version(&header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1737,6 +1958,8 @@ async fn get_storage_at() {
         .unwrap()
         .update_starknet_version(&header.block_number, &StarknetVersion::default())
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, diff.clone(), IndexMap::new())
         .unwrap()
         .commit()
@@ -1953,29 +2176,6 @@ fn generate_client_transaction_client_receipt_and_rpc_receipt(
     )
 }
 
-fn generate_client_transaction_and_rpc_transaction(
-    rng: &mut ChaCha8Rng,
-) -> (ClientTransaction, TransactionWithHash) {
-    // TODO(shahak): Remove retry once v3 transactions are supported and the impl of TryInto will
-    // become impl of Into.
-    loop {
-        let client_transaction = ClientTransaction::get_test_instance(rng);
-        let Ok(starknet_api_transaction): Result<StarknetApiTransaction, _> =
-            client_transaction.clone().try_into()
-        else {
-            continue;
-        };
-        let Ok(rpc_transaction) = starknet_api_transaction.try_into() else {
-            continue;
-        };
-        let transaction_hash = client_transaction.transaction_hash();
-        break (
-            client_transaction,
-            TransactionWithHash { transaction: rpc_transaction, transaction_hash },
-        );
-    }
-}
-
 #[tokio::test]
 async fn get_transaction_by_hash() {
     let method_name = "starknet_V0_6_getTransactionByHash";
@@ -3117,6 +3317,7 @@ async fn serialize_returns_valid_json() {
         get_test_pending_data(),
         get_test_pending_classes(),
         storage_reader,
+        get_test_new_block_sender(),
         NODE_VERSION,
     )
     .await
@@ -3278,6 +3479,8 @@ async fn get_deprecated_class_state_mutability() {
         .unwrap()
         .append_header(header.block_number, &header)
         .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
         .append_state_diff(header.block_number, state_diff, IndexMap::new())
         .unwrap()
         .commit()
@@ -7,7 +7,7 @@ use starknet_api::block::{BlockHash, BlockNumber, BlockStatus, BlockTimestamp, G
 use starknet_api::core::{ContractAddress, GlobalRoot};
 
 use super::error::BLOCK_NOT_FOUND;
-use super::transaction::Transactions;
+use super::transaction::{TransactionWithReceipt, Transactions};
 use crate::api::{BlockHashOrNumber, BlockId, Tag};
 use crate::{get_latest_block_number, internal_server_error};
 
@@ -74,6 +74,15 @@ pub struct Block {
     pub transactions: Transactions,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BlockWithReceipts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<BlockStatus>,
+    #[serde(flatten)]
+    pub header: GeneralBlockHeader,
+    pub transactions: Vec<TransactionWithReceipt>,
+}
+
 pub fn get_block_header_by_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
@@ -94,8 +103,8 @@ pub fn get_block_header_by_number<Mode: TransactionKind>(
 }
 
 /// Return the closest block number that corresponds to the given block id and is accepted (i.e not
-/// pending). Latest block means the most advanced block that we've downloaded and that we've
-/// downloaded its state diff.
+/// pending). Latest block means the most advanced block that we've downloaded both its body and
+/// its state diff.
 pub(crate) fn get_accepted_block_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_id: BlockId,
@@ -133,6 +142,7 @@ pub(crate) fn get_accepted_block_number<Mode: TransactionKind>(
 /// Validates that a given block wasn't reverted. Given an instance of this class, we can call its
 /// `validate` method and it will validate that the block's hash didn't change from the validator's
 /// creation.
+#[derive(Clone)]
 pub(crate) struct BlockNotRevertedValidator {
     block_number: BlockNumber,
     old_block_hash: BlockHash,
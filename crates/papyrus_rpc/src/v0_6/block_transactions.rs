@@ -0,0 +1,116 @@
+//! An API for reading the transactions of huge blocks in chunks, instead of in a single enormous
+//! response of `starknet_getBlockWithTxs`. Served only for accepted blocks.
+#[cfg(test)]
+#[path = "block_transactions_test.rs"]
+mod block_transactions_test;
+
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_proc_macros::versioned_rpc;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::StorageReader;
+use serde::{Deserialize, Serialize};
+use starknet_api::transaction::TransactionOffsetInBlock;
+use tracing::instrument;
+
+use super::api::ContinuationToken;
+use super::block::get_accepted_block_number;
+use super::error::{BLOCK_NOT_FOUND, INVALID_CONTINUATION_TOKEN, PAGE_SIZE_TOO_BIG};
+use super::transaction::{Transaction, TransactionWithHash};
+use crate::api::{BlockId, Tag};
+use crate::{internal_server_error, verify_body_not_pruned, verify_storage_scope};
+
+// The maximal number of transactions in a chunk.
+const MAX_TRANSACTIONS_CHUNK_SIZE: usize = 1000;
+
+/// A chunk of the transactions of a block, in their order in the block.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionsChunk {
+    pub transactions: Vec<TransactionWithHash>,
+    /// Continues to the next chunk of the block. None if this is the last chunk.
+    pub continuation_token: Option<ContinuationToken>,
+}
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait BlockTransactionsJsonRpc {
+    /// Returns up to `chunk_size` transactions of the given block, starting from the one that the
+    /// continuation token points to, or from the first transaction if it's not given. The block
+    /// should be given by its hash or number, so that all the chunks are of the same block.
+    #[method(name = "papyrusGetBlockTransactionsPaged")]
+    async fn get_block_transactions_paged(
+        &self,
+        block_id: BlockId,
+        continuation_token: Option<ContinuationToken>,
+        chunk_size: usize,
+    ) -> RpcResult<TransactionsChunk>;
+}
+
+/// Rpc server of the block transactions API.
+pub struct BlockTransactionsV0_6Impl {
+    storage_reader: StorageReader,
+}
+
+impl BlockTransactionsV0_6Impl {
+    pub fn new(storage_reader: StorageReader) -> Self {
+        Self { storage_reader }
+    }
+}
+
+#[async_trait]
+impl BlockTransactionsJsonRpcV0_6Server for BlockTransactionsV0_6Impl {
+    #[instrument(skip(self), level = "debug", err)]
+    async fn get_block_transactions_paged(
+        &self,
+        block_id: BlockId,
+        continuation_token: Option<ContinuationToken>,
+        chunk_size: usize,
+    ) -> RpcResult<TransactionsChunk> {
+        verify_storage_scope(&self.storage_reader)?;
+        if chunk_size > MAX_TRANSACTIONS_CHUNK_SIZE {
+            return Err(ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG));
+        }
+        if let BlockId::Tag(Tag::Pending) = block_id {
+            return Err(BLOCK_NOT_FOUND.into());
+        }
+        // The continuation token is the offset in the block of the first transaction to return.
+        let start_offset = match continuation_token {
+            Some(ContinuationToken(token)) => TransactionOffsetInBlock(
+                token.parse().map_err(|_| ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN))?,
+            ),
+            None => TransactionOffsetInBlock(0),
+        };
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        verify_body_not_pruned(&txn, block_number)?;
+        // One more transaction is read to know whether there's another chunk.
+        let mut transactions = txn
+            .get_block_transactions_range(block_number, start_offset, chunk_size + 1)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+        let transaction_hashes = txn
+            .get_block_transaction_hashes_range(block_number, start_offset, chunk_size)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(BLOCK_NOT_FOUND))?;
+        let continuation_token = (transactions.len() > chunk_size)
+            .then(|| ContinuationToken((start_offset.0 + chunk_size).to_string()));
+        transactions.truncate(chunk_size);
+
+        Ok(TransactionsChunk {
+            transactions: transactions
+                .into_iter()
+                .zip(transaction_hashes)
+                .map(|(transaction, transaction_hash)| {
+                    Ok(TransactionWithHash {
+                        transaction: Transaction::try_from(transaction)?,
+                        transaction_hash,
+                    })
+                })
+                .collect::<RpcResult<_>>()?,
+            continuation_token,
+        })
+    }
+}
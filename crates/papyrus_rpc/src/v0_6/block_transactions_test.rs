@@ -0,0 +1,102 @@
+use assert_matches::assert_matches;
+use jsonrpsee::core::Error;
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::state::StateDiff;
+use test_utils::{get_test_body_with_transaction_types, TestTransactionType};
+
+use super::{
+    BlockTransactionsJsonRpcV0_6Server,
+    BlockTransactionsV0_6Impl,
+    TransactionsChunk,
+    MAX_TRANSACTIONS_CHUNK_SIZE,
+};
+use crate::api::{BlockHashOrNumber, BlockId};
+use crate::v0_6::api::ContinuationToken;
+use crate::v0_6::error::{INVALID_CONTINUATION_TOKEN, PAGE_SIZE_TOO_BIG};
+use crate::v0_6::transaction::{Transaction, TransactionWithHash};
+
+const METHOD_NAME: &str = "starknet_V0_6_papyrusGetBlockTransactionsPaged";
+const BLOCK_ID: BlockId = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)));
+
+#[tokio::test]
+async fn get_block_transactions_paged() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module: RpcModule<_> = BlockTransactionsV0_6Impl::new(storage_reader).into_rpc();
+    let body = get_test_body_with_transaction_types(&[TestTransactionType::Invoke; 5], 0);
+    let expected_transactions = body
+        .transactions
+        .iter()
+        .zip(&body.transaction_hashes)
+        .map(|(transaction, transaction_hash)| TransactionWithHash {
+            transaction: Transaction::try_from(transaction.clone()).unwrap(),
+            transaction_hash: *transaction_hash,
+        })
+        .collect::<Vec<_>>();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &BlockHeader::default())
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .append_state_diff(BlockNumber(0), StateDiff::default(), [].into())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut transactions = Vec::new();
+    let mut continuation_tokens = Vec::new();
+    let mut continuation_token = None;
+    loop {
+        let chunk = module
+            .call::<_, TransactionsChunk>(METHOD_NAME, (BLOCK_ID, continuation_token, 2))
+            .await
+            .unwrap();
+        transactions.extend(chunk.transactions);
+        continuation_token = chunk.continuation_token;
+        let Some(token) = &continuation_token else {
+            break;
+        };
+        continuation_tokens.push(token.clone());
+    }
+    assert_eq!(transactions, expected_transactions);
+    assert_eq!(
+        continuation_tokens,
+        vec![ContinuationToken("2".to_owned()), ContinuationToken("4".to_owned())]
+    );
+
+    // A chunk that ends with the block has no continuation token.
+    let chunk = module
+        .call::<_, TransactionsChunk>(METHOD_NAME, (BLOCK_ID, None::<ContinuationToken>, 5))
+        .await
+        .unwrap();
+    assert_eq!(chunk, TransactionsChunk { transactions, continuation_token: None });
+
+    let err = module
+        .call::<_, TransactionsChunk>(
+            METHOD_NAME,
+            (BLOCK_ID, None::<ContinuationToken>, MAX_TRANSACTIONS_CHUNK_SIZE + 1),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == ErrorObjectOwned::from(PAGE_SIZE_TOO_BIG));
+
+    let err = module
+        .call::<_, TransactionsChunk>(
+            METHOD_NAME,
+            (BLOCK_ID, Some(ContinuationToken("invalid".to_owned())), 2),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        Error::Call(err) if err == ErrorObjectOwned::from(INVALID_CONTINUATION_TOKEN)
+    );
+}
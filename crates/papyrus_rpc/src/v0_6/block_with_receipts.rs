@@ -0,0 +1,28 @@
+//! An API for reading a block together with the receipts of its transactions, in a single request
+//! instead of a `starknet_getTransactionReceipt` request per transaction. The method is served by
+//! the same server as the rest of the methods of the version.
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use papyrus_proc_macros::versioned_rpc;
+use tracing::instrument;
+
+use super::api::api_impl::JsonRpcServerV0_6Impl;
+use super::block::BlockWithReceipts;
+use crate::api::BlockId;
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait BlockWithReceiptsJsonRpc {
+    /// Gets block information with full transactions and their receipts given a block identifier.
+    #[method(name = "getBlockWithReceipts")]
+    async fn get_block_w_receipts(&self, block_id: BlockId) -> RpcResult<BlockWithReceipts>;
+}
+
+#[async_trait]
+impl BlockWithReceiptsJsonRpcV0_6Server for JsonRpcServerV0_6Impl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_block_w_receipts(&self, block_id: BlockId) -> RpcResult<BlockWithReceipts> {
+        self.get_block_with_receipts(block_id).await
+    }
+}
@@ -23,7 +23,14 @@ use starknet_api::transaction::{
     TransactionSignature,
 };
 use starknet_client::writer::objects::transaction::DeprecatedContractClass;
-use test_utils::{auto_impl_get_test_instance, get_number_of_variants, get_rng, GetTestInstance};
+use test_utils::{
+    assert_json_roundtrip,
+    auto_impl_get_test_instance,
+    for_random_instances,
+    get_number_of_variants,
+    get_rng,
+    GetTestInstance,
+};
 
 use super::super::state::{ContractClass, EntryPointByType};
 use super::{
@@ -173,3 +180,10 @@ fn declare_v3_fits_rpc() {
 
     validate_tx_fits_rpc(tx);
 }
+
+#[test]
+fn declare_v3_serde_roundtrip() {
+    for_random_instances(|tx: BroadcastedDeclareV3Transaction| {
+        assert_json_roundtrip(&BroadcastedDeclareTransaction::V3(tx))
+    });
+}
@@ -0,0 +1,87 @@
+//! An API for discovering the optional features that the node serves, so that clients don't need
+//! to probe them with calls that fail.
+#[cfg(test)]
+#[path = "capabilities_test.rs"]
+mod capabilities_test;
+
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use papyrus_proc_macros::versioned_rpc;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::state_trie::StateTrieStorageReader;
+use papyrus_storage::{StorageReader, StorageScope};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use tracing::instrument;
+
+use crate::internal_server_error;
+use crate::version_config::{VersionState, VERSION_CONFIG};
+
+/// The optional features that the node serves.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct NodeCapabilities {
+    /// The versions of the API that the node serves, for example "V0_6".
+    pub api_versions: Vec<String>,
+    /// Whether transactions can be simulated and their fee estimated.
+    pub execution: bool,
+    /// Whether the transactions of the accepted blocks can be traced.
+    pub traces: bool,
+    /// Whether the bodies of the blocks are served, or only their headers and state.
+    pub block_bodies: bool,
+    /// The first block whose body wasn't pruned. The transactions, receipts and events of the
+    /// blocks below it aren't served.
+    pub first_block_with_body: BlockNumber,
+    /// Whether new blocks, pending transactions and events can be subscribed to through a
+    /// WebSocket connection.
+    pub websocket_subscriptions: bool,
+    /// Whether the papyrus simulation sessions API is served.
+    pub simulation_sessions: bool,
+    /// Whether the storage of the accepted blocks can be proved, see `starknet_getStorageProof`.
+    pub storage_proofs: bool,
+}
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait CapabilitiesJsonRpc {
+    /// Returns the optional features that the node serves.
+    #[method(name = "papyrusCapabilities")]
+    fn capabilities(&self) -> RpcResult<NodeCapabilities>;
+}
+
+/// Rpc server of the capabilities API.
+pub struct CapabilitiesV0_6Impl {
+    storage_reader: StorageReader,
+    enable_simulation_sessions: bool,
+}
+
+impl CapabilitiesV0_6Impl {
+    pub fn new(storage_reader: StorageReader, enable_simulation_sessions: bool) -> Self {
+        Self { storage_reader, enable_simulation_sessions }
+    }
+}
+
+#[async_trait]
+impl CapabilitiesJsonRpcV0_6Server for CapabilitiesV0_6Impl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    fn capabilities(&self) -> RpcResult<NodeCapabilities> {
+        let block_bodies = self.storage_reader.get_scope() == StorageScope::FullArchive;
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        Ok(NodeCapabilities {
+            api_versions: VERSION_CONFIG
+                .iter()
+                .filter(|(_, version_state)| *version_state == VersionState::Supported)
+                .map(|(version_id, _)| version_id.name.to_owned())
+                .collect(),
+            execution: true,
+            // Tracing executes the transactions of the block, which are read from the storage.
+            traces: block_bodies,
+            block_bodies,
+            first_block_with_body: txn.get_pruned_body_marker().map_err(internal_server_error)?,
+            websocket_subscriptions: true,
+            simulation_sessions: self.enable_simulation_sessions,
+            storage_proofs: txn.get_state_trie_marker().map_err(internal_server_error)?
+                > BlockNumber(0),
+        })
+    }
+}
@@ -0,0 +1,28 @@
+use jsonrpsee::{rpc_params, RpcModule};
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+
+use super::{CapabilitiesJsonRpcV0_6Server, CapabilitiesV0_6Impl, NodeCapabilities};
+
+const METHOD_NAME: &str = "starknet_V0_6_papyrusCapabilities";
+
+#[tokio::test]
+async fn capabilities() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let module: RpcModule<_> = CapabilitiesV0_6Impl::new(storage_reader, true).into_rpc();
+    let res = module.call::<_, NodeCapabilities>(METHOD_NAME, rpc_params![]).await.unwrap();
+    assert_eq!(
+        res,
+        NodeCapabilities {
+            api_versions: vec!["V0_4".to_owned(), "V0_5".to_owned(), "V0_6".to_owned()],
+            execution: true,
+            traces: true,
+            block_bodies: true,
+            first_block_with_body: BlockNumber(0),
+            websocket_subscriptions: true,
+            simulation_sessions: true,
+            storage_proofs: false,
+        }
+    );
+}
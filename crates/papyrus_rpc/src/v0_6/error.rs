@@ -1,4 +1,5 @@
 use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_execution::revert_trace::RevertTraceFrame;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug)]
@@ -54,6 +55,9 @@ pub const TOO_MANY_KEYS_IN_FILTER: JsonRpcError<String> =
 #[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
 pub struct ContractError {
     pub revert_error: String,
+    /// The calls that led to the error, from the outermost call to the innermost one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revert_trace: Vec<RevertTraceFrame>,
 }
 
 impl From<ContractError> for JsonRpcError<ContractError> {
@@ -65,6 +69,9 @@ impl From<ContractError> for JsonRpcError<ContractError> {
 pub struct TransactionExecutionError {
     pub transaction_index: usize,
     pub execution_error: String,
+    /// The calls that led to the error, from the outermost call to the innermost one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revert_trace: Vec<RevertTraceFrame>,
 }
 
 impl From<TransactionExecutionError> for JsonRpcError<TransactionExecutionError> {
@@ -72,6 +79,15 @@ impl From<TransactionExecutionError> for JsonRpcError<TransactionExecutionError>
         Self { code: 41, message: "Transaction execution error", data: Some(tx_execution_error) }
     }
 }
+pub const STORAGE_PROOF_NOT_SUPPORTED: JsonRpcError<String> = JsonRpcError {
+    code: 42,
+    message: "The node doesn't support storage proofs for blocks that are too far in the past",
+    data: None,
+};
+
+pub const TOO_MANY_BLOCKS_BACK: JsonRpcError<String> =
+    JsonRpcError { code: 68, message: "Cannot go back more than 1024 blocks", data: None };
+
 pub const CLASS_ALREADY_DECLARED: JsonRpcError<String> =
     JsonRpcError { code: 51, message: "Class already declared", data: None };
 
@@ -1,16 +1,21 @@
 use std::env;
 use std::fs::read_to_string;
+use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use assert_matches::assert_matches;
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use indexmap::{indexmap, IndexMap};
 use jsonrpsee::core::Error;
 use jsonrpsee::RpcModule;
 use lazy_static::lazy_static;
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
 use papyrus_common::state::{DeclaredClassHashEntry, DeployedContract, StorageEntry};
+use papyrus_execution::contract_class_cache::ContractClassCache;
 use papyrus_execution::execution_utils::selector_from_name;
 use papyrus_execution::objects::{
     DeclareTransactionTrace,
@@ -20,7 +25,7 @@ use papyrus_execution::objects::{
     L1HandlerTransactionTrace,
     PriceUnit,
     RevertReason,
-    TransactionTrace,
+    TransactionTrace as ExecutionTransactionTrace,
 };
 use papyrus_execution::testing_instances::get_storage_var_address;
 use papyrus_execution::ExecutableTransactionInput;
@@ -28,7 +33,8 @@ use papyrus_storage::body::BodyStorageWriter;
 use papyrus_storage::compiled_class::CasmStorageWriter;
 use papyrus_storage::header::{HeaderStorageWriter, StarknetVersion};
 use papyrus_storage::state::StateStorageWriter;
-use papyrus_storage::StorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::{StorageReader, StorageWriter};
 use pretty_assertions::assert_eq;
 use starknet_api::block::{
     BlockBody,
@@ -49,7 +55,7 @@ use starknet_api::core::{
 };
 use starknet_api::deprecated_contract_class::ContractClass as SN_API_DeprecatedContractClass;
 use starknet_api::hash::{StarkFelt, StarkHash};
-use starknet_api::state::StateDiff;
+use starknet_api::state::{StateDiff, ThinStateDiff as StarknetApiThinStateDiff};
 use starknet_api::transaction::{
     Calldata,
     Fee,
@@ -67,6 +73,7 @@ use starknet_client::reader::objects::transaction::{
     TransactionReceipt as ClientTransactionReceipt,
 };
 use starknet_client::reader::PendingData;
+use starknet_client::writer::objects::transaction::DeprecatedContractClass;
 use test_utils::{auto_impl_get_test_instance, get_rng, read_json_file, GetTestInstance};
 use tokio::sync::RwLock;
 
@@ -76,6 +83,7 @@ use super::api::{
     FeeEstimate,
     SimulatedTransaction,
     SimulationFlag,
+    TransactionTrace,
     TransactionTraceWithHash,
 };
 use super::broadcasted_transaction::{
@@ -84,6 +92,19 @@ use super::broadcasted_transaction::{
     BroadcastedTransaction,
 };
 use super::error::{TransactionExecutionError, BLOCK_NOT_FOUND, CONTRACT_NOT_FOUND};
+use super::simulation_sessions::{
+    SimulationSessionId,
+    SimulationSessionsJsonRpcV0_6Server,
+    SimulationSessionsV0_6Impl,
+    SIMULATION_SESSION_NOT_FOUND,
+};
+use super::state_overrides::{
+    ContractStateOverride,
+    DeclaredClassOverride,
+    OverriddenContractClass,
+    StateOverride,
+    StateOverridesJsonRpcV0_6Server,
+};
 use super::transaction::{
     DeployAccountTransaction,
     InvokeTransaction,
@@ -92,6 +113,7 @@ use super::transaction::{
     TransactionVersion1,
 };
 use crate::api::{BlockHashOrNumber, BlockId, CallRequest, Tag};
+use crate::compression_utils::{compress_and_encode, MAX_DECOMPRESSED_PROGRAM_SIZE};
 use crate::test_utils::{
     call_and_validate_schema_for_result,
     call_api_then_assert_and_validate_schema_for_result,
@@ -102,6 +124,7 @@ use crate::test_utils::{
     get_test_rpc_config,
     get_test_rpc_server_and_storage_writer,
     get_test_rpc_server_and_storage_writer_from_params,
+    get_test_rpc_server_impl_and_storage_writer_from_params,
     validate_schema,
     SpecFile,
 };
@@ -250,6 +273,184 @@ async fn execution_call() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn execution_call_with_state_override() {
+    let (server, storage_writer) = get_test_rpc_server_impl_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, None, None, None);
+    let module = StateOverridesJsonRpcV0_6Server::into_rpc(server);
+
+    prepare_storage_for_execution(storage_writer);
+
+    let undeployed_contract_address = contract_address!("0x1234");
+    let undeclared_class_hash = class_hash!("0x1234");
+    let key = stark_felt!(1234_u16);
+    let value = stark_felt!(18_u8);
+    // A balance that doesn't fit in 128 bits.
+    let balance = stark_felt!("0x100000000000000000000000000000007");
+
+    // Calling a contract that isn't deployed, as if it was deployed with the deprecated class.
+    let res = module
+        .call::<_, Vec<StarkFelt>>(
+            "starknet_V0_6_papyrusCall",
+            (
+                CallRequest {
+                    contract_address: undeployed_contract_address,
+                    entry_point_selector: selector_from_name("test_storage_read_write"),
+                    calldata: calldata![key, value],
+                },
+                BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0))),
+                StateOverride {
+                    contracts: vec![ContractStateOverride {
+                        address: undeployed_contract_address,
+                        class_hash: Some(class_hash!("0x1")),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![value]);
+
+    // Calling a contract whose class is declared only by the override.
+    let deprecated_class = serde_json::from_value::<SN_API_DeprecatedContractClass>(
+        read_json_file("deprecated_class.json"),
+    )
+    .unwrap();
+    let overridden_class = OverriddenContractClass::Cairo0(DeprecatedContractClass {
+        abi: deprecated_class.abi,
+        compressed_program: compress_and_encode(
+            serde_json::to_value(deprecated_class.program).unwrap(),
+        )
+        .unwrap(),
+        entry_points_by_type: deprecated_class.entry_points_by_type,
+    });
+    let res = module
+        .call::<_, Vec<StarkFelt>>(
+            "starknet_V0_6_papyrusCall",
+            (
+                CallRequest {
+                    contract_address: undeployed_contract_address,
+                    entry_point_selector: selector_from_name("test_storage_read_write"),
+                    calldata: calldata![key, value],
+                },
+                BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0))),
+                StateOverride {
+                    contracts: vec![ContractStateOverride {
+                        address: undeployed_contract_address,
+                        class_hash: Some(undeclared_class_hash),
+                        ..Default::default()
+                    }],
+                    declared_classes: vec![DeclaredClassOverride {
+                        class_hash: undeclared_class_hash,
+                        contract_class: overridden_class,
+                    }],
+                },
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![value]);
+
+    // Reading an overridden balance from the fee token. The balance is a u256, returned as its low
+    // and high 128 bits.
+    let res = module
+        .call::<_, Vec<StarkFelt>>(
+            "starknet_V0_6_papyrusCall",
+            (
+                CallRequest {
+                    contract_address: *TEST_ERC20_CONTRACT_ADDRESS,
+                    entry_point_selector: selector_from_name("balanceOf"),
+                    calldata: calldata![*undeployed_contract_address.0.key()],
+                },
+                BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0))),
+                StateOverride {
+                    contracts: vec![ContractStateOverride {
+                        address: undeployed_contract_address,
+                        balance: Some(balance),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![stark_felt!(7_u8), stark_felt!(1_u8)]);
+}
+
+#[tokio::test]
+async fn simulate_with_state_override() {
+    let (server, storage_writer) = get_test_rpc_server_impl_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, None, None, None);
+    let module = StateOverridesJsonRpcV0_6Server::into_rpc(server);
+
+    prepare_storage_for_execution(storage_writer);
+
+    let invoke = BroadcastedTransaction::Invoke(InvokeTransaction::Version1(InvokeTransactionV1 {
+        max_fee: Fee(1000000 * GAS_PRICE.0),
+        version: TransactionVersion1::Version1,
+        sender_address: *ACCOUNT_ADDRESS,
+        calldata: calldata![
+            *DEPRECATED_CONTRACT_ADDRESS.0.key(),  // Contract address.
+            selector_from_name("return_result").0, // EP selector.
+            stark_felt!(1_u8),                     // Calldata length.
+            stark_felt!(2_u8)                      // Calldata: num.
+        ],
+        nonce: Nonce(stark_felt!(5_u8)),
+        ..Default::default()
+    }));
+
+    // The nonce of the transaction matches only the overridden nonce of the account.
+    let res = module
+        .call::<_, Vec<SimulatedTransaction>>(
+            "starknet_V0_6_papyrusSimulateTransactions",
+            (
+                BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0))),
+                vec![invoke.clone()],
+                Vec::<SimulationFlag>::new(),
+                StateOverride {
+                    contracts: vec![ContractStateOverride {
+                        address: *ACCOUNT_ADDRESS,
+                        nonce: Some(Nonce(stark_felt!(5_u8))),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.len(), 1);
+
+    // Without balance, the account can't pay the fee.
+    let err = module
+        .call::<_, Vec<SimulatedTransaction>>(
+            "starknet_V0_6_papyrusSimulateTransactions",
+            (
+                BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0))),
+                vec![invoke],
+                Vec::<SimulationFlag>::new(),
+                StateOverride {
+                    contracts: vec![ContractStateOverride {
+                        address: *ACCOUNT_ADDRESS,
+                        nonce: Some(Nonce(stark_felt!(5_u8))),
+                        balance: Some(StarkFelt::ZERO),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            ),
+        )
+        .await
+        .unwrap_err();
+    const TRANSACTION_EXECUTION_ERROR_CODE: i32 = 41;
+    assert_matches!(err, Error::Call(err) if err.code() == TRANSACTION_EXECUTION_ERROR_CODE);
+}
+
 #[tokio::test]
 async fn pending_execution_call() {
     let pending_data = get_test_pending_data();
@@ -455,6 +656,50 @@ async fn pending_call_estimate_fee() {
     // calculated inside the hash.
 }
 
+// Test that fee estimations on the pending block use the gas price of the pending block even if the
+// node hasn't synced the pending block's parent yet.
+#[tokio::test]
+async fn pending_call_estimate_fee_with_unsynced_parent() {
+    let pending_data = get_test_pending_data();
+    *pending_data.write().await = PendingData {
+        block: PendingBlock {
+            eth_l1_gas_price: *GAS_PRICE,
+            sequencer_address: *SEQUENCER_ADDRESS,
+            timestamp: BlockTimestamp(BLOCK_TIMESTAMP.0 + 1),
+            parent_block_hash: BlockHash(stark_felt!("0x2")),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (module, storage_writer) = get_test_rpc_server_and_storage_writer_from_params::<
+        JsonRpcServerImpl,
+    >(None, None, Some(pending_data), None, None);
+    // The latest block in the storage has a different gas price than the pending block.
+    prepare_storage_for_execution(storage_writer);
+
+    let invoke = BroadcastedTransaction::Invoke(InvokeTransaction::Version1(InvokeTransactionV1 {
+        max_fee: Fee(1000000 * GAS_PRICE.0),
+        version: TransactionVersion1::Version1,
+        sender_address: *ACCOUNT_ADDRESS,
+        calldata: calldata![
+            *DEPRECATED_CONTRACT_ADDRESS.0.key(),  // Contract address.
+            selector_from_name("return_result").0, // EP selector.
+            stark_felt!(1_u8),                     // Calldata length.
+            stark_felt!(2_u8)                      // Calldata: num.
+        ],
+        ..Default::default()
+    }));
+
+    let res = module
+        .call::<_, Vec<FeeEstimate>>(
+            "starknet_V0_6_estimateFee",
+            (vec![invoke], Vec::<SimulationFlag>::new(), BlockId::Tag(Tag::Pending)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, vec![EXPECTED_FEE_ESTIMATE.clone()]);
+}
+
 #[tokio::test]
 async fn call_simulate() {
     let (module, storage_writer) = get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
@@ -520,9 +765,10 @@ async fn test_call_simulate(
 
     assert_eq!(simulated_tx.fee_estimation, *EXPECTED_FEE_ESTIMATE);
 
-    assert_matches!(simulated_tx.transaction_trace, TransactionTrace::Invoke(_));
+    assert_matches!(simulated_tx.transaction_trace.trace, ExecutionTransactionTrace::Invoke(_));
 
-    let TransactionTrace::Invoke(invoke_trace) = simulated_tx.transaction_trace else {
+    let ExecutionTransactionTrace::Invoke(invoke_trace) = simulated_tx.transaction_trace.trace
+    else {
         unreachable!();
     };
 
@@ -554,7 +800,7 @@ async fn test_call_simulate(
         .await
         .unwrap();
 
-    let TransactionTrace::Invoke(invoke_trace) = &res[0].transaction_trace else {
+    let ExecutionTransactionTrace::Invoke(invoke_trace) = &res[0].transaction_trace.trace else {
         panic!("Got a non-invoke transaction trace from an invoke transaction.");
     };
     // As described above, we want to check that `test_get_execution_info` fails on the transaction
@@ -605,9 +851,10 @@ async fn call_simulate_skip_validate() {
 
     assert_eq!(simulated_tx.fee_estimation, *EXPECTED_FEE_ESTIMATE);
 
-    assert_matches!(simulated_tx.transaction_trace, TransactionTrace::Invoke(_));
+    assert_matches!(simulated_tx.transaction_trace.trace, ExecutionTransactionTrace::Invoke(_));
 
-    let TransactionTrace::Invoke(invoke_trace) = simulated_tx.transaction_trace else {
+    let ExecutionTransactionTrace::Invoke(invoke_trace) = simulated_tx.transaction_trace.trace
+    else {
         unreachable!();
     };
 
@@ -654,9 +901,10 @@ async fn call_simulate_skip_fee_charge() {
 
     assert_eq!(simulated_tx.fee_estimation, *EXPECTED_FEE_ESTIMATE);
 
-    assert_matches!(simulated_tx.transaction_trace, TransactionTrace::Invoke(_));
+    assert_matches!(simulated_tx.transaction_trace.trace, ExecutionTransactionTrace::Invoke(_));
 
-    let TransactionTrace::Invoke(invoke_trace) = simulated_tx.transaction_trace else {
+    let ExecutionTransactionTrace::Invoke(invoke_trace) = simulated_tx.transaction_trace.trace
+    else {
         unreachable!();
     };
 
@@ -665,7 +913,190 @@ async fn call_simulate_skip_fee_charge() {
     assert_matches!(invoke_trace.fee_transfer_invocation, None);
 }
 
+// The execution timeout of the simulation sessions in the tests whose simulations don't time out.
+const EXECUTION_TIMEOUT: Duration = Duration::from_secs(60);
+
 // TODO(shahak): Add test for trace_transaction that doesn't depend on trace_block_transactions
+fn get_simulation_sessions_module(
+    storage_reader: StorageReader,
+    execution_timeout: Duration,
+    session_ttl: Duration,
+) -> RpcModule<SimulationSessionsV0_6Impl> {
+    let config = get_test_rpc_config();
+    SimulationSessionsV0_6Impl::new(
+        config.chain_id,
+        config.execution_config.try_into().expect("failed to load execution config"),
+        ContractClassCache::new(config.execution_class_cache_size_bytes),
+        execution_timeout,
+        config.max_simulation_sessions,
+        session_ttl,
+        storage_reader,
+    )
+    .into_rpc()
+}
+
+#[tokio::test]
+async fn simulation_session() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage_for_execution(storage_writer);
+    let module =
+        get_simulation_sessions_module(storage_reader, EXECUTION_TIMEOUT, Duration::from_secs(60));
+
+    let session_id = module
+        .call::<_, SimulationSessionId>(
+            "starknet_V0_6_papyrusOpenSimulationSession",
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))],
+        )
+        .await
+        .unwrap();
+
+    // The nonce of the second invoke is valid only if the first invoke is in the state of the
+    // session.
+    for nonce in [0_u128, 1] {
+        let invoke =
+            BroadcastedTransaction::Invoke(InvokeTransaction::Version1(InvokeTransactionV1 {
+                max_fee: Fee(1000000 * GAS_PRICE.0),
+                version: TransactionVersion1::Version1,
+                sender_address: *ACCOUNT_ADDRESS,
+                nonce: Nonce(stark_felt!(nonce)),
+                calldata: calldata![
+                    *DEPRECATED_CONTRACT_ADDRESS.0.key(),  // Contract address.
+                    selector_from_name("return_result").0, // EP selector.
+                    stark_felt!(1_u8),                     // Calldata length.
+                    stark_felt!(2_u8)                      // Calldata: num.
+                ],
+                ..Default::default()
+            }));
+        let res = module
+            .call::<_, Vec<SimulatedTransaction>>(
+                "starknet_V0_6_papyrusSimulateInSession",
+                (session_id, vec![invoke], Vec::<SimulationFlag>::new()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+    }
+
+    let closed = module
+        .call::<_, bool>("starknet_V0_6_papyrusCloseSimulationSession", [session_id])
+        .await
+        .unwrap();
+    assert!(closed);
+
+    let err = module
+        .call::<_, Vec<SimulatedTransaction>>(
+            "starknet_V0_6_papyrusSimulateInSession",
+            (session_id, Vec::<BroadcastedTransaction>::new(), Vec::<SimulationFlag>::new()),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == SIMULATION_SESSION_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn simulation_session_expires() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage_for_execution(storage_writer);
+    let module =
+        get_simulation_sessions_module(storage_reader, EXECUTION_TIMEOUT, Duration::from_millis(1));
+
+    let session_id = module
+        .call::<_, SimulationSessionId>(
+            "starknet_V0_6_papyrusOpenSimulationSession",
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))],
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let err = module
+        .call::<_, Vec<SimulatedTransaction>>(
+            "starknet_V0_6_papyrusSimulateInSession",
+            (session_id, Vec::<BroadcastedTransaction>::new(), Vec::<SimulationFlag>::new()),
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == SIMULATION_SESSION_NOT_FOUND.into());
+}
+
+#[tokio::test]
+async fn simulation_session_timed_out() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    prepare_storage_for_execution(storage_writer);
+    let module =
+        get_simulation_sessions_module(storage_reader, Duration::ZERO, Duration::from_secs(60));
+
+    let session_id = module
+        .call::<_, SimulationSessionId>(
+            "starknet_V0_6_papyrusOpenSimulationSession",
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)))],
+        )
+        .await
+        .unwrap();
+    let invoke = BroadcastedTransaction::Invoke(InvokeTransaction::Version1(InvokeTransactionV1 {
+        max_fee: Fee(1000000 * GAS_PRICE.0),
+        version: TransactionVersion1::Version1,
+        sender_address: *ACCOUNT_ADDRESS,
+        calldata: calldata![
+            *DEPRECATED_CONTRACT_ADDRESS.0.key(),  // Contract address.
+            selector_from_name("return_result").0, // EP selector.
+            stark_felt!(1_u8),                     // Calldata length.
+            stark_felt!(2_u8)                      // Calldata: num.
+        ],
+        ..Default::default()
+    }));
+
+    // The simulation times out, and the session is closed without waiting for it to stop.
+    module
+        .call::<_, Vec<SimulatedTransaction>>(
+            "starknet_V0_6_papyrusSimulateInSession",
+            (session_id, vec![invoke], Vec::<SimulationFlag>::new()),
+        )
+        .await
+        .unwrap_err();
+    let closed = module
+        .call::<_, bool>("starknet_V0_6_papyrusCloseSimulationSession", [session_id])
+        .await
+        .unwrap();
+    assert!(!closed);
+}
+
+#[tokio::test]
+async fn simulation_session_of_reverted_block() {
+    let ((storage_reader, storage_writer), _temp_dir) = get_test_storage();
+    let mut storage_writer = prepare_storage_for_execution(storage_writer);
+    let module =
+        get_simulation_sessions_module(storage_reader, EXECUTION_TIMEOUT, Duration::from_secs(60));
+
+    let session_id = module
+        .call::<_, SimulationSessionId>(
+            "starknet_V0_6_papyrusOpenSimulationSession",
+            [BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)))],
+        )
+        .await
+        .unwrap();
+
+    let (txn, _) =
+        storage_writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(1)).unwrap();
+    let (txn, _) = txn.revert_body(BlockNumber(1)).unwrap();
+    let (txn, _) = txn.revert_header(BlockNumber(1)).unwrap();
+    txn.commit().unwrap();
+
+    // The simulation fails, and the session is closed.
+    module
+        .call::<_, Vec<SimulatedTransaction>>(
+            "starknet_V0_6_papyrusSimulateInSession",
+            (session_id, Vec::<BroadcastedTransaction>::new(), Vec::<SimulationFlag>::new()),
+        )
+        .await
+        .unwrap_err();
+    let closed = module
+        .call::<_, bool>("starknet_V0_6_papyrusCloseSimulationSession", [session_id])
+        .await
+        .unwrap();
+    assert!(!closed);
+}
+
 #[tokio::test]
 async fn trace_block_transactions_regular_and_pending() {
     let (module, storage_writer) = get_test_rpc_server_and_storage_writer::<JsonRpcServerImpl>();
@@ -751,14 +1182,14 @@ async fn trace_block_transactions_regular_and_pending() {
     )
     .await;
 
-    assert_matches!(tx_1_trace, TransactionTrace::Invoke(_));
+    assert_matches!(tx_1_trace.trace, ExecutionTransactionTrace::Invoke(_));
 
     let tx_2_trace = module
         .call::<_, TransactionTrace>("starknet_V0_6_traceTransaction", [tx_hash2])
         .await
         .unwrap();
 
-    assert_matches!(tx_2_trace, TransactionTrace::Invoke(_));
+    assert_matches!(tx_2_trace.trace, ExecutionTransactionTrace::Invoke(_));
 
     let res = call_and_validate_schema_for_result::<_, Vec<TransactionTraceWithHash>>(
         &module,
@@ -939,10 +1370,10 @@ async fn trace_block_transactions_and_trace_transaction_execution_context() {
         .commit()
         .unwrap();
 
-    let validate_result = |res| {
+    let validate_result = |res: TransactionTrace| {
         assert_matches!(
-            &res,
-            TransactionTrace::Invoke(invoke_trace)
+            &res.trace,
+            ExecutionTransactionTrace::Invoke(invoke_trace)
             if matches!(invoke_trace.execute_invocation, FunctionInvocationResult::Ok(_))
         );
     };
@@ -1057,10 +1488,10 @@ async fn pending_trace_block_transactions_and_trace_transaction_execution_contex
 
     prepare_storage_for_execution(storage_writer);
 
-    let validate_result = |res| {
+    let validate_result = |res: TransactionTrace| {
         assert_matches!(
-            &res,
-            TransactionTrace::Invoke(invoke_trace)
+            &res.trace,
+            ExecutionTransactionTrace::Invoke(invoke_trace)
             if matches!(invoke_trace.execute_invocation, FunctionInvocationResult::Ok(_))
         );
     };
@@ -1176,25 +1607,26 @@ fn validate_transaction_trace_schema() {
         &VERSION,
     );
 
-    let invoke_trace =
-        TransactionTrace::Invoke(InvokeTransactionTrace::get_test_instance(&mut rng));
-    let serialized = serde_json::to_value(invoke_trace).unwrap();
-    assert!(validate_schema(&schema, &serialized));
-
-    let declare_trace =
-        TransactionTrace::Declare(DeclareTransactionTrace::get_test_instance(&mut rng));
-    let serialized = serde_json::to_value(declare_trace).unwrap();
-    assert!(validate_schema(&schema, &serialized));
-
-    let deploy_account_trace =
-        TransactionTrace::DeployAccount(DeployAccountTransactionTrace::get_test_instance(&mut rng));
-    let serialized = serde_json::to_value(deploy_account_trace).unwrap();
-    assert!(validate_schema(&schema, &serialized));
-
-    let l1_handler_trace =
-        TransactionTrace::L1Handler(L1HandlerTransactionTrace::get_test_instance(&mut rng));
-    let serialized = serde_json::to_value(l1_handler_trace).unwrap();
-    assert!(validate_schema(&schema, &serialized));
+    let execution_traces = [
+        ExecutionTransactionTrace::Invoke(InvokeTransactionTrace::get_test_instance(&mut rng)),
+        ExecutionTransactionTrace::Declare(DeclareTransactionTrace::get_test_instance(&mut rng)),
+        ExecutionTransactionTrace::DeployAccount(DeployAccountTransactionTrace::get_test_instance(
+            &mut rng,
+        )),
+        ExecutionTransactionTrace::L1Handler(L1HandlerTransactionTrace::get_test_instance(
+            &mut rng,
+        )),
+    ];
+    for execution_trace in execution_traces {
+        let trace = TransactionTrace {
+            trace: execution_trace,
+            state_diff: StarknetApiThinStateDiff::get_test_instance(&mut rng).into(),
+        };
+        let serialized = serde_json::to_value(&trace).unwrap();
+        assert!(validate_schema(&schema, &serialized));
+        // The trace should be deserialized back from its spec representation.
+        assert_eq!(serde_json::from_value::<TransactionTrace>(serialized).unwrap(), trace);
+    }
 }
 
 #[test]
@@ -1227,6 +1659,15 @@ fn get_decompressed_program() {
     decompressed.expect("Couldn't decompress program");
 }
 
+#[test]
+fn decompress_too_large_program() {
+    // Compresses to a small program, that is larger than the limit when decompressed.
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&vec![b' '; MAX_DECOMPRESSED_PROGRAM_SIZE + 1]).unwrap();
+    let compressed = base64::encode(encoder.finish().unwrap());
+    assert!(decompress_program(&compressed).is_err());
+}
+
 fn get_test_compressed_program() -> String {
     let path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap())
         .join("resources")
@@ -1234,6 +1675,15 @@ fn get_test_compressed_program() -> String {
     read_to_string(path).expect("Couldn't read compressed program")
 }
 
+impl GetTestInstance for TransactionTrace {
+    fn get_test_instance(rng: &mut rand_chacha::ChaCha8Rng) -> Self {
+        Self {
+            trace: ExecutionTransactionTrace::get_test_instance(rng),
+            state_diff: StarknetApiThinStateDiff::get_test_instance(rng).into(),
+        }
+    }
+}
+
 auto_impl_get_test_instance! {
     pub struct FeeEstimate {
         pub gas_consumed: StarkFelt,
@@ -0,0 +1,106 @@
+//! An API for tracking the delivery of the messages sent from the base layer (L1) to Starknet by a
+//! base layer transaction. Served only for the messages the node tracks, see the
+//! `l1_to_l2_messages_start_block` sync config.
+#[cfg(test)]
+#[path = "l1_to_l2_messages_test.rs"]
+mod l1_to_l2_messages_test;
+
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_common::l1_to_l2_messages::L1TransactionHash;
+use papyrus_proc_macros::versioned_rpc;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::l1_to_l2_messages::L1ToL2MessagesStorageReader;
+use papyrus_storage::StorageReader;
+use serde::{Deserialize, Serialize};
+use starknet_api::transaction::{TransactionExecutionStatus, TransactionHash};
+use tracing::instrument;
+
+use super::error::TRANSACTION_HASH_NOT_FOUND;
+use super::transaction::TransactionFinalityStatus;
+use crate::{get_block_status, internal_server_error, verify_storage_scope};
+
+/// The status of the L1 handler transaction that consumed a message sent from the base layer.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct L1HandlerTransactionStatus {
+    pub transaction_hash: TransactionHash,
+    pub finality_status: TransactionFinalityStatus,
+    pub execution_status: TransactionExecutionStatus,
+}
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait L1ToL2MessagesJsonRpc {
+    /// Returns the statuses of the L1 handler transactions that consumed the messages sent by the
+    /// given base layer transaction, in the order the messages were sent. Messages that weren't
+    /// consumed yet are omitted.
+    #[method(name = "getMessagesStatus")]
+    async fn get_messages_status(
+        &self,
+        transaction_hash: L1TransactionHash,
+    ) -> RpcResult<Vec<L1HandlerTransactionStatus>>;
+}
+
+/// Rpc server of the L1 to L2 messages API.
+pub struct L1ToL2MessagesV0_6Impl {
+    storage_reader: StorageReader,
+}
+
+impl L1ToL2MessagesV0_6Impl {
+    pub fn new(storage_reader: StorageReader) -> Self {
+        Self { storage_reader }
+    }
+}
+
+#[async_trait]
+impl L1ToL2MessagesJsonRpcV0_6Server for L1ToL2MessagesV0_6Impl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_messages_status(
+        &self,
+        transaction_hash: L1TransactionHash,
+    ) -> RpcResult<Vec<L1HandlerTransactionStatus>> {
+        verify_storage_scope(&self.storage_reader)?;
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let message_hashes = txn
+            .get_l1_transaction_message_hashes(&transaction_hash)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
+
+        let mut statuses = Vec::with_capacity(message_hashes.len());
+        for message_hash in message_hashes {
+            let Some(transaction_index) = txn
+                .get_l1_handler_transaction_idx_by_message_hash(&message_hash)
+                .map_err(internal_server_error)?
+            else {
+                continue;
+            };
+            let l1_handler_transaction_hash = txn
+                .get_transaction_hash_by_idx(&transaction_index)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| {
+                    internal_server_error(format!(
+                        "Missing hash of the transaction at index {transaction_index:?}."
+                    ))
+                })?;
+            let execution_status = txn
+                .get_transaction_output(transaction_index)
+                .map_err(internal_server_error)?
+                .ok_or_else(|| {
+                    internal_server_error(format!(
+                        "Missing output of the transaction at index {transaction_index:?}."
+                    ))
+                })?
+                .execution_status()
+                .clone();
+            statuses.push(L1HandlerTransactionStatus {
+                transaction_hash: l1_handler_transaction_hash,
+                finality_status: get_block_status(&txn, transaction_index.0)?.into(),
+                execution_status,
+            });
+        }
+        Ok(statuses)
+    }
+}
@@ -0,0 +1,94 @@
+use assert_matches::assert_matches;
+use jsonrpsee::core::Error;
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use papyrus_common::l1_to_l2_messages::{L1ToL2Message, L1TransactionHash};
+use papyrus_storage::base_layer::BaseLayerStorageWriter;
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::l1_to_l2_messages::L1ToL2MessagesStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::EthAddress;
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{Transaction, TransactionExecutionStatus};
+use test_utils::{get_test_body_with_transaction_types, TestTransactionType};
+
+use super::{L1HandlerTransactionStatus, L1ToL2MessagesJsonRpcV0_6Server, L1ToL2MessagesV0_6Impl};
+use crate::v0_6::error::TRANSACTION_HASH_NOT_FOUND;
+use crate::v0_6::transaction::TransactionFinalityStatus;
+
+const METHOD_NAME: &str = "starknet_V0_6_getMessagesStatus";
+
+#[tokio::test]
+async fn get_messages_status() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module: RpcModule<_> = L1ToL2MessagesV0_6Impl::new(storage_reader).into_rpc();
+
+    let mut body = get_test_body_with_transaction_types(&[TestTransactionType::L1Handler], 0);
+    let Transaction::L1Handler(l1_handler_transaction) = &mut body.transactions[0] else {
+        panic!("Expected an L1 handler transaction.");
+    };
+    // The first element of the calldata is the sender of the message.
+    l1_handler_transaction.calldata.0 = vec![StarkFelt::from(1_u8), StarkFelt::from(2_u8)].into();
+    let l1_transaction_hash = L1TransactionHash([1; 32]);
+    let consumed_message = L1ToL2Message {
+        l1_transaction_hash,
+        from_address: EthAddress::try_from(StarkFelt::from(1_u8)).unwrap(),
+        to_address: l1_handler_transaction.contract_address,
+        entry_point_selector: l1_handler_transaction.entry_point_selector,
+        payload: vec![StarkFelt::from(2_u8)],
+        nonce: l1_handler_transaction.nonce,
+    };
+    let pending_message = L1ToL2Message { l1_transaction_hash, ..Default::default() };
+    let l1_handler_transaction_hash = body.transaction_hashes[0];
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), body)
+        .unwrap()
+        .append_l1_to_l2_messages(&[consumed_message, pending_message], BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // The message that wasn't consumed yet is omitted.
+    let expected_status = L1HandlerTransactionStatus {
+        transaction_hash: l1_handler_transaction_hash,
+        finality_status: TransactionFinalityStatus::AcceptedOnL2,
+        execution_status: TransactionExecutionStatus::Succeeded,
+    };
+    let res = module
+        .call::<_, Vec<L1HandlerTransactionStatus>>(METHOD_NAME, [l1_transaction_hash])
+        .await
+        .unwrap();
+    assert_eq!(res, vec![expected_status.clone()]);
+
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .update_base_layer_block_marker(&BlockNumber(1))
+        .unwrap()
+        .commit()
+        .unwrap();
+    let res = module
+        .call::<_, Vec<L1HandlerTransactionStatus>>(METHOD_NAME, [l1_transaction_hash])
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        vec![L1HandlerTransactionStatus {
+            finality_status: TransactionFinalityStatus::AcceptedOnL1,
+            ..expected_status
+        }]
+    );
+
+    let err = module
+        .call::<_, Vec<L1HandlerTransactionStatus>>(METHOD_NAME, [L1TransactionHash([2; 32])])
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        Error::Call(err) if err == ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND)
+    );
+}
@@ -1,11 +1,20 @@
 pub mod api;
 pub mod block;
+pub mod block_transactions;
+pub mod block_with_receipts;
 pub mod broadcasted_transaction;
+pub mod capabilities;
 pub mod deprecated_contract_class;
 pub mod error;
 #[cfg(test)]
 mod execution_test;
+pub mod l1_to_l2_messages;
+pub mod raw_transactions;
+pub mod simulation_sessions;
 pub mod state;
+pub mod state_overrides;
+pub mod storage_proof;
+pub mod subscriptions;
 pub mod transaction;
 pub mod write_api_error;
 pub mod write_api_result;
@@ -0,0 +1,47 @@
+//! An API for reading the raw JSON the feeder gateway returned for a transaction and its receipt.
+//! The node keeps it for transactions that have data it doesn't model, such as fields or versions
+//! that newer versions of the feeder gateway added, so that the data can still be read.
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_common::raw_transaction::RawTransaction;
+use papyrus_proc_macros::versioned_rpc;
+use papyrus_storage::body::BodyStorageReader;
+use starknet_api::transaction::TransactionHash;
+use tracing::instrument;
+
+use super::api::api_impl::JsonRpcServerV0_6Impl;
+use super::error::TRANSACTION_HASH_NOT_FOUND;
+use crate::{internal_server_error, verify_body_not_pruned, verify_storage_scope};
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait RawTransactionsJsonRpc {
+    /// Gets the raw JSON of an accepted transaction and its receipt given the transaction hash.
+    /// Returns null if the node models all the data of the transaction.
+    #[method(name = "papyrusGetRawTransaction")]
+    async fn get_raw_transaction(
+        &self,
+        transaction_hash: TransactionHash,
+    ) -> RpcResult<Option<RawTransaction>>;
+}
+
+#[async_trait]
+impl RawTransactionsJsonRpcV0_6Server for JsonRpcServerV0_6Impl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn get_raw_transaction(
+        &self,
+        transaction_hash: TransactionHash,
+    ) -> RpcResult<Option<RawTransaction>> {
+        verify_storage_scope(&self.storage_reader)?;
+
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let transaction_index = txn
+            .get_transaction_idx_by_hash(&transaction_hash)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(TRANSACTION_HASH_NOT_FOUND))?;
+        verify_body_not_pruned(&txn, transaction_index.0)?;
+        txn.get_raw_transaction(transaction_index).map_err(internal_server_error)
+    }
+}
@@ -0,0 +1,259 @@
+//! An API, served only if enabled in the config, for simulating many series of transactions on top
+//! of the same state. Each session keeps the changes of its simulations, so every simulation runs
+//! on top of the previous ones. See [`SimulationSession`].
+//!
+//! The number of open sessions is bounded, and sessions that weren't used for a while are closed.
+//! A session whose block was reverted is closed on its next use, and a session whose simulation
+//! timed out is closed right away.
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use jsonrpsee::types::ErrorObjectOwned;
+use lru::LruCache;
+use papyrus_execution::contract_class_cache::ContractClassCache;
+use papyrus_execution::simulation_session::SimulationSession;
+use papyrus_execution::{
+    simulation_flags_to_execution_flags,
+    ExecutionConfigByBlock,
+    ExecutionError,
+};
+use papyrus_proc_macros::versioned_rpc;
+use papyrus_storage::StorageReader;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::ChainId;
+use starknet_api::state::StateNumber;
+use tracing::instrument;
+
+use super::api::{
+    execution_error_to_error_object_owned,
+    FeeEstimate,
+    SimulatedTransaction,
+    SimulationFlag,
+};
+use super::block::{get_accepted_block_number, BlockNotRevertedValidator};
+use super::broadcasted_transaction::BroadcastedTransaction;
+use super::error::{JsonRpcError, BLOCK_NOT_FOUND};
+use crate::api::{BlockId, Tag};
+use crate::{internal_server_error, run_execution};
+
+pub const SIMULATION_SESSION_NOT_FOUND: JsonRpcError<String> =
+    JsonRpcError { code: INVALID_PARAMS_CODE, message: "Simulation session not found", data: None };
+
+/// The identifier of a simulation session.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub struct SimulationSessionId(pub u64);
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait SimulationSessionsJsonRpc {
+    /// Opens a simulation session on top of the state after the given block. Sessions can't be
+    /// opened on top of the pending block.
+    #[method(name = "papyrusOpenSimulationSession")]
+    async fn open_simulation_session(&self, block_id: BlockId) -> RpcResult<SimulationSessionId>;
+
+    /// Simulates the given transactions on top of the state of the session, including the changes
+    /// of the previous simulations in it, and keeps their changes in the session. Once a simulation
+    /// fails, the session can only be closed.
+    #[method(name = "papyrusSimulateInSession")]
+    async fn simulate_in_session(
+        &self,
+        session_id: SimulationSessionId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+    ) -> RpcResult<Vec<SimulatedTransaction>>;
+
+    /// Closes the simulation session. Returns false if there's no such session.
+    #[method(name = "papyrusCloseSimulationSession")]
+    async fn close_simulation_session(&self, session_id: SimulationSessionId) -> RpcResult<bool>;
+}
+
+/// Rpc server of the simulation sessions API.
+pub struct SimulationSessionsV0_6Impl {
+    chain_id: ChainId,
+    execution_config: ExecutionConfigByBlock,
+    class_cache: ContractClassCache,
+    execution_timeout: Duration,
+    session_ttl: Duration,
+    storage_reader: StorageReader,
+    sessions: Mutex<LruCache<SimulationSessionId, OpenSession>>,
+    next_session_id: AtomicU64,
+}
+
+struct OpenSession {
+    session: Arc<Mutex<SimulationSession>>,
+    // Detects a revert of the block the session is on top of, after which the results of the
+    // session's simulations are wrong.
+    block_not_reverted_validator: BlockNotRevertedValidator,
+    last_used: Instant,
+}
+
+impl SimulationSessionsV0_6Impl {
+    /// Creates the server. Opening a session when there are `max_sessions` open sessions closes the
+    /// least recently used one, and sessions that weren't used for `session_ttl` are closed.
+    pub fn new(
+        chain_id: ChainId,
+        execution_config: ExecutionConfigByBlock,
+        class_cache: ContractClassCache,
+        execution_timeout: Duration,
+        max_sessions: NonZeroUsize,
+        session_ttl: Duration,
+        storage_reader: StorageReader,
+    ) -> Self {
+        Self {
+            chain_id,
+            execution_config,
+            class_cache,
+            execution_timeout,
+            session_ttl,
+            storage_reader,
+            sessions: Mutex::new(LruCache::new(max_sessions)),
+            next_session_id: AtomicU64::new(0),
+        }
+    }
+
+    // Returns the session and marks it as used now, unless it expired.
+    fn get_session(
+        &self,
+        session_id: SimulationSessionId,
+    ) -> RpcResult<(Arc<Mutex<SimulationSession>>, BlockNotRevertedValidator)> {
+        let mut sessions = self.sessions.lock().expect("Failed to lock the simulation sessions.");
+        let open_session = sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| ErrorObjectOwned::from(SIMULATION_SESSION_NOT_FOUND))?;
+        if open_session.last_used.elapsed() > self.session_ttl {
+            sessions.pop(&session_id);
+            return Err(SIMULATION_SESSION_NOT_FOUND.into());
+        }
+        open_session.last_used = Instant::now();
+        Ok((open_session.session.clone(), open_session.block_not_reverted_validator.clone()))
+    }
+
+    fn close_session(&self, session_id: SimulationSessionId) -> bool {
+        self.sessions
+            .lock()
+            .expect("Failed to lock the simulation sessions.")
+            .pop(&session_id)
+            .is_some()
+    }
+}
+
+#[async_trait]
+impl SimulationSessionsJsonRpcV0_6Server for SimulationSessionsV0_6Impl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn open_simulation_session(&self, block_id: BlockId) -> RpcResult<SimulationSessionId> {
+        if let BlockId::Tag(Tag::Pending) = block_id {
+            return Err(BLOCK_NOT_FOUND.into());
+        }
+        let storage_txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&storage_txn, block_id)?;
+        let block_not_reverted_validator =
+            BlockNotRevertedValidator::new(block_number, &storage_txn)?;
+        drop(storage_txn);
+        let state_number = StateNumber::right_after_block(block_number);
+        let block_execution_config = self
+            .execution_config
+            .get_execution_config_for_block(block_number)
+            .map_err(|err| {
+                internal_server_error(format!("Failed to get execution config: {}", err))
+            })?
+            .clone();
+        let chain_id = self.chain_id.clone();
+        let reader = self.storage_reader.clone();
+        let class_cache = self.class_cache.clone();
+
+        let session = tokio::task::spawn_blocking(move || {
+            SimulationSession::new(
+                chain_id,
+                reader,
+                None,
+                state_number,
+                block_number,
+                &block_execution_config,
+                Some(class_cache),
+            )
+        })
+        .await
+        .map_err(internal_server_error)?
+        .map_err(execution_error_to_error_object_owned)?;
+
+        let session_id = SimulationSessionId(self.next_session_id.fetch_add(1, Ordering::Relaxed));
+        let mut sessions = self.sessions.lock().expect("Failed to lock the simulation sessions.");
+        // The least recently used sessions are the first to expire.
+        while sessions
+            .peek_lru()
+            .is_some_and(|(_, open_session)| open_session.last_used.elapsed() > self.session_ttl)
+        {
+            sessions.pop_lru();
+        }
+        sessions.put(
+            session_id,
+            OpenSession {
+                session: Arc::new(Mutex::new(session)),
+                block_not_reverted_validator,
+                last_used: Instant::now(),
+            },
+        );
+        Ok(session_id)
+    }
+
+    #[instrument(skip(self, transactions), level = "debug", err)]
+    async fn simulate_in_session(
+        &self,
+        session_id: SimulationSessionId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        let (session, block_not_reverted_validator) = self.get_session(session_id)?;
+        let executable_txns =
+            transactions.into_iter().map(|tx| tx.try_into()).collect::<Result<_, _>>()?;
+        let flags = simulation_flags_to_execution_flags(&simulation_flags);
+
+        let simulation_results = run_execution(self.execution_timeout, move |abort_handle| {
+            // A session runs one simulation at a time. The simulations don't wait for each other,
+            // since the running one may be a simulation that timed out and didn't stop yet.
+            let mut session = match session.try_lock() {
+                Ok(session) => session,
+                Err(TryLockError::WouldBlock) => return Err(ExecutionError::SimulationSessionBusy),
+                Err(TryLockError::Poisoned(_)) => panic!("Failed to lock the simulation session."),
+            };
+            session.simulate_transactions(executable_txns, None, flags, abort_handle)
+        })
+        .await?;
+        // A simulation that timed out holds the session until it stops, and the session can't be
+        // used after it anyway. The session is closed right away instead of when it expires.
+        if let Err(ExecutionError::ExecutionAborted) = simulation_results {
+            self.close_session(session_id);
+        }
+        let simulation_results =
+            simulation_results.map_err(execution_error_to_error_object_owned)?;
+
+        // The session can't be used anymore once its block was reverted.
+        if let Err(err) = block_not_reverted_validator.validate(&self.storage_reader) {
+            self.close_session(session_id);
+            return Err(err);
+        }
+
+        Ok(simulation_results
+            .into_iter()
+            .map(|simulation_output| SimulatedTransaction {
+                fee_estimation: FeeEstimate::from(
+                    simulation_output.gas_price,
+                    simulation_output.fee,
+                    simulation_output.price_unit,
+                ),
+                transaction_trace: simulation_output.into(),
+            })
+            .collect())
+    }
+
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn close_simulation_session(&self, session_id: SimulationSessionId) -> RpcResult<bool> {
+        Ok(self.close_session(session_id))
+    }
+}
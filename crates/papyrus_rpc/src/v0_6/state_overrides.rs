@@ -0,0 +1,166 @@
+//! An API for executing calls and simulating transactions as if changes were applied on top of the
+//! state, for checking how they would behave in a hypothetical state. The changes are never written
+//! to the state.
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_execution::objects::StateOverrides;
+use papyrus_proc_macros::versioned_rpc;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_client::writer::objects::transaction::DeprecatedContractClass;
+use tracing::instrument;
+
+use super::api::api_impl::JsonRpcServerV0_6Impl;
+use super::api::{user_deprecated_contract_class_to_sn_api, SimulatedTransaction, SimulationFlag};
+use super::broadcasted_transaction::BroadcastedTransaction;
+use super::state::{ContractClass, StorageEntry};
+use crate::api::{BlockId, CallRequest};
+
+/// Changes to apply on top of the state for the duration of an execution.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StateOverride {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contracts: Vec<ContractStateOverride>,
+    /// Classes that are treated as declared. They can be set as the classes of contracts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub declared_classes: Vec<DeclaredClassOverride>,
+}
+
+/// Changes to the state of a single contract.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContractStateOverride {
+    pub address: ContractAddress,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Nonce>,
+    /// The class of the contract. The class must be declared, either in the state or by the
+    /// override. If the contract isn't deployed, it's treated as if it was deployed with this
+    /// class.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_hash: Option<ClassHash>,
+    /// The balance of the contract in the ETH fee token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<StarkFelt>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub storage_entries: Vec<StorageEntry>,
+}
+
+/// A class that's treated as declared with the given hash. The hash isn't verified.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DeclaredClassOverride {
+    pub class_hash: ClassHash,
+    pub contract_class: OverriddenContractClass,
+}
+
+/// The definition of an overridden class, in the format of the classes of declare transactions.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OverriddenContractClass {
+    Sierra(ContractClass),
+    Cairo0(DeprecatedContractClass),
+}
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait StateOverridesJsonRpc {
+    /// Executes a call like `starknet_call`, as if the given changes were applied on top of the
+    /// state of the given block.
+    #[method(name = "papyrusCall")]
+    async fn papyrus_call(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<StarkFelt>>;
+
+    /// Simulates transactions like `starknet_simulateTransactions`, as if the given changes were
+    /// applied on top of the state of the given block.
+    #[method(name = "papyrusSimulateTransactions")]
+    async fn papyrus_simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<SimulatedTransaction>>;
+}
+
+#[async_trait]
+impl StateOverridesJsonRpcV0_6Server for JsonRpcServerV0_6Impl {
+    #[instrument(skip(self), level = "debug", err, ret)]
+    async fn papyrus_call(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<StarkFelt>> {
+        let state_overrides = StateOverrides::try_from(state_override)?;
+        self.call_with_state_overrides(request, block_id, Some(state_overrides)).await
+    }
+
+    #[instrument(skip(self, transactions, state_override), level = "debug", err, ret)]
+    async fn papyrus_simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+        state_override: StateOverride,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        let state_overrides = StateOverrides::try_from(state_override)?;
+        self.simulate_transactions_with_state_overrides(
+            block_id,
+            transactions,
+            simulation_flags,
+            Some(state_overrides),
+        )
+        .await
+    }
+}
+
+impl TryFrom<StateOverride> for StateOverrides {
+    type Error = ErrorObjectOwned;
+
+    fn try_from(state_override: StateOverride) -> Result<Self, Self::Error> {
+        let mut state_overrides = StateOverrides::default();
+        for ContractStateOverride { address, nonce, class_hash, balance, storage_entries } in
+            state_override.contracts
+        {
+            if let Some(nonce) = nonce {
+                state_overrides.nonces.insert(address, nonce);
+            }
+            if let Some(class_hash) = class_hash {
+                state_overrides.class_hashes.insert(address, class_hash);
+            }
+            if let Some(balance) = balance {
+                state_overrides.balances.insert(address, balance);
+            }
+            if !storage_entries.is_empty() {
+                state_overrides.storage_diffs.entry(address).or_default().extend(
+                    storage_entries.into_iter().map(|StorageEntry { key, value }| (key, value)),
+                );
+            }
+        }
+        for DeclaredClassOverride { class_hash, contract_class } in state_override.declared_classes
+        {
+            let class = match contract_class {
+                OverriddenContractClass::Sierra(class) => {
+                    ApiContractClass::ContractClass(starknet_api::state::ContractClass {
+                        sierra_program: class.sierra_program,
+                        entry_point_by_type: class.entry_points_by_type.to_hash_map(),
+                        abi: class.abi,
+                    })
+                }
+                OverriddenContractClass::Cairo0(class) => {
+                    ApiContractClass::DeprecatedContractClass(
+                        user_deprecated_contract_class_to_sn_api(class)?,
+                    )
+                }
+            };
+            state_overrides.classes.insert(class_hash, class);
+        }
+        Ok(state_overrides)
+    }
+}
@@ -0,0 +1,229 @@
+//! An API for proving the state of Starknet against the global state root of a block, with the
+//! nodes of the state tries. Served only for the blocks whose tries the node maintains, see the
+//! `maintain_state_tries` sync config.
+#[cfg(test)]
+#[path = "storage_proof_test.rs"]
+mod storage_proof_test;
+
+use async_trait::async_trait;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::ErrorObjectOwned;
+use papyrus_proc_macros::versioned_rpc;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::state_trie::{self, StateTrieStorageReader};
+use papyrus_storage::StorageReader;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockHash;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StateNumber;
+use tracing::instrument;
+
+use super::block::get_accepted_block_number;
+use super::error::{BLOCK_NOT_FOUND, STORAGE_PROOF_NOT_SUPPORTED};
+use crate::api::{BlockId, Tag};
+use crate::internal_server_error;
+
+/// The storage keys of a contract to prove.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ContractStorageKeys {
+    pub contract_address: ContractAddress,
+    pub storage_keys: Vec<StarkFelt>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BinaryNode {
+    pub left: StarkHash,
+    pub right: StarkHash,
+}
+
+/// A node with a single child. The path from the node to its child is given by the `length` least
+/// significant bits of `path`.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct EdgeNode {
+    pub path: StarkFelt,
+    pub length: u8,
+    pub child: StarkHash,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MerkleNode {
+    Binary(BinaryNode),
+    Edge(EdgeNode),
+}
+
+impl From<state_trie::TrieNode> for MerkleNode {
+    fn from(node: state_trie::TrieNode) -> Self {
+        match node {
+            state_trie::TrieNode::Binary(state_trie::BinaryNode { left, right }) => {
+                Self::Binary(BinaryNode { left, right })
+            }
+            state_trie::TrieNode::Edge(state_trie::EdgeNode { path, length, child }) => {
+                Self::Edge(EdgeNode { path, length, child })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct NodeHashToNode {
+    pub node_hash: StarkHash,
+    pub node: MerkleNode,
+}
+
+/// The data the leaf of a contract in the contracts trie is the hash of.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ContractLeafData {
+    pub nonce: Nonce,
+    pub class_hash: ClassHash,
+    pub storage_root: StarkHash,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ContractsProof {
+    pub nodes: Vec<NodeHashToNode>,
+    /// The data of the leaves of the requested contracts, in the order they were requested.
+    pub contract_leaves_data: Vec<ContractLeafData>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct GlobalRoots {
+    pub contracts_tree_root: StarkHash,
+    pub classes_tree_root: StarkHash,
+    pub block_hash: BlockHash,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct StorageProof {
+    pub classes_proof: Vec<NodeHashToNode>,
+    pub contracts_proof: ContractsProof,
+    /// The proofs of the requested storage keys, in the order their contracts were requested.
+    pub contracts_storage_proofs: Vec<Vec<NodeHashToNode>>,
+    pub global_roots: GlobalRoots,
+}
+
+#[versioned_rpc("V0_6")]
+#[async_trait]
+pub trait StorageProofJsonRpc {
+    /// Returns the nodes of the state tries that prove the given classes, contracts and storage
+    /// keys against the global state root of the given block. The proof of a key that isn't in
+    /// its trie proves that it isn't there.
+    #[method(name = "getStorageProof")]
+    async fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<ClassHash>>,
+        contract_addresses: Option<Vec<ContractAddress>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof>;
+}
+
+/// Rpc server of the storage proof API.
+pub struct StorageProofV0_6Impl {
+    storage_reader: StorageReader,
+}
+
+impl StorageProofV0_6Impl {
+    pub fn new(storage_reader: StorageReader) -> Self {
+        Self { storage_reader }
+    }
+}
+
+fn to_node_mapping(
+    nodes: impl IntoIterator<Item = (StarkHash, state_trie::TrieNode)>,
+) -> Vec<NodeHashToNode> {
+    nodes
+        .into_iter()
+        .map(|(node_hash, node)| NodeHashToNode { node_hash, node: node.into() })
+        .collect()
+}
+
+#[async_trait]
+impl StorageProofJsonRpcV0_6Server for StorageProofV0_6Impl {
+    #[instrument(skip(self), level = "debug", err)]
+    async fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<ClassHash>>,
+        contract_addresses: Option<Vec<ContractAddress>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof> {
+        if let BlockId::Tag(Tag::Pending) = block_id {
+            return Err(BLOCK_NOT_FOUND.into());
+        }
+        let txn = self.storage_reader.begin_ro_txn().map_err(internal_server_error)?;
+        let block_number = get_accepted_block_number(&txn, block_id)?;
+        let roots = txn
+            .get_state_trie_roots(block_number)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| ErrorObjectOwned::from(STORAGE_PROOF_NOT_SUPPORTED))?;
+        let block_hash = txn
+            .get_block_header(block_number)
+            .map_err(internal_server_error)?
+            .ok_or_else(|| {
+                internal_server_error(format!("Missing header of block {block_number}."))
+            })?
+            .block_hash;
+
+        let class_keys: Vec<_> =
+            class_hashes.unwrap_or_default().into_iter().map(|class_hash| class_hash.0).collect();
+        let classes_proof = txn
+            .get_state_trie_nodes(roots.classes_trie_root, &class_keys)
+            .map_err(internal_server_error)?;
+
+        let contract_addresses = contract_addresses.unwrap_or_default();
+        let contract_keys: Vec<_> =
+            contract_addresses.iter().map(|contract_address| *contract_address.0.key()).collect();
+        let contracts_proof = txn
+            .get_state_trie_nodes(roots.contracts_trie_root, &contract_keys)
+            .map_err(internal_server_error)?;
+        let state_reader = txn.get_state_reader().map_err(internal_server_error)?;
+        let state_number = StateNumber::right_after_block(block_number);
+        let mut contract_leaves_data = Vec::with_capacity(contract_addresses.len());
+        for contract_address in &contract_addresses {
+            contract_leaves_data.push(ContractLeafData {
+                nonce: state_reader
+                    .get_nonce_at(state_number, contract_address)
+                    .map_err(internal_server_error)?
+                    .unwrap_or_default(),
+                class_hash: state_reader
+                    .get_class_hash_at(state_number, contract_address)
+                    .map_err(internal_server_error)?
+                    .unwrap_or_default(),
+                storage_root: txn
+                    .get_contract_storage_trie_root(block_number, contract_address)
+                    .map_err(internal_server_error)?,
+            });
+        }
+
+        let mut contracts_storage_proofs = vec![];
+        for ContractStorageKeys { contract_address, storage_keys } in
+            contracts_storage_keys.unwrap_or_default()
+        {
+            let storage_root = txn
+                .get_contract_storage_trie_root(block_number, &contract_address)
+                .map_err(internal_server_error)?;
+            let storage_proof = txn
+                .get_state_trie_nodes(storage_root, &storage_keys)
+                .map_err(internal_server_error)?;
+            contracts_storage_proofs.push(to_node_mapping(storage_proof));
+        }
+
+        Ok(StorageProof {
+            classes_proof: to_node_mapping(classes_proof),
+            contracts_proof: ContractsProof {
+                nodes: to_node_mapping(contracts_proof),
+                contract_leaves_data,
+            },
+            contracts_storage_proofs,
+            global_roots: GlobalRoots {
+                contracts_tree_root: roots.contracts_trie_root,
+                classes_tree_root: roots.classes_trie_root,
+                block_hash,
+            },
+        })
+    }
+}
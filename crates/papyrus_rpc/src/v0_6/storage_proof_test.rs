@@ -0,0 +1,135 @@
+use assert_matches::assert_matches;
+use indexmap::{indexmap, IndexMap};
+use jsonrpsee::core::Error;
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::{rpc_params, RpcModule};
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::state_trie::{StateTrieStorageReader, StateTrieStorageWriter};
+use papyrus_storage::test_utils::get_test_storage;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::{StateDiff, StorageKey};
+use starknet_api::{patricia_key, stark_felt};
+
+use super::{
+    ContractLeafData,
+    ContractStorageKeys,
+    GlobalRoots,
+    StorageProof,
+    StorageProofJsonRpcV0_6Server,
+    StorageProofV0_6Impl,
+};
+use crate::api::{BlockHashOrNumber, BlockId};
+use crate::v0_6::error::STORAGE_PROOF_NOT_SUPPORTED;
+
+const METHOD_NAME: &str = "starknet_V0_6_getStorageProof";
+
+#[tokio::test]
+async fn get_storage_proof() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let module: RpcModule<_> = StorageProofV0_6Impl::new(storage_reader.clone()).into_rpc();
+
+    let contract_address = ContractAddress(patricia_key!("0x11"));
+    let class_hash = ClassHash(stark_felt!("0x1"));
+    let storage_key = stark_felt!("0x2");
+    let state_diff = StateDiff {
+        deployed_contracts: indexmap! { contract_address => class_hash },
+        storage_diffs: indexmap! {
+            contract_address => indexmap! {
+                StorageKey(patricia_key!("0x2")) => StarkFelt::from(3_u8),
+            },
+        },
+        nonces: indexmap! { contract_address => Nonce(StarkFelt::from(1_u8)) },
+        ..Default::default()
+    };
+    let header = BlockHeader { block_hash: BlockHash(stark_felt!("0x100")), ..Default::default() };
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &header)
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff, IndexMap::new())
+        .unwrap()
+        .update_state_tries(BlockNumber(0))
+        .unwrap()
+        .commit()
+        .unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let roots = txn.get_state_trie_roots(BlockNumber(0)).unwrap().unwrap();
+    let storage_root =
+        txn.get_contract_storage_trie_root(BlockNumber(0), &contract_address).unwrap();
+    drop(txn);
+
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(0)));
+    let contracts_storage_keys =
+        vec![ContractStorageKeys { contract_address, storage_keys: vec![storage_key] }];
+    let res = module
+        .call::<_, StorageProof>(
+            METHOD_NAME,
+            rpc_params![
+                block_id,
+                Option::<Vec<ClassHash>>::None,
+                vec![contract_address],
+                contracts_storage_keys
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        res.global_roots,
+        GlobalRoots {
+            contracts_tree_root: roots.contracts_trie_root,
+            classes_tree_root: StarkHash::default(),
+            block_hash: header.block_hash,
+        }
+    );
+    assert_eq!(
+        res.contracts_proof.contract_leaves_data,
+        vec![ContractLeafData { nonce: Nonce(StarkFelt::from(1_u8)), class_hash, storage_root }]
+    );
+    // A trie with a single leaf has a single edge node from its root to the leaf.
+    assert_eq!(res.contracts_proof.nodes.len(), 1);
+    assert_eq!(res.contracts_proof.nodes[0].node_hash, roots.contracts_trie_root);
+    assert_eq!(res.contracts_storage_proofs.len(), 1);
+    assert_eq!(res.contracts_storage_proofs[0].len(), 1);
+    assert_eq!(res.contracts_storage_proofs[0][0].node_hash, storage_root);
+    assert!(res.classes_proof.is_empty());
+
+    // A block whose state diff wasn't applied to the tries.
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(
+            BlockNumber(1),
+            &BlockHeader {
+                block_number: BlockNumber(1),
+                parent_hash: header.block_hash,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .append_body(BlockNumber(1), BlockBody::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(1), StateDiff::default(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+    let block_id = BlockId::HashOrNumber(BlockHashOrNumber::Number(BlockNumber(1)));
+    let err = module
+        .call::<_, StorageProof>(
+            METHOD_NAME,
+            rpc_params![block_id, Option::<Vec<ClassHash>>::None, vec![contract_address]],
+        )
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        Error::Call(err) if err == ErrorObjectOwned::from(STORAGE_PROOF_NOT_SUPPORTED)
+    );
+}
@@ -0,0 +1,437 @@
+//! Subscriptions to the data the node stores, served over WebSocket connections.
+//!
+//! The messages of a WebSocket connection don't pass through the middleware that routes requests
+//! by their path, so the subscription methods are named with their version, like
+//! `starknet_V0_6_subscribeNewHeads`.
+#[cfg(test)]
+#[path = "subscriptions_test.rs"]
+mod subscriptions_test;
+
+use std::cmp::min;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
+use papyrus_storage::body::{BodyStorageReader, TransactionIndex};
+use papyrus_storage::db::RO;
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageReader, StorageResult, StorageTxn};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::ContractAddress;
+use starknet_api::transaction::{EventKey, TransactionHash, TransactionOffsetInBlock};
+use starknet_client::reader::objects::transaction::Transaction as ClientTransaction;
+use starknet_client::reader::{PendingData, ReaderClientError};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, watch, RwLock};
+use tracing::{debug, instrument, warn};
+
+use super::block::BlockHeader;
+use super::error::{TOO_MANY_BLOCKS_BACK, TOO_MANY_KEYS_IN_FILTER};
+use super::transaction::{Event, TransactionWithHash};
+use crate::{verify_body_not_pruned, verify_storage_scope};
+
+#[rpc(server, client, namespace = "starknet")]
+pub trait SubscriptionsJsonRpcV0_6 {
+    /// Sends the header of every block the node stores from now on. A block is stored once both
+    /// its body and its state diff are stored.
+    #[subscription(
+        name = "V0_6_subscribeNewHeads" => "V0_6_subscriptionNewHeads",
+        unsubscribe = "V0_6_unsubscribeNewHeads",
+        item = BlockHeader
+    )]
+    async fn subscribe_new_heads(&self) -> SubscriptionResult;
+
+    /// Sends the events that match the filter, of every block the node stores from now on, or from
+    /// the filter's from_block if it's given. The from_block can be up to 1024 blocks back.
+    #[subscription(
+        name = "V0_6_subscribeEvents" => "V0_6_subscriptionEvents",
+        unsubscribe = "V0_6_unsubscribeEvents",
+        item = Event
+    )]
+    async fn subscribe_events(&self, filter: EventSubscriptionFilter) -> SubscriptionResult;
+
+    /// Sends the transactions that are added to the pending block from now on, each one once. The
+    /// transactions are sent by their hashes, or in full if transaction_details is true.
+    #[subscription(
+        name = "V0_6_subscribePendingTransactions" => "V0_6_subscriptionPendingTransactions",
+        unsubscribe = "V0_6_unsubscribePendingTransactions",
+        item = PendingTransaction
+    )]
+    async fn subscribe_pending_transactions(
+        &self,
+        transaction_details: Option<bool>,
+    ) -> SubscriptionResult;
+}
+
+// How often the pending block is checked for new transactions, for all the pending transactions
+// subscriptions.
+const PENDING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// The maximal number of stored blocks whose events an events subscription sends before the
+// blocks the node stores from now on.
+const MAX_REPLAYED_BLOCKS: u64 = 1024;
+
+// The maximal number of stored blocks whose data a subscription reads in a single transaction. A
+// subscription that fell behind catches up in chunks of blocks, so it doesn't hold the data of all
+// the blocks it missed at once.
+const MAX_BLOCKS_PER_READ: u64 = 100;
+
+// How often a subscription checks the storage for new blocks without being notified by the sync.
+// A node that doesn't run the sync, for example one that serves a storage synced by another node,
+// is never notified.
+const STORAGE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The events an events subscription sends.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventSubscriptionFilter {
+    /// The block to start from. The events of the stored blocks from it are sent first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_block: Option<BlockNumber>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<ContractAddress>,
+    #[serde(default)]
+    pub keys: Vec<HashSet<EventKey>>,
+}
+
+impl EventSubscriptionFilter {
+    fn matches(&self, event: &starknet_api::transaction::Event) -> bool {
+        if self.address.is_some_and(|address| address != event.from_address) {
+            return false;
+        }
+        self.keys.iter().enumerate().all(|(i, keys)| {
+            event.content.keys.len() > i
+                && (keys.is_empty() || keys.contains(&event.content.keys[i]))
+        })
+    }
+}
+
+/// A transaction that was added to the pending block.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PendingTransaction {
+    Hash(TransactionHash),
+    Full(TransactionWithHash),
+}
+
+// The transactions of the pending block, shared by all the pending transactions subscriptions.
+#[derive(Debug, Default, Clone)]
+struct PendingTransactions {
+    parent_block_hash: BlockHash,
+    transactions: Arc<Vec<ClientTransaction>>,
+}
+
+/// Rpc server of the subscriptions API.
+pub struct SubscriptionsV0_6Impl {
+    storage_reader: StorageReader,
+    // Notified by the sync with the number of every block it stores.
+    new_block_sender: broadcast::Sender<BlockNumber>,
+    // Updated with the transactions of the pending block whenever new ones are added to it.
+    pending_transactions: watch::Receiver<PendingTransactions>,
+    max_events_keys: usize,
+}
+
+impl SubscriptionsV0_6Impl {
+    pub fn new(
+        storage_reader: StorageReader,
+        new_block_sender: broadcast::Sender<BlockNumber>,
+        pending_data: Arc<RwLock<PendingData>>,
+        max_events_keys: usize,
+    ) -> Self {
+        let (pending_transactions_sender, pending_transactions) =
+            watch::channel(PendingTransactions::default());
+        tokio::spawn(watch_pending_transactions(pending_data, pending_transactions_sender));
+        Self { storage_reader, new_block_sender, pending_transactions, max_events_keys }
+    }
+}
+
+// Checks the pending block, which the sync updates, for new transactions and publishes them to the
+// pending transactions subscriptions. Returns once the server is dropped.
+async fn watch_pending_transactions(
+    pending_data: Arc<RwLock<PendingData>>,
+    sender: watch::Sender<PendingTransactions>,
+) {
+    let mut poll_interval = tokio::time::interval(PENDING_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = sender.closed() => return,
+            _ = poll_interval.tick() => {}
+        }
+        let pending_block = &pending_data.read().await.block;
+        // The pending block only grows until it's replaced by a block with a new parent, so the
+        // transactions are cloned only when they change.
+        sender.send_if_modified(|pending_transactions| {
+            if pending_transactions.parent_block_hash == pending_block.parent_block_hash
+                && pending_transactions.transactions.len() == pending_block.transactions.len()
+            {
+                return false;
+            }
+            *pending_transactions = PendingTransactions {
+                parent_block_hash: pending_block.parent_block_hash,
+                transactions: Arc::new(pending_block.transactions.clone()),
+            };
+            true
+        });
+    }
+}
+
+// Returns the transaction in the format the subscriptions send.
+fn to_full_pending_transaction(
+    client_transaction: ClientTransaction,
+) -> Result<PendingTransaction, String> {
+    let transaction_hash = client_transaction.transaction_hash();
+    let starknet_api_transaction: starknet_api::transaction::Transaction =
+        client_transaction.try_into().map_err(|err: ReaderClientError| err.to_string())?;
+    let transaction = starknet_api_transaction
+        .try_into()
+        .map_err(|err: ErrorObjectOwned| err.message().to_owned())?;
+    Ok(PendingTransaction::Full(TransactionWithHash { transaction, transaction_hash }))
+}
+
+// The first block that doesn't have both a body and a state diff stored. The sync notifies of a
+// block once both are stored.
+fn get_stored_blocks_marker(txn: &StorageTxn<'_, RO>) -> StorageResult<BlockNumber> {
+    Ok(min(txn.get_state_marker()?, txn.get_body_marker()?))
+}
+
+// Returns the first chunk of blocks to read, out of the blocks from next_block to the stored
+// blocks marker. The chunk is empty if there are no such blocks.
+fn next_blocks_chunk(
+    next_block: BlockNumber,
+    stored_blocks_marker: BlockNumber,
+) -> Range<BlockNumber> {
+    let chunk_end = min(stored_blocks_marker, BlockNumber(next_block.0 + MAX_BLOCKS_PER_READ));
+    next_block..chunk_end.max(next_block)
+}
+
+// Returns the headers of the stored blocks in the given range.
+fn read_headers(
+    txn: &StorageTxn<'_, RO>,
+    blocks: Range<BlockNumber>,
+) -> StorageResult<Vec<BlockHeader>> {
+    let mut headers = vec![];
+    for block_number in (blocks.start.0..blocks.end.0).map(BlockNumber) {
+        if let (Some(header), Some(starknet_version)) =
+            (txn.get_block_header(block_number)?, txn.get_starknet_version(block_number)?)
+        {
+            headers.push((header, starknet_version).into());
+        }
+    }
+    Ok(headers)
+}
+
+// Returns the events of the stored blocks in the given range that match the filter. The blocks
+// whose bodies were pruned are skipped.
+fn read_events(
+    txn: &StorageTxn<'_, RO>,
+    blocks: Range<BlockNumber>,
+    filter: &EventSubscriptionFilter,
+) -> StorageResult<Vec<Event>> {
+    let mut events = vec![];
+    for block_number in (blocks.start.0..blocks.end.0).map(BlockNumber) {
+        let (Some(header), Some(transaction_hashes)) =
+            (txn.get_block_header(block_number)?, txn.get_block_transaction_hashes(block_number)?)
+        else {
+            debug!("Skipping the events of block {block_number}, whose body was pruned.");
+            continue;
+        };
+        for (offset, transaction_hash) in transaction_hashes.into_iter().enumerate() {
+            let transaction_index =
+                TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            let transaction_events =
+                txn.get_transaction_events(transaction_index)?.unwrap_or_default();
+            events.extend(
+                transaction_events.into_iter().filter(|event| filter.matches(event)).map(|event| {
+                    Event {
+                        block_hash: Some(header.block_hash),
+                        block_number: Some(block_number),
+                        transaction_hash,
+                        event,
+                    }
+                }),
+            );
+        }
+    }
+    Ok(events)
+}
+
+#[async_trait]
+impl SubscriptionsJsonRpcV0_6Server for SubscriptionsV0_6Impl {
+    #[instrument(skip(self, pending), level = "debug")]
+    async fn subscribe_new_heads(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+        let mut new_blocks = self.new_block_sender.subscribe();
+        let mut poll_interval = tokio::time::interval(STORAGE_POLL_INTERVAL);
+        let mut next_block = get_stored_blocks_marker(&self.storage_reader.begin_ro_txn()?)?;
+        let sink = pending.accept().await?;
+        loop {
+            // The notifications and the polls only wake the subscription up. The stored blocks
+            // whose headers weren't sent yet are found by the markers, so skipped notifications
+            // are caught up with.
+            tokio::select! {
+                _ = sink.closed() => return Ok(()),
+                _ = poll_interval.tick() => {}
+                new_block = new_blocks.recv() => {
+                    if let Err(RecvError::Closed) = new_block {
+                        return Ok(());
+                    }
+                }
+            }
+            // The headers are read in chunks, each in a transaction that is closed before they
+            // are sent.
+            loop {
+                let headers = {
+                    let txn = self.storage_reader.begin_ro_txn()?;
+                    let stored_blocks_marker = get_stored_blocks_marker(&txn)?;
+                    // Blocks were reverted. The headers of the blocks that replace them are sent
+                    // too.
+                    next_block = next_block.min(stored_blocks_marker);
+                    let blocks = next_blocks_chunk(next_block, stored_blocks_marker);
+                    if blocks.is_empty() {
+                        break;
+                    }
+                    next_block = blocks.end;
+                    read_headers(&txn, blocks)?
+                };
+                for header in headers {
+                    sink.send(SubscriptionMessage::from_json(&header)?).await?;
+                }
+            }
+        }
+    }
+
+    #[instrument(skip(self, pending), level = "debug")]
+    async fn subscribe_events(
+        &self,
+        pending: PendingSubscriptionSink,
+        filter: EventSubscriptionFilter,
+    ) -> SubscriptionResult {
+        if let Err(err) = verify_storage_scope(&self.storage_reader) {
+            pending.reject(err).await;
+            return Ok(());
+        }
+        if filter.keys.len() > self.max_events_keys {
+            pending.reject(ErrorObjectOwned::from(TOO_MANY_KEYS_IN_FILTER)).await;
+            return Ok(());
+        }
+        let mut new_blocks = self.new_block_sender.subscribe();
+        let mut poll_interval = tokio::time::interval(STORAGE_POLL_INTERVAL);
+        let (first_block, verified_replay) = {
+            let txn = self.storage_reader.begin_ro_txn()?;
+            let stored_blocks_marker = get_stored_blocks_marker(&txn)?;
+            let first_block = filter.from_block.unwrap_or(stored_blocks_marker);
+            // Like the chunks of getEvents, the events replayed from the stored blocks are
+            // limited.
+            let verified_replay =
+                if stored_blocks_marker.0.saturating_sub(first_block.0) > MAX_REPLAYED_BLOCKS {
+                    Err(ErrorObjectOwned::from(TOO_MANY_BLOCKS_BACK))
+                } else if first_block < stored_blocks_marker {
+                    verify_body_not_pruned(&txn, first_block)
+                } else {
+                    Ok(())
+                };
+            (first_block, verified_replay)
+        };
+        if let Err(err) = verified_replay {
+            pending.reject(err).await;
+            return Ok(());
+        }
+        let mut next_block = first_block;
+        let sink = pending.accept().await?;
+        loop {
+            // The notifications and the polls only wake the subscription up. The stored blocks
+            // whose events weren't sent yet are found by the markers, so skipped notifications
+            // are caught up with. The events of those blocks are read in chunks, each in a
+            // transaction that is closed before they are sent.
+            loop {
+                let events = {
+                    let txn = self.storage_reader.begin_ro_txn()?;
+                    let stored_blocks_marker = get_stored_blocks_marker(&txn)?;
+                    // Blocks were reverted. The events of the blocks that replace them are sent
+                    // too.
+                    if stored_blocks_marker < next_block {
+                        next_block = stored_blocks_marker.max(first_block);
+                    }
+                    let blocks = next_blocks_chunk(next_block, stored_blocks_marker);
+                    if blocks.is_empty() {
+                        break;
+                    }
+                    next_block = blocks.end;
+                    read_events(&txn, blocks, &filter)?
+                };
+                for event in events {
+                    sink.send(SubscriptionMessage::from_json(&event)?).await?;
+                }
+            }
+
+            tokio::select! {
+                _ = sink.closed() => return Ok(()),
+                _ = poll_interval.tick() => {}
+                new_block = new_blocks.recv() => {
+                    if let Err(RecvError::Closed) = new_block {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    #[instrument(skip(self, pending), level = "debug")]
+    async fn subscribe_pending_transactions(
+        &self,
+        pending: PendingSubscriptionSink,
+        transaction_details: Option<bool>,
+    ) -> SubscriptionResult {
+        let transaction_details = transaction_details.unwrap_or(false);
+        let mut pending_transactions = self.pending_transactions.clone();
+        let sink = pending.accept().await?;
+        // The pending block is published with all its transactions, so the ones that were already
+        // sent are skipped. A new pending block is recognized by its parent.
+        let mut pending_parent_hash: Option<BlockHash> = None;
+        let mut sent_transactions = HashSet::new();
+        loop {
+            let PendingTransactions { parent_block_hash, transactions } =
+                pending_transactions.borrow_and_update().clone();
+            if pending_parent_hash != Some(parent_block_hash) {
+                pending_parent_hash = Some(parent_block_hash);
+                sent_transactions.clear();
+            }
+            for client_transaction in transactions.iter() {
+                let transaction_hash = client_transaction.transaction_hash();
+                if !sent_transactions.insert(transaction_hash) {
+                    continue;
+                }
+                let pending_transaction = if transaction_details {
+                    match to_full_pending_transaction(client_transaction.clone()) {
+                        Ok(pending_transaction) => pending_transaction,
+                        Err(err) => {
+                            warn!(
+                                "Skipping pending transaction {transaction_hash}, which can't be \
+                                 converted: {err}."
+                            );
+                            continue;
+                        }
+                    }
+                } else {
+                    PendingTransaction::Hash(transaction_hash)
+                };
+                sink.send(SubscriptionMessage::from_json(&pending_transaction)?).await?;
+            }
+
+            tokio::select! {
+                _ = sink.closed() => return Ok(()),
+                changed = pending_transactions.changed() => {
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::HashSet;
+use std::iter;
+
+use assert_matches::assert_matches;
+use indexmap::IndexMap;
+use jsonrpsee::core::Error;
+use jsonrpsee::{rpc_params, RpcModule};
+use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::header::{HeaderStorageWriter, StarknetVersion};
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockNumber};
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StateDiff;
+use starknet_api::{patricia_key, stark_felt};
+use test_utils::{get_rng, get_test_body};
+use tokio::sync::broadcast;
+
+use super::{
+    EventSubscriptionFilter,
+    PendingTransaction,
+    SubscriptionsJsonRpcV0_6Server,
+    SubscriptionsV0_6Impl,
+    MAX_BLOCKS_PER_READ,
+    MAX_REPLAYED_BLOCKS,
+};
+use crate::test_utils::{generate_client_transaction_and_rpc_transaction, get_test_pending_data};
+use crate::v0_6::block::BlockHeader;
+use crate::v0_6::error::{TOO_MANY_BLOCKS_BACK, TOO_MANY_KEYS_IN_FILTER};
+use crate::v0_6::transaction::Event;
+
+const NEW_HEADS_METHOD_NAME: &str = "starknet_V0_6_subscribeNewHeads";
+const EVENTS_METHOD_NAME: &str = "starknet_V0_6_subscribeEvents";
+const PENDING_TRANSACTIONS_METHOD_NAME: &str = "starknet_V0_6_subscribePendingTransactions";
+const MAX_EVENTS_KEYS: usize = 10;
+
+#[tokio::test]
+async fn subscribe_new_heads() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let (new_block_sender, _) = broadcast::channel(10);
+    let module: RpcModule<_> = SubscriptionsV0_6Impl::new(
+        storage_reader,
+        new_block_sender.clone(),
+        get_test_pending_data(),
+        MAX_EVENTS_KEYS,
+    )
+    .into_rpc();
+    let mut subscription =
+        module.subscribe_unbounded(NEW_HEADS_METHOD_NAME, rpc_params![]).await.unwrap();
+
+    let header = starknet_api::block::BlockHeader {
+        block_hash: BlockHash(stark_felt!("0x1")),
+        ..Default::default()
+    };
+    let starknet_version = StarknetVersion("0.12.3".to_owned());
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_number, &header)
+        .unwrap()
+        .update_starknet_version(&header.block_number, &starknet_version)
+        .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
+        .append_state_diff(header.block_number, StateDiff::default(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+    // A notification of a block that isn't stored is ignored.
+    new_block_sender.send(BlockNumber(1)).unwrap();
+    new_block_sender.send(header.block_number).unwrap();
+
+    let (notification, _) = subscription.next::<BlockHeader>().await.unwrap().unwrap();
+    assert_eq!(notification, BlockHeader::from((header, starknet_version)));
+}
+
+#[tokio::test]
+async fn subscribe_new_heads_without_notifications() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    // Like in a node that doesn't run the sync, the subscription is never notified of new blocks.
+    let (new_block_sender, _) = broadcast::channel(10);
+    let module: RpcModule<_> = SubscriptionsV0_6Impl::new(
+        storage_reader,
+        new_block_sender,
+        get_test_pending_data(),
+        MAX_EVENTS_KEYS,
+    )
+    .into_rpc();
+    let mut subscription =
+        module.subscribe_unbounded(NEW_HEADS_METHOD_NAME, rpc_params![]).await.unwrap();
+
+    let header = starknet_api::block::BlockHeader::default();
+    let starknet_version = StarknetVersion::default();
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(header.block_number, &header)
+        .unwrap()
+        .update_starknet_version(&header.block_number, &starknet_version)
+        .unwrap()
+        .append_body(header.block_number, BlockBody::default())
+        .unwrap()
+        .append_state_diff(header.block_number, StateDiff::default(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let (notification, _) = subscription.next::<BlockHeader>().await.unwrap().unwrap();
+    assert_eq!(notification, BlockHeader::from((header, starknet_version)));
+}
+
+#[tokio::test]
+async fn subscribe_new_heads_catches_up_in_chunks() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let (new_block_sender, _) = broadcast::channel(10);
+    let module: RpcModule<_> = SubscriptionsV0_6Impl::new(
+        storage_reader,
+        new_block_sender.clone(),
+        get_test_pending_data(),
+        MAX_EVENTS_KEYS,
+    )
+    .into_rpc();
+    let mut subscription =
+        module.subscribe_unbounded(NEW_HEADS_METHOD_NAME, rpc_params![]).await.unwrap();
+
+    // More blocks than are read at once are stored before the subscription wakes up.
+    let n_blocks = 2 * MAX_BLOCKS_PER_READ + 1;
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in (0..n_blocks).map(BlockNumber) {
+        let header = starknet_api::block::BlockHeader {
+            block_number,
+            block_hash: BlockHash(StarkFelt::from(block_number.0 + 1)),
+            ..Default::default()
+        };
+        txn = txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .update_starknet_version(&block_number, &StarknetVersion::default())
+            .unwrap()
+            .append_body(block_number, BlockBody::default())
+            .unwrap()
+            .append_state_diff(block_number, StateDiff::default(), IndexMap::new())
+            .unwrap();
+    }
+    txn.commit().unwrap();
+    new_block_sender.send(BlockNumber(n_blocks - 1)).unwrap();
+
+    for block_number in (0..n_blocks).map(BlockNumber) {
+        let (notification, _) = subscription.next::<BlockHeader>().await.unwrap().unwrap();
+        assert_eq!(notification.block_number, block_number);
+    }
+}
+
+// Stores a block with events of two contracts, and returns the events of the first contract.
+fn store_block_with_events(
+    storage_writer: &mut StorageWriter,
+    block_number: BlockNumber,
+    from_addresses: &[ContractAddress],
+) -> Vec<Event> {
+    let header = starknet_api::block::BlockHeader {
+        block_number,
+        block_hash: BlockHash(StarkFelt::from(block_number.0 + 1)),
+        ..Default::default()
+    };
+    let body = get_test_body(3, Some(4), Some(from_addresses.to_vec()), None);
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &header)
+        .unwrap()
+        .append_body(block_number, body.clone())
+        .unwrap()
+        .append_state_diff(block_number, StateDiff::default(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    body.transaction_outputs
+        .iter()
+        .zip(body.transaction_hashes)
+        .flat_map(|(output, transaction_hash)| {
+            output.events().iter().map(move |event| Event {
+                block_hash: Some(header.block_hash),
+                block_number: Some(block_number),
+                transaction_hash,
+                event: event.clone(),
+            })
+        })
+        .filter(|event| event.event.from_address == from_addresses[0])
+        .collect()
+}
+
+#[tokio::test]
+async fn subscribe_events() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let (new_block_sender, _) = broadcast::channel(10);
+    let module: RpcModule<_> = SubscriptionsV0_6Impl::new(
+        storage_reader,
+        new_block_sender.clone(),
+        get_test_pending_data(),
+        MAX_EVENTS_KEYS,
+    )
+    .into_rpc();
+    let from_addresses =
+        vec![ContractAddress(patricia_key!("0x22")), ContractAddress(patricia_key!("0x23"))];
+    let filter = EventSubscriptionFilter {
+        from_block: Some(BlockNumber(0)),
+        address: Some(from_addresses[0]),
+        keys: vec![],
+    };
+
+    // The events of the stored blocks are sent first.
+    let mut expected_events =
+        store_block_with_events(&mut storage_writer, BlockNumber(0), &from_addresses);
+    let mut subscription =
+        module.subscribe_unbounded(EVENTS_METHOD_NAME, rpc_params![filter]).await.unwrap();
+
+    expected_events.extend(store_block_with_events(
+        &mut storage_writer,
+        BlockNumber(1),
+        &from_addresses,
+    ));
+    new_block_sender.send(BlockNumber(1)).unwrap();
+
+    for expected_event in expected_events {
+        let (event, _) = subscription.next::<Event>().await.unwrap().unwrap();
+        assert_eq!(event, expected_event);
+    }
+
+    // Too many keys.
+    let filter = EventSubscriptionFilter {
+        keys: vec![HashSet::new(); MAX_EVENTS_KEYS + 1],
+        ..Default::default()
+    };
+    let err =
+        module.subscribe_unbounded(EVENTS_METHOD_NAME, rpc_params![filter]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == TOO_MANY_KEYS_IN_FILTER.into());
+
+    // Too many blocks back.
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    for block_number in (2..=MAX_REPLAYED_BLOCKS).map(BlockNumber) {
+        let header = starknet_api::block::BlockHeader {
+            block_number,
+            block_hash: BlockHash(StarkFelt::from(block_number.0 + 1)),
+            ..Default::default()
+        };
+        txn = txn
+            .append_header(block_number, &header)
+            .unwrap()
+            .append_body(block_number, BlockBody::default())
+            .unwrap()
+            .append_state_diff(block_number, StateDiff::default(), IndexMap::new())
+            .unwrap();
+    }
+    txn.commit().unwrap();
+    let filter = EventSubscriptionFilter { from_block: Some(BlockNumber(0)), ..Default::default() };
+    let err =
+        module.subscribe_unbounded(EVENTS_METHOD_NAME, rpc_params![filter]).await.unwrap_err();
+    assert_matches!(err, Error::Call(err) if err == TOO_MANY_BLOCKS_BACK.into());
+    let filter = EventSubscriptionFilter { from_block: Some(BlockNumber(1)), ..Default::default() };
+    module.subscribe_unbounded(EVENTS_METHOD_NAME, rpc_params![filter]).await.unwrap();
+}
+
+#[tokio::test]
+async fn subscribe_pending_transactions() {
+    let ((storage_reader, _), _temp_dir) = get_test_storage();
+    let pending_data = get_test_pending_data();
+    let module: RpcModule<_> = SubscriptionsV0_6Impl::new(
+        storage_reader,
+        broadcast::channel(1).0,
+        pending_data.clone(),
+        MAX_EVENTS_KEYS,
+    )
+    .into_rpc();
+    let mut rng = get_rng();
+    let (client_transactions, rpc_transactions): (Vec<_>, Vec<_>) =
+        iter::repeat_with(|| generate_client_transaction_and_rpc_transaction(&mut rng))
+            .take(3)
+            .unzip();
+    pending_data.write().await.block.transactions.push(client_transactions[0].clone());
+
+    let mut hashes_subscription =
+        module.subscribe_unbounded(PENDING_TRANSACTIONS_METHOD_NAME, rpc_params![]).await.unwrap();
+    let mut full_subscription = module
+        .subscribe_unbounded(PENDING_TRANSACTIONS_METHOD_NAME, rpc_params![true])
+        .await
+        .unwrap();
+    let (transaction, _) = hashes_subscription.next::<PendingTransaction>().await.unwrap().unwrap();
+    assert_eq!(transaction, PendingTransaction::Hash(rpc_transactions[0].transaction_hash));
+    let (transaction, _) = full_subscription.next::<PendingTransaction>().await.unwrap().unwrap();
+    assert_eq!(transaction, PendingTransaction::Full(rpc_transactions[0].clone()));
+
+    // The transactions that were already sent are skipped when the pending block is refreshed.
+    pending_data.write().await.block.transactions = client_transactions;
+    for rpc_transaction in rpc_transactions.into_iter().skip(1) {
+        let (transaction, _) =
+            hashes_subscription.next::<PendingTransaction>().await.unwrap().unwrap();
+        assert_eq!(transaction, PendingTransaction::Hash(rpc_transaction.transaction_hash));
+        let (transaction, _) =
+            full_subscription.next::<PendingTransaction>().await.unwrap().unwrap();
+        assert_eq!(transaction, PendingTransaction::Full(rpc_transaction));
+    }
+}
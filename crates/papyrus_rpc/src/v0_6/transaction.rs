@@ -47,7 +47,7 @@ use starknet_api::transaction::{
 use starknet_client::writer::objects::transaction as client_transaction;
 
 use super::error::BLOCK_NOT_FOUND;
-use crate::internal_server_error;
+use crate::{internal_server_error, verify_body_not_pruned};
 
 #[derive(
     Debug, Deserialize, Serialize, Default, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord,
@@ -511,6 +511,12 @@ pub struct TransactionWithHash {
     pub transaction: Transaction,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionWithReceipt {
+    pub transaction: Transaction,
+    pub receipt: GeneralTransactionReceipt,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
 #[serde(tag = "type")]
 pub enum Transaction {
@@ -1036,6 +1042,7 @@ pub fn get_block_txs_by_number<
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
 ) -> Result<Vec<Transaction>, ErrorObjectOwned> {
+    verify_body_not_pruned(txn, block_number)?;
     let transactions = txn
         .get_block_transactions(block_number)
         .map_err(internal_server_error)?
@@ -1048,6 +1055,7 @@ pub fn get_block_tx_hashes_by_number<Mode: TransactionKind>(
     txn: &StorageTxn<'_, Mode>,
     block_number: BlockNumber,
 ) -> Result<Vec<TransactionHash>, ErrorObjectOwned> {
+    verify_body_not_pruned(txn, block_number)?;
     let transaction_hashes = txn
         .get_block_transaction_hashes(block_number)
         .map_err(internal_server_error)?
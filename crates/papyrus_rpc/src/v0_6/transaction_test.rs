@@ -26,7 +26,14 @@ use starknet_api::transaction::{
 };
 use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
 use starknet_client::writer::objects::transaction as client_transaction;
-use test_utils::{auto_impl_get_test_instance, get_number_of_variants, get_rng, GetTestInstance};
+use test_utils::{
+    assert_json_roundtrip,
+    auto_impl_get_test_instance,
+    for_random_instances,
+    get_number_of_variants,
+    get_rng,
+    GetTestInstance,
+};
 
 use super::super::transaction::{L1HandlerMsgHash, L1L2MsgHash};
 use super::{
@@ -272,3 +279,13 @@ fn l1handler_msg_hash_serde() {
     let expected_hash = L1_HANDLER_TX.calc_msg_hash();
     assert_eq!(des, expected_hash);
 }
+
+#[test]
+fn invoke_transaction_serde_roundtrip() {
+    for_random_instances(|tx: InvokeTransaction| assert_json_roundtrip(&tx));
+}
+
+#[test]
+fn deploy_account_transaction_serde_roundtrip() {
+    for_random_instances(|tx: DeployAccountTransaction| assert_json_roundtrip(&tx));
+}
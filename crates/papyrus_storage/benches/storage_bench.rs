@@ -0,0 +1,179 @@
+#![allow(clippy::unwrap_used)]
+//! Benchmarks of the storage hot paths on a generated storage with thousands of blocks.
+//!
+//! Run with `cargo bench -p papyrus_storage --features chain_fixture,testing`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use indexmap::IndexMap;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::chain_fixture::{ChainFixture, ChainFixtureBuilder};
+use papyrus_storage::header::HeaderStorageReader;
+use papyrus_storage::open_storage;
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::test_utils::get_test_config;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use starknet_api::block::BlockNumber;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{StateDiff, StateNumber, StorageKey};
+
+const N_BLOCKS: u64 = 2000;
+// The number of storage entries that each appended state diff updates.
+const STORAGE_UPDATES_PER_STATE_DIFF: usize = 100;
+// The number of threads that read concurrently in the parallel reads benchmark.
+const N_READERS: u64 = 8;
+
+fn random_block_number(rng: &mut ChaCha8Rng) -> BlockNumber {
+    BlockNumber(rng.gen_range(0..N_BLOCKS))
+}
+
+// Returns a state diff that updates random storage entries of the contracts of the fixture.
+fn random_state_diff(rng: &mut ChaCha8Rng, fixture: &ChainFixture) -> StateDiff {
+    let mut state_diff = StateDiff::default();
+    for _ in 0..STORAGE_UPDATES_PER_STATE_DIFF {
+        let address = fixture.contracts[rng.gen_range(0..fixture.contracts.len())];
+        let key = StorageKey::from(rng.gen_range(0..fixture.storage_keys_per_contract));
+        state_diff
+            .storage_diffs
+            .entry(address)
+            .or_default()
+            .insert(key, StarkFelt::from(rng.gen::<u64>()));
+    }
+    state_diff
+}
+
+fn storage_benchmark(c: &mut Criterion) {
+    let fixture = ChainFixtureBuilder::new(N_BLOCKS).build();
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.mmap_file_config.max_size = 1 << 30; // 1GB
+    let (reader, mut writer) = open_storage(config).unwrap();
+    fixture.write_to_storage(&mut writer).unwrap();
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+    c.bench_function("get_storage_at", |b| {
+        b.iter_batched(
+            || {
+                let state_diff = &fixture.state_diffs[rng.gen_range(0..fixture.state_diffs.len())];
+                let (address, storage_diff) = state_diff
+                    .storage_diffs
+                    .get_index(rng.gen_range(0..state_diff.storage_diffs.len()))
+                    .unwrap();
+                let (key, _) =
+                    storage_diff.get_index(rng.gen_range(0..storage_diff.len())).unwrap();
+                (StateNumber::right_after_block(random_block_number(&mut rng)), *address, *key)
+            },
+            |(state_number, address, key)| {
+                let txn = reader.begin_ro_txn().unwrap();
+                let state_reader = txn.get_state_reader().unwrap();
+                black_box(state_reader.get_storage_at(state_number, &address, &key).unwrap())
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("get_block_header", |b| {
+        b.iter_batched(
+            || random_block_number(&mut rng),
+            |block_number| {
+                let txn = reader.begin_ro_txn().unwrap();
+                black_box(txn.get_block_header(block_number).unwrap().unwrap())
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("get_block_transactions", |b| {
+        b.iter_batched(
+            || random_block_number(&mut rng),
+            |block_number| {
+                let txn = reader.begin_ro_txn().unwrap();
+                black_box(txn.get_block_transactions(block_number).unwrap().unwrap())
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("get_transaction_idx_by_hash", |b| {
+        b.iter_batched(
+            || {
+                let body = &fixture.blocks[random_block_number(&mut rng).0 as usize].body;
+                body.transaction_hashes[rng.gen_range(0..body.transaction_hashes.len())]
+            },
+            |transaction_hash| {
+                let txn = reader.begin_ro_txn().unwrap();
+                black_box(txn.get_transaction_idx_by_hash(&transaction_hash).unwrap().unwrap())
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // Each iteration appends the state diff of the next block, so the storage keeps growing.
+    let mut next_block_number = BlockNumber(N_BLOCKS);
+    c.bench_function("append_state_diff", |b| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                let state_diff = random_state_diff(&mut rng, &fixture);
+                let start = Instant::now();
+                writer
+                    .begin_rw_txn()
+                    .unwrap()
+                    .append_state_diff(next_block_number, state_diff, IndexMap::new())
+                    .unwrap()
+                    .commit()
+                    .unwrap();
+                elapsed += start.elapsed();
+                next_block_number = next_block_number.next();
+            }
+            elapsed
+        })
+    });
+
+    // Each iteration reads a header on each of the reader threads, while another thread keeps
+    // appending state diffs, like the sync does while the node serves requests.
+    let stop_writing = AtomicBool::new(false);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let mut rng = ChaCha8Rng::seed_from_u64(1);
+            while !stop_writing.load(Ordering::Relaxed) {
+                let state_diff = random_state_diff(&mut rng, &fixture);
+                writer
+                    .begin_rw_txn()
+                    .unwrap()
+                    .append_state_diff(next_block_number, state_diff, IndexMap::new())
+                    .unwrap()
+                    .commit()
+                    .unwrap();
+                next_block_number = next_block_number.next();
+            }
+        });
+        c.bench_function("parallel_get_block_header_while_writing", |b| {
+            b.iter_custom(|iters| {
+                let start = Instant::now();
+                thread::scope(|readers_scope| {
+                    for reader_index in 0..N_READERS {
+                        let reader = reader.clone();
+                        readers_scope.spawn(move || {
+                            let mut rng = ChaCha8Rng::seed_from_u64(reader_index);
+                            for _ in 0..iters {
+                                let block_number = random_block_number(&mut rng);
+                                let txn = reader.begin_ro_txn().unwrap();
+                                black_box(txn.get_block_header(block_number).unwrap().unwrap());
+                            }
+                        });
+                    }
+                });
+                start.elapsed()
+            })
+        });
+        stop_writing.store(true, Ordering::Relaxed);
+    });
+}
+
+criterion_group!(benches, storage_benchmark);
+criterion_main!(benches);
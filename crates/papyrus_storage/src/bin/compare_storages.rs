@@ -0,0 +1,93 @@
+use std::process::ExitCode;
+
+use clap::{Arg, Command};
+use papyrus_storage::utils::compare_storages_by_block_range;
+
+/// This executable compares two storages and prints the markers and the block ranges of the tables
+/// in which they differ.
+fn main() -> ExitCode {
+    let cli_params = get_cli_params();
+    let comparison = match compare_storages_by_block_range(
+        cli_params.first_path_prefix.into(),
+        cli_params.second_path_prefix.into(),
+        &cli_params.chain_id,
+        cli_params.start_block,
+        cli_params.end_block,
+    ) {
+        Ok(comparison) => comparison,
+        Err(e) => {
+            println!("Failed comparing the storages with error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if comparison.is_empty() {
+        println!("The storages are identical.");
+        return ExitCode::SUCCESS;
+    }
+    for marker_difference in comparison.markers {
+        println!("{marker_difference}");
+    }
+    for table_difference in comparison.tables {
+        println!("{table_difference}");
+    }
+    ExitCode::FAILURE
+}
+
+struct CliParams {
+    first_path_prefix: String,
+    second_path_prefix: String,
+    chain_id: String,
+    start_block: u64,
+    end_block: Option<u64>,
+}
+
+/// The first_path_prefix, second_path_prefix and chain_id arguments are mandatory. The start_block
+/// argument is inclusive and defaults to 0, and the end_block argument is exclusive and defaults to
+/// the highest marker of the storages.
+fn get_cli_params() -> CliParams {
+    let matches = Command::new("Compare storages")
+        .arg(
+            Arg::new("first_path_prefix")
+                .short('f')
+                .long("first_path_prefix")
+                .required(true)
+                .help("The path prefix of the first storage."),
+        )
+        .arg(
+            Arg::new("second_path_prefix")
+                .short('s')
+                .long("second_path_prefix")
+                .required(true)
+                .help("The path prefix of the second storage."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storages, SN_MAIN/SN_GOERLI."),
+        )
+        .arg(
+            Arg::new("start_block")
+                .long("start_block")
+                .default_value("0")
+                .help("The block number to start comparing from."),
+        )
+        .arg(Arg::new("end_block").long("end_block").help("The block number to end comparing at."))
+        .get_matches();
+
+    let get_arg = |name: &str| matches.get_one::<String>(name).map(String::to_string);
+    let parse_block_number =
+        |block_number: String| block_number.parse::<u64>().expect("Failed parsing block number");
+    let first_path_prefix = get_arg("first_path_prefix").expect("Failed parsing first_path_prefix");
+    let second_path_prefix =
+        get_arg("second_path_prefix").expect("Failed parsing second_path_prefix");
+    let chain_id = get_arg("chain_id").expect("Failed parsing chain_id");
+    let start_block =
+        parse_block_number(get_arg("start_block").expect("Failed parsing start_block"));
+    let end_block = get_arg("end_block").map(parse_block_number);
+    if end_block.is_some_and(|end_block| start_block >= end_block) {
+        panic!("start_block must be smaller than end_block");
+    }
+    CliParams { first_path_prefix, second_path_prefix, chain_id, start_block, end_block }
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::{Arg, Command};
+use papyrus_storage::snapshot::export_snapshot;
+
+/// This executable exports the blocks of a storage into a snapshot file, from which a new storage
+/// can be imported.
+fn main() -> ExitCode {
+    let cli_params = get_cli_params();
+    match export_snapshot(
+        cli_params.path_prefix.into(),
+        &cli_params.chain_id,
+        cli_params.end_block,
+        Path::new(&cli_params.snapshot_path),
+    ) {
+        Ok(manifest) => {
+            println!(
+                "Exported blocks 0..{} to {}. The hash of the last block is {:?}.",
+                manifest.end_block, cli_params.snapshot_path, manifest.last_block_hash
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("Failed exporting the snapshot with error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct CliParams {
+    path_prefix: String,
+    chain_id: String,
+    end_block: Option<u64>,
+    snapshot_path: String,
+}
+
+/// The path_prefix and chain_id arguments are mandatory. The end_block argument is exclusive and
+/// defaults to the last block whose data is stored.
+fn get_cli_params() -> CliParams {
+    let matches = Command::new("Export snapshot")
+        .arg(
+            Arg::new("path_prefix")
+                .short('p')
+                .long("path_prefix")
+                .required(true)
+                .help("The path prefix of the storage."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storage, SN_MAIN/SN_GOERLI."),
+        )
+        .arg(Arg::new("end_block").long("end_block").help("The block number to end exporting at."))
+        .arg(
+            Arg::new("snapshot_path")
+                .short('s')
+                .long("snapshot_path")
+                .default_value("snapshot.gz")
+                .help("The path of the snapshot file to create."),
+        )
+        .get_matches();
+
+    let get_arg = |name: &str| matches.get_one::<String>(name).map(String::to_string);
+    let path_prefix = get_arg("path_prefix").expect("Failed parsing path_prefix");
+    let chain_id = get_arg("chain_id").expect("Failed parsing chain_id");
+    let end_block = get_arg("end_block")
+        .map(|block_number| block_number.parse::<u64>().expect("Failed parsing end_block"));
+    let snapshot_path = get_arg("snapshot_path").expect("Failed parsing snapshot_path");
+    CliParams { path_prefix, chain_id, end_block, snapshot_path }
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::{Arg, Command};
+use papyrus_storage::snapshot::import_snapshot;
+
+/// This executable imports a snapshot file that was exported from another storage into a new
+/// storage, so that a node that uses it doesn't have to sync the blocks in the snapshot.
+fn main() -> ExitCode {
+    let cli_params = get_cli_params();
+    match import_snapshot(
+        cli_params.path_prefix.into(),
+        &cli_params.chain_id,
+        Path::new(&cli_params.snapshot_path),
+        cli_params.verify_hashes,
+    ) {
+        Ok(manifest) => {
+            println!(
+                "Imported blocks 0..{}. The hash of the last block is {:?}.",
+                manifest.end_block, manifest.last_block_hash
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("Failed importing the snapshot with error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct CliParams {
+    path_prefix: String,
+    chain_id: String,
+    snapshot_path: String,
+    verify_hashes: bool,
+}
+
+/// The path_prefix, chain_id and snapshot_path arguments are mandatory.
+fn get_cli_params() -> CliParams {
+    let matches = Command::new("Import snapshot")
+        .arg(
+            Arg::new("path_prefix")
+                .short('p')
+                .long("path_prefix")
+                .required(true)
+                .help("The path prefix of the new storage."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storage, SN_MAIN/SN_GOERLI."),
+        )
+        .arg(
+            Arg::new("snapshot_path")
+                .short('s')
+                .long("snapshot_path")
+                .required(true)
+                .help("The path of the snapshot file."),
+        )
+        .arg(
+            Arg::new("verify_hashes")
+                .long("verify_hashes")
+                .default_value("true")
+                .help("Whether to verify the hashes of the blocks and transactions, true/false."),
+        )
+        .get_matches();
+
+    let get_arg = |name: &str| matches.get_one::<String>(name).map(String::to_string);
+    let path_prefix = get_arg("path_prefix").expect("Failed parsing path_prefix");
+    let chain_id = get_arg("chain_id").expect("Failed parsing chain_id");
+    let snapshot_path = get_arg("snapshot_path").expect("Failed parsing snapshot_path");
+    let verify_hashes = get_arg("verify_hashes")
+        .expect("Failed parsing verify_hashes")
+        .parse::<bool>()
+        .expect("Failed parsing verify_hashes");
+    CliParams { path_prefix, chain_id, snapshot_path, verify_hashes }
+}
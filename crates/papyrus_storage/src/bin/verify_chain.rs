@@ -0,0 +1,83 @@
+use std::process::ExitCode;
+
+use clap::{Arg, Command};
+use papyrus_storage::utils::verify_chain;
+
+/// This executable walks the stored chain, recomputes the hashes of its transactions and blocks and
+/// prints the first block in which they diverge from the stored hashes.
+fn main() -> ExitCode {
+    let cli_params = get_cli_params();
+    let verification = match verify_chain(
+        cli_params.path_prefix.into(),
+        &cli_params.chain_id,
+        cli_params.start_block,
+        cli_params.end_block,
+    ) {
+        Ok(verification) => verification,
+        Err(e) => {
+            println!("Failed verifying the chain with error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match verification.divergence {
+        Some(divergence) => {
+            println!("{divergence}");
+            ExitCode::FAILURE
+        }
+        None => {
+            println!(
+                "The hashes of blocks {}..{} are valid.",
+                cli_params.start_block, verification.end_block
+            );
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+struct CliParams {
+    path_prefix: String,
+    chain_id: String,
+    start_block: u64,
+    end_block: Option<u64>,
+}
+
+/// The path_prefix and chain_id arguments are mandatory. The start_block argument is inclusive and
+/// defaults to 0, and the end_block argument is exclusive and defaults to the last stored block.
+fn get_cli_params() -> CliParams {
+    let matches = Command::new("Verify chain")
+        .arg(
+            Arg::new("path_prefix")
+                .short('p')
+                .long("path_prefix")
+                .required(true)
+                .help("The path prefix of the storage."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storage, SN_MAIN/SN_GOERLI."),
+        )
+        .arg(
+            Arg::new("start_block")
+                .long("start_block")
+                .default_value("0")
+                .help("The block number to start verifying from."),
+        )
+        .arg(Arg::new("end_block").long("end_block").help("The block number to end verifying at."))
+        .get_matches();
+
+    let get_arg = |name: &str| matches.get_one::<String>(name).map(String::to_string);
+    let parse_block_number =
+        |block_number: String| block_number.parse::<u64>().expect("Failed parsing block number");
+    let path_prefix = get_arg("path_prefix").expect("Failed parsing path_prefix");
+    let chain_id = get_arg("chain_id").expect("Failed parsing chain_id");
+    let start_block =
+        parse_block_number(get_arg("start_block").expect("Failed parsing start_block"));
+    let end_block = get_arg("end_block").map(parse_block_number);
+    if end_block.is_some_and(|end_block| start_block >= end_block) {
+        panic!("start_block must be smaller than end_block");
+    }
+    CliParams { path_prefix, chain_id, start_block, end_block }
+}
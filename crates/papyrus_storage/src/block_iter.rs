@@ -0,0 +1,279 @@
+//! Interface for iterating over the data of consecutive blocks.
+//!
+//! The iterators read the rows of the blocks with cursors that go over the tables in order,
+//! instead of looking up every block separately, so reading long ranges of blocks (for example
+//! when syncing a peer or exporting a snapshot) is cheap.
+//! Import [`BlockIterReader`] to iterate over the headers, bodies and state diffs of a range of
+//! blocks using a [`StorageTxn`].
+//!
+//!  # Example
+//! ```
+//! use papyrus_storage::block_iter::BlockIterReader;
+//! use papyrus_storage::header::HeaderStorageWriter;
+//! use papyrus_storage::open_storage;
+//! # use papyrus_storage::{db::DbConfig, StorageConfig};
+//! # use starknet_api::core::ChainId;
+//! use starknet_api::block::{BlockHeader, BlockNumber};
+//!
+//! # let dir_handle = tempfile::tempdir().unwrap();
+//! # let dir = dir_handle.path().to_path_buf();
+//! # let db_config = DbConfig {
+//! #     path_prefix: dir,
+//! #     chain_id: ChainId("SN_MAIN".to_owned()),
+//! #     enforce_file_exists: false,
+//! #     min_size: 1 << 20,    // 1MB
+//! #     max_size: 1 << 35,    // 32GB
+//! #     growth_step: 1 << 26, // 64MB
+//! #     ..Default::default()
+//! # };
+//! # let storage_config = StorageConfig{db_config, ..Default::default()};
+//! let (reader, mut writer) = open_storage(storage_config)?;
+//! writer.begin_rw_txn()?.append_header(BlockNumber(0), &BlockHeader::default())?.commit()?;
+//!
+//! let txn = reader.begin_ro_txn()?;
+//! let block_numbers = txn
+//!     .iter_headers(BlockNumber(0)..BlockNumber(10))?             // Stops at the last header.
+//!     .map(|res| res.map(|(block_number, _header)| block_number))
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! assert_eq!(block_numbers, vec![BlockNumber(0)]);
+//! # Ok::<(), papyrus_storage::StorageError>(())
+//! ```
+
+#[cfg(test)]
+#[path = "block_iter_test.rs"]
+mod block_iter_test;
+
+use std::fmt::Debug;
+use std::ops::Range;
+
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::transaction::{Transaction, TransactionHash, TransactionOffsetInBlock};
+
+use crate::body::events::ThinTransactionOutput;
+use crate::body::{BodyStorageReader, TransactionIndex};
+use crate::db::serialization::{NoVersionValueWrapper, StorageSerde, ValueSerde};
+use crate::db::{DbCursor, RO};
+use crate::header::HeaderStorageReader;
+use crate::mmap_file::LocationInFile;
+use crate::state::StateStorageReader;
+use crate::{FileHandlers, StorageResult, StorageTxn};
+
+/// An interface for iterating over the data of a range of blocks, by the order of the block
+/// number. The iterators yield the block number with the data of the block, and end after an
+/// error.
+pub trait BlockIterReader<'env> {
+    /// Returns an iterator over the headers of the blocks in the given range. The iteration stops
+    /// at the header marker.
+    fn iter_headers(
+        &'env self,
+        block_numbers: Range<BlockNumber>,
+    ) -> StorageResult<HeaderIter<'env>>;
+
+    /// Returns an iterator over the bodies of the blocks in the given range. The blocks whose
+    /// bodies were pruned are skipped, and the iteration stops at the body marker.
+    fn iter_bodies(&'env self, block_numbers: Range<BlockNumber>) -> StorageResult<BodyIter<'env>>;
+
+    /// Returns an iterator over the state diffs of the blocks in the given range. The iteration
+    /// stops at the state marker.
+    fn iter_state_diffs(
+        &'env self,
+        block_numbers: Range<BlockNumber>,
+    ) -> StorageResult<StateDiffIter<'env>>;
+}
+
+// TODO: support all read transactions (including RW).
+impl<'env> BlockIterReader<'env> for StorageTxn<'env, RO> {
+    fn iter_headers(
+        &'env self,
+        block_numbers: Range<BlockNumber>,
+    ) -> StorageResult<HeaderIter<'env>> {
+        let end = block_numbers.end.min(self.get_header_marker()?);
+        let headers_table = self.open_table(&self.tables.headers)?;
+        let cursor = headers_table.cursor(&self.txn)?;
+        Ok(HeaderIter(BlockNumberCursor::new(cursor, block_numbers.start, end)?))
+    }
+
+    fn iter_bodies(&'env self, block_numbers: Range<BlockNumber>) -> StorageResult<BodyIter<'env>> {
+        let start = block_numbers.start.max(self.get_pruned_body_marker()?);
+        let end = block_numbers.end.min(self.get_body_marker()?);
+        let transactions_table = self.open_table(&self.tables.transactions)?;
+        let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
+        let transaction_idx_to_hash_table =
+            self.open_table(&self.tables.transaction_idx_to_hash)?;
+        Ok(BodyIter {
+            transactions: TransactionIndexCursor::new(
+                transactions_table.cursor(&self.txn)?,
+                start,
+            )?,
+            transaction_outputs: TransactionIndexCursor::new(
+                transaction_outputs_table.cursor(&self.txn)?,
+                start,
+            )?,
+            transaction_hashes: TransactionIndexCursor::new(
+                transaction_idx_to_hash_table.cursor(&self.txn)?,
+                start,
+            )?,
+            next_block_number: start,
+            end,
+        })
+    }
+
+    fn iter_state_diffs(
+        &'env self,
+        block_numbers: Range<BlockNumber>,
+    ) -> StorageResult<StateDiffIter<'env>> {
+        let end = block_numbers.end.min(self.get_state_marker()?);
+        let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
+        let cursor = state_diffs_table.cursor(&self.txn)?;
+        Ok(StateDiffIter {
+            locations: BlockNumberCursor::new(cursor, block_numbers.start, end)?,
+            file_handlers: &self.file_handlers,
+        })
+    }
+}
+
+/// An iterator over the headers of consecutive blocks.
+pub struct HeaderIter<'env>(BlockNumberCursor<'env, NoVersionValueWrapper<BlockHeader>>);
+
+impl Iterator for HeaderIter<'_> {
+    type Item = StorageResult<(BlockNumber, BlockHeader)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().transpose()
+    }
+}
+
+/// The body of a block as it's stored, where the transaction outputs hold the addresses of their
+/// events instead of the events.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct ThinBlockBody {
+    /// The transactions of the block, by their order in the block.
+    pub transactions: Vec<Transaction>,
+    /// The outputs of the transactions, by the order of the transactions.
+    pub transaction_outputs: Vec<ThinTransactionOutput>,
+    /// The hashes of the transactions, by the order of the transactions.
+    pub transaction_hashes: Vec<TransactionHash>,
+}
+
+/// An iterator over the bodies of consecutive blocks.
+pub struct BodyIter<'env> {
+    transactions: TransactionIndexCursor<'env, Transaction>,
+    transaction_outputs: TransactionIndexCursor<'env, ThinTransactionOutput>,
+    transaction_hashes: TransactionIndexCursor<'env, TransactionHash>,
+    next_block_number: BlockNumber,
+    end: BlockNumber,
+}
+
+impl BodyIter<'_> {
+    fn read_body(&mut self, block_number: BlockNumber) -> StorageResult<ThinBlockBody> {
+        Ok(ThinBlockBody {
+            transactions: self.transactions.read_block(block_number)?,
+            transaction_outputs: self.transaction_outputs.read_block(block_number)?,
+            transaction_hashes: self.transaction_hashes.read_block(block_number)?,
+        })
+    }
+}
+
+impl Iterator for BodyIter<'_> {
+    type Item = StorageResult<(BlockNumber, ThinBlockBody)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_block_number >= self.end {
+            return None;
+        }
+        let block_number = self.next_block_number;
+        let body = self.read_body(block_number);
+        self.next_block_number = if body.is_ok() { block_number.next() } else { self.end };
+        Some(body.map(|body| (block_number, body)))
+    }
+}
+
+/// An iterator over the state diffs of consecutive blocks.
+pub struct StateDiffIter<'env> {
+    locations: BlockNumberCursor<'env, NoVersionValueWrapper<LocationInFile>>,
+    file_handlers: &'env FileHandlers<RO>,
+}
+
+impl StateDiffIter<'_> {
+    fn read_next(&mut self) -> StorageResult<Option<(BlockNumber, ThinStateDiff)>> {
+        let Some((block_number, location)) = self.locations.next()? else {
+            return Ok(None);
+        };
+        Ok(Some((block_number, self.file_handlers.get_thin_state_diff_unchecked(location)?)))
+    }
+}
+
+impl Iterator for StateDiffIter<'_> {
+    type Item = StorageResult<(BlockNumber, ThinStateDiff)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+// A cursor over a table whose keys are block numbers, that stops before the end block number.
+struct BlockNumberCursor<'env, V: ValueSerde> {
+    current: Option<(BlockNumber, V::Value)>,
+    cursor: DbCursor<'env, RO, BlockNumber, V>,
+    end: BlockNumber,
+}
+
+impl<'env, V: ValueSerde> BlockNumberCursor<'env, V> {
+    fn new(
+        mut cursor: DbCursor<'env, RO, BlockNumber, V>,
+        start: BlockNumber,
+        end: BlockNumber,
+    ) -> StorageResult<Self> {
+        let current = cursor.lower_bound(&start)?;
+        Ok(Self { current, cursor, end })
+    }
+
+    fn next(&mut self) -> StorageResult<Option<(BlockNumber, V::Value)>> {
+        let Some((block_number, value)) = self.current.take() else {
+            return Ok(None);
+        };
+        if block_number >= self.end {
+            return Ok(None);
+        }
+        self.current = self.cursor.next()?;
+        Ok(Some((block_number, value)))
+    }
+}
+
+// A cursor over a table whose keys are transaction indices, that reads the rows of a block at a
+// time.
+struct TransactionIndexCursor<'env, V: StorageSerde + Debug> {
+    current: Option<(TransactionIndex, V)>,
+    cursor: DbCursor<'env, RO, TransactionIndex, NoVersionValueWrapper<V>>,
+}
+
+impl<'env, V: StorageSerde + Debug> TransactionIndexCursor<'env, V> {
+    fn new(
+        mut cursor: DbCursor<'env, RO, TransactionIndex, NoVersionValueWrapper<V>>,
+        start: BlockNumber,
+    ) -> StorageResult<Self> {
+        let current = cursor.lower_bound(&TransactionIndex(start, TransactionOffsetInBlock(0)))?;
+        Ok(Self { current, cursor })
+    }
+
+    // Returns the values of the rows of the given block, which shouldn't precede the block of the
+    // previous call.
+    fn read_block(&mut self, block_number: BlockNumber) -> StorageResult<Vec<V>> {
+        let mut res = Vec::new();
+        loop {
+            match self.current.take() {
+                Some((TransactionIndex(current_block_number, _), value))
+                    if current_block_number == block_number =>
+                {
+                    res.push(value);
+                    self.current = self.cursor.next()?;
+                }
+                current => {
+                    self.current = current;
+                    return Ok(res);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkFelt;
+use starknet_api::stark_felt;
+use starknet_api::state::StateDiff;
+use test_utils::{get_test_block, get_test_state_diff};
+
+use crate::block_iter::{BlockIterReader, ThinBlockBody};
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::header::HeaderStorageWriter;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_config, get_test_storage};
+use crate::{open_storage, StorageReader, StorageWriter};
+
+// Appends the bodies of consecutive blocks with the given numbers of transactions, from block 0.
+fn append_bodies(writer: &mut StorageWriter, transaction_counts: &[usize]) {
+    let body = get_test_block(transaction_counts.iter().sum(), None, None, None).body;
+    let mut first_transaction = 0;
+    for (block_number, transaction_count) in transaction_counts.iter().enumerate() {
+        let transactions = first_transaction..first_transaction + transaction_count;
+        first_transaction = transactions.end;
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_body(
+                BlockNumber(block_number as u64),
+                BlockBody {
+                    transactions: body.transactions[transactions.clone()].to_vec(),
+                    transaction_outputs: body.transaction_outputs[transactions.clone()].to_vec(),
+                    transaction_hashes: body.transaction_hashes[transactions].to_vec(),
+                },
+            )
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+// Returns the stored bodies of the given blocks, read separately.
+fn get_bodies(
+    reader: &StorageReader,
+    block_numbers: impl Iterator<Item = u64>,
+) -> Vec<(BlockNumber, ThinBlockBody)> {
+    let txn = reader.begin_ro_txn().unwrap();
+    block_numbers
+        .map(BlockNumber)
+        .map(|block_number| {
+            let body = ThinBlockBody {
+                transactions: txn.get_block_transactions(block_number).unwrap().unwrap(),
+                transaction_outputs: txn
+                    .get_block_transaction_outputs(block_number)
+                    .unwrap()
+                    .unwrap(),
+                transaction_hashes: txn
+                    .get_block_transaction_hashes(block_number)
+                    .unwrap()
+                    .unwrap(),
+            };
+            (block_number, body)
+        })
+        .collect()
+}
+
+#[test]
+fn iter_headers() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let headers = (0..3_u8)
+        .map(|i| BlockHeader {
+            block_hash: BlockHash(stark_felt!(i)),
+            block_number: BlockNumber(i.into()),
+            ..BlockHeader::default()
+        })
+        .collect::<Vec<_>>();
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for header in &headers {
+        txn = txn.append_header(header.block_number, header).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let iter_headers = |start, end| {
+        txn.iter_headers(BlockNumber(start)..BlockNumber(end))
+            .unwrap()
+            .map(|res| res.unwrap().1)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(iter_headers(0, 3), headers);
+    assert_eq!(iter_headers(1, 2), headers[1..2]);
+    // The iteration stops at the header marker.
+    assert_eq!(iter_headers(1, 10), headers[1..]);
+    assert!(iter_headers(3, 10).is_empty());
+    assert!(iter_headers(2, 1).is_empty());
+}
+
+#[test]
+fn iter_bodies() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    // The body of block 1 is empty.
+    append_bodies(&mut writer, &[1, 0, 2, 1]);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let iter_bodies = |start, end| {
+        txn.iter_bodies(BlockNumber(start)..BlockNumber(end))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    };
+    assert_eq!(iter_bodies(0, 4), get_bodies(&reader, 0..4));
+    assert_eq!(iter_bodies(1, 3), get_bodies(&reader, 1..3));
+    // The iteration stops at the body marker.
+    assert_eq!(iter_bodies(2, 10), get_bodies(&reader, 2..4));
+    assert!(iter_bodies(4, 10).is_empty());
+}
+
+#[test]
+fn iter_bodies_skips_pruned_bodies() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.body_retention_blocks = Some(2);
+    let (reader, mut writer) = open_storage(config).unwrap();
+    append_bodies(&mut writer, &[1, 1, 1, 1]);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruned_body_marker().unwrap(), BlockNumber(2));
+    let bodies = txn
+        .iter_bodies(BlockNumber(0)..BlockNumber(4))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(bodies, get_bodies(&reader, 2..4));
+}
+
+#[test]
+fn iter_state_diffs() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), get_test_state_diff(), IndexMap::new())
+        .unwrap()
+        .append_state_diff(BlockNumber(1), StateDiff::default(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let expected_state_diffs = (0..2)
+        .map(|i| (BlockNumber(i), txn.get_state_diff(BlockNumber(i)).unwrap().unwrap()))
+        .collect::<Vec<_>>();
+    let iter_state_diffs = |start, end| {
+        txn.iter_state_diffs(BlockNumber(start)..BlockNumber(end))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    };
+    assert_eq!(iter_state_diffs(0, 2), expected_state_diffs);
+    // The iteration stops at the state marker.
+    assert_eq!(iter_state_diffs(1, 10), expected_state_diffs[1..]);
+    assert!(iter_state_diffs(2, 10).is_empty());
+}
@@ -1,5 +1,7 @@
 use assert_matches::assert_matches;
+use papyrus_common::raw_transaction::RawTransaction;
 use pretty_assertions::assert_eq;
+use serde_json::json;
 use starknet_api::block::{BlockBody, BlockNumber};
 use starknet_api::transaction::TransactionOffsetInBlock;
 use test_case::test_case;
@@ -8,8 +10,8 @@ use test_utils::{get_test_block, get_test_body};
 use crate::body::events::ThinTransactionOutput;
 use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
 use crate::db::{DbError, KeyAlreadyExistsError};
-use crate::test_utils::{get_test_storage, get_test_storage_by_scope};
-use crate::{StorageError, StorageScope, StorageWriter};
+use crate::test_utils::{get_test_config, get_test_storage, get_test_storage_by_scope};
+use crate::{open_storage, StorageError, StorageScope, StorageWriter};
 
 #[tokio::test]
 async fn append_body() {
@@ -164,6 +166,30 @@ async fn append_body() {
     );
     assert_eq!(txn.get_block_transaction_hashes(BlockNumber(3)).unwrap(), None);
 
+    // Check ranges of block transactions.
+    let offset = TransactionOffsetInBlock;
+    assert_eq!(
+        txn.get_block_transactions_range(BlockNumber(2), offset(0), 1).unwrap(),
+        Some(vec![txs[1].clone()])
+    );
+    assert_eq!(
+        txn.get_block_transactions_range(BlockNumber(2), offset(1), 5).unwrap(),
+        Some(vec![txs[2].clone()])
+    );
+    assert_eq!(
+        txn.get_block_transactions_range(BlockNumber(2), offset(2), 5).unwrap(),
+        Some(vec![])
+    );
+    assert_eq!(
+        txn.get_block_transactions_range(BlockNumber(1), offset(0), 5).unwrap(),
+        Some(vec![])
+    );
+    assert_eq!(txn.get_block_transactions_range(BlockNumber(3), offset(0), 5).unwrap(), None);
+    assert_eq!(
+        txn.get_block_transaction_hashes_range(BlockNumber(2), offset(1), 1).unwrap(),
+        Some(vec![tx_hashes[2]])
+    );
+
     // Check block transaction outputs.
     assert_eq!(
         txn.get_block_transaction_outputs(BlockNumber(0)).unwrap(),
@@ -387,6 +413,100 @@ async fn revert_transactions() {
     );
 }
 
+#[tokio::test]
+async fn bodies_beyond_the_retention_are_pruned() {
+    let (mut config, _temp_dir) = get_test_config(None);
+    config.body_retention_blocks = Some(2);
+    let (reader, mut writer) = open_storage(config).unwrap();
+    let body = get_test_block(4, None, None, None).body;
+    let bodies = (0..4).map(|offset| BlockBody {
+        transactions: vec![body.transactions[offset].clone()],
+        transaction_outputs: vec![body.transaction_outputs[offset].clone()],
+        transaction_hashes: vec![body.transaction_hashes[offset]],
+    });
+    for (block_number, body) in bodies.enumerate() {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_body(BlockNumber(block_number as u64), body)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    // Only the bodies of the 2 latest blocks are kept.
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(4));
+    assert_eq!(txn.get_pruned_body_marker().unwrap(), BlockNumber(2));
+    for (offset, tx_hash) in body.transaction_hashes.iter().enumerate() {
+        let block_number = BlockNumber(offset as u64);
+        let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(0));
+        let is_pruned = offset < 2;
+        assert_eq!(txn.get_transaction(tx_index).unwrap().is_none(), is_pruned);
+        assert_eq!(txn.get_transaction_events(tx_index).unwrap().is_none(), is_pruned);
+        // The transaction hashes are kept, so a pruned transaction isn't taken for a missing one.
+        assert_eq!(txn.get_transaction_idx_by_hash(tx_hash).unwrap(), Some(tx_index));
+        assert_eq!(txn.get_block_transactions_count(block_number).unwrap(), Some(1));
+    }
+    drop(txn);
+
+    // A block whose body was pruned is pruned again after it's reverted and appended.
+    let mut txn = writer.begin_rw_txn().unwrap();
+    for block_number in (1..4).rev() {
+        txn = txn.revert_body(BlockNumber(block_number)).unwrap().0;
+    }
+    txn.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruned_body_marker().unwrap(), BlockNumber(1));
+    for tx_hash in &body.transaction_hashes[1..] {
+        assert!(txn.get_transaction_idx_by_hash(tx_hash).unwrap().is_none());
+    }
+    drop(txn);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(1), BlockBody::default())
+        .unwrap()
+        .append_body(BlockNumber(2), BlockBody::default())
+        .unwrap()
+        .append_body(BlockNumber(3), BlockBody::default())
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_pruned_body_marker().unwrap(), BlockNumber(2));
+}
+
+#[tokio::test]
+async fn append_raw_transactions() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let raw_transaction = RawTransaction {
+        transaction: json!({"type": "INVOKE_FUNCTION", "unknown_field": "0x1"}),
+        receipt: json!({"transaction_index": 1}),
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_body(BlockNumber(0), get_test_body(2, None, None, None))
+        .unwrap()
+        .append_raw_transactions(
+            BlockNumber(0),
+            [(TransactionOffsetInBlock(1), raw_transaction.clone())].into(),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let tx_index = |offset| TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(offset));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_raw_transaction(tx_index(0)).unwrap(), None);
+    assert_eq!(txn.get_raw_transaction(tx_index(1)).unwrap(), Some(raw_transaction));
+    drop(txn);
+
+    // The raw transactions are removed with the body.
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap().0.commit().unwrap();
+    assert_eq!(reader.begin_ro_txn().unwrap().get_raw_transaction(tx_index(1)).unwrap(), None);
+}
+
 fn append_2_bodies(writer: &mut StorageWriter) {
     writer
         .begin_rw_txn()
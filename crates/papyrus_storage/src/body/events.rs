@@ -24,6 +24,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     ..Default::default()
 //! # };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! // The API allows read-only interactions with the events. To write events, use the body writer.
@@ -48,20 +49,37 @@
 #[path = "events_test.rs"]
 mod events_test;
 
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ContractAddress;
+use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{
+    DeclareTransactionOutput,
+    DeployAccountTransactionOutput,
+    DeployTransactionOutput,
+    Event,
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     ExecutionResources,
     Fee,
+    InvokeTransactionOutput,
+    L1HandlerTransactionOutput,
     MessageToL1,
     TransactionExecutionStatus,
+    TransactionOffsetInBlock,
     TransactionOutput,
 };
 
-use crate::body::{EventsTable, EventsTableKey, TransactionIndex};
+use crate::body::{
+    EventsBloomFiltersTable,
+    EventsByFirstKeyTableKey,
+    EventsTable,
+    EventsTableKey,
+    TransactionIndex,
+};
 use crate::db::serialization::NoVersionValueWrapper;
 use crate::db::{DbCursor, DbTransaction, RO};
 use crate::{StorageResult, StorageTxn};
@@ -90,6 +108,44 @@ pub trait EventsReader<'txn, 'env> {
         event_index: EventIndex,
         to_block_number: BlockNumber,
     ) -> StorageResult<EventIter<'txn, 'env>>;
+
+    /// Returns an iterator over the events whose first key is the given key, by the order of the
+    /// event index, so filters that narrow the events by their first key don't go over the other
+    /// events.
+    ///
+    /// # Arguments
+    /// * first_key - the first key of the iterated events.
+    /// * event_index - event index to start iterate from it.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn iter_events_by_first_key(
+        &'env self,
+        first_key: EventKey,
+        event_index: EventIndex,
+    ) -> StorageResult<EventIter<'txn, 'env>>;
+
+    /// Returns an iterator over events by the order of the event index, that skips the blocks whose
+    /// [`EventsBloomFilter`] shows that none of their events matches the given address and keys,
+    /// without reading their transaction outputs. The iterated events aren't filtered, so some of
+    /// them may not match.
+    ///
+    /// # Arguments
+    /// * address - contract address that the events were emitted by, if given.
+    /// * keys - for every position, the keys that the event key in this position is one of. An
+    ///   empty set matches any key.
+    /// * event_index - event index to start iterate from it.
+    /// * to_block_number - block number to stop iterate at it.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn iter_events_by_bloom_filter(
+        &'env self,
+        address: Option<ContractAddress>,
+        keys: Vec<HashSet<EventKey>>,
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIter<'txn, 'env>>;
 }
 
 // TODO: support all read transactions (including RW).
@@ -106,19 +162,62 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
             ));
         }
 
-        Ok(EventIter::ByEventIndex(self.iter_events_by_event_index(event_index, to_block_number)?))
+        Ok(EventIter::ByEventIndex(self.iter_events_by_event_index(
+            event_index,
+            to_block_number,
+            None,
+        )?))
+    }
+
+    fn iter_events_by_first_key(
+        &'env self,
+        first_key: EventKey,
+        event_index: EventIndex,
+    ) -> StorageResult<EventIter<'txn, 'env>> {
+        let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+        let mut cursor = events_by_first_key_table.cursor(&self.txn)?;
+        let current = cursor.lower_bound(&(first_key.clone(), event_index))?;
+        Ok(EventIter::ByFirstKey(EventIterByFirstKey {
+            txn: &self.txn,
+            first_key,
+            current,
+            cursor,
+            events_table: self.open_table(&self.tables.events)?,
+        }))
+    }
+
+    fn iter_events_by_bloom_filter(
+        &'env self,
+        address: Option<ContractAddress>,
+        keys: Vec<HashSet<EventKey>>,
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIter<'txn, 'env>> {
+        let block_skipper = BlockSkipper {
+            events_bloom_filters_table: self.open_table(&self.tables.events_bloom_filters)?,
+            address,
+            keys,
+            matching_block: None,
+        };
+        Ok(EventIter::ByEventIndex(self.iter_events_by_event_index(
+            event_index,
+            to_block_number,
+            Some(block_skipper),
+        )?))
     }
 }
 
 #[allow(missing_docs)]
-/// A wrapper of two iterators [`EventIterByContractAddress`] and [`EventIterByEventIndex`].
+/// A wrapper of the iterators [`EventIterByContractAddress`], [`EventIterByEventIndex`] and
+/// [`EventIterByFirstKey`].
 pub enum EventIter<'txn, 'env> {
     ByContractAddress(EventIterByContractAddress<'txn>),
     ByEventIndex(EventIterByEventIndex<'txn, 'env>),
+    ByFirstKey(EventIterByFirstKey<'txn, 'env>),
 }
 
-/// This iterator is a wrapper of two iterators [`EventIterByContractAddress`]
-/// and [`EventIterByEventIndex`].
+/// This iterator is a wrapper of the iterators [`EventIterByContractAddress`],
+/// [`EventIterByEventIndex`] and [`EventIterByFirstKey`].
 /// With this wrapper we can execute the same code, regardless the
 /// type of iteration used.
 impl Iterator for EventIter<'_, '_> {
@@ -128,6 +227,7 @@ impl Iterator for EventIter<'_, '_> {
         match self {
             EventIter::ByContractAddress(it) => it.next(),
             EventIter::ByEventIndex(it) => it.next(),
+            EventIter::ByFirstKey(it) => it.next(),
         }
         .unwrap_or(None)
     }
@@ -163,6 +263,7 @@ pub struct EventIterByEventIndex<'txn, 'env> {
     events_table: EventsTable<'env>,
     event_index_in_tx_current: EventIndexInTransactionOutput,
     to_block_number: BlockNumber,
+    block_skipper: Option<BlockSkipper<'env>>,
 }
 
 impl EventIterByEventIndex<'_, '_> {
@@ -193,10 +294,20 @@ impl EventIterByEventIndex<'_, '_> {
     /// Returns [`StorageError`](crate::StorageError) if there was an error.
     fn find_next_event_by_event_index(&mut self) -> StorageResult<()> {
         while let Some((tx_index, tx_output)) = &self.tx_current {
-            if tx_index.0 > self.to_block_number {
+            let block_number = tx_index.0;
+            if block_number > self.to_block_number {
                 self.tx_current = None;
                 break;
             }
+            if let Some(block_skipper) = &mut self.block_skipper {
+                if block_skipper.should_skip(self.txn, block_number)? {
+                    let next_block_start =
+                        TransactionIndex(block_number.next(), TransactionOffsetInBlock(0));
+                    self.tx_current = self.tx_cursor.lower_bound(&next_block_start)?;
+                    self.event_index_in_tx_current = EventIndexInTransactionOutput(0);
+                    continue;
+                }
+            }
             // Checks if there's an event in the current event index.
             if tx_output.events_contract_addresses_as_ref().len() > self.event_index_in_tx_current.0
             {
@@ -213,6 +324,67 @@ impl EventIterByEventIndex<'_, '_> {
     }
 }
 
+// Decides which blocks an iterator by the event index skips, by their events bloom filters.
+struct BlockSkipper<'env> {
+    events_bloom_filters_table: EventsBloomFiltersTable<'env>,
+    address: Option<ContractAddress>,
+    keys: Vec<HashSet<EventKey>>,
+    // The last block that wasn't skipped, so that its bloom filter isn't read again for each of
+    // its transactions.
+    matching_block: Option<BlockNumber>,
+}
+
+impl BlockSkipper<'_> {
+    fn should_skip(
+        &mut self,
+        txn: &DbTransaction<'_, RO>,
+        block_number: BlockNumber,
+    ) -> StorageResult<bool> {
+        if self.matching_block == Some(block_number) {
+            return Ok(false);
+        }
+        // A block without a bloom filter isn't skipped.
+        let should_skip = match self.events_bloom_filters_table.get(txn, &block_number)? {
+            Some(events_bloom_filter) => {
+                !events_bloom_filter.may_match(self.address.as_ref(), &self.keys)
+            }
+            None => false,
+        };
+        if !should_skip {
+            self.matching_block = Some(block_number);
+        }
+        Ok(should_skip)
+    }
+}
+
+/// This iterator goes over the events with a given first key, by the order of the event index.
+pub struct EventIterByFirstKey<'txn, 'env> {
+    txn: &'txn DbTransaction<'env, RO>,
+    first_key: EventKey,
+    current: Option<EventsByFirstKeyTableKeyValue>,
+    cursor: EventsByFirstKeyTableCursor<'txn>,
+    events_table: EventsTable<'env>,
+}
+
+impl EventIterByFirstKey<'_, '_> {
+    /// Returns the next event. If there are no more events with the first key, returns None.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn next(&mut self) -> StorageResult<Option<EventsTableKeyValue>> {
+        let Some(((first_key, event_index), address)) = self.current.take() else {
+            return Ok(None);
+        };
+        if first_key != self.first_key {
+            return Ok(None);
+        }
+        self.current = self.cursor.next()?;
+        let key = (address, event_index);
+        let Some(content) = self.events_table.get(self.txn, &key)? else { return Ok(None) };
+        Ok(Some((key, content)))
+    }
+}
+
 impl<'txn, 'env> StorageTxn<'env, RO> {
     /// Returns an events iterator that iterates events by the events table key from the given key.
     ///
@@ -237,6 +409,7 @@ impl<'txn, 'env> StorageTxn<'env, RO> {
     /// * event_index - event index to start from the first event with an index greater or equals
     ///   to.
     /// * to_block_number - block number to stop iterate at it.
+    /// * block_skipper - decides which blocks to skip, if given.
     ///
     /// # Errors
     /// Returns [`StorageError`](crate::StorageError) if there was an error.
@@ -244,6 +417,7 @@ impl<'txn, 'env> StorageTxn<'env, RO> {
         &'env self,
         event_index: EventIndex,
         to_block_number: BlockNumber,
+        block_skipper: Option<BlockSkipper<'env>>,
     ) -> StorageResult<EventIterByEventIndex<'txn, 'env>> {
         let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
         let mut tx_cursor = transaction_outputs_table.cursor(&self.txn)?;
@@ -257,12 +431,113 @@ impl<'txn, 'env> StorageTxn<'env, RO> {
             events_table,
             event_index_in_tx_current: event_index.1,
             to_block_number,
+            block_skipper,
         };
         it.find_next_event_by_event_index()?;
         Ok(it)
     }
 }
 
+// The number of bits that are set in an events bloom filter for every address and key. With about
+// 10 bits per item, as the filters are sized, the false positive rate of a filter is about 1%.
+const EVENTS_BLOOM_FILTER_HASHES: u64 = 7;
+// Distinguishes between an address and a key with the same value in an events bloom filter.
+const BLOOM_ADDRESS_TAG: u8 = 0;
+const BLOOM_KEY_TAG: u8 = 1;
+
+/// A bloom filter over the contract addresses and keys of the events of a block. If the filter
+/// doesn't contain an address or a key, none of the events of the block has it, so queries for
+/// events can skip the block. The filter may contain addresses and keys that the block doesn't
+/// have. The filter is built by an [`EventsBloomFilterBuilder`], which sizes it by the number of
+/// distinct addresses and keys of the block.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct EventsBloomFilter(pub Vec<u64>);
+
+impl EventsBloomFilter {
+    /// Returns false if none of the events of the block was emitted by the given address.
+    pub fn may_contain_address(&self, address: &ContractAddress) -> bool {
+        self.contains(bloom_item_hash(BLOOM_ADDRESS_TAG, address.0.key()))
+    }
+
+    /// Returns false if none of the events of the block has the given key, in any position.
+    pub fn may_contain_key(&self, key: &EventKey) -> bool {
+        self.contains(bloom_item_hash(BLOOM_KEY_TAG, &key.0))
+    }
+
+    /// Returns false if none of the events of the block was emitted by the given address (if
+    /// given) and has, for every position, one of the keys of the position (an empty set matches
+    /// any key).
+    pub fn may_match(&self, address: Option<&ContractAddress>, keys: &[HashSet<EventKey>]) -> bool {
+        if let Some(address) = address {
+            if !self.may_contain_address(address) {
+                return false;
+            }
+        }
+        keys.iter().all(|keys| keys.is_empty() || keys.iter().any(|key| self.may_contain_key(key)))
+    }
+
+    fn contains(&self, item_hash: u64) -> bool {
+        // An empty filter contains nothing.
+        !self.0.is_empty()
+            && bloom_bits(item_hash, self.n_bits())
+                .all(|bit| self.0[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn n_bits(&self) -> u64 {
+        self.0.len() as u64 * 64
+    }
+}
+
+/// Collects the contract addresses and keys of the events of a block, and builds their
+/// [`EventsBloomFilter`] once all of them are known.
+#[derive(Debug, Default)]
+pub struct EventsBloomFilterBuilder(HashSet<u64>);
+
+impl EventsBloomFilterBuilder {
+    /// Adds the contract address and the keys of the event to the filter.
+    pub fn add_event(&mut self, event: &Event) {
+        self.0.insert(bloom_item_hash(BLOOM_ADDRESS_TAG, event.from_address.0.key()));
+        for key in &event.content.keys {
+            self.0.insert(bloom_item_hash(BLOOM_KEY_TAG, &key.0));
+        }
+    }
+
+    /// Returns a filter with the optimal number of bits for the number of distinct addresses and
+    /// keys that were added, n * hashes / ln(2), rounded up to whole words.
+    pub fn build(self) -> EventsBloomFilter {
+        let n_bits = (self.0.len() as f64 * EVENTS_BLOOM_FILTER_HASHES as f64
+            / std::f64::consts::LN_2)
+            .ceil() as u64;
+        let mut events_bloom_filter = EventsBloomFilter(vec![0; n_bits.div_ceil(64) as usize]);
+        let n_bits = events_bloom_filter.n_bits();
+        for item_hash in self.0 {
+            for bit in bloom_bits(item_hash, n_bits) {
+                events_bloom_filter.0[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        events_bloom_filter
+    }
+}
+
+// Returns the FNV-1a hash of an item of an events bloom filter, which is stable across versions as
+// the filters are stored.
+fn bloom_item_hash(tag: u8, item: &StarkFelt) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in std::iter::once(tag).chain(item.bytes().iter().copied()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// Returns the bits of the item in an events bloom filter with the given number of bits. The bits
+// are derived by double hashing from the hash of the item.
+fn bloom_bits(item_hash: u64, n_bits: u64) -> impl Iterator<Item = usize> {
+    let (first_hash, second_hash) = (item_hash & 0xffffffff, (item_hash >> 32) | 1);
+    (0..EVENTS_BLOOM_FILTER_HASHES)
+        .map(move |i| (first_hash.wrapping_add(i.wrapping_mul(second_hash)) % n_bits) as usize)
+}
+
 #[allow(missing_docs)]
 /// Each [`ThinTransactionOutput`] holds a list of event contract addresses so that given a thin
 /// transaction output we can get all its events from the events table (see
@@ -320,6 +595,78 @@ impl ThinTransactionOutput {
             ThinTransactionOutput::L1Handler(tx_output) => tx_output.actual_fee,
         }
     }
+    /// Returns the messages sent by the transaction to the base layer.
+    pub fn messages_sent(&self) -> &Vec<MessageToL1> {
+        match self {
+            ThinTransactionOutput::Declare(tx_output) => &tx_output.messages_sent,
+            ThinTransactionOutput::Deploy(tx_output) => &tx_output.messages_sent,
+            ThinTransactionOutput::DeployAccount(tx_output) => &tx_output.messages_sent,
+            ThinTransactionOutput::Invoke(tx_output) => &tx_output.messages_sent,
+            ThinTransactionOutput::L1Handler(tx_output) => &tx_output.messages_sent,
+        }
+    }
+    /// Returns the execution resources of the transaction.
+    pub fn execution_resources(&self) -> &ExecutionResources {
+        match self {
+            ThinTransactionOutput::Declare(tx_output) => &tx_output.execution_resources,
+            ThinTransactionOutput::Deploy(tx_output) => &tx_output.execution_resources,
+            ThinTransactionOutput::DeployAccount(tx_output) => &tx_output.execution_resources,
+            ThinTransactionOutput::Invoke(tx_output) => &tx_output.execution_resources,
+            ThinTransactionOutput::L1Handler(tx_output) => &tx_output.execution_resources,
+        }
+    }
+    /// Returns the full transaction output, given the events that the transaction emitted.
+    pub fn into_transaction_output(self, events: Vec<Event>) -> TransactionOutput {
+        match self {
+            ThinTransactionOutput::Declare(tx_output) => {
+                TransactionOutput::Declare(DeclareTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::Deploy(tx_output) => {
+                TransactionOutput::Deploy(DeployTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    contract_address: tx_output.contract_address,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::DeployAccount(tx_output) => {
+                TransactionOutput::DeployAccount(DeployAccountTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    contract_address: tx_output.contract_address,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::Invoke(tx_output) => {
+                TransactionOutput::Invoke(InvokeTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+            ThinTransactionOutput::L1Handler(tx_output) => {
+                TransactionOutput::L1Handler(L1HandlerTransactionOutput {
+                    actual_fee: tx_output.actual_fee,
+                    messages_sent: tx_output.messages_sent,
+                    events,
+                    execution_status: tx_output.execution_status,
+                    execution_resources: tx_output.execution_resources,
+                })
+            }
+        }
+    }
 }
 /// A thin version of
 /// [`InvokeTransactionOutput`](starknet_api::transaction::InvokeTransactionOutput), not holding the
@@ -468,6 +815,11 @@ impl From<TransactionOutput> for ThinTransactionOutput {
 
 /// A key-value pair of the events table.
 type EventsTableKeyValue = (EventsTableKey, EventContent);
+/// A key-value pair of the events by first key table.
+type EventsByFirstKeyTableKeyValue = (EventsByFirstKeyTableKey, ContractAddress);
+/// A cursor of the events by first key table.
+type EventsByFirstKeyTableCursor<'txn> =
+    DbCursor<'txn, RO, EventsByFirstKeyTableKey, NoVersionValueWrapper<ContractAddress>>;
 /// A cursor of the events table.
 type EventsTableCursor<'txn> =
     DbCursor<'txn, RO, EventsTableKey, NoVersionValueWrapper<EventContent>>;
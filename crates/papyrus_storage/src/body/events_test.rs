@@ -1,16 +1,26 @@
+use std::collections::HashSet;
 use std::vec;
 
 use assert_matches::assert_matches;
 use camelpaste::paste;
 use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
 use starknet_api::core::{ContractAddress, PatriciaKey};
 use starknet_api::hash::StarkHash;
 use starknet_api::patricia_key;
-use starknet_api::transaction::{EventIndexInTransactionOutput, TransactionOffsetInBlock};
-use test_utils::get_test_block;
+use starknet_api::transaction::{
+    Event,
+    EventContent,
+    EventData,
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionOffsetInBlock,
+};
+use test_utils::{get_test_block, get_test_body};
 
 use crate::body::events::{
     EventIndex,
+    EventsBloomFilterBuilder,
     EventsReader,
     ThinDeclareTransactionOutput,
     ThinDeployAccountTransactionOutput,
@@ -19,7 +29,7 @@ use crate::body::events::{
     ThinL1HandlerTransactionOutput,
     ThinTransactionOutput,
 };
-use crate::body::{BodyStorageWriter, TransactionIndex};
+use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
 use crate::header::HeaderStorageWriter;
 use crate::test_utils::get_test_storage;
 
@@ -112,6 +122,152 @@ async fn iter_events_by_index() {
     }
 }
 
+#[tokio::test]
+async fn iter_events_by_first_key() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let first_key = EventKey(StarkHash::from(1_u128));
+    let other_key = EventKey(StarkHash::from(2_u128));
+    // Some of the events have the key, but not as their first key.
+    let keys = vec![vec![first_key.clone(), other_key], vec![first_key.clone()]];
+    let block = get_test_block(3, Some(5), None, Some(keys));
+    let block_number = block.header.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let mut emitted_events = vec![];
+    for (tx_i, tx_output) in block.body.transaction_outputs.iter().enumerate() {
+        for (event_i, event) in tx_output.events().iter().enumerate() {
+            if event.content.keys.first() != Some(&first_key) {
+                continue;
+            }
+            let event_index = EventIndex(
+                TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
+                EventIndexInTransactionOutput(event_i),
+            );
+            emitted_events.push(((event.from_address, event_index), event.content.clone()))
+        }
+    }
+
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let events = txn.iter_events_by_first_key(first_key.clone(), event_index).unwrap();
+    assert_eq!(events.collect::<Vec<_>>(), emitted_events);
+
+    // The index is cleaned once the body is reverted.
+    drop(txn);
+    storage_writer.begin_rw_txn().unwrap().revert_body(block_number).unwrap().0.commit().unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    assert!(txn.iter_events_by_first_key(first_key, event_index).unwrap().next().is_none());
+}
+
+#[test]
+fn events_bloom_filter() {
+    let address = ContractAddress(patricia_key!("0x1"));
+    let key = EventKey(StarkHash::from(2_u128));
+    let other_address = ContractAddress(patricia_key!("0x3"));
+    let other_key = EventKey(StarkHash::from(4_u128));
+    let mut events_bloom_filter_builder = EventsBloomFilterBuilder::default();
+    events_bloom_filter_builder.add_event(&Event {
+        from_address: address,
+        content: EventContent { keys: vec![key.clone()], data: EventData::default() },
+    });
+    let events_bloom_filter = events_bloom_filter_builder.build();
+
+    assert!(events_bloom_filter.may_contain_address(&address));
+    assert!(events_bloom_filter.may_contain_key(&key));
+    assert!(!events_bloom_filter.may_contain_address(&other_address));
+    assert!(!events_bloom_filter.may_contain_key(&other_key));
+    // Addresses and keys with the same value are distinguished.
+    assert!(!events_bloom_filter.may_contain_key(&EventKey(*address.0.key())));
+    assert!(!events_bloom_filter.may_contain_address(&ContractAddress(patricia_key!("0x2"))));
+
+    // It's enough that one of the keys of every position may be contained.
+    let keys = vec![HashSet::from([key.clone(), other_key.clone()]), HashSet::new()];
+    assert!(events_bloom_filter.may_match(Some(&address), &keys));
+    assert!(events_bloom_filter.may_match(None, &[]));
+    assert!(!events_bloom_filter.may_match(Some(&other_address), &keys));
+    // The key and the other key are in different positions.
+    let keys = vec![HashSet::from([key]), HashSet::from([other_key])];
+    assert!(!events_bloom_filter.may_match(None, &keys));
+}
+
+#[test]
+fn events_bloom_filter_is_sized_by_its_items() {
+    const N_EVENTS: u128 = 1000;
+    let event = |address: u128| Event {
+        from_address: ContractAddress(PatriciaKey::try_from(StarkHash::from(address)).unwrap()),
+        content: EventContent::default(),
+    };
+    let mut events_bloom_filter_builder = EventsBloomFilterBuilder::default();
+    for address in 0..N_EVENTS {
+        events_bloom_filter_builder.add_event(&event(address));
+    }
+    let events_bloom_filter = events_bloom_filter_builder.build();
+    // About 10 bits per item.
+    assert_eq!(events_bloom_filter.0.len(), 158);
+    assert!(events_bloom_filter.may_contain_address(&event(0).from_address));
+
+    // The false positive rate is about 1%.
+    let false_positives = (N_EVENTS..2 * N_EVENTS)
+        .filter(|address| events_bloom_filter.may_contain_address(&event(*address).from_address))
+        .count();
+    assert!(false_positives < 30, "{false_positives} false positives.");
+
+    // A filter of a block without events contains nothing.
+    let empty_events_bloom_filter = EventsBloomFilterBuilder::default().build();
+    assert!(empty_events_bloom_filter.0.is_empty());
+    assert!(!empty_events_bloom_filter.may_contain_address(&event(0).from_address));
+}
+
+#[tokio::test]
+async fn iter_events_by_bloom_filter() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let key = EventKey(StarkHash::from(1_u128));
+    let other_key = EventKey(StarkHash::from(2_u128));
+    // Only the events of block 1 have the key.
+    let mut txn = storage_writer.begin_rw_txn().unwrap();
+    let event_keys = [other_key.clone(), key.clone(), other_key];
+    for (block_number, event_key) in event_keys.into_iter().enumerate() {
+        let body = get_test_body(2, Some(2), None, Some(vec![vec![event_key]]));
+        txn = txn.append_body(BlockNumber(block_number as u64), body).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let event_index = EventIndex(
+        TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    // Returns the block numbers of the iterated events.
+    let iter_events = |keys: Vec<HashSet<EventKey>>| {
+        txn.iter_events_by_bloom_filter(None, keys, event_index, BlockNumber(2))
+            .unwrap()
+            .map(|((_address, event_index), _content)| (event_index.0).0)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(iter_events(vec![HashSet::from([key])]), vec![BlockNumber(1); 4]);
+    let block_numbers = [BlockNumber(0), BlockNumber(1), BlockNumber(2)];
+    assert_eq!(iter_events(vec![]), block_numbers.map(|block_number| [block_number; 4]).concat());
+    assert!(iter_events(vec![HashSet::from([EventKey(StarkHash::from(3_u128))])]).is_empty());
+
+    // The bloom filter is deleted once the body is reverted.
+    assert!(txn.get_events_bloom_filter(BlockNumber(2)).unwrap().is_some());
+    drop(txn);
+    storage_writer.begin_rw_txn().unwrap().revert_body(BlockNumber(2)).unwrap().0.commit().unwrap();
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    assert!(txn.get_events_bloom_filter(BlockNumber(2)).unwrap().is_none());
+}
+
 #[tokio::test]
 async fn revert_events() {
     let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
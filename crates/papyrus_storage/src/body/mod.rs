@@ -24,6 +24,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     ..Default::default()
 //! # };
 //! let block = Block::default();
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
@@ -42,8 +43,11 @@
 mod body_test;
 pub mod events;
 
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 
+use papyrus_common::l1_to_l2_messages::{l1_handler_message_hash, L1ToL2MessageHash};
+use papyrus_common::raw_transaction::RawTransaction;
 use papyrus_proc_macros::latency_histogram;
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockBody, BlockNumber};
@@ -52,6 +56,7 @@ use starknet_api::transaction::{
     Event,
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
     Transaction,
     TransactionHash,
     TransactionOffsetInBlock,
@@ -59,9 +64,15 @@ use starknet_api::transaction::{
 };
 use tracing::debug;
 
-use crate::body::events::{EventIndex, ThinTransactionOutput};
+use crate::body::events::{
+    EventIndex,
+    EventsBloomFilter,
+    EventsBloomFilterBuilder,
+    ThinTransactionOutput,
+};
 use crate::db::serialization::{NoVersionValueWrapper, StorageSerde};
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+use crate::recent_blocks_cache::CacheUpdate;
 use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageScope, StorageTxn};
 
 type TransactionsTable<'env> =
@@ -72,8 +83,15 @@ type TransactionHashToIdxTable<'env> =
     TableHandle<'env, TransactionHash, NoVersionValueWrapper<TransactionIndex>>;
 type TransactionIdxToHashTable<'env> =
     TableHandle<'env, TransactionIndex, NoVersionValueWrapper<TransactionHash>>;
+type MessageHashToIdxTable<'env> =
+    TableHandle<'env, L1ToL2MessageHash, NoVersionValueWrapper<TransactionIndex>>;
 type EventsTableKey = (ContractAddress, EventIndex);
 type EventsTable<'env> = TableHandle<'env, EventsTableKey, NoVersionValueWrapper<EventContent>>;
+type EventsByFirstKeyTableKey = (EventKey, EventIndex);
+type EventsByFirstKeyTable<'env> =
+    TableHandle<'env, EventsByFirstKeyTableKey, NoVersionValueWrapper<ContractAddress>>;
+type EventsBloomFiltersTable<'env> =
+    TableHandle<'env, BlockNumber, NoVersionValueWrapper<EventsBloomFilter>>;
 
 /// The index of a transaction in a block.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -85,6 +103,10 @@ pub trait BodyStorageReader {
     /// The body marker is the first block number that doesn't exist yet.
     fn get_body_marker(&self) -> StorageResult<BlockNumber>;
 
+    /// The pruned body marker is the first block number whose body wasn't pruned. The transactions,
+    /// transaction outputs and events of the blocks below it aren't stored.
+    fn get_pruned_body_marker(&self) -> StorageResult<BlockNumber>;
+
     /// Returns the transaction and its execution status at the given index.
     fn get_transaction(
         &self,
@@ -127,6 +149,24 @@ pub trait BodyStorageReader {
         block_number: BlockNumber,
     ) -> StorageResult<Option<Vec<TransactionHash>>>;
 
+    /// Returns up to `max_count` transactions of the block with the given number, starting from the
+    /// given offset, without reading the rest of the block.
+    fn get_block_transactions_range(
+        &self,
+        block_number: BlockNumber,
+        start_offset: TransactionOffsetInBlock,
+        max_count: usize,
+    ) -> StorageResult<Option<Vec<Transaction>>>;
+
+    /// Returns up to `max_count` transaction hashes of the block with the given number, starting
+    /// from the given offset, without reading the rest of the block.
+    fn get_block_transaction_hashes_range(
+        &self,
+        block_number: BlockNumber,
+        start_offset: TransactionOffsetInBlock,
+        max_count: usize,
+    ) -> StorageResult<Option<Vec<TransactionHash>>>;
+
     /// Returns the transaction outputs of the block with the given number.
     fn get_block_transaction_outputs(
         &self,
@@ -138,6 +178,20 @@ pub trait BodyStorageReader {
         &self,
         block_number: BlockNumber,
     ) -> StorageResult<Option<usize>>;
+
+    /// Returns the bloom filter over the contract addresses and keys of the events of the block
+    /// with the given number.
+    fn get_events_bloom_filter(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<EventsBloomFilter>>;
+
+    /// Returns the raw JSON of the transaction at the given index, if it was stored with the body
+    /// because it had data that the node doesn't model.
+    fn get_raw_transaction(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> StorageResult<Option<RawTransaction>>;
 }
 
 type RevertedBlockBody =
@@ -155,6 +209,15 @@ where
     // TODO(yair): make this work without consuming the body.
     fn append_body(self, block_number: BlockNumber, block_body: BlockBody) -> StorageResult<Self>;
 
+    /// Stores the raw JSON of transactions of a block, by their offset in the block, next to its
+    /// body. The body of the block should be appended first. The raw transactions are removed
+    /// with the body.
+    fn append_raw_transactions(
+        self,
+        block_number: BlockNumber,
+        raw_transactions: BTreeMap<TransactionOffsetInBlock, RawTransaction>,
+    ) -> StorageResult<Self>;
+
     /// Removes a block body from the storage and returns the removed data.
     fn revert_body(
         self,
@@ -168,6 +231,11 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         Ok(markers_table.get(&self.txn, &MarkerKind::Body)?.unwrap_or_default())
     }
 
+    fn get_pruned_body_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::PrunedBody)?.unwrap_or_default())
+    }
+
     fn get_transaction(
         &self,
         transaction_index: TransactionIndex,
@@ -214,6 +282,12 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         &self,
         tx_hash: &TransactionHash,
     ) -> StorageResult<Option<TransactionIndex>> {
+        if let Some(idx) = self
+            .recent_blocks_cache
+            .get_transaction_idx(self.recent_blocks_cache_view.as_ref(), tx_hash)
+        {
+            return Ok(Some(idx));
+        }
         let transaction_hash_to_idx_table =
             self.open_table(&self.tables.transaction_hash_to_idx)?;
         let idx = transaction_hash_to_idx_table.get(&self.txn, tx_hash)?;
@@ -238,6 +312,37 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
         self.get_transactions_in_block(block_number, transactions_table)
     }
 
+    fn get_block_transactions_range(
+        &self,
+        block_number: BlockNumber,
+        start_offset: TransactionOffsetInBlock,
+        max_count: usize,
+    ) -> StorageResult<Option<Vec<Transaction>>> {
+        let transactions_table = self.open_table(&self.tables.transactions)?;
+        self.get_transactions_in_block_range(
+            block_number,
+            start_offset,
+            max_count,
+            transactions_table,
+        )
+    }
+
+    fn get_block_transaction_hashes_range(
+        &self,
+        block_number: BlockNumber,
+        start_offset: TransactionOffsetInBlock,
+        max_count: usize,
+    ) -> StorageResult<Option<Vec<TransactionHash>>> {
+        let transaction_idx_to_hash_table =
+            self.open_table(&self.tables.transaction_idx_to_hash)?;
+        self.get_transactions_in_block_range(
+            block_number,
+            start_offset,
+            max_count,
+            transaction_idx_to_hash_table,
+        )
+    }
+
     fn get_block_transaction_hashes(
         &self,
         block_number: BlockNumber,
@@ -280,6 +385,22 @@ impl<'env, Mode: TransactionKind> BodyStorageReader for StorageTxn<'env, Mode> {
 
         Ok(Some(last_tx_index.0 + 1))
     }
+
+    fn get_events_bloom_filter(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<EventsBloomFilter>> {
+        let events_bloom_filters_table = self.open_table(&self.tables.events_bloom_filters)?;
+        Ok(events_bloom_filters_table.get(&self.txn, &block_number)?)
+    }
+
+    fn get_raw_transaction(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> StorageResult<Option<RawTransaction>> {
+        let raw_transactions_table = self.open_table(&self.tables.raw_transactions)?;
+        Ok(raw_transactions_table.get(&self.txn, &transaction_index)?)
+    }
 }
 
 impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
@@ -290,16 +411,30 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         &self,
         block_number: BlockNumber,
         table: TableHandle<'env, TransactionIndex, NoVersionValueWrapper<V>>,
+    ) -> StorageResult<Option<Vec<V>>> {
+        self.get_transactions_in_block_range(
+            block_number,
+            TransactionOffsetInBlock(0),
+            usize::MAX,
+            table,
+        )
+    }
+
+    fn get_transactions_in_block_range<V: StorageSerde + Debug>(
+        &self,
+        block_number: BlockNumber,
+        start_offset: TransactionOffsetInBlock,
+        max_count: usize,
+        table: TableHandle<'env, TransactionIndex, NoVersionValueWrapper<V>>,
     ) -> StorageResult<Option<Vec<V>>> {
         if self.get_body_marker()? <= block_number {
             return Ok(None);
         }
         let mut cursor = table.cursor(&self.txn)?;
-        let mut current =
-            cursor.lower_bound(&TransactionIndex(block_number, TransactionOffsetInBlock(0)))?;
+        let mut current = cursor.lower_bound(&TransactionIndex(block_number, start_offset))?;
         let mut res = Vec::new();
         while let Some((TransactionIndex(current_block_number, _), tx)) = current {
-            if current_block_number != block_number {
+            if current_block_number != block_number || res.len() == max_count {
                 break;
             }
             res.push(tx);
@@ -311,7 +446,16 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
 
 impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
     #[latency_histogram("storage_append_body_latency_seconds")]
-    fn append_body(self, block_number: BlockNumber, block_body: BlockBody) -> StorageResult<Self> {
+    fn append_body(
+        mut self,
+        block_number: BlockNumber,
+        block_body: BlockBody,
+    ) -> StorageResult<Self> {
+        if self.scope != StorageScope::StateOnly {
+            self.update_recent_blocks_cache(|| {
+                CacheUpdate::AppendTransactions(block_number, block_body.transaction_hashes.clone())
+            });
+        }
         let markers_table = self.open_table(&self.tables.markers)?;
         update_marker(&self.txn, &markers_table, block_number)?;
 
@@ -319,10 +463,14 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
             let transactions_table = self.open_table(&self.tables.transactions)?;
             let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
             let events_table = self.open_table(&self.tables.events)?;
+            let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+            let events_bloom_filters_table = self.open_table(&self.tables.events_bloom_filters)?;
             let transaction_hash_to_idx_table =
                 self.open_table(&self.tables.transaction_hash_to_idx)?;
             let transaction_idx_to_hash_table =
                 self.open_table(&self.tables.transaction_idx_to_hash)?;
+            let message_hash_to_idx_table =
+                self.open_table(&self.tables.l1_handler_message_hash_to_idx)?;
 
             write_transactions(
                 &block_body,
@@ -330,6 +478,7 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
                 &transactions_table,
                 &transaction_hash_to_idx_table,
                 &transaction_idx_to_hash_table,
+                &message_hash_to_idx_table,
                 block_number,
             )?;
             write_transaction_outputs(
@@ -337,19 +486,42 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
                 &self.txn,
                 &transaction_outputs_table,
                 &events_table,
+                &events_by_first_key_table,
+                &events_bloom_filters_table,
                 block_number,
             )?;
+
+            if let Some(body_retention_blocks) = self.body_retention_blocks {
+                self.prune_bodies(block_number.next(), body_retention_blocks)?;
+            }
         }
 
         Ok(self)
     }
 
-    fn revert_body(
+    fn append_raw_transactions(
         self,
         block_number: BlockNumber,
-    ) -> StorageResult<(Self, Option<RevertedBlockBody>)> {
-        let markers_table = self.open_table(&self.tables.markers)?;
+        raw_transactions: BTreeMap<TransactionOffsetInBlock, RawTransaction>,
+    ) -> StorageResult<Self> {
+        if self.scope == StorageScope::StateOnly {
+            return Ok(self);
+        }
+        let raw_transactions_table = self.open_table(&self.tables.raw_transactions)?;
+        for (offset, raw_transaction) in raw_transactions {
+            raw_transactions_table.insert(
+                &self.txn,
+                &TransactionIndex(block_number, offset),
+                &raw_transaction,
+            )?;
+        }
+        Ok(self)
+    }
 
+    fn revert_body(
+        mut self,
+        block_number: BlockNumber,
+    ) -> StorageResult<(Self, Option<RevertedBlockBody>)> {
         // Assert that body marker equals the reverted block number + 1
         let current_header_marker = self.get_body_marker()?;
         if current_header_marker != block_number.next() {
@@ -359,59 +531,151 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
             );
             return Ok((self, None));
         }
+        if self.scope != StorageScope::StateOnly {
+            self.update_recent_blocks_cache(|| CacheUpdate::RevertTransactions(block_number));
+        }
 
-        let reverted_block_body = 'reverted_block_body: {
-            if self.scope == StorageScope::StateOnly {
-                break 'reverted_block_body None;
-            }
+        let markers_table = self.open_table(&self.tables.markers)?;
 
-            let transactions_table = self.open_table(&self.tables.transactions)?;
-            let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
-            let transaction_hash_to_idx_table =
-                self.open_table(&self.tables.transaction_hash_to_idx)?;
-            let transaction_idx_to_hash_table =
-                self.open_table(&self.tables.transaction_idx_to_hash)?;
-            let events_table = self.open_table(&self.tables.events)?;
+        // Only the transaction hashes of a block whose body was pruned are left to delete.
+        let pruned_body_marker = self.get_pruned_body_marker()?;
+        let reverted_block_body = if self.scope == StorageScope::StateOnly {
+            None
+        } else if block_number < pruned_body_marker {
+            self.delete_pruned_transaction_hashes(block_number)?;
+            None
+        } else {
+            Some(self.delete_body_data(block_number, true)?)
+        };
 
-            let transactions = self
-                .get_block_transactions(block_number)?
-                .unwrap_or_else(|| panic!("Missing transactions for block {block_number}."));
-            let transaction_outputs = self
-                .get_block_transaction_outputs(block_number)?
-                .unwrap_or_else(|| panic!("Missing transaction outputs for block {block_number}."));
-            let transaction_hashes = self
-                .get_block_transaction_hashes(block_number)?
-                .unwrap_or_else(|| panic!("Missing transaction hashes for block {block_number}."));
-
-            // Delete the transactions data.
-            let mut events = vec![];
-            for (offset, tx_output) in transaction_outputs.iter().enumerate() {
-                let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
-                let tx_hash = self.get_transaction_hash_by_idx(&tx_index)?.unwrap_or_else(|| {
-                    panic!("Missing transaction hash for transaction index {tx_index:?}.")
+        // A block whose body was pruned may be appended again after the revert.
+        if pruned_body_marker > block_number {
+            markers_table.upsert(&self.txn, &MarkerKind::PrunedBody, &block_number)?;
+        }
+        markers_table.upsert(&self.txn, &MarkerKind::Body, &block_number)?;
+        Ok((self, reverted_block_body))
+    }
+}
+
+// The maximal number of bodies that are pruned when a body is appended, so that enabling the
+// pruning for an existing storage doesn't create a huge transaction. The pruning catches up while
+// the following bodies are appended.
+const MAX_BODIES_TO_PRUNE_PER_APPEND: u64 = 10;
+
+impl<'env> StorageTxn<'env, RW> {
+    // Deletes the transactions, transaction outputs and events of the block and returns them. The
+    // hashes of the transactions are deleted only if `delete_transaction_hashes` is set, so that a
+    // pruned block keeps them and a transaction whose body was pruned can be told apart from a
+    // transaction that doesn't exist.
+    fn delete_body_data(
+        &self,
+        block_number: BlockNumber,
+        delete_transaction_hashes: bool,
+    ) -> StorageResult<RevertedBlockBody> {
+        let transactions_table = self.open_table(&self.tables.transactions)?;
+        let transaction_outputs_table = self.open_table(&self.tables.transaction_outputs)?;
+        let transaction_hash_to_idx_table =
+            self.open_table(&self.tables.transaction_hash_to_idx)?;
+        let transaction_idx_to_hash_table =
+            self.open_table(&self.tables.transaction_idx_to_hash)?;
+        let message_hash_to_idx_table =
+            self.open_table(&self.tables.l1_handler_message_hash_to_idx)?;
+        let events_table = self.open_table(&self.tables.events)?;
+        let events_by_first_key_table = self.open_table(&self.tables.events_by_first_key)?;
+        let events_bloom_filters_table = self.open_table(&self.tables.events_bloom_filters)?;
+        let raw_transactions_table = self.open_table(&self.tables.raw_transactions)?;
+
+        let transactions = self
+            .get_block_transactions(block_number)?
+            .unwrap_or_else(|| panic!("Missing transactions for block {block_number}."));
+        let transaction_outputs = self
+            .get_block_transaction_outputs(block_number)?
+            .unwrap_or_else(|| panic!("Missing transaction outputs for block {block_number}."));
+        let transaction_hashes = self
+            .get_block_transaction_hashes(block_number)?
+            .unwrap_or_else(|| panic!("Missing transaction hashes for block {block_number}."));
+
+        // Delete the transactions data.
+        let mut events = vec![];
+        for (offset, tx_output) in transaction_outputs.iter().enumerate() {
+            let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            let tx_hash = self.get_transaction_hash_by_idx(&tx_index)?.unwrap_or_else(|| {
+                panic!("Missing transaction hash for transaction index {tx_index:?}.")
+            });
+            let mut tx_events = vec![];
+            for (index, from_address) in
+                tx_output.events_contract_addresses_as_ref().iter().enumerate()
+            {
+                let event_index = EventIndex(tx_index, EventIndexInTransactionOutput(index));
+                let key = (*from_address, event_index);
+                let content = events_table.get(&self.txn, &key)?.unwrap_or_else(|| {
+                    panic!("Missing events for transaction output {tx_index:?}.")
                 });
-                let mut tx_events = vec![];
-                for (index, from_address) in
-                    tx_output.events_contract_addresses_as_ref().iter().enumerate()
-                {
-                    let key =
-                        (*from_address, EventIndex(tx_index, EventIndexInTransactionOutput(index)));
-                    tx_events.push(events_table.get(&self.txn, &key)?.unwrap_or_else(|| {
-                        panic!("Missing events for transaction output {tx_index:?}.")
-                    }));
-                    events_table.delete(&self.txn, &key)?;
+                events_table.delete(&self.txn, &key)?;
+                if let Some(first_key) = content.keys.first() {
+                    events_by_first_key_table
+                        .delete(&self.txn, &(first_key.clone(), event_index))?;
                 }
-                events.push(tx_events);
-                transactions_table.delete(&self.txn, &tx_index)?;
-                transaction_outputs_table.delete(&self.txn, &tx_index)?;
+                tx_events.push(content);
+            }
+            events.push(tx_events);
+            transactions_table.delete(&self.txn, &tx_index)?;
+            transaction_outputs_table.delete(&self.txn, &tx_index)?;
+            if delete_transaction_hashes {
                 transaction_hash_to_idx_table.delete(&self.txn, &tx_hash)?;
                 transaction_idx_to_hash_table.delete(&self.txn, &tx_index)?;
             }
-            Some((transactions, transaction_outputs, transaction_hashes, events))
-        };
+            raw_transactions_table.delete(&self.txn, &tx_index)?;
+            if let Transaction::L1Handler(tx) = &transactions[offset] {
+                if let Some(message_hash) = l1_handler_message_hash(tx) {
+                    // The message may have been sent again by a later transaction.
+                    if message_hash_to_idx_table.get(&self.txn, &message_hash)? == Some(tx_index) {
+                        message_hash_to_idx_table.delete(&self.txn, &message_hash)?;
+                    }
+                }
+            }
+        }
+        events_bloom_filters_table.delete(&self.txn, &block_number)?;
+        Ok((transactions, transaction_outputs, transaction_hashes, events))
+    }
 
-        markers_table.upsert(&self.txn, &MarkerKind::Body, &block_number)?;
-        Ok((self, reverted_block_body))
+    // Deletes the transaction hashes that the pruning of the body of the block kept.
+    fn delete_pruned_transaction_hashes(&self, block_number: BlockNumber) -> StorageResult<()> {
+        let transaction_hash_to_idx_table =
+            self.open_table(&self.tables.transaction_hash_to_idx)?;
+        let transaction_idx_to_hash_table =
+            self.open_table(&self.tables.transaction_idx_to_hash)?;
+        let transaction_hashes =
+            self.get_block_transaction_hashes(block_number)?.unwrap_or_default();
+        for (offset, tx_hash) in transaction_hashes.iter().enumerate() {
+            let tx_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            transaction_hash_to_idx_table.delete(&self.txn, tx_hash)?;
+            transaction_idx_to_hash_table.delete(&self.txn, &tx_index)?;
+        }
+        Ok(())
+    }
+
+    // Deletes the bodies of the blocks that precede the latest body_retention_blocks blocks
+    // (starting from the given body marker), leaving their headers, state diffs and transaction
+    // hashes in place.
+    fn prune_bodies(
+        &self,
+        body_marker: BlockNumber,
+        body_retention_blocks: u64,
+    ) -> StorageResult<()> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let mut pruned_body_marker = self.get_pruned_body_marker()?;
+        let first_retained_block = body_marker
+            .0
+            .saturating_sub(body_retention_blocks)
+            .min(pruned_body_marker.0 + MAX_BODIES_TO_PRUNE_PER_APPEND);
+        while pruned_body_marker.0 < first_retained_block {
+            debug!("Pruning the body of block {pruned_body_marker}.");
+            self.delete_body_data(pruned_body_marker, false)?;
+            pruned_body_marker = pruned_body_marker.next();
+        }
+        markers_table.upsert(&self.txn, &MarkerKind::PrunedBody, &pruned_body_marker)?;
+        Ok(())
     }
 }
 
@@ -421,6 +685,7 @@ fn write_transactions<'env>(
     transactions_table: &'env TransactionsTable<'env>,
     transaction_hash_to_idx_table: &'env TransactionHashToIdxTable<'env>,
     transaction_idx_to_hash_table: &'env TransactionIdxToHashTable<'env>,
+    message_hash_to_idx_table: &'env MessageHashToIdxTable<'env>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
     for (index, (tx, tx_hash)) in
@@ -435,6 +700,13 @@ fn write_transactions<'env>(
             tx_hash,
             transaction_index,
         )?;
+        // An L1 handler transaction that failed can't be sent again with the same message, but
+        // the index keeps the latest transaction to avoid failing on such a duplicate.
+        if let Transaction::L1Handler(tx) = tx {
+            if let Some(message_hash) = l1_handler_message_hash(tx) {
+                message_hash_to_idx_table.upsert(txn, &message_hash, &transaction_index)?;
+            }
+        }
         transactions_table.insert(txn, &transaction_index, tx)?;
     }
     Ok(())
@@ -445,18 +717,29 @@ fn write_transaction_outputs<'env>(
     txn: &DbTransaction<'env, RW>,
     transaction_outputs_table: &'env TransactionOutputsTable<'env>,
     events_table: &'env EventsTable<'env>,
+    events_by_first_key_table: &'env EventsByFirstKeyTable<'env>,
+    events_bloom_filters_table: &'env EventsBloomFiltersTable<'env>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
+    let mut events_bloom_filter_builder = EventsBloomFilterBuilder::default();
     for (index, tx_output) in block_body.transaction_outputs.into_iter().enumerate() {
         let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(index));
 
-        write_events(&tx_output, txn, events_table, transaction_index)?;
+        write_events(
+            &tx_output,
+            txn,
+            events_table,
+            events_by_first_key_table,
+            &mut events_bloom_filter_builder,
+            transaction_index,
+        )?;
         transaction_outputs_table.insert(
             txn,
             &transaction_index,
             &ThinTransactionOutput::from(tx_output),
         )?;
     }
+    events_bloom_filters_table.insert(txn, &block_number, &events_bloom_filter_builder.build())?;
     Ok(())
 }
 
@@ -464,11 +747,22 @@ fn write_events<'env>(
     tx_output: &TransactionOutput,
     txn: &DbTransaction<'env, RW>,
     events_table: &'env EventsTable<'env>,
+    events_by_first_key_table: &'env EventsByFirstKeyTable<'env>,
+    events_bloom_filter_builder: &mut EventsBloomFilterBuilder,
     transaction_index: TransactionIndex,
 ) -> StorageResult<()> {
     for (index, event) in tx_output.events().iter().enumerate() {
         let event_index = EventIndex(transaction_index, EventIndexInTransactionOutput(index));
         events_table.insert(txn, &(event.from_address, event_index), &event.content)?;
+        events_bloom_filter_builder.add_event(event);
+        // Filters usually narrow the events by their first key, so the events are indexed by it.
+        if let Some(first_key) = event.content.keys.first() {
+            events_by_first_key_table.insert(
+                txn,
+                &(first_key.clone(), event_index),
+                &event.from_address,
+            )?;
+        }
     }
     Ok(())
 }
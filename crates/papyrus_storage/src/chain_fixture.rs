@@ -0,0 +1,238 @@
+//! A deterministic chain for tests and benchmarks of the storage crate users.
+#[cfg(test)]
+#[path = "chain_fixture_test.rs"]
+mod chain_fixture_test;
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use indexmap::IndexMap;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use starknet_api::block::{Block, BlockHash, BlockHeader, BlockNumber, BlockTimestamp};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::{ContractClass, StateDiff, StorageKey};
+use starknet_api::transaction::{EventKey, TransactionHash};
+use test_utils::{get_rand_test_body_with_transaction_types, TestTransactionType};
+
+use crate::body::BodyStorageWriter;
+use crate::header::HeaderStorageWriter;
+use crate::state::StateStorageWriter;
+use crate::{StorageResult, StorageWriter};
+
+/// Builds a [`ChainFixture`]: a chain that is generated deterministically from a seed, so tests and
+/// benchmarks in different crates can use the same reproducible data.
+///
+/// Block 0 deploys the initial contracts. Every block has a random number of transactions, most of
+/// them invokes, and each of its transactions has the same random number of events, emitted by the
+/// deployed contracts. The state diff
+/// of a block declares a class for each declare transaction, deploys a contract for each deploy
+/// account transaction, increments the nonces of the contracts that sent invokes and updates
+/// random storage keys of the deployed contracts.
+#[derive(Clone, Debug)]
+pub struct ChainFixtureBuilder {
+    n_blocks: u64,
+    seed: u64,
+    transactions_per_block: RangeInclusive<usize>,
+    max_events_per_transaction: usize,
+    initial_contracts: usize,
+    storage_keys_per_contract: u128,
+    storage_updates_per_block: usize,
+}
+
+impl ChainFixtureBuilder {
+    /// Returns a builder of a chain with the given number of blocks and the default distributions.
+    pub fn new(n_blocks: u64) -> Self {
+        Self {
+            n_blocks,
+            seed: 0,
+            transactions_per_block: 1..=20,
+            max_events_per_transaction: 3,
+            initial_contracts: 100,
+            storage_keys_per_contract: 32,
+            storage_updates_per_block: 20,
+        }
+    }
+
+    /// Sets the seed the chain is generated from.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the range of the number of transactions in a block.
+    pub fn transactions_per_block(mut self, transactions_per_block: RangeInclusive<usize>) -> Self {
+        self.transactions_per_block = transactions_per_block;
+        self
+    }
+
+    /// Sets the maximal number of events of a transaction.
+    pub fn max_events_per_transaction(mut self, max_events_per_transaction: usize) -> Self {
+        self.max_events_per_transaction = max_events_per_transaction;
+        self
+    }
+
+    /// Sets the number of contracts that block 0 deploys. Must be positive.
+    pub fn initial_contracts(mut self, initial_contracts: usize) -> Self {
+        self.initial_contracts = initial_contracts;
+        self
+    }
+
+    /// Sets the number of storage keys of each contract that the blocks update.
+    pub fn storage_keys_per_contract(mut self, storage_keys_per_contract: u128) -> Self {
+        self.storage_keys_per_contract = storage_keys_per_contract;
+        self
+    }
+
+    /// Sets the number of storage entries that each block updates.
+    pub fn storage_updates_per_block(mut self, storage_updates_per_block: usize) -> Self {
+        self.storage_updates_per_block = storage_updates_per_block;
+        self
+    }
+
+    /// Generates the chain.
+    pub fn build(&self) -> ChainFixture {
+        assert!(self.initial_contracts > 0, "The chain must deploy contracts in block 0.");
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut fixture = ChainFixture {
+            blocks: vec![],
+            state_diffs: vec![],
+            contracts: vec![],
+            storage_keys_per_contract: self.storage_keys_per_contract,
+        };
+        let mut nonces = HashMap::new();
+        let mut parent_hash = BlockHash::default();
+        for block_number in (0..self.n_blocks).map(BlockNumber) {
+            let mut state_diff = StateDiff::default();
+            if block_number == BlockNumber(0) {
+                state_diff
+                    .deprecated_declared_classes
+                    .insert(FIXTURE_ACCOUNT_CLASS_HASH, DeprecatedContractClass::default());
+                for _ in 0..self.initial_contracts {
+                    deploy_random_contract(&mut rng, &mut fixture.contracts, &mut state_diff);
+                }
+            }
+
+            let transaction_types = (0..rng.gen_range(self.transactions_per_block.clone()))
+                .map(|_| random_transaction_type(&mut rng))
+                .collect::<Vec<_>>();
+            let event_keys = (0..FIXTURE_EVENT_SELECTORS)
+                .map(|selector| EventKey(StarkFelt::from(selector)))
+                .collect();
+            let mut body = get_rand_test_body_with_transaction_types(
+                &mut rng,
+                &transaction_types,
+                rng.gen_range(0..=self.max_events_per_transaction),
+                Some(fixture.contracts.clone()),
+                Some(vec![event_keys]),
+            );
+            body.transaction_hashes =
+                body.transactions.iter().map(|_| TransactionHash(random_felt(&mut rng))).collect();
+
+            for transaction_type in &transaction_types {
+                match transaction_type {
+                    TestTransactionType::Declare => {
+                        state_diff.declared_classes.insert(
+                            ClassHash(random_felt(&mut rng)),
+                            (CompiledClassHash(random_felt(&mut rng)), ContractClass::default()),
+                        );
+                    }
+                    TestTransactionType::DeployAccount => {
+                        deploy_random_contract(&mut rng, &mut fixture.contracts, &mut state_diff);
+                    }
+                    TestTransactionType::Invoke => {
+                        let sender = fixture.contracts[rng.gen_range(0..fixture.contracts.len())];
+                        let nonce = nonces.entry(sender).or_insert(0_u64);
+                        *nonce += 1;
+                        state_diff.nonces.insert(sender, Nonce(StarkFelt::from(*nonce)));
+                    }
+                    TestTransactionType::Deploy | TestTransactionType::L1Handler => {}
+                }
+            }
+            for _ in 0..self.storage_updates_per_block {
+                let address = fixture.contracts[rng.gen_range(0..fixture.contracts.len())];
+                let key = StorageKey::from(rng.gen_range(0..self.storage_keys_per_contract));
+                state_diff
+                    .storage_diffs
+                    .entry(address)
+                    .or_default()
+                    .insert(key, StarkFelt::from(rng.next_u64()));
+            }
+
+            let header = BlockHeader {
+                block_hash: BlockHash(random_felt(&mut rng)),
+                parent_hash,
+                block_number,
+                timestamp: BlockTimestamp(FIXTURE_GENESIS_TIMESTAMP + block_number.0 * 12),
+                ..Default::default()
+            };
+            parent_hash = header.block_hash;
+            fixture.blocks.push(Block { header, body });
+            fixture.state_diffs.push(state_diff);
+        }
+        fixture
+    }
+}
+
+/// A chain generated by a [`ChainFixtureBuilder`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainFixture {
+    /// The blocks of the chain, starting from block 0.
+    pub blocks: Vec<Block>,
+    /// The state diffs of the blocks.
+    pub state_diffs: Vec<StateDiff>,
+    /// The addresses of all the contracts the chain deploys.
+    pub contracts: Vec<ContractAddress>,
+    /// The storage keys of each contract that the blocks update are below this number.
+    pub storage_keys_per_contract: u128,
+}
+
+impl ChainFixture {
+    /// Writes the chain into the storage, which must not have blocks.
+    pub fn write_to_storage(&self, writer: &mut StorageWriter) -> StorageResult<()> {
+        for (block, state_diff) in self.blocks.iter().zip(&self.state_diffs) {
+            let block_number = block.header.block_number;
+            writer
+                .begin_rw_txn()?
+                .append_header(block_number, &block.header)?
+                .append_body(block_number, block.body.clone())?
+                .append_state_diff(block_number, state_diff.clone(), IndexMap::new())?
+                .commit()?;
+        }
+        Ok(())
+    }
+}
+
+// The class of the contracts the chain deploys, declared in block 0.
+const FIXTURE_ACCOUNT_CLASS_HASH: ClassHash = ClassHash(StarkFelt::ONE);
+// The number of different selectors of the events, which are their only key.
+const FIXTURE_EVENT_SELECTORS: u64 = 4;
+// The timestamp of block 0. The blocks are 12 seconds apart.
+const FIXTURE_GENESIS_TIMESTAMP: u64 = 1_700_000_000;
+
+// Returns a random transaction type, with the proportions of a typical block.
+fn random_transaction_type(rng: &mut ChaCha8Rng) -> TestTransactionType {
+    match rng.gen_range(0..100) {
+        0..=84 => TestTransactionType::Invoke,
+        85..=89 => TestTransactionType::DeployAccount,
+        90..=94 => TestTransactionType::Declare,
+        _ => TestTransactionType::L1Handler,
+    }
+}
+
+// The felt is small enough to be a contract address or a storage key.
+fn random_felt(rng: &mut ChaCha8Rng) -> StarkFelt {
+    StarkFelt::from(rng.gen::<u128>())
+}
+
+fn deploy_random_contract(
+    rng: &mut ChaCha8Rng,
+    contracts: &mut Vec<ContractAddress>,
+    state_diff: &mut StateDiff,
+) {
+    let address = ContractAddress::from(rng.gen::<u128>());
+    state_diff.deployed_contracts.insert(address, FIXTURE_ACCOUNT_CLASS_HASH);
+    contracts.push(address);
+}
@@ -0,0 +1,71 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::state::StateNumber;
+
+use crate::body::BodyStorageReader;
+use crate::chain_fixture::ChainFixtureBuilder;
+use crate::header::HeaderStorageReader;
+use crate::state::StateStorageReader;
+use crate::test_utils::get_test_storage;
+
+#[test]
+fn chain_fixture_is_deterministic() {
+    let builder = ChainFixtureBuilder::new(10).seed(7);
+    assert_eq!(builder.build(), builder.build());
+    assert_ne!(builder.build(), builder.clone().seed(8).build());
+}
+
+#[test]
+fn chain_fixture_is_chained() {
+    let fixture = ChainFixtureBuilder::new(10).transactions_per_block(2..=5).build();
+    assert_eq!(fixture.blocks.len(), 10);
+    assert_eq!(fixture.state_diffs.len(), 10);
+    for (block_number, block) in fixture.blocks.iter().enumerate() {
+        assert_eq!(block.header.block_number, BlockNumber(block_number as u64));
+        assert!((2..=5).contains(&block.body.transactions.len()));
+        assert_eq!(block.body.transaction_hashes.len(), block.body.transactions.len());
+    }
+    for blocks in fixture.blocks.windows(2) {
+        assert_eq!(blocks[1].header.parent_hash, blocks[0].header.block_hash);
+        assert!(blocks[1].header.timestamp > blocks[0].header.timestamp);
+    }
+}
+
+#[test]
+fn chain_fixture_write_to_storage() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let fixture = ChainFixtureBuilder::new(5).initial_contracts(3).build();
+    fixture.write_to_storage(&mut writer).unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(5));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(5));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(5));
+
+    let last_block = fixture.blocks.last().unwrap();
+    assert_eq!(txn.get_block_header(BlockNumber(4)).unwrap().as_ref(), Some(&last_block.header));
+    assert_eq!(
+        txn.get_block_transactions(BlockNumber(4)).unwrap(),
+        Some(last_block.body.transactions.clone())
+    );
+
+    // The latest value of each updated storage entry is readable.
+    let state_reader = txn.get_state_reader().unwrap();
+    let state_number = StateNumber::right_after_block(BlockNumber(4));
+    for state_diff in &fixture.state_diffs {
+        for (address, storage_diff) in &state_diff.storage_diffs {
+            for key in storage_diff.keys() {
+                let latest_value = fixture
+                    .state_diffs
+                    .iter()
+                    .rev()
+                    .find_map(|state_diff| state_diff.storage_diffs.get(address)?.get(key))
+                    .unwrap();
+                assert_eq!(
+                    state_reader.get_storage_at(state_number, address, key).unwrap(),
+                    *latest_value
+                );
+            }
+        }
+    }
+}
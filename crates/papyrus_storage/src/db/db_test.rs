@@ -4,7 +4,18 @@ use pretty_assertions::assert_eq;
 use tempfile::TempDir;
 
 use crate::db::serialization::{NoVersionValueWrapper, ValueSerde, VersionZeroWrapper};
-use crate::db::{get_page_size, open_env, DbError, DbIter, DbReader, DbResult, DbWriter};
+use crate::db::{
+    get_page_size,
+    open_env,
+    open_env_read_only,
+    DbConfig,
+    DbError,
+    DbIter,
+    DbReader,
+    DbResult,
+    DbSyncMode,
+    DbWriter,
+};
 use crate::test_utils::get_test_config;
 
 fn get_test_env() -> ((DbReader, DbWriter), TempDir) {
@@ -61,6 +72,37 @@ fn open_env_with_enforce_file_exists() {
     assert_matches!(result, Err(DbError::FileDoesNotExist(_)));
 }
 
+#[test]
+fn open_env_read_only_scenario() {
+    let (config, _temp_dir) = get_test_config(None);
+
+    // A read-only environment can't be created.
+    let result = open_env_read_only(&config.db_config);
+    assert_matches!(result, Err(DbError::FileDoesNotExist(_)));
+
+    // Write a value with a writable environment. Called inside a block to drop the db handlers
+    // before opening the read-only environment.
+    {
+        let (reader, mut writer) = open_env(&config.db_config).unwrap();
+        let table_id =
+            writer.create_table::<[u8; 3], NoVersionValueWrapper<[u8; 5]>>("table").unwrap();
+        let txn = reader.begin_ro_txn().unwrap();
+        let table = txn.open_table(&table_id).unwrap();
+        let wtxn = writer.begin_rw_txn().unwrap();
+        table.insert(&wtxn, b"key", b"data0").unwrap();
+        wtxn.commit().unwrap();
+    }
+
+    let reader = open_env_read_only(&config.db_config).unwrap();
+    let table_id = reader.get_table::<[u8; 3], NoVersionValueWrapper<[u8; 5]>>("table").unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    let table = txn.open_table(&table_id).unwrap();
+    assert_eq!(table.get(&txn, b"key").unwrap(), Some(*b"data0"));
+
+    // Tables can't be created in a read-only environment.
+    assert!(reader.get_table::<[u8; 3], NoVersionValueWrapper<[u8; 5]>>("other_table").is_err());
+}
+
 #[test]
 fn txns_scenarios() {
     // Create an environment and a table.
@@ -102,6 +144,26 @@ fn txns_scenarios() {
     assert_eq!(table.get(&txn3, b"key").unwrap(), None);
 }
 
+#[test]
+fn safe_no_sync_commits_are_kept() {
+    let (config, _temp_dir) = get_test_config(None);
+    let db_config = DbConfig { sync_mode: DbSyncMode::SafeNoSync, ..config.db_config };
+    // Called inside a block to drop the db handlers before the environment is opened again.
+    let table_id = {
+        let (_reader, mut writer) = open_env(&db_config).unwrap();
+        let table_id =
+            writer.create_table::<[u8; 3], NoVersionValueWrapper<[u8; 5]>>("table").unwrap();
+        let wtxn = writer.begin_rw_txn().unwrap();
+        wtxn.open_table(&table_id).unwrap().insert(&wtxn, b"key", b"data0").unwrap();
+        wtxn.commit().unwrap();
+        table_id
+    };
+
+    let (reader, _writer) = open_env(&db_config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.open_table(&table_id).unwrap().get(&txn, b"key").unwrap(), Some(*b"data0"));
+}
+
 #[test]
 fn insert_duplicate_key() {
     // Create an environment and a table.
@@ -28,7 +28,17 @@ use std::path::PathBuf;
 use std::result;
 use std::sync::Arc;
 
-use libmdbx::{Cursor, Geometry, PageSize, TableFlags, WriteFlags, WriteMap};
+use libmdbx::{
+    Cursor,
+    DatabaseFlags,
+    Geometry,
+    Mode,
+    PageSize,
+    SyncMode,
+    TableFlags,
+    WriteFlags,
+    WriteMap,
+};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::validators::{validate_ascii, validate_path_exists};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
@@ -39,7 +49,7 @@ use validator::Validate;
 use self::serialization::{Key, ValueSerde};
 
 // Maximum number of Sub-Databases.
-const MAX_DBS: usize = 19;
+const MAX_DBS: usize = 30;
 
 // Note that NO_TLS mode is used by default.
 type EnvironmentKind = WriteMap;
@@ -67,6 +77,31 @@ pub struct DbConfig {
     pub max_size: usize,
     /// The growth step of the database.
     pub growth_step: isize,
+    /// The maximum number of concurrent read transactions, of all the processes that open the
+    /// database.
+    pub max_readers: u32,
+    /// Whether a commit waits until the data is written to the disk.
+    pub sync_mode: DbSyncMode,
+}
+
+/// The durability of the committed data of the database.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum DbSyncMode {
+    /// Every commit is written to the disk before it returns.
+    #[default]
+    Durable,
+    /// Commits are written to the disk by the operating system. The database stays consistent
+    /// after a crash of the system, but the latest commits may be lost.
+    SafeNoSync,
+}
+
+impl From<DbSyncMode> for SyncMode {
+    fn from(sync_mode: DbSyncMode) -> Self {
+        match sync_mode {
+            DbSyncMode::Durable => SyncMode::Durable,
+            DbSyncMode::SafeNoSync => SyncMode::SafeNoSync,
+        }
+    }
 }
 
 impl Default for DbConfig {
@@ -78,6 +113,8 @@ impl Default for DbConfig {
             min_size: 1 << 20,    // 1MB
             max_size: 1 << 40,    // 1TB
             growth_step: 1 << 32, // 4GB
+            max_readers: 1 << 13, // 8K readers
+            sync_mode: DbSyncMode::Durable,
         }
     }
 }
@@ -124,6 +161,21 @@ impl SerializeConfig for DbConfig {
                  grow.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "max_readers",
+                &self.max_readers,
+                "The maximum number of concurrent read transactions of all the processes that \
+                 open the node's storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "sync_mode",
+                &self.sync_mode,
+                "Durable to write every commit to the disk before it returns, or SafeNoSync to \
+                 let the operating system write commits, which is faster but may lose the latest \
+                 commits if the system crashes.",
+                ParamPrivacyInput::Public,
+            ),
         ])
     }
 }
@@ -158,7 +210,7 @@ pub enum DbError {
     FileDoesNotExist(PathBuf),
 }
 
-type DbResult<V> = result::Result<V, DbError>;
+pub(crate) type DbResult<V> = result::Result<V, DbError>;
 
 /// A helper struct for DbError::KeyAlreadyExists.
 #[derive(Debug)]
@@ -187,9 +239,12 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
     if config.enforce_file_exists && !db_file_path.exists() {
         return Err(DbError::FileDoesNotExist(db_file_path));
     }
-    const MAX_READERS: u32 = 1 << 13; // 8K readers
     let env = Arc::new(
         Environment::new()
+            .set_flags(DatabaseFlags {
+                mode: Mode::ReadWrite { sync_mode: config.sync_mode.into() },
+                ..Default::default()
+            })
             .set_geometry(Geometry {
                 size: Some(config.min_size..config.max_size),
                 growth_step: Some(config.growth_step),
@@ -197,12 +252,31 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
                 ..Default::default()
             })
             .set_max_tables(MAX_DBS)
-            .set_max_readers(MAX_READERS)
+            .set_max_readers(config.max_readers)
             .open(&config.path())?,
     );
     Ok((DbReader { env: env.clone() }, DbWriter { env }))
 }
 
+/// Opens an existing MDBX environment for reading only and returns a reader to it.
+/// The environment may be written concurrently by another process. Every read transaction sees the
+/// data that was committed before it began, and the geometry of the database is taken from the
+/// file, so the database can grow while it is open.
+pub(crate) fn open_env_read_only(config: &DbConfig) -> DbResult<DbReader> {
+    let db_file_path = config.path().join("mdbx.dat");
+    if !db_file_path.exists() {
+        return Err(DbError::FileDoesNotExist(db_file_path));
+    }
+    let env = Arc::new(
+        Environment::new()
+            .set_flags(DatabaseFlags { mode: Mode::ReadOnly, ..Default::default() })
+            .set_max_tables(MAX_DBS)
+            .set_max_readers(config.max_readers)
+            .open(&config.path())?,
+    );
+    Ok(DbReader { env })
+}
+
 // Size in bytes.
 const MDBX_MIN_PAGESIZE: usize = 256;
 const MDBX_MAX_PAGESIZE: usize = 65536; // 64KB
@@ -232,6 +306,16 @@ impl DbReader {
     pub(crate) fn begin_ro_txn(&self) -> DbResult<DbReadTransaction<'_>> {
         Ok(DbReadTransaction { txn: self.env.begin_ro_txn()? })
     }
+
+    // Returns the identifier of an existing table, for a reader that can't create tables.
+    pub(crate) fn get_table<K: Key + Debug, V: ValueSerde + Debug>(
+        &self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<K, V>> {
+        let txn = self.env.begin_ro_txn()?;
+        txn.open_table(Some(name))?;
+        Ok(TableIdentifier { name, _key_type: PhantomData {}, _value_type: PhantomData {} })
+    }
 }
 
 type DbReadTransaction<'env> = DbTransaction<'env, RO>;
@@ -20,6 +20,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     ..Default::default()
 //! # };
 //! let block = Block::default();
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
@@ -46,6 +47,7 @@ use tracing::debug;
 
 use crate::db::serialization::NoVersionValueWrapper;
 use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+use crate::recent_blocks_cache::CacheUpdate;
 use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageTxn};
 
 type BlockHashToNumberTable<'env> =
@@ -107,6 +109,12 @@ impl<'env, Mode: TransactionKind> HeaderStorageReader for StorageTxn<'env, Mode>
     }
 
     fn get_block_header(&self, block_number: BlockNumber) -> StorageResult<Option<BlockHeader>> {
+        if let Some(block_header) = self
+            .recent_blocks_cache
+            .get_header(self.recent_blocks_cache_view.as_ref(), block_number)
+        {
+            return Ok(Some(block_header));
+        }
         let headers_table = self.open_table(&self.tables.headers)?;
         let block_header = headers_table.get(&self.txn, &block_number)?;
         Ok(block_header)
@@ -146,10 +154,13 @@ impl<'env, Mode: TransactionKind> HeaderStorageReader for StorageTxn<'env, Mode>
 
 impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
     fn append_header(
-        self,
+        mut self,
         block_number: BlockNumber,
         block_header: &BlockHeader,
     ) -> StorageResult<Self> {
+        self.update_recent_blocks_cache(|| {
+            CacheUpdate::AppendHeader(block_number, block_header.clone())
+        });
         let markers_table = self.open_table(&self.tables.markers)?;
         let headers_table = self.open_table(&self.tables.headers)?;
         let block_hash_to_number_table = self.open_table(&self.tables.block_hash_to_number)?;
@@ -184,14 +195,9 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
     }
 
     fn revert_header(
-        self,
+        mut self,
         block_number: BlockNumber,
     ) -> StorageResult<(Self, Option<BlockHeader>)> {
-        let markers_table = self.open_table(&self.tables.markers)?;
-        let headers_table = self.open_table(&self.tables.headers)?;
-        let block_hash_to_number_table = self.open_table(&self.tables.block_hash_to_number)?;
-        let starknet_version_table = self.open_table(&self.tables.starknet_version)?;
-
         // Assert that header marker equals the reverted block number + 1
         let current_header_marker = self.get_header_marker()?;
 
@@ -204,6 +210,12 @@ impl<'env> HeaderStorageWriter for StorageTxn<'env, RW> {
             );
             return Ok((self, None));
         }
+        self.update_recent_blocks_cache(|| CacheUpdate::RevertHeader(block_number));
+
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let headers_table = self.open_table(&self.tables.headers)?;
+        let block_hash_to_number_table = self.open_table(&self.tables.block_hash_to_number)?;
+        let starknet_version_table = self.open_table(&self.tables.starknet_version)?;
 
         let reverted_header = headers_table
             .get(&self.txn, &block_number)?
@@ -246,3 +246,50 @@ async fn starknet_version() {
         reader.begin_ro_txn().unwrap().get_starknet_version(BlockNumber(3)).unwrap();
     assert_eq!(block_3_starknet_version.unwrap(), second_version);
 }
+
+#[tokio::test]
+async fn read_txn_sees_headers_committed_before_it_began() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let first_header =
+        BlockHeader { block_hash: BlockHash(stark_felt!("0x1")), ..Default::default() };
+    let second_header = BlockHeader {
+        block_hash: BlockHash(stark_felt!("0x2")),
+        block_number: BlockNumber(1),
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &first_header)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn_before_append = reader.begin_ro_txn().unwrap();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(1), &second_header)
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(txn_before_append.get_block_header(BlockNumber(1)).unwrap(), None);
+
+    let txn_before_revert = reader.begin_ro_txn().unwrap();
+    let replacing_header = BlockHeader { block_number: BlockNumber(1), ..Default::default() };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .revert_header(BlockNumber(1))
+        .unwrap()
+        .0
+        .append_header(BlockNumber(1), &replacing_header)
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(txn_before_revert.get_block_header(BlockNumber(1)).unwrap(), Some(second_header));
+    assert_eq!(
+        reader.begin_ro_txn().unwrap().get_block_header(BlockNumber(1)).unwrap(),
+        Some(replacing_header)
+    );
+}
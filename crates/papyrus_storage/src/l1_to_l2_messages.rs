@@ -0,0 +1,110 @@
+//! Interface for tracking the delivery of messages from the base layer (L1) to Starknet (L2).
+//!
+//! Every L1 handler transaction consumes a message sent on the base layer. When a block body is
+//! appended, its L1 handler transactions are indexed by the hashes of their messages. The messages
+//! themselves are read from the logs of the base layer and stored by the hash of the base layer
+//! transaction that sent them, so the L2 transactions of an L1 transaction can be found.
+//!
+//! Import [`L1ToL2MessagesStorageReader`] and [`L1ToL2MessagesStorageWriter`] to read and write
+//! data related to the messages using a [`StorageTxn`].
+#[cfg(test)]
+#[path = "l1_to_l2_messages_test.rs"]
+mod l1_to_l2_messages_test;
+
+use papyrus_common::l1_to_l2_messages::{L1ToL2Message, L1ToL2MessageHash, L1TransactionHash};
+use starknet_api::block::BlockNumber;
+
+use crate::body::TransactionIndex;
+use crate::db::{TransactionKind, RW};
+use crate::{MarkerKind, StorageResult, StorageTxn};
+
+/// Interface for reading data related to the L1 to L2 messages.
+pub trait L1ToL2MessagesStorageReader {
+    /// Returns the index of the L1 handler transaction that consumed the message with the given
+    /// hash.
+    fn get_l1_handler_transaction_idx_by_message_hash(
+        &self,
+        message_hash: &L1ToL2MessageHash,
+    ) -> StorageResult<Option<TransactionIndex>>;
+
+    /// Returns the hashes of the messages sent by the base layer transaction with the given hash,
+    /// in the order they were sent.
+    fn get_l1_transaction_message_hashes(
+        &self,
+        l1_transaction_hash: &L1TransactionHash,
+    ) -> StorageResult<Option<Vec<L1ToL2MessageHash>>>;
+
+    /// The messages marker is the first base layer block whose messages weren't stored yet.
+    // The marker is a block number of the base layer and not of Starknet.
+    fn get_l1_to_l2_messages_marker(&self) -> StorageResult<BlockNumber>;
+}
+
+/// Interface for writing data related to the L1 to L2 messages.
+pub trait L1ToL2MessagesStorageWriter
+where
+    Self: Sized,
+{
+    /// Stores the messages sent in the base layer blocks up to the given marker (exclusive), and
+    /// updates the messages marker. Messages that are already stored are ignored.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn append_l1_to_l2_messages(
+        self,
+        messages: &[L1ToL2Message],
+        marker: BlockNumber,
+    ) -> StorageResult<Self>;
+}
+
+impl<'env, Mode: TransactionKind> L1ToL2MessagesStorageReader for StorageTxn<'env, Mode> {
+    fn get_l1_handler_transaction_idx_by_message_hash(
+        &self,
+        message_hash: &L1ToL2MessageHash,
+    ) -> StorageResult<Option<TransactionIndex>> {
+        let message_hash_to_idx_table =
+            self.open_table(&self.tables.l1_handler_message_hash_to_idx)?;
+        Ok(message_hash_to_idx_table.get(&self.txn, message_hash)?)
+    }
+
+    fn get_l1_transaction_message_hashes(
+        &self,
+        l1_transaction_hash: &L1TransactionHash,
+    ) -> StorageResult<Option<Vec<L1ToL2MessageHash>>> {
+        let l1_transaction_messages_table =
+            self.open_table(&self.tables.l1_transaction_message_hashes)?;
+        Ok(l1_transaction_messages_table.get(&self.txn, l1_transaction_hash)?)
+    }
+
+    fn get_l1_to_l2_messages_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::L1ToL2Messages)?.unwrap_or_default())
+    }
+}
+
+impl<'env> L1ToL2MessagesStorageWriter for StorageTxn<'env, RW> {
+    fn append_l1_to_l2_messages(
+        self,
+        messages: &[L1ToL2Message],
+        marker: BlockNumber,
+    ) -> StorageResult<Self> {
+        let l1_transaction_messages_table =
+            self.open_table(&self.tables.l1_transaction_message_hashes)?;
+        for message in messages {
+            let mut message_hashes = l1_transaction_messages_table
+                .get(&self.txn, &message.l1_transaction_hash)?
+                .unwrap_or_default();
+            let message_hash = message.hash();
+            if message_hashes.contains(&message_hash) {
+                continue;
+            }
+            message_hashes.push(message_hash);
+            l1_transaction_messages_table.upsert(
+                &self.txn,
+                &message.l1_transaction_hash,
+                &message_hashes,
+            )?;
+        }
+
+        let markers_table = self.open_table(&self.tables.markers)?;
+        markers_table.upsert(&self.txn, &MarkerKind::L1ToL2Messages, &marker)?;
+        Ok(self)
+    }
+}
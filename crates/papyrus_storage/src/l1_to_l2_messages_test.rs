@@ -0,0 +1,82 @@
+use papyrus_common::l1_to_l2_messages::{
+    l1_handler_message_hash,
+    L1ToL2Message,
+    L1TransactionHash,
+};
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{Transaction, TransactionOffsetInBlock};
+use test_utils::{get_test_body_with_transaction_types, TestTransactionType};
+
+use crate::body::{BodyStorageWriter, TransactionIndex};
+use crate::l1_to_l2_messages::{L1ToL2MessagesStorageReader, L1ToL2MessagesStorageWriter};
+use crate::test_utils::get_test_storage;
+
+#[test]
+fn l1_handler_transactions_are_indexed_by_message_hash() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let mut body = get_test_body_with_transaction_types(
+        &[TestTransactionType::Invoke, TestTransactionType::L1Handler],
+        0,
+    );
+    let Transaction::L1Handler(l1_handler_transaction) = &mut body.transactions[1] else {
+        panic!("Expected an L1 handler transaction.");
+    };
+    // The first element of the calldata is the sender of the message.
+    l1_handler_transaction.calldata.0 = vec![StarkFelt::from(1_u8), StarkFelt::from(2_u8)].into();
+    let message_hash = l1_handler_message_hash(l1_handler_transaction).unwrap();
+    writer.begin_rw_txn().unwrap().append_body(BlockNumber(0), body).unwrap().commit().unwrap();
+
+    let expected_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(1));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.get_l1_handler_transaction_idx_by_message_hash(&message_hash).unwrap(),
+        Some(expected_index)
+    );
+    drop(txn);
+
+    writer.begin_rw_txn().unwrap().revert_body(BlockNumber(0)).unwrap().0.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_l1_handler_transaction_idx_by_message_hash(&message_hash).unwrap(), None);
+}
+
+#[test]
+fn append_l1_to_l2_messages() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let l1_transaction_hash = L1TransactionHash([1; 32]);
+    let first_message = L1ToL2Message { l1_transaction_hash, ..Default::default() };
+    let second_message = L1ToL2Message {
+        l1_transaction_hash,
+        payload: vec![StarkFelt::from(1_u8)],
+        ..Default::default()
+    };
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_l1_to_l2_messages_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_l1_transaction_message_hashes(&l1_transaction_hash).unwrap(), None);
+    drop(txn);
+
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_l1_to_l2_messages(&[first_message.clone()], BlockNumber(10))
+        .unwrap()
+        .commit()
+        .unwrap();
+    // Storing a message again doesn't duplicate it.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_l1_to_l2_messages(&[first_message.clone(), second_message.clone()], BlockNumber(20))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_l1_to_l2_messages_marker().unwrap(), BlockNumber(20));
+    assert_eq!(
+        txn.get_l1_transaction_message_hashes(&l1_transaction_hash).unwrap(),
+        Some(vec![first_message.hash(), second_message.hash()])
+    );
+}
@@ -34,6 +34,7 @@
 //!     min_size: 1 << 20,    // 1MB
 //!     max_size: 1 << 35,    // 32GB
 //!     growth_step: 1 << 26, // 64MB
+//!     ..Default::default()
 //! };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! let (reader, mut writer) = open_storage(storage_config)?;
@@ -51,7 +52,10 @@
 //! [`libmdbx`]: https://docs.rs/libmdbx/latest/libmdbx/
 
 pub mod base_layer;
+pub mod block_iter;
 pub mod body;
+#[cfg(any(feature = "chain_fixture", test))]
+pub mod chain_fixture;
 pub mod compiled_class;
 pub mod utils;
 // TODO(yair): Make the compression_utils module pub(crate) or extract it from the crate.
@@ -59,9 +63,14 @@ pub mod utils;
 pub mod compression_utils;
 pub mod db;
 pub mod header;
+pub mod l1_to_l2_messages;
 pub mod mmap_file;
+mod recent_blocks_cache;
 mod serializers;
+pub mod snapshot;
 pub mod state;
+pub mod state_trie;
+mod storage_metrics;
 mod version;
 
 #[cfg(test)]
@@ -80,6 +89,7 @@ use db::db_stats::{DbTableStats, DbWholeStats};
 use db::serialization::{Key, NoVersionValueWrapper, ValueSerde};
 use mmap_file::{
     open_file,
+    open_file_read_only,
     FileHandler,
     LocationInFile,
     MMapFileError,
@@ -87,26 +97,35 @@ use mmap_file::{
     Reader,
     Writer,
 };
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_common::l1_to_l2_messages::{L1ToL2MessageHash, L1TransactionHash};
+use papyrus_common::raw_transaction::RawTransaction;
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
 use starknet_api::core::{ClassHash, ContractAddress, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
-use starknet_api::hash::StarkFelt;
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::{ContractClass, StorageKey, ThinStateDiff};
-use starknet_api::transaction::{EventContent, Transaction, TransactionHash};
+use starknet_api::transaction::{EventContent, EventKey, Transaction, TransactionHash};
 use tracing::{debug, warn};
 use validator::Validate;
 use version::{StorageVersionError, Version};
 
-use crate::body::events::ThinTransactionOutput;
+use crate::body::events::{EventsBloomFilter, ThinTransactionOutput};
 use crate::body::TransactionIndex;
 use crate::db::{
     open_env,
+    open_env_read_only,
     DbConfig,
     DbError,
     DbReader,
+    DbResult,
     DbTransaction,
     DbWriter,
     TableHandle,
@@ -116,18 +135,21 @@ use crate::db::{
     RW,
 };
 use crate::header::StarknetVersion;
+use crate::recent_blocks_cache::{CacheUpdate, CacheView, RecentBlocksCache};
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state_trie::{StateTrieRoots, TrieNode};
+use crate::storage_metrics::ReadTransactionGuard;
 use crate::version::{VersionStorageReader, VersionStorageWriter};
 
 /// The current version of the storage state code.
 /// Whenever a breaking change is introduced, the version is incremented and a storage
 /// migration is required for existing storages.
-pub const STORAGE_VERSION_STATE: Version = Version(10);
+pub const STORAGE_VERSION_STATE: Version = Version(11);
 /// The current version of the storage blocks code.
 /// Whenever a breaking change is introduced, the version is incremented and a storage
 /// migration is required for existing storages.
 /// This version is only checked for storages that store transactions (StorageScope::FullArchive).
-pub const STORAGE_VERSION_BLOCKS: Version = Version(10);
+pub const STORAGE_VERSION_BLOCKS: Version = Version(11);
 
 /// Opens a storage and returns a [`StorageReader`] and a [`StorageWriter`].
 pub fn open_storage(
@@ -143,11 +165,22 @@ pub fn open_storage(
         deprecated_declared_classes: db_writer.create_table("deprecated_declared_classes")?,
         deployed_contracts: db_writer.create_table("deployed_contracts")?,
         events: db_writer.create_table("events")?,
+        events_by_first_key: db_writer.create_table("events_by_first_key")?,
+        events_bloom_filters: db_writer.create_table("events_bloom_filters")?,
         headers: db_writer.create_table("headers")?,
+        l1_handler_message_hash_to_idx: db_writer.create_table("l1_handler_message_hash_to_idx")?,
+        l1_transaction_message_hashes: db_writer.create_table("l1_transaction_message_hashes")?,
+        latest_class_hashes: db_writer.create_table("latest_class_hashes")?,
+        latest_contract_storage: db_writer.create_table("latest_contract_storage")?,
+        latest_nonces: db_writer.create_table("latest_nonces")?,
         markers: db_writer.create_table("markers")?,
         nonces: db_writer.create_table("nonces")?,
+        raw_transactions: db_writer.create_table("raw_transactions")?,
         file_offsets: db_writer.create_table("file_offsets")?,
         state_diffs: db_writer.create_table("state_diffs")?,
+        state_trie_nodes: db_writer.create_table("state_trie_nodes")?,
+        state_trie_roots: db_writer.create_table("state_trie_roots")?,
+        contract_storage_trie_roots: db_writer.create_table("contract_storage_trie_roots")?,
         transaction_hash_to_idx: db_writer.create_table("transaction_hash_to_idx")?,
         transaction_idx_to_hash: db_writer.create_table("transaction_idx_to_hash")?,
         transaction_outputs: db_writer.create_table("transaction_outputs")?,
@@ -164,19 +197,52 @@ pub fn open_storage(
         &tables.file_offsets,
     )?;
 
+    let recent_blocks_cache =
+        Arc::new(RecentBlocksCache::new(storage_config.recent_blocks_cache_size));
+
     let reader = StorageReader {
         db_reader,
         tables: tables.clone(),
         scope: storage_config.scope,
         file_readers,
+        recent_blocks_cache: recent_blocks_cache.clone(),
+    };
+    let writer = StorageWriter {
+        db_writer,
+        tables,
+        scope: storage_config.scope,
+        body_retention_blocks: storage_config.body_retention_blocks,
+        file_writers,
+        recent_blocks_cache,
     };
-    let writer = StorageWriter { db_writer, tables, scope: storage_config.scope, file_writers };
 
     let writer = set_version_if_needed(reader.clone(), writer)?;
     verify_storage_version(reader.clone())?;
     Ok((reader, writer))
 }
 
+/// Opens a storage that another process writes, and returns a [`StorageReader`] to it.
+/// The storage must have been opened with [`open_storage`] before. Every transaction of the reader
+/// sees the data the writing process committed before the transaction began, so a transaction
+/// should be short-lived to observe new blocks (and to let the writing process reuse the pages of
+/// the data it replaces). The latest blocks are not cached, since only the writing process knows
+/// when they change.
+pub fn open_storage_read_only(storage_config: StorageConfig) -> StorageResult<StorageReader> {
+    let db_reader = open_env_read_only(&storage_config.db_config)?;
+    let tables = Arc::new(Tables::open_existing(&db_reader)?);
+    let file_readers =
+        open_storage_files_read_only(&storage_config.db_config, storage_config.mmap_file_config)?;
+    let reader = StorageReader {
+        db_reader,
+        tables,
+        scope: storage_config.scope,
+        file_readers,
+        recent_blocks_cache: Arc::new(RecentBlocksCache::new(0)),
+    };
+    verify_storage_version(reader.clone())?;
+    Ok(reader)
+}
+
 // In case storage version does not exist, set it to the crate version.
 // Expected to happen once - when the node is launched for the first time.
 // If the storage scope has changed, update accordingly.
@@ -321,26 +387,51 @@ pub enum StorageScope {
 }
 
 /// A struct for starting RO transactions ([`StorageTxn`]) to the storage.
+/// Any number of RO transactions, up to the reader slots of the database, can be open concurrently
+/// from any threads, and they don't block the writer. They should still be short-lived, since the
+/// database can't reuse the pages that an open transaction may read.
 #[derive(Clone)]
 pub struct StorageReader {
     db_reader: DbReader,
     file_readers: FileHandlers<RO>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    recent_blocks_cache: Arc<RecentBlocksCache>,
 }
 
 impl StorageReader {
     /// Takes a snapshot of the current state of the storage and returns a [`StorageTxn`] for
     /// reading data from the storage.
     pub fn begin_ro_txn(&self) -> StorageResult<StorageTxn<'_, RO>> {
+        // The view of the cache must be taken before the snapshot of the database.
+        let recent_blocks_cache_view = self.recent_blocks_cache.view();
         Ok(StorageTxn {
             txn: self.db_reader.begin_ro_txn()?,
             file_handlers: self.file_readers.clone(),
             tables: self.tables.clone(),
             scope: self.scope,
+            body_retention_blocks: None,
+            recent_blocks_cache: self.recent_blocks_cache.clone(),
+            recent_blocks_cache_view,
+            recent_blocks_cache_updates: Vec::new(),
+            _read_transaction_guard: Some(ReadTransactionGuard::new()),
         })
     }
 
+    /// Like [`StorageReader::begin_ro_txn`], but the returned transaction keeps its own reader, so
+    /// it can outlive this reader, for example to read a single snapshot of the storage while
+    /// serving a request.
+    pub fn begin_owned_ro_txn(&self) -> StorageResult<OwnedStorageTxn> {
+        let reader = self.clone();
+        let txn = reader.begin_ro_txn()?;
+        // SAFETY: The transaction borrows the database environment, which is behind an Arc that
+        // the owned reader holds until the transaction is dropped, so it doesn't move or get
+        // dropped while the transaction is open.
+        let txn =
+            unsafe { std::mem::transmute::<StorageTxn<'_, RO>, StorageTxn<'static, RO>>(txn) };
+        Ok(OwnedStorageTxn { txn, _reader: reader })
+    }
+
     /// Returns metadata about the tables in the storage.
     pub fn db_tables_stats(&self) -> StorageResult<DbStats> {
         let mut tables_stats = BTreeMap::new();
@@ -356,6 +447,20 @@ impl StorageReader {
     }
 }
 
+/// A RO transaction ([`StorageTxn`]) together with the reader it was started from.
+pub struct OwnedStorageTxn {
+    // Declared before the reader, so that it's dropped before the reader.
+    txn: StorageTxn<'static, RO>,
+    _reader: StorageReader,
+}
+
+impl OwnedStorageTxn {
+    /// Returns the transaction, for reading data from the storage.
+    pub fn txn(&self) -> &StorageTxn<'_, RO> {
+        &self.txn
+    }
+}
+
 /// A struct for starting RW transactions ([`StorageTxn`]) to the storage.
 /// There is a single non clonable writer instance, to make sure there is only one write transaction
 /// at any given moment.
@@ -364,6 +469,8 @@ pub struct StorageWriter {
     file_writers: FileHandlers<RW>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    body_retention_blocks: Option<u64>,
+    recent_blocks_cache: Arc<RecentBlocksCache>,
 }
 
 impl StorageWriter {
@@ -375,6 +482,12 @@ impl StorageWriter {
             file_handlers: self.file_writers.clone(),
             tables: self.tables.clone(),
             scope: self.scope,
+            body_retention_blocks: self.body_retention_blocks,
+            recent_blocks_cache: self.recent_blocks_cache.clone(),
+            // A RW transaction reads its own changes, so it doesn't read from the cache.
+            recent_blocks_cache_view: None,
+            recent_blocks_cache_updates: Vec::new(),
+            _read_transaction_guard: None,
         })
     }
 }
@@ -386,13 +499,33 @@ pub struct StorageTxn<'env, Mode: TransactionKind> {
     file_handlers: FileHandlers<Mode>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    // The number of latest blocks whose bodies are kept when appending bodies. Set only for RW
+    // transactions.
+    body_retention_blocks: Option<u64>,
+    recent_blocks_cache: Arc<RecentBlocksCache>,
+    recent_blocks_cache_view: Option<CacheView>,
+    recent_blocks_cache_updates: Vec<CacheUpdate>,
+    // Records the metrics of RO transactions while they are open.
+    _read_transaction_guard: Option<ReadTransactionGuard>,
 }
 
 impl<'env> StorageTxn<'env, RW> {
     /// Commits the changes made in the transaction to the storage.
     pub fn commit(self) -> StorageResult<()> {
+        // Reverted blocks are removed from the cache before they are removed from the database,
+        // and appended blocks are added to the cache only after they are in the database.
+        self.recent_blocks_cache.invalidate_reverted(&self.recent_blocks_cache_updates);
         self.file_handlers.flush();
-        Ok(self.txn.commit()?)
+        self.txn.commit()?;
+        self.recent_blocks_cache.apply(&self.recent_blocks_cache_updates);
+        Ok(())
+    }
+
+    // Records a change to the latest blocks, to update the cache with when committing.
+    pub(crate) fn update_recent_blocks_cache(&mut self, update: impl FnOnce() -> CacheUpdate) {
+        if self.recent_blocks_cache.is_enabled() {
+            self.recent_blocks_cache_updates.push(update());
+        }
     }
 }
 
@@ -404,6 +537,10 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         if self.scope == StorageScope::StateOnly {
             let unused_tables = [
                 self.tables.events.name,
+                self.tables.events_by_first_key.name,
+                self.tables.events_bloom_filters.name,
+                self.tables.l1_handler_message_hash_to_idx.name,
+                self.tables.raw_transactions.name,
                 self.tables.transaction_hash_to_idx.name,
                 self.tables.transaction_idx_to_hash.name,
                 self.tables.transaction_outputs.name,
@@ -435,11 +572,22 @@ struct_field_names! {
         deprecated_declared_classes: TableIdentifier<ClassHash, NoVersionValueWrapper<IndexedDeprecatedContractClass>>,
         deployed_contracts: TableIdentifier<(ContractAddress, BlockNumber), NoVersionValueWrapper<ClassHash>>,
         events: TableIdentifier<(ContractAddress, EventIndex), NoVersionValueWrapper<EventContent>>,
+        events_by_first_key: TableIdentifier<(EventKey, EventIndex), NoVersionValueWrapper<ContractAddress>>,
+        events_bloom_filters: TableIdentifier<BlockNumber, NoVersionValueWrapper<EventsBloomFilter>>,
         headers: TableIdentifier<BlockNumber, NoVersionValueWrapper<BlockHeader>>,
+        l1_handler_message_hash_to_idx: TableIdentifier<L1ToL2MessageHash, NoVersionValueWrapper<TransactionIndex>>,
+        l1_transaction_message_hashes: TableIdentifier<L1TransactionHash, NoVersionValueWrapper<Vec<L1ToL2MessageHash>>>,
+        latest_class_hashes: TableIdentifier<ContractAddress, NoVersionValueWrapper<ClassHash>>,
+        latest_contract_storage: TableIdentifier<(ContractAddress, StorageKey), NoVersionValueWrapper<StarkFelt>>,
+        latest_nonces: TableIdentifier<ContractAddress, NoVersionValueWrapper<Nonce>>,
         markers: TableIdentifier<MarkerKind, NoVersionValueWrapper<BlockNumber>>,
         nonces: TableIdentifier<(ContractAddress, BlockNumber), NoVersionValueWrapper<Nonce>>,
+        raw_transactions: TableIdentifier<TransactionIndex, NoVersionValueWrapper<RawTransaction>>,
         file_offsets: TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>>,
         state_diffs: TableIdentifier<BlockNumber, NoVersionValueWrapper<LocationInFile>>,
+        state_trie_nodes: TableIdentifier<StarkHash, NoVersionValueWrapper<TrieNode>>,
+        state_trie_roots: TableIdentifier<BlockNumber, NoVersionValueWrapper<StateTrieRoots>>,
+        contract_storage_trie_roots: TableIdentifier<(ContractAddress, BlockNumber), NoVersionValueWrapper<StarkHash>>,
         transaction_hash_to_idx: TableIdentifier<TransactionHash, NoVersionValueWrapper<TransactionIndex>>,
         transaction_idx_to_hash: TableIdentifier<TransactionIndex, NoVersionValueWrapper<TransactionHash>>,
         transaction_outputs: TableIdentifier<TransactionIndex, NoVersionValueWrapper<ThinTransactionOutput>>,
@@ -462,6 +610,12 @@ macro_rules! struct_field_names {
                 static NAMES: &'static [&'static str] = &[$(stringify!($fname)),*];
                 NAMES
             }
+
+            // Returns the identifiers of the tables, which must already exist in the database.
+            // Each table is named after its field.
+            fn open_existing(db_reader: &DbReader) -> DbResult<Self> {
+                Ok(Self { $($fname : db_reader.get_table(stringify!($fname))?),* })
+            }
         }
     }
 }
@@ -511,23 +665,64 @@ pub type StorageResult<V> = std::result::Result<V, StorageError>;
 
 /// A struct for the configuration of the storage.
 #[allow(missing_docs)]
-#[derive(Serialize, Debug, Default, Deserialize, Clone, PartialEq, Validate)]
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Validate)]
 pub struct StorageConfig {
     #[validate]
     pub db_config: DbConfig,
     #[validate]
     pub mmap_file_config: MmapFileConfig,
     pub scope: StorageScope,
+    pub recent_blocks_cache_size: usize,
+    pub read_only: bool,
+    pub body_retention_blocks: Option<u64>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            db_config: DbConfig::default(),
+            mmap_file_config: MmapFileConfig::default(),
+            scope: StorageScope::default(),
+            recent_blocks_cache_size: 100,
+            read_only: false,
+            body_retention_blocks: None,
+        }
+    }
 }
 
 impl SerializeConfig for StorageConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let mut dumped_config = BTreeMap::from_iter([ser_param(
-            "scope",
-            &self.scope,
-            "The categories of data saved in storage.",
+        let mut dumped_config = BTreeMap::from_iter([
+            ser_param(
+                "scope",
+                &self.scope,
+                "The categories of data saved in storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "recent_blocks_cache_size",
+                &self.recent_blocks_cache_size,
+                "The number of latest blocks whose headers and transaction locations are cached \
+                 in memory. If 0, nothing is cached.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "read_only",
+                &self.read_only,
+                "Whether to open the storage for reading only, to serve the storage of another \
+                 node process that syncs it. A node with a read-only storage doesn't sync.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        dumped_config.extend(ser_optional_param(
+            &self.body_retention_blocks,
+            1000,
+            "body_retention_blocks",
+            "The number of latest blocks whose bodies (transactions, receipts and events) are \
+             kept. The bodies of older blocks are pruned, while their headers and state diffs are \
+             kept. If not set, no body is pruned.",
             ParamPrivacyInput::Public,
-        )]);
+        ));
         dumped_config
             .extend(append_sub_config_name(self.mmap_file_config.dump(), "mmap_file_config"));
         dumped_config.extend(append_sub_config_name(self.db_config.dump(), "db_config"));
@@ -550,12 +745,18 @@ pub struct DbStats {
 // - CompiledClass <= State <= Header
 // - Body <= Header
 // - BaseLayerBlock <= Header
+// - StateTrie <= State
+// - PrunedBody <= Body
 pub(crate) enum MarkerKind {
     Header,
     Body,
     State,
     CompiledClass,
     BaseLayerBlock,
+    L1ToL2Messages,
+    StateTrie,
+    // The first block whose body wasn't pruned.
+    PrunedBody,
 }
 
 pub(crate) type MarkersTable<'env> =
@@ -695,6 +896,27 @@ fn open_storage_files(
     ))
 }
 
+fn open_storage_files_read_only(
+    db_config: &DbConfig,
+    mmap_file_config: MmapFileConfig,
+) -> StorageResult<FileHandlers<RO>> {
+    Ok(FileHandlers {
+        thin_state_diff: open_file_read_only(
+            mmap_file_config.clone(),
+            db_config.path().join("thin_state_diff.dat"),
+        )?,
+        contract_class: open_file_read_only(
+            mmap_file_config.clone(),
+            db_config.path().join("contract_class.dat"),
+        )?,
+        casm: open_file_read_only(mmap_file_config.clone(), db_config.path().join("casm.dat"))?,
+        deprecated_contract_class: open_file_read_only(
+            mmap_file_config,
+            db_config.path().join("deprecated_contract_class.dat"),
+        )?,
+    })
+}
+
 /// Represents a kind of mmap file.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord)]
 pub enum OffsetKind {
@@ -45,6 +45,30 @@ fn write_read() {
     dir.close().unwrap();
 }
 
+#[test]
+fn read_only_reader_sees_appended_objects() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().to_path_buf().join("test_read_only");
+    let (mut writer, _reader) =
+        open_file::<NoVersionValueWrapper<Vec<u8>>>(get_mmap_file_test_config(), path.clone(), 0)
+            .unwrap();
+    let read_only_reader =
+        open_file_read_only::<NoVersionValueWrapper<Vec<u8>>>(get_mmap_file_test_config(), path)
+            .unwrap();
+
+    // Append objects after the reader was opened, until the file grows.
+    let data = vec![7; 1 << 12];
+    let mut locations = Vec::new();
+    for _ in 0..(get_mmap_file_test_config().growth_step / data.len()) {
+        locations.push(writer.append(&data));
+    }
+    for location in locations {
+        assert_eq!(read_only_reader.get(location).unwrap().unwrap(), data);
+    }
+
+    dir.close().unwrap();
+}
+
 #[test]
 fn concurrent_reads() {
     let dir = tempdir().unwrap();
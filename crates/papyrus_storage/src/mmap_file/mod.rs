@@ -15,7 +15,7 @@ use std::path::PathBuf;
 use std::result;
 use std::sync::{Arc, Mutex};
 
-use memmap2::{MmapMut, MmapOptions};
+use memmap2::{Mmap, MmapMut, MmapOptions};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 #[cfg(test)]
@@ -139,7 +139,7 @@ struct MMapFile<V: ValueSerde> {
     config: MmapFileConfig,
     file: File,
     size: usize,
-    mmap: MmapMut,
+    mmap: Mapping,
     offset: usize,
     should_flush: bool,
     _value_type: PhantomData<V>,
@@ -158,11 +158,35 @@ impl<V: ValueSerde> MMapFile<V> {
     /// Flushes the mmap to the file.
     fn flush(&mut self) {
         debug!("Flushing mmap to file");
-        self.mmap.flush().expect("Failed to flush the mmap");
+        self.mmap.writable().flush().expect("Failed to flush the mmap");
         self.should_flush = false;
     }
 }
 
+/// The memory map of a file. Only the process that writes the file maps it as writable.
+#[derive(Debug)]
+enum Mapping {
+    Writable(MmapMut),
+    ReadOnly(Mmap),
+}
+
+impl Mapping {
+    fn as_ptr(&self) -> *const u8 {
+        match self {
+            Self::Writable(mmap) => mmap.as_ptr(),
+            Self::ReadOnly(mmap) => mmap.as_ptr(),
+        }
+    }
+
+    // Only files that are opened for writing have writers, so a read-only map is never written.
+    fn writable(&mut self) -> &mut MmapMut {
+        match self {
+            Self::Writable(mmap) => mmap,
+            Self::ReadOnly(_) => panic!("A file that is opened for reading only can't be written."),
+        }
+    }
+}
+
 /// Open a memory mapped file, create it if it doesn't exist.
 #[instrument(level = "debug", err)]
 pub(crate) fn open_file<V: ValueSerde>(
@@ -172,7 +196,8 @@ pub(crate) fn open_file<V: ValueSerde>(
 ) -> MmapFileResult<(FileHandler<V, RW>, FileHandler<V, RO>)> {
     let file = OpenOptions::new().read(true).write(true).create(true).open(path)?;
     let size = file.metadata()?.len();
-    let mmap = unsafe { MmapOptions::new().len(config.max_size).map_mut(&file)? };
+    let mmap =
+        Mapping::Writable(unsafe { MmapOptions::new().len(config.max_size).map_mut(&file)? });
     let mmap_ptr = mmap.as_ptr();
     let mmap_file = MMapFile {
         config,
@@ -198,6 +223,36 @@ pub(crate) fn open_file<V: ValueSerde>(
     Ok((write_file_handler, read_file_handler))
 }
 
+/// Opens an existing memory mapped file for reading only, in a process other than the one that
+/// writes it.
+/// The mapping is shared, so objects appended by the writing process are visible once it writes
+/// them. The writing process grows the file before it commits the locations of the appended
+/// objects, so the committed locations are always within the file.
+#[instrument(level = "debug", err)]
+pub(crate) fn open_file_read_only<V: ValueSerde>(
+    config: MmapFileConfig,
+    path: PathBuf,
+) -> MmapFileResult<FileHandler<V, RO>> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let size = file.metadata()?.len();
+    let mmap = Mapping::ReadOnly(unsafe { MmapOptions::new().len(config.max_size).map(&file)? });
+    let mmap_ptr = mmap.as_ptr();
+    let mmap_file = MMapFile {
+        config,
+        file,
+        mmap,
+        size: size.try_into().expect("size should fit in usize"),
+        offset: 0,
+        should_flush: false,
+        _value_type: PhantomData {},
+    };
+    Ok(FileHandler {
+        memory_ptr: mmap_ptr,
+        mmap_file: Arc::new(Mutex::new(mmap_file)),
+        _mode: PhantomData,
+    })
+}
+
 /// A wrapper around `MMapFile` that provides both write and read interfaces.
 #[derive(Clone, Debug)]
 pub(crate) struct FileHandler<V: ValueSerde, Mode: TransactionKind> {
@@ -233,10 +288,11 @@ impl<V: ValueSerde + Debug> Writer<V> for FileHandler<V, RW> {
             let mut mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
             offset = mmap_file.offset;
             debug!("Inserting object at offset: {}", offset);
-            let mut mmap_slice = &mut mmap_file.mmap[offset..];
+            let mut mmap_slice = &mut mmap_file.mmap.writable()[offset..];
             let _ = val.serialize_into(&mut mmap_slice);
             mmap_file
                 .mmap
+                .writable()
                 .flush_async_range(offset, len)
                 .expect("Failed to asynchronously flush the mmap after inserting");
             mmap_file.offset += len;
@@ -0,0 +1,196 @@
+//! An in-memory cache of the headers and the transaction locations of the latest blocks, in front
+//! of the database. Most of the reads are of data near the tip of the chain, so they are served
+//! without reading and deserializing the data from the database.
+//!
+//! A read transaction sees only the blocks that were committed before it began. To ensure that, a
+//! transaction takes a [`CacheView`] before it begins and reads from the cache only blocks that
+//! are below the markers of the view. Appended blocks are added to the cache after they are
+//! committed, and reverted blocks are removed from the cache before the revert is committed, which
+//! invalidates the views taken before it.
+#[cfg(test)]
+#[path = "recent_blocks_cache_test.rs"]
+mod recent_blocks_cache_test;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use starknet_api::block::{BlockHeader, BlockNumber};
+use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
+
+use crate::body::TransactionIndex;
+
+pub(crate) struct RecentBlocksCache {
+    // The number of latest blocks to cache. If zero, nothing is cached.
+    n_blocks: usize,
+    blocks: RwLock<CachedBlocks>,
+}
+
+#[derive(Default)]
+struct CachedBlocks {
+    // Incremented whenever blocks are reverted.
+    generation: u64,
+    headers: BTreeMap<BlockNumber, BlockHeader>,
+    transaction_hashes: BTreeMap<BlockNumber, Vec<TransactionHash>>,
+    transaction_locations: HashMap<TransactionHash, TransactionIndex>,
+}
+
+/// The blocks of the cache a transaction can read.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CacheView {
+    generation: u64,
+    header_marker: BlockNumber,
+    body_marker: BlockNumber,
+}
+
+/// A change to the latest blocks, made by a RW transaction.
+#[derive(Debug)]
+pub(crate) enum CacheUpdate {
+    AppendHeader(BlockNumber, BlockHeader),
+    RevertHeader(BlockNumber),
+    AppendTransactions(BlockNumber, Vec<TransactionHash>),
+    RevertTransactions(BlockNumber),
+}
+
+impl RecentBlocksCache {
+    pub(crate) fn new(n_blocks: usize) -> Self {
+        Self { n_blocks, blocks: RwLock::new(CachedBlocks::default()) }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.n_blocks > 0
+    }
+
+    // Should be called before beginning the transaction that reads with the returned view.
+    pub(crate) fn view(&self) -> Option<CacheView> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let blocks = self.blocks.read().expect("Failed to lock the recent blocks cache.");
+        let next_block = |last_block: Option<&BlockNumber>| {
+            last_block.map(|block_number| block_number.next()).unwrap_or_default()
+        };
+        Some(CacheView {
+            generation: blocks.generation,
+            header_marker: next_block(blocks.headers.keys().next_back()),
+            body_marker: next_block(blocks.transaction_hashes.keys().next_back()),
+        })
+    }
+
+    pub(crate) fn get_header(
+        &self,
+        view: Option<&CacheView>,
+        block_number: BlockNumber,
+    ) -> Option<BlockHeader> {
+        let view = view?;
+        if block_number >= view.header_marker {
+            return None;
+        }
+        let blocks = self.blocks.read().expect("Failed to lock the recent blocks cache.");
+        if blocks.generation != view.generation {
+            return None;
+        }
+        blocks.headers.get(&block_number).cloned()
+    }
+
+    pub(crate) fn get_transaction_idx(
+        &self,
+        view: Option<&CacheView>,
+        transaction_hash: &TransactionHash,
+    ) -> Option<TransactionIndex> {
+        let view = view?;
+        let blocks = self.blocks.read().expect("Failed to lock the recent blocks cache.");
+        if blocks.generation != view.generation {
+            return None;
+        }
+        blocks
+            .transaction_locations
+            .get(transaction_hash)
+            .filter(|transaction_index| transaction_index.0 < view.body_marker)
+            .copied()
+    }
+
+    // Should be called before committing the transaction that made the updates.
+    pub(crate) fn invalidate_reverted(&self, updates: &[CacheUpdate]) {
+        if !updates.iter().any(CacheUpdate::is_revert) {
+            return;
+        }
+        let mut blocks = self.blocks.write().expect("Failed to lock the recent blocks cache.");
+        blocks.generation += 1;
+        for update in updates.iter().filter(|update| update.is_revert()) {
+            blocks.apply(update, self.n_blocks);
+        }
+    }
+
+    // Should be called after committing the transaction that made the updates.
+    pub(crate) fn apply(&self, updates: &[CacheUpdate]) {
+        if updates.is_empty() {
+            return;
+        }
+        let mut blocks = self.blocks.write().expect("Failed to lock the recent blocks cache.");
+        for update in updates {
+            blocks.apply(update, self.n_blocks);
+        }
+    }
+}
+
+impl CachedBlocks {
+    fn apply(&mut self, update: &CacheUpdate, n_blocks: usize) {
+        match update {
+            CacheUpdate::AppendHeader(block_number, header) => {
+                self.headers.insert(*block_number, header.clone());
+                while self.headers.len() > n_blocks {
+                    self.headers.pop_first();
+                }
+            }
+            CacheUpdate::RevertHeader(block_number) => {
+                self.headers.split_off(block_number);
+            }
+            CacheUpdate::AppendTransactions(block_number, transaction_hashes) => {
+                for (offset, transaction_hash) in transaction_hashes.iter().enumerate() {
+                    self.transaction_locations.insert(
+                        *transaction_hash,
+                        TransactionIndex(*block_number, TransactionOffsetInBlock(offset)),
+                    );
+                }
+                self.transaction_hashes.insert(*block_number, transaction_hashes.clone());
+                while self.transaction_hashes.len() > n_blocks {
+                    if let Some((block_number, transaction_hashes)) =
+                        self.transaction_hashes.pop_first()
+                    {
+                        self.remove_transaction_locations(block_number, &transaction_hashes);
+                    }
+                }
+            }
+            CacheUpdate::RevertTransactions(block_number) => {
+                for (block_number, transaction_hashes) in
+                    self.transaction_hashes.split_off(block_number)
+                {
+                    self.remove_transaction_locations(block_number, &transaction_hashes);
+                }
+            }
+        }
+    }
+
+    fn remove_transaction_locations(
+        &mut self,
+        block_number: BlockNumber,
+        transaction_hashes: &[TransactionHash],
+    ) {
+        for transaction_hash in transaction_hashes {
+            // Don't remove the location of a transaction with the same hash in another block.
+            if self
+                .transaction_locations
+                .get(transaction_hash)
+                .is_some_and(|transaction_index| transaction_index.0 == block_number)
+            {
+                self.transaction_locations.remove(transaction_hash);
+            }
+        }
+    }
+}
+
+impl CacheUpdate {
+    fn is_revert(&self) -> bool {
+        matches!(self, CacheUpdate::RevertHeader(_) | CacheUpdate::RevertTransactions(_))
+    }
+}
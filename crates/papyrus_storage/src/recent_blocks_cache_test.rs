@@ -0,0 +1,86 @@
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::hash::StarkHash;
+use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
+
+use super::{CacheUpdate, RecentBlocksCache};
+use crate::body::TransactionIndex;
+
+fn header(block_number: u64) -> BlockHeader {
+    BlockHeader {
+        block_hash: BlockHash(StarkHash::from(block_number)),
+        block_number: BlockNumber(block_number),
+        ..Default::default()
+    }
+}
+
+fn transaction_hash(block_number: u64) -> TransactionHash {
+    TransactionHash(StarkHash::from(block_number))
+}
+
+fn append_block(block_number: u64) -> [CacheUpdate; 2] {
+    [
+        CacheUpdate::AppendHeader(BlockNumber(block_number), header(block_number)),
+        CacheUpdate::AppendTransactions(
+            BlockNumber(block_number),
+            vec![transaction_hash(block_number)],
+        ),
+    ]
+}
+
+#[test]
+fn keeps_only_the_latest_blocks() {
+    let cache = RecentBlocksCache::new(2);
+    for block_number in 0..3 {
+        cache.apply(&append_block(block_number));
+    }
+
+    let view = cache.view();
+    assert_eq!(cache.get_header(view.as_ref(), BlockNumber(0)), None);
+    assert_eq!(cache.get_header(view.as_ref(), BlockNumber(2)), Some(header(2)));
+    assert_eq!(cache.get_transaction_idx(view.as_ref(), &transaction_hash(0)), None);
+    assert_eq!(
+        cache.get_transaction_idx(view.as_ref(), &transaction_hash(2)),
+        Some(TransactionIndex(BlockNumber(2), TransactionOffsetInBlock(0)))
+    );
+}
+
+#[test]
+fn views_exclude_blocks_appended_after_them() {
+    let cache = RecentBlocksCache::new(10);
+    cache.apply(&append_block(0));
+    let view = cache.view();
+    cache.apply(&append_block(1));
+
+    assert_eq!(cache.get_header(view.as_ref(), BlockNumber(0)), Some(header(0)));
+    assert_eq!(cache.get_header(view.as_ref(), BlockNumber(1)), None);
+    assert_eq!(cache.get_transaction_idx(view.as_ref(), &transaction_hash(1)), None);
+}
+
+#[test]
+fn reverts_invalidate_previous_views() {
+    let cache = RecentBlocksCache::new(10);
+    cache.apply(&append_block(0));
+    cache.apply(&append_block(1));
+    let view_before_revert = cache.view();
+
+    let revert = [
+        CacheUpdate::RevertTransactions(BlockNumber(1)),
+        CacheUpdate::RevertHeader(BlockNumber(1)),
+    ];
+    cache.invalidate_reverted(&revert);
+    assert_eq!(cache.get_header(view_before_revert.as_ref(), BlockNumber(0)), None);
+
+    let view_after_revert = cache.view();
+    assert_eq!(cache.get_header(view_after_revert.as_ref(), BlockNumber(0)), Some(header(0)));
+    assert_eq!(cache.get_header(view_after_revert.as_ref(), BlockNumber(1)), None);
+    assert_eq!(cache.get_transaction_idx(view_after_revert.as_ref(), &transaction_hash(1)), None);
+}
+
+#[test]
+fn disabled_cache_is_empty() {
+    let cache = RecentBlocksCache::new(0);
+    cache.apply(&append_block(0));
+    assert!(cache.view().is_none());
+    assert_eq!(cache.get_header(cache.view().as_ref(), BlockNumber(0)), None);
+}
@@ -20,6 +20,8 @@ use cairo_lang_utils::bigint::BigUintAsHex;
 use indexmap::IndexMap;
 use integer_encoding::*;
 use num_bigint::BigUint;
+use papyrus_common::l1_to_l2_messages::{L1ToL2MessageHash, L1TransactionHash};
+use papyrus_common::raw_transaction::RawTransaction;
 use parity_scale_codec::{Decode, Encode};
 use primitive_types::H160;
 use starknet_api::block::{
@@ -107,6 +109,7 @@ use starknet_api::transaction::{
 
 use crate::body::events::{
     EventIndex,
+    EventsBloomFilter,
     ThinDeclareTransactionOutput,
     ThinDeployAccountTransactionOutput,
     ThinDeployTransactionOutput,
@@ -128,6 +131,7 @@ use crate::mmap_file::LocationInFile;
 #[cfg(test)]
 use crate::serializers::serializers_test::{create_storage_serde_test, StorageSerdeTest};
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state_trie::{BinaryNode, EdgeNode, StateTrieRoots, TrieNode};
 use crate::version::Version;
 use crate::{MarkerKind, OffsetKind};
 
@@ -136,6 +140,10 @@ const COMPRESSION_THRESHOLD_BYTES: usize = 384;
 
 auto_storage_serde! {
     pub struct AccountDeploymentData(pub Vec<StarkFelt>);
+    pub struct BinaryNode {
+        pub left: StarkHash,
+        pub right: StarkHash,
+    }
     pub struct BlockHash(pub StarkHash);
     pub struct BlockHeader {
         pub block_hash: BlockHash,
@@ -208,6 +216,11 @@ auto_storage_serde! {
         V1(DeployAccountTransactionV1) = 0,
         V3(DeployAccountTransactionV3) = 1,
     }
+    pub struct EdgeNode {
+        pub path: StarkFelt,
+        pub length: u8,
+        pub child: StarkHash,
+    }
     pub struct DeprecatedEntryPoint {
         pub selector: EntryPointSelector,
         pub offset: EntryPointOffset,
@@ -244,6 +257,7 @@ auto_storage_serde! {
     struct EventIndex(pub TransactionIndex, pub EventIndexInTransactionOutput);
     pub struct EventIndexInTransactionOutput(pub usize);
     pub struct EventKey(pub StarkFelt);
+    pub struct EventsBloomFilter(pub Vec<u64>);
     pub struct Fee(pub u128);
     pub struct FunctionAbiEntry {
         pub name: String,
@@ -270,7 +284,9 @@ auto_storage_serde! {
         No = 0,
         Yes = 1,
     }
+    pub struct L1ToL2MessageHash(pub [u8; 32]);
     pub struct L1ToL2Payload(pub Vec<StarkFelt>);
+    pub struct L1TransactionHash(pub [u8; 32]);
     pub struct L2ToL1Payload(pub Vec<StarkFelt>);
     enum MarkerKind {
         Header = 0,
@@ -278,6 +294,9 @@ auto_storage_serde! {
         State = 2,
         CompiledClass = 3,
         BaseLayerBlock = 4,
+        L1ToL2Messages = 5,
+        StateTrie = 6,
+        PrunedBody = 7,
     }
     pub struct MessageToL1 {
         pub to_address: EthAddress,
@@ -312,6 +331,10 @@ auto_storage_serde! {
         pub prime: serde_json::Value,
         pub reference_manager: serde_json::Value,
     }
+    pub struct RawTransaction {
+        pub transaction: serde_json::Value,
+        pub receipt: serde_json::Value,
+    }
     pub enum Resource {
         L1Gas = 0,
         L2Gas = 1,
@@ -331,6 +354,10 @@ auto_storage_serde! {
         pub offset: usize,
     }
     pub struct StarknetVersion(pub String);
+    pub struct StateTrieRoots {
+        pub contracts_trie_root: StarkHash,
+        pub classes_trie_root: StarkHash,
+    }
     pub struct Tip(pub u64);
     pub struct ThinDeclareTransactionOutput {
         pub actual_fee: Fee,
@@ -392,6 +419,10 @@ auto_storage_serde! {
         Reverted = 1,
     }
     pub struct TransactionHash(pub StarkHash);
+    pub enum TrieNode {
+        Binary(BinaryNode) = 0,
+        Edge(EdgeNode) = 1,
+    }
     struct TransactionIndex(pub BlockNumber, pub TransactionOffsetInBlock);
     pub struct TransactionOffsetInBlock(pub usize);
     pub struct TransactionSignature(pub Vec<StarkFelt>);
@@ -441,6 +472,8 @@ auto_storage_serde! {
     (ContractAddress, BlockNumber);
     (ContractAddress, Nonce);
     (ContractAddress, EventIndex);
+    (ContractAddress, StorageKey);
+    (EventKey, EventIndex);
     (ContractAddress, StorageKey, BlockHash);
     (ContractAddress, StorageKey, BlockNumber);
     (usize, Vec<Hint>);
@@ -7,7 +7,7 @@ use starknet_api::block::BlockNumber;
 use starknet_api::core::ContractAddress;
 use starknet_api::hash::StarkHash;
 use starknet_api::state::StorageKey;
-use test_utils::{get_rng, read_json_file, GetTestInstance};
+use test_utils::{for_random_instances, read_json_file, GetTestInstance};
 
 use crate::db::serialization::StorageSerde;
 
@@ -16,16 +16,17 @@ pub trait StorageSerdeTest: StorageSerde {
 }
 
 // Implements the [`storage_serde_test`] function for every type that
-// implements the [`StorageSerde`] and [`GetTestInstance`] traits.
+// implements the [`StorageSerde`] and [`GetTestInstance`] traits. The test checks that many random
+// instances of the type roundtrip.
 impl<T: StorageSerde + GetTestInstance + Eq + Debug> StorageSerdeTest for T {
     fn storage_serde_test() {
-        let mut rng = get_rng();
-        let item = T::get_test_instance(&mut rng);
-        let mut serialized: Vec<u8> = Vec::new();
-        item.serialize_into(&mut serialized).unwrap();
-        let bytes = serialized.into_boxed_slice();
-        let deserialized = T::deserialize_from(&mut bytes.as_ref());
-        assert_eq!(item, deserialized.unwrap());
+        for_random_instances(|item: T| {
+            let mut serialized: Vec<u8> = Vec::new();
+            item.serialize_into(&mut serialized).unwrap();
+            let bytes = serialized.into_boxed_slice();
+            let deserialized = T::deserialize_from(&mut bytes.as_ref());
+            assert_eq!(item, deserialized.unwrap());
+        });
     }
 }
 
@@ -0,0 +1,441 @@
+//! Exports the blocks of a storage into a snapshot file, and imports a snapshot into a new
+//! storage, so that a new node can start from the blocks of another node instead of syncing them.
+//!
+//! A snapshot is a gzip-compressed stream of JSON lines: a [`SnapshotManifest`] followed by the
+//! data of each block (header, body, state diff and compiled classes), in ascending order. The
+//! gzip trailer holds a CRC32 checksum of the stream, which is verified when the stream is read to
+//! its end. On import, each block is also verified to point to the hash of the previous block, and
+//! the last block is verified to have the hash in the manifest. Optionally, the hashes of the
+//! blocks and their transactions are recomputed and verified as well. The whole snapshot is
+//! verified before any of its blocks is stored.
+#[cfg(test)]
+#[path = "snapshot_test.rs"]
+mod snapshot_test;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{Block, BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ChainId, ClassHash};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::state::{StateDiff, StateNumber};
+use starknet_api::StarknetApiError;
+use tracing::info;
+
+use crate::base_layer::{BaseLayerStorageReader, BaseLayerStorageWriter};
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
+use crate::db::{RO, RW};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter, StarknetVersion};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::utils::{find_block_divergence, read_block, ChainDivergence};
+use crate::{
+    open_storage,
+    open_storage_read_only,
+    StorageConfig,
+    StorageError,
+    StorageReader,
+    StorageScope,
+    StorageTxn,
+    StorageWriter,
+};
+
+/// The version of the snapshot format. Snapshots of other versions can't be imported.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 0;
+
+// The number of blocks that are exported or imported in each storage transaction, and between
+// progress logs.
+const BATCH_SIZE: u64 = 100;
+
+/// Describes the blocks in a snapshot.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SnapshotManifest {
+    /// The version of the snapshot format.
+    pub format_version: u32,
+    /// The chain the blocks belong to.
+    pub chain_id: ChainId,
+    /// The snapshot holds the blocks up to this block, exclusive.
+    pub end_block: BlockNumber,
+    /// The hash of the last block in the snapshot.
+    pub last_block_hash: BlockHash,
+    /// The base layer block marker of the blocks in the snapshot.
+    pub base_layer_block_marker: BlockNumber,
+}
+
+// The data of a block in a snapshot, in the form in which it's appended to the storage.
+#[derive(Debug, Deserialize, Serialize)]
+struct SnapshotBlock {
+    header: BlockHeader,
+    starknet_version: StarknetVersion,
+    body: BlockBody,
+    state_diff: StateDiff,
+    deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
+    casms: IndexMap<ClassHash, CasmContractClass>,
+}
+
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum SnapshotError {
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+    #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
+    #[error("Snapshots can be exported only from full-archive storages without pruned bodies.")]
+    UnsupportedStorage,
+    #[error("The storage has only {available} blocks, but {requested} blocks were requested.")]
+    MissingBlocks { requested: BlockNumber, available: BlockNumber },
+    #[error("Snapshots can be imported only into empty storages.")]
+    StorageNotEmpty,
+    #[error("Snapshot format version {0} is not supported.")]
+    UnsupportedFormatVersion(u32),
+    #[error(
+        "The snapshot is of chain {}, but the storage is of chain {}.",
+        .snapshot_chain_id.0,
+        .storage_chain_id.0
+    )]
+    ChainIdMismatch { snapshot_chain_id: ChainId, storage_chain_id: ChainId },
+    #[error("The snapshot ended at block {0}, before the end block in its manifest.")]
+    Truncated(BlockNumber),
+    #[error("Expected block {expected} in the snapshot, found block {found}.")]
+    UnexpectedBlock { expected: BlockNumber, found: BlockNumber },
+    #[error("{0}.")]
+    ChainDivergence(ChainDivergence),
+    #[error("The hash of the last block is {found:?}, but the manifest has {expected:?}.")]
+    LastBlockHashMismatch { expected: BlockHash, found: BlockHash },
+}
+
+/// Exports the blocks of the storage in the given path up to the given block (exclusive) into a
+/// snapshot file. If no end block is given, all the blocks whose header, body, state diff and
+/// compiled classes are stored are exported. The storage is opened read-only, so it can be exported
+/// while a node syncs it.
+pub fn export_snapshot(
+    path_prefix: PathBuf,
+    chain_id: &str,
+    end_block: Option<u64>,
+    snapshot_path: &Path,
+) -> Result<SnapshotManifest, SnapshotError> {
+    let mut storage_config = StorageConfig::default();
+    storage_config.db_config.path_prefix = path_prefix;
+    storage_config.db_config.chain_id = ChainId(chain_id.to_string());
+    storage_config.db_config.enforce_file_exists = true;
+    let reader = open_storage_read_only(storage_config)?;
+    export_snapshot_internal(
+        &reader,
+        ChainId(chain_id.to_string()),
+        end_block.map(BlockNumber),
+        snapshot_path,
+    )
+}
+
+fn export_snapshot_internal(
+    reader: &StorageReader,
+    chain_id: ChainId,
+    end_block: Option<BlockNumber>,
+    snapshot_path: &Path,
+) -> Result<SnapshotManifest, SnapshotError> {
+    let mut txn = reader.begin_ro_txn()?;
+    if reader.get_scope() != StorageScope::FullArchive
+        || txn.get_pruned_body_marker()? > BlockNumber(0)
+    {
+        return Err(SnapshotError::UnsupportedStorage);
+    }
+    let available = txn
+        .get_header_marker()?
+        .min(txn.get_body_marker()?)
+        .min(txn.get_state_marker()?)
+        .min(txn.get_compiled_class_marker()?);
+    let end_block = end_block.unwrap_or(available);
+    if end_block > available {
+        return Err(SnapshotError::MissingBlocks { requested: end_block, available });
+    }
+    let last_block_hash = match end_block.prev() {
+        Some(last_block) => {
+            txn.get_block_header(last_block)?
+                .expect("Blocks below the markers should exist.")
+                .block_hash
+        }
+        None => BlockHash::default(),
+    };
+    let manifest = SnapshotManifest {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        chain_id,
+        end_block,
+        last_block_hash,
+        base_layer_block_marker: txn.get_base_layer_block_marker()?.min(end_block),
+    };
+
+    let mut writer =
+        GzEncoder::new(BufWriter::new(File::create(snapshot_path)?), Compression::default());
+    write_line(&mut writer, &manifest)?;
+    // The blocks are read in a transaction per batch, so that a node that syncs the storage can
+    // reuse the pages it frees meanwhile. Blocks that are reverted and replaced during the export
+    // break the chain of block hashes, which is verified as in the import.
+    let mut previous_block_hash = None;
+    for block_number in (0..end_block.0).map(BlockNumber) {
+        let block = read_snapshot_block(&txn, block_number)?;
+        verify_snapshot_block(
+            &block,
+            block_number,
+            previous_block_hash,
+            &manifest.chain_id,
+            false,
+        )?;
+        previous_block_hash = Some(block.header.block_hash);
+        write_line(&mut writer, &block)?;
+        if block_number.next().0 % BATCH_SIZE == 0 {
+            info!("Exported blocks up to {block_number}.");
+            txn = reader.begin_ro_txn()?;
+        }
+    }
+    verify_last_block_hash(previous_block_hash, &manifest)?;
+    writer.finish()?.flush()?;
+    Ok(manifest)
+}
+
+/// Imports the snapshot file into a new storage in the given path, and returns the manifest of the
+/// snapshot. If verify_hashes is set, the hashes of the blocks and their transactions are
+/// recomputed and verified as well. The whole snapshot, including its checksum, is verified before
+/// any block is stored, so an invalid snapshot leaves the storage empty. The blocks are then
+/// committed in batches, so if storing them fails, the storage should be deleted before importing
+/// again.
+pub fn import_snapshot(
+    path_prefix: PathBuf,
+    chain_id: &str,
+    snapshot_path: &Path,
+    verify_hashes: bool,
+) -> Result<SnapshotManifest, SnapshotError> {
+    let mut storage_config = StorageConfig::default();
+    storage_config.db_config.path_prefix = path_prefix;
+    storage_config.db_config.chain_id = ChainId(chain_id.to_string());
+    let (_, mut writer) = open_storage(storage_config)?;
+    import_snapshot_internal(
+        &mut writer,
+        &ChainId(chain_id.to_string()),
+        snapshot_path,
+        verify_hashes,
+    )
+}
+
+fn import_snapshot_internal(
+    writer: &mut StorageWriter,
+    chain_id: &ChainId,
+    snapshot_path: &Path,
+    verify_hashes: bool,
+) -> Result<SnapshotManifest, SnapshotError> {
+    if writer.begin_rw_txn()?.get_header_marker()? > BlockNumber(0) {
+        return Err(SnapshotError::StorageNotEmpty);
+    }
+    let manifest = verify_snapshot(snapshot_path, chain_id, verify_hashes)?;
+
+    let (mut reader, _) = open_snapshot(snapshot_path)?;
+    let mut txn = writer.begin_rw_txn()?;
+    for block_number in (0..manifest.end_block.0).map(BlockNumber) {
+        let block: SnapshotBlock = read_line(&mut reader, block_number)?;
+        txn = append_snapshot_block(txn, block_number, block)?;
+        if block_number.next().0 % BATCH_SIZE == 0 {
+            txn.commit()?;
+            info!("Imported blocks up to {block_number}.");
+            txn = writer.begin_rw_txn()?;
+        }
+    }
+    txn.update_base_layer_block_marker(&manifest.base_layer_block_marker)?.commit()?;
+    Ok(manifest)
+}
+
+// Opens the snapshot file and reads its manifest. The returned reader is at the first block.
+fn open_snapshot(snapshot_path: &Path) -> Result<(impl BufRead, SnapshotManifest), SnapshotError> {
+    let mut reader = BufReader::new(GzDecoder::new(BufReader::new(File::open(snapshot_path)?)));
+    let manifest: SnapshotManifest = read_line(&mut reader, BlockNumber(0))?;
+    if manifest.format_version != SNAPSHOT_FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedFormatVersion(manifest.format_version));
+    }
+    Ok((reader, manifest))
+}
+
+// Reads the whole snapshot file and verifies its blocks and checksum, without storing anything.
+fn verify_snapshot(
+    snapshot_path: &Path,
+    chain_id: &ChainId,
+    verify_hashes: bool,
+) -> Result<SnapshotManifest, SnapshotError> {
+    let (mut reader, manifest) = open_snapshot(snapshot_path)?;
+    if manifest.chain_id != *chain_id {
+        return Err(SnapshotError::ChainIdMismatch {
+            snapshot_chain_id: manifest.chain_id,
+            storage_chain_id: chain_id.clone(),
+        });
+    }
+    let mut previous_block_hash = None;
+    for block_number in (0..manifest.end_block.0).map(BlockNumber) {
+        let block: SnapshotBlock = read_line(&mut reader, block_number)?;
+        verify_snapshot_block(&block, block_number, previous_block_hash, chain_id, verify_hashes)?;
+        previous_block_hash = Some(block.header.block_hash);
+        if block_number.next().0 % BATCH_SIZE == 0 {
+            info!("Verified blocks up to {block_number}.");
+        }
+    }
+    verify_last_block_hash(previous_block_hash, &manifest)?;
+    // Reading the stream to its end verifies its checksum.
+    reader.read_to_end(&mut Vec::new())?;
+    Ok(manifest)
+}
+
+fn write_line(writer: &mut impl Write, value: &impl Serialize) -> Result<(), SnapshotError> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+// Reads a JSON line of the snapshot. The block number is of the block that is being read, for the
+// error in case the snapshot ended.
+fn read_line<T: DeserializeOwned>(
+    reader: &mut impl BufRead,
+    block_number: BlockNumber,
+) -> Result<T, SnapshotError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(SnapshotError::Truncated(block_number));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+fn read_snapshot_block(
+    txn: &StorageTxn<'_, RO>,
+    block_number: BlockNumber,
+) -> Result<SnapshotBlock, SnapshotError> {
+    let missing_data =
+        || StorageError::DBInconsistency { msg: format!("Missing data of block {block_number}.") };
+    let Block { header, body } = read_block(txn, block_number)?;
+    let starknet_version = txn.get_starknet_version(block_number)?.ok_or_else(missing_data)?;
+    let thin_state_diff = txn.get_state_diff(block_number)?.ok_or_else(missing_data)?;
+    let state_reader = txn.get_state_reader()?;
+    let state_number = StateNumber::right_after_block(block_number);
+
+    let mut declared_classes = IndexMap::new();
+    let mut casms = IndexMap::new();
+    for (class_hash, compiled_class_hash) in &thin_state_diff.declared_classes {
+        let class = state_reader
+            .get_class_definition_at(state_number, class_hash)?
+            .ok_or_else(missing_data)?;
+        declared_classes.insert(*class_hash, (*compiled_class_hash, class));
+        casms.insert(*class_hash, txn.get_casm(class_hash)?.ok_or_else(missing_data)?);
+    }
+    let mut deprecated_declared_classes = IndexMap::new();
+    for class_hash in &thin_state_diff.deprecated_declared_classes {
+        let class = state_reader
+            .get_deprecated_class_definition_at(state_number, class_hash)?
+            .ok_or_else(missing_data)?;
+        deprecated_declared_classes.insert(*class_hash, class);
+    }
+    // Until Starknet 0.11, deploying a contract declared its class implicitly, in which case the
+    // class is stored in the block of the deployment.
+    let mut deployed_contract_class_definitions = IndexMap::new();
+    for class_hash in thin_state_diff.deployed_contracts.values() {
+        if deprecated_declared_classes.contains_key(class_hash)
+            || deployed_contract_class_definitions.contains_key(class_hash)
+            || state_reader.get_deprecated_class_definition_block_number(class_hash)?
+                != Some(block_number)
+        {
+            continue;
+        }
+        let class = state_reader
+            .get_deprecated_class_definition_at(state_number, class_hash)?
+            .ok_or_else(missing_data)?;
+        deployed_contract_class_definitions.insert(*class_hash, class);
+    }
+
+    let state_diff = StateDiff {
+        deployed_contracts: thin_state_diff.deployed_contracts,
+        storage_diffs: thin_state_diff.storage_diffs,
+        declared_classes,
+        deprecated_declared_classes,
+        nonces: thin_state_diff.nonces,
+        replaced_classes: thin_state_diff.replaced_classes,
+    };
+    Ok(SnapshotBlock {
+        header,
+        starknet_version,
+        body,
+        state_diff,
+        deployed_contract_class_definitions,
+        casms,
+    })
+}
+
+fn verify_snapshot_block(
+    block: &SnapshotBlock,
+    block_number: BlockNumber,
+    previous_block_hash: Option<BlockHash>,
+    chain_id: &ChainId,
+    verify_hashes: bool,
+) -> Result<(), SnapshotError> {
+    if block.header.block_number != block_number {
+        return Err(SnapshotError::UnexpectedBlock {
+            expected: block_number,
+            found: block.header.block_number,
+        });
+    }
+    let divergence = if verify_hashes {
+        let block = Block { header: block.header.clone(), body: block.body.clone() };
+        find_block_divergence(&block, previous_block_hash, chain_id)?
+    } else {
+        previous_block_hash
+            .filter(|previous_block_hash| *previous_block_hash != block.header.parent_hash)
+            .map(|previous_block_hash| ChainDivergence::ParentHash {
+                block_number,
+                parent_hash: block.header.parent_hash,
+                previous_block_hash,
+            })
+    };
+    match divergence {
+        Some(divergence) => Err(SnapshotError::ChainDivergence(divergence)),
+        None => Ok(()),
+    }
+}
+
+fn verify_last_block_hash(
+    last_block_hash: Option<BlockHash>,
+    manifest: &SnapshotManifest,
+) -> Result<(), SnapshotError> {
+    match last_block_hash {
+        Some(last_block_hash) if last_block_hash != manifest.last_block_hash => {
+            Err(SnapshotError::LastBlockHashMismatch {
+                expected: manifest.last_block_hash,
+                found: last_block_hash,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn append_snapshot_block(
+    txn: StorageTxn<'_, RW>,
+    block_number: BlockNumber,
+    block: SnapshotBlock,
+) -> Result<StorageTxn<'_, RW>, SnapshotError> {
+    let mut txn = txn
+        .append_header(block_number, &block.header)?
+        .update_starknet_version(&block_number, &block.starknet_version)?
+        .append_body(block_number, block.body)?
+        .append_state_diff(
+            block_number,
+            block.state_diff,
+            block.deployed_contract_class_definitions,
+        )?;
+    for (class_hash, casm) in &block.casms {
+        txn = txn.append_casm(class_hash, casm)?;
+    }
+    Ok(txn)
+}
@@ -0,0 +1,141 @@
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+use tempfile::TempDir;
+
+use crate::chain_fixture::ChainFixtureBuilder;
+use crate::compiled_class::CasmStorageWriter;
+use crate::header::HeaderStorageReader;
+use crate::snapshot::{export_snapshot_internal, import_snapshot_internal, SnapshotError};
+use crate::test_utils::get_test_storage;
+use crate::utils::compare_storages_by_block_range_internal;
+use crate::StorageReader;
+
+const N_BLOCKS: u64 = 10;
+
+fn chain_id() -> ChainId {
+    ChainId("SN_TEST".to_owned())
+}
+
+// Returns a storage with a chain whose classes are compiled, and the path of a snapshot file in a
+// temporary directory.
+fn setup() -> (StorageReader, PathBuf, (TempDir, TempDir)) {
+    let ((reader, mut writer), storage_dir) = get_test_storage();
+    let fixture = ChainFixtureBuilder::new(N_BLOCKS).transactions_per_block(5..=20).build();
+    fixture.write_to_storage(&mut writer).unwrap();
+    for state_diff in &fixture.state_diffs {
+        for class_hash in state_diff.declared_classes.keys() {
+            writer
+                .begin_rw_txn()
+                .unwrap()
+                .append_casm(class_hash, &CasmContractClass::default())
+                .unwrap()
+                .commit()
+                .unwrap();
+        }
+    }
+    let snapshot_dir = tempfile::tempdir().unwrap();
+    let snapshot_path = snapshot_dir.path().join("snapshot.gz");
+    (reader, snapshot_path, (storage_dir, snapshot_dir))
+}
+
+#[test]
+fn imported_snapshot_matches_the_exported_storage() {
+    let (reader, snapshot_path, _temp_dirs) = setup();
+    let manifest = export_snapshot_internal(&reader, chain_id(), None, &snapshot_path).unwrap();
+    assert_eq!(manifest.end_block, BlockNumber(N_BLOCKS));
+    let last_block_hash = reader
+        .begin_ro_txn()
+        .unwrap()
+        .get_block_header(BlockNumber(N_BLOCKS - 1))
+        .unwrap()
+        .unwrap()
+        .block_hash;
+    assert_eq!(manifest.last_block_hash, last_block_hash);
+
+    let ((imported_reader, mut imported_writer), _imported_storage_dir) = get_test_storage();
+    let imported_manifest =
+        import_snapshot_internal(&mut imported_writer, &chain_id(), &snapshot_path, false).unwrap();
+    assert_eq!(imported_manifest, manifest);
+    let comparison =
+        compare_storages_by_block_range_internal(&reader, &imported_reader, 0, None).unwrap();
+    assert!(comparison.is_empty(), "{comparison:?}");
+
+    // The storage must be empty.
+    let err = import_snapshot_internal(&mut imported_writer, &chain_id(), &snapshot_path, false)
+        .unwrap_err();
+    assert_matches!(err, SnapshotError::StorageNotEmpty);
+}
+
+#[test]
+fn snapshot_is_exported_up_to_the_end_block() {
+    let (reader, snapshot_path, _temp_dirs) = setup();
+    let end_block = BlockNumber(N_BLOCKS / 2);
+    let manifest =
+        export_snapshot_internal(&reader, chain_id(), Some(end_block), &snapshot_path).unwrap();
+    assert_eq!(manifest.end_block, end_block);
+
+    let ((imported_reader, mut imported_writer), _imported_storage_dir) = get_test_storage();
+    import_snapshot_internal(&mut imported_writer, &chain_id(), &snapshot_path, false).unwrap();
+    assert_eq!(imported_reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), end_block);
+
+    let err = export_snapshot_internal(
+        &reader,
+        chain_id(),
+        Some(BlockNumber(N_BLOCKS + 1)),
+        &snapshot_path,
+    )
+    .unwrap_err();
+    assert_matches!(err, SnapshotError::MissingBlocks { .. });
+}
+
+#[test]
+fn invalid_snapshot_is_not_imported() {
+    let (reader, snapshot_path, _temp_dirs) = setup();
+    export_snapshot_internal(&reader, chain_id(), None, &snapshot_path).unwrap();
+
+    let ((_, mut imported_writer), _imported_storage_dir) = get_test_storage();
+    let other_chain_id = ChainId("SN_OTHER".to_owned());
+    let err =
+        import_snapshot_internal(&mut imported_writer, &other_chain_id, &snapshot_path, false)
+            .unwrap_err();
+    assert_matches!(err, SnapshotError::ChainIdMismatch { .. });
+
+    // The hashes of the fixture's transactions are random.
+    let ((_, mut imported_writer), _imported_storage_dir) = get_test_storage();
+    let err = import_snapshot_internal(&mut imported_writer, &chain_id(), &snapshot_path, true)
+        .unwrap_err();
+    assert_matches!(err, SnapshotError::ChainDivergence(_));
+
+    // The snapshot is verified before any block is stored, so a snapshot with a wrong checksum
+    // leaves the storage empty.
+    let mut snapshot_file = OpenOptions::new().read(true).write(true).open(&snapshot_path).unwrap();
+    let snapshot_len = snapshot_file.metadata().unwrap().len();
+    // The gzip trailer is the CRC32 checksum followed by the length of the stream, 4 bytes each.
+    let checksum_offset = snapshot_len - 8;
+    let mut checksum_byte = [0u8];
+    snapshot_file.seek(SeekFrom::Start(checksum_offset)).unwrap();
+    snapshot_file.read_exact(&mut checksum_byte).unwrap();
+    snapshot_file.seek(SeekFrom::Start(checksum_offset)).unwrap();
+    snapshot_file.write_all(&[!checksum_byte[0]]).unwrap();
+    let ((imported_reader, mut imported_writer), _imported_storage_dir) = get_test_storage();
+    assert!(
+        import_snapshot_internal(&mut imported_writer, &chain_id(), &snapshot_path, false).is_err()
+    );
+    assert_eq!(
+        imported_reader.begin_ro_txn().unwrap().get_header_marker().unwrap(),
+        BlockNumber(0)
+    );
+
+    snapshot_file.set_len(snapshot_len / 2).unwrap();
+    let ((_, mut imported_writer), _imported_storage_dir) = get_test_storage();
+    assert!(
+        import_snapshot_internal(&mut imported_writer, &chain_id(), &snapshot_path, false).is_err()
+    );
+}
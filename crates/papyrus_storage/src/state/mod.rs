@@ -24,6 +24,7 @@
 //! #     min_size: 1 << 20,    // 1MB
 //! #     max_size: 1 << 35,    // 32GB
 //! #     growth_step: 1 << 26, // 64MB
+//! #     ..Default::default()
 //! # };
 //! # let storage_config = StorageConfig{db_config, ..Default::default()};
 //! let state_diff = StateDiff::default();
@@ -56,9 +57,10 @@ use std::collections::HashSet;
 
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 use indexmap::IndexMap;
+use papyrus_common::pending_classes::ApiContractClass;
 use papyrus_proc_macros::latency_histogram;
 use starknet_api::block::BlockNumber;
-use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::StarkFelt;
 use starknet_api::state::{ContractClass, StateDiff, StateNumber, StorageKey, ThinStateDiff};
@@ -68,6 +70,7 @@ use crate::db::serialization::NoVersionValueWrapper;
 use crate::db::{DbError, DbTransaction, TableHandle, TransactionKind, RW};
 use crate::mmap_file::LocationInFile;
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state_trie::revert_state_trie_roots;
 use crate::{
     FileHandlers,
     MarkerKind,
@@ -93,6 +96,12 @@ type ContractStorageTable<'env> =
     TableHandle<'env, (ContractAddress, StorageKey, BlockNumber), NoVersionValueWrapper<StarkFelt>>;
 type NoncesTable<'env> =
     TableHandle<'env, (ContractAddress, BlockNumber), NoVersionValueWrapper<Nonce>>;
+type StateDiffsTable<'env> = TableHandle<'env, BlockNumber, NoVersionValueWrapper<LocationInFile>>;
+type LatestClassHashesTable<'env> =
+    TableHandle<'env, ContractAddress, NoVersionValueWrapper<ClassHash>>;
+type LatestContractStorageTable<'env> =
+    TableHandle<'env, (ContractAddress, StorageKey), NoVersionValueWrapper<StarkFelt>>;
+type LatestNoncesTable<'env> = TableHandle<'env, ContractAddress, NoVersionValueWrapper<Nonce>>;
 
 /// Interface for reading data related to the state.
 // Structure of state data:
@@ -112,6 +121,10 @@ type NoncesTable<'env> =
 //   block_num.
 // * nonces_table: (contract_address, block_num) -> (nonce). Specifies that at `block_num`, the
 //   nonce of `contract_address` was changed to `nonce`.
+// * latest_class_hashes_table, latest_storage_table, latest_nonces_table: the values of the
+//   deployed_contracts_table, storage_table and nonces_table at the latest state, without the block
+//   number, so that the latest state is read with a single lookup. They are updated with every
+//   appended state diff, and restored from the history tables when a state diff is reverted.
 
 pub trait StateStorageReader<Mode: TransactionKind> {
     /// The state marker is the first block number that doesn't exist yet.
@@ -193,6 +206,12 @@ pub struct StateReader<'env, Mode: TransactionKind> {
     deployed_contracts_table: DeployedContractsTable<'env>,
     nonces_table: NoncesTable<'env>,
     storage_table: ContractStorageTable<'env>,
+    state_diffs_table: StateDiffsTable<'env>,
+    latest_class_hashes_table: LatestClassHashesTable<'env>,
+    latest_nonces_table: LatestNoncesTable<'env>,
+    latest_storage_table: LatestContractStorageTable<'env>,
+    // The first state that is read from the latest state tables.
+    state_marker: BlockNumber,
     file_handlers: &'env FileHandlers<Mode>,
 }
 
@@ -216,6 +235,10 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         let deployed_contracts_table = txn.txn.open_table(&txn.tables.deployed_contracts)?;
         let nonces_table = txn.txn.open_table(&txn.tables.nonces)?;
         let storage_table = txn.txn.open_table(&txn.tables.contract_storage)?;
+        let state_diffs_table = txn.txn.open_table(&txn.tables.state_diffs)?;
+        let latest_class_hashes_table = txn.txn.open_table(&txn.tables.latest_class_hashes)?;
+        let latest_nonces_table = txn.txn.open_table(&txn.tables.latest_nonces)?;
+        let latest_storage_table = txn.txn.open_table(&txn.tables.latest_contract_storage)?;
         Ok(StateReader {
             txn: &txn.txn,
             declared_classes_table,
@@ -224,10 +247,21 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
             deployed_contracts_table,
             nonces_table,
             storage_table,
+            state_diffs_table,
+            latest_class_hashes_table,
+            latest_nonces_table,
+            latest_storage_table,
+            state_marker: txn.get_state_marker()?,
             file_handlers: &txn.file_handlers,
         })
     }
 
+    // Whether the given state is the latest state (or a later one), which is read from the latest
+    // state tables instead of the history tables.
+    fn is_latest_state(&self, state_number: StateNumber) -> bool {
+        state_number.block_after() >= self.state_marker
+    }
+
     /// Returns the class hash at a given state number.
     /// If class hash is not found, returns `None`.
     ///
@@ -242,17 +276,15 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         state_number: StateNumber,
         address: &ContractAddress,
     ) -> StorageResult<Option<ClassHash>> {
-        let first_irrelevant_block: BlockNumber = state_number.block_after();
-        let db_key = (*address, first_irrelevant_block);
-        let mut cursor = self.deployed_contracts_table.cursor(self.txn)?;
-        cursor.lower_bound(&db_key)?;
-        let res = cursor.prev()?;
-
-        match res {
-            None => Ok(None),
-            Some(((got_address, _), _)) if got_address != *address => Ok(None),
-            Some((_, class_hash)) => Ok(Some(class_hash)),
+        if self.is_latest_state(state_number) {
+            return Ok(self.latest_class_hashes_table.get(self.txn, address)?);
         }
+        get_class_hash_before_block(
+            self.txn,
+            &self.deployed_contracts_table,
+            address,
+            state_number.block_after(),
+        )
     }
 
     /// Returns the nonce at a given state number.
@@ -269,26 +301,10 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         state_number: StateNumber,
         address: &ContractAddress,
     ) -> StorageResult<Option<Nonce>> {
-        // State diff updates are indexed by the block_number at which they occurred.
-        let first_irrelevant_block: BlockNumber = state_number.block_after();
-        // The relevant update is the last update strictly before `first_irrelevant_block`.
-        let db_key = (*address, first_irrelevant_block);
-        // Find the previous db item.
-        let mut cursor = self.nonces_table.cursor(self.txn)?;
-        cursor.lower_bound(&db_key)?;
-        let res = cursor.prev()?;
-        match res {
-            None => Ok(None),
-            Some(((got_address, _got_block_number), value)) => {
-                if got_address != *address {
-                    // The previous item belongs to different address, which means there is no
-                    // previous state diff for this item.
-                    return Ok(None);
-                };
-                // The previous db item indeed belongs to this address and key.
-                Ok(Some(value))
-            }
+        if self.is_latest_state(state_number) {
+            return Ok(self.latest_nonces_table.get(self.txn, address)?);
         }
+        get_nonce_before_block(self.txn, &self.nonces_table, address, state_number.block_after())
     }
 
     /// Returns the storage value at a given state number for a given contract and key.
@@ -307,26 +323,18 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         address: &ContractAddress,
         key: &StorageKey,
     ) -> StorageResult<StarkFelt> {
-        // The updates to the storage key are indexed by the block_number at which they occurred.
-        let first_irrelevant_block: BlockNumber = state_number.block_after();
-        // The relevant update is the last update strictly before `first_irrelevant_block`.
-        let db_key = (*address, *key, first_irrelevant_block);
-        // Find the previous db item.
-        let mut cursor = self.storage_table.cursor(self.txn)?;
-        cursor.lower_bound(&db_key)?;
-        let res = cursor.prev()?;
-        match res {
-            None => Ok(StarkFelt::default()),
-            Some(((got_address, got_key, _got_block_number), value)) => {
-                if got_address != *address || got_key != *key {
-                    // The previous item belongs to different key, which means there is no
-                    // previous state diff for this item.
-                    return Ok(StarkFelt::default());
-                };
-                // The previous db item indeed belongs to this address and key.
-                Ok(value)
-            }
-        }
+        let value = if self.is_latest_state(state_number) {
+            self.latest_storage_table.get(self.txn, &(*address, *key))?
+        } else {
+            get_storage_before_block(
+                self.txn,
+                &self.storage_table,
+                address,
+                key,
+                state_number.block_after(),
+            )?
+        };
+        Ok(value.unwrap_or_default())
     }
 
     /// Returns the class definition at a given state number.
@@ -407,6 +415,85 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
             self.file_handlers.get_deprecated_contract_class_unchecked(value.location_in_file)?,
         ))
     }
+
+    /// Returns the block number for a given deprecated class hash (the block in which it was
+    /// defined). If the class is not defined, returns `None`.
+    ///
+    /// # Arguments
+    /// * class_hash - class hash to search for.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the table.
+    pub fn get_deprecated_class_definition_block_number(
+        &self,
+        class_hash: &ClassHash,
+    ) -> StorageResult<Option<BlockNumber>> {
+        Ok(self
+            .deprecated_declared_classes_table
+            .get(self.txn, class_hash)?
+            .map(|value| value.block_number))
+    }
+
+    /// Returns the definition of a class at a given state number, whether it's a class or a
+    /// deprecated class.
+    /// If the class isn't declared at the given state number, returns `None`.
+    ///
+    /// # Arguments
+    /// * state_number - state number to search before.
+    /// * class_hash - class hash to search for.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the tables.
+    pub fn get_class_at(
+        &self,
+        state_number: StateNumber,
+        class_hash: &ClassHash,
+    ) -> StorageResult<Option<ApiContractClass>> {
+        // The class might be a deprecated class. Search it first in the declared classes and if
+        // not found, search in the deprecated classes.
+        if let Some(class) = self.get_class_definition_at(state_number, class_hash)? {
+            return Ok(Some(ApiContractClass::ContractClass(class)));
+        }
+        Ok(self
+            .get_deprecated_class_definition_at(state_number, class_hash)?
+            .map(ApiContractClass::DeprecatedContractClass))
+    }
+
+    /// Returns the compiled class hash of a class, as declared in the state diff of the block in
+    /// which the class was defined.
+    /// If the class is not defined, returns `None`.
+    ///
+    /// # Arguments
+    /// * class_hash - class hash to search for.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`] if there was an error searching the tables.
+    ///
+    /// Returns [`StorageError`]::DBInconsistency if the class is declared but the compiled class
+    /// hash isn't in the state diff of its block.
+    pub fn get_compiled_class_hash(
+        &self,
+        class_hash: &ClassHash,
+    ) -> StorageResult<Option<CompiledClassHash>> {
+        let Some(block_number) = self.get_class_definition_block_number(class_hash)? else {
+            return Ok(None);
+        };
+        let Some(state_diff_location) = self.state_diffs_table.get(self.txn, &block_number)? else {
+            return Err(StorageError::DBInconsistency {
+                msg: format!("Missing state diff of block {block_number}."),
+            });
+        };
+        let state_diff = self.file_handlers.get_thin_state_diff_unchecked(state_diff_location)?;
+        let Some(compiled_class_hash) = state_diff.declared_classes.get(class_hash) else {
+            return Err(StorageError::DBInconsistency {
+                msg: format!(
+                    "Missing the declaration of class {class_hash} in the state diff of block \
+                     {block_number}."
+                ),
+            });
+        };
+        Ok(Some(*compiled_class_hash))
+    }
 }
 
 impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
@@ -427,6 +514,9 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         let storage_table = self.open_table(&self.tables.contract_storage)?;
         let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
         let file_offset_table = self.txn.open_table(&self.tables.file_offsets)?;
+        let latest_class_hashes_table = self.open_table(&self.tables.latest_class_hashes)?;
+        let latest_nonces_table = self.open_table(&self.tables.latest_nonces)?;
+        let latest_storage_table = self.open_table(&self.tables.latest_contract_storage)?;
 
         update_marker(&self.txn, &markers_table, block_number)?;
 
@@ -437,14 +527,29 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             block_number,
             &deployed_contracts_table,
             &nonces_table,
+            &latest_class_hashes_table,
+            &latest_nonces_table,
+        )?;
+        write_storage_diffs(
+            &state_diff.storage_diffs,
+            &self.txn,
+            block_number,
+            &storage_table,
+            &latest_storage_table,
+        )?;
+        write_nonces(
+            &state_diff.nonces,
+            &self.txn,
+            block_number,
+            &nonces_table,
+            &latest_nonces_table,
         )?;
-        write_storage_diffs(&state_diff.storage_diffs, &self.txn, block_number, &storage_table)?;
-        write_nonces(&state_diff.nonces, &self.txn, block_number, &nonces_table)?;
         write_replaced_classes(
             &state_diff.replaced_classes,
             &self.txn,
             block_number,
             &deployed_contracts_table,
+            &latest_class_hashes_table,
         )?;
 
         // Write state diff.
@@ -518,6 +623,12 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         let nonces_table = self.open_table(&self.tables.nonces)?;
         let storage_table = self.open_table(&self.tables.contract_storage)?;
         let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
+        let state_trie_roots_table = self.open_table(&self.tables.state_trie_roots)?;
+        let contract_storage_trie_roots_table =
+            self.open_table(&self.tables.contract_storage_trie_roots)?;
+        let latest_class_hashes_table = self.open_table(&self.tables.latest_class_hashes)?;
+        let latest_nonces_table = self.open_table(&self.tables.latest_nonces)?;
+        let latest_storage_table = self.open_table(&self.tables.latest_contract_storage)?;
 
         let current_state_marker = self.get_state_marker()?;
 
@@ -540,6 +651,14 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
         if compiled_classes_marker == block_number.next() {
             markers_table.upsert(&self.txn, &MarkerKind::CompiledClass, &block_number)?;
         }
+        revert_state_trie_roots(
+            &self.txn,
+            &markers_table,
+            &state_trie_roots_table,
+            &contract_storage_trie_roots_table,
+            block_number,
+            &thin_state_diff,
+        )?;
         let deleted_classes = delete_declared_classes(
             &self.txn,
             &thin_state_diff,
@@ -576,6 +695,27 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             &thin_state_diff,
             &deployed_contracts_table,
         )?;
+        revert_latest_class_hashes(
+            &self.txn,
+            block_number,
+            &thin_state_diff,
+            &deployed_contracts_table,
+            &latest_class_hashes_table,
+        )?;
+        revert_latest_nonces(
+            &self.txn,
+            block_number,
+            &thin_state_diff,
+            &nonces_table,
+            &latest_nonces_table,
+        )?;
+        revert_latest_storage(
+            &self.txn,
+            block_number,
+            &thin_state_diff,
+            &storage_table,
+            &latest_storage_table,
+        )?;
 
         Ok((
             self,
@@ -680,9 +820,13 @@ fn write_deployed_contracts<'env>(
     block_number: BlockNumber,
     deployed_contracts_table: &'env DeployedContractsTable<'env>,
     nonces_table: &'env NoncesTable<'env>,
+    latest_class_hashes_table: &'env LatestClassHashesTable<'env>,
+    latest_nonces_table: &'env LatestNoncesTable<'env>,
 ) -> StorageResult<()> {
     for (address, class_hash) in deployed_contracts {
         deployed_contracts_table.insert(txn, &(*address, block_number), class_hash)?;
+        latest_class_hashes_table.upsert(txn, address, class_hash)?;
+        latest_nonces_table.upsert(txn, address, &Nonce::default())?;
 
         nonces_table.insert(txn, &(*address, block_number), &Nonce::default()).map_err(|err| {
             if matches!(err, DbError::KeyAlreadyExists(..)) {
@@ -704,9 +848,11 @@ fn write_nonces<'env>(
     txn: &DbTransaction<'env, RW>,
     block_number: BlockNumber,
     contracts_table: &'env NoncesTable<'env>,
+    latest_nonces_table: &'env LatestNoncesTable<'env>,
 ) -> StorageResult<()> {
     for (contract_address, nonce) in nonces {
         contracts_table.upsert(txn, &(*contract_address, block_number), nonce)?;
+        latest_nonces_table.upsert(txn, contract_address, nonce)?;
     }
     Ok(())
 }
@@ -716,9 +862,11 @@ fn write_replaced_classes<'env>(
     txn: &DbTransaction<'env, RW>,
     block_number: BlockNumber,
     deployed_contracts_table: &'env DeployedContractsTable<'env>,
+    latest_class_hashes_table: &'env LatestClassHashesTable<'env>,
 ) -> StorageResult<()> {
     for (contract_address, class_hash) in replaced_classes {
         deployed_contracts_table.insert(txn, &(*contract_address, block_number), class_hash)?;
+        latest_class_hashes_table.upsert(txn, contract_address, class_hash)?;
     }
     Ok(())
 }
@@ -728,10 +876,12 @@ fn write_storage_diffs<'env>(
     txn: &DbTransaction<'env, RW>,
     block_number: BlockNumber,
     storage_table: &'env ContractStorageTable<'env>,
+    latest_storage_table: &'env LatestContractStorageTable<'env>,
 ) -> StorageResult<()> {
     for (address, storage_entries) in storage_diffs {
         for (key, value) in storage_entries {
             storage_table.upsert(txn, &(*address, *key, block_number), value)?;
+            latest_storage_table.upsert(txn, &(*address, *key), value)?;
         }
     }
     Ok(())
@@ -875,3 +1025,146 @@ fn delete_replaced_classes<'env>(
     }
     Ok(())
 }
+
+// Restores the class hashes of the contracts of the reverted block in the latest state, after
+// their history in the block was deleted.
+fn revert_latest_class_hashes<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    deployed_contracts_table: &'env DeployedContractsTable<'env>,
+    latest_class_hashes_table: &'env LatestClassHashesTable<'env>,
+) -> StorageResult<()> {
+    for contract_address in
+        thin_state_diff.deployed_contracts.keys().chain(thin_state_diff.replaced_classes.keys())
+    {
+        match get_class_hash_before_block(
+            txn,
+            deployed_contracts_table,
+            contract_address,
+            block_number,
+        )? {
+            Some(class_hash) => {
+                latest_class_hashes_table.upsert(txn, contract_address, &class_hash)?
+            }
+            None => latest_class_hashes_table.delete(txn, contract_address)?,
+        }
+    }
+    Ok(())
+}
+
+// Restores the nonces of the contracts of the reverted block in the latest state, after their
+// history in the block was deleted.
+fn revert_latest_nonces<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    nonces_table: &'env NoncesTable<'env>,
+    latest_nonces_table: &'env LatestNoncesTable<'env>,
+) -> StorageResult<()> {
+    for contract_address in
+        thin_state_diff.deployed_contracts.keys().chain(thin_state_diff.nonces.keys())
+    {
+        match get_nonce_before_block(txn, nonces_table, contract_address, block_number)? {
+            Some(nonce) => latest_nonces_table.upsert(txn, contract_address, &nonce)?,
+            None => latest_nonces_table.delete(txn, contract_address)?,
+        }
+    }
+    Ok(())
+}
+
+// Restores the storage values of the reverted block in the latest state, after their history in
+// the block was deleted.
+fn revert_latest_storage<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    storage_table: &'env ContractStorageTable<'env>,
+    latest_storage_table: &'env LatestContractStorageTable<'env>,
+) -> StorageResult<()> {
+    for (address, storage_entries) in &thin_state_diff.storage_diffs {
+        for key in storage_entries.keys() {
+            match get_storage_before_block(txn, storage_table, address, key, block_number)? {
+                Some(value) => latest_storage_table.upsert(txn, &(*address, *key), &value)?,
+                None => latest_storage_table.delete(txn, &(*address, *key))?,
+            }
+        }
+    }
+    Ok(())
+}
+
+// Returns the class hash of the contract right before the given block, by the history of the
+// deployed contracts and replaced classes.
+fn get_class_hash_before_block<'env, Mode: TransactionKind>(
+    txn: &'env DbTransaction<'env, Mode>,
+    deployed_contracts_table: &'env DeployedContractsTable<'env>,
+    address: &ContractAddress,
+    block_number: BlockNumber,
+) -> StorageResult<Option<ClassHash>> {
+    let db_key = (*address, block_number);
+    let mut cursor = deployed_contracts_table.cursor(txn)?;
+    cursor.lower_bound(&db_key)?;
+    let res = cursor.prev()?;
+
+    match res {
+        None => Ok(None),
+        Some(((got_address, _), _)) if got_address != *address => Ok(None),
+        Some((_, class_hash)) => Ok(Some(class_hash)),
+    }
+}
+
+// Returns the nonce of the contract right before the given block, by the history of the nonces.
+fn get_nonce_before_block<'env, Mode: TransactionKind>(
+    txn: &'env DbTransaction<'env, Mode>,
+    nonces_table: &'env NoncesTable<'env>,
+    address: &ContractAddress,
+    block_number: BlockNumber,
+) -> StorageResult<Option<Nonce>> {
+    // The relevant update is the last update strictly before `block_number`.
+    let db_key = (*address, block_number);
+    // Find the previous db item.
+    let mut cursor = nonces_table.cursor(txn)?;
+    cursor.lower_bound(&db_key)?;
+    let res = cursor.prev()?;
+    match res {
+        None => Ok(None),
+        Some(((got_address, _got_block_number), value)) => {
+            if got_address != *address {
+                // The previous item belongs to different address, which means there is no
+                // previous state diff for this item.
+                return Ok(None);
+            };
+            // The previous db item indeed belongs to this address and key.
+            Ok(Some(value))
+        }
+    }
+}
+
+// Returns the storage value right before the given block, by the history of the storage diffs. If
+// the key wasn't written before the block, returns `None`.
+fn get_storage_before_block<'env, Mode: TransactionKind>(
+    txn: &'env DbTransaction<'env, Mode>,
+    storage_table: &'env ContractStorageTable<'env>,
+    address: &ContractAddress,
+    key: &StorageKey,
+    block_number: BlockNumber,
+) -> StorageResult<Option<StarkFelt>> {
+    // The relevant update is the last update strictly before `block_number`.
+    let db_key = (*address, *key, block_number);
+    // Find the previous db item.
+    let mut cursor = storage_table.cursor(txn)?;
+    cursor.lower_bound(&db_key)?;
+    let res = cursor.prev()?;
+    match res {
+        None => Ok(None),
+        Some(((got_address, got_key, _got_block_number), value)) => {
+            if got_address != *address || got_key != *key {
+                // The previous item belongs to different key, which means there is no
+                // previous state diff for this item.
+                return Ok(None);
+            };
+            // The previous db item indeed belongs to this address and key.
+            Ok(Some(value))
+        }
+    }
+}
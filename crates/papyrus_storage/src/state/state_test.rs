@@ -1,6 +1,7 @@
 use assert_matches::assert_matches;
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 use indexmap::{indexmap, IndexMap};
+use papyrus_common::pending_classes::ApiContractClass;
 use pretty_assertions::assert_eq;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
@@ -59,11 +60,17 @@ fn append_state_diff_declared_classes() {
     assert!(statetxn.get_deprecated_class_definition_at(state0, &dc0).unwrap().is_none());
     assert!(statetxn.get_deprecated_class_definition_at(state1, &dc0).unwrap().is_some());
     assert!(statetxn.get_deprecated_class_definition_at(state2, &dc0).unwrap().is_some());
+    assert_eq!(
+        statetxn.get_deprecated_class_definition_block_number(&dc0).unwrap(),
+        Some(BlockNumber(0))
+    );
 
     // Class1.
     assert!(statetxn.get_deprecated_class_definition_at(state0, &dc1).unwrap().is_none());
     assert!(statetxn.get_deprecated_class_definition_at(state1, &dc1).unwrap().is_some());
     assert!(statetxn.get_deprecated_class_definition_at(state2, &dc1).unwrap().is_some());
+    // A new class isn't a deprecated class.
+    assert_eq!(statetxn.get_deprecated_class_definition_block_number(&nc0).unwrap(), None);
 
     // New Classes Test
     drop(txn);
@@ -83,6 +90,59 @@ fn append_state_diff_declared_classes() {
     assert_eq!(statetxn.get_class_definition_block_number(&nc1).unwrap(), Some(BlockNumber(1)));
 }
 
+#[test]
+fn get_class_at_and_compiled_class_hash() {
+    let deprecated_class_hash = ClassHash(stark_felt!("0x00"));
+    let deprecated_class = DeprecatedContractClass::default();
+    let class_hash = ClassHash(stark_felt!("0x10"));
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x20"));
+    let class = ContractClass::default();
+    let diff0 = StateDiff {
+        deprecated_declared_classes: IndexMap::from([(
+            deprecated_class_hash,
+            deprecated_class.clone(),
+        )]),
+        ..Default::default()
+    };
+    let diff1 = StateDiff {
+        declared_classes: IndexMap::from([(class_hash, (compiled_class_hash, class.clone()))]),
+        ..Default::default()
+    };
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), diff0, IndexMap::new())
+        .unwrap()
+        .append_state_diff(BlockNumber(1), diff1, IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let state1 = StateNumber::right_before_block(BlockNumber(1));
+    let state2 = StateNumber::right_before_block(BlockNumber(2));
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+
+    assert_eq!(
+        state_reader.get_class_at(state1, &deprecated_class_hash).unwrap(),
+        Some(ApiContractClass::DeprecatedContractClass(deprecated_class))
+    );
+    assert_eq!(state_reader.get_class_at(state1, &class_hash).unwrap(), None);
+    assert_eq!(
+        state_reader.get_class_at(state2, &class_hash).unwrap(),
+        Some(ApiContractClass::ContractClass(class))
+    );
+
+    assert_eq!(
+        state_reader.get_compiled_class_hash(&class_hash).unwrap(),
+        Some(compiled_class_hash)
+    );
+    // Deprecated classes don't have compiled classes.
+    assert_eq!(state_reader.get_compiled_class_hash(&deprecated_class_hash).unwrap(), None);
+}
+
 #[test]
 fn append_state_diff_replaced_classes() {
     let contract_0 = ContractAddress(patricia_key!("0x00"));
@@ -488,6 +548,53 @@ fn revert_state() {
     assert!(txn.get_casm(&class2).unwrap().is_none());
 }
 
+#[test]
+fn historical_and_latest_state() {
+    let contract = ContractAddress(patricia_key!("0x1"));
+    let key = StorageKey(patricia_key!("0x1"));
+    let (class0, class1) = (ClassHash(stark_felt!("0x11")), ClassHash(stark_felt!("0x22")));
+    let (value0, value1) = (StarkFelt::from(1_u8), StarkFelt::from(2_u8));
+    let (nonce0, nonce1) = (Nonce(StarkFelt::from(1_u8)), Nonce(StarkFelt::from(2_u8)));
+    let state_diff0 = StateDiff {
+        deployed_contracts: IndexMap::from([(contract, class0)]),
+        storage_diffs: IndexMap::from([(contract, IndexMap::from([(key, value0)]))]),
+        nonces: IndexMap::from([(contract, nonce0)]),
+        ..Default::default()
+    };
+    let state_diff1 = StateDiff {
+        storage_diffs: IndexMap::from([(contract, IndexMap::from([(key, value1)]))]),
+        nonces: IndexMap::from([(contract, nonce1)]),
+        replaced_classes: IndexMap::from([(contract, class1)]),
+        ..Default::default()
+    };
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff0, IndexMap::new())
+        .unwrap()
+        .append_state_diff(BlockNumber(1), state_diff1, IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    let expected_states = [
+        (StateNumber::right_before_block(BlockNumber(0)), None, StarkFelt::default(), None),
+        (StateNumber::right_after_block(BlockNumber(0)), Some(class0), value0, Some(nonce0)),
+        // The latest state.
+        (StateNumber::right_after_block(BlockNumber(1)), Some(class1), value1, Some(nonce1)),
+        (StateNumber::right_after_block(BlockNumber(5)), Some(class1), value1, Some(nonce1)),
+    ];
+    for (state_number, class_hash, value, nonce) in expected_states {
+        assert_eq!(state_reader.get_class_hash_at(state_number, &contract).unwrap(), class_hash);
+        assert_eq!(state_reader.get_storage_at(state_number, &contract, &key).unwrap(), value);
+        assert_eq!(state_reader.get_nonce_at(state_number, &contract).unwrap(), nonce);
+    }
+}
+
 #[test]
 fn get_nonce_key_serialization() {
     let ((reader, mut writer), _temp_dir) = get_test_storage();
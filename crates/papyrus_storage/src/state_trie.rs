@@ -0,0 +1,581 @@
+//! Interface for handling the Patricia-Merkle tries that commit to the Starknet state.
+//!
+//! The global state root of a block commits to two tries of height 251:
+//! * The contracts trie, which maps each contract address to the hash of the contract's class hash,
+//!   nonce and storage trie root. The storage trie of a contract maps its storage keys to their
+//!   values. The contracts trie and the storage tries are hashed with Pedersen.
+//! * The classes trie, which maps the hash of each Cairo 1 class to its compiled class hash. It's
+//!   hashed with Poseidon.
+//!
+//! The tries are updated with the state diff of each block once it's stored, starting from the
+//! first block. The nodes of the tries are stored by their hashes and the roots are stored per
+//! block, so the tries of any block since the first one can be read. Reverting a block deletes its
+//! roots and keeps its nodes. The nodes are never pruned, since they are shared by the tries of
+//! many blocks, so the tries take a significant part of the storage.
+//!
+//! Import [`StateTrieStorageReader`] and [`StateTrieStorageWriter`] to read and write data related
+//! to the tries using a [`StorageTxn`].
+#[cfg(test)]
+#[path = "state_trie_test.rs"]
+mod state_trie_test;
+
+use std::collections::BTreeSet;
+
+use indexmap::IndexMap;
+use papyrus_proc_macros::latency_histogram;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, GlobalRoot, Nonce};
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_api::state::{StateNumber, ThinStateDiff};
+use starknet_crypto::{poseidon_hash, poseidon_hash_many, FieldElement};
+
+use crate::db::serialization::NoVersionValueWrapper;
+use crate::db::{DbTransaction, TableHandle, TransactionKind, RW};
+use crate::state::StateStorageReader;
+use crate::{MarkerKind, MarkersTable, StorageError, StorageResult, StorageTxn};
+
+type StateTrieNodesTable<'env> = TableHandle<'env, StarkHash, NoVersionValueWrapper<TrieNode>>;
+type StateTrieRootsTable<'env> =
+    TableHandle<'env, BlockNumber, NoVersionValueWrapper<StateTrieRoots>>;
+type ContractStorageTrieRootsTable<'env> =
+    TableHandle<'env, (ContractAddress, BlockNumber), NoVersionValueWrapper<StarkHash>>;
+
+const TRIE_HEIGHT: u8 = 251;
+// The keys of the tries are 251 bits long, so the most significant bits of their 256 bits
+// representation are skipped.
+const SKIPPED_KEY_BITS: usize = 256 - TRIE_HEIGHT as usize;
+
+/// A node of a state trie with two children.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BinaryNode {
+    pub left: StarkHash,
+    pub right: StarkHash,
+}
+
+/// A node of a state trie with a single child. The path from the node to its child is given by the
+/// `length` least significant bits of `path`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeNode {
+    pub path: StarkFelt,
+    pub length: u8,
+    pub child: StarkHash,
+}
+
+/// A node of a state trie. The leaves aren't nodes, since the hash of a leaf is its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrieNode {
+    Binary(BinaryNode),
+    Edge(EdgeNode),
+}
+
+/// The roots of the state tries after a block. The root of an empty trie is zero.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StateTrieRoots {
+    pub contracts_trie_root: StarkHash,
+    pub classes_trie_root: StarkHash,
+}
+
+impl StateTrieRoots {
+    /// Returns the global state root the tries commit to.
+    pub fn global_root(&self) -> GlobalRoot {
+        // The global root of the blocks before the first Cairo 1 class was declared is the root
+        // of the contracts trie.
+        if self.classes_trie_root == StarkHash::default() {
+            return GlobalRoot(self.contracts_trie_root);
+        }
+        GlobalRoot(
+            poseidon_hash_many(&[
+                FieldElement::from(ascii_as_felt("STARKNET_STATE_V0")),
+                FieldElement::from(self.contracts_trie_root),
+                FieldElement::from(self.classes_trie_root),
+            ])
+            .into(),
+        )
+    }
+}
+
+/// Interface for reading data related to the state tries.
+pub trait StateTrieStorageReader {
+    /// The state trie marker is the first block whose state diff wasn't applied to the tries yet.
+    fn get_state_trie_marker(&self) -> StorageResult<BlockNumber>;
+
+    /// Returns the roots of the state tries after the given block.
+    fn get_state_trie_roots(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<StateTrieRoots>>;
+
+    /// Returns the root of the storage trie of a contract after the given block, which must be
+    /// below the state trie marker. The root is zero if the contract has no storage.
+    fn get_contract_storage_trie_root(
+        &self,
+        block_number: BlockNumber,
+        contract_address: &ContractAddress,
+    ) -> StorageResult<StarkHash>;
+
+    /// Returns the nodes on the paths from the root of a trie to the given keys, mapped by their
+    /// hashes. The path to a key that isn't in the trie ends at the node where the key's path
+    /// leaves the trie.
+    fn get_state_trie_nodes(
+        &self,
+        root: StarkHash,
+        keys: &[StarkFelt],
+    ) -> StorageResult<IndexMap<StarkHash, TrieNode>>;
+}
+
+/// Interface for writing data related to the state tries.
+pub trait StateTrieStorageWriter
+where
+    Self: Sized,
+{
+    /// Applies the stored state diff of the given block to the tries, and advances the state trie
+    /// marker. The block must be the state trie marker.
+    // To enforce that no commit happen after a failure, we consume and return Self on success.
+    fn update_state_tries(self, block_number: BlockNumber) -> StorageResult<Self>;
+}
+
+impl<'env, Mode: TransactionKind> StateTrieStorageReader for StorageTxn<'env, Mode> {
+    fn get_state_trie_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::StateTrie)?.unwrap_or_default())
+    }
+
+    fn get_state_trie_roots(
+        &self,
+        block_number: BlockNumber,
+    ) -> StorageResult<Option<StateTrieRoots>> {
+        let roots_table = self.open_table(&self.tables.state_trie_roots)?;
+        Ok(roots_table.get(&self.txn, &block_number)?)
+    }
+
+    fn get_contract_storage_trie_root(
+        &self,
+        block_number: BlockNumber,
+        contract_address: &ContractAddress,
+    ) -> StorageResult<StarkHash> {
+        let storage_roots_table = self.open_table(&self.tables.contract_storage_trie_roots)?;
+        get_storage_trie_root(
+            &self.txn,
+            &storage_roots_table,
+            StateNumber::right_after_block(block_number),
+            contract_address,
+        )
+    }
+
+    fn get_state_trie_nodes(
+        &self,
+        root: StarkHash,
+        keys: &[StarkFelt],
+    ) -> StorageResult<IndexMap<StarkHash, TrieNode>> {
+        let nodes_table = self.open_table(&self.tables.state_trie_nodes)?;
+        let mut nodes = IndexMap::new();
+        for key in keys {
+            let key = TrieKey::new(key);
+            let mut hash = root;
+            let mut height = 0;
+            while height < TRIE_HEIGHT && hash != StarkHash::default() {
+                let node = read_node(&self.txn, &nodes_table, hash)?;
+                nodes.insert(hash, node);
+                match node {
+                    TrieNode::Binary(BinaryNode { left, right }) => {
+                        hash = if key.bit(height) { right } else { left };
+                        height += 1;
+                    }
+                    TrieNode::Edge(EdgeNode { path, length, child }) => {
+                        if key.path(height, length) != path {
+                            break;
+                        }
+                        hash = child;
+                        height += length;
+                    }
+                }
+            }
+        }
+        Ok(nodes)
+    }
+}
+
+impl<'env> StateTrieStorageWriter for StorageTxn<'env, RW> {
+    #[latency_histogram("storage_update_state_tries_latency_seconds")]
+    fn update_state_tries(self, block_number: BlockNumber) -> StorageResult<Self> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        let nodes_table = self.open_table(&self.tables.state_trie_nodes)?;
+        let roots_table = self.open_table(&self.tables.state_trie_roots)?;
+        let storage_roots_table = self.open_table(&self.tables.contract_storage_trie_roots)?;
+
+        let state_trie_marker = self.get_state_trie_marker()?;
+        if state_trie_marker != block_number {
+            return Err(StorageError::MarkerMismatch {
+                expected: state_trie_marker,
+                found: block_number,
+            });
+        }
+        let missing_data = |data: &str| StorageError::DBInconsistency {
+            msg: format!("Missing {data} of block {block_number}."),
+        };
+        let state_diff =
+            self.get_state_diff(block_number)?.ok_or_else(|| missing_data("state diff"))?;
+        let previous_roots = match block_number.prev() {
+            None => StateTrieRoots::default(),
+            Some(previous_block) => roots_table
+                .get(&self.txn, &previous_block)?
+                .ok_or_else(|| missing_data("previous state trie roots"))?,
+        };
+        let pedersen_trie =
+            TrieUpdater { txn: &self.txn, nodes_table: &nodes_table, hash: pedersen_hash };
+        let poseidon_trie =
+            TrieUpdater { txn: &self.txn, nodes_table: &nodes_table, hash: poseidon };
+
+        // The storage tries, before the contracts trie whose leaves depend on them.
+        let mut storage_roots = IndexMap::new();
+        for (contract_address, storage_diffs) in &state_diff.storage_diffs {
+            let previous_root = get_storage_trie_root(
+                &self.txn,
+                &storage_roots_table,
+                StateNumber::right_before_block(block_number),
+                contract_address,
+            )?;
+            let updates = storage_diffs
+                .iter()
+                .map(|(storage_key, value)| (TrieKey::new(storage_key.0.key()), *value))
+                .collect();
+            let root = pedersen_trie.update(previous_root, updates)?;
+            storage_roots_table.upsert(&self.txn, &(*contract_address, block_number), &root)?;
+            storage_roots.insert(*contract_address, root);
+        }
+
+        let state_reader = self.get_state_reader()?;
+        let state_number = StateNumber::right_after_block(block_number);
+        let mut contract_updates = Vec::new();
+        for contract_address in changed_contracts(&state_diff) {
+            let class_hash = state_reader
+                .get_class_hash_at(state_number, &contract_address)?
+                .unwrap_or_default();
+            let nonce =
+                state_reader.get_nonce_at(state_number, &contract_address)?.unwrap_or_default();
+            let storage_root = match storage_roots.get(&contract_address) {
+                Some(root) => *root,
+                None => get_storage_trie_root(
+                    &self.txn,
+                    &storage_roots_table,
+                    state_number,
+                    &contract_address,
+                )?,
+            };
+            contract_updates.push((
+                TrieKey::new(contract_address.0.key()),
+                contract_state_hash(class_hash, storage_root, nonce),
+            ));
+        }
+        let contracts_trie_root =
+            pedersen_trie.update(previous_roots.contracts_trie_root, contract_updates)?;
+
+        let class_updates = state_diff
+            .declared_classes
+            .iter()
+            .map(|(class_hash, compiled_class_hash)| {
+                (TrieKey::new(&class_hash.0), class_leaf(compiled_class_hash))
+            })
+            .collect();
+        let classes_trie_root =
+            poseidon_trie.update(previous_roots.classes_trie_root, class_updates)?;
+
+        roots_table.insert(
+            &self.txn,
+            &block_number,
+            &StateTrieRoots { contracts_trie_root, classes_trie_root },
+        )?;
+        markers_table.upsert(&self.txn, &MarkerKind::StateTrie, &block_number.next())?;
+        Ok(self)
+    }
+}
+
+// Deletes the roots of the tries after the block whose state diff is reverted, if the state diff
+// was applied to the tries.
+pub(crate) fn revert_state_trie_roots<'env>(
+    txn: &DbTransaction<'env, RW>,
+    markers_table: &'env MarkersTable<'env>,
+    roots_table: &'env StateTrieRootsTable<'env>,
+    storage_roots_table: &'env ContractStorageTrieRootsTable<'env>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+) -> StorageResult<()> {
+    let state_trie_marker = markers_table.get(txn, &MarkerKind::StateTrie)?.unwrap_or_default();
+    if state_trie_marker != block_number.next() {
+        return Ok(());
+    }
+    markers_table.upsert(txn, &MarkerKind::StateTrie, &block_number)?;
+    roots_table.delete(txn, &block_number)?;
+    for contract_address in thin_state_diff.storage_diffs.keys() {
+        storage_roots_table.delete(txn, &(*contract_address, block_number))?;
+    }
+    Ok(())
+}
+
+// The contracts whose leaves in the contracts trie the state diff changes.
+fn changed_contracts(state_diff: &ThinStateDiff) -> BTreeSet<ContractAddress> {
+    state_diff
+        .deployed_contracts
+        .keys()
+        .chain(state_diff.storage_diffs.keys())
+        .chain(state_diff.nonces.keys())
+        .chain(state_diff.replaced_classes.keys())
+        .copied()
+        .collect()
+}
+
+fn get_storage_trie_root<'env, Mode: TransactionKind>(
+    txn: &DbTransaction<'env, Mode>,
+    storage_roots_table: &ContractStorageTrieRootsTable<'env>,
+    state_number: StateNumber,
+    contract_address: &ContractAddress,
+) -> StorageResult<StarkHash> {
+    // The root was last updated by the last block before the first irrelevant block that changed
+    // the contract's storage.
+    let mut cursor = storage_roots_table.cursor(txn)?;
+    cursor.lower_bound(&(*contract_address, state_number.block_after()))?;
+    match cursor.prev()? {
+        Some(((got_address, _), root)) if got_address == *contract_address => Ok(root),
+        _ => Ok(StarkHash::default()),
+    }
+}
+
+fn read_node<'env, Mode: TransactionKind>(
+    txn: &DbTransaction<'env, Mode>,
+    nodes_table: &StateTrieNodesTable<'env>,
+    hash: StarkHash,
+) -> StorageResult<TrieNode> {
+    nodes_table.get(txn, &hash)?.ok_or_else(|| StorageError::DBInconsistency {
+        msg: format!("Missing state trie node {hash}."),
+    })
+}
+
+fn contract_state_hash(class_hash: ClassHash, storage_root: StarkHash, nonce: Nonce) -> StarkHash {
+    // The last element is the version of the contract state hash.
+    let hash = pedersen_hash(&pedersen_hash(&class_hash.0, &storage_root), &nonce.0);
+    pedersen_hash(&hash, &StarkFelt::default())
+}
+
+fn class_leaf(compiled_class_hash: &CompiledClassHash) -> StarkHash {
+    poseidon(&ascii_as_felt("CONTRACT_CLASS_LEAF_V0"), &compiled_class_hash.0)
+}
+
+fn poseidon(left: &StarkFelt, right: &StarkFelt) -> StarkHash {
+    poseidon_hash(FieldElement::from(*left), FieldElement::from(*right)).into()
+}
+
+fn ascii_as_felt(ascii: &str) -> StarkFelt {
+    let mut bytes = [0; 32];
+    bytes[32 - ascii.len()..].copy_from_slice(ascii.as_bytes());
+    StarkFelt::new(bytes).expect("A short ASCII string should be a valid felt.")
+}
+
+// A key of a trie, as the path from the root to its leaf. The root is at height 0 and the leaves
+// are at height 251.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct TrieKey([u8; 32]);
+
+impl TrieKey {
+    fn new(felt: &StarkFelt) -> Self {
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(felt.bytes());
+        Self(bytes)
+    }
+
+    // Returns the bit that leads from the given height to the next one.
+    fn bit(&self, height: u8) -> bool {
+        let position = SKIPPED_KEY_BITS + usize::from(height);
+        self.0[position / 8] & (0x80 >> (position % 8)) != 0
+    }
+
+    fn set_bit(&mut self, height: u8, bit: bool) {
+        let position = SKIPPED_KEY_BITS + usize::from(height);
+        let mask = 0x80 >> (position % 8);
+        if bit {
+            self.0[position / 8] |= mask;
+        } else {
+            self.0[position / 8] &= !mask;
+        }
+    }
+
+    // Returns the bits that lead from the given height to the given length below it, as the path of
+    // an edge.
+    fn path(&self, height: u8, length: u8) -> StarkFelt {
+        let mut path = Self([0; 32]);
+        for i in 0..length {
+            path.set_bit(TRIE_HEIGHT - length + i, self.bit(height + i));
+        }
+        StarkFelt::new(path.0).expect("A path of at most 251 bits should be a valid felt.")
+    }
+
+    // Sets the bits that lead from the given height to the given length below it to the path of an
+    // edge.
+    fn set_path(&mut self, height: u8, path: &StarkFelt, length: u8) {
+        let path = Self::new(path);
+        for i in 0..length {
+            self.set_bit(height + i, path.bit(TRIE_HEIGHT - length + i));
+        }
+    }
+}
+
+// A stored sub-trie, seen from its root.
+enum StoredSubTrie {
+    Empty,
+    Leaf(StarkFelt),
+    Binary(StarkHash, BinaryNode),
+    // The part of the edge below its first `consumed` bits.
+    Edge { node: EdgeNode, consumed: u8 },
+}
+
+// A sub-trie after an update. An edge from its root isn't stored until the sub-trie's parent is
+// known, since the parent may extend it.
+enum UpdatedSubTrie {
+    Empty,
+    // A leaf or a stored binary node.
+    Hash(StarkHash),
+    // An edge of the given length from the sub-trie's root, along the path of the key.
+    Edge { key: TrieKey, length: u8, child: StarkHash },
+}
+
+// Applies updates to a trie, and stores the nodes of the updated trie.
+struct TrieUpdater<'a, 'env> {
+    txn: &'a DbTransaction<'env, RW>,
+    nodes_table: &'a StateTrieNodesTable<'env>,
+    hash: fn(&StarkFelt, &StarkFelt) -> StarkHash,
+}
+
+impl<'a, 'env> TrieUpdater<'a, 'env> {
+    // Sets the values of the keys in the trie with the given root, and returns the root of the
+    // updated trie. A key whose value is set to zero is removed from the trie. Each key may appear
+    // only once.
+    fn update(
+        &self,
+        root: StarkHash,
+        mut updates: Vec<(TrieKey, StarkFelt)>,
+    ) -> StorageResult<StarkHash> {
+        updates.sort_unstable_by_key(|(key, _)| *key);
+        let root = self.read(root, 0)?;
+        let updated_root = self.update_sub_trie(root, 0, TrieKey([0; 32]), &updates)?;
+        self.store(updated_root, 0)
+    }
+
+    fn read(&self, hash: StarkHash, height: u8) -> StorageResult<StoredSubTrie> {
+        if hash == StarkHash::default() {
+            return Ok(StoredSubTrie::Empty);
+        }
+        if height == TRIE_HEIGHT {
+            return Ok(StoredSubTrie::Leaf(hash));
+        }
+        Ok(match read_node(self.txn, self.nodes_table, hash)? {
+            TrieNode::Binary(node) => StoredSubTrie::Binary(hash, node),
+            TrieNode::Edge(node) => StoredSubTrie::Edge { node, consumed: 0 },
+        })
+    }
+
+    // The prefix is a key whose bits above the height lead to the sub-trie. The updates are sorted
+    // and their keys lead to the sub-trie.
+    fn update_sub_trie(
+        &self,
+        sub_trie: StoredSubTrie,
+        height: u8,
+        prefix: TrieKey,
+        updates: &[(TrieKey, StarkFelt)],
+    ) -> StorageResult<UpdatedSubTrie> {
+        if updates.is_empty() {
+            return Ok(unchanged(sub_trie, height, prefix));
+        }
+        if height == TRIE_HEIGHT {
+            let (_, value) = updates[0];
+            if value == StarkFelt::default() {
+                return Ok(UpdatedSubTrie::Empty);
+            }
+            return Ok(UpdatedSubTrie::Hash(value));
+        }
+
+        let (left, right) = match sub_trie {
+            StoredSubTrie::Empty => (StoredSubTrie::Empty, StoredSubTrie::Empty),
+            StoredSubTrie::Leaf(_) => unreachable!("Leaves are only at the bottom of the trie."),
+            StoredSubTrie::Binary(_, BinaryNode { left, right }) => {
+                (self.read(left, height + 1)?, self.read(right, height + 1)?)
+            }
+            StoredSubTrie::Edge { node, consumed } => {
+                let bit = TrieKey::new(&node.path).bit(TRIE_HEIGHT - node.length + consumed);
+                let below = if consumed + 1 == node.length {
+                    self.read(node.child, height + 1)?
+                } else {
+                    StoredSubTrie::Edge { node, consumed: consumed + 1 }
+                };
+                if bit {
+                    (StoredSubTrie::Empty, below)
+                } else {
+                    (below, StoredSubTrie::Empty)
+                }
+            }
+        };
+        let mut left_prefix = prefix;
+        left_prefix.set_bit(height, false);
+        let mut right_prefix = prefix;
+        right_prefix.set_bit(height, true);
+        let split = updates.partition_point(|(key, _)| !key.bit(height));
+        let left = self.update_sub_trie(left, height + 1, left_prefix, &updates[..split])?;
+        let right = self.update_sub_trie(right, height + 1, right_prefix, &updates[split..])?;
+
+        match (left, right) {
+            (UpdatedSubTrie::Empty, UpdatedSubTrie::Empty) => Ok(UpdatedSubTrie::Empty),
+            (child, UpdatedSubTrie::Empty) => Ok(extend_edge(child, left_prefix)),
+            (UpdatedSubTrie::Empty, child) => Ok(extend_edge(child, right_prefix)),
+            (left, right) => {
+                let node = BinaryNode {
+                    left: self.store(left, height + 1)?,
+                    right: self.store(right, height + 1)?,
+                };
+                let hash = (self.hash)(&node.left, &node.right);
+                self.nodes_table.upsert(self.txn, &hash, &TrieNode::Binary(node))?;
+                Ok(UpdatedSubTrie::Hash(hash))
+            }
+        }
+    }
+
+    // Stores the edge from the root of the sub-trie, if there is one, and returns the hash of the
+    // sub-trie.
+    fn store(&self, sub_trie: UpdatedSubTrie, height: u8) -> StorageResult<StarkHash> {
+        match sub_trie {
+            UpdatedSubTrie::Empty => Ok(StarkHash::default()),
+            UpdatedSubTrie::Hash(hash) => Ok(hash),
+            UpdatedSubTrie::Edge { key, length, child } => {
+                let node = EdgeNode { path: key.path(height, length), length, child };
+                let child_and_path_hash = (self.hash)(&node.child, &node.path);
+                let hash = StarkFelt::from(
+                    FieldElement::from(child_and_path_hash) + FieldElement::from(node.length),
+                );
+                self.nodes_table.upsert(self.txn, &hash, &TrieNode::Edge(node))?;
+                Ok(hash)
+            }
+        }
+    }
+}
+
+fn unchanged(sub_trie: StoredSubTrie, height: u8, prefix: TrieKey) -> UpdatedSubTrie {
+    match sub_trie {
+        StoredSubTrie::Empty => UpdatedSubTrie::Empty,
+        StoredSubTrie::Leaf(hash) | StoredSubTrie::Binary(hash, _) => UpdatedSubTrie::Hash(hash),
+        StoredSubTrie::Edge { node, consumed } => {
+            let length = node.length - consumed;
+            let mut key = prefix;
+            key.set_path(height, &node.path, length);
+            UpdatedSubTrie::Edge { key, length, child: node.child }
+        }
+    }
+}
+
+// Returns the sub-trie whose root has the given sub-trie as its only child. The prefix leads to the
+// child.
+fn extend_edge(child: UpdatedSubTrie, prefix: TrieKey) -> UpdatedSubTrie {
+    match child {
+        UpdatedSubTrie::Empty => UpdatedSubTrie::Empty,
+        UpdatedSubTrie::Hash(hash) => UpdatedSubTrie::Edge { key: prefix, length: 1, child: hash },
+        UpdatedSubTrie::Edge { key, length, child } => {
+            UpdatedSubTrie::Edge { key, length: length + 1, child }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use assert_matches::assert_matches;
+use indexmap::{indexmap, IndexMap};
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, PatriciaKey};
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_api::state::{ContractClass, StateDiff, StorageKey};
+use starknet_api::{patricia_key, stark_felt};
+use starknet_crypto::FieldElement;
+
+use crate::state::StateStorageWriter;
+use crate::state_trie::{
+    BinaryNode,
+    EdgeNode,
+    StateTrieRoots,
+    StateTrieStorageReader,
+    StateTrieStorageWriter,
+    TrieKey,
+    TrieNode,
+    TRIE_HEIGHT,
+};
+use crate::test_utils::get_test_storage;
+use crate::{StorageError, StorageWriter};
+
+fn storage_diff(contract_address: ContractAddress, entries: &[(u8, u8)]) -> StateDiff {
+    let storage_entries = entries
+        .iter()
+        .map(|(key, value)| (StorageKey::from(u128::from(*key)), StarkFelt::from(*value)))
+        .collect();
+    StateDiff {
+        deployed_contracts: indexmap! { contract_address => ClassHash(stark_felt!("0x1")) },
+        storage_diffs: indexmap! { contract_address => storage_entries },
+        ..Default::default()
+    }
+}
+
+// Appends the state diffs from the given block and applies them to the tries, and returns the
+// roots of the tries after the last one.
+fn append_state_diffs(
+    writer: &mut StorageWriter,
+    first_block_number: BlockNumber,
+    state_diffs: Vec<StateDiff>,
+) -> StateTrieRoots {
+    let mut block_number = first_block_number;
+    for state_diff in state_diffs {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(block_number, state_diff, IndexMap::new())
+            .unwrap()
+            .update_state_tries(block_number)
+            .unwrap()
+            .commit()
+            .unwrap();
+        block_number = block_number.next();
+    }
+    let last_block_number = block_number.prev().unwrap();
+    writer.begin_rw_txn().unwrap().get_state_trie_roots(last_block_number).unwrap().unwrap()
+}
+
+#[test]
+fn roots_do_not_depend_on_update_batches() {
+    let address = ContractAddress(patricia_key!("0x11"));
+    let ((_, mut one_block_writer), _temp_dir) = get_test_storage();
+    let one_block_roots = append_state_diffs(
+        &mut one_block_writer,
+        BlockNumber(0),
+        vec![storage_diff(address, &[(1, 1), (2, 2), (200, 3), (201, 4)])],
+    );
+    let ((_, mut two_blocks_writer), _temp_dir) = get_test_storage();
+    let two_blocks_roots = append_state_diffs(
+        &mut two_blocks_writer,
+        BlockNumber(0),
+        vec![
+            storage_diff(address, &[(201, 4), (2, 2)]),
+            StateDiff {
+                storage_diffs: indexmap! {
+                    address => indexmap! {
+                        StorageKey::from(1_u128) => StarkFelt::from(1_u8),
+                        StorageKey::from(200_u128) => StarkFelt::from(3_u8),
+                    },
+                },
+                ..Default::default()
+            },
+        ],
+    );
+
+    assert_ne!(one_block_roots.contracts_trie_root, StarkHash::default());
+    assert_eq!(one_block_roots, two_blocks_roots);
+    assert_eq!(
+        one_block_writer
+            .begin_rw_txn()
+            .unwrap()
+            .get_contract_storage_trie_root(BlockNumber(0), &address)
+            .unwrap(),
+        two_blocks_writer
+            .begin_rw_txn()
+            .unwrap()
+            .get_contract_storage_trie_root(BlockNumber(1), &address)
+            .unwrap()
+    );
+}
+
+#[test]
+fn deleting_keys_restores_roots() {
+    let address = ContractAddress(patricia_key!("0x11"));
+    let ((_, mut writer), _temp_dir) = get_test_storage();
+    let roots = append_state_diffs(
+        &mut writer,
+        BlockNumber(0),
+        vec![storage_diff(address, &[(1, 1), (2, 2)])],
+    );
+
+    let roots_after_deletion = append_state_diffs(
+        &mut writer,
+        BlockNumber(1),
+        vec![
+            StateDiff {
+                storage_diffs: indexmap! {
+                    address => indexmap! { StorageKey::from(3_u128) => StarkFelt::from(3_u8) },
+                },
+                ..Default::default()
+            },
+            StateDiff {
+                storage_diffs: indexmap! {
+                    address => indexmap! { StorageKey::from(3_u128) => StarkFelt::default() },
+                },
+                ..Default::default()
+            },
+        ],
+    );
+    assert_eq!(roots, roots_after_deletion);
+}
+
+#[test]
+fn classes_trie_and_global_root() {
+    let ((_, mut writer), _temp_dir) = get_test_storage();
+    let roots = append_state_diffs(
+        &mut writer,
+        BlockNumber(0),
+        vec![storage_diff(ContractAddress(patricia_key!("0x11")), &[(1, 1)])],
+    );
+    assert_eq!(roots.classes_trie_root, StarkHash::default());
+    assert_eq!(roots.global_root().0, roots.contracts_trie_root);
+
+    let roots = append_state_diffs(
+        &mut writer,
+        BlockNumber(1),
+        vec![StateDiff {
+            declared_classes: indexmap! {
+                ClassHash(stark_felt!("0x2")) =>
+                    (CompiledClassHash(stark_felt!("0x3")), ContractClass::default()),
+            },
+            ..Default::default()
+        }],
+    );
+    assert_ne!(roots.classes_trie_root, StarkHash::default());
+    assert_ne!(roots.global_root().0, roots.contracts_trie_root);
+}
+
+#[test]
+fn revert_state_tries() {
+    let address = ContractAddress(patricia_key!("0x11"));
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_state_diffs(&mut writer, BlockNumber(0), vec![storage_diff(address, &[(1, 1)])]);
+    let roots = append_state_diffs(
+        &mut writer,
+        BlockNumber(1),
+        vec![storage_diff(ContractAddress(patricia_key!("0x12")), &[(1, 1)])],
+    );
+
+    // Only the state diffs that were applied to the tries revert them.
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(2), StateDiff::default(), IndexMap::new())
+        .unwrap()
+        .commit()
+        .unwrap();
+    writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(2)).unwrap().0.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_trie_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_state_trie_roots(BlockNumber(1)).unwrap(), Some(roots));
+    drop(txn);
+
+    writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(1)).unwrap().0.commit().unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_trie_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_trie_roots(BlockNumber(1)).unwrap(), None);
+    assert_eq!(
+        txn.get_contract_storage_trie_root(BlockNumber(1), &ContractAddress(patricia_key!("0x12")))
+            .unwrap(),
+        StarkHash::default()
+    );
+    drop(txn);
+
+    // The tries can be updated with the state diff that replaces the reverted one.
+    let replacing_roots = append_state_diffs(
+        &mut writer,
+        BlockNumber(1),
+        vec![storage_diff(ContractAddress(patricia_key!("0x12")), &[(1, 1)])],
+    );
+    assert_eq!(replacing_roots, roots);
+}
+
+#[test]
+fn update_state_tries_marker_mismatch() {
+    let ((_, mut writer), _temp_dir) = get_test_storage();
+    let txn = writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), StateDiff::default(), IndexMap::new())
+        .unwrap();
+    assert_matches!(
+        txn.update_state_tries(BlockNumber(1)).err(),
+        Some(StorageError::MarkerMismatch { expected, found })
+            if expected == BlockNumber(0) && found == BlockNumber(1)
+    );
+}
+
+#[test]
+fn proof_nodes_lead_from_root_to_keys() {
+    let address = ContractAddress(patricia_key!("0x11"));
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_state_diffs(
+        &mut writer,
+        BlockNumber(0),
+        vec![storage_diff(address, &[(1, 1), (2, 2), (3, 3), (200, 4)])],
+    );
+    let txn = reader.begin_ro_txn().unwrap();
+    let root = txn.get_contract_storage_trie_root(BlockNumber(0), &address).unwrap();
+
+    for (key, expected_leaf) in [(2_u8, StarkFelt::from(2_u8)), (4, StarkFelt::default())] {
+        let key = StarkFelt::from(key);
+        let nodes = txn.get_state_trie_nodes(root, &[key]).unwrap();
+        let key = TrieKey::new(&key);
+        let mut hash = root;
+        let mut height = 0;
+        while height < TRIE_HEIGHT {
+            let Some(node) = nodes.get(&hash) else {
+                break;
+            };
+            match *node {
+                TrieNode::Binary(BinaryNode { left, right }) => {
+                    assert_eq!(pedersen_hash(&left, &right), hash);
+                    hash = if key.bit(height) { right } else { left };
+                    height += 1;
+                }
+                TrieNode::Edge(EdgeNode { path, length, child }) => {
+                    let edge_hash = FieldElement::from(pedersen_hash(&child, &path))
+                        + FieldElement::from(length);
+                    assert_eq!(StarkFelt::from(edge_hash), hash);
+                    if key.path(height, length) != path {
+                        break;
+                    }
+                    hash = child;
+                    height += length;
+                }
+            }
+        }
+        let leaf = if height == TRIE_HEIGHT { hash } else { StarkFelt::default() };
+        assert_eq!(leaf, expected_leaf);
+    }
+}
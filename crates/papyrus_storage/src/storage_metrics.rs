@@ -0,0 +1,46 @@
+//! Metrics of the read transactions of the storage, for spotting readers that hold transactions
+//! for too long.
+//!
+//! Read transactions don't block the writer, nor each other: each of them reads a snapshot of the
+//! database, and the writer never waits for them. However, the database can't reuse the pages that
+//! an open read transaction may still read, so a long read transaction makes the database file
+//! grow while the node syncs, and every open transaction takes one of the reader slots of the
+//! database.
+use std::time::{Duration, Instant};
+
+use metrics::{decrement_gauge, histogram, increment_gauge};
+use tracing::warn;
+
+// Name of the metrics.
+const OPEN_READ_TRANSACTIONS: &str = "storage_open_read_transactions";
+const READ_TRANSACTION_DURATION: &str = "storage_read_transaction_duration_seconds";
+
+// Read transactions that are open longer than this are logged.
+const LONG_READ_TRANSACTION_DURATION: Duration = Duration::from_secs(60);
+
+// Records a read transaction from its creation until it's dropped.
+pub(crate) struct ReadTransactionGuard {
+    start_time: Instant,
+}
+
+impl ReadTransactionGuard {
+    pub(crate) fn new() -> Self {
+        increment_gauge!(OPEN_READ_TRANSACTIONS, 1.0);
+        Self { start_time: Instant::now() }
+    }
+}
+
+impl Drop for ReadTransactionGuard {
+    fn drop(&mut self) {
+        let duration = self.start_time.elapsed();
+        decrement_gauge!(OPEN_READ_TRANSACTIONS, 1.0);
+        histogram!(READ_TRANSACTION_DURATION, duration.as_secs_f64());
+        if duration > LONG_READ_TRANSACTION_DURATION {
+            warn!(
+                "A storage read transaction was open for {} seconds, which prevents the storage \
+                 from reusing its free pages.",
+                duration.as_secs()
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
+use rand_chacha::ChaCha8Rng;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ContractAddress;
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::transaction::{
     EventIndexInTransactionOutput,
     ExecutionResources,
@@ -11,6 +13,7 @@ use starknet_api::transaction::{
 use test_utils::{auto_impl_get_test_instance, get_number_of_variants, GetTestInstance};
 
 use crate::body::events::{
+    EventsBloomFilter,
     ThinDeclareTransactionOutput,
     ThinDeployAccountTransactionOutput,
     ThinDeployTransactionOutput,
@@ -23,10 +26,20 @@ use crate::compression_utils::IsCompressed;
 use crate::header::StarknetVersion;
 use crate::mmap_file::LocationInFile;
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state_trie::{BinaryNode, EdgeNode, StateTrieRoots, TrieNode};
 use crate::version::Version;
 use crate::{EventIndex, MarkerKind, OffsetKind};
 
 auto_impl_get_test_instance! {
+    pub struct BinaryNode {
+        pub left: StarkHash,
+        pub right: StarkHash,
+    }
+    pub struct EdgeNode {
+        pub path: StarkFelt,
+        pub length: u8,
+        pub child: StarkHash,
+    }
     struct EventIndex(pub TransactionIndex, pub EventIndexInTransactionOutput);
     pub struct IndexedDeprecatedContractClass {
         pub block_number: BlockNumber,
@@ -41,6 +54,10 @@ auto_impl_get_test_instance! {
         Body = 1,
         State = 2,
         CompiledClass = 3,
+        BaseLayerBlock = 4,
+        L1ToL2Messages = 5,
+        StateTrie = 6,
+        PrunedBody = 7,
     }
     pub enum OffsetKind {
         ThinStateDiff = 0,
@@ -49,6 +66,10 @@ auto_impl_get_test_instance! {
         DeprecatedContractClass = 3,
     }
     pub struct StarknetVersion(pub String);
+    pub struct StateTrieRoots {
+        pub contracts_trie_root: StarkHash,
+        pub classes_trie_root: StarkHash,
+    }
     pub struct ThinDeclareTransactionOutput {
         pub actual_fee: Fee,
         pub messages_sent: Vec<MessageToL1>,
@@ -94,5 +115,15 @@ auto_impl_get_test_instance! {
         L1Handler(ThinL1HandlerTransactionOutput) = 4,
     }
     struct TransactionIndex(pub BlockNumber, pub TransactionOffsetInBlock);
+    pub enum TrieNode {
+        Binary(BinaryNode) = 0,
+        Edge(EdgeNode) = 1,
+    }
     pub struct Version(pub u32);
 }
+
+impl GetTestInstance for EventsBloomFilter {
+    fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
+        Self(Vec::get_test_instance(rng))
+    }
+}
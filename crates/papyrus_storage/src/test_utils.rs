@@ -26,9 +26,11 @@ pub fn get_test_config(storage_scope: Option<StorageScope>) -> (StorageConfig, T
                 min_size: 1 << 20,    // 1MB
                 max_size: 1 << 35,    // 32GB
                 growth_step: 1 << 26, // 64MB
+                ..Default::default()
             },
             scope: storage_scope,
             mmap_file_config: get_mmap_file_test_config(),
+            ..Default::default()
         },
         dir,
     )
@@ -1,22 +1,41 @@
-//! module for external utils, such as dumping a storage table to a file
+//! module for external utils, such as dumping a storage table to a file, comparing storages or
+//! verifying the hashes of the stored chain
 #[cfg(test)]
 #[path = "utils_test.rs"]
 mod utils_test;
 
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 
+use papyrus_common::block_hash::validate_block_hash;
+use papyrus_common::transaction_hash::validate_transaction_hash;
+use papyrus_common::TransactionOptions;
 use serde::Serialize;
-use starknet_api::block::BlockNumber;
+use starknet_api::block::{Block, BlockBody, BlockHash, BlockNumber};
 use starknet_api::core::{ChainId, ClassHash, CompiledClassHash};
 use starknet_api::hash::StarkFelt;
-use starknet_api::state::{EntryPoint, EntryPointType};
+use starknet_api::state::{EntryPoint, EntryPointType, StateNumber};
+use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
+use starknet_api::StarknetApiError;
 
+use crate::base_layer::BaseLayerStorageReader;
+use crate::body::{BodyStorageReader, TransactionIndex};
 use crate::compiled_class::CasmStorageReader;
 use crate::db::RO;
+use crate::header::HeaderStorageReader;
 use crate::state::StateStorageReader;
-use crate::{open_storage, StorageConfig, StorageError, StorageResult, StorageTxn};
+use crate::{
+    open_storage,
+    open_storage_read_only,
+    StorageConfig,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageTxn,
+};
 
 #[derive(Serialize)]
 struct DumpDeclaredClass {
@@ -84,3 +103,405 @@ fn dump_declared_classes_table_by_block_range_internal(
     writer.write_all(b"]")?;
     Ok(())
 }
+
+/// The kind of a difference between the data of a block in two storages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DifferenceKind {
+    /// Only the second storage has the data.
+    MissingInFirst,
+    /// Only the first storage has the data.
+    MissingInSecond,
+    /// Both storages have the data, with different values.
+    Mismatch,
+}
+
+/// A range of blocks whose data in a table differs between two storages in the same way.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableDifference {
+    /// The name of the table.
+    pub table: &'static str,
+    /// How the data differs.
+    pub kind: DifferenceKind,
+    /// The first block of the range.
+    pub start_block: BlockNumber,
+    /// The block after the last block of the range.
+    pub end_block: BlockNumber,
+}
+
+impl Display for TableDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:?} in blocks {}..{}",
+            self.table, self.kind, self.start_block, self.end_block
+        )
+    }
+}
+
+/// A marker whose value differs between two storages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkerDifference {
+    /// The name of the marker.
+    pub marker: &'static str,
+    /// The value of the marker in the first storage.
+    pub first: BlockNumber,
+    /// The value of the marker in the second storage.
+    pub second: BlockNumber,
+}
+
+impl Display for MarkerDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} marker: {} != {}", self.marker, self.first, self.second)
+    }
+}
+
+/// The differences between two storages.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageComparison {
+    /// The markers that differ.
+    pub markers: Vec<MarkerDifference>,
+    /// The block ranges in which the tables differ, ordered by table and block.
+    pub tables: Vec<TableDifference>,
+}
+
+impl StorageComparison {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty() && self.tables.is_empty()
+    }
+}
+
+/// Compares the storages in the given paths at a given block range. The storages are opened
+/// read-only, so they can be compared while nodes sync them. If no end block is given, the block
+/// range ends at the highest marker of the storages.
+pub fn compare_storages_by_block_range(
+    first_path_prefix: PathBuf,
+    second_path_prefix: PathBuf,
+    chain_id: &str,
+    start_block: u64,
+    end_block: Option<u64>,
+) -> StorageResult<StorageComparison> {
+    let open_existing_storage = |path_prefix: PathBuf| {
+        let mut storage_config = StorageConfig::default();
+        storage_config.db_config.path_prefix = path_prefix;
+        storage_config.db_config.chain_id = ChainId(chain_id.to_string());
+        storage_config.db_config.enforce_file_exists = true;
+        open_storage_read_only(storage_config)
+    };
+    let first_reader = open_existing_storage(first_path_prefix)?;
+    let second_reader = open_existing_storage(second_path_prefix)?;
+    compare_storages_by_block_range_internal(&first_reader, &second_reader, start_block, end_block)
+}
+
+type MarkerGetter = fn(&StorageTxn<'_, RO>) -> StorageResult<BlockNumber>;
+
+pub(crate) fn compare_storages_by_block_range_internal(
+    first_reader: &StorageReader,
+    second_reader: &StorageReader,
+    start_block: u64,
+    end_block: Option<u64>,
+) -> StorageResult<StorageComparison> {
+    let first_txn = first_reader.begin_ro_txn()?;
+    let second_txn = second_reader.begin_ro_txn()?;
+    let mut comparison = StorageComparison::default();
+
+    let markers: [(&'static str, MarkerGetter); 5] = [
+        ("header", |txn| txn.get_header_marker()),
+        ("body", |txn| txn.get_body_marker()),
+        ("state", |txn| txn.get_state_marker()),
+        ("compiled_class", |txn| txn.get_compiled_class_marker()),
+        ("base_layer", |txn| txn.get_base_layer_block_marker()),
+    ];
+    let mut highest_marker = BlockNumber(0);
+    for (marker, get_marker) in markers {
+        let first = get_marker(&first_txn)?;
+        let second = get_marker(&second_txn)?;
+        highest_marker = highest_marker.max(first).max(second);
+        if first != second {
+            comparison.markers.push(MarkerDifference { marker, first, second });
+        }
+    }
+
+    let blocks = (start_block..end_block.unwrap_or(highest_marker.0)).map(BlockNumber);
+    let tables = &mut comparison.tables;
+    let txns = (&first_txn, &second_txn);
+    compare_table(txns, blocks.clone(), "headers", tables, |txn, block_number| {
+        txn.get_block_header(block_number)
+    })?;
+    compare_table(txns, blocks.clone(), "transactions", tables, |txn, block_number| {
+        txn.get_block_transactions(block_number)
+    })?;
+    compare_table(txns, blocks.clone(), "transaction_outputs", tables, |txn, block_number| {
+        txn.get_block_transaction_outputs(block_number)
+    })?;
+    compare_table(txns, blocks.clone(), "transaction_hashes", tables, |txn, block_number| {
+        txn.get_block_transaction_hashes(block_number)
+    })?;
+    compare_table(txns, blocks.clone(), "state_diffs", tables, |txn, block_number| {
+        txn.get_state_diff(block_number)
+    })?;
+    // The classes are compared for the classes declared in the block in the compared storage.
+    compare_table(txns, blocks.clone(), "declared_classes", tables, |txn, block_number| {
+        let Some(state_diff) = txn.get_state_diff(block_number)? else {
+            return Ok(None);
+        };
+        let state_reader = txn.get_state_reader()?;
+        let state_number = StateNumber::right_after_block(block_number);
+        state_diff
+            .declared_classes
+            .keys()
+            .map(|class_hash| {
+                Ok((*class_hash, state_reader.get_class_definition_at(state_number, class_hash)?))
+            })
+            .collect::<StorageResult<Vec<_>>>()
+            .map(Some)
+    })?;
+    compare_table(
+        txns,
+        blocks.clone(),
+        "deprecated_declared_classes",
+        tables,
+        |txn, block_number| {
+            let Some(state_diff) = txn.get_state_diff(block_number)? else {
+                return Ok(None);
+            };
+            let state_reader = txn.get_state_reader()?;
+            let state_number = StateNumber::right_after_block(block_number);
+            state_diff
+                .deprecated_declared_classes
+                .iter()
+                .map(|class_hash| {
+                    Ok((
+                        *class_hash,
+                        state_reader
+                            .get_deprecated_class_definition_at(state_number, class_hash)?,
+                    ))
+                })
+                .collect::<StorageResult<Vec<_>>>()
+                .map(Some)
+        },
+    )?;
+    compare_table(txns, blocks, "casms", tables, |txn, block_number| {
+        let Some(state_diff) = txn.get_state_diff(block_number)? else {
+            return Ok(None);
+        };
+        state_diff
+            .declared_classes
+            .keys()
+            .map(|class_hash| Ok((*class_hash, txn.get_casm(class_hash)?)))
+            .collect::<StorageResult<Vec<_>>>()
+            .map(Some)
+    })?;
+
+    Ok(comparison)
+}
+
+// Compares the data of a table in each of the blocks, and adds the blocks in which it differs to
+// the differences. Consecutive blocks with the same kind of difference are merged into a range.
+fn compare_table<T: PartialEq>(
+    (first_txn, second_txn): (&StorageTxn<'_, RO>, &StorageTxn<'_, RO>),
+    blocks: impl Iterator<Item = BlockNumber>,
+    table: &'static str,
+    differences: &mut Vec<TableDifference>,
+    get_block_data: impl Fn(&StorageTxn<'_, RO>, BlockNumber) -> StorageResult<Option<T>>,
+) -> StorageResult<()> {
+    for block_number in blocks {
+        let kind = match (
+            get_block_data(first_txn, block_number)?,
+            get_block_data(second_txn, block_number)?,
+        ) {
+            (None, None) => continue,
+            (None, Some(_)) => DifferenceKind::MissingInFirst,
+            (Some(_), None) => DifferenceKind::MissingInSecond,
+            (Some(first), Some(second)) if first != second => DifferenceKind::Mismatch,
+            (Some(_), Some(_)) => continue,
+        };
+        match differences.last_mut() {
+            Some(last)
+                if last.table == table && last.kind == kind && last.end_block == block_number =>
+            {
+                last.end_block = block_number.next();
+            }
+            _ => differences.push(TableDifference {
+                table,
+                kind,
+                start_block: block_number,
+                end_block: block_number.next(),
+            }),
+        }
+    }
+    Ok(())
+}
+
+/// The first block in which the hashes of the stored chain diverge from the hashes that are
+/// recomputed from its data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainDivergence {
+    /// The parent hash of the block is not the hash of the previous block.
+    ParentHash { block_number: BlockNumber, parent_hash: BlockHash, previous_block_hash: BlockHash },
+    /// The hash of a transaction doesn't match any of the transaction hash functions.
+    TransactionHash {
+        block_number: BlockNumber,
+        transaction_offset: TransactionOffsetInBlock,
+        transaction_hash: TransactionHash,
+    },
+    /// The block hash doesn't match any of the block hash functions, i.e. the header or the
+    /// transactions and events commitments are corrupted.
+    BlockHash { block_number: BlockNumber, block_hash: BlockHash },
+}
+
+impl Display for ChainDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainDivergence::ParentHash { block_number, parent_hash, previous_block_hash } => {
+                write!(
+                    f,
+                    "Block {block_number}: parent hash {parent_hash:?} is not the hash of the \
+                     previous block {previous_block_hash:?}"
+                )
+            }
+            ChainDivergence::TransactionHash {
+                block_number,
+                transaction_offset,
+                transaction_hash,
+            } => write!(
+                f,
+                "Block {block_number}: hash {transaction_hash:?} of the transaction at offset {} \
+                 doesn't match the transaction",
+                transaction_offset.0
+            ),
+            ChainDivergence::BlockHash { block_number, block_hash } => {
+                write!(f, "Block {block_number}: hash {block_hash:?} doesn't match the block")
+            }
+        }
+    }
+}
+
+/// The result of verifying the hashes of the stored chain at a block range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainVerification {
+    /// The block after the last block that was verified.
+    pub end_block: BlockNumber,
+    /// The first divergence found, if any. No block after it is verified.
+    pub divergence: Option<ChainDivergence>,
+}
+
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum ChainVerificationError {
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
+}
+
+/// Walks the stored chain at a given block range, recomputes the hashes of the transactions and
+/// the blocks, and checks that each block points to the hash of the previous block. Stops at the
+/// first divergence. If no end block is given, or if the storage doesn't have the blocks up to the
+/// end block, the block range ends at the last block that has both a header and a body. The storage
+/// is opened read-only, so it can be verified while a node syncs it.
+pub fn verify_chain(
+    path_prefix: PathBuf,
+    chain_id: &str,
+    start_block: u64,
+    end_block: Option<u64>,
+) -> Result<ChainVerification, ChainVerificationError> {
+    let mut storage_config = StorageConfig::default();
+    storage_config.db_config.path_prefix = path_prefix;
+    storage_config.db_config.chain_id = ChainId(chain_id.to_string());
+    storage_config.db_config.enforce_file_exists = true;
+    let reader = open_storage_read_only(storage_config)?;
+    verify_chain_internal(&reader, &ChainId(chain_id.to_string()), start_block, end_block)
+}
+
+fn verify_chain_internal(
+    reader: &StorageReader,
+    chain_id: &ChainId,
+    start_block: u64,
+    end_block: Option<u64>,
+) -> Result<ChainVerification, ChainVerificationError> {
+    let txn = reader.begin_ro_txn()?;
+    let marker = txn.get_header_marker()?.min(txn.get_body_marker()?);
+    let end_block = end_block.map_or(marker, |end_block| marker.min(BlockNumber(end_block)));
+    let mut previous_block_hash = match BlockNumber(start_block).prev() {
+        Some(previous_block_number) => {
+            txn.get_block_header(previous_block_number)?.map(|header| header.block_hash)
+        }
+        None => None,
+    };
+
+    for block_number in (start_block..end_block.0).map(BlockNumber) {
+        let block = read_block(&txn, block_number)?;
+        let divergence = find_block_divergence(&block, previous_block_hash, chain_id)?;
+        if divergence.is_some() {
+            return Ok(ChainVerification { end_block: block_number.next(), divergence });
+        }
+        previous_block_hash = Some(block.header.block_hash);
+    }
+    Ok(ChainVerification { end_block, divergence: None })
+}
+
+/// Reads the header and the body of the block with the given number. Fails if any of them is
+/// missing.
+pub fn read_block(txn: &StorageTxn<'_, RO>, block_number: BlockNumber) -> StorageResult<Block> {
+    let missing_data =
+        || StorageError::DBInconsistency { msg: format!("Missing data of block {block_number}.") };
+    let header = txn.get_block_header(block_number)?.ok_or_else(missing_data)?;
+    let transactions = txn.get_block_transactions(block_number)?.ok_or_else(missing_data)?;
+    let transaction_hashes =
+        txn.get_block_transaction_hashes(block_number)?.ok_or_else(missing_data)?;
+    let transaction_outputs = txn
+        .get_block_transaction_outputs(block_number)?
+        .ok_or_else(missing_data)?
+        .into_iter()
+        .enumerate()
+        .map(|(offset, thin_output)| {
+            let transaction_index =
+                TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+            let events = txn.get_transaction_events(transaction_index)?.ok_or_else(missing_data)?;
+            Ok(thin_output.into_transaction_output(events))
+        })
+        .collect::<StorageResult<_>>()?;
+    Ok(Block { header, body: BlockBody { transactions, transaction_outputs, transaction_hashes } })
+}
+
+pub(crate) fn find_block_divergence(
+    block: &Block,
+    previous_block_hash: Option<BlockHash>,
+    chain_id: &ChainId,
+) -> Result<Option<ChainDivergence>, StarknetApiError> {
+    let block_number = block.header.block_number;
+    if let Some(previous_block_hash) = previous_block_hash {
+        if block.header.parent_hash != previous_block_hash {
+            return Ok(Some(ChainDivergence::ParentHash {
+                block_number,
+                parent_hash: block.header.parent_hash,
+                previous_block_hash,
+            }));
+        }
+    }
+    for (offset, (transaction, transaction_hash)) in
+        block.body.transactions.iter().zip(&block.body.transaction_hashes).enumerate()
+    {
+        if !validate_transaction_hash(
+            transaction,
+            &block_number,
+            chain_id,
+            *transaction_hash,
+            &TransactionOptions::default(),
+        )? {
+            return Ok(Some(ChainDivergence::TransactionHash {
+                block_number,
+                transaction_offset: TransactionOffsetInBlock(offset),
+                transaction_hash: *transaction_hash,
+            }));
+        }
+    }
+    if !validate_block_hash(block, chain_id)? {
+        return Ok(Some(ChainDivergence::BlockHash {
+            block_number,
+            block_hash: block.header.block_hash,
+        }));
+    }
+    Ok(None)
+}
@@ -2,16 +2,35 @@ use std::collections::HashMap;
 use std::fs;
 
 use indexmap::indexmap;
+use papyrus_common::block_hash::calculate_block_hash;
+use papyrus_common::transaction_hash::get_transaction_hash;
+use papyrus_common::TransactionOptions;
 use pretty_assertions::assert_eq;
-use starknet_api::block::BlockNumber;
-use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::block::{Block, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ChainId, ClassHash, CompiledClassHash};
 use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::{ContractClass, StateDiff};
+use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
 
 // use super::dump_table_to_file;
+use crate::body::BodyStorageWriter;
+use crate::chain_fixture::ChainFixtureBuilder;
+use crate::header::HeaderStorageWriter;
 use crate::state::StateStorageWriter;
 use crate::test_utils::get_test_storage;
-use crate::utils::{dump_declared_classes_table_by_block_range_internal, DumpDeclaredClass};
+use crate::utils::{
+    compare_storages_by_block_range_internal,
+    dump_declared_classes_table_by_block_range_internal,
+    verify_chain_internal,
+    ChainDivergence,
+    ChainVerification,
+    DifferenceKind,
+    DumpDeclaredClass,
+    MarkerDifference,
+    StorageComparison,
+    TableDifference,
+};
+use crate::StorageWriter;
 
 // TODO(yael): fix dump_table_to_file.
 #[test]
@@ -70,3 +89,187 @@ fn test_dump_declared_classes() {
     ];
     assert_eq!(file_content, serde_json::to_string(&expected_declared_classes).unwrap());
 }
+
+fn append_headers(writer: &mut StorageWriter, block_hashes: &[u64]) {
+    for (i, block_hash) in block_hashes.iter().enumerate() {
+        let header = BlockHeader {
+            block_hash: BlockHash(StarkFelt::from(*block_hash)),
+            block_number: BlockNumber(i as u64),
+            ..Default::default()
+        };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(header.block_number, &header)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+#[test]
+fn compare_storages() {
+    let ((first_reader, mut first_writer), _first_temp_dir) = get_test_storage();
+    let ((second_reader, mut second_writer), _second_temp_dir) = get_test_storage();
+    append_headers(&mut first_writer, &[1, 2, 3, 4, 5]);
+    append_headers(&mut second_writer, &[1, 102, 103]);
+
+    let comparison =
+        compare_storages_by_block_range_internal(&first_reader, &second_reader, 0, None).unwrap();
+    assert_eq!(
+        comparison,
+        StorageComparison {
+            markers: vec![MarkerDifference {
+                marker: "header",
+                first: BlockNumber(5),
+                second: BlockNumber(3),
+            }],
+            tables: vec![
+                TableDifference {
+                    table: "headers",
+                    kind: DifferenceKind::Mismatch,
+                    start_block: BlockNumber(1),
+                    end_block: BlockNumber(3),
+                },
+                TableDifference {
+                    table: "headers",
+                    kind: DifferenceKind::MissingInSecond,
+                    start_block: BlockNumber(3),
+                    end_block: BlockNumber(5),
+                },
+            ],
+        }
+    );
+
+    // Only the blocks in the given range are compared.
+    let comparison =
+        compare_storages_by_block_range_internal(&second_reader, &first_reader, 2, Some(4))
+            .unwrap();
+    assert_eq!(
+        comparison.tables,
+        vec![
+            TableDifference {
+                table: "headers",
+                kind: DifferenceKind::Mismatch,
+                start_block: BlockNumber(2),
+                end_block: BlockNumber(3),
+            },
+            TableDifference {
+                table: "headers",
+                kind: DifferenceKind::MissingInFirst,
+                start_block: BlockNumber(3),
+                end_block: BlockNumber(4),
+            },
+        ]
+    );
+
+    let comparison =
+        compare_storages_by_block_range_internal(&first_reader, &first_reader, 0, None).unwrap();
+    assert!(comparison.is_empty());
+}
+
+// Returns a chain whose transaction hashes and block hashes are computed with the current hash
+// functions.
+fn hashed_chain(n_blocks: u64, chain_id: &ChainId) -> Vec<Block> {
+    let mut blocks = ChainFixtureBuilder::new(n_blocks)
+        .transactions_per_block(1..=3)
+        .initial_contracts(2)
+        .build()
+        .blocks;
+    let mut parent_hash = BlockHash::default();
+    for block in &mut blocks {
+        block.body.transaction_hashes = block
+            .body
+            .transactions
+            .iter()
+            .map(|transaction| {
+                get_transaction_hash(transaction, chain_id, &TransactionOptions::default()).unwrap()
+            })
+            .collect();
+        block.header.parent_hash = parent_hash;
+        block.header.block_hash = BlockHash(calculate_block_hash(block, chain_id).unwrap());
+        parent_hash = block.header.block_hash;
+    }
+    blocks
+}
+
+fn append_blocks(writer: &mut StorageWriter, blocks: &[Block]) {
+    for block in blocks {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(block.header.block_number, &block.header)
+            .unwrap()
+            .append_body(block.header.block_number, block.body.clone())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+#[test]
+fn verify_chain() {
+    let chain_id = ChainId("SN_MAIN".to_owned());
+    let blocks = hashed_chain(5, &chain_id);
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer, &blocks);
+
+    assert_eq!(
+        verify_chain_internal(&reader, &chain_id, 0, None).unwrap(),
+        ChainVerification { end_block: BlockNumber(5), divergence: None }
+    );
+    // The end block is limited by the stored blocks.
+    assert_eq!(
+        verify_chain_internal(&reader, &chain_id, 3, Some(10)).unwrap(),
+        ChainVerification { end_block: BlockNumber(5), divergence: None }
+    );
+}
+
+#[test]
+fn verify_chain_with_divergence() {
+    let chain_id = ChainId("SN_MAIN".to_owned());
+
+    let mut blocks = hashed_chain(5, &chain_id);
+    let corrupted_hash = TransactionHash(StarkFelt::from(1_u128));
+    blocks[3].body.transaction_hashes[0] = corrupted_hash;
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer, &blocks);
+    assert_eq!(
+        verify_chain_internal(&reader, &chain_id, 0, None).unwrap(),
+        ChainVerification {
+            end_block: BlockNumber(4),
+            divergence: Some(ChainDivergence::TransactionHash {
+                block_number: BlockNumber(3),
+                transaction_offset: TransactionOffsetInBlock(0),
+                transaction_hash: corrupted_hash,
+            }),
+        }
+    );
+
+    let mut blocks = hashed_chain(5, &chain_id);
+    blocks[2].header.timestamp.0 += 1;
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer, &blocks);
+    assert_eq!(
+        verify_chain_internal(&reader, &chain_id, 0, None).unwrap().divergence,
+        Some(ChainDivergence::BlockHash {
+            block_number: BlockNumber(2),
+            block_hash: blocks[2].header.block_hash,
+        })
+    );
+
+    // A block that doesn't point to the previous block, although its own hash is valid.
+    let mut blocks = hashed_chain(5, &chain_id);
+    blocks[1].header.parent_hash = BlockHash(StarkFelt::from(1_u128));
+    blocks[1].header.block_hash = BlockHash(calculate_block_hash(&blocks[1], &chain_id).unwrap());
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    append_blocks(&mut writer, &blocks);
+    assert_eq!(
+        verify_chain_internal(&reader, &chain_id, 0, None).unwrap().divergence,
+        Some(ChainDivergence::ParentHash {
+            block_number: BlockNumber(1),
+            parent_hash: BlockHash(StarkFelt::from(1_u128)),
+            previous_block_hash: blocks[0].header.block_hash,
+        })
+    );
+}
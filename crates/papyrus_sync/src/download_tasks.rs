@@ -0,0 +1,110 @@
+//! Downloads the data of a range of blocks by several tasks that run at once, each downloading a
+//! consecutive part of the range. The data is yielded in the order of the blocks, so it's stored in
+//! order while the tasks of the later parts are still downloading.
+#[cfg(test)]
+#[path = "download_tasks_test.rs"]
+mod download_tasks_test;
+
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use async_stream::stream;
+use futures_util::stream::BoxStream;
+use futures_util::{Stream, StreamExt};
+use starknet_api::block::BlockNumber;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+// A running download of a part of the range. The task is aborted once dropped, so the downloads
+// stop when the stream that yields their data is dropped.
+struct DownloadTask<Item> {
+    receiver: mpsc::Receiver<Item>,
+    handle: JoinHandle<()>,
+}
+
+impl<Item> Drop for DownloadTask<Item> {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Returns the data of the blocks in [`from`, `up_to`), downloaded by tasks of at most
+/// `blocks_per_task` blocks each, with at most `max_tasks` tasks running at once. `download`
+/// returns the stream of the data of the blocks in the given range.
+///
+/// A task keeps the data it downloaded until it's yielded, so at most `max_tasks` times
+/// `blocks_per_task` blocks are kept in memory, if the streams return an item per block.
+pub(crate) fn download_in_tasks<Source, Item>(
+    source: Arc<Source>,
+    from: BlockNumber,
+    up_to: BlockNumber,
+    blocks_per_task: u64,
+    max_tasks: usize,
+    download: for<'a> fn(&'a Source, BlockNumber, BlockNumber) -> BoxStream<'a, Item>,
+) -> impl Stream<Item = Item>
+where
+    Source: Send + Sync + 'static,
+    Item: Send + 'static,
+{
+    let blocks_per_task = blocks_per_task.max(1);
+    let max_tasks = max_tasks.max(1);
+    let mut parts = (from.0..up_to.0).step_by(blocks_per_task as usize).map(move |part_from| {
+        (BlockNumber(part_from), BlockNumber(min(part_from + blocks_per_task, up_to.0)))
+    });
+
+    stream! {
+        let mut tasks = VecDeque::with_capacity(max_tasks);
+        loop {
+            while tasks.len() < max_tasks {
+                let Some((part_from, part_up_to)) = parts.next() else {
+                    break;
+                };
+                tasks.push_back(spawn_download_task(
+                    source.clone(),
+                    part_from,
+                    part_up_to,
+                    blocks_per_task as usize,
+                    download,
+                ));
+            }
+            let Some(mut task) = tasks.pop_front() else {
+                break;
+            };
+            while let Some(item) = task.receiver.recv().await {
+                yield item;
+            }
+            // The data of a task that panicked is incomplete, so the panic is propagated instead of
+            // continuing with the next part.
+            if let Err(err) = (&mut task.handle).await {
+                if err.is_panic() {
+                    std::panic::resume_unwind(err.into_panic());
+                }
+            }
+        }
+    }
+}
+
+fn spawn_download_task<Source, Item>(
+    source: Arc<Source>,
+    from: BlockNumber,
+    up_to: BlockNumber,
+    capacity: usize,
+    download: for<'a> fn(&'a Source, BlockNumber, BlockNumber) -> BoxStream<'a, Item>,
+) -> DownloadTask<Item>
+where
+    Source: Send + Sync + 'static,
+    Item: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(capacity);
+    let handle = tokio::spawn(async move {
+        let mut items = download(&source, from, up_to);
+        while let Some(item) = items.next().await {
+            // The receiver is dropped only together with the task.
+            if sender.send(item).await.is_err() {
+                break;
+            }
+        }
+    });
+    DownloadTask { receiver, handle }
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_stream::stream;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use starknet_api::block::BlockNumber;
+use tokio::sync::Barrier;
+
+use crate::download_tasks::download_in_tasks;
+
+const MAX_TASKS: usize = 3;
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Streams the numbers of the blocks once all the tasks that may run at once started downloading, so
+// the download gets stuck unless the tasks run concurrently.
+struct BlockNumbersSource {
+    barrier: Barrier,
+    running_tasks: AtomicUsize,
+    max_running_tasks: AtomicUsize,
+}
+
+impl BlockNumbersSource {
+    fn new() -> Self {
+        Self {
+            barrier: Barrier::new(MAX_TASKS),
+            running_tasks: AtomicUsize::new(0),
+            max_running_tasks: AtomicUsize::new(0),
+        }
+    }
+
+    fn stream_block_numbers(
+        &self,
+        from: BlockNumber,
+        up_to: BlockNumber,
+    ) -> BoxStream<'_, BlockNumber> {
+        stream! {
+            let running_tasks = self.running_tasks.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running_tasks.fetch_max(running_tasks, Ordering::SeqCst);
+            self.barrier.wait().await;
+            for block_number in from.iter_up_to(up_to) {
+                yield block_number;
+            }
+            self.running_tasks.fetch_sub(1, Ordering::SeqCst);
+        }
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn data_is_downloaded_concurrently_and_yielded_in_order() {
+    let source = Arc::new(BlockNumbersSource::new());
+    let stream = download_in_tasks(
+        source.clone(),
+        BlockNumber(0),
+        BlockNumber(12),
+        2,
+        MAX_TASKS,
+        BlockNumbersSource::stream_block_numbers,
+    );
+
+    let block_numbers = tokio::time::timeout(TIMEOUT, stream.collect::<Vec<_>>())
+        .await
+        .expect("The tasks didn't download concurrently.");
+    assert_eq!(block_numbers, BlockNumber(0).iter_up_to(BlockNumber(12)).collect::<Vec<_>>());
+    assert_eq!(source.max_running_tasks.load(Ordering::SeqCst), MAX_TASKS);
+}
+
+#[tokio::test]
+async fn last_part_is_shorter() {
+    let source = Arc::new(BlockNumbersSource::new());
+    // 3 tasks, the last with a single block.
+    let stream = download_in_tasks(
+        source,
+        BlockNumber(5),
+        BlockNumber(10),
+        2,
+        MAX_TASKS,
+        BlockNumbersSource::stream_block_numbers,
+    );
+
+    let block_numbers = tokio::time::timeout(TIMEOUT, stream.collect::<Vec<_>>()).await.unwrap();
+    assert_eq!(block_numbers, BlockNumber(5).iter_up_to(BlockNumber(10)).collect::<Vec<_>>());
+}
@@ -5,6 +5,8 @@
 #[cfg(test)]
 mod sync_test;
 
+mod download_tasks;
+pub mod p2p_sync;
 mod pending_sync;
 pub mod sources;
 
@@ -18,29 +20,38 @@ use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 use chrono::{TimeZone, Utc};
 use futures_util::{pin_mut, select, Stream, StreamExt};
 use indexmap::IndexMap;
+use papyrus_common::l1_to_l2_messages::L1ToL2Message;
 use papyrus_common::pending_classes::PendingClasses;
+use papyrus_common::raw_transaction::RawTransaction;
 use papyrus_common::{metrics as papyrus_metrics, BlockHashAndNumber};
 use papyrus_config::converters::deserialize_seconds_to_duration;
-use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
 use papyrus_storage::base_layer::BaseLayerStorageWriter;
-use papyrus_storage::body::BodyStorageWriter;
+use papyrus_storage::body::{BodyStorageReader, BodyStorageWriter};
 use papyrus_storage::compiled_class::{CasmStorageReader, CasmStorageWriter};
-use papyrus_storage::db::DbError;
+use papyrus_storage::db::{DbError, TransactionKind, RW};
 use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter, StarknetVersion};
+use papyrus_storage::l1_to_l2_messages::{
+    L1ToL2MessagesStorageReader,
+    L1ToL2MessagesStorageWriter,
+};
 use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
-use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use papyrus_storage::state_trie::{StateTrieStorageReader, StateTrieStorageWriter};
+use papyrus_storage::{StorageError, StorageReader, StorageResult, StorageTxn, StorageWriter};
 use serde::{Deserialize, Serialize};
 use sources::base_layer::BaseLayerSourceError;
 use starknet_api::block::{Block, BlockHash, BlockNumber, BlockSignature};
-use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::core::{ClassHash, CompiledClassHash, GlobalRoot};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::StateDiff;
+use starknet_api::transaction::TransactionOffsetInBlock;
 use starknet_client::reader::PendingData;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, error, info, instrument, trace, warn};
 
+use crate::download_tasks::download_in_tasks;
 use crate::pending_sync::sync_pending_data;
 use crate::sources::base_layer::{BaseLayerSourceTrait, EthereumBaseLayerSource};
 use crate::sources::central::{CentralError, CentralSource, CentralSourceTrait};
@@ -53,6 +64,12 @@ const PENDING_SLEEP_DURATION: Duration = Duration::from_millis(500);
 // Sleep duration, in seconds, between sync progress checks.
 const SLEEP_TIME_SYNC_PROGRESS: Duration = Duration::from_secs(300);
 
+// The maximal number of base layer blocks whose L1 to L2 messages are requested at once.
+const L1_TO_L2_MESSAGES_MAX_BLOCK_RANGE: u64 = 1000;
+// The messages of a base layer block are stored only after it has this many confirmations, so
+// they aren't reverted by a reorg of the base layer.
+const L1_TO_L2_MESSAGES_MIN_CONFIRMATIONS: u64 = 10;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SyncConfig {
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
@@ -63,11 +80,15 @@ pub struct SyncConfig {
     pub recoverable_error_sleep_duration: Duration,
     pub blocks_max_stream_size: u32,
     pub state_updates_max_stream_size: u32,
+    pub max_concurrent_download_tasks: usize,
+    pub blocks_per_download_task: u32,
+    pub l1_to_l2_messages_start_block: Option<u64>,
+    pub maintain_state_tries: bool,
 }
 
 impl SerializeConfig for SyncConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut dump = BTreeMap::from_iter([
             ser_param(
                 "block_propagation_sleep_duration",
                 &self.block_propagation_sleep_duration.as_secs(),
@@ -99,7 +120,43 @@ impl SerializeConfig for SyncConfig {
                 "Max amount of state updates to download in a stream.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+            ser_param(
+                "max_concurrent_download_tasks",
+                &self.max_concurrent_download_tasks,
+                "Max amount of tasks that download blocks at once, and likewise for state \
+                 updates. Each task downloads consecutive blocks, and the downloaded data is \
+                 stored in the order of the blocks.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "blocks_per_download_task",
+                &self.blocks_per_download_task,
+                "Max amount of blocks or state updates a download task downloads. The data is \
+                 kept in memory until it's stored, so up to max_concurrent_download_tasks times \
+                 this amount of blocks and of state updates are kept in memory.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "maintain_state_tries",
+                &self.maintain_state_tries,
+                "Whether to maintain the state tries the storage proofs are served from, and \
+                 reject the blocks whose state roots don't match them. The tries are built from \
+                 the first block, so the stored blocks are applied to them first. The trie nodes \
+                 of all the blocks are kept and never pruned, so the storage grows significantly \
+                 larger.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        dump.append(&mut ser_optional_param(
+            &self.l1_to_l2_messages_start_block,
+            0,
+            "l1_to_l2_messages_start_block",
+            "The base layer block from which the messages to Starknet are tracked, by the hashes \
+             of the base layer transactions that sent them. If not set, the messages aren't \
+             tracked.",
+            ParamPrivacyInput::Public,
+        ));
+        dump
     }
 }
 
@@ -111,6 +168,10 @@ impl Default for SyncConfig {
             recoverable_error_sleep_duration: Duration::from_secs(3),
             blocks_max_stream_size: 1000,
             state_updates_max_stream_size: 1000,
+            max_concurrent_download_tasks: 4,
+            blocks_per_download_task: 100,
+            l1_to_l2_messages_start_block: None,
+            maintain_state_tries: false,
         }
     }
 }
@@ -131,6 +192,8 @@ pub struct GenericStateSync<
     base_layer_source: Arc<TBaseLayerSource>,
     reader: StorageReader,
     writer: StorageWriter,
+    // Notified with the number of every block whose data is fully stored.
+    new_block_sender: broadcast::Sender<BlockNumber>,
 }
 
 pub type StateSyncResult = Result<(), StateSyncError>;
@@ -167,6 +230,24 @@ pub enum StateSyncError {
         base_layer_hash: BlockHash,
         l2_hash: BlockHash,
     },
+    #[error(
+        "The state root of block {block_number} doesn't match its state diff. Header state root: \
+         {header_state_root}, computed state root: {computed_state_root}."
+    )]
+    StateRootMismatch {
+        block_number: BlockNumber,
+        header_state_root: GlobalRoot,
+        computed_state_root: GlobalRoot,
+    },
+    #[error(
+        "The stored header of block {block_number} doesn't match the block from the central \
+         source. Stored block hash: {stored_block_hash}, central block hash: {central_block_hash}."
+    )]
+    StoredHeaderMismatch {
+        block_number: BlockNumber,
+        stored_block_hash: BlockHash,
+        central_block_hash: BlockHash,
+    },
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -178,6 +259,9 @@ pub enum SyncEvent {
         block: Block,
         signature: BlockSignature,
         starknet_version: StarknetVersion,
+        // The raw JSON of the transactions that had fields unknown to the node, by their offset in
+        // the block.
+        raw_transactions: BTreeMap<TransactionOffsetInBlock, RawTransaction>,
     },
     StateDiffAvailable {
         block_number: BlockNumber,
@@ -198,6 +282,11 @@ pub enum SyncEvent {
         block_number: BlockNumber,
         block_hash: BlockHash,
     },
+    L1ToL2MessagesAvailable {
+        messages: Vec<L1ToL2Message>,
+        // The first base layer block whose messages aren't included.
+        next_base_layer_block: u64,
+    },
 }
 
 impl<
@@ -247,6 +336,9 @@ impl<
                     );
                     true
                 }
+                // The stored block was reverted in the central source, so the sync restarts and
+                // reverts it too.
+                StateSyncError::StoredHeaderMismatch { .. } => true,
                 StateSyncError::BaseLayerHashMismatch { .. } => true,
                 StateSyncError::BaseLayerBlockWithoutMatchingHeader { .. } => true,
                 _ => false,
@@ -256,10 +348,17 @@ impl<
 
     // Sync until encountering an error:
     //  1. If needed, revert blocks from the end of the chain.
-    //  2. Create infinite block and state diff streams to fetch data from the central source.
-    //  3. Fetch data from the streams with unblocking wait while there is no new data.
+    //  2. If needed, apply the stored state diffs to the state tries.
+    //  3. Create infinite block and state diff streams to fetch data from the central source. The
+    //     state diffs are fetched for every block whose header is stored, in parallel to the
+    //     bodies, so once the header chain was synced (for example from the peers of the node), the
+    //     bodies and the state diffs of its blocks are downloaded concurrently. Each stream
+    //     downloads by several tasks at once, see `download_in_tasks`, and yields the data in the
+    //     order of the blocks, so it's stored in order.
+    //  4. Fetch data from the streams with unblocking wait while there is no new data.
     async fn sync_while_ok(&mut self) -> StateSyncResult {
         self.handle_block_reverts().await?;
+        self.catch_up_state_tries()?;
         let block_stream = stream_new_blocks(
             self.reader.clone(),
             self.central_source.clone(),
@@ -270,6 +369,8 @@ impl<
             self.config.block_propagation_sleep_duration,
             PENDING_SLEEP_DURATION,
             self.config.blocks_max_stream_size,
+            self.config.blocks_per_download_task,
+            self.config.max_concurrent_download_tasks,
         )
         .fuse();
         let state_diff_stream = stream_new_state_diffs(
@@ -277,6 +378,8 @@ impl<
             self.central_source.clone(),
             self.config.block_propagation_sleep_duration,
             self.config.state_updates_max_stream_size,
+            self.config.blocks_per_download_task,
+            self.config.max_concurrent_download_tasks,
         )
         .fuse();
         let compiled_class_stream = stream_new_compiled_classes(
@@ -293,6 +396,17 @@ impl<
             self.config.base_layer_propagation_sleep_duration,
         )
         .fuse();
+        let l1_to_l2_messages_stream = match self.config.l1_to_l2_messages_start_block {
+            Some(start_block) => stream_l1_to_l2_messages(
+                self.reader.clone(),
+                self.base_layer_source.clone(),
+                self.config.base_layer_propagation_sleep_duration,
+                start_block,
+            )
+            .boxed(),
+            None => futures_util::stream::pending().boxed(),
+        }
+        .fuse();
         // TODO(dvir): try use interval instead of stream.
         // TODO: fix the bug and remove this check.
         let check_sync_progress = check_sync_progress(self.reader.clone()).fuse();
@@ -301,6 +415,7 @@ impl<
             state_diff_stream,
             compiled_class_stream,
             base_layer_block_stream,
+            l1_to_l2_messages_stream,
             check_sync_progress
         );
 
@@ -311,6 +426,7 @@ impl<
               res = state_diff_stream.next() => res,
               res = compiled_class_stream.next() => res,
               res = base_layer_block_stream.next() => res,
+              res = l1_to_l2_messages_stream.next() => res,
               res = check_sync_progress.next() => res,
               complete => break,
             }
@@ -330,7 +446,8 @@ impl<
                 block,
                 signature: _signature,
                 starknet_version,
-            } => self.store_block(block_number, block, &starknet_version),
+                raw_transactions,
+            } => self.store_block(block_number, block, &starknet_version, raw_transactions),
             SyncEvent::StateDiffAvailable {
                 block_number,
                 block_hash,
@@ -350,17 +467,21 @@ impl<
             SyncEvent::NewBaseLayerBlock { block_number, block_hash } => {
                 self.store_base_layer_block(block_number, block_hash)
             }
+            SyncEvent::L1ToL2MessagesAvailable { messages, next_base_layer_block } => {
+                self.store_l1_to_l2_messages(&messages, next_base_layer_block)
+            }
             SyncEvent::NoProgress => Err(StateSyncError::NoProgress),
         }
     }
 
     #[latency_histogram("sync_store_block_latency_seconds")]
-    #[instrument(skip(self, block), level = "debug", fields(block_hash = %block.header.block_hash), err)]
+    #[instrument(skip(self, block, raw_transactions), level = "debug", fields(block_hash = %block.header.block_hash), err)]
     fn store_block(
         &mut self,
         block_number: BlockNumber,
         block: Block,
         starknet_version: &StarknetVersion,
+        raw_transactions: BTreeMap<TransactionOffsetInBlock, RawTransaction>,
     ) -> StateSyncResult {
         // Assuming the central source is trusted, detect reverts by comparing the incoming block's
         // parent hash to the current hash.
@@ -368,14 +489,34 @@ impl<
 
         debug!("Storing block.");
         trace!("Block data: {block:#?}");
-        self.writer
-            .begin_rw_txn()?
-            .append_header(block_number, &block.header)?
-            .update_starknet_version(&block_number, starknet_version)?
+        let mut txn = self.writer.begin_rw_txn()?;
+        // The header might have been synced from the peers of the node already, in which case only
+        // the body is stored.
+        match txn.get_block_header(block_number)? {
+            Some(stored_header) if stored_header.block_hash != block.header.block_hash => {
+                return Err(StateSyncError::StoredHeaderMismatch {
+                    block_number,
+                    stored_block_hash: stored_header.block_hash,
+                    central_block_hash: block.header.block_hash,
+                });
+            }
+            Some(_) => {}
+            None => {
+                txn = txn.append_header(block_number, &block.header)?;
+                metrics::gauge!(
+                    papyrus_metrics::PAPYRUS_HEADER_MARKER,
+                    block_number.next().0 as f64
+                );
+            }
+        }
+        txn.update_starknet_version(&block_number, starknet_version)?
             .append_body(block_number, block.body)?
+            .append_raw_transactions(block_number, raw_transactions)?
             .commit()?;
-        metrics::gauge!(papyrus_metrics::PAPYRUS_HEADER_MARKER, block_number.next().0 as f64);
         metrics::gauge!(papyrus_metrics::PAPYRUS_BODY_MARKER, block_number.next().0 as f64);
+        if self.reader.begin_ro_txn()?.get_state_marker()? > block_number {
+            self.announce_stored_block(block_number, block.header.block_hash);
+        }
         let dt = Utc::now()
             - Utc
                 .timestamp_opt(block.header.timestamp.0 as i64, 0)
@@ -398,24 +539,53 @@ impl<
         state_diff: StateDiff,
         deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
     ) -> StateSyncResult {
-        // TODO(dan): verifications - verify state diff against stored header.
         debug!("Storing state diff.");
         trace!("StateDiff data: {state_diff:#?}");
-        self.writer
-            .begin_rw_txn()?
-            .append_state_diff(block_number, state_diff, deployed_contract_class_definitions)?
-            .commit()?;
+        // The state diffs are downloaded only for blocks whose header is stored, and they must
+        // belong to the stored block.
+        let mut txn = self.writer.begin_rw_txn()?;
+        let stored_block_hash = txn
+            .get_block_header(block_number)?
+            .ok_or(StorageError::DBInconsistency {
+                msg: format!(
+                    "Missing the header of block {block_number} whose state diff arrived."
+                ),
+            })?
+            .block_hash;
+        if stored_block_hash != block_hash {
+            return Err(StateSyncError::StoredHeaderMismatch {
+                block_number,
+                stored_block_hash,
+                central_block_hash: block_hash,
+            });
+        }
+        txn =
+            txn.append_state_diff(block_number, state_diff, deployed_contract_class_definitions)?;
+        if self.config.maintain_state_tries {
+            txn = txn.update_state_tries(block_number)?;
+            // The transaction isn't committed if the state root doesn't match.
+            verify_state_root(&txn, block_number)?;
+        }
+        txn.commit()?;
         metrics::gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, block_number.next().0 as f64);
         let compiled_class_marker = self.reader.begin_ro_txn()?.get_compiled_class_marker()?;
         metrics::gauge!(
             papyrus_metrics::PAPYRUS_COMPILED_CLASS_MARKER,
             compiled_class_marker.0 as f64
         );
+        if self.reader.begin_ro_txn()?.get_body_marker()? > block_number {
+            self.announce_stored_block(block_number, block_hash);
+        }
+        Ok(())
+    }
 
+    // Called once both the body and the state diff of the block are stored, whichever is stored
+    // last.
+    fn announce_stored_block(&self, block_number: BlockNumber, block_hash: BlockHash) {
         // Info the user on syncing the block once all the data is stored.
         info!("Added block {} with hash {}.", block_number, block_hash);
-
-        Ok(())
+        // Sending fails only when there are no receivers, which is fine.
+        let _ = self.new_block_sender.send(block_number);
     }
 
     #[latency_histogram("sync_store_compiled_class_latency_seconds")]
@@ -480,6 +650,22 @@ impl<
         Ok(())
     }
 
+    fn store_l1_to_l2_messages(
+        &mut self,
+        messages: &[L1ToL2Message],
+        next_base_layer_block: u64,
+    ) -> StateSyncResult {
+        debug!(
+            "Storing {} L1 to L2 messages sent before base layer block {next_base_layer_block}.",
+            messages.len()
+        );
+        self.writer
+            .begin_rw_txn()?
+            .append_l1_to_l2_messages(messages, BlockNumber(next_base_layer_block))?
+            .commit()?;
+        Ok(())
+    }
+
     // Compares the block's parent hash to the stored block.
     fn verify_parent_block_hash(
         &self,
@@ -513,49 +699,68 @@ impl<
         Ok(())
     }
 
-    // Reverts data if needed.
+    // Reverts the stored blocks that aren't part of the chain of the central source, back to the
+    // common ancestor of the two chains. The new chain is applied on top of it by the streams.
     async fn handle_block_reverts(&mut self) -> Result<(), StateSyncError> {
         debug!("Handling block reverts.");
         let header_marker = self.reader.begin_ro_txn()?.get_header_marker()?;
 
-        // Revert last blocks if needed.
-        let mut last_block_in_storage = header_marker.prev();
-        while let Some(block_number) = last_block_in_storage {
-            if self.should_revert_block(block_number).await? {
-                self.revert_block(block_number)?;
-                last_block_in_storage = block_number.prev();
-            } else {
+        let mut common_ancestor_marker = header_marker;
+        while let Some(block_number) = common_ancestor_marker.prev() {
+            if !self.should_revert_block(block_number).await? {
                 break;
             }
+            common_ancestor_marker = block_number;
+        }
+        if common_ancestor_marker == header_marker {
+            return Ok(());
         }
-        Ok(())
-    }
-
-    // TODO(dan): update necessary metrics.
-    // Deletes the block data from the storage.
-    #[allow(clippy::expect_fun_call)]
-    #[instrument(skip(self), level = "debug", err)]
-    fn revert_block(&mut self, block_number: BlockNumber) -> StateSyncResult {
-        debug!("Reverting block.");
 
+        info!(
+            "Reverting blocks [{common_ancestor_marker} - {header_marker}), which aren't part of \
+             the chain of the central source."
+        );
+        // The blocks are reverted in a single transaction, so the storage never holds only part of
+        // the reverted chain.
         let mut txn = self.writer.begin_rw_txn()?;
-        txn = txn.try_revert_base_layer_marker(block_number)?;
-        let res = txn.revert_header(block_number)?;
-        txn = res.0;
-        let mut reverted_block_hash: Option<BlockHash> = None;
-        if let Some(header) = res.1 {
-            reverted_block_hash = Some(header.block_hash);
-
-            let res = txn.revert_body(block_number)?;
-            txn = res.0;
-
-            let res = txn.revert_state_diff(block_number)?;
-            txn = res.0;
+        let mut next_block_number = header_marker;
+        while let Some(block_number) =
+            next_block_number.prev().filter(|block_number| *block_number >= common_ancestor_marker)
+        {
+            txn = revert_block(txn, block_number)?;
+            next_block_number = block_number;
         }
-
         txn.commit()?;
-        if let Some(hash) = reverted_block_hash {
-            info!(%hash, "Reverted block.");
+
+        let txn = self.reader.begin_ro_txn()?;
+        metrics::gauge!(papyrus_metrics::PAPYRUS_HEADER_MARKER, txn.get_header_marker()?.0 as f64);
+        metrics::gauge!(papyrus_metrics::PAPYRUS_BODY_MARKER, txn.get_body_marker()?.0 as f64);
+        metrics::gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, txn.get_state_marker()?.0 as f64);
+        Ok(())
+    }
+
+    // Applies the stored state diffs that weren't applied to the state tries yet, for example when
+    // maintaining the tries was enabled after blocks were stored.
+    fn catch_up_state_tries(&mut self) -> StateSyncResult {
+        if !self.config.maintain_state_tries {
+            return Ok(());
+        }
+        let txn = self.reader.begin_ro_txn()?;
+        let state_marker = txn.get_state_marker()?;
+        let mut state_trie_marker = txn.get_state_trie_marker()?;
+        drop(txn);
+        if state_trie_marker < state_marker {
+            info!(
+                "Applying the state diffs from block {state_trie_marker} up to block \
+                 {state_marker} to the state tries."
+            );
+        }
+        while state_trie_marker < state_marker {
+            let txn = self.writer.begin_rw_txn()?.update_state_tries(state_trie_marker)?;
+            verify_state_root(&txn, state_trie_marker)?;
+            txn.commit()?;
+            debug!("Applied the state diff of block {state_trie_marker} to the state tries.");
+            state_trie_marker = state_trie_marker.next();
         }
         Ok(())
     }
@@ -592,10 +797,14 @@ fn stream_new_blocks<
     block_propagation_sleep_duration: Duration,
     pending_sleep_duration: Duration,
     max_stream_size: u32,
+    blocks_per_download_task: u32,
+    max_concurrent_download_tasks: usize,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
         loop {
-            let header_marker = reader.begin_ro_txn()?.get_header_marker()?;
+            // The headers might be ahead of the bodies if they were synced from the peers of the
+            // node, so the blocks are downloaded from the body marker.
+            let body_marker = reader.begin_ro_txn()?.get_body_marker()?;
             let latest_central_block = central_source.get_latest_block().await?;
             *shared_highest_block.write().await = latest_central_block;
             let central_block_marker = latest_central_block.map_or(
@@ -604,9 +813,13 @@ fn stream_new_blocks<
             metrics::gauge!(
                 papyrus_metrics::PAPYRUS_CENTRAL_BLOCK_MARKER, central_block_marker.0 as f64
             );
-            if header_marker == central_block_marker {
+            metrics::gauge!(
+                papyrus_metrics::PAPYRUS_SYNC_LAG_BLOCKS,
+                central_block_marker.0.saturating_sub(body_marker.0) as f64
+            );
+            if body_marker == central_block_marker {
                 // Only if the node have the last block and state (without casms), sync pending data.
-                if reader.begin_ro_txn()?.get_state_marker()? == header_marker{
+                if reader.begin_ro_txn()?.get_state_marker()? == body_marker{
                     // Here is the only place we update the pending data.
                     debug!("Start polling for pending data.");
                     sync_pending_data(
@@ -624,40 +837,65 @@ fn stream_new_blocks<
                 };
                 continue;
             }
-            let up_to = min(central_block_marker, BlockNumber(header_marker.0 + max_stream_size as u64));
-            debug!("Downloading blocks [{} - {}).", header_marker, up_to);
-            let block_stream =
-                central_source.stream_new_blocks(header_marker, up_to).fuse();
+            let up_to = min(central_block_marker, BlockNumber(body_marker.0 + max_stream_size as u64));
+            debug!("Downloading blocks [{} - {}).", body_marker, up_to);
+            let block_stream = download_in_tasks(
+                central_source.clone(),
+                body_marker,
+                up_to,
+                blocks_per_download_task.into(),
+                max_concurrent_download_tasks,
+                TCentralSource::stream_new_blocks,
+            )
+            .fuse();
             pin_mut!(block_stream);
             while let Some(maybe_block) = block_stream.next().await {
-                let (block_number, block, signature, starknet_version) = maybe_block?;
-                yield SyncEvent::BlockAvailable { block_number, block , signature, starknet_version};
+                let (block_number, block, signature, starknet_version, raw_transactions) =
+                    maybe_block?;
+                yield SyncEvent::BlockAvailable {
+                    block_number,
+                    block,
+                    signature,
+                    starknet_version,
+                    raw_transactions,
+                };
             }
         }
     }
 }
 
-fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send>(
+fn stream_new_state_diffs<TCentralSource: CentralSourceTrait + Sync + Send + 'static>(
     reader: StorageReader,
     central_source: Arc<TCentralSource>,
     block_propagation_sleep_duration: Duration,
     max_stream_size: u32,
+    blocks_per_download_task: u32,
+    max_concurrent_download_tasks: usize,
 ) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
     try_stream! {
         loop {
+            // The state diffs don't depend on the bodies, so they're downloaded for every block
+            // whose header is stored, while its body may still be downloaded.
             let txn = reader.begin_ro_txn()?;
             let state_marker = txn.get_state_marker()?;
             let last_block_number = txn.get_header_marker()?;
             drop(txn);
-            if state_marker == last_block_number {
-                debug!("State updates syncing reached the last downloaded block, waiting for more blocks.");
+            if state_marker >= last_block_number {
+                debug!("State updates syncing reached the last downloaded header, waiting for more blocks.");
                 tokio::time::sleep(block_propagation_sleep_duration).await;
                 continue;
             }
             let up_to = min(last_block_number, BlockNumber(state_marker.0 + max_stream_size as u64));
             debug!("Downloading state diffs [{} - {}).", state_marker, up_to);
-            let state_diff_stream =
-                central_source.stream_state_updates(state_marker, up_to).fuse();
+            let state_diff_stream = download_in_tasks(
+                central_source.clone(),
+                state_marker,
+                up_to,
+                blocks_per_download_task.into(),
+                max_concurrent_download_tasks,
+                TCentralSource::stream_state_updates,
+            )
+            .fuse();
             pin_mut!(state_diff_stream);
 
             while let Some(maybe_state_diff) = state_diff_stream.next().await {
@@ -691,20 +929,71 @@ pub fn sort_state_diff(diff: &mut StateDiff) {
     }
 }
 
+// Compares the global root of the state tries after the given block with the state root in its
+// header.
+fn verify_state_root<Mode: TransactionKind>(
+    txn: &StorageTxn<'_, Mode>,
+    block_number: BlockNumber,
+) -> StateSyncResult {
+    let computed_state_root = txn
+        .get_state_trie_roots(block_number)?
+        .expect("The state tries should be updated with the block.")
+        .global_root();
+    let header_state_root = txn
+        .get_block_header(block_number)?
+        .ok_or(StorageError::DBInconsistency {
+            msg: format!("Missing the header of block {block_number} whose state diff is stored."),
+        })?
+        .state_root;
+    if header_state_root != computed_state_root {
+        return Err(StateSyncError::StateRootMismatch {
+            block_number,
+            header_state_root,
+            computed_state_root,
+        });
+    }
+    Ok(())
+}
+
+// Deletes the data of the last stored block in the given transaction. The body and the state diff
+// are reverted only if they were stored, since they may be behind the header.
+#[instrument(skip(txn), level = "debug", err)]
+fn revert_block(
+    txn: StorageTxn<'_, RW>,
+    block_number: BlockNumber,
+) -> StorageResult<StorageTxn<'_, RW>> {
+    debug!("Reverting block.");
+    let (txn, reverted_header) =
+        txn.try_revert_base_layer_marker(block_number)?.revert_header(block_number)?;
+    let Some(reverted_header) = reverted_header else {
+        return Ok(txn);
+    };
+    let (txn, _) = txn.revert_body(block_number)?;
+    let (txn, _) = txn.revert_state_diff(block_number)?;
+    info!(hash = %reverted_header.block_hash, "Reverted block.");
+    Ok(txn)
+}
+
 pub type StateSync = GenericStateSync<CentralSource, PendingSource, EthereumBaseLayerSource>;
 
-impl StateSync {
+impl<
+    TCentralSource: CentralSourceTrait + Sync + Send,
+    TPendingSource: PendingSourceTrait + Sync + Send,
+    TBaseLayerSource: BaseLayerSourceTrait + Sync + Send,
+> GenericStateSync<TCentralSource, TPendingSource, TBaseLayerSource>
+{
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: SyncConfig,
         shared_highest_block: Arc<RwLock<Option<BlockHashAndNumber>>>,
         pending_data: Arc<RwLock<PendingData>>,
         pending_classes: Arc<RwLock<PendingClasses>>,
-        central_source: CentralSource,
-        pending_source: PendingSource,
-        base_layer_source: EthereumBaseLayerSource,
+        central_source: TCentralSource,
+        pending_source: TPendingSource,
+        base_layer_source: TBaseLayerSource,
         reader: StorageReader,
         writer: StorageWriter,
+        new_block_sender: broadcast::Sender<BlockNumber>,
     ) -> Self {
         Self {
             config,
@@ -716,6 +1005,7 @@ impl StateSync {
             base_layer_source: Arc::new(base_layer_source),
             reader,
             writer,
+            new_block_sender,
         }
     }
 }
@@ -801,6 +1091,40 @@ fn stream_new_base_layer_block<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
     }
 }
 
+// Streams the L1 to L2 messages of the base layer blocks from start_block, or from the first block
+// whose messages weren't stored yet if it's later.
+fn stream_l1_to_l2_messages<TBaseLayerSource: BaseLayerSourceTrait + Sync>(
+    reader: StorageReader,
+    base_layer_source: Arc<TBaseLayerSource>,
+    base_layer_propagation_sleep_duration: Duration,
+    start_block: u64,
+) -> impl Stream<Item = Result<SyncEvent, StateSyncError>> {
+    try_stream! {
+        loop {
+            let from_block =
+                reader.begin_ro_txn()?.get_l1_to_l2_messages_marker()?.0.max(start_block);
+            let latest_block = base_layer_source
+                .latest_block_number(L1_TO_L2_MESSAGES_MIN_CONFIRMATIONS)
+                .await?;
+            let Some(latest_block) = latest_block.filter(|latest_block| *latest_block >= from_block)
+            else {
+                debug!(
+                    "Waiting for base layer block {from_block} to get the L1 to L2 messages sent \
+                     in it."
+                );
+                tokio::time::sleep(base_layer_propagation_sleep_duration).await;
+                continue;
+            };
+            let to_block = min(latest_block, from_block + L1_TO_L2_MESSAGES_MAX_BLOCK_RANGE - 1);
+            let messages = base_layer_source.l1_to_l2_messages(from_block, to_block).await?;
+            yield SyncEvent::L1ToL2MessagesAvailable {
+                messages,
+                next_base_layer_block: to_block + 1,
+            };
+        }
+    }
+}
+
 // This function is used to check if the sync is stuck.
 // TODO: fix the bug and remove this function.
 // TODO(dvir): add a test for this scenario.
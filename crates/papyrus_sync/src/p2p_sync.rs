@@ -0,0 +1,451 @@
+//! Syncs the block headers and the state diffs from the peers of the node in the p2p network. The
+//! headers are synced first, and the state diffs follow them, with the definitions of the classes
+//! they declare. A state diff is verified against the state root in the header of its block, so the
+//! state tries are updated with every state diff synced here, and state diffs are synced only if
+//! the tries are up to date with the stored state diffs. The protocol doesn't carry the
+//! transactions of the blocks, so once the peers stop providing new blocks, the node continues with
+//! the central sync, which fills in the bodies and the compiled classes of the blocks synced here.
+//! If the central source doesn't agree with a block synced here, the central sync reverts it.
+#[cfg(test)]
+#[path = "p2p_sync_test.rs"]
+mod p2p_sync_test;
+
+use std::cmp::min;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use futures_util::{StreamExt, TryStreamExt};
+use indexmap::IndexMap;
+use papyrus_common::metrics as papyrus_metrics;
+use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use papyrus_network::network_manager::QueryError;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::state_trie::{StateTrieStorageReader, StateTrieStorageWriter};
+use papyrus_storage::{StorageError, StorageReader, StorageWriter};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ClassHash, GlobalRoot};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::{StarkFelt, GENESIS_HASH};
+use starknet_api::stark_felt;
+use starknet_api::state::{ContractClass, StateDiff, StateNumber, ThinStateDiff};
+use tracing::{debug, info, warn};
+
+use crate::sort_state_diff;
+use crate::sources::p2p::{P2PSource, P2PSourceError, P2PSourceTrait};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct P2PSyncConfig {
+    pub num_headers_per_query: u64,
+    pub num_state_diffs_per_query: u64,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub query_sleep_duration: Duration,
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    pub no_progress_timeout: Duration,
+}
+
+impl SerializeConfig for P2PSyncConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "num_headers_per_query",
+                &self.num_headers_per_query,
+                "The maximum amount of headers to ask from peers in each iteration.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "num_state_diffs_per_query",
+                &self.num_state_diffs_per_query,
+                "The maximum amount of state diffs to ask from peers in each iteration.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "query_sleep_duration",
+                &self.query_sleep_duration.as_secs(),
+                "Time in seconds to wait before querying the peers again, after they had no new \
+                 blocks or after a query failed.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "no_progress_timeout",
+                &self.no_progress_timeout.as_secs(),
+                "Time in seconds without a new header or state diff from the peers after which \
+                 the node falls back to the central sync.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl Default for P2PSyncConfig {
+    fn default() -> Self {
+        P2PSyncConfig {
+            num_headers_per_query: 10000,
+            num_state_diffs_per_query: 1000,
+            query_sleep_duration: Duration::from_secs(2),
+            no_progress_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum P2PSyncError {
+    #[error(
+        "No header or state diff was synced from the peers for {} seconds.",
+        no_progress_timeout.as_secs()
+    )]
+    NoProgress { no_progress_timeout: Duration },
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error(transparent)]
+    P2PSourceError(#[from] P2PSourceError),
+    #[error(
+        "The state diff from the peer declares class {class_hash} with another Cairo version."
+    )]
+    ClassVersionMismatch { class_hash: ClassHash },
+    #[error(
+        "The state root of block {block_number} doesn't match the state diff from the peer. \
+         Header state root: {header_state_root}, computed state root: {computed_state_root}."
+    )]
+    StateRootMismatch {
+        block_number: BlockNumber,
+        header_state_root: GlobalRoot,
+        computed_state_root: GlobalRoot,
+    },
+}
+
+pub struct GenericP2PSync<TP2PSource: P2PSourceTrait + Sync + Send> {
+    config: P2PSyncConfig,
+    source: TP2PSource,
+    reader: StorageReader,
+    writer: StorageWriter,
+}
+
+pub type P2PSync = GenericP2PSync<P2PSource>;
+
+impl<TP2PSource: P2PSourceTrait + Sync + Send> GenericP2PSync<TP2PSource> {
+    pub fn new(
+        config: P2PSyncConfig,
+        source: TP2PSource,
+        reader: StorageReader,
+        writer: StorageWriter,
+    ) -> Self {
+        Self { config, source, reader, writer }
+    }
+
+    /// Syncs the block headers and the state diffs from the peers. Returns only with an error, for
+    /// example when no block data was synced for the configured timeout.
+    pub async fn run(&mut self) -> Result<(), P2PSyncError> {
+        info!("Syncing the block headers and state diffs from the peers of the node.");
+        let sync_state_diffs = state_tries_are_up_to_date(&self.reader)?;
+        if !sync_state_diffs {
+            info!(
+                "The state tries aren't up to date with the stored state diffs, so the state \
+                 diffs aren't synced from the peers."
+            );
+        }
+        let mut last_progress = Instant::now();
+        loop {
+            let mut made_progress = self.sync_headers().await?;
+            if sync_state_diffs {
+                made_progress |= self.sync_state_diffs().await?;
+            }
+            if made_progress {
+                last_progress = Instant::now();
+            } else if last_progress.elapsed() >= self.config.no_progress_timeout {
+                return Err(P2PSyncError::NoProgress {
+                    no_progress_timeout: self.config.no_progress_timeout,
+                });
+            } else {
+                tokio::time::sleep(self.config.query_sleep_duration).await;
+            }
+        }
+    }
+
+    // Syncs the headers of the blocks after the stored headers. Returns whether any header was
+    // stored.
+    async fn sync_headers(&mut self) -> Result<bool, P2PSyncError> {
+        let (header_marker, parent_hash) = get_last_synced_block(&self.reader)?;
+        debug!("Querying the peers for the headers from block {header_marker}.");
+        let mut headers_stream = self.source.stream_headers(
+            header_marker,
+            parent_hash,
+            self.config.num_headers_per_query,
+        );
+        let mut stored_headers = false;
+        while let Some(maybe_header) = headers_stream.next().await {
+            match maybe_header {
+                Ok(header) => {
+                    store_header(&mut self.writer, header)?;
+                    stored_headers = true;
+                }
+                Err(P2PSourceError::Query(QueryError::NoPeers)) => {
+                    debug!("No peer is connected, waiting for peers to connect.");
+                    break;
+                }
+                // A peer that sent invalid headers is banned, and the network lowers the score of
+                // a peer whose session failed, so the next query is likely to be sent to another
+                // peer.
+                Err(err) => {
+                    warn!("Failed to get the block headers from a peer: {err}.");
+                    break;
+                }
+            }
+        }
+        Ok(stored_headers)
+    }
+
+    // Syncs the state diffs of the blocks whose headers are stored. Returns whether any state diff
+    // was stored.
+    async fn sync_state_diffs(&mut self) -> Result<bool, P2PSyncError> {
+        let (state_marker, headers) =
+            get_headers_without_state_diffs(&self.reader, self.config.num_state_diffs_per_query)?;
+        if headers.is_empty() {
+            return Ok(false);
+        }
+        debug!("Querying the peers for the state diffs from block {state_marker}.");
+        let block_hashes = headers.iter().map(|header| header.block_hash).collect();
+        let mut state_diffs_stream = self.source.stream_state_diffs(state_marker, block_hashes);
+        let mut headers = headers.into_iter();
+        let mut stored_state_diffs = false;
+        while let Some(maybe_state_diff) = state_diffs_stream.next().await {
+            let (thin_state_diff, outbound_session_id) = match maybe_state_diff {
+                Ok(state_diff_and_session) => state_diff_and_session,
+                Err(P2PSourceError::Query(QueryError::NoPeers)) => {
+                    debug!("No peer is connected, waiting for peers to connect.");
+                    break;
+                }
+                Err(err) => {
+                    warn!("Failed to get the state diffs from a peer: {err}.");
+                    break;
+                }
+            };
+            let header = headers.next().expect("The source returns a state diff for each block.");
+            let stored_state_diff = complete_state_diff(
+                &self.source,
+                &self.reader,
+                header.block_number,
+                thin_state_diff,
+            )
+            .await
+            .and_then(|(state_diff, deployed_contract_class_definitions)| {
+                store_state_diff(
+                    &mut self.writer,
+                    &header,
+                    state_diff,
+                    deployed_contract_class_definitions,
+                )
+            });
+            match stored_state_diff {
+                Ok(()) => stored_state_diffs = true,
+                Err(P2PSyncError::StorageError(err)) => return Err(err.into()),
+                Err(err) => {
+                    // The classes are received in other sessions, possibly from other peers, so
+                    // only a state diff that contradicts the header of its block bans its peer.
+                    if let P2PSyncError::StateRootMismatch { .. } = err {
+                        if let Err(report_err) =
+                            self.source.report_state_diffs_session(outbound_session_id)
+                        {
+                            warn!("Failed to report the peer of the state diff: {report_err}.");
+                        }
+                    }
+                    warn!("Failed to sync the state diff of block {}: {err}.", header.block_number);
+                    break;
+                }
+            }
+        }
+        Ok(stored_state_diffs)
+    }
+
+    /// Returns the storage writer, so that another sync can continue from where this one stopped.
+    pub fn into_storage_writer(self) -> StorageWriter {
+        self.writer
+    }
+}
+
+// Returns the number of the next block to sync, and the hash of the block before it, which the
+// parent hash of the next block must match.
+fn get_last_synced_block(reader: &StorageReader) -> Result<(BlockNumber, BlockHash), P2PSyncError> {
+    let txn = reader.begin_ro_txn()?;
+    let header_marker = txn.get_header_marker()?;
+    let Some(last_block_number) = header_marker.prev() else {
+        return Ok((header_marker, BlockHash(stark_felt!(GENESIS_HASH))));
+    };
+    let last_block_hash = txn
+        .get_block_header(last_block_number)?
+        .ok_or(StorageError::DBInconsistency {
+            msg: format!("Missing the header of block {last_block_number}."),
+        })?
+        .block_hash;
+    Ok((header_marker, last_block_hash))
+}
+
+// The source verifies that the header continues the stored chain.
+fn store_header(writer: &mut StorageWriter, header: BlockHeader) -> Result<(), P2PSyncError> {
+    let block_number = header.block_number;
+    writer.begin_rw_txn()?.append_header(block_number, &header)?.commit()?;
+    metrics::gauge!(papyrus_metrics::PAPYRUS_HEADER_MARKER, block_number.next().0 as f64);
+    debug!("Stored the header of block {block_number} with hash {}.", header.block_hash);
+    Ok(())
+}
+
+// Returns whether every stored state diff was applied to the state tries, which is required to
+// verify the state diffs from the peers.
+fn state_tries_are_up_to_date(reader: &StorageReader) -> Result<bool, P2PSyncError> {
+    let txn = reader.begin_ro_txn()?;
+    Ok(txn.get_state_trie_marker()? == txn.get_state_marker()?)
+}
+
+// Returns the state marker, and the headers of the blocks from it whose state diffs aren't stored,
+// up to the given number of headers.
+fn get_headers_without_state_diffs(
+    reader: &StorageReader,
+    limit: u64,
+) -> Result<(BlockNumber, Vec<BlockHeader>), P2PSyncError> {
+    let txn = reader.begin_ro_txn()?;
+    let state_marker = txn.get_state_marker()?;
+    let end_block_number = min(txn.get_header_marker()?.0, state_marker.0.saturating_add(limit));
+    let headers = (state_marker.0..end_block_number)
+        .map(|block_number| {
+            let block_number = BlockNumber(block_number);
+            txn.get_block_header(block_number)?.ok_or(StorageError::DBInconsistency {
+                msg: format!("Missing the header of block {block_number}."),
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((state_marker, headers))
+}
+
+// Completes a state diff from the peers with the definitions of its classes, and returns it with
+// the definitions of the classes that its contracts were deployed with without declaring them,
+// which was possible before Starknet 0.11. The state diffs from the peers don't tell a deployed
+// contract apart from a contract whose class was replaced, so a class hash of a contract that was
+// already deployed is a replaced class.
+async fn complete_state_diff<TP2PSource: P2PSourceTrait>(
+    source: &TP2PSource,
+    reader: &StorageReader,
+    block_number: BlockNumber,
+    thin_state_diff: ThinStateDiff,
+) -> Result<(StateDiff, IndexMap<ClassHash, DeprecatedContractClass>), P2PSyncError> {
+    let mut deployed_contracts = IndexMap::new();
+    let mut replaced_classes = IndexMap::new();
+    let mut undeclared_class_hashes = Vec::new();
+    {
+        let txn = reader.begin_ro_txn()?;
+        let state_reader = txn.get_state_reader()?;
+        let state_number = StateNumber::right_before_block(block_number);
+        for (address, class_hash) in thin_state_diff.deployed_contracts {
+            if state_reader.get_class_hash_at(state_number, &address)?.is_some() {
+                replaced_classes.insert(address, class_hash);
+                continue;
+            }
+            deployed_contracts.insert(address, class_hash);
+            let is_declared = thin_state_diff.declared_classes.contains_key(&class_hash)
+                || thin_state_diff.deprecated_declared_classes.contains(&class_hash)
+                || undeclared_class_hashes.contains(&class_hash)
+                || state_reader.get_class_definition_block_number(&class_hash)?.is_some()
+                || state_reader
+                    .get_deprecated_class_definition_block_number(&class_hash)?
+                    .is_some();
+            if !is_declared {
+                undeclared_class_hashes.push(class_hash);
+            }
+        }
+    }
+
+    let class_hashes = thin_state_diff
+        .declared_classes
+        .keys()
+        .chain(&thin_state_diff.deprecated_declared_classes)
+        .chain(&undeclared_class_hashes)
+        .copied()
+        .collect();
+    let mut classes: HashMap<ClassHash, ApiContractClass> =
+        source.stream_classes(class_hashes).try_collect().await?;
+    let declared_classes = thin_state_diff
+        .declared_classes
+        .into_iter()
+        .map(|(class_hash, compiled_class_hash)| {
+            Ok((class_hash, (compiled_class_hash, take_class(&mut classes, class_hash)?)))
+        })
+        .collect::<Result<_, P2PSyncError>>()?;
+    let deprecated_declared_classes = thin_state_diff
+        .deprecated_declared_classes
+        .into_iter()
+        .map(|class_hash| Ok((class_hash, take_deprecated_class(&mut classes, class_hash)?)))
+        .collect::<Result<_, P2PSyncError>>()?;
+    let deployed_contract_class_definitions = undeclared_class_hashes
+        .into_iter()
+        .map(|class_hash| Ok((class_hash, take_deprecated_class(&mut classes, class_hash)?)))
+        .collect::<Result<_, P2PSyncError>>()?;
+
+    let mut state_diff = StateDiff {
+        deployed_contracts,
+        storage_diffs: thin_state_diff.storage_diffs,
+        declared_classes,
+        deprecated_declared_classes,
+        nonces: thin_state_diff.nonces,
+        replaced_classes,
+    };
+    sort_state_diff(&mut state_diff);
+    Ok((state_diff, deployed_contract_class_definitions))
+}
+
+fn take_class(
+    classes: &mut HashMap<ClassHash, ApiContractClass>,
+    class_hash: ClassHash,
+) -> Result<ContractClass, P2PSyncError> {
+    match classes.remove(&class_hash) {
+        Some(ApiContractClass::ContractClass(class)) => Ok(class),
+        Some(ApiContractClass::DeprecatedContractClass(_)) => {
+            Err(P2PSyncError::ClassVersionMismatch { class_hash })
+        }
+        None => Err(P2PSourceError::MissingClass { class_hash }.into()),
+    }
+}
+
+fn take_deprecated_class(
+    classes: &mut HashMap<ClassHash, ApiContractClass>,
+    class_hash: ClassHash,
+) -> Result<DeprecatedContractClass, P2PSyncError> {
+    match classes.remove(&class_hash) {
+        Some(ApiContractClass::DeprecatedContractClass(class)) => Ok(class),
+        Some(ApiContractClass::ContractClass(_)) => {
+            Err(P2PSyncError::ClassVersionMismatch { class_hash })
+        }
+        None => Err(P2PSourceError::MissingClass { class_hash }.into()),
+    }
+}
+
+// The state diff is stored only if it leads to the state root in the header of its block.
+fn store_state_diff(
+    writer: &mut StorageWriter,
+    header: &BlockHeader,
+    state_diff: StateDiff,
+    deployed_contract_class_definitions: IndexMap<ClassHash, DeprecatedContractClass>,
+) -> Result<(), P2PSyncError> {
+    let block_number = header.block_number;
+    let txn = writer
+        .begin_rw_txn()?
+        .append_state_diff(block_number, state_diff, deployed_contract_class_definitions)?
+        .update_state_tries(block_number)?;
+    let computed_state_root = txn
+        .get_state_trie_roots(block_number)?
+        .expect("The state tries should be updated with the block.")
+        .global_root();
+    if computed_state_root != header.state_root {
+        return Err(P2PSyncError::StateRootMismatch {
+            block_number,
+            header_state_root: header.state_root,
+            computed_state_root,
+        });
+    }
+    txn.commit()?;
+    metrics::gauge!(papyrus_metrics::PAPYRUS_STATE_MARKER, block_number.next().0 as f64);
+    debug!("Stored the state diff of block {block_number}.");
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use async_stream::stream;
+use futures::stream::BoxStream;
+use futures_util::StreamExt;
+use indexmap::{indexmap, IndexMap};
+use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_network::network_manager::QueryError;
+use papyrus_network::streamed_data::OutboundSessionId;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter};
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::state_trie::{StateTrieStorageReader, StateTrieStorageWriter};
+use papyrus_storage::test_utils::get_test_storage;
+use papyrus_storage::StorageWriter;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    GlobalRoot,
+    Nonce,
+    PatriciaKey,
+};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::{StarkFelt, StarkHash, GENESIS_HASH};
+use starknet_api::state::{ContractClass, StateDiff, StorageKey, ThinStateDiff};
+use starknet_api::{patricia_key, stark_felt};
+
+use super::{GenericP2PSync, P2PSyncConfig, P2PSyncError};
+use crate::sources::p2p::{MockP2PSourceTrait, P2PSourceError};
+
+const N_BLOCKS: u64 = 5;
+
+fn config() -> P2PSyncConfig {
+    P2PSyncConfig {
+        num_headers_per_query: N_BLOCKS,
+        num_state_diffs_per_query: N_BLOCKS,
+        query_sleep_duration: Duration::ZERO,
+        no_progress_timeout: Duration::ZERO,
+    }
+}
+
+fn block_hash(block_number: u64) -> BlockHash {
+    BlockHash(StarkHash::from(block_number + 1))
+}
+
+fn header(block_number: u64) -> BlockHeader {
+    BlockHeader {
+        block_number: BlockNumber(block_number),
+        block_hash: block_hash(block_number),
+        parent_hash: block_number
+            .checked_sub(1)
+            .map(block_hash)
+            .unwrap_or(BlockHash(stark_felt!(GENESIS_HASH))),
+        ..Default::default()
+    }
+}
+
+fn no_peers_stream<T>() -> BoxStream<'static, Result<T, P2PSourceError>> {
+    stream! { yield Err(P2PSourceError::Query(QueryError::NoPeers)); }.boxed()
+}
+
+#[tokio::test]
+async fn sync_stores_headers_until_no_progress() {
+    let ((reader, writer), _temp_dir) = get_test_storage();
+    let mut source = MockP2PSourceTrait::new();
+    source
+        .expect_stream_headers()
+        .withf(|initial_block_number, parent_hash, limit| {
+            *initial_block_number == BlockNumber(0)
+                && *parent_hash == BlockHash(stark_felt!(GENESIS_HASH))
+                && *limit == N_BLOCKS
+        })
+        .times(1)
+        .returning(|_, _, _| stream! { for i in 0..N_BLOCKS { yield Ok(header(i)); } }.boxed());
+    source.expect_stream_headers().returning(|_, _, _| no_peers_stream());
+    source.expect_stream_state_diffs().returning(|_, _| no_peers_stream());
+
+    let mut p2p_sync = GenericP2PSync::new(config(), source, reader.clone(), writer);
+    assert_matches!(p2p_sync.run().await, Err(P2PSyncError::NoProgress { .. }));
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(N_BLOCKS));
+    for i in 0..N_BLOCKS {
+        assert_eq!(txn.get_block_header(BlockNumber(i)).unwrap().unwrap(), header(i));
+    }
+}
+
+#[tokio::test]
+async fn sync_continues_after_a_peer_sends_invalid_headers() {
+    let ((reader, writer), _temp_dir) = get_test_storage();
+    let mut source = MockP2PSourceTrait::new();
+    // The first peer sends a header that doesn't continue the chain, and the next query continues
+    // from the last valid header.
+    source
+        .expect_stream_headers()
+        .withf(|initial_block_number, _, _| *initial_block_number == BlockNumber(0))
+        .times(1)
+        .returning(|_, _, _| {
+            stream! {
+                yield Ok(header(0));
+                yield Err(P2PSourceError::ParentBlockHashMismatch {
+                    block_number: BlockNumber(1),
+                    parent_hash: BlockHash::default(),
+                    previous_block_hash: block_hash(0),
+                });
+            }
+            .boxed()
+        });
+    source
+        .expect_stream_headers()
+        .withf(|initial_block_number, parent_hash, _| {
+            *initial_block_number == BlockNumber(1) && *parent_hash == block_hash(0)
+        })
+        .times(1)
+        .returning(|_, _, _| stream! { for i in 1..N_BLOCKS { yield Ok(header(i)); } }.boxed());
+    source.expect_stream_headers().returning(|_, _, _| no_peers_stream());
+    source.expect_stream_state_diffs().returning(|_, _| no_peers_stream());
+
+    let config = P2PSyncConfig {
+        query_sleep_duration: Duration::from_millis(10),
+        no_progress_timeout: Duration::from_secs(1),
+        ..config()
+    };
+    let mut p2p_sync = GenericP2PSync::new(config, source, reader.clone(), writer);
+    assert_matches!(p2p_sync.run().await, Err(P2PSyncError::NoProgress { .. }));
+    assert_eq!(reader.begin_ro_txn().unwrap().get_header_marker().unwrap(), BlockNumber(N_BLOCKS));
+}
+
+fn address() -> ContractAddress {
+    ContractAddress(patricia_key!("0x1"))
+}
+
+// The state diffs of the blocks, as they're stored. The contract is deployed in the first block,
+// and its class is replaced in the second one.
+fn state_diffs() -> Vec<StateDiff> {
+    vec![
+        StateDiff {
+            deployed_contracts: indexmap! { address() => ClassHash(stark_felt!("0x10")) },
+            storage_diffs: indexmap! {
+                address() => indexmap! { StorageKey(patricia_key!("0x2")) => stark_felt!("0x3") },
+            },
+            deprecated_declared_classes: indexmap! {
+                ClassHash(stark_felt!("0x10")) => DeprecatedContractClass::default(),
+            },
+            ..Default::default()
+        },
+        StateDiff {
+            declared_classes: indexmap! {
+                ClassHash(stark_felt!("0x20")) =>
+                    (CompiledClassHash(stark_felt!("0x21")), ContractClass::default()),
+            },
+            nonces: indexmap! { address() => Nonce(stark_felt!("0x1")) },
+            replaced_classes: indexmap! { address() => ClassHash(stark_felt!("0x20")) },
+            ..Default::default()
+        },
+    ]
+}
+
+// The state diff as it's received from the peers, which don't tell a replaced class apart from a
+// deployed contract.
+fn peer_state_diff(state_diff: StateDiff) -> ThinStateDiff {
+    let (mut thin_state_diff, _, _) = ThinStateDiff::from_state_diff(state_diff);
+    let replaced_classes = std::mem::take(&mut thin_state_diff.replaced_classes);
+    thin_state_diff.deployed_contracts.extend(replaced_classes);
+    thin_state_diff
+}
+
+fn peer_class(class_hash: ClassHash) -> ApiContractClass {
+    if class_hash == ClassHash(stark_felt!("0x20")) {
+        ApiContractClass::ContractClass(ContractClass::default())
+    } else {
+        ApiContractClass::DeprecatedContractClass(DeprecatedContractClass::default())
+    }
+}
+
+// Returns the state roots after each of the state diffs.
+fn state_roots(state_diffs: &[StateDiff]) -> Vec<GlobalRoot> {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    for (block_number, state_diff) in state_diffs.iter().enumerate() {
+        let block_number = BlockNumber(block_number as u64);
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(block_number, state_diff.clone(), IndexMap::new())
+            .unwrap()
+            .update_state_tries(block_number)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+    let txn = reader.begin_ro_txn().unwrap();
+    (0..state_diffs.len())
+        .map(|block_number| {
+            txn.get_state_trie_roots(BlockNumber(block_number as u64))
+                .unwrap()
+                .unwrap()
+                .global_root()
+        })
+        .collect()
+}
+
+fn store_headers(writer: &mut StorageWriter, state_roots: &[GlobalRoot]) {
+    for (block_number, state_root) in state_roots.iter().enumerate() {
+        let header = BlockHeader { state_root: *state_root, ..header(block_number as u64) };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(header.block_number, &header)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn sync_stores_state_diffs_with_their_classes() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let state_diffs = state_diffs();
+    store_headers(&mut writer, &state_roots(&state_diffs));
+
+    let mut source = MockP2PSourceTrait::new();
+    source.expect_stream_headers().returning(|_, _, _| no_peers_stream());
+    let peer_state_diffs = state_diffs.iter().cloned().map(peer_state_diff).collect::<Vec<_>>();
+    source
+        .expect_stream_state_diffs()
+        .withf(|initial_block_number, block_hashes| {
+            *initial_block_number == BlockNumber(0)
+                && *block_hashes == vec![block_hash(0), block_hash(1)]
+        })
+        .times(1)
+        .returning(move |_, _| {
+            let peer_state_diffs = peer_state_diffs.clone();
+            stream! {
+                for state_diff in peer_state_diffs {
+                    yield Ok((state_diff, OutboundSessionId::default()));
+                }
+            }
+            .boxed()
+        });
+    source.expect_stream_classes().times(2).returning(|class_hashes| {
+        stream! {
+            for class_hash in class_hashes {
+                yield Ok((class_hash, peer_class(class_hash)));
+            }
+        }
+        .boxed()
+    });
+
+    let mut p2p_sync = GenericP2PSync::new(config(), source, reader.clone(), writer);
+    assert_matches!(p2p_sync.run().await, Err(P2PSyncError::NoProgress { .. }));
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(2));
+    for (block_number, state_diff) in state_diffs.into_iter().enumerate() {
+        assert_eq!(
+            txn.get_state_diff(BlockNumber(block_number as u64)).unwrap().unwrap(),
+            ThinStateDiff::from_state_diff(state_diff).0
+        );
+    }
+}
+
+#[tokio::test]
+async fn state_diff_that_does_not_match_the_state_root_is_not_stored() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let state_diffs = state_diffs();
+    // The state root of the first block is of the state after the second block.
+    let state_roots = state_roots(&state_diffs);
+    store_headers(&mut writer, &[state_roots[1]]);
+
+    let mut source = MockP2PSourceTrait::new();
+    source.expect_stream_headers().returning(|_, _, _| no_peers_stream());
+    let peer_state_diff = peer_state_diff(state_diffs[0].clone());
+    source.expect_stream_state_diffs().times(1).returning(move |_, _| {
+        let peer_state_diff = peer_state_diff.clone();
+        stream! { yield Ok((peer_state_diff, OutboundSessionId::default())); }.boxed()
+    });
+    source.expect_stream_classes().returning(|class_hashes| {
+        stream! {
+            for class_hash in class_hashes {
+                yield Ok((class_hash, peer_class(class_hash)));
+            }
+        }
+        .boxed()
+    });
+    source.expect_report_state_diffs_session().times(1).returning(|_| Ok(()));
+
+    let mut p2p_sync = GenericP2PSync::new(config(), source, reader.clone(), writer);
+    assert_matches!(p2p_sync.run().await, Err(P2PSyncError::NoProgress { .. }));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_state_trie_marker().unwrap(), BlockNumber(0));
+}
@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use mockall::automock;
 use papyrus_base_layer::ethereum_base_layer_contract::EthereumBaseLayerContract;
 use papyrus_base_layer::BaseLayerContract;
+use papyrus_common::l1_to_l2_messages::L1ToL2Message;
 use starknet_api::block::{BlockHash, BlockNumber};
 
 pub type EthereumBaseLayerSource = EthereumBaseLayerContract;
@@ -25,6 +26,17 @@ pub trait BaseLayerSourceTrait {
     async fn latest_proved_block(
         &self,
     ) -> Result<Option<(BlockNumber, BlockHash)>, BaseLayerSourceError>;
+
+    async fn latest_block_number(
+        &self,
+        min_confirmations: u64,
+    ) -> Result<Option<u64>, BaseLayerSourceError>;
+
+    async fn l1_to_l2_messages(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<L1ToL2Message>, BaseLayerSourceError>;
 }
 
 #[async_trait]
@@ -40,4 +52,23 @@ impl<
             .await
             .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))
     }
+
+    async fn latest_block_number(
+        &self,
+        min_confirmations: u64,
+    ) -> Result<Option<u64>, BaseLayerSourceError> {
+        self.latest_block_number(Some(min_confirmations))
+            .await
+            .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))
+    }
+
+    async fn l1_to_l2_messages(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<L1ToL2Message>, BaseLayerSourceError> {
+        self.l1_to_l2_messages(from_block, to_block)
+            .await
+            .map_err(|e| BaseLayerSourceError::BaseLayerContractError(Box::new(e)))
+    }
 }
@@ -6,6 +6,7 @@ mod state_update_stream;
 use std::collections::{BTreeMap, HashMap};
 use std::num::NonZeroUsize;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use async_stream::stream;
 use async_trait::async_trait;
@@ -18,21 +19,38 @@ use lru::LruCache;
 #[cfg(test)]
 use mockall::automock;
 use papyrus_common::pending_classes::ApiContractClass;
-use papyrus_common::BlockHashAndNumber;
-use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
-use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig};
+use papyrus_common::raw_transaction::RawTransaction;
+use papyrus_common::transaction_hash::validate_transaction_hash;
+use papyrus_common::{BlockHashAndNumber, TransactionOptions};
+use papyrus_config::converters::{
+    deserialize_milliseconds_to_duration,
+    deserialize_optional_map,
+    serialize_optional_map,
+};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_optional_param,
+    ser_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_storage::header::StarknetVersion;
 use papyrus_storage::state::StateStorageReader;
 use papyrus_storage::{StorageError, StorageReader};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::{Block, BlockHash, BlockNumber, BlockSignature};
-use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::core::{ChainId, ClassHash, CompiledClassHash};
 use starknet_api::crypto::Signature;
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::StateDiff;
+use starknet_api::transaction::TransactionOffsetInBlock;
 use starknet_api::StarknetApiError;
-use starknet_client::reader::{ReaderClientError, StarknetFeederGatewayClient, StarknetReader};
+use starknet_client::reader::{
+    ReaderClientError,
+    StarknetFeederGatewayClient,
+    StarknetFeederGatewayClientConfig,
+    StarknetReader,
+};
 use starknet_client::{ClientCreationError, RetryConfig};
 use tracing::{debug, trace};
 
@@ -45,6 +63,10 @@ pub struct CentralSourceConfig {
     pub url: String,
     #[serde(deserialize_with = "deserialize_optional_map")]
     pub http_headers: Option<HashMap<String, String>>,
+    pub proxy_url: Option<String>,
+    pub max_response_size_bytes: usize,
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub response_cache_ttl: Duration,
     pub max_state_updates_to_download: usize,
     pub max_state_updates_to_store_in_memory: usize,
     pub max_classes_to_download: usize,
@@ -59,12 +81,17 @@ impl Default for CentralSourceConfig {
             concurrent_requests: 10,
             url: String::from("https://alpha-mainnet.starknet.io/"),
             http_headers: None,
+            proxy_url: None,
+            // 1 GiB.
+            max_response_size_bytes: 1 << 30,
+            response_cache_ttl: Duration::from_millis(200),
             max_state_updates_to_download: 20,
             max_state_updates_to_store_in_memory: 20,
             max_classes_to_download: 20,
             class_cache_size: 100,
             retry_config: RetryConfig {
                 retry_base_millis: 30,
+                backoff_multiplier: 30,
                 retry_max_delay_millis: 30000,
                 max_retries: 10,
             },
@@ -94,6 +121,21 @@ impl SerializeConfig for CentralSourceConfig {
                 "'k1:v1 k2:v2 ...' headers for SN-client.",
                 ParamPrivacyInput::Private,
             ),
+            ser_param(
+                "max_response_size_bytes",
+                &self.max_response_size_bytes,
+                "Maximum size in bytes of a response from Starknet feeder-gateway. Larger \
+                 responses are dropped while they're received instead of being kept in memory.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "response_cache_ttl",
+                &self.response_cache_ttl.as_millis(),
+                "Time in milliseconds during which a response from Starknet feeder-gateway is \
+                 reused for identical requests. Identical concurrent requests are sent once. 0 \
+                 disables the cache.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "max_state_updates_to_download",
                 &self.max_state_updates_to_download,
@@ -119,8 +161,19 @@ impl SerializeConfig for CentralSourceConfig {
                 ParamPrivacyInput::Public,
             ),
         ]);
-        chain!(self_params_dump, append_sub_config_name(self.retry_config.dump(), "retry_config"))
-            .collect()
+        chain!(
+            self_params_dump,
+            ser_optional_param(
+                &self.proxy_url,
+                String::new(),
+                "proxy_url",
+                "URL of an HTTP, HTTPS or SOCKS5 proxy for the requests to Starknet \
+                 feeder-gateway, for example socks5://127.0.0.1:1080.",
+                ParamPrivacyInput::Private,
+            ),
+            append_sub_config_name(self.retry_config.dump(), "retry_config"),
+        )
+        .collect()
     }
 }
 
@@ -153,6 +206,8 @@ pub enum CentralError {
     StorageError(#[from] StorageError),
     #[error("Wrong type of contract class")]
     BadContractClassType,
+    #[error("The central source doesn't serve the chain {}.", chain_id.0)]
+    ChainIdMismatch { chain_id: ChainId },
 }
 
 #[cfg_attr(test, automock)]
@@ -191,8 +246,16 @@ pub trait CentralSourceTrait {
     ) -> Result<CasmContractClass, CentralError>;
 }
 
-pub(crate) type BlocksStream<'a> =
-    BoxStream<'a, Result<(BlockNumber, Block, BlockSignature, StarknetVersion), CentralError>>;
+// The raw transactions are the transactions of the block that had fields unknown to the node, see
+// `starknet_client::reader::Block::raw_transactions`.
+type CentralBlock = (
+    BlockNumber,
+    Block,
+    BlockSignature,
+    StarknetVersion,
+    BTreeMap<TransactionOffsetInBlock, RawTransaction>,
+);
+pub(crate) type BlocksStream<'a> = BoxStream<'a, CentralResult<CentralBlock>>;
 type CentralStateUpdate =
     (BlockNumber, BlockHash, StateDiff, IndexMap<ClassHash, DeprecatedContractClass>);
 pub(crate) type StateUpdatesStream<'a> = BoxStream<'a, CentralResult<CentralStateUpdate>>;
@@ -266,8 +329,14 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
                 let maybe_central_block =
                     client_to_central_block(current_block_number, maybe_client_block);
                 match maybe_central_block {
-                    Ok((block, signature, version)) => {
-                        yield Ok((current_block_number, block, signature, version));
+                    Ok((block, signature, version, raw_transactions)) => {
+                        yield Ok((
+                            current_block_number,
+                            block,
+                            signature,
+                            version,
+                            raw_transactions,
+                        ));
                     }
                     Err(err) => {
                         yield (Err(err));
@@ -384,6 +453,40 @@ impl<TStarknetClient: StarknetReader + Send + Sync + 'static> CentralSourceTrait
     }
 }
 
+impl<TStarknetClient: StarknetReader + Send + Sync + 'static>
+    GenericCentralSource<TStarknetClient>
+{
+    /// Verifies that the central source serves the given chain. The hashes of transactions depend
+    /// on the chain id, so the hashes of the transactions of the genesis block are validated with
+    /// it. If there is no genesis block yet, or it has no transactions, there is nothing to verify.
+    pub async fn verify_chain_id(&self, chain_id: &ChainId) -> Result<(), CentralError> {
+        let genesis_block_number = BlockNumber(0);
+        let Some(genesis_block) =
+            self.starknet_client.block(genesis_block_number).await.map_err(Arc::new)?
+        else {
+            return Ok(());
+        };
+        let (genesis_block, _version) =
+            genesis_block.to_starknet_api_block_and_version().map_err(Arc::new)?;
+        for (transaction, transaction_hash) in
+            genesis_block.body.transactions.iter().zip(genesis_block.body.transaction_hashes)
+        {
+            if !validate_transaction_hash(
+                transaction,
+                &genesis_block_number,
+                chain_id,
+                transaction_hash,
+                &TransactionOptions::default(),
+            )
+            .map_err(Arc::new)?
+            {
+                return Err(CentralError::ChainIdMismatch { chain_id: chain_id.clone() });
+            }
+        }
+        Ok(())
+    }
+}
+
 fn client_to_central_block(
     current_block_number: BlockNumber,
     maybe_client_block: Result<
@@ -393,11 +496,17 @@ fn client_to_central_block(
         ),
         ReaderClientError,
     >,
-) -> CentralResult<(Block, BlockSignature, StarknetVersion)> {
+) -> CentralResult<(
+    Block,
+    BlockSignature,
+    StarknetVersion,
+    BTreeMap<TransactionOffsetInBlock, RawTransaction>,
+)> {
     match maybe_client_block {
-        Ok((Some(block), Some(signature_data))) => {
+        Ok((Some(mut block), Some(signature_data))) => {
             debug!("Received new block {current_block_number} with hash {}.", block.block_hash);
             trace!("Block: {block:#?}, signature data: {signature_data:#?}.");
+            let raw_transactions = std::mem::take(&mut block.raw_transactions);
             let (block, version) = block
                 .to_starknet_api_block_and_version()
                 .map_err(|err| CentralError::ClientError(Arc::new(err)))?;
@@ -408,6 +517,7 @@ fn client_to_central_block(
                     s: signature_data.signature[1],
                 }),
                 StarknetVersion(version),
+                raw_transactions,
             ))
         }
         Ok((None, Some(_))) => {
@@ -436,7 +546,13 @@ impl CentralSource {
     ) -> Result<CentralSource, ClientCreationError> {
         let starknet_client = StarknetFeederGatewayClient::new(
             &config.url,
-            config.http_headers,
+            StarknetFeederGatewayClientConfig {
+                http_headers: config.http_headers,
+                proxy_url: config.proxy_url,
+                max_response_size_bytes: Some(config.max_response_size_bytes),
+                response_cache_ttl: (!config.response_cache_ttl.is_zero())
+                    .then_some(config.response_cache_ttl),
+            },
             node_version,
             config.retry_config,
         )?;
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -20,7 +21,7 @@ use starknet_api::hash::StarkFelt;
 use starknet_api::stark_felt;
 use starknet_api::state::StateDiff;
 use starknet_client::reader::PendingData;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{broadcast, Mutex, RwLock};
 use tracing::{debug, error};
 
 use super::pending::MockPendingSourceTrait;
@@ -105,6 +106,10 @@ async fn run_sync(
             recoverable_error_sleep_duration: SYNC_SLEEP_DURATION,
             blocks_max_stream_size: STREAM_SIZE,
             state_updates_max_stream_size: STREAM_SIZE,
+            max_concurrent_download_tasks: 2,
+            blocks_per_download_task: 2,
+            l1_to_l2_messages_start_block: None,
+            maintain_state_tries: false,
         },
         shared_highest_block: Arc::new(RwLock::new(None)),
         pending_data: Arc::new(RwLock::new(PendingData::default())),
@@ -114,6 +119,7 @@ async fn run_sync(
         base_layer_source: Arc::new(base_layer),
         reader,
         writer,
+        new_block_sender: broadcast::channel(1).0,
     };
 
     state_sync.run().await?;
@@ -182,7 +188,8 @@ async fn sync_happy_flow() {
                     block_number,
                     Block { header, body: BlockBody::default() },
                     BlockSignature::default(),
-                    StarknetVersion(STARKNET_VERSION.to_string())
+                    StarknetVersion(STARKNET_VERSION.to_string()),
+                    BTreeMap::new(),
                 ));
             }
         }
@@ -470,6 +477,7 @@ async fn sync_with_revert() {
                             Block{ header, body: BlockBody::default() },
                             BlockSignature::default(),
                             StarknetVersion(STARKNET_VERSION.to_string()),
+                            BTreeMap::new(),
                         ));
                     }
                 }
@@ -489,7 +497,8 @@ async fn sync_with_revert() {
                             i,
                             Block{header, body: BlockBody::default()},
                             BlockSignature::default(),
-                            StarknetVersion(STARKNET_VERSION.to_string())
+                            StarknetVersion(STARKNET_VERSION.to_string()),
+                            BTreeMap::new(),
                         ));
                     }
                 }
@@ -590,6 +599,7 @@ async fn test_unrecoverable_sync_error_flow() {
                 Block { header, body: BlockBody::default()},
                 BlockSignature::default(),
                 StarknetVersion(STARKNET_VERSION.to_string()),
+                BTreeMap::new(),
             ));
         }
         .boxed();
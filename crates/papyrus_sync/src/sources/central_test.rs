@@ -7,12 +7,15 @@ use futures_util::pin_mut;
 use indexmap::{indexmap, IndexMap};
 use lru::LruCache;
 use mockall::predicate;
+use papyrus_common::transaction_hash::get_transaction_hash;
+use papyrus_common::TransactionOptions;
 use papyrus_storage::state::StateStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use pretty_assertions::assert_eq;
 use reqwest::StatusCode;
 use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::{
+    ChainId,
     ClassHash,
     CompiledClassHash,
     ContractAddress,
@@ -23,7 +26,12 @@ use starknet_api::core::{
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::{ContractClass as sn_api_ContractClass, StateDiff, StorageKey};
+use starknet_api::transaction::Calldata;
 use starknet_api::{patricia_key, stark_felt};
+use starknet_client::reader::objects::transaction::{
+    L1HandlerTransaction,
+    Transaction as ClientTransaction,
+};
 use starknet_client::reader::{
     Block,
     BlockSignatureData,
@@ -36,6 +44,7 @@ use starknet_client::reader::{
     ReplacedClass,
     StateUpdate,
     StorageEntry,
+    TransactionReceipt,
 };
 use starknet_client::ClientError;
 use tokio_stream::StreamExt;
@@ -100,7 +109,7 @@ async fn stream_block_headers() {
     let stream =
         central_source.stream_new_blocks(expected_block_num, BlockNumber(END_BLOCK_NUMBER));
     pin_mut!(stream);
-    while let Some(Ok((block_number, _block, _signature_data, _starknet_version))) =
+    while let Some(Ok((block_number, _block, _signature_data, _starknet_version, _))) =
         stream.next().await
     {
         assert_eq!(expected_block_num, block_number);
@@ -570,6 +579,53 @@ async fn get_compiled_class() {
     assert_eq!(central_source.get_compiled_class(class_hash).await.unwrap(), compiled_class);
 }
 
+#[tokio::test]
+async fn verify_chain_id() {
+    let chain_id = ChainId("SN_MAIN".to_owned());
+    let mut transaction = L1HandlerTransaction {
+        contract_address: ContractAddress(patricia_key!("0x1")),
+        calldata: Calldata(Arc::new(vec![stark_felt!("0x2")])),
+        ..Default::default()
+    };
+    transaction.transaction_hash = get_transaction_hash(
+        &starknet_api::transaction::Transaction::L1Handler(transaction.clone().into()),
+        &chain_id,
+        &TransactionOptions::default(),
+    )
+    .unwrap();
+    let genesis_block = Block {
+        block_number: BlockNumber(0),
+        transaction_receipts: vec![TransactionReceipt {
+            transaction_hash: transaction.transaction_hash,
+            ..Default::default()
+        }],
+        transactions: vec![ClientTransaction::L1Handler(transaction)],
+        ..Default::default()
+    };
+
+    let mut mock = MockStarknetReader::new();
+    mock.expect_block()
+        .with(predicate::eq(BlockNumber(0)))
+        .times(2)
+        .returning(move |_| Ok(Some(genesis_block.clone())));
+    let ((reader, _), _temp_dir) = get_test_storage();
+    let central_source = GenericCentralSource {
+        concurrent_requests: TEST_CONCURRENT_REQUESTS,
+        starknet_client: Arc::new(mock),
+        storage_reader: reader,
+        state_update_stream_config: state_update_stream_config_for_test(),
+        class_cache: get_test_class_cache(),
+        compiled_class_cache: get_test_compiled_class_cache(),
+    };
+
+    central_source.verify_chain_id(&chain_id).await.unwrap();
+    let other_chain_id = ChainId("SN_GOERLI".to_owned());
+    assert_matches!(
+        central_source.verify_chain_id(&other_chain_id).await,
+        Err(CentralError::ChainIdMismatch { chain_id }) if chain_id == other_chain_id
+    );
+}
+
 fn state_update_stream_config_for_test() -> StateUpdateStreamConfig {
     StateUpdateStreamConfig {
         max_state_updates_to_download: 10,
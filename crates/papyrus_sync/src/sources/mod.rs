@@ -2,4 +2,5 @@ pub mod base_layer;
 pub mod central;
 #[cfg(test)]
 mod central_sync_test;
+pub mod p2p;
 pub mod pending;
@@ -0,0 +1,327 @@
+use async_stream::try_stream;
+use futures::stream::BoxStream;
+use futures_util::StreamExt;
+#[cfg(test)]
+use mockall::automock;
+use papyrus_common::block_hash::{validate_header_block_hash, BlockCommitments};
+use papyrus_common::pending_classes::ApiContractClass;
+use papyrus_network::converters::ProtobufConversionError;
+use papyrus_network::messages::protobuf::block_headers_response_part::HeaderMessage;
+use papyrus_network::messages::protobuf::classes_response::ClassMessage;
+use papyrus_network::messages::protobuf::state_diffs_response::StateDiffMessage;
+use papyrus_network::network_manager::{
+    BlockHeadersQuerier,
+    ClassesQuerier,
+    QueryError,
+    QuerySession,
+    StateDiffsQuerier,
+};
+use papyrus_network::streamed_data::OutboundSessionId;
+use papyrus_network::{
+    BlockID,
+    BlockQuery,
+    ClassesQuery,
+    Direction,
+    MAX_BLOCKS_PER_QUERY,
+    MAX_CLASSES_PER_QUERY,
+};
+use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ChainId, ClassHash};
+use starknet_api::state::ThinStateDiff;
+use starknet_api::StarknetApiError;
+
+pub type HeadersStream<'a> = BoxStream<'a, Result<BlockHeader, P2PSourceError>>;
+// Each state diff is returned with the session in which it was received.
+pub type StateDiffsStream<'a> =
+    BoxStream<'a, Result<(ThinStateDiff, OutboundSessionId), P2PSourceError>>;
+pub type ClassesStream<'a> = BoxStream<'a, Result<(ClassHash, ApiContractClass), P2PSourceError>>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum P2PSourceError {
+    #[error(transparent)]
+    Query(#[from] QueryError),
+    #[error(transparent)]
+    ProtobufConversion(#[from] ProtobufConversionError),
+    #[error("Expected block {expected_block_number} from the peer, got block {block_number}.")]
+    WrongBlockNumber { expected_block_number: BlockNumber, block_number: BlockNumber },
+    #[error("The peer sent the signatures of block {block_number} without its header.")]
+    SignaturesWithoutHeader { block_number: BlockNumber },
+    #[error(
+        "The parent hash of block {block_number} from the peer is {parent_hash}, but the hash of \
+         the previous block is {previous_block_hash}."
+    )]
+    ParentBlockHashMismatch {
+        block_number: BlockNumber,
+        parent_hash: BlockHash,
+        previous_block_hash: BlockHash,
+    },
+    #[error("The hash {block_hash} of block {block_number} from the peer is invalid.")]
+    InvalidBlockHash { block_number: BlockNumber, block_hash: BlockHash },
+    #[error(
+        "The peer sent the state diff of block {block_number} with hash {block_hash}, but the \
+         hash of the stored block is {stored_block_hash}."
+    )]
+    StoredBlockHashMismatch {
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        stored_block_hash: BlockHash,
+    },
+    #[error("Expected class {expected_class_hash} from the peer, got class {class_hash}.")]
+    WrongClassHash { expected_class_hash: ClassHash, class_hash: ClassHash },
+    #[error("The peer doesn't have class {class_hash}.")]
+    MissingClass { class_hash: ClassHash },
+    #[error(transparent)]
+    StarknetApi(#[from] StarknetApiError),
+}
+
+#[cfg_attr(test, automock)]
+pub trait P2PSourceTrait {
+    /// Returns the headers of the blocks from the given block, up to the given number of headers.
+    /// The headers must continue the chain of the block whose hash is `parent_hash`. The stream
+    /// ends earlier if the peer doesn't have more blocks, and fails if the session with the peer
+    /// fails or the peer sends invalid headers.
+    fn stream_headers(
+        &self,
+        initial_block_number: BlockNumber,
+        parent_hash: BlockHash,
+        limit: u64,
+    ) -> HeadersStream<'_>;
+
+    /// Returns the state diffs of the blocks from the given block, one for each of the given block
+    /// hashes, which are the hashes of the stored headers of these blocks. The stream ends earlier
+    /// if the peer doesn't have more state diffs, and fails if the session with the peer fails or
+    /// the peer sends a state diff of another block.
+    fn stream_state_diffs(
+        &self,
+        initial_block_number: BlockNumber,
+        block_hashes: Vec<BlockHash>,
+    ) -> StateDiffsStream<'_>;
+
+    /// Returns the definitions of the given classes, in the order of their hashes. Fails if the
+    /// peer doesn't have one of the classes.
+    fn stream_classes(&self, class_hashes: Vec<ClassHash>) -> ClassesStream<'_>;
+
+    /// Closes the session in which a state diff that contradicts the chain was received, and bans
+    /// its peer.
+    fn report_state_diffs_session(
+        &self,
+        outbound_session_id: OutboundSessionId,
+    ) -> Result<(), P2PSourceError>;
+}
+
+/// Gets the blocks of the given chain from the peers of the node in the p2p network.
+pub struct P2PSource {
+    pub headers_querier: BlockHeadersQuerier,
+    pub state_diffs_querier: StateDiffsQuerier,
+    pub classes_querier: ClassesQuerier,
+    pub chain_id: ChainId,
+}
+
+impl P2PSourceTrait for P2PSource {
+    // The peer sends the header of every block with the commitments to its body, followed by its
+    // signatures, which contain the hash of the block. The hash is verified against the header and
+    // the commitments, and a peer that sends a header that doesn't continue the chain or whose
+    // hash is invalid is reported, so it isn't queried again.
+    fn stream_headers(
+        &self,
+        initial_block_number: BlockNumber,
+        parent_hash: BlockHash,
+        limit: u64,
+    ) -> HeadersStream<'_> {
+        try_stream! {
+            let query = BlockQuery {
+                start: BlockID::Number(initial_block_number),
+                direction: Direction::Forward,
+                // Peers reject queries for more blocks.
+                limit: limit.min(MAX_BLOCKS_PER_QUERY),
+                skip: 0,
+                step: 1,
+            };
+            let QuerySession { outbound_session_id, mut responses } =
+                self.headers_querier.query(query).await?;
+            let mut expected_block_number = initial_block_number;
+            let mut previous_block_hash = parent_hash;
+            let mut header_without_hash: Option<(BlockHeader, BlockCommitments)> = None;
+            'responses: while let Some(response) = responses.next().await {
+                for part in response?.part {
+                    let header_message =
+                        part.header_message.ok_or(ProtobufConversionError::MissingField {
+                            field_description: "BlockHeadersResponsePart::header_message",
+                        })?;
+                    match header_message {
+                        HeaderMessage::Header(header) => {
+                            let commitments = BlockCommitments::try_from(&header)?;
+                            let header = BlockHeader::try_from(header)?;
+                            if header.block_number != expected_block_number {
+                                Err(P2PSourceError::WrongBlockNumber {
+                                    expected_block_number,
+                                    block_number: header.block_number,
+                                })?;
+                            }
+                            header_without_hash = Some((header, commitments));
+                        }
+                        HeaderMessage::Signatures(signatures) => {
+                            let block_id =
+                                signatures.block.ok_or(ProtobufConversionError::MissingField {
+                                    field_description: "Signatures::block",
+                                })?;
+                            let block_number = BlockNumber(block_id.number);
+                            let (mut header, commitments) = header_without_hash
+                                .take()
+                                .ok_or(P2PSourceError::SignaturesWithoutHeader { block_number })?;
+                            if block_number != header.block_number {
+                                Err(P2PSourceError::WrongBlockNumber {
+                                    expected_block_number: header.block_number,
+                                    block_number,
+                                })?;
+                            }
+                            header.block_hash = BlockHash(
+                                block_id
+                                    .header
+                                    .ok_or(ProtobufConversionError::MissingField {
+                                        field_description: "BlockID::header",
+                                    })?
+                                    .try_into()?,
+                            );
+                            if header.parent_hash != previous_block_hash {
+                                self.headers_querier.report_session(outbound_session_id)?;
+                                Err(P2PSourceError::ParentBlockHashMismatch {
+                                    block_number,
+                                    parent_hash: header.parent_hash,
+                                    previous_block_hash,
+                                })?;
+                            }
+                            if !validate_header_block_hash(&header, &commitments, &self.chain_id)? {
+                                self.headers_querier.report_session(outbound_session_id)?;
+                                Err(P2PSourceError::InvalidBlockHash {
+                                    block_number,
+                                    block_hash: header.block_hash,
+                                })?;
+                            }
+                            expected_block_number = expected_block_number.next();
+                            previous_block_hash = header.block_hash;
+                            yield header;
+                        }
+                        HeaderMessage::Fin(_) => break 'responses,
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+
+    // The peer sends the state diffs with the hashes of their blocks, which must be the hashes of
+    // the stored headers. The state diffs themselves are verified by the sync, against the state
+    // roots of the headers.
+    fn stream_state_diffs(
+        &self,
+        initial_block_number: BlockNumber,
+        block_hashes: Vec<BlockHash>,
+    ) -> StateDiffsStream<'_> {
+        try_stream! {
+            let query = BlockQuery {
+                start: BlockID::Number(initial_block_number),
+                direction: Direction::Forward,
+                // Peers reject queries for more blocks.
+                limit: (block_hashes.len() as u64).min(MAX_BLOCKS_PER_QUERY),
+                skip: 0,
+                step: 1,
+            };
+            let QuerySession { outbound_session_id, mut responses } =
+                self.state_diffs_querier.query(query).await?;
+            let mut expected_block_number = initial_block_number;
+            let mut stored_block_hashes = block_hashes.into_iter();
+            while let Some(response) = responses.next().await {
+                let response = response?;
+                let state_diff_message =
+                    response.state_diff_message.ok_or(ProtobufConversionError::MissingField {
+                        field_description: "StateDiffsResponse::state_diff_message",
+                    })?;
+                let StateDiffMessage::Diff(state_diff) = state_diff_message else {
+                    break;
+                };
+                // The peer sent more state diffs than were asked for.
+                let Some(stored_block_hash) = stored_block_hashes.next() else {
+                    break;
+                };
+                let block_id = response.block_id.ok_or(ProtobufConversionError::MissingField {
+                    field_description: "StateDiffsResponse::block_id",
+                })?;
+                let block_number = BlockNumber(block_id.number);
+                if block_number != expected_block_number {
+                    Err(P2PSourceError::WrongBlockNumber { expected_block_number, block_number })?;
+                }
+                let block_hash = BlockHash(
+                    block_id
+                        .header
+                        .ok_or(ProtobufConversionError::MissingField {
+                            field_description: "BlockID::header",
+                        })?
+                        .try_into()?,
+                );
+                if block_hash != stored_block_hash {
+                    self.state_diffs_querier.report_session(outbound_session_id)?;
+                    Err(P2PSourceError::StoredBlockHashMismatch {
+                        block_number,
+                        block_hash,
+                        stored_block_hash,
+                    })?;
+                }
+                expected_block_number = expected_block_number.next();
+                yield (ThinStateDiff::try_from(state_diff)?, outbound_session_id);
+            }
+        }
+        .boxed()
+    }
+
+    // Peers answer the classes in the order of their hashes, up to the first class they don't have.
+    fn stream_classes(&self, class_hashes: Vec<ClassHash>) -> ClassesStream<'_> {
+        try_stream! {
+            for class_hashes in class_hashes.chunks(MAX_CLASSES_PER_QUERY) {
+                let query = ClassesQuery { class_hashes: class_hashes.to_vec() };
+                let QuerySession { mut responses, .. } = self.classes_querier.query(query).await?;
+                let mut expected_class_hashes = class_hashes.iter();
+                while let Some(response) = responses.next().await {
+                    let response = response?;
+                    let class_message =
+                        response.class_message.ok_or(ProtobufConversionError::MissingField {
+                            field_description: "ClassesResponse::class_message",
+                        })?;
+                    let ClassMessage::Class(class) = class_message else {
+                        break;
+                    };
+                    // The peer sent more classes than were asked for.
+                    let Some(expected_class_hash) = expected_class_hashes.next() else {
+                        break;
+                    };
+                    let class_hash = ClassHash(
+                        response
+                            .class_hash
+                            .ok_or(ProtobufConversionError::MissingField {
+                                field_description: "ClassesResponse::class_hash",
+                            })?
+                            .try_into()?,
+                    );
+                    if class_hash != *expected_class_hash {
+                        Err(P2PSourceError::WrongClassHash {
+                            expected_class_hash: *expected_class_hash,
+                            class_hash,
+                        })?;
+                    }
+                    yield (class_hash, ApiContractClass::try_from(class)?);
+                }
+                if let Some(class_hash) = expected_class_hashes.next() {
+                    Err(P2PSourceError::MissingClass { class_hash: *class_hash })?;
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn report_state_diffs_session(
+        &self,
+        outbound_session_id: OutboundSessionId,
+    ) -> Result<(), P2PSourceError> {
+        Ok(self.state_diffs_querier.report_session(outbound_session_id)?)
+    }
+}
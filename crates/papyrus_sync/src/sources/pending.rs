@@ -11,6 +11,7 @@ use starknet_client::reader::{
     PendingData,
     ReaderClientError,
     StarknetFeederGatewayClient,
+    StarknetFeederGatewayClientConfig,
     StarknetReader,
 };
 use starknet_client::ClientCreationError;
@@ -59,7 +60,13 @@ impl PendingSource {
     ) -> Result<PendingSource, ClientCreationError> {
         let starknet_client = StarknetFeederGatewayClient::new(
             &config.url,
-            config.http_headers,
+            StarknetFeederGatewayClientConfig {
+                http_headers: config.http_headers,
+                proxy_url: config.proxy_url,
+                max_response_size_bytes: Some(config.max_response_size_bytes),
+                response_cache_ttl: (!config.response_cache_ttl.is_zero())
+                    .then_some(config.response_cache_ttl),
+            },
             node_version,
             config.retry_config,
         )?;
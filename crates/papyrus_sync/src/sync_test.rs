@@ -1,3 +1,5 @@
+use std::cmp::min;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -5,14 +7,26 @@ use assert_matches::assert_matches;
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 use futures_util::StreamExt;
 use indexmap::IndexMap;
+use papyrus_common::l1_to_l2_messages::{L1ToL2Message, L1TransactionHash};
 use papyrus_common::pending_classes::{ApiContractClass, PendingClasses, PendingClassesTrait};
 use papyrus_storage::base_layer::BaseLayerStorageReader;
-use papyrus_storage::header::HeaderStorageWriter;
+use papyrus_storage::body::BodyStorageReader;
+use papyrus_storage::header::{HeaderStorageReader, HeaderStorageWriter, StarknetVersion};
+use papyrus_storage::l1_to_l2_messages::L1ToL2MessagesStorageWriter;
+use papyrus_storage::state::{StateStorageReader, StateStorageWriter};
+use papyrus_storage::state_trie::{StateTrieStorageReader, StateTrieStorageWriter};
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_storage::{StorageReader, StorageWriter};
 use pretty_assertions::assert_eq;
-use starknet_api::block::{BlockHash, BlockHeader, BlockNumber};
-use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::block::{Block, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{
+    ClassHash,
+    CompiledClassHash,
+    ContractAddress,
+    GlobalRoot,
+    Nonce,
+    PatriciaKey,
+};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::hash::{StarkFelt, StarkHash, GENESIS_HASH};
 use starknet_api::state::{ContractClass, StateDiff, StorageKey};
@@ -22,14 +36,16 @@ use starknet_client::reader::objects::state::StateDiff as ClientStateDiff;
 use starknet_client::reader::objects::transaction::Transaction as ClientTransaction;
 use starknet_client::reader::{DeclaredClassHashEntry, PendingData};
 use test_utils::{get_rng, GetTestInstance};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
 use crate::sources::base_layer::MockBaseLayerSourceTrait;
 use crate::sources::central::MockCentralSourceTrait;
 use crate::sources::pending::MockPendingSourceTrait;
 use crate::{
     sort_state_diff,
+    stream_l1_to_l2_messages,
     stream_new_base_layer_block,
+    stream_new_state_diffs,
     sync_pending_data,
     GenericStateSync,
     StateSyncError,
@@ -165,6 +181,56 @@ async fn stream_new_base_layer_block_no_blocks_on_base_layer() {
     assert_matches!(event, SyncEvent::NewBaseLayerBlock { block_number: BlockNumber(1), .. });
 }
 
+#[tokio::test]
+async fn stream_l1_to_l2_messages_by_block_ranges() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let message =
+        L1ToL2Message { l1_transaction_hash: L1TransactionHash([1; 32]), ..Default::default() };
+
+    let mut mock = MockBaseLayerSourceTrait::new();
+    // In the first polling the start block isn't confirmed yet.
+    let mut latest_block_numbers = vec![None, Some(1500), Some(1500), Some(2500)].into_iter();
+    mock.expect_latest_block_number()
+        .times(4)
+        .returning(move |_| Ok(latest_block_numbers.next().unwrap()));
+    let returned_message = message.clone();
+    mock.expect_l1_to_l2_messages()
+        .withf(|from_block, to_block| *from_block == 100 && *to_block == 1099)
+        .times(1)
+        .returning(move |_, _| Ok(vec![returned_message.clone()]));
+    mock.expect_l1_to_l2_messages()
+        .withf(|from_block, to_block| *from_block == 1100 && *to_block == 1500)
+        .times(1)
+        .returning(|_, _| Ok(vec![]));
+    mock.expect_l1_to_l2_messages()
+        .withf(|from_block, to_block| *from_block == 1501 && *to_block == 2500)
+        .times(1)
+        .returning(|_, _| Ok(vec![]));
+
+    let mut stream =
+        stream_l1_to_l2_messages(reader, Arc::new(mock), Duration::from_millis(0), 100).boxed();
+    for (expected_messages, next_base_layer_block) in
+        [(vec![message], 1100), (vec![], 1501), (vec![], 2501)]
+    {
+        let event = stream.next().await.unwrap().unwrap();
+        let SyncEvent::L1ToL2MessagesAvailable { messages, next_base_layer_block: next_block } =
+            event
+        else {
+            panic!("Expected L1 to L2 messages, got {event:?}.");
+        };
+        assert_eq!(messages, expected_messages);
+        assert_eq!(next_block, next_base_layer_block);
+        // The stream continues from the stored marker.
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_l1_to_l2_messages(&messages, BlockNumber(next_block))
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
 #[test]
 fn store_base_layer_block_test() {
     let (reader, mut writer) = get_test_storage().0;
@@ -193,6 +259,7 @@ fn store_base_layer_block_test() {
         base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
         reader,
         writer,
+        new_block_sender: broadcast::channel(1).0,
     };
 
     // Trying to store a block without a header in the storage.
@@ -212,6 +279,300 @@ fn store_base_layer_block_test() {
     assert_eq!(base_layer_marker, BlockNumber(1));
 }
 
+#[test]
+fn store_state_diff_verifies_state_root() {
+    let address = ContractAddress(patricia_key!("0x11"));
+    let state_diff = StateDiff {
+        deployed_contracts: IndexMap::from([(address, ClassHash(stark_felt!("0x1")))]),
+        storage_diffs: IndexMap::from([(
+            address,
+            IndexMap::from([(StorageKey(patricia_key!("0x2")), stark_felt!("0x3"))]),
+        )]),
+        ..StateDiff::default()
+    };
+    // The state root of the state diff, computed in a separate storage.
+    let ((_, mut other_writer), _other_temp_dir) = get_test_storage();
+    let state_root = other_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff.clone(), IndexMap::new())
+        .unwrap()
+        .update_state_tries(BlockNumber(0))
+        .unwrap()
+        .get_state_trie_roots(BlockNumber(0))
+        .unwrap()
+        .unwrap()
+        .global_root();
+    assert_ne!(state_root, GlobalRoot::default());
+
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    // The state root of the second block is wrong, since its state diff doesn't change the state.
+    for (block_number, state_root) in [(0, state_root), (1, GlobalRoot::default())] {
+        let header = BlockHeader {
+            block_number: BlockNumber(block_number),
+            block_hash: BlockHash(block_number.into()),
+            state_root,
+            ..BlockHeader::default()
+        };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(BlockNumber(block_number), &header)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig { maintain_state_tries: true, ..SyncConfig::default() },
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        new_block_sender: broadcast::channel(1).0,
+    };
+
+    gen_state_sync
+        .store_state_diff(BlockNumber(0), BlockHash(0_u64.into()), state_diff, IndexMap::new())
+        .unwrap();
+    let res = gen_state_sync.store_state_diff(
+        BlockNumber(1),
+        BlockHash(1_u64.into()),
+        StateDiff::default(),
+        IndexMap::new(),
+    );
+    assert_matches!(
+        res,
+        Err(StateSyncError::StateRootMismatch {
+            block_number: BlockNumber(1),
+            header_state_root,
+            computed_state_root,
+        }) if header_state_root == GlobalRoot::default() && computed_state_root == state_root
+    );
+    // The state diff of the rejected block isn't stored.
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(1));
+    assert_eq!(txn.get_state_trie_marker().unwrap(), BlockNumber(1));
+}
+
+#[test]
+fn store_block_with_stored_header() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    // The headers of the first two blocks were already synced, for example from the peers.
+    add_headers(2, &mut writer);
+    let stored_header = reader.begin_ro_txn().unwrap().get_block_header(BlockNumber(0)).unwrap();
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        new_block_sender: broadcast::channel(1).0,
+    };
+    let starknet_version = StarknetVersion("0.12.3".to_owned());
+
+    // Only the body of a block with the stored header is stored.
+    let block = Block { header: stored_header.unwrap(), ..Block::default() };
+    gen_state_sync.store_block(BlockNumber(0), block, &starknet_version, BTreeMap::new()).unwrap();
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+    drop(txn);
+
+    // A block that doesn't match the stored header is rejected.
+    let block = Block {
+        header: BlockHeader {
+            block_number: BlockNumber(1),
+            block_hash: BlockHash(stark_felt!("0x666")),
+            // The hash of the first stored header.
+            parent_hash: BlockHash(0_u64.into()),
+            ..BlockHeader::default()
+        },
+        ..Block::default()
+    };
+    let res = gen_state_sync.store_block(BlockNumber(1), block, &starknet_version, BTreeMap::new());
+    assert_matches!(
+        res,
+        Err(StateSyncError::StoredHeaderMismatch { block_number: BlockNumber(1), .. })
+    );
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(1));
+}
+
+#[tokio::test]
+async fn state_diffs_are_streamed_for_stored_headers() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    // The headers were synced without the bodies, for example from the peers.
+    add_headers(5, &mut writer);
+
+    // The state diffs are downloaded by tasks of 2 blocks each, and the last task gets a single
+    // block.
+    let mut mock = MockCentralSourceTrait::new();
+    mock.expect_stream_state_updates()
+        .withf(|initial_block_number, up_to_block_number| {
+            initial_block_number.0 % 2 == 0
+                && *up_to_block_number == min(initial_block_number.next().next(), BlockNumber(5))
+        })
+        .times(3)
+        .returning(|initial_block_number, up_to_block_number| {
+            futures_util::stream::iter(initial_block_number.iter_up_to(up_to_block_number).map(
+                |block_number| {
+                    Ok((
+                        block_number,
+                        BlockHash(block_number.0.into()),
+                        StateDiff::default(),
+                        IndexMap::new(),
+                    ))
+                },
+            ))
+            .boxed()
+        });
+    let mut stream =
+        stream_new_state_diffs(reader, Arc::new(mock), Duration::ZERO, 10, 2, 2).boxed();
+
+    for i in 0..5 {
+        let event = stream.next().await.unwrap().unwrap();
+        assert_matches!(
+            event,
+            SyncEvent::StateDiffAvailable { block_number, .. } if block_number == BlockNumber(i)
+        );
+    }
+}
+
+#[test]
+fn block_is_announced_once_its_body_and_state_diff_are_stored() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    add_headers(1, &mut writer);
+    let stored_header =
+        reader.begin_ro_txn().unwrap().get_block_header(BlockNumber(0)).unwrap().unwrap();
+    let (new_block_sender, mut new_block_receiver) = broadcast::channel(1);
+
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(MockCentralSourceTrait::new()),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        new_block_sender,
+    };
+
+    // A state diff that doesn't belong to the stored header is rejected.
+    let res = gen_state_sync.store_state_diff(
+        BlockNumber(0),
+        BlockHash(stark_felt!("0x666")),
+        StateDiff::default(),
+        IndexMap::new(),
+    );
+    assert_matches!(
+        res,
+        Err(StateSyncError::StoredHeaderMismatch { block_number: BlockNumber(0), .. })
+    );
+
+    // The state diff is stored before the body, and the block is announced only with the body.
+    gen_state_sync
+        .store_state_diff(
+            BlockNumber(0),
+            stored_header.block_hash,
+            StateDiff::default(),
+            IndexMap::new(),
+        )
+        .unwrap();
+    assert!(new_block_receiver.try_recv().is_err());
+
+    let block = Block { header: stored_header, ..Block::default() };
+    gen_state_sync
+        .store_block(BlockNumber(0), block, &StarknetVersion("0.12.3".to_owned()), BTreeMap::new())
+        .unwrap();
+    assert_eq!(new_block_receiver.try_recv().unwrap(), BlockNumber(0));
+}
+
+#[tokio::test]
+async fn blocks_are_reverted_to_the_common_ancestor() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    add_headers(5, &mut writer);
+    for i in 0..5 {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(BlockNumber(i), StateDiff::default(), IndexMap::new())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    // The central source forked after block 1, and its chain is shorter than the stored one.
+    let mut central_source = MockCentralSourceTrait::new();
+    central_source.expect_get_block_hash().returning(|block_number| match block_number.0 {
+        0..=1 => Ok(Some(BlockHash(block_number.0.into()))),
+        2..=3 => Ok(Some(BlockHash(stark_felt!("0x666")))),
+        _ => Ok(None),
+    });
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(central_source),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        new_block_sender: broadcast::channel(1).0,
+    };
+
+    gen_state_sync.handle_block_reverts().await.unwrap();
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(2));
+    assert!(txn.get_block_header(BlockNumber(2)).unwrap().is_none());
+    assert!(txn.get_block_header(BlockNumber(1)).unwrap().is_some());
+}
+
+#[tokio::test]
+async fn headers_without_bodies_are_reverted() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    // The headers were synced from the peers, and the central source doesn't agree with the last
+    // one. Storing its body fails with a recoverable error, after which the sync reverts it.
+    add_headers(3, &mut writer);
+
+    let mut central_source = MockCentralSourceTrait::new();
+    central_source.expect_get_block_hash().returning(|block_number| match block_number.0 {
+        0..=1 => Ok(Some(BlockHash(block_number.0.into()))),
+        _ => Ok(Some(BlockHash(stark_felt!("0x666")))),
+    });
+    let mut gen_state_sync = GenericStateSync {
+        config: SyncConfig::default(),
+        shared_highest_block: Arc::new(RwLock::new(None)),
+        pending_data: Arc::new(RwLock::new(PendingData::default())),
+        central_source: Arc::new(central_source),
+        pending_source: Arc::new(MockPendingSourceTrait::new()),
+        pending_classes: Arc::new(RwLock::new(PendingClasses::default())),
+        base_layer_source: Arc::new(MockBaseLayerSourceTrait::new()),
+        reader,
+        writer,
+        new_block_sender: broadcast::channel(1).0,
+    };
+
+    gen_state_sync.handle_block_reverts().await.unwrap();
+    let txn = gen_state_sync.reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_header_marker().unwrap(), BlockNumber(2));
+    assert_eq!(txn.get_body_marker().unwrap(), BlockNumber(0));
+    assert_eq!(txn.get_state_marker().unwrap(), BlockNumber(0));
+}
+
 // Adds to the storage 'headers_num' headers.
 fn add_headers(headers_num: u64, writer: &mut StorageWriter) {
     for i in 0..headers_num {
@@ -523,8 +884,8 @@ async fn pending_sync_doesnt_stop_when_data_has_block_hash_field_with_the_same_h
 }
 
 #[tokio::test]
-async fn pending_sync_updates_when_data_has_block_hash_field_with_the_same_hash_and_more_transactions()
- {
+async fn pending_sync_updates_when_data_has_block_hash_field_with_the_same_hash_and_more_transactions(
+) {
     const FIRST_BLOCK_HASH: BlockHash = BlockHash(StarkHash::ONE);
     let genesis_hash = BlockHash(stark_felt!(GENESIS_HASH));
     // Storage with one block header.
@@ -0,0 +1,50 @@
+//! Metrics of the requests to Starknet, for attributing slow syncing to the upstream source.
+//!
+//! All the metrics are labeled with the endpoint of the request, which is the path of its URL (for
+//! example `feeder_gateway/get_block`).
+use std::time::Duration;
+
+use metrics::{counter, histogram, increment_counter};
+use reqwest::{RequestBuilder, StatusCode};
+
+// Name of the metrics.
+const REQUEST_LATENCY: &str = "starknet_client_request_latency_seconds";
+const RESPONSES: &str = "starknet_client_responses";
+const RETRIES: &str = "starknet_client_retries";
+
+// Labels for the metrics.
+const ENDPOINT_LABEL: &str = "endpoint";
+const STATUS_CODE_LABEL: &str = "status_code";
+
+// The status code label of requests that didn't get a response.
+const NO_RESPONSE: &str = "no_response";
+// The endpoint label of requests whose URL can't be determined.
+const UNKNOWN_ENDPOINT: &str = "unknown";
+
+// Returns the endpoint label of the request.
+pub(crate) fn endpoint(request_builder: &RequestBuilder) -> String {
+    request_builder
+        .try_clone()
+        .and_then(|request_builder| request_builder.build().ok())
+        .map(|request| request.url().path().trim_start_matches('/').to_owned())
+        .unwrap_or_else(|| UNKNOWN_ENDPOINT.to_owned())
+}
+
+// Records a single request, given the status code of its response if it got one.
+pub(crate) fn record_request(endpoint: &str, status_code: Option<StatusCode>, latency: Duration) {
+    let status_code =
+        status_code.map_or_else(|| NO_RESPONSE.to_owned(), |code| code.as_u16().to_string());
+    histogram!(REQUEST_LATENCY, latency.as_secs_f64(), ENDPOINT_LABEL => endpoint.to_owned());
+    increment_counter!(
+        RESPONSES,
+        ENDPOINT_LABEL => endpoint.to_owned(),
+        STATUS_CODE_LABEL => status_code
+    );
+}
+
+// Records the number of times a request was retried.
+pub(crate) fn record_retries(endpoint: &str, retries: usize) {
+    if retries > 0 {
+        counter!(RETRIES, retries as u64, ENDPOINT_LABEL => endpoint.to_owned());
+    }
+}
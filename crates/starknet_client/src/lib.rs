@@ -8,19 +8,27 @@
 //!
 //! [`Starknet`]: https://starknet.io/
 
+mod client_metrics;
 pub mod reader;
 pub mod retry;
 #[cfg(test)]
 mod starknet_client_test;
 pub mod starknet_error;
-#[cfg(test)]
-mod test_utils;
+#[cfg(any(feature = "testing", test))]
+pub mod test_utils;
 pub mod writer;
 
 use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
+use bytes::{Buf, Bytes};
 use reqwest::header::HeaderMap;
-use reqwest::{Client, RequestBuilder, StatusCode};
+use reqwest::{Client, Proxy, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::sync::mpsc;
 use tracing::warn;
 
 use self::retry::Retry;
@@ -30,11 +38,21 @@ pub use self::starknet_error::{KnownStarknetErrorCode, StarknetError, StarknetEr
 /// A [`Result`] in which the error is a [`ClientError`].
 type ClientResult<T> = Result<T, ClientError>;
 
+// The maximal number of characters of a raw response that are logged. Responses such as blocks and
+// classes can be many megabytes long.
+const MAX_LOGGED_RESPONSE_CHARS: usize = 1000;
+// The maximal number of bytes of a streamed response that are kept for reporting deserialization
+// errors.
+const MAX_STREAMED_RESPONSE_PREFIX_BYTES: usize = 1 << 16;
+// The maximal number of received chunks of a streamed response that wait for the deserialization.
+const STREAMED_CHUNKS_CHANNEL_CAPACITY: usize = 16;
+
 /// A starknet client.
 struct StarknetClient {
     http_headers: HeaderMap,
     pub internal_client: Client,
     retry_config: RetryConfig,
+    max_response_size_bytes: Option<usize>,
 }
 
 /// Errors that might be encountered while creating the client.
@@ -67,12 +85,19 @@ pub enum ClientError {
     /// A client error representing http request errors.
     #[error(transparent)]
     RequestError(#[from] reqwest::Error),
+    /// A client error representing responses that are larger than the maximal response size.
+    #[error("The response is larger than the maximal response size of {max_size} bytes.")]
+    ResponseTooLarge { max_size: usize },
     /// A client error representing errors that might be solved by retrying mechanism.
     #[error("Retry error code: {:?}, message: {:?}.", code, message)]
     RetryError { code: RetryErrorCode, message: String },
     /// A client error representing deserialization errors.
     #[error(transparent)]
     SerdeError(#[from] serde_json::Error),
+    /// A client error representing a response that failed to deserialize while it was received.
+    /// Contains the beginning of the raw response.
+    #[error("Failed to deserialize the response: {error}.")]
+    StreamedResponseDeserializationError { error: serde_json::Error, response_prefix: String },
     /// A client error representing errors returned by the starknet client.
     #[error(transparent)]
     StarknetError(#[from] StarknetError),
@@ -89,8 +114,13 @@ enum RequestWithRetryError {
 
 impl StarknetClient {
     /// Creates a new client for a starknet gateway at `url_str` with retry_config [`RetryConfig`].
+    /// If `proxy_url` is given, all the requests are sent through the HTTP, HTTPS or SOCKS5 proxy
+    /// at that URL. If `max_response_size_bytes` is given, responses that are larger fail with
+    /// [`ClientError::ResponseTooLarge`].
     pub fn new(
         http_headers: Option<HashMap<String, String>>,
+        proxy_url: Option<String>,
+        max_response_size_bytes: Option<usize>,
         node_version: &'static str,
         retry_config: RetryConfig,
     ) -> Result<Self, ClientCreationError> {
@@ -107,9 +137,14 @@ impl StarknetClient {
             product_version = node_version,
             system_information = system_information
         );
+        let mut client_builder = Client::builder().user_agent(app_user_agent);
+        if let Some(proxy_url) = proxy_url {
+            client_builder = client_builder.proxy(Proxy::all(proxy_url)?);
+        }
         Ok(StarknetClient {
             http_headers: header_map,
-            internal_client: Client::builder().user_agent(app_user_agent).build()?,
+            internal_client: client_builder.build()?,
+            max_response_size_bytes,
             retry_config,
         })
     }
@@ -161,12 +196,40 @@ impl StarknetClient {
         &self,
         request_builder: RequestBuilder,
     ) -> ClientResult<String> {
+        self.request_with_retry_and_read(request_builder, |response| self.read_body(response)).await
+    }
+
+    /// Like [`StarknetClient::request_with_retry`], but deserializes the body of a successful
+    /// response while it's received, so the raw response is never kept in memory as a whole.
+    /// A response that fails to deserialize fails with
+    /// [`ClientError::StreamedResponseDeserializationError`].
+    pub async fn request_object_with_retry<Object: DeserializeOwned + Send + 'static>(
+        &self,
+        request_builder: RequestBuilder,
+    ) -> ClientResult<Object> {
+        self.request_with_retry_and_read(request_builder, |response| self.read_object(response))
+            .await
+    }
+
+    // Sends the request with retries, and reads the body of a successful response with `read`.
+    async fn request_with_retry_and_read<T, F, Fut>(
+        &self,
+        request_builder: RequestBuilder,
+        read: F,
+    ) -> ClientResult<T>
+    where
+        F: Fn(Response) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let endpoint = client_metrics::endpoint(&request_builder);
+        let attempts = AtomicUsize::new(0);
         let res = Retry::new(&self.retry_config)
             .start_with_condition(
                 || async {
+                    attempts.fetch_add(1, Ordering::Relaxed);
                     match request_builder.try_clone() {
                         Some(request_builder) => self
-                            .request(request_builder)
+                            .request(request_builder, &endpoint, &read)
                             .await
                             .map_err(RequestWithRetryError::ClientError),
                         None => Err(RequestWithRetryError::CloneError),
@@ -175,30 +238,48 @@ impl StarknetClient {
                 Self::should_retry,
             )
             .await;
+        client_metrics::record_retries(
+            &endpoint,
+            attempts.load(Ordering::Relaxed).saturating_sub(1),
+        );
 
         match res {
-            Ok(string) => Ok(string),
+            Ok(object) => Ok(object),
             Err(RequestWithRetryError::ClientError(err)) => Err(Self::get_retry_error_code(&err)
                 .map(|code| ClientError::RetryError { code, message: err.to_string() })
                 .unwrap_or(err)),
             Err(RequestWithRetryError::CloneError) => {
                 warn!("Starknet client got an unclonable request. Can't retry upon failure.");
-                self.request(request_builder).await
+                self.request(request_builder, &endpoint, &read).await
             }
         }
     }
 
-    async fn request(&self, request_builder: RequestBuilder) -> ClientResult<String> {
-        let res = request_builder.headers(self.http_headers.clone()).send().await;
-        let (code, message) = match res {
-            Ok(response) => (response.status(), response.text().await?),
-            Err(err) => {
-                let msg = err.to_string();
-                (err.status().ok_or(err)?, msg)
-            }
+    async fn request<T, F, Fut>(
+        &self,
+        request_builder: RequestBuilder,
+        endpoint: &str,
+        read: &F,
+    ) -> ClientResult<T>
+    where
+        F: Fn(Response) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let start_time = Instant::now();
+        let response = self.send(request_builder, read).await;
+        client_metrics::record_request(
+            endpoint,
+            response.as_ref().ok().map(|response| match response {
+                SentRequestResponse::Ok(_) => StatusCode::OK,
+                SentRequestResponse::Failure { code, .. } => *code,
+            }),
+            start_time.elapsed(),
+        );
+        let (code, message) = match response? {
+            SentRequestResponse::Ok(object) => return Ok(object),
+            SentRequestResponse::Failure { code, message } => (code, message),
         };
         match code {
-            StatusCode::OK => Ok(message),
             // TODO(Omri): The error code returned from SN changed from error 500 to error 400. For
             // now, keeping both options. In the future, remove the '500' (INTERNAL_SERVER_ERROR)
             // option.
@@ -209,4 +290,142 @@ impl StarknetClient {
             _ => Err(ClientError::BadResponseStatus { code, message }),
         }
     }
+
+    // Sends the request and reads the body of its response. The body of a successful response is
+    // read with `read`.
+    async fn send<T, F, Fut>(
+        &self,
+        request_builder: RequestBuilder,
+        read: &F,
+    ) -> ClientResult<SentRequestResponse<T>>
+    where
+        F: Fn(Response) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let res = request_builder.headers(self.http_headers.clone()).send().await;
+        match res {
+            Ok(response) if response.status() == StatusCode::OK => {
+                Ok(SentRequestResponse::Ok(read(response).await?))
+            }
+            Ok(response) => Ok(SentRequestResponse::Failure {
+                code: response.status(),
+                message: self.read_body(response).await?,
+            }),
+            Err(err) => {
+                let message = err.to_string();
+                Ok(SentRequestResponse::Failure { code: err.status().ok_or(err)?, message })
+            }
+        }
+    }
+
+    // Reads the body of the response chunk by chunk, so a response that exceeds the maximal
+    // response size fails before it's all kept in memory.
+    async fn read_body(&self, mut response: Response) -> ClientResult<String> {
+        let Some(max_size) = self.max_response_size_bytes else {
+            return Ok(response.text().await?);
+        };
+        self.verify_content_length(&response)?;
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > max_size {
+                return Err(ClientError::ResponseTooLarge { max_size });
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(String::from_utf8(body)
+            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()))
+    }
+
+    // Deserializes the body of the response on a blocking thread while its chunks are received.
+    // Only a few chunks and the beginning of the body (for reporting deserialization errors) are
+    // kept in memory besides the deserialized object.
+    async fn read_object<Object: DeserializeOwned + Send + 'static>(
+        &self,
+        mut response: Response,
+    ) -> ClientResult<Object> {
+        self.verify_content_length(&response)?;
+        let (chunk_sender, chunk_receiver) = mpsc::channel(STREAMED_CHUNKS_CHANNEL_CAPACITY);
+        let deserialization = tokio::task::spawn_blocking(move || {
+            serde_json::from_reader::<_, Object>(ChunksReader {
+                receiver: chunk_receiver,
+                chunk: Bytes::new(),
+            })
+        });
+        let mut size = 0;
+        let mut response_prefix = Vec::new();
+        // If the function returns early, the deserialization gets the end of the body and fails.
+        while let Some(chunk) = response.chunk().await? {
+            size += chunk.len();
+            if let Some(max_size) = self.max_response_size_bytes {
+                if size > max_size {
+                    return Err(ClientError::ResponseTooLarge { max_size });
+                }
+            }
+            let prefix_len =
+                MAX_STREAMED_RESPONSE_PREFIX_BYTES.saturating_sub(response_prefix.len());
+            response_prefix.extend_from_slice(&chunk[..prefix_len.min(chunk.len())]);
+            // The deserialization ended before the body did, which means it failed.
+            if chunk_sender.send(chunk).await.is_err() {
+                break;
+            }
+        }
+        drop(chunk_sender);
+        deserialization.await.expect("Failed to join the deserialization of a response.").map_err(
+            |error| ClientError::StreamedResponseDeserializationError {
+                error,
+                response_prefix: String::from_utf8_lossy(&response_prefix).into_owned(),
+            },
+        )
+    }
+
+    fn verify_content_length(&self, response: &Response) -> ClientResult<()> {
+        match self.max_response_size_bytes {
+            Some(max_size)
+                if response.content_length().is_some_and(|length| length > max_size as u64) =>
+            {
+                Err(ClientError::ResponseTooLarge { max_size })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// The response to a sent request: either the read body of a successful response, or the status
+// code and raw body of a failed response.
+enum SentRequestResponse<T> {
+    Ok(T),
+    Failure { code: StatusCode, message: String },
+}
+
+// A blocking reader of the chunks of a response body that are received by another task.
+struct ChunksReader {
+    receiver: mpsc::Receiver<Bytes>,
+    // The part of the current chunk that wasn't read yet.
+    chunk: Bytes,
+}
+
+impl Read for ChunksReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk,
+                // The body ended.
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk[..len]);
+        self.chunk.advance(len);
+        Ok(len)
+    }
+}
+
+// Returns the beginning of a raw response, for logging it.
+pub(crate) fn truncate_for_log(response: &str) -> String {
+    match response.char_indices().nth(MAX_LOGGED_RESPONSE_CHARS) {
+        Some((end, _)) => {
+            format!("{}... (truncated, {} bytes in total)", &response[..end], response.len())
+        }
+        None => response.to_owned(),
+    }
 }
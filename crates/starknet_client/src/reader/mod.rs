@@ -3,23 +3,27 @@
 //! [`Starknet`]: https://starknet.io/
 
 pub mod objects;
+mod response_cache;
 #[cfg(test)]
 mod starknet_feeder_gateway_client_test;
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 #[cfg(any(feature = "testing", test))]
 use mockall::automock;
 use papyrus_common::pending_classes::ApiContractClass;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ClassHash;
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::transaction::TransactionHash;
 use starknet_api::StarknetApiError;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 use url::Url;
 
 pub use crate::reader::objects::block::{
@@ -40,9 +44,10 @@ pub use crate::reader::objects::state::{
 };
 #[cfg(doc)]
 pub use crate::reader::objects::transaction::TransactionReceipt;
+use crate::reader::response_cache::ResponseCache;
 use crate::retry::RetryConfig;
 use crate::starknet_error::{KnownStarknetErrorCode, StarknetError, StarknetErrorCode};
-use crate::{ClientCreationError, ClientError, StarknetClient};
+use crate::{truncate_for_log, ClientCreationError, ClientError, StarknetClient};
 
 /// Errors that may be returned from a reader client.
 #[derive(thiserror::Error, Debug)]
@@ -56,6 +61,12 @@ pub enum ReaderClientError {
     /// is responsible for serde errors coming from StarknetClient.
     #[error(transparent)]
     SerdeError(#[from] serde_json::Error),
+    /// A client error representing a response that couldn't be deserialized, for example because
+    /// the feeder gateway changed the format of its objects. Contains the raw response so the
+    /// incompatibility can be reported. Classes and state updates are deserialized while they're
+    /// received, so only the beginning of their raw response is kept.
+    #[error("Failed to deserialize the response of starknet server: {error}.")]
+    DeserializationError { error: serde_json::Error, raw_response: String },
     /// A client error representing errors from [`starknet_api`].
     #[error(transparent)]
     StarknetApiError(#[from] StarknetApiError),
@@ -109,12 +120,29 @@ pub trait StarknetReader {
     ) -> ReaderClientResult<Option<BlockSignatureData>>;
 }
 
+/// The configuration of a [`StarknetFeederGatewayClient`], besides the URL of the feeder gateway.
+/// By default, requests are sent directly, responses aren't limited in size and aren't cached.
+#[derive(Clone, Debug, Default)]
+pub struct StarknetFeederGatewayClientConfig {
+    /// Headers that are added to all the requests.
+    pub http_headers: Option<HashMap<String, String>>,
+    /// An HTTP, HTTPS or SOCKS5 proxy that all the requests are sent through.
+    pub proxy_url: Option<String>,
+    /// Responses that are larger fail with [`ClientError::ResponseTooLarge`].
+    pub max_response_size_bytes: Option<usize>,
+    /// If given, identical concurrent requests share a single request to the feeder gateway, and
+    /// responses are reused for identical requests during the given duration. Classes and state
+    /// updates are deserialized while they're received, and aren't cached.
+    pub response_cache_ttl: Option<Duration>,
+}
+
 /// A client for the [`Starknet`] feeder gateway.
 ///
 /// [`Starknet`]: https://starknet.io/
 pub struct StarknetFeederGatewayClient {
     urls: StarknetUrls,
     client: StarknetClient,
+    response_cache: ResponseCache,
 }
 
 #[derive(Clone, Debug)]
@@ -176,23 +204,44 @@ impl StarknetUrls {
 }
 
 impl StarknetFeederGatewayClient {
+    /// Creates a client of the feeder gateway at the given URL.
     pub fn new(
         url_str: &str,
-        http_headers: Option<HashMap<String, String>>,
+        config: StarknetFeederGatewayClientConfig,
         node_version: &'static str,
         retry_config: RetryConfig,
     ) -> Result<Self, ClientCreationError> {
         Ok(StarknetFeederGatewayClient {
             urls: StarknetUrls::new(url_str)?,
-            client: StarknetClient::new(http_headers, node_version, retry_config)?,
+            client: StarknetClient::new(
+                config.http_headers,
+                config.proxy_url,
+                config.max_response_size_bytes,
+                node_version,
+                retry_config,
+            )?,
+            response_cache: ResponseCache::new(config.response_cache_ttl),
         })
     }
 
     async fn request_with_retry_url(&self, url: Url) -> ReaderClientResult<String> {
-        self.client
-            .request_with_retry(self.client.internal_client.get(url))
+        self.response_cache
+            .get_or_request(url.clone(), || async {
+                self.client
+                    .request_with_retry(self.client.internal_client.get(url))
+                    .await
+                    .map_err(Into::<ReaderClientError>::into)
+            })
             .await
-            .map_err(Into::<ReaderClientError>::into)
+    }
+
+    // Requests an object that can be large. The object is deserialized while it's received, and
+    // the response isn't cached.
+    async fn request_object_with_retry_url<Object: DeserializeOwned + Send + 'static>(
+        &self,
+        url: Url,
+    ) -> ReaderClientResult<Object> {
+        Ok(self.client.request_object_with_retry(self.client.internal_client.get(url)).await?)
     }
 
     async fn request_block(
@@ -205,11 +254,26 @@ impl StarknetFeederGatewayClient {
         url.query_pairs_mut().append_pair(BLOCK_NUMBER_QUERY, block_number.as_str());
 
         let response = self.request_with_retry_url(url).await;
-        load_object_from_response(
+        let Some(raw_block) = load_object_from_response::<Value>(
             response,
             KnownStarknetErrorCode::BlockNotFound,
             format!("Failed to get block number {block_number:?} from starknet server."),
-        )
+        )?
+        else {
+            return Ok(None);
+        };
+        // Unknown fields of the transactions are tolerated, see `Block::from_raw_block`.
+        match Block::from_raw_block(&raw_block) {
+            Ok(block) => Ok(Some(block)),
+            Err(error) => {
+                let raw_response = raw_block.to_string();
+                warn!(
+                    "Failed to deserialize the block from starknet server: {error}. Raw block: {}",
+                    truncate_for_log(&raw_response)
+                );
+                Err(ReaderClientError::DeserializationError { error, raw_response })
+            }
+        }
     }
 }
 
@@ -234,8 +298,8 @@ impl StarknetReader for StarknetFeederGatewayClient {
         let class_hash = serde_json::to_string(&class_hash)?;
         url.query_pairs_mut()
             .append_pair(CLASS_HASH_QUERY, &class_hash.as_str()[1..class_hash.len() - 1]);
-        let response = self.request_with_retry_url(url).await;
-        load_object_from_response(
+        let response = self.request_object_with_retry_url(url).await;
+        load_streamed_object(
             response,
             KnownStarknetErrorCode::UndeclaredClass,
             format!("Failed to get class with hash {class_hash:?} from starknet server."),
@@ -249,8 +313,8 @@ impl StarknetReader for StarknetFeederGatewayClient {
     ) -> ReaderClientResult<Option<StateUpdate>> {
         let mut url = self.urls.get_state_update.clone();
         url.query_pairs_mut().append_pair(BLOCK_NUMBER_QUERY, &block_number.to_string());
-        let response = self.request_with_retry_url(url).await;
-        load_object_from_response(
+        let response = self.request_object_with_retry_url(url).await;
+        load_streamed_object(
             response,
             KnownStarknetErrorCode::BlockNotFound,
             format!(
@@ -324,8 +388,8 @@ impl StarknetReader for StarknetFeederGatewayClient {
         let class_hash = serde_json::to_string(&class_hash)?;
         url.query_pairs_mut()
             .append_pair(CLASS_HASH_QUERY, &class_hash.as_str()[1..class_hash.len() - 1]);
-        let response = self.request_with_retry_url(url).await;
-        load_object_from_response(
+        let response = self.request_object_with_retry_url(url).await;
+        load_streamed_object(
             response,
             KnownStarknetErrorCode::UndeclaredClass,
             format!("Failed to get compiled class with hash {class_hash:?} from starknet server."),
@@ -366,14 +430,58 @@ impl StarknetReader for StarknetFeederGatewayClient {
 }
 
 /// Load an object from a json string response. If there was a StarknetError with
-/// `none_error_code`, return None. If there was a different error, log `error_message`.
+/// `none_error_code`, return None. If there was a different error, log `error_message`. If the
+/// response can't be deserialized, log it and return it inside the error.
 fn load_object_from_response<Object: for<'a> Deserialize<'a>>(
     response: ReaderClientResult<String>,
     none_error_code: KnownStarknetErrorCode,
     error_message: String,
+) -> ReaderClientResult<Option<Object>> {
+    let response = response.and_then(|raw_object| {
+        serde_json::from_str(&raw_object).map_err(|error| {
+            warn!(
+                "Failed to deserialize the response of starknet server: {error}. Raw response: {}",
+                truncate_for_log(&raw_object)
+            );
+            ReaderClientError::DeserializationError { error, raw_response: raw_object }
+        })
+    });
+    object_or_none(response, none_error_code, error_message)
+}
+
+/// Like [`load_object_from_response`], for an object that was deserialized while its response was
+/// received. Only the beginning of the raw response is logged and returned inside the error.
+fn load_streamed_object<Object>(
+    response: ReaderClientResult<Object>,
+    none_error_code: KnownStarknetErrorCode,
+    error_message: String,
+) -> ReaderClientResult<Option<Object>> {
+    let response = response.map_err(|err| match err {
+        ReaderClientError::ClientError(ClientError::StreamedResponseDeserializationError {
+            error,
+            response_prefix,
+        }) => {
+            warn!(
+                "Failed to deserialize the response of starknet server: {error}. Raw response: {}",
+                truncate_for_log(&response_prefix)
+            );
+            ReaderClientError::DeserializationError { error, raw_response: response_prefix }
+        }
+        err => err,
+    });
+    object_or_none(response, none_error_code, error_message)
+}
+
+// Returns None if there was a StarknetError with `none_error_code`, and logs `error_message` upon
+// other request errors.
+fn object_or_none<Object>(
+    response: ReaderClientResult<Object>,
+    none_error_code: KnownStarknetErrorCode,
+    error_message: String,
 ) -> ReaderClientResult<Option<Object>> {
     match response {
-        Ok(raw_object) => Ok(Some(serde_json::from_str(&raw_object)?)),
+        Ok(object) => Ok(Some(object)),
+        Err(err @ ReaderClientError::DeserializationError { .. }) => Err(err),
         Err(ReaderClientError::ClientError(ClientError::StarknetError(StarknetError {
             code: StarknetErrorCode::KnownErrorCode(error_code),
             message: _,
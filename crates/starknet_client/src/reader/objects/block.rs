@@ -2,9 +2,12 @@
 #[path = "block_test.rs"]
 mod block_test;
 
+use std::collections::BTreeMap;
 use std::ops::Index;
 
+use papyrus_common::raw_transaction::RawTransaction;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use starknet_api::block::{
     Block as starknet_api_block,
     BlockHash,
@@ -17,6 +20,7 @@ use starknet_api::hash::StarkFelt;
 #[cfg(doc)]
 use starknet_api::transaction::TransactionOutput as starknet_api_transaction_output;
 use starknet_api::transaction::{TransactionHash, TransactionOffsetInBlock};
+use tracing::warn;
 
 use crate::reader::objects::transaction::{
     L1ToL2Message,
@@ -49,6 +53,66 @@ pub struct Block {
     // Default since old blocks don't include this field.
     #[serde(default)]
     pub starknet_version: String,
+    /// The raw JSON of the transactions that had fields which the node doesn't know, by their
+    /// offset in the block. These fields were removed from [`transactions`](Block::transactions)
+    /// and [`transaction_receipts`](Block::transaction_receipts).
+    #[serde(skip)]
+    pub raw_transactions: BTreeMap<TransactionOffsetInBlock, RawTransaction>,
+}
+
+impl Block {
+    /// Deserializes a block as returned by the starknet gateway. The raw JSON of the transactions
+    /// that the node can't fully represent, because they have fields that the node doesn't know or
+    /// a version that it doesn't know, is kept in [`raw_transactions`](Block::raw_transactions).
+    pub fn from_raw_block(raw_block: &Value) -> Result<Self, serde_json::Error> {
+        let mut block = Self::deserialize(raw_block)?;
+        let (Some(Value::Array(raw_transactions)), Some(Value::Array(raw_receipts))) =
+            (raw_block.get("transactions"), raw_block.get("transaction_receipts"))
+        else {
+            return Ok(block);
+        };
+        let transactions = block.transactions.iter().zip(&block.transaction_receipts);
+        let raw_transactions = raw_transactions.iter().zip(raw_receipts);
+        for (i, ((transaction, receipt), (raw_transaction, raw_receipt))) in
+            transactions.zip(raw_transactions).enumerate()
+        {
+            let unknown_fields = transaction
+                .unknown_fields()
+                .keys()
+                .chain(receipt.unknown_fields.keys())
+                .collect::<Vec<_>>();
+            if unknown_fields.is_empty() && transaction.has_known_version() {
+                continue;
+            }
+            warn!(
+                "Transaction {} of block {} has unknown fields {:?} or an unknown version {:?}. \
+                 Keeping its raw JSON.",
+                i,
+                block.block_number,
+                unknown_fields,
+                transaction.transaction_version()
+            );
+            block.raw_transactions.insert(
+                TransactionOffsetInBlock(i),
+                RawTransaction {
+                    transaction: raw_transaction.clone(),
+                    receipt: raw_receipt.clone(),
+                },
+            );
+        }
+        Ok(block)
+    }
+
+    /// Serializes the block as returned by the starknet gateway, with the raw JSON of the
+    /// transactions that the node can't fully represent instead of their known data.
+    pub fn to_raw_block(&self) -> Result<Value, serde_json::Error> {
+        let mut raw_block = serde_json::to_value(self)?;
+        for (TransactionOffsetInBlock(i), raw_transaction) in &self.raw_transactions {
+            raw_block["transactions"][i] = raw_transaction.transaction.clone();
+            raw_block["transaction_receipts"][i] = raw_transaction.receipt.clone();
+        }
+        Ok(raw_block)
+    }
 }
 
 /// Errors that might be encountered while converting the client representation of a [`Block`] to a
@@ -186,7 +250,13 @@ impl Block {
         let transactions: Vec<_> = self
             .transactions
             .into_iter()
-            .map(starknet_api::transaction::Transaction::try_from)
+            // Transactions of unknown versions are stored as the latest version the node knows,
+            // and their raw JSON is kept.
+            .map(|transaction| {
+                starknet_api::transaction::Transaction::try_from(
+                    transaction.into_latest_known_version(),
+                )
+            })
             .collect::<Result<_, ReaderClientError>>()?;
 
         // Get the header.
@@ -1,6 +1,8 @@
 use assert_matches::assert_matches;
 use indexmap::IndexMap;
+use papyrus_common::raw_transaction::RawTransaction;
 use pretty_assertions::assert_eq;
+use serde_json::json;
 use starknet_api::block::BlockHash;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
 use starknet_api::hash::{StarkFelt, StarkHash};
@@ -30,6 +32,77 @@ fn load_block_succeeds() {
     }
 }
 
+#[test]
+fn load_block_with_unknown_transaction_fields() {
+    let mut raw_block =
+        serde_json::from_str::<serde_json::Value>(&read_resource_file("reader/block.json"))
+            .unwrap();
+    raw_block["transactions"][0]["unknown_field"] = json!("0x1");
+    raw_block["transaction_receipts"][0]["another_unknown_field"] = json!([]);
+    let block = Block::from_raw_block(&raw_block).unwrap();
+
+    assert_eq!(
+        block.transactions[0].unknown_fields(),
+        json!({ "unknown_field": "0x1" }).as_object().unwrap()
+    );
+    assert_eq!(
+        &block.transaction_receipts[0].unknown_fields,
+        json!({ "another_unknown_field": [] }).as_object().unwrap()
+    );
+    let expected_raw_transactions = [(
+        TransactionOffsetInBlock(0),
+        RawTransaction {
+            transaction: raw_block["transactions"][0].clone(),
+            receipt: raw_block["transaction_receipts"][0].clone(),
+        },
+    )]
+    .into();
+    assert_eq!(block.raw_transactions, expected_raw_transactions);
+
+    // The unknown fields are served back.
+    let served_block = block.to_raw_block().unwrap();
+    assert_eq!(served_block["transactions"][0], raw_block["transactions"][0]);
+    assert_eq!(served_block["transaction_receipts"][0], raw_block["transaction_receipts"][0]);
+}
+
+#[test]
+fn load_block_with_unknown_transaction_version() {
+    let mut raw_block =
+        serde_json::from_str::<serde_json::Value>(&read_resource_file("reader/block.json"))
+            .unwrap();
+    let invoke_offset = raw_block["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .position(|transaction| transaction["type"] == "INVOKE_FUNCTION")
+        .unwrap();
+    raw_block["transactions"][invoke_offset]["version"] = json!("0x4");
+    let block = Block::from_raw_block(&raw_block).unwrap();
+
+    assert_eq!(
+        block.raw_transactions.keys().collect::<Vec<_>>(),
+        vec![&TransactionOffsetInBlock(invoke_offset)]
+    );
+    // The transaction is stored as the latest version that the node knows.
+    let (block, _) = block.to_starknet_api_block_and_version().unwrap();
+    assert_matches!(
+        block.body.transactions[invoke_offset],
+        starknet_api::transaction::Transaction::Invoke(
+            starknet_api::transaction::InvokeTransaction::V3(_)
+        )
+    );
+}
+
+#[test]
+fn load_block_without_unknown_transaction_fields() {
+    // The old transactions name their sender address by an alias, which isn't an unknown field.
+    for block_path in ["reader/block.json", "reader/block_pre_v0_13.json"] {
+        let raw_block = serde_json::from_str(&read_resource_file(block_path)).unwrap();
+        let block = Block::from_raw_block(&raw_block).unwrap();
+        assert!(block.raw_transactions.is_empty(), "{block_path}: {:?}", block.raw_transactions);
+    }
+}
+
 #[test]
 fn load_block_state_update_succeeds() {
     let expected_state_update = StateUpdate {
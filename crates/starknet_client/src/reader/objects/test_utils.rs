@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use serde_json::{Map, Value};
 use starknet_api::core::{
     ClassHash,
     CompiledClassHash,
@@ -72,6 +73,7 @@ auto_impl_get_test_instance! {
         pub max_fee: Option<Fee>,
         pub version: TransactionVersion,
         pub transaction_hash: TransactionHash,
+        pub unknown_fields: Map<String, Value>,
     }
     pub struct IntermediateDeployAccountTransaction {
         pub resource_bounds: Option<ResourceBoundsMapping>,
@@ -88,6 +90,7 @@ auto_impl_get_test_instance! {
         pub max_fee: Option<Fee>,
         pub transaction_hash: TransactionHash,
         pub version: TransactionVersion,
+        pub unknown_fields: Map<String, Value>,
     }
     pub struct DeployTransaction {
         pub contract_address: ContractAddress,
@@ -96,6 +99,7 @@ auto_impl_get_test_instance! {
         pub constructor_calldata: Calldata,
         pub transaction_hash: TransactionHash,
         pub version: TransactionVersion,
+        pub unknown_fields: Map<String, Value>,
     }
     pub struct IntermediateInvokeTransaction {
         pub resource_bounds: Option<ResourceBoundsMapping>,
@@ -112,6 +116,7 @@ auto_impl_get_test_instance! {
         pub account_deployment_data: Option<AccountDeploymentData>,
         pub transaction_hash: TransactionHash,
         pub version: TransactionVersion,
+        pub unknown_fields: Map<String, Value>,
     }
     pub struct L1HandlerTransaction {
         pub transaction_hash: TransactionHash,
@@ -120,6 +125,7 @@ auto_impl_get_test_instance! {
         pub contract_address: ContractAddress,
         pub entry_point_selector: EntryPointSelector,
         pub calldata: Calldata,
+        pub unknown_fields: Map<String, Value>,
     }
     pub struct ContractClass {
         pub sierra_program: Vec<StarkFelt>,
@@ -136,6 +142,7 @@ auto_impl_get_test_instance! {
         pub execution_resources: ExecutionResources,
         pub actual_fee: Fee,
         pub execution_status: TransactionExecutionStatus,
+        pub unknown_fields: Map<String, Value>,
     }
     pub struct L1ToL2Message {
         pub from_address: EthAddress,
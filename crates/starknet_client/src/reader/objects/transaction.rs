@@ -5,6 +5,7 @@ mod transaction_test;
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use starknet_api::core::{
     ClassHash,
@@ -43,6 +44,9 @@ use starknet_api::transaction::{
 use crate::reader::ReaderClientError;
 
 // TODO(dan): consider extracting common fields out (version, hash, type).
+// Fields that the node doesn't know are collected in the `unknown_fields` of the transactions, so
+// that blocks with new fields can still be synced. `Block::from_raw_block` keeps the raw JSON of
+// these transactions.
 #[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
 #[serde(tag = "type")]
 pub enum Transaction {
@@ -129,10 +133,89 @@ impl Transaction {
             Transaction::L1Handler(tx) => tx.version,
         }
     }
+
+    pub fn unknown_fields(&self) -> &Map<String, Value> {
+        match self {
+            Transaction::Declare(tx) => &tx.unknown_fields,
+            Transaction::Deploy(tx) => &tx.unknown_fields,
+            Transaction::DeployAccount(tx) => &tx.unknown_fields,
+            Transaction::Invoke(tx) => &tx.unknown_fields,
+            Transaction::L1Handler(tx) => &tx.unknown_fields,
+        }
+    }
+
+    /// Whether the node can convert a transaction of this type and version. Deploy and L1 handler
+    /// transactions are converted regardless of their version.
+    pub fn has_known_version(&self) -> bool {
+        let version = self.transaction_version();
+        match self {
+            Transaction::Declare(_) => [
+                TransactionVersion::ZERO,
+                TransactionVersion::ONE,
+                TransactionVersion::TWO,
+                TransactionVersion::THREE,
+            ]
+            .contains(&version),
+            Transaction::DeployAccount(_) => {
+                [TransactionVersion::ONE, TransactionVersion::THREE].contains(&version)
+            }
+            Transaction::Invoke(_) => {
+                [TransactionVersion::ZERO, TransactionVersion::ONE, TransactionVersion::THREE]
+                    .contains(&version)
+            }
+            Transaction::Deploy(_) | Transaction::L1Handler(_) => true,
+        }
+    }
+
+    /// Converts a transaction of a version that the node doesn't know to the latest version of its
+    /// type that the node knows, as a placeholder for storing it. Fields of the latest version
+    /// that the transaction doesn't have are filled with default values.
+    pub fn into_latest_known_version(self) -> Self {
+        if self.has_known_version() {
+            return self;
+        }
+        let nonce_data_availability_mode = Some(ReservedDataAvailabilityMode::Reserved);
+        let fee_data_availability_mode = Some(ReservedDataAvailabilityMode::Reserved);
+        match self {
+            Transaction::Declare(tx) => Transaction::Declare(IntermediateDeclareTransaction {
+                resource_bounds: Some(tx.resource_bounds.unwrap_or_default()),
+                tip: Some(tx.tip.unwrap_or_default()),
+                compiled_class_hash: Some(tx.compiled_class_hash.unwrap_or_default()),
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+                paymaster_data: Some(tx.paymaster_data.unwrap_or_default()),
+                account_deployment_data: Some(tx.account_deployment_data.unwrap_or_default()),
+                version: TransactionVersion::THREE,
+                ..tx
+            }),
+            Transaction::DeployAccount(tx) => {
+                Transaction::DeployAccount(IntermediateDeployAccountTransaction {
+                    resource_bounds: Some(tx.resource_bounds.unwrap_or_default()),
+                    tip: Some(tx.tip.unwrap_or_default()),
+                    nonce_data_availability_mode,
+                    fee_data_availability_mode,
+                    paymaster_data: Some(tx.paymaster_data.unwrap_or_default()),
+                    version: TransactionVersion::THREE,
+                    ..tx
+                })
+            }
+            Transaction::Invoke(tx) => Transaction::Invoke(IntermediateInvokeTransaction {
+                resource_bounds: Some(tx.resource_bounds.unwrap_or_default()),
+                tip: Some(tx.tip.unwrap_or_default()),
+                nonce: Some(tx.nonce.unwrap_or_default()),
+                nonce_data_availability_mode,
+                fee_data_availability_mode,
+                paymaster_data: Some(tx.paymaster_data.unwrap_or_default()),
+                account_deployment_data: Some(tx.account_deployment_data.unwrap_or_default()),
+                version: TransactionVersion::THREE,
+                ..tx
+            }),
+            Transaction::Deploy(_) | Transaction::L1Handler(_) => self,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
 pub struct L1HandlerTransaction {
     pub transaction_hash: TransactionHash,
     pub version: TransactionVersion,
@@ -141,6 +224,9 @@ pub struct L1HandlerTransaction {
     pub contract_address: ContractAddress,
     pub entry_point_selector: EntryPointSelector,
     pub calldata: Calldata,
+    /// Fields that the node doesn't know.
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
 }
 
 impl From<L1HandlerTransaction> for starknet_api::transaction::L1HandlerTransaction {
@@ -171,7 +257,6 @@ impl From<ReservedDataAvailabilityMode> for starknet_api::data_availability::Dat
 
 // TODO(shahak, 01/11/2023): Add serde tests for v3 transactions.
 #[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
-#[serde(deny_unknown_fields)]
 pub struct IntermediateDeclareTransaction {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resource_bounds: Option<ResourceBoundsMapping>,
@@ -195,6 +280,9 @@ pub struct IntermediateDeclareTransaction {
     pub max_fee: Option<Fee>,
     pub version: TransactionVersion,
     pub transaction_hash: TransactionHash,
+    /// Fields that the node doesn't know.
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
 }
 
 // TODO(shahak, 01/11/2023): Add conversion tests.
@@ -309,7 +397,6 @@ impl TryFrom<IntermediateDeclareTransaction> for starknet_api::transaction::Decl
 }
 
 #[derive(Debug, Default, Deserialize, Serialize, Clone, Eq, PartialEq)]
-#[serde(deny_unknown_fields)]
 pub struct DeployTransaction {
     pub contract_address: ContractAddress,
     pub contract_address_salt: ContractAddressSalt,
@@ -318,6 +405,9 @@ pub struct DeployTransaction {
     pub transaction_hash: TransactionHash,
     #[serde(default)]
     pub version: TransactionVersion,
+    /// Fields that the node doesn't know.
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
 }
 
 impl From<DeployTransaction> for starknet_api::transaction::DeployTransaction {
@@ -333,7 +423,6 @@ impl From<DeployTransaction> for starknet_api::transaction::DeployTransaction {
 
 // TODO(shahak, 01/11/2023): Add serde tests for v3 transactions.
 #[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
-#[serde(deny_unknown_fields)]
 pub struct IntermediateDeployAccountTransaction {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resource_bounds: Option<ResourceBoundsMapping>,
@@ -358,6 +447,9 @@ pub struct IntermediateDeployAccountTransaction {
     pub max_fee: Option<Fee>,
     pub transaction_hash: TransactionHash,
     pub version: TransactionVersion,
+    /// Fields that the node doesn't know.
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
 }
 
 // TODO(shahak, 01/11/2023): Add conversion tests.
@@ -459,7 +551,6 @@ impl TryFrom<IntermediateDeployAccountTransaction>
 
 // TODO(shahak, 01/11/2023): Add serde tests for v3 transactions.
 #[derive(Debug, Default, Deserialize, Serialize, Clone, Eq, PartialEq)]
-#[serde(deny_unknown_fields)]
 pub struct IntermediateInvokeTransaction {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resource_bounds: Option<ResourceBoundsMapping>,
@@ -488,6 +579,9 @@ pub struct IntermediateInvokeTransaction {
     pub account_deployment_data: Option<AccountDeploymentData>,
     pub transaction_hash: TransactionHash,
     pub version: TransactionVersion,
+    /// Fields that the node doesn't know.
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
 }
 
 // TODO(shahak, 01/11/2023): Add conversion tests.
@@ -683,6 +777,9 @@ pub struct TransactionReceipt {
     pub actual_fee: Fee,
     #[serde(default)]
     pub execution_status: TransactionExecutionStatus,
+    /// Fields that the node doesn't know.
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
 }
 
 impl TransactionReceipt {
@@ -61,21 +61,40 @@ fn load_transaction_succeeds() {
 }
 
 #[test]
-fn load_transaction_unknown_field_fails() {
+fn load_transaction_unknown_field_is_collected() {
     for file_name in [
         "reader/deploy_v0.json",
         "reader/invoke_v0.json",
         "reader/declare_v0.json",
         "reader/deploy_account_v3.json",
     ] {
-        let mut json_value: serde_json::Value =
-            serde_json::from_str(&read_resource_file(file_name)).unwrap();
+        let raw_transaction = read_resource_file(file_name);
+        let mut json_value: serde_json::Value = serde_json::from_str(&raw_transaction).unwrap();
         json_value
             .as_object_mut()
             .unwrap()
             .insert("unknown_field".to_string(), serde_json::Value::Null);
         let json_str = serde_json::to_string(&json_value).unwrap();
-        assert!(serde_json::from_str::<Transaction>(&json_str).is_err(), "filename: {}", file_name);
+        let transaction = serde_json::from_str::<Transaction>(&json_str).unwrap();
+        assert_eq!(
+            transaction.unknown_fields().keys().collect::<Vec<_>>(),
+            vec!["unknown_field"],
+            "filename: {}",
+            file_name
+        );
+        assert!(
+            serde_json::from_str::<Transaction>(&raw_transaction)
+                .unwrap()
+                .unknown_fields()
+                .is_empty(),
+            "filename: {}",
+            file_name
+        );
+        // The unknown field is kept when the transaction is serialized back.
+        assert_eq!(
+            serde_json::to_value(&transaction).unwrap().get("unknown_field"),
+            Some(&serde_json::Value::Null)
+        );
     }
 }
 
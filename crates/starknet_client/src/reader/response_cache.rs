@@ -0,0 +1,78 @@
+//! Coalescing of identical concurrent requests to the feeder gateway, and a short-lived cache of
+//! their responses.
+//!
+//! Components that read the same data at the same time (for example the sync and the pending
+//! tracker) share a single upstream request instead of each sending its own.
+#[cfg(test)]
+#[path = "response_cache_test.rs"]
+mod response_cache_test;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::OnceCell;
+use url::Url;
+
+use super::ReaderClientResult;
+
+#[derive(Debug)]
+struct CachedResponse {
+    response: String,
+    received_at: Instant,
+}
+
+type Entry = Arc<OnceCell<CachedResponse>>;
+
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    // None means responses aren't cached, and identical requests aren't coalesced.
+    ttl: Option<Duration>,
+    entries: Mutex<HashMap<Url, Entry>>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(ttl: Option<Duration>) -> Self {
+        Self { ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    // Returns the response to a request to the given URL, sending it with `send_request` only if
+    // there's no identical request in flight and no response to an identical request that was
+    // received less than the TTL ago. Failures aren't shared: if the request in flight fails, one
+    // of its waiters sends the request again.
+    pub(crate) async fn get_or_request<F, Fut>(
+        &self,
+        url: Url,
+        send_request: F,
+    ) -> ReaderClientResult<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = ReaderClientResult<String>>,
+    {
+        let Some(ttl) = self.ttl else {
+            return send_request().await;
+        };
+        let entry = {
+            let mut entries = self.entries.lock().expect("Failed to lock the response cache.");
+            entries.retain(|_, entry| !is_stale(entry, ttl));
+            entries.entry(url).or_default().clone()
+        };
+        let cached_response = entry
+            .get_or_try_init(|| async {
+                let response = send_request().await?;
+                Ok(CachedResponse { response, received_at: Instant::now() })
+            })
+            .await?;
+        Ok(cached_response.response.clone())
+    }
+}
+
+// An entry is stale if its response expired, or if its request failed and no one waits for it.
+// While a request is in flight, its sender holds a reference to the entry.
+fn is_stale(entry: &Entry, ttl: Duration) -> bool {
+    match entry.get() {
+        Some(cached_response) => cached_response.received_at.elapsed() >= ttl,
+        None => Arc::strong_count(entry) == 1,
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use reqwest::StatusCode;
+use url::Url;
+
+use super::ResponseCache;
+use crate::reader::{ReaderClientError, ReaderClientResult};
+use crate::ClientError;
+
+const RESPONSE: &str = "response";
+const TTL: Duration = Duration::from_millis(100);
+
+fn url() -> Url {
+    Url::parse("https://url/feeder_gateway/get_block?blockNumber=1").unwrap()
+}
+
+// Sends a request through the cache, counting the requests that were actually sent.
+async fn request(
+    cache: &ResponseCache,
+    sent_requests: &AtomicUsize,
+    response: ReaderClientResult<String>,
+) -> ReaderClientResult<String> {
+    cache
+        .get_or_request(url(), || async {
+            sent_requests.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            response
+        })
+        .await
+}
+
+fn bad_status() -> ReaderClientResult<String> {
+    Err(ReaderClientError::ClientError(ClientError::BadResponseStatus {
+        code: StatusCode::INTERNAL_SERVER_ERROR,
+        message: String::new(),
+    }))
+}
+
+#[tokio::test]
+async fn concurrent_requests_are_coalesced() {
+    let cache = ResponseCache::new(Some(TTL));
+    let sent_requests = AtomicUsize::new(0);
+
+    let responses = tokio::join!(
+        request(&cache, &sent_requests, Ok(RESPONSE.to_owned())),
+        request(&cache, &sent_requests, Ok(RESPONSE.to_owned())),
+        request(&cache, &sent_requests, Ok(RESPONSE.to_owned())),
+    );
+
+    assert_eq!(sent_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(responses.0.unwrap(), RESPONSE);
+    assert_eq!(responses.1.unwrap(), RESPONSE);
+    assert_eq!(responses.2.unwrap(), RESPONSE);
+}
+
+#[tokio::test]
+async fn responses_expire() {
+    let cache = ResponseCache::new(Some(TTL));
+    let sent_requests = AtomicUsize::new(0);
+
+    request(&cache, &sent_requests, Ok(RESPONSE.to_owned())).await.unwrap();
+    request(&cache, &sent_requests, Ok(RESPONSE.to_owned())).await.unwrap();
+    assert_eq!(sent_requests.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(TTL).await;
+    request(&cache, &sent_requests, Ok(RESPONSE.to_owned())).await.unwrap();
+    assert_eq!(sent_requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn failures_are_not_cached() {
+    let cache = ResponseCache::new(Some(TTL));
+    let sent_requests = AtomicUsize::new(0);
+
+    let response = request(&cache, &sent_requests, bad_status()).await;
+    assert_matches!(
+        response,
+        Err(ReaderClientError::ClientError(ClientError::BadResponseStatus { .. }))
+    );
+    let response = request(&cache, &sent_requests, Ok(RESPONSE.to_owned())).await;
+    assert_eq!(response.unwrap(), RESPONSE);
+    assert_eq!(sent_requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn disabled_cache() {
+    let cache = ResponseCache::new(None);
+    let sent_requests = AtomicUsize::new(0);
+
+    let _ = tokio::join!(
+        request(&cache, &sent_requests, Ok(RESPONSE.to_owned())),
+        request(&cache, &sent_requests, Ok(RESPONSE.to_owned())),
+        request(&cache, &sent_requests, Ok(RESPONSE.to_owned())),
+    );
+
+    assert_eq!(sent_requests.load(Ordering::SeqCst), 3);
+}
@@ -7,6 +7,7 @@ use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 use indexmap::indexmap;
 use mockito::mock;
 use pretty_assertions::assert_eq;
+use serde_json::Map;
 use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::{
     ClassHash,
@@ -41,6 +42,7 @@ use super::{
     ReaderClientError,
     ReaderClientResult,
     StarknetFeederGatewayClient,
+    StarknetFeederGatewayClientConfig,
     StarknetReader,
     BLOCK_NUMBER_QUERY,
     CLASS_HASH_QUERY,
@@ -57,9 +59,13 @@ const FEEDER_GATEWAY_ALIVE_RESPONSE: &str = "FeederGateway is alive!";
 #[test]
 fn new_urls() {
     let url_base_str = "https://url";
-    let starknet_client =
-        StarknetFeederGatewayClient::new(url_base_str, None, NODE_VERSION, get_test_config())
-            .unwrap();
+    let starknet_client = StarknetFeederGatewayClient::new(
+        url_base_str,
+        StarknetFeederGatewayClientConfig::default(),
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap();
     assert_eq!(
         starknet_client.urls.get_block.as_str(),
         url_base_str.to_string() + "/" + GET_BLOCK_URL
@@ -74,7 +80,7 @@ fn new_urls() {
 async fn get_block_number() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -120,6 +126,7 @@ async fn declare_tx_serde() {
             "0x2f2ef64daffdc72bf33b34ad024891691b8eb1d0ab70cc7f8fb71f6fd5e1f22"
         )),
         signature: TransactionSignature(vec![]),
+        unknown_fields: Map::new(),
     };
     let raw_declare_tx = serde_json::to_string(&declare_tx).unwrap();
     assert_eq!(declare_tx, serde_json::from_str(&raw_declare_tx).unwrap());
@@ -129,7 +136,7 @@ async fn declare_tx_serde() {
 async fn state_update() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -169,7 +176,7 @@ async fn serialization_precision() {
 async fn contract_class() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -225,7 +232,7 @@ async fn contract_class() {
 async fn deprecated_contract_class() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -324,7 +331,7 @@ async fn deprecated_contract_class() {
 async fn pending_data() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -345,7 +352,7 @@ async fn pending_data() {
 async fn get_block() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -376,7 +383,7 @@ async fn get_block() {
 async fn compiled_class_by_hash() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -423,7 +430,7 @@ async fn compiled_class_by_hash() {
 async fn is_alive() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -441,7 +448,7 @@ async fn is_alive() {
 async fn state_update_with_empty_storage_diff() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -469,7 +476,7 @@ async fn test_unserializable<
 ) {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -478,7 +485,10 @@ async fn test_unserializable<
     let mock = mock("GET", url_suffix).with_status(200).with_body(body).create();
     let error = call_method(starknet_client).await.unwrap_err();
     mock.assert();
-    assert_matches!(error, ReaderClientError::SerdeError(_));
+    assert_matches!(
+        error,
+        ReaderClientError::DeserializationError { raw_response, .. } if raw_response == body
+    );
 }
 
 #[tokio::test]
@@ -536,7 +546,7 @@ async fn compiled_class_by_hash_unserializable() {
 async fn get_block_signature() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
@@ -566,7 +576,7 @@ async fn get_block_signature() {
 async fn get_block_signature_unknown_block() {
     let starknet_client = StarknetFeederGatewayClient::new(
         &mockito::server_url(),
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
         get_test_config(),
     )
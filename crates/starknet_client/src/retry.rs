@@ -10,7 +10,6 @@ use std::time::Duration;
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
-use tokio_retry::strategy::ExponentialBackoff;
 use tokio_retry::{Action, Condition, RetryIf};
 use tracing::debug;
 
@@ -19,6 +18,8 @@ use tracing::debug;
 pub struct RetryConfig {
     /// The initial waiting time in milliseconds.
     pub retry_base_millis: u64,
+    /// The factor by which the waiting time is multiplied after every retry.
+    pub backoff_multiplier: u64,
     /// The maximum waiting time in milliseconds.
     pub retry_max_delay_millis: u64,
     /// The maximum number of retries.
@@ -35,6 +36,12 @@ impl SerializeConfig for RetryConfig {
                  exponentially.",
                 ParamPrivacyInput::Public,
             ),
+            ser_param(
+                "backoff_multiplier",
+                &self.backoff_multiplier,
+                "Factor by which the waiting time is multiplied after every failed request.",
+                ParamPrivacyInput::Public,
+            ),
             ser_param(
                 "retry_max_delay_millis",
                 &self.retry_max_delay_millis,
@@ -51,8 +58,28 @@ impl SerializeConfig for RetryConfig {
     }
 }
 
+/// An exponential backoff strategy. The first waiting time is the base waiting time, and every
+/// following waiting time is the previous one multiplied by the multiplier, up to the maximum
+/// waiting time.
+#[derive(Clone, Debug)]
+struct ExponentialBackoff {
+    current_millis: u64,
+    multiplier: u64,
+    max_delay_millis: u64,
+}
+
+impl Iterator for ExponentialBackoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay_millis = self.current_millis.min(self.max_delay_millis);
+        self.current_millis = self.current_millis.saturating_mul(self.multiplier);
+        Some(Duration::from_millis(delay_millis))
+    }
+}
+
 /// A utility for retrying actions with a configurable backoff and error filter. Uses an
-/// [`ExponentialBackoff`] strategy.
+/// exponential backoff strategy.
 pub struct Retry {
     strategy: Take<ExponentialBackoff>,
 }
@@ -60,9 +87,12 @@ pub struct Retry {
 impl Retry {
     pub fn new(config: &RetryConfig) -> Self {
         Retry {
-            strategy: ExponentialBackoff::from_millis(config.retry_base_millis)
-                .max_delay(Duration::from_millis(config.retry_max_delay_millis))
-                .take(config.max_retries),
+            strategy: ExponentialBackoff {
+                current_millis: config.retry_base_millis,
+                multiplier: config.backoff_multiplier,
+                max_delay_millis: config.retry_max_delay_millis,
+            }
+            .take(config.max_retries),
         }
     }
 
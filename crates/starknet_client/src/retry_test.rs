@@ -2,7 +2,7 @@ use std::sync::{Arc, Mutex};
 
 use pretty_assertions::assert_eq;
 
-use super::Retry;
+use super::{Retry, RetryConfig};
 use crate::test_utils::retry::get_test_config;
 
 struct Worker {
@@ -47,3 +47,15 @@ async fn success_on_third_attempt() {
     Retry::new(&config).start(|| worker.work()).await.unwrap();
     assert_eq!(worker.get_last_attempt(), 3);
 }
+
+#[test]
+fn waiting_time_is_multiplied_up_to_max_delay() {
+    let config = RetryConfig {
+        retry_base_millis: 10,
+        backoff_multiplier: 3,
+        retry_max_delay_millis: 100,
+        max_retries: 5,
+    };
+    let delays: Vec<_> = Retry::new(&config).strategy.map(|delay| delay.as_millis()).collect();
+    assert_eq!(delays, vec![10, 30, 90, 100, 100]);
+}
@@ -4,7 +4,14 @@ use reqwest::StatusCode;
 
 use crate::starknet_error::{KnownStarknetErrorCode, StarknetError, StarknetErrorCode};
 use crate::test_utils::retry::{get_test_config, MAX_RETRIES};
-use crate::{ClientError, RetryErrorCode, StarknetClient};
+use crate::{
+    truncate_for_log,
+    ClientCreationError,
+    ClientError,
+    RetryErrorCode,
+    StarknetClient,
+    MAX_LOGGED_RESPONSE_CHARS,
+};
 
 const NODE_VERSION: &str = "NODE VERSION";
 const URL_SUFFIX: &str = "/query";
@@ -12,7 +19,8 @@ const URL_SUFFIX: &str = "/query";
 #[tokio::test]
 async fn request_with_retry_positive_flow() {
     const BODY: &str = "body";
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     let mock = mock("GET", URL_SUFFIX).with_status(200).with_body(BODY).create();
     let mut url = mockito::server_url();
     url.push_str(URL_SUFFIX);
@@ -25,7 +33,8 @@ async fn request_with_retry_positive_flow() {
 #[tokio::test]
 async fn request_with_retry_bad_response_status() {
     let error_code = StatusCode::NOT_FOUND;
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     let mock = mock("GET", URL_SUFFIX).with_status(error_code.as_u16().into()).create();
     let mut url = mockito::server_url();
     url.push_str(URL_SUFFIX);
@@ -40,7 +49,8 @@ async fn request_with_retry_bad_response_status() {
 
 #[tokio::test]
 async fn request_with_retry_starknet_error_no_retry() {
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     let expected_starknet_error = StarknetError {
         code: StarknetErrorCode::KnownErrorCode(KnownStarknetErrorCode::UndeclaredClass),
         message: "message".to_string(),
@@ -62,7 +72,8 @@ async fn request_with_retry_starknet_error_no_retry() {
 
 #[tokio::test]
 async fn request_with_retry_serde_error_in_starknet_error() {
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     let mock = mock("GET", URL_SUFFIX)
         .with_status(StatusCode::BAD_REQUEST.as_u16().into())
         .with_body("body")
@@ -77,7 +88,8 @@ async fn request_with_retry_serde_error_in_starknet_error() {
 
 #[tokio::test]
 async fn request_with_retry_max_retries_reached() {
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     for (status_code, error_code) in [
         (StatusCode::TEMPORARY_REDIRECT, RetryErrorCode::Redirect),
         (StatusCode::REQUEST_TIMEOUT, RetryErrorCode::Timeout),
@@ -104,7 +116,8 @@ async fn request_with_retry_max_retries_reached() {
 async fn request_with_retry_success_on_retry() {
     const BODY: &str = "body";
     assert_ne!(0, MAX_RETRIES);
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     for status_code in [
         StatusCode::TEMPORARY_REDIRECT,
         StatusCode::REQUEST_TIMEOUT,
@@ -129,7 +142,8 @@ async fn request_with_retry_success_on_retry() {
 
 #[tokio::test]
 async fn request_with_retry_starknet_error_max_retries_reached() {
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     let starknet_error = StarknetError {
         code: StarknetErrorCode::KnownErrorCode(KnownStarknetErrorCode::TransactionLimitExceeded),
         message: "message".to_string(),
@@ -155,7 +169,8 @@ async fn request_with_retry_starknet_error_max_retries_reached() {
 async fn request_with_retry_starknet_error_success_on_retry() {
     const BODY: &str = "body";
     assert_ne!(0, MAX_RETRIES);
-    let starknet_client = StarknetClient::new(None, NODE_VERSION, get_test_config()).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
     let starknet_error = StarknetError {
         code: StarknetErrorCode::KnownErrorCode(KnownStarknetErrorCode::TransactionLimitExceeded),
         message: "message".to_string(),
@@ -184,3 +199,118 @@ fn serialization_precision() {
     let deserialized = serde_json::to_string(&serialized).unwrap();
     assert_eq!(input, deserialized);
 }
+
+#[tokio::test]
+async fn request_with_retry_response_too_large() {
+    const BODY: &str = "body";
+    let starknet_client =
+        StarknetClient::new(None, None, Some(BODY.len() - 1), NODE_VERSION, get_test_config())
+            .unwrap();
+    let mock = mock("GET", URL_SUFFIX).with_status(200).with_body(BODY).create();
+    let mut url = mockito::server_url();
+    url.push_str(URL_SUFFIX);
+    let result =
+        starknet_client.request_with_retry(starknet_client.internal_client.get(&url)).await;
+    assert_matches!(result, Err(ClientError::ResponseTooLarge { max_size }) if max_size == BODY.len() - 1);
+    mock.assert();
+
+    // A response of exactly the maximal size is accepted.
+    let starknet_client =
+        StarknetClient::new(None, None, Some(BODY.len()), NODE_VERSION, get_test_config()).unwrap();
+    let mock = mock("GET", URL_SUFFIX).with_status(200).with_body(BODY).create();
+    let result =
+        starknet_client.request_with_retry(starknet_client.internal_client.get(&url)).await;
+    assert_eq!(result.unwrap(), BODY);
+    mock.assert();
+}
+
+#[tokio::test]
+async fn request_object_with_retry() {
+    // Large enough to be received in many chunks.
+    let object: Vec<u64> = (0..100000).collect();
+    let body = serde_json::to_string(&object).unwrap();
+    let starknet_client =
+        StarknetClient::new(None, None, Some(body.len()), NODE_VERSION, get_test_config()).unwrap();
+    let mock = mock("GET", URL_SUFFIX).with_status(200).with_body(&body).create();
+    let mut url = mockito::server_url();
+    url.push_str(URL_SUFFIX);
+    let result = starknet_client
+        .request_object_with_retry::<Vec<u64>>(starknet_client.internal_client.get(&url))
+        .await;
+    assert_eq!(result.unwrap(), object);
+    mock.assert();
+
+    let starknet_client =
+        StarknetClient::new(None, None, Some(body.len() - 1), NODE_VERSION, get_test_config())
+            .unwrap();
+    let mock = mock("GET", URL_SUFFIX).with_status(200).with_body(&body).create();
+    let result = starknet_client
+        .request_object_with_retry::<Vec<u64>>(starknet_client.internal_client.get(&url))
+        .await;
+    assert_matches!(result, Err(ClientError::ResponseTooLarge { max_size }) if max_size == body.len() - 1);
+    mock.assert();
+}
+
+#[tokio::test]
+async fn request_object_with_retry_unserializable() {
+    const BODY: &str = "[1, 2, \"3\"]";
+    let starknet_client =
+        StarknetClient::new(None, None, None, NODE_VERSION, get_test_config()).unwrap();
+    let mock = mock("GET", URL_SUFFIX).with_status(200).with_body(BODY).create();
+    let mut url = mockito::server_url();
+    url.push_str(URL_SUFFIX);
+    let result = starknet_client
+        .request_object_with_retry::<Vec<u64>>(starknet_client.internal_client.get(&url))
+        .await;
+    assert_matches!(
+        result,
+        Err(ClientError::StreamedResponseDeserializationError { response_prefix, .. })
+        if response_prefix == BODY
+    );
+    mock.assert();
+}
+
+#[test]
+fn invalid_proxy_url() {
+    let result = StarknetClient::new(
+        None,
+        Some("not a url".to_owned()),
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    );
+    assert_matches!(result, Err(ClientCreationError::BuildError(_)));
+}
+
+#[tokio::test]
+async fn request_through_proxy() {
+    const BODY: &str = "body";
+    // The proxy gets the full URL of the request, and the server of the URL is never contacted.
+    const URL: &str = "http://starknet.invalid/query";
+    let starknet_client = StarknetClient::new(
+        None,
+        Some(mockito::server_url()),
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap();
+    let mock = mock("GET", URL).with_status(200).with_body(BODY).create();
+    let result = starknet_client.request_with_retry(starknet_client.internal_client.get(URL)).await;
+    assert_eq!(result.unwrap(), BODY);
+    mock.assert();
+}
+
+#[test]
+fn truncate_long_response_for_log() {
+    const SHORT_RESPONSE: &str = "short response";
+    assert_eq!(truncate_for_log(SHORT_RESPONSE), SHORT_RESPONSE);
+
+    // Multi-byte characters aren't split.
+    let long_response = "\u{e9}".repeat(MAX_LOGGED_RESPONSE_CHARS + 1);
+    let truncated = truncate_for_log(&long_response);
+    assert!(truncated.starts_with(&"\u{e9}".repeat(MAX_LOGGED_RESPONSE_CHARS)));
+    assert!(
+        truncated.ends_with(&format!("... (truncated, {} bytes in total)", long_response.len()))
+    );
+}
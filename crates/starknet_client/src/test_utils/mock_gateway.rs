@@ -0,0 +1,183 @@
+//! A mock of the Starknet feeder gateway and gateway, served over HTTP on a local port.
+//!
+//! Unlike a global mockito server, every [`MockGateway`] is independent, so tests that use it can
+//! run in parallel. Responses are programmed per endpoint and can be delayed or preceded by
+//! injected faults, for testing how the clients and their users handle a flaky gateway.
+#[cfg(test)]
+#[path = "mock_gateway_test.rs"]
+mod mock_gateway_test;
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use tokio::sync::oneshot;
+
+use crate::starknet_error::StarknetError;
+
+/// A response of the mock gateway.
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: StatusCode,
+    pub body: String,
+    /// The time to wait before responding.
+    pub delay: Duration,
+}
+
+impl MockResponse {
+    /// A successful response with the given body.
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self { status: StatusCode::OK, body: body.into(), delay: Duration::ZERO }
+    }
+
+    /// A response with the given status and an empty body.
+    pub fn with_status(status: StatusCode) -> Self {
+        Self { status, body: String::new(), delay: Duration::ZERO }
+    }
+
+    /// A response with the given error, as the gateway returns it.
+    pub fn starknet_error(error: &StarknetError) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            body: serde_json::to_string(error).expect("Failed to serialize a Starknet error."),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Returns the response, delayed by the given duration.
+    pub fn delayed(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+}
+
+/// A fault that the mock gateway injects instead of responding.
+#[derive(Clone, Debug)]
+pub enum Fault {
+    /// Respond with the given response instead of the programmed one.
+    Respond(MockResponse),
+    /// Close the connection without responding.
+    Disconnect,
+}
+
+/// A request that the mock gateway received.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// The path and the query of the request, for example
+    /// `/feeder_gateway/get_block?blockNumber=1`.
+    pub path_and_query: String,
+    pub body: String,
+}
+
+#[derive(Debug, Default)]
+struct MockGatewayState {
+    // Keyed by the path and the query of the requests.
+    responses: HashMap<String, MockResponse>,
+    faults: HashMap<String, VecDeque<Fault>>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// A mock gateway that serves programmed responses until it's dropped. Requests to endpoints
+/// without a programmed response get a 404 response.
+pub struct MockGateway {
+    address: SocketAddr,
+    state: Arc<Mutex<MockGatewayState>>,
+    _shutdown_sender: oneshot::Sender<()>,
+}
+
+impl MockGateway {
+    /// Starts a mock gateway on a free local port. Must be called from within a tokio runtime.
+    pub fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockGatewayState::default()));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_connection| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(state.clone(), request)
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let address = server.local_addr();
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            shutdown_receiver.await.ok();
+        }));
+        Self { address, state, _shutdown_sender: shutdown_sender }
+    }
+
+    /// The URL of the mock gateway, to be given to the clients.
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.address)
+    }
+
+    /// Sets the response to requests with the given path and query, for example
+    /// `/feeder_gateway/get_block?blockNumber=1`.
+    pub fn set_response(&self, path_and_query: &str, response: MockResponse) {
+        self.lock_state().responses.insert(path_and_query.to_owned(), response);
+    }
+
+    /// Injects the given fault to the next `times` requests with the given path and query. The
+    /// faults are injected after the faults that were already injected to these requests.
+    pub fn inject_fault(&self, path_and_query: &str, fault: Fault, times: usize) {
+        self.lock_state()
+            .faults
+            .entry(path_and_query.to_owned())
+            .or_default()
+            .extend(std::iter::repeat(fault).take(times));
+    }
+
+    /// Returns the requests the mock gateway received, in the order they were received.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock_state().requests.clone()
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, MockGatewayState> {
+        self.state.lock().expect("Failed to lock the mock gateway state.")
+    }
+}
+
+// Returns an error, which makes hyper close the connection, if a disconnection fault is injected.
+async fn handle_request(
+    state: Arc<Mutex<MockGatewayState>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, String> {
+    let method = request.method().to_string();
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|path_and_query| path_and_query.to_string())
+        .unwrap_or_default();
+    let body = hyper::body::to_bytes(request.into_body()).await.map_err(|err| err.to_string())?;
+    let body = String::from_utf8_lossy(&body).into_owned();
+
+    let response = {
+        let mut state = state.lock().expect("Failed to lock the mock gateway state.");
+        state.requests.push(RecordedRequest {
+            method,
+            path_and_query: path_and_query.clone(),
+            body,
+        });
+        let fault = state.faults.get_mut(&path_and_query).and_then(|faults| faults.pop_front());
+        match fault {
+            Some(Fault::Disconnect) => return Err("Injected a disconnection.".to_owned()),
+            Some(Fault::Respond(response)) => response,
+            None => state
+                .responses
+                .get(&path_and_query)
+                .cloned()
+                .unwrap_or_else(|| MockResponse::with_status(StatusCode::NOT_FOUND)),
+        }
+    };
+
+    tokio::time::sleep(response.delay).await;
+    Ok(Response::builder()
+        .status(response.status)
+        .body(Body::from(response.body))
+        .expect("Failed to build a mock gateway response."))
+}
@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant};
+
+use assert_matches::assert_matches;
+use reqwest::StatusCode;
+use starknet_api::block::BlockNumber;
+
+use super::{Fault, MockGateway, MockResponse, RecordedRequest};
+use crate::reader::{
+    ReaderClientError,
+    StarknetFeederGatewayClient,
+    StarknetFeederGatewayClientConfig,
+    StarknetReader,
+};
+use crate::starknet_error::{KnownStarknetErrorCode, StarknetError, StarknetErrorCode};
+use crate::test_utils::retry::{get_test_config, MAX_RETRIES};
+use crate::ClientError;
+
+const NODE_VERSION: &str = "NODE VERSION";
+const IS_ALIVE_PATH: &str = "/feeder_gateway/is_alive";
+const FEEDER_GATEWAY_ALIVE_RESPONSE: &str = "FeederGateway is alive!";
+
+fn client(mock_gateway: &MockGateway) -> StarknetFeederGatewayClient {
+    StarknetFeederGatewayClient::new(
+        &mock_gateway.url(),
+        StarknetFeederGatewayClientConfig::default(),
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn programmed_response() {
+    let mock_gateway = MockGateway::start();
+    mock_gateway.set_response(IS_ALIVE_PATH, MockResponse::ok(FEEDER_GATEWAY_ALIVE_RESPONSE));
+
+    assert!(client(&mock_gateway).is_alive().await);
+    assert_eq!(
+        mock_gateway.requests(),
+        vec![RecordedRequest {
+            method: "GET".to_owned(),
+            path_and_query: IS_ALIVE_PATH.to_owned(),
+            body: String::new(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn starknet_error_response() {
+    let mock_gateway = MockGateway::start();
+    let path_and_query = "/feeder_gateway/get_block?blockNumber=1";
+    mock_gateway.set_response(
+        path_and_query,
+        MockResponse::starknet_error(&StarknetError {
+            code: StarknetErrorCode::KnownErrorCode(KnownStarknetErrorCode::BlockNotFound),
+            message: "Block not found.".to_owned(),
+        }),
+    );
+
+    assert!(client(&mock_gateway).block(BlockNumber(1)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn faults_are_retried() {
+    let mock_gateway = MockGateway::start();
+    mock_gateway.set_response(IS_ALIVE_PATH, MockResponse::ok(FEEDER_GATEWAY_ALIVE_RESPONSE));
+    mock_gateway.inject_fault(
+        IS_ALIVE_PATH,
+        Fault::Respond(MockResponse::with_status(StatusCode::SERVICE_UNAVAILABLE)),
+        2,
+    );
+
+    assert!(client(&mock_gateway).is_alive().await);
+    assert_eq!(mock_gateway.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn disconnection() {
+    let mock_gateway = MockGateway::start();
+    let path_and_query = "/feeder_gateway/get_block?blockNumber=1";
+    mock_gateway.inject_fault(path_and_query, Fault::Disconnect, 1);
+
+    let result = client(&mock_gateway).block(BlockNumber(1)).await;
+    assert_matches!(result, Err(ReaderClientError::ClientError(ClientError::RequestError(_))));
+}
+
+#[tokio::test]
+async fn too_many_faults() {
+    let mock_gateway = MockGateway::start();
+    mock_gateway.inject_fault(
+        "/feeder_gateway/get_block?blockNumber=1",
+        Fault::Respond(MockResponse::with_status(StatusCode::SERVICE_UNAVAILABLE)),
+        MAX_RETRIES + 1,
+    );
+
+    let result = client(&mock_gateway).block(BlockNumber(1)).await;
+    assert_matches!(result, Err(ReaderClientError::ClientError(ClientError::RetryError { .. })));
+    assert_eq!(mock_gateway.requests().len(), MAX_RETRIES + 1);
+}
+
+#[tokio::test]
+async fn delayed_response() {
+    const DELAY: Duration = Duration::from_millis(100);
+    let mock_gateway = MockGateway::start();
+    mock_gateway.set_response(
+        IS_ALIVE_PATH,
+        MockResponse::ok(FEEDER_GATEWAY_ALIVE_RESPONSE).delayed(DELAY),
+    );
+
+    let start_time = Instant::now();
+    assert!(client(&mock_gateway).is_alive().await);
+    assert!(start_time.elapsed() >= DELAY);
+}
@@ -1,3 +1,4 @@
+pub mod mock_gateway;
 #[cfg(test)]
 pub mod read_resource;
 #[cfg(test)]
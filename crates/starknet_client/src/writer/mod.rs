@@ -7,11 +7,13 @@ pub mod objects;
 #[cfg(test)]
 mod starknet_gateway_client_test;
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 #[cfg(any(feature = "testing", test))]
 use mockall::automock;
 use serde::{Deserialize, Serialize};
-use tracing::instrument;
+use tracing::{instrument, warn};
 use url::Url;
 
 use crate::writer::objects::response::{DeclareResponse, DeployAccountResponse, InvokeResponse};
@@ -20,7 +22,7 @@ use crate::writer::objects::transaction::{
     DeployAccountTransaction,
     InvokeTransaction,
 };
-use crate::{ClientCreationError, ClientError, RetryConfig, StarknetClient};
+use crate::{truncate_for_log, ClientCreationError, ClientError, RetryConfig, StarknetClient};
 
 /// Errors that may be returned from a writer client.
 #[derive(thiserror::Error, Debug)]
@@ -34,6 +36,11 @@ pub enum WriterClientError {
     /// is responsible for serde errors coming from StarknetClient.
     #[error(transparent)]
     SerdeError(#[from] serde_json::Error),
+    /// A client error representing a response that couldn't be deserialized, for example because
+    /// the gateway changed the format of its responses. Contains the raw response so the
+    /// incompatibility can be reported.
+    #[error("Failed to deserialize the response of starknet gateway: {error}.")]
+    DeserializationError { error: serde_json::Error, raw_response: String },
 }
 
 pub type WriterClientResult<T> = Result<T, WriterClientError>;
@@ -122,13 +129,15 @@ impl StarknetWriter for StarknetGatewayClient {
 impl StarknetGatewayClient {
     pub fn new(
         starknet_url: &str,
+        http_headers: Option<HashMap<String, String>>,
+        proxy_url: Option<String>,
         node_version: &'static str,
         retry_config: RetryConfig,
     ) -> Result<Self, ClientCreationError> {
         Ok(StarknetGatewayClient {
             add_transaction_url: Url::parse(starknet_url)?.join(ADD_TRANSACTION_URL_SUFFIX)?,
             is_alive_url: Url::parse(starknet_url)?.join(GATEWAY_IS_ALIVE)?,
-            client: StarknetClient::new(None, node_version, retry_config)?,
+            client: StarknetClient::new(http_headers, proxy_url, None, node_version, retry_config)?,
         })
     }
 
@@ -145,6 +154,12 @@ impl StarknetGatewayClient {
                     .body(serde_json::to_string(&tx)?),
             )
             .await?;
-        Ok(serde_json::from_str::<Response>(&response)?)
+        serde_json::from_str::<Response>(&response).map_err(|error| {
+            warn!(
+                "Failed to deserialize the response of starknet gateway: {error}. Raw response: {}",
+                truncate_for_log(&response)
+            );
+            WriterClientError::DeserializationError { error, raw_response: response }
+        })
     }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::future::Future;
 
@@ -21,9 +22,14 @@ async fn run_add_transaction<
     resource_file_response_path: &str,
     add_transaction_function: F,
 ) -> WriterClientResult<Response> {
-    let client =
-        StarknetGatewayClient::new(&mockito::server_url(), NODE_VERSION, get_test_config())
-            .unwrap();
+    let client = StarknetGatewayClient::new(
+        &mockito::server_url(),
+        None,
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap();
     let tx_json_value = read_json_file(resource_file_transaction_path);
     let tx = serde_json::from_value::<Transaction>(tx_json_value.clone()).unwrap();
     let response_json_value = read_json_file(resource_file_response_path);
@@ -71,23 +77,49 @@ async fn test_add_transaction_fails_serde<
     resource_file_response_path: &str,
     add_transaction_function: F,
 ) {
-    let Err(WriterClientError::SerdeError(_)) = run_add_transaction(
+    let Err(WriterClientError::DeserializationError { .. }) = run_add_transaction(
         resource_file_transaction_path,
         resource_file_response_path,
         add_transaction_function,
     )
     .await
     else {
-        panic!("Adding a transaction with bad response did not cause a SerdeError");
+        panic!("Adding a transaction with bad response did not cause a DeserializationError");
     };
 }
 
 #[tokio::test]
 async fn is_alive() {
-    let starknet_client =
-        StarknetGatewayClient::new(&mockito::server_url(), NODE_VERSION, get_test_config())
-            .unwrap();
+    let starknet_client = StarknetGatewayClient::new(
+        &mockito::server_url(),
+        None,
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap();
+    let mock_is_alive = mock("GET", "/gateway/is_alive")
+        .with_status(200)
+        .with_body(GATEWAY_ALIVE_RESPONSE)
+        .create();
+    let response = starknet_client.is_alive().await;
+    mock_is_alive.assert();
+    assert!(response);
+}
+
+#[tokio::test]
+async fn requests_contain_http_headers() {
+    let http_headers = HashMap::from([("api-key".to_owned(), "secret".to_owned())]);
+    let starknet_client = StarknetGatewayClient::new(
+        &mockito::server_url(),
+        Some(http_headers),
+        None,
+        NODE_VERSION,
+        get_test_config(),
+    )
+    .unwrap();
     let mock_is_alive = mock("GET", "/gateway/is_alive")
+        .match_header("api-key", "secret")
         .with_status(200)
         .with_body(GATEWAY_ALIVE_RESPONSE)
         .create();
@@ -3,7 +3,11 @@ use serde::Serialize;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ClassHash;
 use starknet_api::hash::StarkHash;
-use starknet_client::reader::{StarknetFeederGatewayClient, StarknetReader};
+use starknet_client::reader::{
+    StarknetFeederGatewayClient,
+    StarknetFeederGatewayClientConfig,
+    StarknetReader,
+};
 use starknet_client::retry::RetryConfig;
 use tokio::join;
 
@@ -136,9 +140,14 @@ async fn test_alpha_testnet() {
 async fn run(test_env_data: TestEnvData) {
     let starknet_client = StarknetFeederGatewayClient::new(
         &test_env_data.url,
-        None,
+        StarknetFeederGatewayClientConfig::default(),
         NODE_VERSION,
-        RetryConfig { retry_base_millis: 30, retry_max_delay_millis: 30000, max_retries: 10 },
+        RetryConfig {
+            retry_base_millis: 30,
+            backoff_multiplier: 30,
+            retry_max_delay_millis: 30000,
+            max_retries: 10,
+        },
     )
     .expect("Create new client");
 
@@ -0,0 +1,63 @@
+use pretty_assertions::assert_eq;
+use starknet_api::transaction::Transaction;
+
+use crate::{
+    get_test_block_with_transaction_types,
+    get_test_state_diff_with_sizes,
+    TestStateDiffSizes,
+    TestTransactionType,
+};
+
+#[test]
+fn block_with_transaction_types() {
+    let transaction_types = [
+        TestTransactionType::Invoke,
+        TestTransactionType::Declare,
+        TestTransactionType::L1Handler,
+        TestTransactionType::DeployAccount,
+        TestTransactionType::Deploy,
+        TestTransactionType::Invoke,
+    ];
+    let block = get_test_block_with_transaction_types(&transaction_types, 2);
+
+    let generated_types = block
+        .body
+        .transactions
+        .iter()
+        .map(|transaction| match transaction {
+            Transaction::Declare(_) => TestTransactionType::Declare,
+            Transaction::Deploy(_) => TestTransactionType::Deploy,
+            Transaction::DeployAccount(_) => TestTransactionType::DeployAccount,
+            Transaction::Invoke(_) => TestTransactionType::Invoke,
+            Transaction::L1Handler(_) => TestTransactionType::L1Handler,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(generated_types, transaction_types);
+    assert_eq!(block.body.transaction_outputs.len(), transaction_types.len());
+    assert!(block.body.transaction_outputs.iter().all(|output| output.events().len() == 2));
+}
+
+#[test]
+fn state_diff_with_sizes() {
+    let sizes = TestStateDiffSizes {
+        deployed_contracts: 5,
+        storage_entries_per_contract: 3,
+        declared_classes: 2,
+        deprecated_declared_classes: 4,
+        nonces: 1,
+    };
+    let state_diff = get_test_state_diff_with_sizes(sizes);
+
+    assert_eq!(state_diff.deployed_contracts.len(), 5);
+    assert_eq!(state_diff.storage_diffs.len(), 5);
+    assert!(state_diff.storage_diffs.values().all(|storage_entries| storage_entries.len() == 3));
+    assert_eq!(state_diff.declared_classes.len(), 2);
+    assert_eq!(state_diff.deprecated_declared_classes.len(), 4);
+    assert_eq!(state_diff.nonces.len(), 1);
+    assert!(state_diff.replaced_classes.is_empty());
+    // The class hashes of the declared and deprecated classes are distinct.
+    assert!(state_diff
+        .declared_classes
+        .keys()
+        .all(|class_hash| !state_diff.deprecated_declared_classes.contains_key(class_hash)));
+}
@@ -1,10 +1,13 @@
 #![allow(clippy::unwrap_used)]
 #[cfg(test)]
+mod generators_test;
+#[cfg(test)]
 mod precision_test;
 
 use std::cmp::max;
 use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::fmt::Debug;
 use std::fs::read_to_string;
 use std::hash::Hash;
 use std::net::SocketAddr;
@@ -30,6 +33,8 @@ use cairo_lang_starknet::NestedIntList;
 use cairo_lang_utils::bigint::BigUintAsHex;
 use indexmap::IndexMap;
 use num_bigint::BigUint;
+use papyrus_common::l1_to_l2_messages::{L1ToL2MessageHash, L1TransactionHash};
+use papyrus_common::raw_transaction::RawTransaction;
 use primitive_types::H160;
 use prometheus_parse::Value;
 use rand::{Rng, RngCore, SeedableRng};
@@ -192,6 +197,28 @@ pub fn get_rng() -> ChaCha8Rng {
     ChaCha8Rng::seed_from_u64(seed)
 }
 
+/// The number of random instances that [`for_random_instances`] runs a test on.
+pub const RANDOM_INSTANCES_PER_TEST: usize = 20;
+
+/// Runs the given test on [`RANDOM_INSTANCES_PER_TEST`] random instances of `T`, for checking that
+/// a property holds for any instance (for example, that an encoding roundtrips). All the instances
+/// are generated from a single random generator, so a failure can be reproduced by setting the
+/// seed, see [`get_rng`].
+pub fn for_random_instances<T: GetTestInstance>(mut test: impl FnMut(T)) {
+    let mut rng = get_rng();
+    for _ in 0..RANDOM_INSTANCES_PER_TEST {
+        test(T::get_test_instance(&mut rng));
+    }
+}
+
+/// Asserts that the item is the same after serializing it to JSON and deserializing it back.
+pub fn assert_json_roundtrip<T: Serialize + for<'a> Deserialize<'a> + PartialEq + Debug>(item: &T) {
+    let json_value = serde_json::to_value(item).unwrap();
+    let deserialized = serde_json::from_value::<T>(json_value.clone())
+        .unwrap_or_else(|err| panic!("Failed to deserialize {json_value}: {err}."));
+    assert_eq!(item, &deserialized);
+}
+
 /// Use to get the value of a metric by name and labels.
 // If the data contains a metric with metric_name and labels returns its value else None.
 pub fn prometheus_is_contained(
@@ -217,10 +244,11 @@ pub fn prometheus_is_contained(
 // INTERNAL FUNCTIONS
 //////////////////////////////////////////////////////////////////////////
 
-/// Returns a test block with a variable number of transactions and events.
+/// Returns a test block with transactions of the given types (any type where it's None) and a
+/// variable number of events.
 fn get_rand_test_block_with_events(
     rng: &mut ChaCha8Rng,
-    transaction_count: usize,
+    transaction_types: &[Option<TestTransactionType>],
     events_per_tx: usize,
     from_addresses: Option<Vec<ContractAddress>>,
     keys: Option<Vec<Vec<EventKey>>>,
@@ -229,7 +257,7 @@ fn get_rand_test_block_with_events(
         header: BlockHeader::default(),
         body: get_rand_test_body_with_events(
             rng,
-            transaction_count,
+            transaction_types,
             events_per_tx,
             from_addresses,
             keys,
@@ -248,25 +276,69 @@ fn is_v3_transaction(transaction: &Transaction) -> bool {
     )
 }
 
-/// Returns a test block body with a variable number of transactions and events.
+fn get_transaction_type(transaction: &Transaction) -> TestTransactionType {
+    match transaction {
+        Transaction::Declare(_) => TestTransactionType::Declare,
+        Transaction::Deploy(_) => TestTransactionType::Deploy,
+        Transaction::DeployAccount(_) => TestTransactionType::DeployAccount,
+        Transaction::Invoke(_) => TestTransactionType::Invoke,
+        Transaction::L1Handler(_) => TestTransactionType::L1Handler,
+    }
+}
+
+/// Returns a random transaction of the given type, or of any type if it's None.
+fn get_rand_test_transaction(
+    rng: &mut ChaCha8Rng,
+    transaction_type: Option<TestTransactionType>,
+) -> Transaction {
+    loop {
+        let transaction = Transaction::get_test_instance(rng);
+        if !is_v3_transaction(&transaction)
+            && transaction_type
+                .map_or(true, |tx_type| tx_type == get_transaction_type(&transaction))
+        {
+            return transaction;
+        }
+    }
+}
+
+/// Returns a test block body with transactions of the given types (any type where it's None) and
+/// a variable number of events.
 fn get_rand_test_body_with_events(
     rng: &mut ChaCha8Rng,
-    transaction_count: usize,
+    transaction_types: &[Option<TestTransactionType>],
     events_per_tx: usize,
     from_addresses: Option<Vec<ContractAddress>>,
     keys: Option<Vec<Vec<EventKey>>>,
+) -> BlockBody {
+    get_rand_test_body_with_outputs(
+        rng,
+        transaction_types,
+        events_per_tx,
+        from_addresses,
+        keys,
+        |_rng, transaction| get_test_transaction_output(transaction),
+    )
+}
+
+// Like get_rand_test_body_with_events, with the outputs of the transactions (before their events
+// are set) created by `get_transaction_output`.
+fn get_rand_test_body_with_outputs(
+    rng: &mut ChaCha8Rng,
+    transaction_types: &[Option<TestTransactionType>],
+    events_per_tx: usize,
+    from_addresses: Option<Vec<ContractAddress>>,
+    keys: Option<Vec<Vec<EventKey>>>,
+    get_transaction_output: impl Fn(&mut ChaCha8Rng, &Transaction) -> TransactionOutput,
 ) -> BlockBody {
     let mut transactions = vec![];
     let mut transaction_outputs = vec![];
     let mut transaction_hashes = vec![];
     let mut transaction_execution_statuses = vec![];
-    for i in 0..transaction_count {
-        let mut transaction = Transaction::get_test_instance(rng);
-        while is_v3_transaction(&transaction) {
-            transaction = Transaction::get_test_instance(rng);
-        }
+    for (i, transaction_type) in transaction_types.iter().enumerate() {
+        let transaction = get_rand_test_transaction(rng, *transaction_type);
         transaction_hashes.push(TransactionHash(StarkHash::from(i as u128)));
-        let transaction_output = get_test_transaction_output(&transaction);
+        let transaction_output = get_transaction_output(rng, &transaction);
         transactions.push(transaction);
         transaction_outputs.push(transaction_output);
         transaction_execution_statuses.push(TransactionExecutionStatus::default());
@@ -301,8 +373,16 @@ fn get_rand_test_body_with_events(
 }
 
 fn get_test_transaction_output(transaction: &Transaction) -> TransactionOutput {
-    let mut rng = get_rng();
-    let execution_resources = ExecutionResources::get_test_instance(&mut rng);
+    get_rand_test_transaction_output(&mut get_rng(), transaction)
+}
+
+// Returns an output of the transaction with random execution resources, generated from the given
+// random generator.
+fn get_rand_test_transaction_output(
+    rng: &mut ChaCha8Rng,
+    transaction: &Transaction,
+) -> TransactionOutput {
+    let execution_resources = ExecutionResources::get_test_instance(rng);
     match transaction {
         Transaction::Declare(_) => TransactionOutput::Declare(DeclareTransactionOutput {
             execution_resources,
@@ -355,7 +435,7 @@ pub fn get_test_block(
     let events_per_tx = if let Some(events_per_tx) = events_per_tx { events_per_tx } else { 0 };
     get_rand_test_block_with_events(
         &mut rng,
-        transaction_count,
+        &vec![None; transaction_count],
         events_per_tx,
         from_addresses,
         keys,
@@ -371,7 +451,148 @@ pub fn get_test_body(
 ) -> BlockBody {
     let mut rng = get_rng();
     let events_per_tx = if let Some(events_per_tx) = events_per_tx { events_per_tx } else { 0 };
-    get_rand_test_body_with_events(&mut rng, transaction_count, events_per_tx, from_addresses, keys)
+    get_rand_test_body_with_events(
+        &mut rng,
+        &vec![None; transaction_count],
+        events_per_tx,
+        from_addresses,
+        keys,
+    )
+}
+
+/// The type of a transaction in a test block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TestTransactionType {
+    Declare,
+    Deploy,
+    DeployAccount,
+    Invoke,
+    L1Handler,
+}
+
+/// Returns a test block whose transactions are of the given types, in the given order, with
+/// `events_per_tx` events in each transaction.
+pub fn get_test_block_with_transaction_types(
+    transaction_types: &[TestTransactionType],
+    events_per_tx: usize,
+) -> Block {
+    Block {
+        header: BlockHeader::default(),
+        body: get_test_body_with_transaction_types(transaction_types, events_per_tx),
+    }
+}
+
+/// Returns a test block body whose transactions are of the given types, in the given order, with
+/// `events_per_tx` events in each transaction.
+pub fn get_test_body_with_transaction_types(
+    transaction_types: &[TestTransactionType],
+    events_per_tx: usize,
+) -> BlockBody {
+    let mut rng = get_rng();
+    let transaction_types = transaction_types.iter().copied().map(Some).collect::<Vec<_>>();
+    get_rand_test_body_with_events(&mut rng, &transaction_types, events_per_tx, None, None)
+}
+
+/// Returns a random test block body whose transactions are of the given types, in the given order,
+/// with `events_per_tx` events in each transaction. The events are emitted by the given addresses
+/// and have the given keys, as in [`get_test_body`]. The whole body, including the outputs of the
+/// transactions, is generated from the given random generator.
+pub fn get_rand_test_body_with_transaction_types(
+    rng: &mut ChaCha8Rng,
+    transaction_types: &[TestTransactionType],
+    events_per_tx: usize,
+    from_addresses: Option<Vec<ContractAddress>>,
+    keys: Option<Vec<Vec<EventKey>>>,
+) -> BlockBody {
+    let transaction_types = transaction_types.iter().copied().map(Some).collect::<Vec<_>>();
+    get_rand_test_body_with_outputs(
+        rng,
+        &transaction_types,
+        events_per_tx,
+        from_addresses,
+        keys,
+        get_rand_test_transaction_output,
+    )
+}
+
+/// The number of items in each part of a test state diff.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TestStateDiffSizes {
+    pub deployed_contracts: usize,
+    /// The number of storage entries of each deployed contract.
+    pub storage_entries_per_contract: usize,
+    pub declared_classes: usize,
+    pub deprecated_declared_classes: usize,
+    /// The number of deployed contracts whose nonce is updated. Must not exceed the number of
+    /// deployed contracts.
+    pub nonces: usize,
+}
+
+/// Returns a state diff with the given number of items in each part, with random classes. All the
+/// class hashes and contract addresses in the state diff are distinct, and the deployed contracts
+/// are instances of the declared classes (or of deprecated ones if there are no declared classes).
+pub fn get_test_state_diff_with_sizes(sizes: TestStateDiffSizes) -> StateDiff {
+    assert!(
+        sizes.nonces <= sizes.deployed_contracts,
+        "The nonces are of deployed contracts, so there can't be more of them."
+    );
+    let mut rng = get_rng();
+    let deprecated_declared_classes = (0..sizes.deprecated_declared_classes)
+        .map(|i| {
+            (
+                ClassHash(StarkHash::from(i as u128)),
+                DeprecatedContractClass::get_test_instance(&mut rng),
+            )
+        })
+        .collect::<IndexMap<_, _>>();
+    let declared_classes = (sizes.deprecated_declared_classes
+        ..sizes.deprecated_declared_classes + sizes.declared_classes)
+        .map(|i| {
+            (
+                ClassHash(StarkHash::from(i as u128)),
+                (
+                    CompiledClassHash(StarkHash::from(i as u128)),
+                    ContractClass::get_test_instance(&mut rng),
+                ),
+            )
+        })
+        .collect::<IndexMap<_, _>>();
+    let class_hashes = declared_classes
+        .keys()
+        .chain(deprecated_declared_classes.keys())
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut deployed_contracts = IndexMap::new();
+    let mut storage_diffs = IndexMap::new();
+    let mut nonces = IndexMap::new();
+    for i in 0..sizes.deployed_contracts {
+        let address = ContractAddress::from(i as u128);
+        let class_hash = if class_hashes.is_empty() {
+            ClassHash::default()
+        } else {
+            class_hashes[i % class_hashes.len()]
+        };
+        deployed_contracts.insert(address, class_hash);
+        if sizes.storage_entries_per_contract > 0 {
+            let storage_entries = (0..sizes.storage_entries_per_contract)
+                .map(|key| (StorageKey::from(key as u128), StarkFelt::from(rng.next_u64())))
+                .collect();
+            storage_diffs.insert(address, storage_entries);
+        }
+        if i < sizes.nonces {
+            nonces.insert(address, Nonce(StarkFelt::from(rng.next_u64())));
+        }
+    }
+
+    StateDiff {
+        deployed_contracts,
+        storage_diffs,
+        declared_classes,
+        deprecated_declared_classes,
+        nonces,
+        replaced_classes: IndexMap::new(),
+    }
 }
 
 // Returns a state diff with one item in each IndexMap.
@@ -628,6 +849,10 @@ auto_impl_get_test_instance! {
         pub prime: serde_json::Value,
         pub reference_manager: serde_json::Value,
     }
+    pub struct RawTransaction {
+        pub transaction: serde_json::Value,
+        pub receipt: serde_json::Value,
+    }
     pub enum Resource {
         L1Gas = 0,
         L2Gas = 1,
@@ -829,6 +1054,7 @@ macro_rules! default_impl_get_test_instance {
 // Implements the [`GetTestInstance`] trait for primitive types.
 ////////////////////////////////////////////////////////////////////////
 default_impl_get_test_instance!(serde_json::Value);
+default_impl_get_test_instance!(serde_json::Map<String, serde_json::Value>);
 default_impl_get_test_instance!(String);
 impl<T: GetTestInstance> GetTestInstance for Arc<T> {
     fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
@@ -917,6 +1143,18 @@ impl GetTestInstance for Hint {
     }
 }
 
+impl GetTestInstance for L1ToL2MessageHash {
+    fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
+        Self(rng.gen())
+    }
+}
+
+impl GetTestInstance for L1TransactionHash {
+    fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
+        Self(rng.gen())
+    }
+}
+
 impl GetTestInstance for ExecutionResources {
     fn get_test_instance(rng: &mut ChaCha8Rng) -> Self {
         let rand_not_zero = || max(1, get_rng().next_u64());